@@ -0,0 +1,244 @@
+//! Terminal capabilities.
+//!
+//! Capabilities are guessed from environment variables (`TERM`, `COLORTERM` and `TERM_PROGRAM`),
+//! and can be confirmed by querying the terminal (see [`TuiBackendBuilder::probe_capabilities`]).
+//!
+//! Anything that can't be detected is assumed to be unsupported.
+//!
+//! [`TuiBackendBuilder::probe_capabilities`]: crate::tui::TuiBackendBuilder::probe_capabilities
+use anathema_geometry::Size;
+
+/// The number of colours supported by the terminal
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ColorDepth {
+    /// The 16 system colours
+    #[default]
+    Ansi16,
+    /// The 256 colour palette
+    Ansi256,
+    /// 24 bit colours
+    TrueColor,
+}
+
+/// Features supported by the terminal
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Supported colours
+    pub colors: ColorDepth,
+    /// The kitty keyboard protocol is enabled
+    pub kitty_keyboard: bool,
+    /// Synchronized output (mode 2026)
+    pub synchronized_output: bool,
+    /// The kitty graphics protocol
+    pub kitty_graphics: bool,
+    /// Sixel graphics
+    pub sixel: bool,
+    /// The iTerm2 inline image protocol
+    pub iterm_images: bool,
+    /// The size of a cell in pixels
+    pub cell_size: Option<Size>,
+}
+
+impl Capabilities {
+    /// Guess the capabilities from the environment variables of the process
+    pub fn from_env() -> Self {
+        let mut capabilities = Self::from_vars(|name| std::env::var(name).ok());
+
+        // The Windows console supports 24 bit colours once virtual terminal sequences
+        // are enabled, and doesn't set `TERM`
+        if cfg!(windows) && std::env::var_os("TERM").is_none() {
+            capabilities.colors = ColorDepth::TrueColor;
+        }
+
+        capabilities
+    }
+
+    /// Guess the capabilities from environment variables, where `var` returns the value of a
+    /// variable. This is useful when the terminal is not the one of the process (e.g over ssh).
+    pub fn from_vars(var: impl Fn(&str) -> Option<String>) -> Self {
+        let term = var("TERM").unwrap_or_default();
+        let program = var("TERM_PROGRAM").unwrap_or_default();
+        let colorterm = var("COLORTERM").unwrap_or_default();
+
+        let kitty = term.contains("kitty") || program == "ghostty" || term.contains("ghostty");
+        let iterm = matches!(program.as_str(), "iTerm.app" | "WezTerm");
+
+        let alacritty = term.contains("alacritty");
+
+        let truecolor = matches!(colorterm.as_str(), "truecolor" | "24bit")
+            || term.ends_with("-direct")
+            || alacritty
+            || kitty
+            || iterm;
+
+        let colors = match truecolor {
+            true => ColorDepth::TrueColor,
+            false if term.contains("256color") => ColorDepth::Ansi256,
+            false => ColorDepth::Ansi16,
+        };
+
+        Self {
+            colors,
+            synchronized_output: kitty || iterm || alacritty,
+            kitty_graphics: kitty || program == "WezTerm",
+            iterm_images: iterm,
+            ..Self::default()
+        }
+    }
+
+    /// Update the capabilities with the replies of the terminal to the queries
+    /// for synchronized output, kitty graphics, the cell size and the primary device attributes.
+    pub fn apply_replies(&mut self, replies: &[u8]) {
+        let mut replies = replies;
+        while let Some(start) = replies.iter().position(|b| *b == 0x1b) {
+            replies = &replies[start + 1..];
+            match replies.first() {
+                // Control sequence, ending with a byte in the range 0x40..=0x7e
+                Some(b'[') => {
+                    let Some(end) = replies[1..].iter().position(|b| (0x40..=0x7e).contains(b)) else { return };
+                    let params = &replies[1..end + 1];
+                    self.apply_csi(params, replies[end + 1]);
+                    replies = &replies[end + 2..];
+                }
+                // Application program command, ending with ST
+                Some(b'_') => {
+                    let Some(end) = replies.windows(2).position(|w| w == b"\x1b\\") else { return };
+                    if replies[1..end].starts_with(b"Gi=31;OK") {
+                        self.kitty_graphics = true;
+                    }
+                    replies = &replies[end..];
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn apply_csi(&mut self, params: &[u8], end: u8) {
+        let params = String::from_utf8_lossy(params);
+        match end {
+            // Mode report: `?2026;1$y` or `?2026;2$y` if synchronized output is supported
+            b'y' => {
+                if let Some(mode) = params.strip_prefix("?2026;").and_then(|p| p.strip_suffix('$')) {
+                    self.synchronized_output = matches!(mode, "1" | "2");
+                }
+            }
+            // Primary device attributes, where 4 is sixel support
+            b'c' => {
+                if let Some(attributes) = params.strip_prefix('?') {
+                    self.sixel = attributes.split(';').skip(1).any(|attr| attr == "4");
+                }
+            }
+            // Cell size: `6;height;width`
+            b't' => {
+                let mut params = params.split(';').map(|p| p.parse::<usize>().ok());
+                if let (Some(Some(6)), Some(Some(height)), Some(Some(width))) =
+                    (params.next(), params.next(), params.next())
+                {
+                    self.cell_size = Some(Size::new(width, height));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Query synchronized output, kitty graphics and the cell size,
+/// followed by the primary device attributes, which every terminal replies to.
+#[cfg(all(unix, feature = "crossterm"))]
+pub(crate) const QUERY: &[u8] = b"\x1b[?2026$p\x1b_Gi=31,s=1,v=1,a=q,t=d,f=24;AAAA\x1b\\\x1b[16t\x1b[c";
+
+// True once the reply to the primary device attributes is received
+#[cfg(all(unix, feature = "crossterm"))]
+fn has_device_attributes(replies: &[u8]) -> bool {
+    replies
+        .windows(3)
+        .rposition(|w| w == b"\x1b[?")
+        .is_some_and(|start| replies[start..].ends_with(b"c"))
+}
+
+/// Write the query to the output and read the replies from stdin,
+/// until the reply to the primary device attributes or the timeout.
+///
+/// This has to be called before anything else reads from stdin,
+/// and with raw mode enabled.
+#[cfg(all(unix, feature = "crossterm"))]
+pub(crate) fn query(mut output: impl std::io::Write, timeout: std::time::Duration) -> std::io::Result<Vec<u8>> {
+    use std::io::IsTerminal;
+    use std::os::fd::AsFd;
+    use std::time::Instant;
+
+    use rustix::event::{poll, PollFd, PollFlags};
+
+    let stdin = std::io::stdin();
+    if !stdin.is_terminal() {
+        return Ok(vec![]);
+    }
+
+    output.write_all(QUERY)?;
+    output.flush()?;
+
+    let deadline = Instant::now() + timeout;
+    let mut replies = vec![];
+    let mut buffer = [0; 256];
+
+    while !has_device_attributes(&replies) {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        if timeout.is_zero() {
+            break;
+        }
+
+        let fd = stdin.as_fd();
+        let mut fds = [PollFd::new(&fd, PollFlags::IN)];
+        if poll(&mut fds, timeout.as_millis() as i32)? == 0 {
+            break;
+        }
+
+        match rustix::io::read(fd, &mut buffer)? {
+            0 => break,
+            n => replies.extend_from_slice(&buffer[..n]),
+        }
+    }
+
+    Ok(replies)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn env() {
+        let vars = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| vars.iter().find(|(n, _)| *n == name).map(|(_, v)| v.to_string())
+        };
+
+        let caps = Capabilities::from_vars(vars(&[("TERM", "xterm-256color"), ("COLORTERM", "truecolor")]));
+        assert_eq!(caps.colors, ColorDepth::TrueColor);
+        assert!(!caps.kitty_graphics);
+
+        let caps = Capabilities::from_vars(vars(&[("TERM", "screen-256color")]));
+        assert_eq!(caps.colors, ColorDepth::Ansi256);
+
+        let caps = Capabilities::from_vars(vars(&[("TERM", "xterm-kitty")]));
+        assert!(caps.colors == ColorDepth::TrueColor && caps.kitty_graphics);
+        assert!(caps.synchronized_output);
+
+        let caps = Capabilities::from_vars(vars(&[("TERM", "linux")]));
+        assert_eq!(caps, Capabilities::default());
+    }
+
+    #[test]
+    fn replies() {
+        let mut caps = Capabilities::default();
+        caps.apply_replies(b"\x1b[?2026;2$y\x1b_Gi=31;OK\x1b\\\x1b[6;20;10t\x1b[?62;4;22c");
+        assert!(caps.synchronized_output);
+        assert!(caps.kitty_graphics);
+        assert!(caps.sixel);
+        assert_eq!(caps.cell_size, Some(Size::new(10, 20)));
+
+        // Not supported
+        let mut caps = Capabilities::default();
+        caps.apply_replies(b"\x1b[?2026;0$y\x1b_Gi=31;ENOTSUPPORTED\x1b\\\x1b[?1;2c");
+        assert_eq!(caps, Capabilities::default());
+    }
+}
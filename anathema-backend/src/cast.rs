@@ -0,0 +1,241 @@
+//! Record the rendered frames of a backend as an [asciinema](https://asciinema.org) cast.
+//!
+//! ```ignore
+//! let backend = Cast::to_file(TuiBackend::builder().finish()?, "demo.cast")?;
+//! ```
+//!
+//! The cast is written in the v2 format: a header followed by one line per frame,
+//! with the escape sequences that draw the changes since the previous frame.
+//! The cast can be played with `asciinema play demo.cast`.
+use std::fs::File;
+use std::io::{BufWriter, Result, Write};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anathema_geometry::Size;
+use anathema_store::tree::{Node, TreeValues};
+use anathema_widgets::components::events::Event;
+use anathema_widgets::debug::Overlay;
+use anathema_widgets::time::Instant;
+use anathema_widgets::{AttributeStorage, Element, WidgetKind, WidgetRenderer};
+
+use crate::tui::Screen;
+use crate::{Backend, Capabilities, EventSource, Renderer, Waker};
+
+/// Writes every frame rendered by the inner backend to the output, as an asciinema cast.
+///
+/// The frames are painted to a separate screen, so any backend can be recorded.
+pub struct Cast<B> {
+    backend: B,
+    output: Box<dyn Write>,
+    screen: Screen,
+    frame: Vec<u8>,
+    start: Option<Instant>,
+}
+
+impl<B: Backend> Cast<B> {
+    /// Record the frames of the backend to the output
+    pub fn new(backend: B, output: impl Write + 'static) -> Self {
+        let screen = Screen::new(backend.size());
+        Self {
+            backend,
+            output: Box::new(output),
+            screen,
+            frame: vec![],
+            start: None,
+        }
+    }
+
+    /// Record the frames of the backend to a file.
+    /// If the file exists it's truncated.
+    pub fn to_file(backend: B, path: impl AsRef<Path>) -> Result<Self> {
+        let file = File::create(path)?;
+        Ok(Self::new(backend, BufWriter::new(file)))
+    }
+
+    /// The inner backend
+    pub fn inner(&self) -> &B {
+        &self.backend
+    }
+
+    // The time since the first frame, writing the header before the first frame
+    fn elapsed(&mut self) -> Result<Duration> {
+        if let Some(start) = self.start {
+            return Ok(start.elapsed());
+        }
+
+        let size = self.screen.size();
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        writeln!(
+            self.output,
+            r#"{{"version": 2, "width": {}, "height": {}, "timestamp": {}}}"#,
+            size.width,
+            size.height,
+            timestamp.as_secs()
+        )?;
+
+        self.start = Some(Instant::now());
+        Ok(Duration::ZERO)
+    }
+
+    fn write_event(&mut self, code: &str, data: &str) -> Result<()> {
+        let time = self.elapsed()?;
+        writeln!(
+            self.output,
+            "[{:.6}, \"{code}\", \"{}\"]",
+            time.as_secs_f64(),
+            escape(data)
+        )?;
+        self.output.flush()
+    }
+}
+
+impl<B: Backend> EventSource for Cast<B> {
+    fn next_event(&mut self, timeout: Duration) -> Option<Event> {
+        self.backend.next_event(timeout)
+    }
+
+    fn waker(&self) -> Option<Waker> {
+        self.backend.waker()
+    }
+}
+
+impl<B: Backend> Renderer for Cast<B> {
+    fn size(&self) -> Size {
+        self.backend.size()
+    }
+
+    fn resize(&mut self, new_size: Size) {
+        self.screen.resize(new_size);
+        let _ = self.write_event("r", &format!("{}x{}", new_size.width, new_size.height));
+        self.backend.resize(new_size)
+    }
+
+    fn paint<'bp>(
+        &mut self,
+        element: &mut Element<'bp>,
+        children: &[Node],
+        values: &mut TreeValues<WidgetKind<'bp>>,
+        attribute_storage: &AttributeStorage<'bp>,
+        ignore_floats: bool,
+    ) {
+        // Only the first paint reports the damaged regions,
+        // and the screen of the cast always compares the entire buffer
+        self.backend
+            .paint(element, children, values, attribute_storage, ignore_floats);
+        anathema_widgets::paint::paint(
+            &mut self.screen,
+            element,
+            children,
+            values,
+            attribute_storage,
+            ignore_floats,
+        );
+    }
+
+    fn render(&mut self) {
+        self.frame.clear();
+        if self.start.is_none() {
+            // Hide the cursor of the player
+            let _ = Screen::hide_cursor(&mut self.frame);
+        }
+
+        if self.screen.render(&mut self.frame).is_ok() && !self.frame.is_empty() {
+            let frame = std::mem::take(&mut self.frame);
+            let _ = self.write_event("o", &String::from_utf8_lossy(&frame));
+            self.frame = frame;
+        }
+
+        self.backend.render()
+    }
+
+    fn cells_written(&self) -> usize {
+        self.backend.cells_written()
+    }
+
+    fn clear(&mut self) {
+        self.screen.erase();
+        self.backend.clear()
+    }
+
+    fn overlay(&mut self, overlay: &dyn Overlay) {
+        self.backend.overlay(overlay);
+        overlay.paint(&mut self.screen);
+    }
+
+    fn finalize(&mut self) {
+        self.backend.finalize()
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.backend.capabilities()
+    }
+}
+
+// Escape a string for a JSON string literal
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use anathema_geometry::LocalPos;
+
+    use super::*;
+    use crate::testing::TestBackend;
+
+    #[derive(Clone, Default)]
+    struct Output(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Output {
+        fn write(&mut self, buf: &[u8]) -> Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn escape_json() {
+        assert_eq!(escape("a\"\\\n\x1b[1m"), "a\\\"\\\\\\n\\u001b[1m");
+    }
+
+    #[test]
+    fn record_frames() {
+        let output = Output::default();
+        let mut cast = Cast::new(TestBackend::new((2, 1)), output.clone());
+
+        cast.screen.paint_glyph('x', LocalPos::ZERO);
+        cast.render();
+
+        // Nothing changed, nothing is written
+        cast.render();
+
+        cast.resize(Size::new(3, 2));
+
+        let output = String::from_utf8(output.0.take()).unwrap();
+        let lines = output.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with(r#"{"version": 2, "width": 2, "height": 1, "timestamp": "#));
+        assert!(lines[1].starts_with("[0.000000, \"o\", \"\\u001b[?25l"));
+        assert!(lines[1].ends_with("x\"]"));
+        assert!(lines[2].ends_with(", \"r\", \"3x2\"]"));
+    }
+}
@@ -0,0 +1,148 @@
+//! Combine an event source and a renderer into a backend.
+//!
+//! ```ignore
+//! // Terminal input, with a custom renderer
+//! let events = Events::new(Duration::from_millis(500));
+//! let backend = Composed::new(events, MyRenderer::new());
+//! ```
+use std::time::Duration;
+
+use anathema_geometry::Size;
+use anathema_store::tree::{Node, TreeValues};
+use anathema_widgets::components::events::Event;
+use anathema_widgets::debug::Overlay;
+use anathema_widgets::{AttributeStorage, Element, WidgetKind};
+
+use crate::{Capabilities, EventSource, Renderer, Waker};
+
+/// A backend made out of a separate [`EventSource`] and [`Renderer`].
+pub struct Composed<E, R> {
+    events: E,
+    renderer: R,
+}
+
+impl<E: EventSource, R: Renderer> Composed<E, R> {
+    /// Create a backend that polls `events` and paints with the `renderer`
+    pub fn new(events: E, renderer: R) -> Self {
+        Self { events, renderer }
+    }
+
+    /// The event source
+    pub fn events(&self) -> &E {
+        &self.events
+    }
+
+    /// Mutable reference to the event source
+    pub fn events_mut(&mut self) -> &mut E {
+        &mut self.events
+    }
+
+    /// The renderer
+    pub fn renderer(&self) -> &R {
+        &self.renderer
+    }
+
+    /// Mutable reference to the renderer
+    pub fn renderer_mut(&mut self) -> &mut R {
+        &mut self.renderer
+    }
+
+    /// Split the backend into the event source and the renderer
+    pub fn into_parts(self) -> (E, R) {
+        (self.events, self.renderer)
+    }
+}
+
+impl<E: EventSource, R> EventSource for Composed<E, R> {
+    fn next_event(&mut self, timeout: Duration) -> Option<Event> {
+        self.events.next_event(timeout)
+    }
+
+    fn waker(&self) -> Option<Waker> {
+        self.events.waker()
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<E: crate::AsyncEventSource, R> crate::AsyncEventSource for Composed<E, R> {
+    async fn wait_event(&mut self) -> Option<Event> {
+        self.events.wait_event().await
+    }
+}
+
+impl<E, R: Renderer> Renderer for Composed<E, R> {
+    fn size(&self) -> Size {
+        self.renderer.size()
+    }
+
+    fn resize(&mut self, new_size: Size) {
+        self.renderer.resize(new_size);
+    }
+
+    fn paint<'bp>(
+        &mut self,
+        element: &mut Element<'bp>,
+        children: &[Node],
+        values: &mut TreeValues<WidgetKind<'bp>>,
+        attribute_storage: &AttributeStorage<'bp>,
+        ignore_floats: bool,
+    ) {
+        self.renderer
+            .paint(element, children, values, attribute_storage, ignore_floats);
+    }
+
+    fn render(&mut self) {
+        self.renderer.render();
+    }
+
+    fn clear(&mut self) {
+        self.renderer.clear();
+    }
+
+    fn overlay(&mut self, overlay: &dyn Overlay) {
+        self.renderer.overlay(overlay);
+    }
+
+    fn cells_written(&self) -> usize {
+        self.renderer.cells_written()
+    }
+
+    fn finalize(&mut self) {
+        self.renderer.finalize();
+    }
+
+    fn set_title(&mut self, title: &str) {
+        self.renderer.set_title(title);
+    }
+
+    fn bell(&mut self) {
+        self.renderer.bell();
+    }
+
+    fn copy(&mut self, text: &str) {
+        self.renderer.copy(text);
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.renderer.capabilities()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::VecDeque;
+
+    use super::*;
+    use crate::testing::TestBackend;
+
+    #[test]
+    fn synthetic_events() {
+        let events = VecDeque::from([Event::Resize(4, 2), Event::Stop]);
+        let mut backend = Composed::new(events, TestBackend::new(Size::new(3, 2)));
+
+        assert_eq!(backend.size(), Size::new(3, 2));
+        assert!(matches!(backend.next_event(Duration::ZERO), Some(Event::Resize(4, 2))));
+        assert!(matches!(backend.next_event(Duration::ZERO), Some(Event::Stop)));
+        assert!(backend.next_event(Duration::ZERO).is_none());
+    }
+}
@@ -0,0 +1,244 @@
+//! Export a painted frame as HTML.
+//!
+//! ```ignore
+//! let html = HtmlExport::new().buffer(&buffer);
+//! std::fs::write("frame.html", html)?;
+//! ```
+//!
+//! The frame is a `<pre>` element, with a `<span>` for every run of cells sharing the same style.
+//! Palette colours use the default xterm colours.
+use std::fmt::Write;
+
+use anathema_state::Color;
+
+use crate::testing::TestSurface;
+use crate::tui::{ansi, Attributes, Buffer, Glyph, Style};
+
+/// Converts glyphs and styles to HTML
+#[derive(Debug, Copy, Clone)]
+pub struct HtmlExport {
+    foreground: Color,
+    background: Color,
+}
+
+impl Default for HtmlExport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HtmlExport {
+    /// Create a new exporter, with light grey text on a black background
+    pub const fn new() -> Self {
+        Self {
+            foreground: Color::Grey,
+            background: Color::Black,
+        }
+    }
+
+    /// The colour of text without a foreground colour
+    pub fn foreground(mut self, color: Color) -> Self {
+        self.foreground = color;
+        self
+    }
+
+    /// The colour of cells without a background colour
+    pub fn background(mut self, color: Color) -> Self {
+        self.background = color;
+        self
+    }
+
+    /// Export a buffer, e.g the buffer of a [`Screen`](crate::tui::Screen)
+    pub fn buffer(&self, buffer: &Buffer) -> String {
+        self.export(buffer.text_rows())
+    }
+
+    /// Export the surface of a [`TestBackend`](crate::testing::TestBackend)
+    pub fn surface(&self, surface: &TestSurface) -> String {
+        self.export(
+            surface
+                .rows()
+                .map(|row| row.iter().map(|cell| (Glyph::Char(cell.c), cell.style))),
+        )
+    }
+
+    fn export<'a, R, C>(&self, rows: R) -> String
+    where
+        R: Iterator<Item = C>,
+        C: Iterator<Item = (Glyph<'a>, Style)>,
+    {
+        let mut html = format!(
+            r#"<pre style="color: {}; background-color: {}">"#,
+            css_color(self.foreground),
+            css_color(self.background)
+        );
+
+        for (y, row) in rows.enumerate() {
+            if y > 0 {
+                html.push('\n');
+            }
+
+            let mut span: Option<(String, String)> = None;
+            for (glyph, style) in row {
+                let css = self.css(style);
+                match &mut span {
+                    Some((span_css, text)) if *span_css == css => escape(glyph, text),
+                    _ => {
+                        if let Some((css, text)) = span.take() {
+                            push_span(&mut html, &css, &text);
+                        }
+                        let mut text = String::new();
+                        escape(glyph, &mut text);
+                        span = Some((css, text));
+                    }
+                }
+            }
+
+            if let Some((css, text)) = span {
+                push_span(&mut html, &css, &text);
+            }
+        }
+
+        html.push_str("</pre>");
+        html
+    }
+
+    // The inline style of a cell
+    fn css(&self, style: Style) -> String {
+        let mut fg = style.fg.filter(|c| *c != Color::Reset);
+        let mut bg = style.bg.filter(|c| *c != Color::Reset);
+
+        if style.attributes.contains(Attributes::INVERSE) {
+            let inverse_fg = bg.unwrap_or(self.background);
+            bg = Some(fg.unwrap_or(self.foreground));
+            fg = Some(inverse_fg);
+        }
+
+        let mut css = String::new();
+        if let Some(fg) = fg {
+            let _ = write!(css, "color: {}; ", css_color(fg));
+        }
+
+        if let Some(bg) = bg {
+            let _ = write!(css, "background-color: {}; ", css_color(bg));
+        }
+
+        if style.attributes.contains(Attributes::BOLD) {
+            css.push_str("font-weight: bold; ");
+        }
+
+        if style.attributes.contains(Attributes::DIM) {
+            css.push_str("opacity: 0.5; ");
+        }
+
+        if style.attributes.contains(Attributes::ITALIC) {
+            css.push_str("font-style: italic; ");
+        }
+
+        let decorations = [
+            (Attributes::ANY_UNDERLINE, "underline"),
+            (Attributes::OVERLINED, "overline"),
+            (Attributes::CROSSED_OUT, "line-through"),
+        ]
+        .into_iter()
+        .filter(|(attribute, _)| style.attributes.intersects(*attribute))
+        .map(|(_, decoration)| decoration)
+        .collect::<Vec<_>>();
+
+        if !decorations.is_empty() {
+            let _ = write!(css, "text-decoration: {}; ", decorations.join(" "));
+        }
+
+        let decoration_style = match style.attributes {
+            a if a.contains(Attributes::CURLY_UNDERLINED) => Some("wavy"),
+            a if a.contains(Attributes::DOTTED_UNDERLINED) => Some("dotted"),
+            a if a.contains(Attributes::DASHED_UNDERLINED) => Some("dashed"),
+            a if a.contains(Attributes::DOUBLE_UNDERLINED) => Some("double"),
+            _ => None,
+        };
+
+        if let Some(decoration_style) = decoration_style {
+            let _ = write!(css, "text-decoration-style: {decoration_style}; ");
+        }
+
+        if let Some(color) = style.underline_color.filter(|c| *c != Color::Reset) {
+            if style.attributes.intersects(Attributes::ANY_UNDERLINE) {
+                let _ = write!(css, "text-decoration-color: {}; ", css_color(color));
+            }
+        }
+
+        css.truncate(css.trim_end().len());
+        css
+    }
+}
+
+fn push_span(html: &mut String, css: &str, text: &str) {
+    match css.is_empty() {
+        true => html.push_str(text),
+        false => {
+            let _ = write!(html, r#"<span style="{css}">{text}</span>"#);
+        }
+    }
+}
+
+fn escape(glyph: Glyph<'_>, output: &mut String) {
+    let mut escape_char = |c| match c {
+        '&' => output.push_str("&amp;"),
+        '<' => output.push_str("&lt;"),
+        '>' => output.push_str("&gt;"),
+        c => output.push(c),
+    };
+
+    match glyph {
+        Glyph::Char(c) => escape_char(c),
+        Glyph::Cluster(glyph) => glyph.chars().for_each(escape_char),
+    }
+}
+
+fn css_color(color: Color) -> String {
+    let (r, g, b) = match color {
+        Color::Rgb(r, g, b) => (r, g, b),
+        color => match ansi::palette_index(color) {
+            Some(index) => ansi::palette_rgb(index),
+            None => return "inherit".into(),
+        },
+    };
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+#[cfg(test)]
+mod test {
+    use anathema_geometry::LocalPos;
+
+    use super::*;
+
+    #[test]
+    fn palette() {
+        assert_eq!(css_color(Color::Red), "#cd0000");
+        assert_eq!(css_color(Color::AnsiVal(196)), "#ff0000");
+        assert_eq!(css_color(Color::AnsiVal(244)), "#808080");
+        assert_eq!(css_color(Color::Rgb(1, 2, 3)), "#010203");
+    }
+
+    #[test]
+    fn export_buffer() {
+        let mut buffer = Buffer::new((4u16, 2));
+        let mut style = Style::new();
+        style.set_fg(Color::Rgb(255, 0, 0));
+        style.set_bold(true);
+
+        buffer.put_char('<', LocalPos::new(0, 0));
+        buffer.update_cell(style, LocalPos::new(1, 0));
+        buffer.put_char('a', LocalPos::new(1, 0));
+        buffer.update_cell(style, LocalPos::new(2, 0));
+        buffer.put_char('b', LocalPos::new(2, 0));
+        buffer.put_char('c', LocalPos::new(0, 1));
+
+        let html = HtmlExport::new().buffer(&buffer);
+        assert_eq!(
+            html,
+            "<pre style=\"color: #e5e5e5; background-color: #000000\">&lt;\
+             <span style=\"color: #ff0000; font-weight: bold;\">ab</span> \nc   </pre>"
+        );
+    }
+}
@@ -1,19 +1,94 @@
+use std::any::Any;
+use std::collections::VecDeque;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
 use std::time::Duration;
 
+use anathema_debug::profile::span;
 use anathema_geometry::{Pos, Size};
 use anathema_store::tree::{AsNodePath, Node, TreeValues};
 use anathema_widgets::components::events::Event;
-use anathema_widgets::layout::{layout_widget, position_widget, Constraints, LayoutCtx, LayoutFilter, Viewport};
-use anathema_widgets::{AttributeStorage, Element, FloatingWidgets, WidgetKind, WidgetTree};
+use anathema_widgets::debug::{LayoutOverlay, Overlay};
+use anathema_widgets::layout::{
+    layout_widget, position_widget, relayout_widget, Constraints, Diagnostic, LayoutCtx, LayoutDirection, LayoutFilter,
+    Viewport,
+};
+use anathema_widgets::paint::z_index;
+use anathema_widgets::time::Instant;
+use anathema_widgets::{AttributeStorage, DirtyWidgets, Element, FloatingWidgets, WidgetId, WidgetKind, WidgetTree};
+pub use capabilities::{Capabilities, ColorDepth};
+pub use compose::Composed;
 
-pub mod test;
+pub mod capabilities;
+pub mod cast;
+pub mod compose;
+pub mod html;
+pub mod record;
+#[cfg(feature = "ssh")]
+pub mod ssh;
+#[cfg(feature = "termion")]
+pub mod termion;
+#[cfg(feature = "termwiz")]
+pub mod termwiz;
+pub mod testing;
 pub mod tui;
+#[cfg(any(target_arch = "wasm32", test))]
+pub mod wasm;
+pub mod write;
 
-pub trait Backend {
-    fn size(&self) -> Size;
+/// Makes an [`EventSource`] that is waiting for events return early.
+/// Wakers can be called from any thread.
+pub type Waker = Arc<dyn Fn() + Send + Sync>;
 
+/// A source of events, e.g the terminal input.
+pub trait EventSource {
+    /// Wait at most `timeout` for the next event.
+    /// An idle runtime sleeps in this call.
     fn next_event(&mut self, timeout: Duration) -> Option<Event>;
 
+    /// A [`Waker`] that makes a waiting [`next_event`](EventSource::next_event) return,
+    /// with or without an event.
+    ///
+    /// The runtime wakes the event source when a message is sent, so an idle runtime
+    /// can wait for the next event, message or timer without waking up in between.
+    /// Event sources without a waker are polled once per frame budget while idle.
+    fn waker(&self) -> Option<Waker> {
+        None
+    }
+}
+
+/// Synthetic events, returned in the order they were pushed.
+/// The timeout is ignored.
+impl EventSource for VecDeque<Event> {
+    fn next_event(&mut self, _: Duration) -> Option<Event> {
+        self.pop_front()
+    }
+}
+
+/// An [`EventSource`] that can be awaited, used by the async runtime.
+#[cfg(feature = "tokio")]
+pub trait AsyncEventSource: EventSource {
+    /// Wait for the next event.
+    /// The future is dropped if a message or a timer wakes the runtime first,
+    /// so it must not lose an event when it's cancelled.
+    fn wait_event(&mut self) -> impl std::future::Future<Output = Option<Event>>;
+}
+
+/// Synthetic events. Once every event is returned this never completes.
+#[cfg(feature = "tokio")]
+impl AsyncEventSource for VecDeque<Event> {
+    async fn wait_event(&mut self) -> Option<Event> {
+        match self.pop_front() {
+            Some(event) => Some(event),
+            None => std::future::pending().await,
+        }
+    }
+}
+
+/// Paints the widgets and renders the output.
+pub trait Renderer {
+    fn size(&self) -> Size;
+
     fn resize(&mut self, new_size: Size);
 
     /// Paint the widgets
@@ -32,8 +107,94 @@ pub trait Backend {
     /// Clear is called immediately after `render` is called.
     fn clear(&mut self);
 
-    /// Finalizes the backend. This is called when the runtime starts.
+    /// Paint an overlay on top of the widgets, e.g the layout debug overlay.
+    /// This is called after the widgets are painted, if an overlay is enabled.
+    /// Renderers without a surface ignore this.
+    #[allow(unused_variables)]
+    fn overlay(&mut self, overlay: &dyn Overlay) {}
+
+    /// The number of cells written to the output by the last call to `render`.
+    /// Renderers that don't diff their output report zero.
+    fn cells_written(&self) -> usize {
+        0
+    }
+
+    /// Finalizes the renderer. This is called when the runtime starts.
     fn finalize(&mut self) {}
+
+    /// Set the title of the terminal window.
+    /// Renderers without a terminal ignore this.
+    #[allow(unused_variables)]
+    fn set_title(&mut self, title: &str) {}
+
+    /// Ring the terminal bell.
+    /// Renderers without a terminal ignore this.
+    fn bell(&mut self) {}
+
+    /// Copy the text to the clipboard of the terminal, e.g the text selected with the mouse.
+    /// Renderers without a terminal ignore this.
+    #[allow(unused_variables)]
+    fn copy(&mut self, text: &str) {}
+
+    /// The features supported by the terminal.
+    /// Nothing is supported by default.
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::default()
+    }
+}
+
+/// A backend is both an [`EventSource`] and a [`Renderer`].
+///
+/// This is implemented for everything that implements both traits,
+/// use [`Composed`] to combine a separate event source and renderer.
+pub trait Backend: EventSource + Renderer {}
+
+impl<T: EventSource + Renderer> Backend for T {}
+
+/// Time spent in each stage of a [`WidgetCycle`]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct CycleTimings {
+    /// Layout and position
+    pub layout: Duration,
+    /// Paint
+    pub paint: Duration,
+    /// The number of widgets laid out.
+    /// Widgets that didn't change and got the same constraints are skipped
+    pub laid_out: usize,
+}
+
+/// A panic raised while laying out the widgets, caught by a [`WidgetCycle`]
+/// (see [`WidgetCycle::catch_layout_panics`]).
+pub struct LayoutPanic {
+    /// The innermost element that was being laid out, if any
+    pub widget_id: Option<WidgetId>,
+    /// The panic payload, to resume the panic with if it can't be handled
+    pub payload: Box<dyn Any + Send>,
+}
+
+// Lay out with `f`, catching a panic if `catch` is set.
+// Returns `false` if the layout panicked.
+fn try_layout<'a, 'bp>(
+    catch: bool,
+    caught: &mut Option<LayoutPanic>,
+    ctx: &mut LayoutCtx<'a, 'bp>,
+    f: impl FnOnce(&mut LayoutCtx<'a, 'bp>),
+) -> bool {
+    if !catch {
+        f(ctx);
+        return true;
+    }
+
+    match panic::catch_unwind(AssertUnwindSafe(|| f(ctx))) {
+        Ok(()) => true,
+        Err(payload) => {
+            *caught = Some(LayoutPanic {
+                widget_id: ctx.laying_out(),
+                payload,
+            });
+            false
+        }
+    }
 }
 
 // TODO: rename this.
@@ -46,6 +207,13 @@ pub struct WidgetCycle<'rt, 'bp, T> {
     attribute_storage: &'rt AttributeStorage<'bp>,
     floating_widgets: &'rt FloatingWidgets,
     viewport: Viewport,
+    deadline: Option<Instant>,
+    interrupted: bool,
+    overlay: bool,
+    diagnostics: Vec<Diagnostic>,
+    invalidated: Vec<WidgetId>,
+    catch_panics: bool,
+    panic: Option<LayoutPanic>,
 }
 
 impl<'rt, 'bp, T: Backend> WidgetCycle<'rt, 'bp, T> {
@@ -64,44 +232,208 @@ impl<'rt, 'bp, T: Backend> WidgetCycle<'rt, 'bp, T> {
             attribute_storage,
             floating_widgets,
             viewport,
+            deadline: None,
+            interrupted: false,
+            overlay: false,
+            diagnostics: vec![],
+            invalidated: vec![],
+            catch_panics: false,
+            panic: None,
         }
     }
 
-    fn floating(&mut self) {
-        // Floating widgets
-        for widget_id in self.floating_widgets.iter() {
+    /// Yield once the deadline has passed, leaving the remaining layout
+    /// for the next cycle. Nothing is painted by an interrupted cycle.
+    pub fn layout_deadline(mut self, deadline: Option<Instant>) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    /// Paint the [`LayoutOverlay`] on top of the widgets
+    pub fn debug_overlay(mut self, enabled: bool) -> Self {
+        self.overlay = enabled;
+        self
+    }
+
+    /// Widgets that changed since the last cycle, e.g. by a change to the state.
+    /// The closest element above each widget is laid out again in place,
+    /// and the rest of the tree is only laid out again if the size of that element changed.
+    pub fn invalidated(mut self, widgets: impl IntoIterator<Item = WidgetId>) -> Self {
+        self.invalidated.extend(widgets);
+        self
+    }
+
+    /// Catch a panic raised during the layout instead of unwinding.
+    /// The cycle is interrupted and the panic is returned by [`WidgetCycle::take_layout_panic`].
+    pub fn catch_layout_panics(mut self, enabled: bool) -> Self {
+        self.catch_panics = enabled;
+        self
+    }
+
+    /// The panic caught during the layout, if any
+    pub fn take_layout_panic(&mut self) -> Option<LayoutPanic> {
+        self.panic.take()
+    }
+
+    /// The problems found during the layout, e.g a widget that exceeds its constraints
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.diagnostics)
+    }
+
+    /// True if the layout ran out of time (or a caught panic cut it short)
+    /// and the cycle has to run again to finish it.
+    pub fn interrupted(&self) -> bool {
+        self.interrupted
+    }
+
+    // The closest element above the widget,
+    // along with the layout direction it inherits from the element above that.
+    fn closest_element(&self, widget_id: WidgetId) -> Option<(WidgetId, LayoutDirection)> {
+        let mut path = self.tree.try_path_ref(widget_id)?;
+        let mut element = None;
+        while let Some(parent) = path.parent() {
+            if let Some(WidgetKind::Element(el)) = self.tree.get_ref_by_path(parent) {
+                match element {
+                    None => element = Some(el.id()),
+                    Some(element) => return Some((element, el.direction())),
+                }
+            }
+            path = parent;
+        }
+        element.map(|element| (element, self.viewport.direction()))
+    }
+
+    // Lay out the closest element above each invalidated widget, using its previous constraints.
+    // The layout only continues up the tree (in `run`) if that changes the size of the element.
+    fn subtrees(&mut self, timings: &mut CycleTimings) {
+        let mut subtrees = vec![];
+        for widget_id in std::mem::take(&mut self.invalidated) {
+            let Some(subtree) = self.closest_element(widget_id) else { continue };
+            if !subtrees.contains(&subtree) {
+                subtrees.push(subtree);
+            }
+        }
+
+        // The parents of the subtrees that changed size
+        let mut dirty_widgets = DirtyWidgets::empty();
+
+        for (widget_id, direction) in subtrees {
+            let layout_span = span("layout");
+            let now = Instant::now();
+            let mut layout_ctx = LayoutCtx::new(self.attribute_storage, &self.viewport).with_deadline(self.deadline);
+            layout_ctx.direction = direction;
+
+            let mut contained = false;
+            let mut panicked = false;
+            self.tree.with_nodes_and_values(widget_id, |widget, children, values| {
+                let WidgetKind::Element(el) = widget else { return };
+                panicked = !try_layout(self.catch_panics, &mut self.panic, &mut layout_ctx, |ctx| {
+                    contained = relayout_widget(el, children, values, ctx)
+                });
+                if contained && !panicked {
+                    let pos = el.get_pos();
+                    position_widget(pos, el, children, values, self.attribute_storage, true, self.viewport);
+                }
+            });
+
+            timings.laid_out += layout_ctx.completed();
+            timings.layout += now.elapsed();
+            self.diagnostics.extend(layout_ctx.take_diagnostics());
+            drop(layout_span);
+
+            if panicked {
+                self.interrupted = true;
+                break;
+            }
+
+            if !contained {
+                if let Some((parent, _)) = self.closest_element(widget_id) {
+                    dirty_widgets.push(parent);
+                }
+            }
+        }
+
+        dirty_widgets.apply(self.tree);
+    }
+
+    fn floating(&mut self, timings: &mut CycleTimings) {
+        // Floating widgets, painted in the order of their `z` attribute
+        let mut floating_widgets = self.floating_widgets.iter().copied().collect::<Vec<_>>();
+        floating_widgets.sort_by_key(|widget_id| z_index(self.attribute_storage, *widget_id));
+
+        for widget_id in &floating_widgets {
+            if self.interrupted {
+                break;
+            }
+
+            // Portals are placed inside their outlet, rather than their parent
+            let outlet = self.attribute_storage.portal_outlet(*widget_id).and_then(|outlet| {
+                match self.tree.get_ref_by_id(outlet) {
+                    Some(WidgetKind::Element(el)) => Some((el.inner_bounds(), el.direction())),
+                    _ => None,
+                }
+            });
+
             // Find the parent widget and get the position
             // If no parent element is found assume Pos::ZERO
             let mut parent = self.tree.path_ref(*widget_id).parent();
-            let (pos, constraints) = loop {
-                match parent {
-                    None => break (Pos::ZERO, self.constraints),
-                    Some(p) => match self.tree.get_ref_by_path(p) {
-                        Some(WidgetKind::Element(el)) => {
-                            let bounds = el.inner_bounds();
-                            break (bounds.start, Constraints::from(bounds));
-                        }
-                        _ => parent = p.parent(),
-                    },
-                }
+            let (pos, constraints, direction) = match outlet {
+                Some((bounds, direction)) => (bounds.start, Constraints::from(bounds), direction),
+                None => loop {
+                    match parent {
+                        None => break (Pos::ZERO, self.constraints, self.viewport.direction()),
+                        Some(p) => match self.tree.get_ref_by_path(p) {
+                            Some(WidgetKind::Element(el)) => {
+                                let bounds = el.inner_bounds();
+                                break (bounds.start, Constraints::from(bounds), el.direction());
+                            }
+                            _ => parent = p.parent(),
+                        },
+                    }
+                },
             };
 
             self.tree.with_nodes_and_values(*widget_id, |widget, children, values| {
                 let WidgetKind::Element(el) = widget else { unreachable!("this is always a floating widget") };
-                let mut layout_ctx = LayoutCtx::new(self.attribute_storage, &self.viewport);
+                let layout_span = span("layout");
+                let now = Instant::now();
+                let mut layout_ctx =
+                    LayoutCtx::new(self.attribute_storage, &self.viewport).with_deadline(self.deadline);
+                // Floating widgets inherit the direction of the element they are placed in
+                layout_ctx.direction = direction;
 
-                layout_widget(el, children, values, constraints, &mut layout_ctx, true);
+                let laid_out = try_layout(self.catch_panics, &mut self.panic, &mut layout_ctx, |ctx| {
+                    layout_widget(el, children, values, constraints, ctx, true)
+                });
+                timings.laid_out += layout_ctx.completed();
+                self.diagnostics.extend(layout_ctx.take_diagnostics());
+                if !laid_out || layout_ctx.interrupted() {
+                    self.interrupted = true;
+                    timings.layout += now.elapsed();
+                    return;
+                }
 
                 // Position
                 position_widget(pos, el, children, values, self.attribute_storage, true, self.viewport);
+                timings.layout += now.elapsed();
+                drop(layout_span);
 
                 // Paint
+                let _span = span("paint");
+                let now = Instant::now();
                 self.backend.paint(el, children, values, self.attribute_storage, true);
+                timings.paint += now.elapsed();
             });
         }
     }
 
-    pub fn run(&mut self) {
+    pub fn run(&mut self) -> CycleTimings {
+        let mut timings = CycleTimings::default();
+        self.subtrees(&mut timings);
+        if self.panic.is_some() {
+            return timings;
+        }
+
         let mut filter = LayoutFilter::new(true, self.attribute_storage);
         self.tree.for_each(&mut filter).first(&mut |widget, children, values| {
             // Layout
@@ -110,8 +442,19 @@ impl<'rt, 'bp, T: Backend> WidgetCycle<'rt, 'bp, T> {
             //
             //       That doesn't have as much of an impact here
             //       as it will do when dealing with the floating widgets
-            let mut layout_ctx = LayoutCtx::new(self.attribute_storage, &self.viewport);
-            layout_widget(widget, children, values, self.constraints, &mut layout_ctx, true);
+            let layout_span = span("layout");
+            let now = Instant::now();
+            let mut layout_ctx = LayoutCtx::new(self.attribute_storage, &self.viewport).with_deadline(self.deadline);
+            let laid_out = try_layout(self.catch_panics, &mut self.panic, &mut layout_ctx, |ctx| {
+                layout_widget(widget, children, values, self.constraints, ctx, true)
+            });
+            timings.laid_out += layout_ctx.completed();
+            self.diagnostics.extend(layout_ctx.take_diagnostics());
+            if !laid_out || layout_ctx.interrupted() {
+                self.interrupted = true;
+                timings.layout += now.elapsed();
+                return;
+            }
 
             // Position
             position_widget(
@@ -123,12 +466,24 @@ impl<'rt, 'bp, T: Backend> WidgetCycle<'rt, 'bp, T> {
                 true,
                 self.viewport,
             );
+            timings.layout += now.elapsed();
+            drop(layout_span);
 
             // Paint
+            let _span = span("paint");
+            let now = Instant::now();
             self.backend
                 .paint(widget, children, values, self.attribute_storage, true);
+            timings.paint += now.elapsed();
         });
 
-        self.floating();
+        self.floating(&mut timings);
+
+        if self.overlay && !self.interrupted {
+            let overlay = LayoutOverlay::new(self.tree, self.attribute_storage);
+            self.backend.overlay(&overlay);
+        }
+
+        timings
     }
 }
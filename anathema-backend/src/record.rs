@@ -0,0 +1,575 @@
+//! Record the events of a backend and replay them later.
+//!
+//! ```ignore
+//! // Record a session
+//! let backend = Record::to_file(TuiBackend::builder().finish()?, "session.events")?;
+//!
+//! // ... and replay it
+//! let backend = Replay::from_file(TuiBackend::builder().finish()?, "session.events")?;
+//! ```
+//!
+//! Every event is stored on a separate line, prefixed with the number of
+//! microseconds since the first call to [`EventSource::next_event`].
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Error, ErrorKind, Result, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use anathema_geometry::Size;
+use anathema_store::tree::{Node, TreeValues};
+use anathema_widgets::components::events::{
+    Event, ImeEvent, KeyCode, KeyEvent, KeyState, MouseButton, MouseEvent, MouseState, Preedit,
+};
+use anathema_widgets::debug::Overlay;
+use anathema_widgets::time::Instant;
+use anathema_widgets::{AttributeStorage, Element, WidgetKind};
+
+use crate::{Backend, Capabilities, EventSource, Renderer, Waker};
+
+/// Writes every event received by the inner backend to the output.
+pub struct Record<B> {
+    backend: B,
+    output: Box<dyn Write>,
+    start: Option<Instant>,
+}
+
+impl<B: Backend> Record<B> {
+    /// Record the events of the backend to the output
+    pub fn new(backend: B, output: impl Write + 'static) -> Self {
+        Self {
+            backend,
+            output: Box::new(output),
+            start: None,
+        }
+    }
+
+    /// Record the events of the backend to a file.
+    /// If the file exists it's truncated.
+    pub fn to_file(backend: B, path: impl AsRef<Path>) -> Result<Self> {
+        let file = File::create(path)?;
+        Ok(Self::new(backend, BufWriter::new(file)))
+    }
+
+    /// The inner backend
+    pub fn inner(&self) -> &B {
+        &self.backend
+    }
+}
+
+/// Replays recorded events, at the same pace as they were recorded.
+///
+/// Once all the events are replayed the events of the inner backend are used.
+pub struct Replay<B> {
+    backend: B,
+    events: VecDeque<(Duration, Event)>,
+    start: Option<Instant>,
+    realtime: bool,
+}
+
+impl<B: Backend> Replay<B> {
+    /// Replay the events read from the input
+    pub fn new(backend: B, input: impl BufRead) -> Result<Self> {
+        let mut events = VecDeque::new();
+        for line in input.lines() {
+            let line = line?;
+            if line.is_empty() {
+                continue;
+            }
+            events.push_back(decode_line(&line)?);
+        }
+
+        Ok(Self {
+            backend,
+            events,
+            start: None,
+            realtime: true,
+        })
+    }
+
+    /// Replay the events of a file created by [`Record::to_file`]
+    pub fn from_file(backend: B, path: impl AsRef<Path>) -> Result<Self> {
+        let file = File::open(path)?;
+        Self::new(backend, BufReader::new(file))
+    }
+
+    /// Replay the events as fast as possible, ignoring the timestamps.
+    /// This is useful for tests.
+    pub fn fast(mut self) -> Self {
+        self.realtime = false;
+        self
+    }
+
+    /// The number of events left to replay
+    pub fn remaining(&self) -> usize {
+        self.events.len()
+    }
+
+    /// The inner backend
+    pub fn inner(&self) -> &B {
+        &self.backend
+    }
+}
+
+impl<B: Backend> EventSource for Record<B> {
+    fn next_event(&mut self, timeout: Duration) -> Option<Event> {
+        let start = *self.start.get_or_insert_with(Instant::now);
+        let event = self.backend.next_event(timeout)?;
+        let _ = writeln!(self.output, "{}", encode_line(start.elapsed(), &event));
+        let _ = self.output.flush();
+        Some(event)
+    }
+
+    fn waker(&self) -> Option<Waker> {
+        self.backend.waker()
+    }
+}
+
+impl<B: Backend> Renderer for Record<B> {
+    fn size(&self) -> Size {
+        self.backend.size()
+    }
+
+    fn resize(&mut self, new_size: Size) {
+        self.backend.resize(new_size)
+    }
+
+    fn paint<'bp>(
+        &mut self,
+        element: &mut Element<'bp>,
+        children: &[Node],
+        values: &mut TreeValues<WidgetKind<'bp>>,
+        attribute_storage: &AttributeStorage<'bp>,
+        ignore_floats: bool,
+    ) {
+        self.backend
+            .paint(element, children, values, attribute_storage, ignore_floats)
+    }
+
+    fn render(&mut self) {
+        self.backend.render()
+    }
+
+    fn cells_written(&self) -> usize {
+        self.backend.cells_written()
+    }
+
+    fn clear(&mut self) {
+        self.backend.clear()
+    }
+
+    fn overlay(&mut self, overlay: &dyn Overlay) {
+        self.backend.overlay(overlay)
+    }
+
+    fn finalize(&mut self) {
+        self.backend.finalize()
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.backend.capabilities()
+    }
+}
+
+impl<B: Backend> EventSource for Replay<B> {
+    fn next_event(&mut self, timeout: Duration) -> Option<Event> {
+        let start = *self.start.get_or_insert_with(Instant::now);
+
+        let Some((at, _)) = self.events.front() else { return self.backend.next_event(timeout) };
+
+        if self.realtime {
+            let remaining = at.saturating_sub(start.elapsed());
+            if remaining > timeout {
+                std::thread::sleep(timeout);
+                return None;
+            }
+            std::thread::sleep(remaining);
+        }
+
+        self.events.pop_front().map(|(_, event)| event)
+    }
+}
+
+impl<B: Backend> Renderer for Replay<B> {
+    fn size(&self) -> Size {
+        self.backend.size()
+    }
+
+    fn resize(&mut self, new_size: Size) {
+        self.backend.resize(new_size)
+    }
+
+    fn paint<'bp>(
+        &mut self,
+        element: &mut Element<'bp>,
+        children: &[Node],
+        values: &mut TreeValues<WidgetKind<'bp>>,
+        attribute_storage: &AttributeStorage<'bp>,
+        ignore_floats: bool,
+    ) {
+        self.backend
+            .paint(element, children, values, attribute_storage, ignore_floats)
+    }
+
+    fn render(&mut self) {
+        self.backend.render()
+    }
+
+    fn cells_written(&self) -> usize {
+        self.backend.cells_written()
+    }
+
+    fn clear(&mut self) {
+        self.backend.clear()
+    }
+
+    fn overlay(&mut self, overlay: &dyn Overlay) {
+        self.backend.overlay(overlay)
+    }
+
+    fn finalize(&mut self) {
+        self.backend.finalize()
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.backend.capabilities()
+    }
+}
+
+// -----------------------------------------------------------------------------
+//   - Encoding -
+//   <micros> <event> [args...]
+// -----------------------------------------------------------------------------
+fn encode_line(at: Duration, event: &Event) -> String {
+    format!("{} {}", at.as_micros(), encode_event(event))
+}
+
+fn encode_event(event: &Event) -> String {
+    match event {
+        Event::Noop => "noop".into(),
+        Event::Stop => "stop".into(),
+        Event::FocusGained => "focus-gained".into(),
+        Event::FocusLost => "focus-lost".into(),
+        Event::Resume => "resume".into(),
+        // The value of a raw event can't be stored, so it's replayed as a no-op
+        Event::Raw(_) => "noop".into(),
+        Event::Resize(width, height) => format!("resize {width} {height}"),
+        Event::Paste(text) => format!("paste {}", escape(text)),
+        Event::Ime(ime) => match ime {
+            ImeEvent::Start => "ime start".into(),
+            ImeEvent::Cancel => "ime cancel".into(),
+            ImeEvent::Commit(text) => format!("ime commit {}", escape(text)),
+            ImeEvent::Preedit(preedit) => {
+                let cursor = preedit.cursor.map_or("-".into(), |cursor| cursor.to_string());
+                let selection = match &preedit.selection {
+                    Some(selection) => format!("{}-{}", selection.start, selection.end),
+                    None => "-".into(),
+                };
+                format!("ime preedit {cursor} {selection} {}", escape(&preedit.text))
+            }
+        },
+        Event::Key(key) => {
+            let state = match key.state {
+                KeyState::Press => "press",
+                KeyState::Repeat => "repeat",
+                KeyState::Release => "release",
+            };
+            let mut modifiers = String::new();
+            for (set, c) in [(key.ctrl, 'c'), (key.alt, 'a'), (key.shift, 's'), (key.super_key, 'm')] {
+                if set {
+                    modifiers.push(c);
+                }
+            }
+            if modifiers.is_empty() {
+                modifiers.push('-');
+            }
+            format!("key {state} {modifiers} {}", encode_key_code(key.code))
+        }
+        Event::Mouse(mouse) => {
+            let button = |button: &MouseButton| match button {
+                MouseButton::Left => "left",
+                MouseButton::Middle => "middle",
+                MouseButton::Right => "right",
+            };
+            let state = match &mouse.state {
+                MouseState::Down(b) => format!("down:{}", button(b)),
+                MouseState::Up(b) => format!("up:{}", button(b)),
+                MouseState::Drag(b) => format!("drag:{}", button(b)),
+                MouseState::Move => "move".into(),
+                MouseState::ScrollUp => "scroll-up".into(),
+                MouseState::ScrollDown => "scroll-down".into(),
+                MouseState::ScrollLeft => "scroll-left".into(),
+                MouseState::ScrollRight => "scroll-right".into(),
+                MouseState::Enter => "enter".into(),
+                MouseState::Leave => "leave".into(),
+                // States added after the recording format can't be replayed
+                _ => return "noop".into(),
+            };
+            format!("mouse {} {} {} {state}", mouse.x, mouse.y, mouse.clicks)
+        }
+    }
+}
+
+fn encode_key_code(code: KeyCode) -> String {
+    let name = match code {
+        KeyCode::Char(c) => return format!("char:{}", c as u32),
+        KeyCode::F(n) => return format!("f:{n}"),
+        KeyCode::Tab => "tab",
+        KeyCode::BackTab => "backtab",
+        KeyCode::CtrlC => "ctrl-c",
+        KeyCode::Backspace => "backspace",
+        KeyCode::Enter => "enter",
+        KeyCode::Left => "left",
+        KeyCode::Right => "right",
+        KeyCode::Up => "up",
+        KeyCode::Down => "down",
+        KeyCode::Home => "home",
+        KeyCode::End => "end",
+        KeyCode::PageUp => "pageup",
+        KeyCode::PageDown => "pagedown",
+        KeyCode::Delete => "delete",
+        KeyCode::Insert => "insert",
+        KeyCode::Null => "null",
+        KeyCode::Esc => "esc",
+        KeyCode::CapsLock => "capslock",
+        KeyCode::ScrollLock => "scrolllock",
+        KeyCode::NumLock => "numlock",
+        KeyCode::PrintScreen => "printscreen",
+        KeyCode::Pause => "pause",
+        KeyCode::Menu => "menu",
+        KeyCode::KeypadBegin => "keypadbegin",
+    };
+    name.into()
+}
+
+// -----------------------------------------------------------------------------
+//   - Decoding -
+// -----------------------------------------------------------------------------
+fn invalid(line: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("invalid event: {line}"))
+}
+
+fn decode_line(line: &str) -> Result<(Duration, Event)> {
+    let (at, event) = line.split_once(' ').ok_or_else(|| invalid(line))?;
+    let at = at.parse().map_err(|_| invalid(line))?;
+    let event = decode_event(event).ok_or_else(|| invalid(line))?;
+    Ok((Duration::from_micros(at), event))
+}
+
+fn decode_event(event: &str) -> Option<Event> {
+    let (kind, args) = event.split_once(' ').unwrap_or((event, ""));
+    let event = match kind {
+        "noop" => Event::Noop,
+        "stop" => Event::Stop,
+        "focus-gained" => Event::FocusGained,
+        "focus-lost" => Event::FocusLost,
+        "resume" => Event::Resume,
+        "paste" => Event::Paste(unescape(args)?),
+        "ime" => {
+            let (kind, args) = args.split_once(' ').unwrap_or((args, ""));
+            let ime = match kind {
+                "start" => ImeEvent::Start,
+                "cancel" => ImeEvent::Cancel,
+                "commit" => ImeEvent::Commit(unescape(args)?),
+                "preedit" => {
+                    let mut args = args.splitn(3, ' ');
+                    let cursor = match args.next()? {
+                        "-" => None,
+                        cursor => Some(cursor.parse().ok()?),
+                    };
+                    let selection = match args.next()? {
+                        "-" => None,
+                        selection => {
+                            let (start, end) = selection.split_once('-')?;
+                            Some(start.parse().ok()?..end.parse().ok()?)
+                        }
+                    };
+                    let text = unescape(args.next()?)?;
+                    ImeEvent::Preedit(Preedit {
+                        text,
+                        cursor,
+                        selection,
+                    })
+                }
+                _ => return None,
+            };
+            Event::Ime(ime)
+        }
+        "resize" => {
+            let (width, height) = args.split_once(' ')?;
+            Event::Resize(width.parse().ok()?, height.parse().ok()?)
+        }
+        "key" => {
+            let mut args = args.split(' ');
+            let state = match args.next()? {
+                "press" => KeyState::Press,
+                "repeat" => KeyState::Repeat,
+                "release" => KeyState::Release,
+                _ => return None,
+            };
+            let modifiers = args.next()?;
+            let code = decode_key_code(args.next()?)?;
+            let mut key = KeyEvent::new(code, state);
+            key.ctrl = modifiers.contains('c');
+            key.alt = modifiers.contains('a');
+            key.shift = modifiers.contains('s');
+            key.super_key = modifiers.contains('m');
+            Event::Key(key)
+        }
+        "mouse" => {
+            let mut args = args.split(' ');
+            let x = args.next()?.parse().ok()?;
+            let y = args.next()?.parse().ok()?;
+            let clicks = args.next()?.parse().ok()?;
+            let state = args.next()?;
+            let button = |button: &str| match button {
+                "left" => Some(MouseButton::Left),
+                "middle" => Some(MouseButton::Middle),
+                "right" => Some(MouseButton::Right),
+                _ => None,
+            };
+            let state = match state.split_once(':') {
+                Some(("down", b)) => MouseState::Down(button(b)?),
+                Some(("up", b)) => MouseState::Up(button(b)?),
+                Some(("drag", b)) => MouseState::Drag(button(b)?),
+                Some(_) => return None,
+                None => match state {
+                    "move" => MouseState::Move,
+                    "scroll-up" => MouseState::ScrollUp,
+                    "scroll-down" => MouseState::ScrollDown,
+                    "scroll-left" => MouseState::ScrollLeft,
+                    "scroll-right" => MouseState::ScrollRight,
+                    "enter" => MouseState::Enter,
+                    "leave" => MouseState::Leave,
+                    _ => return None,
+                },
+            };
+            Event::Mouse(MouseEvent::new(x, y, state).with_clicks(clicks))
+        }
+        _ => return None,
+    };
+    Some(event)
+}
+
+fn decode_key_code(code: &str) -> Option<KeyCode> {
+    if let Some(c) = code.strip_prefix("char:") {
+        return char::from_u32(c.parse().ok()?).map(KeyCode::Char);
+    }
+
+    if let Some(n) = code.strip_prefix("f:") {
+        return n.parse().ok().map(KeyCode::F);
+    }
+
+    let code = match code {
+        "tab" => KeyCode::Tab,
+        "backtab" => KeyCode::BackTab,
+        "ctrl-c" => KeyCode::CtrlC,
+        "backspace" => KeyCode::Backspace,
+        "enter" => KeyCode::Enter,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "delete" => KeyCode::Delete,
+        "insert" => KeyCode::Insert,
+        "null" => KeyCode::Null,
+        "esc" => KeyCode::Esc,
+        "capslock" => KeyCode::CapsLock,
+        "scrolllock" => KeyCode::ScrollLock,
+        "numlock" => KeyCode::NumLock,
+        "printscreen" => KeyCode::PrintScreen,
+        "pause" => KeyCode::Pause,
+        "menu" => KeyCode::Menu,
+        "keypadbegin" => KeyCode::KeypadBegin,
+        _ => return None,
+    };
+    Some(code)
+}
+
+// Text is stored on a single line
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn unescape(text: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next()? {
+                '\\' => unescaped.push('\\'),
+                'n' => unescaped.push('\n'),
+                'r' => unescaped.push('\r'),
+                _ => return None,
+            },
+            c => unescaped.push(c),
+        }
+    }
+    Some(unescaped)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::TestBackend;
+
+    fn events() -> Vec<Event> {
+        vec![
+            Event::Key(KeyEvent::press(KeyCode::Char(' ')).with_ctrl().with_shift()),
+            Event::Key(KeyEvent::new(KeyCode::F(5), KeyState::Release)),
+            Event::Mouse(MouseEvent::new(3, 4, MouseState::Down(MouseButton::Right)).with_clicks(2)),
+            Event::Paste("a \\ b\nc".into()),
+            Event::Ime(ImeEvent::Preedit(
+                Preedit::new("ni hao").with_cursor(2).with_selection(0..2),
+            )),
+            Event::Ime(ImeEvent::Preedit(Preedit::new(""))),
+            Event::Ime(ImeEvent::Commit("你好".into())),
+            Event::Resize(80, 24),
+            Event::FocusLost,
+        ]
+    }
+
+    #[test]
+    fn round_trip() {
+        for event in events() {
+            let line = encode_line(Duration::from_micros(42), &event);
+            let (at, decoded) = decode_line(&line).unwrap();
+            assert_eq!(at, Duration::from_micros(42));
+            assert_eq!(format!("{decoded:?}"), format!("{event:?}"), "{line}");
+        }
+    }
+
+    #[test]
+    fn replay() {
+        let recording = "0 key press - char:97\n10 resize 5 5\n";
+        let mut backend = Replay::new(TestBackend::new((1, 1)), recording.as_bytes())
+            .unwrap()
+            .fast();
+
+        assert_eq!(backend.remaining(), 2);
+        assert!(matches!(backend.next_event(Duration::ZERO), Some(Event::Key(_))));
+        assert!(matches!(backend.next_event(Duration::ZERO), Some(Event::Resize(5, 5))));
+        assert!(backend.next_event(Duration::ZERO).is_none());
+    }
+
+    #[test]
+    fn invalid_recording() {
+        let error = Replay::new(TestBackend::new((1, 1)), "0 key press - nope".as_bytes())
+            .err()
+            .unwrap();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+}
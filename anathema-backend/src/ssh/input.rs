@@ -0,0 +1,343 @@
+// Decode the bytes sent by a terminal into events.
+//
+// This covers what common terminals send with SGR mouse reporting
+// and bracketed paste enabled: printable characters, control characters,
+// CSI and SS3 sequences (with modifiers), mouse reports and pastes.
+use std::time::Instant;
+
+use anathema_widgets::components::events::{
+    ClickCounter, Event, KeyCode, KeyEvent, MouseButton, MouseEvent, MouseState,
+};
+
+const PASTE_START: &[u8] = b"\x1b[200~";
+const PASTE_END: &[u8] = b"\x1b[201~";
+
+enum Parsed {
+    Event(Event, usize),
+    // Bytes that don't produce an event
+    Skip(usize),
+    // More bytes are needed
+    Incomplete,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct InputParser {
+    buffer: Vec<u8>,
+    paste: Option<Vec<u8>>,
+    clicks: ClickCounter,
+}
+
+impl InputParser {
+    /// Decode the bytes, pushing the events to `events`.
+    /// Incomplete sequences are kept until the next call.
+    pub(crate) fn feed(&mut self, bytes: &[u8], events: &mut impl Extend<Event>) {
+        self.buffer.extend_from_slice(bytes);
+        let mut offset = 0;
+
+        loop {
+            let input = &self.buffer[offset..];
+            if input.is_empty() {
+                break;
+            }
+
+            if let Some(paste) = &mut self.paste {
+                match find(input, PASTE_END) {
+                    Some(end) => {
+                        paste.extend_from_slice(&input[..end]);
+                        let text = String::from_utf8_lossy(paste).into_owned();
+                        events.extend(Some(Event::Paste(text)));
+                        self.paste = None;
+                        offset += end + PASTE_END.len();
+                        continue;
+                    }
+                    None => {
+                        // Keep enough of the input to find an end marker split across reads
+                        let keep = input.len().min(PASTE_END.len() - 1);
+                        paste.extend_from_slice(&input[..input.len() - keep]);
+                        offset += input.len() - keep;
+                        break;
+                    }
+                }
+            }
+
+            if input.starts_with(PASTE_START) {
+                self.paste = Some(vec![]);
+                offset += PASTE_START.len();
+                continue;
+            }
+
+            match parse(input) {
+                Parsed::Event(event, len) => {
+                    let event = match event {
+                        Event::Mouse(mouse) => Event::Mouse(self.clicks.count(mouse, Instant::now())),
+                        event => event,
+                    };
+                    events.extend(Some(event));
+                    offset += len;
+                }
+                Parsed::Skip(len) => offset += len,
+                // A lone escape is the escape key,
+                // as a sequence is sent in one go
+                Parsed::Incomplete if input == b"\x1b" => {
+                    events.extend(Some(Event::Key(KeyEvent::press(KeyCode::Esc))));
+                    offset += 1;
+                }
+                Parsed::Incomplete => break,
+            }
+        }
+
+        self.buffer.drain(..offset);
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+fn key(code: KeyCode) -> Parsed {
+    Parsed::Event(Event::Key(KeyEvent::press(code)), 1)
+}
+
+fn ctrl(c: char) -> Parsed {
+    let key = KeyEvent::press(KeyCode::Char(c)).with_ctrl();
+    Parsed::Event(Event::Key(key), 1)
+}
+
+fn parse(input: &[u8]) -> Parsed {
+    match input[0] {
+        0x1b => parse_escape(input),
+        b'\r' | b'\n' => key(KeyCode::Enter),
+        b'\t' => key(KeyCode::Tab),
+        0x7f | 0x08 => key(KeyCode::Backspace),
+        0x03 => Parsed::Event(Event::Stop, 1),
+        0x00 => ctrl(' '),
+        b @ 0x01..=0x1a => ctrl((b - 1 + b'a') as char),
+        b @ 0x1c..=0x1f => ctrl((b + 0x40) as char),
+        b => {
+            let len = match b {
+                0x00..=0x7f => 1,
+                0xc0..=0xdf => 2,
+                0xe0..=0xef => 3,
+                0xf0..=0xf7 => 4,
+                _ => return Parsed::Skip(1),
+            };
+            if input.len() < len {
+                return Parsed::Incomplete;
+            }
+            match std::str::from_utf8(&input[..len]).ok().and_then(|s| s.chars().next()) {
+                Some(c) => {
+                    let mut key = KeyEvent::press(KeyCode::Char(c));
+                    key.shift = c.is_uppercase();
+                    Parsed::Event(Event::Key(key), len)
+                }
+                None => Parsed::Skip(1),
+            }
+        }
+    }
+}
+
+fn parse_escape(input: &[u8]) -> Parsed {
+    let Some(&next) = input.get(1) else { return Parsed::Incomplete };
+    match next {
+        b'[' => parse_csi(input),
+        b'O' => {
+            let Some(&last) = input.get(2) else { return Parsed::Incomplete };
+            match ss3_key(last) {
+                Some(code) => Parsed::Event(Event::Key(KeyEvent::press(code)), 3),
+                None => Parsed::Skip(3),
+            }
+        }
+        0x1b => key(KeyCode::Esc),
+        // Alt is sent as an escape before the key
+        _ => match parse(&input[1..]) {
+            Parsed::Event(Event::Key(key), len) => Parsed::Event(Event::Key(key.with_alt()), len + 1),
+            Parsed::Incomplete => Parsed::Incomplete,
+            _ => key(KeyCode::Esc),
+        },
+    }
+}
+
+fn ss3_key(byte: u8) -> Option<KeyCode> {
+    let code = match byte {
+        b'P' => KeyCode::F(1),
+        b'Q' => KeyCode::F(2),
+        b'R' => KeyCode::F(3),
+        b'S' => KeyCode::F(4),
+        b'A' => KeyCode::Up,
+        b'B' => KeyCode::Down,
+        b'C' => KeyCode::Right,
+        b'D' => KeyCode::Left,
+        b'H' => KeyCode::Home,
+        b'F' => KeyCode::End,
+        _ => return None,
+    };
+    Some(code)
+}
+
+fn parse_csi(input: &[u8]) -> Parsed {
+    let Some(end) = input[2..].iter().position(|b| (0x40..=0x7e).contains(b)) else {
+        return Parsed::Incomplete;
+    };
+    let end = end + 2;
+    let len = end + 1;
+    let Ok(params) = std::str::from_utf8(&input[2..end]) else { return Parsed::Skip(len) };
+    let last = input[end];
+
+    if let Some(params) = params.strip_prefix('<') {
+        return match parse_mouse(params, last) {
+            Some(mouse) => Parsed::Event(Event::Mouse(mouse), len),
+            None => Parsed::Skip(len),
+        };
+    }
+
+    let mut params = params.split(';').map(|p| p.parse::<u8>().unwrap_or(1));
+    let first = params.next().unwrap_or(1);
+    let modifiers = params.next().unwrap_or(1);
+
+    let code = match last {
+        b'A' => KeyCode::Up,
+        b'B' => KeyCode::Down,
+        b'C' => KeyCode::Right,
+        b'D' => KeyCode::Left,
+        b'H' => KeyCode::Home,
+        b'F' => KeyCode::End,
+        b'Z' => KeyCode::BackTab,
+        b'P' | b'Q' | b'R' | b'S' => ss3_key(last).unwrap_or(KeyCode::Null),
+        b'I' => return Parsed::Event(Event::FocusGained, len),
+        b'O' => return Parsed::Event(Event::FocusLost, len),
+        b'~' => match first {
+            1 | 7 => KeyCode::Home,
+            2 => KeyCode::Insert,
+            3 => KeyCode::Delete,
+            4 | 8 => KeyCode::End,
+            5 => KeyCode::PageUp,
+            6 => KeyCode::PageDown,
+            n @ 11..=15 => KeyCode::F(n - 10),
+            n @ 17..=21 => KeyCode::F(n - 11),
+            n @ 23..=24 => KeyCode::F(n - 12),
+            _ => return Parsed::Skip(len),
+        },
+        _ => return Parsed::Skip(len),
+    };
+
+    // The modifier parameter is one more than the modifier bits
+    let bits = modifiers.saturating_sub(1);
+    let mut key = KeyEvent::press(code);
+    key.shift = bits & 1 != 0 || code == KeyCode::BackTab;
+    key.alt = bits & 2 != 0;
+    key.ctrl = bits & 4 != 0;
+    key.super_key = bits & 8 != 0;
+    Parsed::Event(Event::Key(key), len)
+}
+
+// SGR mouse report: `<button;x;y` followed by `M` (press) or `m` (release)
+fn parse_mouse(params: &str, last: u8) -> Option<MouseEvent> {
+    let mut params = params.split(';').map(|p| p.parse::<u16>().ok());
+    let code = params.next()??;
+    let x = params.next()??;
+    let y = params.next()??;
+
+    let button = match code & 0b11 {
+        0 => Some(MouseButton::Left),
+        1 => Some(MouseButton::Middle),
+        2 => Some(MouseButton::Right),
+        _ => None,
+    };
+
+    let state = if code & 64 != 0 {
+        match code & 0b11 {
+            0 => MouseState::ScrollUp,
+            1 => MouseState::ScrollDown,
+            2 => MouseState::ScrollLeft,
+            _ => MouseState::ScrollRight,
+        }
+    } else if code & 32 != 0 {
+        match button {
+            Some(button) => MouseState::Drag(button),
+            None => MouseState::Move,
+        }
+    } else {
+        match last {
+            b'M' => MouseState::Down(button?),
+            b'm' => MouseState::Up(button?),
+            _ => return None,
+        }
+    };
+
+    // Positions are one-based
+    Some(MouseEvent::new(x.saturating_sub(1), y.saturating_sub(1), state))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse_all(parser: &mut InputParser, bytes: &[u8]) -> Vec<Event> {
+        let mut events = vec![];
+        parser.feed(bytes, &mut events);
+        events
+    }
+
+    fn key(event: &Event) -> KeyEvent {
+        match event {
+            Event::Key(key) => *key,
+            event => panic!("expected a key, got {event:?}"),
+        }
+    }
+
+    #[test]
+    fn keys() {
+        let mut parser = InputParser::default();
+        let events = parse_all(&mut parser, "a\x01\x1b[1;5A\x1b[3~\x1bOP\x1bxé\r".as_bytes());
+        assert_eq!(events.len(), 8);
+
+        assert_eq!(key(&events[0]).code, KeyCode::Char('a'));
+        let ctrl_a = key(&events[1]);
+        assert!(ctrl_a.ctrl && ctrl_a.code == KeyCode::Char('a'));
+        let ctrl_up = key(&events[2]);
+        assert!(ctrl_up.ctrl && ctrl_up.code == KeyCode::Up);
+        assert_eq!(key(&events[3]).code, KeyCode::Delete);
+        assert_eq!(key(&events[4]).code, KeyCode::F(1));
+        let alt_x = key(&events[5]);
+        assert!(alt_x.alt && alt_x.code == KeyCode::Char('x'));
+        assert_eq!(key(&events[6]).code, KeyCode::Char('é'));
+    }
+
+    #[test]
+    fn split_sequences() {
+        let mut parser = InputParser::default();
+        assert!(parse_all(&mut parser, b"\x1b[1;").is_empty());
+        let events = parse_all(&mut parser, b"2B");
+        let key = key(&events[0]);
+        assert!(key.shift && key.code == KeyCode::Down);
+
+        // A lone escape is the escape key
+        let events = parse_all(&mut parser, b"\x1b");
+        assert_eq!(super::test::key(&events[0]).code, KeyCode::Esc);
+    }
+
+    #[test]
+    fn mouse() {
+        let mut parser = InputParser::default();
+        let events = parse_all(&mut parser, b"\x1b[<0;3;4M\x1b[<32;4;4M\x1b[<0;4;4m\x1b[<65;1;1M");
+
+        let Event::Mouse(down) = events[0] else { panic!() };
+        assert!(down.lsb_down());
+        assert_eq!((down.x, down.y), (2, 3));
+        let Event::Mouse(drag) = events[1] else { panic!() };
+        assert!(matches!(drag.state, MouseState::Drag(MouseButton::Left)));
+        let Event::Mouse(up) = events[2] else { panic!() };
+        assert!(up.lsb_up());
+        let Event::Mouse(scroll) = events[3] else { panic!() };
+        assert!(matches!(scroll.state, MouseState::ScrollDown));
+    }
+
+    #[test]
+    fn paste() {
+        let mut parser = InputParser::default();
+        assert!(parse_all(&mut parser, b"\x1b[200~hello\x1b[20").is_empty());
+        let events = parse_all(&mut parser, b"1~x");
+        assert!(matches!(&events[0], Event::Paste(text) if text == "hello"));
+        assert_eq!(key(&events[1]).code, KeyCode::Char('x'));
+    }
+}
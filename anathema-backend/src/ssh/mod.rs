@@ -0,0 +1,487 @@
+//! Serve an application over SSH, using [russh](https://docs.rs/russh).
+//!
+//! Every session that requests a shell gets its own [`SshBackend`], passed to the
+//! application on a separate thread, where it can create and run a runtime.
+//! The size of the client terminal and its input are specific to the session.
+//!
+//! ```ignore
+//! let config = russh::server::Config {
+//!     keys: vec![host_key],
+//!     ..Default::default()
+//! };
+//!
+//! SshServer::new(config, |backend| {
+//!     let document = Document::new("@index");
+//!     let mut builder = Runtime::builder(document, backend);
+//!     builder.default::<Index>("index", "templates/index.aml").unwrap();
+//!     let _ = builder.finish().map(|mut runtime| runtime.run());
+//! })
+//! .authenticate(|credentials| match credentials {
+//!     Credentials::PublicKey { key, .. } => authorized_keys.contains(key),
+//!     _ => false,
+//! })
+//! .run(("0.0.0.0", 2222))
+//! .await?;
+//! ```
+//!
+//! Every client is rejected unless an authentication function is set with [`SshServer::authenticate`],
+//! or anyone is allowed in with [`SshServer::allow_anonymous`].
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+
+use anathema_geometry::Size;
+use anathema_store::tree::{Node, TreeValues};
+use anathema_widgets::components::events::Event;
+use anathema_widgets::debug::Overlay;
+use anathema_widgets::{AttributeStorage, Element, WidgetKind, WidgetRenderer};
+use russh::keys::PublicKey;
+use russh::server::{Auth, ChannelOpenHandle, Config, Handler, Msg, Server, Session};
+use russh::{Channel, ChannelId, ChannelOpenFailure, Pty};
+use tokio::net::ToSocketAddrs;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+
+use self::input::InputParser;
+use crate::tui::Screen;
+use crate::{Capabilities, EventSource, Renderer, Waker};
+
+mod input;
+
+// Alternate screen, hidden cursor, SGR mouse reporting and bracketed paste
+const SETUP: &[u8] = b"\x1b[?1049h\x1b[?25l\x1b[?1000h\x1b[?1002h\x1b[?1006h\x1b[?2004h";
+const RESTORE: &[u8] = b"\x1b[?2004l\x1b[?1006l\x1b[?1002l\x1b[?1000l\x1b[?25h\x1b[?1049l";
+
+// Sent from the session to the backend
+enum SessionInput {
+    Data(Vec<u8>),
+    Resize(u16, u16),
+    Closed,
+    // Sent by the waker of the backend
+    Wake,
+}
+
+/// The credentials offered by a client
+#[derive(Debug)]
+pub enum Credentials<'a> {
+    /// No credentials, only a user name
+    None { user: &'a str },
+    /// A password
+    Password { user: &'a str, password: &'a str },
+    /// A public key
+    PublicKey { user: &'a str, key: &'a PublicKey },
+}
+
+/// Backend for a single SSH session
+pub struct SshBackend {
+    screen: Screen,
+    input: Receiver<SessionInput>,
+    // Weak, so the input is disconnected once the session is gone
+    wake: Weak<Sender<SessionInput>>,
+    output: UnboundedSender<Vec<u8>>,
+    buffer: Vec<u8>,
+    parser: InputParser,
+    events: VecDeque<Event>,
+    user: String,
+    term: String,
+    peer: Option<SocketAddr>,
+    // The original title is restored if the title was set
+    title_set: bool,
+    cells_written: usize,
+}
+
+impl SshBackend {
+    /// The name of the authenticated user
+    pub fn user(&self) -> &str {
+        &self.user
+    }
+
+    /// The terminal type of the client, e.g `xterm-256color`
+    pub fn term(&self) -> &str {
+        &self.term
+    }
+
+    /// The address of the client
+    pub fn peer(&self) -> Option<SocketAddr> {
+        self.peer
+    }
+
+    fn flush(&mut self) {
+        if self.buffer.is_empty() {
+            return;
+        }
+        // The session is gone if this fails, which is reported as `Event::Stop`
+        let _ = self.output.send(std::mem::take(&mut self.buffer));
+    }
+
+    fn input(&mut self, input: SessionInput) {
+        match input {
+            SessionInput::Data(bytes) => self.parser.feed(&bytes, &mut self.events),
+            SessionInput::Resize(width, height) => self.events.push_back(Event::Resize(width, height)),
+            SessionInput::Closed => self.events.push_back(Event::Stop),
+            SessionInput::Wake => (),
+        }
+    }
+}
+
+impl EventSource for SshBackend {
+    fn next_event(&mut self, timeout: Duration) -> Option<Event> {
+        let deadline = Instant::now() + timeout;
+        while self.events.is_empty() {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            match self.input.recv_timeout(timeout) {
+                Ok(SessionInput::Wake) => return None,
+                Ok(input) => self.input(input),
+                Err(RecvTimeoutError::Timeout) => return None,
+                Err(RecvTimeoutError::Disconnected) => return Some(Event::Stop),
+            }
+        }
+        self.events.pop_front()
+    }
+
+    fn waker(&self) -> Option<Waker> {
+        let wake = self.wake.clone();
+        Some(Arc::new(move || {
+            if let Some(wake) = wake.upgrade() {
+                let _ = wake.send(SessionInput::Wake);
+            }
+        }))
+    }
+}
+
+impl Renderer for SshBackend {
+    fn size(&self) -> Size {
+        self.screen.size()
+    }
+
+    fn resize(&mut self, new_size: Size) {
+        self.screen.resize(new_size);
+    }
+
+    fn paint<'bp>(
+        &mut self,
+        element: &mut Element<'bp>,
+        children: &[Node],
+        values: &mut TreeValues<WidgetKind<'bp>>,
+        attribute_storage: &AttributeStorage<'bp>,
+        ignore_floats: bool,
+    ) {
+        anathema_widgets::paint::paint(
+            &mut self.screen,
+            element,
+            children,
+            values,
+            attribute_storage,
+            ignore_floats,
+        );
+    }
+
+    fn render(&mut self) {
+        self.cells_written = self.screen.render(&mut self.buffer).unwrap_or(0);
+        self.flush();
+    }
+
+    fn cells_written(&self) -> usize {
+        self.cells_written
+    }
+
+    fn clear(&mut self) {
+        self.screen.erase();
+    }
+
+    fn overlay(&mut self, overlay: &dyn Overlay) {
+        overlay.paint(&mut self.screen);
+    }
+
+    fn finalize(&mut self) {
+        self.buffer.extend_from_slice(SETUP);
+        self.flush();
+    }
+
+    fn set_title(&mut self, title: &str) {
+        let save = !std::mem::replace(&mut self.title_set, true);
+        let _ = Screen::set_title(&mut self.buffer, title, save);
+        self.flush();
+    }
+
+    fn bell(&mut self) {
+        let _ = Screen::bell(&mut self.buffer);
+        self.flush();
+    }
+
+    fn copy(&mut self, text: &str) {
+        let _ = Screen::copy(&mut self.buffer, text);
+        self.flush();
+    }
+
+    // Only the terminal type is known
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::from_vars(|name| (name == "TERM").then(|| self.term.clone()))
+    }
+}
+
+impl Drop for SshBackend {
+    fn drop(&mut self) {
+        // Restore the client terminal.
+        // Once the output is dropped the session is closed.
+        if self.title_set {
+            let _ = Screen::restore_title(&mut self.buffer);
+        }
+        self.buffer.extend_from_slice(RESTORE);
+        self.flush();
+    }
+}
+
+type App = Arc<dyn Fn(SshBackend) + Send + Sync>;
+type Authenticate = Arc<dyn Fn(&Credentials<'_>) -> bool + Send + Sync>;
+
+/// An SSH server, running the application once for every session.
+/// See the [module documentation](self).
+pub struct SshServer {
+    config: Arc<Config>,
+    app: App,
+    authenticate: Authenticate,
+}
+
+impl SshServer {
+    /// Create a server that calls `app` with the backend of every new session.
+    /// The function is called on a new thread, and the session ends when it returns.
+    ///
+    /// No client is accepted until an authentication function is set with [`SshServer::authenticate`],
+    /// or [`SshServer::allow_anonymous`] is called.
+    pub fn new(config: Config, app: impl Fn(SshBackend) + Send + Sync + 'static) -> Self {
+        Self {
+            config: Arc::new(config),
+            app: Arc::new(app),
+            authenticate: Arc::new(|_| false),
+        }
+    }
+
+    /// Only accept clients with credentials accepted by the function
+    pub fn authenticate(mut self, f: impl Fn(&Credentials<'_>) -> bool + Send + Sync + 'static) -> Self {
+        self.authenticate = Arc::new(f);
+        self
+    }
+
+    /// Accept every client, including clients without credentials
+    pub fn allow_anonymous(self) -> Self {
+        self.authenticate(|_| true)
+    }
+
+    /// Accept connections on the address.
+    /// This has to be called from within a tokio runtime.
+    pub async fn run(mut self, addr: impl ToSocketAddrs + Send) -> std::io::Result<()> {
+        let config = self.config.clone();
+        self.run_on_address(config, addr).await
+    }
+}
+
+impl Server for SshServer {
+    type Handler = SessionHandler;
+
+    fn new_client(&mut self, peer: Option<SocketAddr>) -> SessionHandler {
+        SessionHandler {
+            app: self.app.clone(),
+            authenticate: self.authenticate.clone(),
+            peer,
+            user: String::new(),
+            term: String::new(),
+            size: Size::new(80, 24),
+            channel: None,
+            input: None,
+        }
+    }
+}
+
+/// The connection of a single client
+pub struct SessionHandler {
+    app: App,
+    authenticate: Authenticate,
+    peer: Option<SocketAddr>,
+    user: String,
+    term: String,
+    size: Size,
+    channel: Option<ChannelId>,
+    input: Option<Arc<Sender<SessionInput>>>,
+}
+
+impl SessionHandler {
+    fn auth(&mut self, credentials: Credentials<'_>) -> Auth {
+        let user = match credentials {
+            Credentials::None { user } | Credentials::Password { user, .. } | Credentials::PublicKey { user, .. } => {
+                user
+            }
+        };
+
+        match (self.authenticate)(&credentials) {
+            true => {
+                self.user = user.to_string();
+                Auth::Accept
+            }
+            false => Auth::reject(),
+        }
+    }
+
+    fn send(&self, input: SessionInput) {
+        if let Some(sender) = &self.input {
+            let _ = sender.send(input);
+        }
+    }
+}
+
+impl Handler for SessionHandler {
+    type Error = russh::Error;
+
+    async fn auth_none(&mut self, user: &str) -> Result<Auth, Self::Error> {
+        Ok(self.auth(Credentials::None { user }))
+    }
+
+    async fn auth_password(&mut self, user: &str, password: &str) -> Result<Auth, Self::Error> {
+        Ok(self.auth(Credentials::Password { user, password }))
+    }
+
+    async fn auth_publickey(&mut self, user: &str, key: &PublicKey) -> Result<Auth, Self::Error> {
+        Ok(self.auth(Credentials::PublicKey { user, key }))
+    }
+
+    async fn channel_open_session(
+        &mut self,
+        channel: Channel<Msg>,
+        reply: ChannelOpenHandle,
+        _: &mut Session,
+    ) -> Result<(), Self::Error> {
+        // One application per connection
+        if self.channel.is_some() {
+            reply.reject(ChannelOpenFailure::AdministrativelyProhibited).await;
+            return Ok(());
+        }
+        self.channel = Some(channel.id());
+        reply.accept().await;
+        Ok(())
+    }
+
+    async fn pty_request(
+        &mut self,
+        channel: ChannelId,
+        term: &str,
+        col_width: u32,
+        row_height: u32,
+        _: u32,
+        _: u32,
+        _: &[(Pty, u32)],
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        self.term = term.to_string();
+        self.size = Size::new(col_width as usize, row_height as usize);
+        session.channel_success(channel)
+    }
+
+    async fn shell_request(&mut self, channel: ChannelId, session: &mut Session) -> Result<(), Self::Error> {
+        if self.input.is_some() || self.channel != Some(channel) {
+            return session.channel_failure(channel);
+        }
+
+        let (input, receiver) = mpsc::channel();
+        let input = Arc::new(input);
+        let (output, mut output_receiver) = unbounded_channel::<Vec<u8>>();
+        let wake = Arc::downgrade(&input);
+        self.input = Some(input);
+
+        let backend = SshBackend {
+            screen: Screen::new(self.size),
+            wake,
+            input: receiver,
+            output,
+            buffer: vec![],
+            parser: InputParser::default(),
+            events: VecDeque::new(),
+            user: self.user.clone(),
+            term: self.term.clone(),
+            peer: self.peer,
+            title_set: false,
+            cells_written: 0,
+        };
+
+        // Forward the output to the client, and close the session
+        // once the backend is dropped
+        let handle = session.handle();
+        tokio::spawn(async move {
+            while let Some(data) = output_receiver.recv().await {
+                if handle.data(channel, data).await.is_err() {
+                    break;
+                }
+            }
+            let _ = handle.eof(channel).await;
+            let _ = handle.close(channel).await;
+        });
+
+        let app = self.app.clone();
+        std::thread::spawn(move || app(backend));
+
+        session.channel_success(channel)
+    }
+
+    async fn data(&mut self, _: ChannelId, data: &[u8], _: &mut Session) -> Result<(), Self::Error> {
+        self.send(SessionInput::Data(data.to_vec()));
+        Ok(())
+    }
+
+    async fn window_change_request(
+        &mut self,
+        _: ChannelId,
+        col_width: u32,
+        row_height: u32,
+        _: u32,
+        _: u32,
+        _: &mut Session,
+    ) -> Result<(), Self::Error> {
+        self.send(SessionInput::Resize(col_width as u16, row_height as u16));
+        Ok(())
+    }
+
+    async fn channel_eof(&mut self, _: ChannelId, _: &mut Session) -> Result<(), Self::Error> {
+        self.send(SessionInput::Closed);
+        Ok(())
+    }
+
+    async fn channel_close(&mut self, _: ChannelId, _: &mut Session) -> Result<(), Self::Error> {
+        self.send(SessionInput::Closed);
+        Ok(())
+    }
+}
+
+impl Drop for SessionHandler {
+    fn drop(&mut self) {
+        self.send(SessionInput::Closed);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn user(server: &mut SshServer) -> Auth {
+        server.new_client(None).auth(Credentials::None { user: "anathema" })
+    }
+
+    #[test]
+    fn clients_are_rejected_by_default() {
+        let mut server = SshServer::new(Config::default(), |_| {});
+        assert!(matches!(user(&mut server), Auth::Reject { .. }));
+
+        let mut server = SshServer::new(Config::default(), |_| {}).allow_anonymous();
+        assert!(matches!(user(&mut server), Auth::Accept));
+
+        let mut server = SshServer::new(Config::default(), |_| {})
+            .authenticate(|credentials| matches!(credentials, Credentials::Password { password: "pass", .. }));
+        assert!(matches!(user(&mut server), Auth::Reject { .. }));
+        let mut client = server.new_client(None);
+        let auth = client.auth(Credentials::Password {
+            user: "anathema",
+            password: "pass",
+        });
+        assert!(matches!(auth, Auth::Accept));
+        assert_eq!(client.user, "anathema");
+    }
+}
@@ -0,0 +1,487 @@
+//! A terminal backend using [termion](https://docs.rs/termion), for when crossterm isn't an option.
+//!
+//! This shares the [`Screen`] (and with it the buffer and the diffing) with the
+//! [`TuiBackend`](crate::tui::TuiBackend), only the terminal setup and the input differ.
+//!
+//! ```ignore
+//! let backend = TermionBackend::builder()
+//!     .enable_alt_screen()
+//!     .enable_raw_mode()
+//!     .hide_cursor()
+//!     .finish()?;
+//! ```
+//!
+//! Termion only supports unix-like systems.
+//! Key release events, focus reporting and bracketed paste are not supported.
+use std::io::{stdin, stdout, Result, Stdout, Write};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use ::termion::event::{Event as TEvent, Key, MouseButton as TMouseButton, MouseEvent as TMouseEvent};
+use ::termion::input::{MouseTerminal, TermRead};
+use ::termion::raw::{IntoRawMode, RawTerminal};
+use ::termion::screen::{ToAlternateScreen, ToMainScreen};
+use anathema_geometry::Size;
+use anathema_store::tree::{Node, TreeValues};
+use anathema_widgets::components::events::{
+    ClickCounter, Event, KeyCode, KeyEvent, MouseButton, MouseEvent, MouseState, RawEvent,
+};
+use anathema_widgets::debug::Overlay;
+use anathema_widgets::{AttributeStorage, Element, WidgetKind, WidgetRenderer};
+
+use crate::tui::{half_block_event, ColorDowngrade, Screen};
+use crate::{Capabilities, EventSource, Renderer};
+
+/// Backend builder for a termion backend.
+pub struct TermionBackendBuilder {
+    hide_cursor: bool,
+    enable_raw_mode: bool,
+    enable_alt_screen: bool,
+    enable_mouse: bool,
+    enable_raw_events: bool,
+    synchronized_output: bool,
+    track_damage: bool,
+    color_downgrade: ColorDowngrade,
+    half_blocks: bool,
+    click_interval: Duration,
+}
+
+impl TermionBackendBuilder {
+    /// Enable an alternative screen.
+    /// When using this with stdout it means the output will not persist
+    /// once the program exits.
+    pub fn enable_alt_screen(mut self) -> Self {
+        self.enable_alt_screen = true;
+        self
+    }
+
+    /// Enable mouse support.
+    pub fn enable_mouse(mut self) -> Self {
+        self.enable_mouse = true;
+        self
+    }
+
+    /// When raw mode is enabled, every key press is sent to the terminal.
+    /// If raw mode is not enabled, the return key has to be pressed to
+    /// send characters to the terminal.
+    pub fn enable_raw_mode(mut self) -> Self {
+        self.enable_raw_mode = true;
+        self
+    }
+
+    /// Forward the events anathema doesn't model as [`Event::Raw`],
+    /// instead of dropping them.
+    pub fn enable_raw_events(mut self) -> Self {
+        self.enable_raw_events = true;
+        self
+    }
+
+    /// Hide the text cursor.
+    pub fn hide_cursor(mut self) -> Self {
+        self.hide_cursor = true;
+        self
+    }
+
+    /// The maximum time between two mouse button presses
+    /// for them to count as a double click.
+    /// The default is 500ms.
+    pub fn click_interval(mut self, interval: Duration) -> Self {
+        self.click_interval = interval;
+        self
+    }
+
+    /// Don't wrap the frames in synchronized updates.
+    ///
+    /// By default every frame is drawn as a synchronized update (mode 2026)
+    /// if the terminal supports it, so large repaints don't tear.
+    pub fn disable_synchronized_output(mut self) -> Self {
+        self.synchronized_output = false;
+        self
+    }
+
+    /// Compare the entire screen with the previous frame on every render.
+    ///
+    /// By default only the regions of the widgets that changed are compared,
+    /// which requires everything on the screen to be painted by widgets.
+    pub fn disable_damage_tracking(mut self) -> Self {
+        self.track_damage = false;
+        self
+    }
+
+    /// How 24 bit colours are mapped to the palette,
+    /// when the terminal only supports 256 or 16 colours.
+    /// The default is [`ColorDowngrade::Nearest`].
+    pub fn color_downgrade(mut self, strategy: ColorDowngrade) -> Self {
+        self.color_downgrade = strategy;
+        self
+    }
+
+    /// Draw two rows of cells per row of the terminal using half block characters,
+    /// doubling the vertical resolution.
+    ///
+    /// Every cell is drawn as a single colour: the background of a blank cell
+    /// and the foreground of anything else, so this is meant for canvases
+    /// and images rather than text.
+    /// The size of the screen (and the mouse positions) are in cells, not terminal rows.
+    pub fn half_blocks(mut self) -> Self {
+        self.half_blocks = true;
+        self
+    }
+
+    /// Consume self and create the termion backend.
+    pub fn finish(self) -> Result<TermionBackend> {
+        let size = ::termion::terminal_size()?;
+
+        // Termion has no way to poll stdin with a timeout,
+        // so the input is read on a separate thread.
+        let (sender, events) = channel();
+        std::thread::spawn(move || {
+            for event in stdin().events() {
+                let Ok(event) = event else { continue };
+                if sender.send(event).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let capabilities = Capabilities::from_env();
+        let mut screen = Screen::new(size);
+        screen.set_color_depth(capabilities.colors, self.color_downgrade);
+        screen.set_synchronized_output(self.synchronized_output && capabilities.synchronized_output);
+        screen.set_track_damage(self.track_damage);
+        screen.set_half_blocks(self.half_blocks);
+
+        let backend = TermionBackend {
+            screen,
+            output: stdout(),
+            events,
+            size: size.into(),
+            clicks: ClickCounter::new(self.click_interval),
+            pressed: None,
+
+            hide_cursor: self.hide_cursor,
+            enable_raw_mode: self.enable_raw_mode,
+            enable_alt_screen: self.enable_alt_screen,
+            enable_mouse: self.enable_mouse,
+            enable_raw_events: self.enable_raw_events,
+            half_blocks: self.half_blocks,
+            raw: None,
+            mouse: None,
+            title_set: false,
+            cells_written: 0,
+        };
+
+        Ok(backend)
+    }
+}
+
+/// Terminal backend using termion
+pub struct TermionBackend {
+    screen: Screen,
+    output: Stdout,
+    events: Receiver<TEvent>,
+    // The size of the terminal, used to detect a resize
+    size: Size,
+    clicks: ClickCounter,
+    // Termion doesn't report which button was released or dragged
+    pressed: Option<MouseButton>,
+
+    // Settings
+    hide_cursor: bool,
+    enable_raw_mode: bool,
+    enable_alt_screen: bool,
+    enable_mouse: bool,
+    enable_raw_events: bool,
+    half_blocks: bool,
+
+    // The terminal is restored when these are dropped
+    raw: Option<RawTerminal<Stdout>>,
+    mouse: Option<MouseTerminal<Stdout>>,
+    // The original title is restored if the title was set
+    title_set: bool,
+    // The number of cells written by the last render
+    cells_written: usize,
+}
+
+impl TermionBackend {
+    /// Create a new instance of the termion backend.
+    pub fn builder() -> TermionBackendBuilder {
+        TermionBackendBuilder {
+            hide_cursor: false,
+            enable_raw_mode: false,
+            enable_alt_screen: false,
+            enable_mouse: false,
+            enable_raw_events: false,
+            synchronized_output: true,
+            track_damage: true,
+            color_downgrade: ColorDowngrade::Nearest,
+            half_blocks: false,
+            click_interval: ClickCounter::DEFAULT_INTERVAL,
+        }
+    }
+
+    fn poll(&mut self, timeout: Duration) -> Option<Event> {
+        if let Some(event) = self.resized() {
+            return Some(event);
+        }
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            match self.events.recv_timeout(timeout) {
+                Ok(event) => match self.translate(event) {
+                    Some(event) => break Some(event),
+                    None => continue,
+                },
+                Err(RecvTimeoutError::Timeout | RecvTimeoutError::Disconnected) => break None,
+            }
+        }
+    }
+
+    // Termion doesn't report resize events,
+    // so the size is compared to the previous size instead
+    fn resized(&mut self) -> Option<Event> {
+        let (width, height) = ::termion::terminal_size().ok()?;
+        let size = Size::from((width, height));
+        if size == self.size {
+            return None;
+        }
+        self.size = size;
+        Some(Event::Resize(width, height))
+    }
+
+    fn translate(&mut self, event: TEvent) -> Option<Event> {
+        let event = match event {
+            TEvent::Key(Key::Ctrl('c')) => Event::Stop,
+            TEvent::Key(key) => match translate_key(key) {
+                Some(key) => Event::Key(key),
+                None if self.enable_raw_events => Event::Raw(RawEvent::new(event)),
+                None => return None,
+            },
+            TEvent::Mouse(mouse) => {
+                let mouse = translate_mouse(mouse, &mut self.pressed);
+                Event::Mouse(self.clicks.count(mouse, Instant::now()))
+            }
+            TEvent::Unsupported(_) if self.enable_raw_events => Event::Raw(RawEvent::new(event)),
+            TEvent::Unsupported(_) => return None,
+        };
+        Some(event)
+    }
+}
+
+impl EventSource for TermionBackend {
+    fn next_event(&mut self, timeout: Duration) -> Option<Event> {
+        let event = self.poll(timeout)?;
+        match self.half_blocks {
+            true => Some(half_block_event(event)),
+            false => Some(event),
+        }
+    }
+}
+
+impl Renderer for TermionBackend {
+    fn size(&self) -> Size {
+        self.screen.size()
+    }
+
+    fn resize(&mut self, new_size: Size) {
+        self.screen.resize(new_size);
+    }
+
+    fn paint<'bp>(
+        &mut self,
+        element: &mut Element<'bp>,
+        children: &[Node],
+        values: &mut TreeValues<WidgetKind<'bp>>,
+        attribute_storage: &AttributeStorage<'bp>,
+        ignore_floats: bool,
+    ) {
+        anathema_widgets::paint::paint(
+            &mut self.screen,
+            element,
+            children,
+            values,
+            attribute_storage,
+            ignore_floats,
+        );
+    }
+
+    fn render(&mut self) {
+        self.cells_written = self.screen.render(&mut self.output).unwrap_or(0);
+    }
+
+    fn cells_written(&self) -> usize {
+        self.cells_written
+    }
+
+    fn clear(&mut self) {
+        self.screen.erase();
+    }
+
+    fn overlay(&mut self, overlay: &dyn Overlay) {
+        overlay.paint(&mut self.screen);
+    }
+
+    fn finalize(&mut self) {
+        if self.hide_cursor {
+            let _ = Screen::hide_cursor(&mut self.output);
+        }
+
+        if self.enable_raw_mode {
+            self.raw = stdout().into_raw_mode().ok();
+        }
+
+        if self.enable_alt_screen {
+            let _ = write!(self.output, "{ToAlternateScreen}");
+        }
+
+        if self.enable_mouse {
+            self.mouse = Some(MouseTerminal::from(stdout()));
+        }
+
+        let _ = self.output.flush();
+    }
+
+    fn set_title(&mut self, title: &str) {
+        let save = !std::mem::replace(&mut self.title_set, true);
+        let _ = Screen::set_title(&mut self.output, title, save);
+        let _ = self.output.flush();
+    }
+
+    fn bell(&mut self) {
+        let _ = Screen::bell(&mut self.output);
+        let _ = self.output.flush();
+    }
+
+    fn copy(&mut self, text: &str) {
+        let _ = Screen::copy(&mut self.output, text);
+        let _ = self.output.flush();
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::from_env()
+    }
+}
+
+impl Drop for TermionBackend {
+    fn drop(&mut self) {
+        self.mouse.take();
+        if self.enable_alt_screen {
+            let _ = write!(self.output, "{ToMainScreen}");
+        }
+        if self.title_set {
+            let _ = Screen::restore_title(&mut self.output);
+        }
+        let _ = Screen::reset_cursor_shape(&mut self.output);
+        let _ = Screen::show_cursor(&mut self.output);
+        let _ = self.output.flush();
+        self.raw.take();
+    }
+}
+
+fn translate_key(key: Key) -> Option<KeyEvent> {
+    let (code, ctrl, alt, shift) = match key {
+        Key::Backspace => (KeyCode::Backspace, false, false, false),
+        Key::Left => (KeyCode::Left, false, false, false),
+        Key::ShiftLeft => (KeyCode::Left, false, false, true),
+        Key::AltLeft => (KeyCode::Left, false, true, false),
+        Key::CtrlLeft => (KeyCode::Left, true, false, false),
+        Key::Right => (KeyCode::Right, false, false, false),
+        Key::ShiftRight => (KeyCode::Right, false, false, true),
+        Key::AltRight => (KeyCode::Right, false, true, false),
+        Key::CtrlRight => (KeyCode::Right, true, false, false),
+        Key::Up => (KeyCode::Up, false, false, false),
+        Key::ShiftUp => (KeyCode::Up, false, false, true),
+        Key::AltUp => (KeyCode::Up, false, true, false),
+        Key::CtrlUp => (KeyCode::Up, true, false, false),
+        Key::Down => (KeyCode::Down, false, false, false),
+        Key::ShiftDown => (KeyCode::Down, false, false, true),
+        Key::AltDown => (KeyCode::Down, false, true, false),
+        Key::CtrlDown => (KeyCode::Down, true, false, false),
+        Key::Home => (KeyCode::Home, false, false, false),
+        Key::CtrlHome => (KeyCode::Home, true, false, false),
+        Key::End => (KeyCode::End, false, false, false),
+        Key::CtrlEnd => (KeyCode::End, true, false, false),
+        Key::PageUp => (KeyCode::PageUp, false, false, false),
+        Key::PageDown => (KeyCode::PageDown, false, false, false),
+        Key::BackTab => (KeyCode::BackTab, false, false, true),
+        Key::Delete => (KeyCode::Delete, false, false, false),
+        Key::Insert => (KeyCode::Insert, false, false, false),
+        Key::F(n) => (KeyCode::F(n), false, false, false),
+        Key::Char('\n') => (KeyCode::Enter, false, false, false),
+        Key::Char('\t') => (KeyCode::Tab, false, false, false),
+        Key::Char(c) => (KeyCode::Char(c), false, false, c.is_uppercase()),
+        Key::Alt(c) => (KeyCode::Char(c), false, true, c.is_uppercase()),
+        Key::Ctrl(c) => (KeyCode::Char(c), true, false, false),
+        Key::Null => (KeyCode::Null, false, false, false),
+        Key::Esc => (KeyCode::Esc, false, false, false),
+        _ => return None,
+    };
+
+    let mut key = KeyEvent::press(code);
+    key.ctrl = ctrl;
+    key.alt = alt;
+    key.shift = shift;
+    Some(key)
+}
+
+// Termion positions are one-based
+fn translate_mouse(mouse: TMouseEvent, pressed: &mut Option<MouseButton>) -> MouseEvent {
+    let (state, x, y) = match mouse {
+        TMouseEvent::Press(button, x, y) => {
+            let state = match button {
+                TMouseButton::Left => MouseState::Down(MouseButton::Left),
+                TMouseButton::Right => MouseState::Down(MouseButton::Right),
+                TMouseButton::Middle => MouseState::Down(MouseButton::Middle),
+                TMouseButton::WheelUp => MouseState::ScrollUp,
+                TMouseButton::WheelDown => MouseState::ScrollDown,
+                TMouseButton::WheelLeft => MouseState::ScrollLeft,
+                TMouseButton::WheelRight => MouseState::ScrollRight,
+            };
+            if let MouseState::Down(button) = state {
+                *pressed = Some(button);
+            }
+            (state, x, y)
+        }
+        TMouseEvent::Release(x, y) => {
+            let button = pressed.take().unwrap_or(MouseButton::Left);
+            (MouseState::Up(button), x, y)
+        }
+        TMouseEvent::Hold(x, y) => (MouseState::Drag(pressed.unwrap_or(MouseButton::Left)), x, y),
+    };
+
+    MouseEvent::new(x.saturating_sub(1), y.saturating_sub(1), state)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn translate_keys() {
+        let key = translate_key(Key::Ctrl('a')).unwrap();
+        assert_eq!(key.code, KeyCode::Char('a'));
+        assert!(key.ctrl);
+
+        let key = translate_key(Key::ShiftUp).unwrap();
+        assert_eq!(key.code, KeyCode::Up);
+        assert!(key.shift);
+
+        assert_eq!(translate_key(Key::Char('\n')).unwrap().code, KeyCode::Enter);
+    }
+
+    #[test]
+    fn translate_mouse_drag() {
+        let mut pressed = None;
+        let down = translate_mouse(TMouseEvent::Press(TMouseButton::Right, 3, 4), &mut pressed);
+        assert!(down.rsb_down());
+        assert_eq!((down.x, down.y), (2, 3));
+
+        let drag = translate_mouse(TMouseEvent::Hold(4, 4), &mut pressed);
+        assert!(matches!(drag.state, MouseState::Drag(MouseButton::Right)));
+
+        let up = translate_mouse(TMouseEvent::Release(4, 4), &mut pressed);
+        assert!(matches!(up.state, MouseState::Up(MouseButton::Right)));
+        assert!(pressed.is_none());
+    }
+}
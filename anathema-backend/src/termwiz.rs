@@ -0,0 +1,490 @@
+//! A terminal backend using [termwiz](https://docs.rs/termwiz), the terminal library of wezterm.
+//!
+//! Unlike the other terminal backends this doesn't use the [`Screen`](crate::tui::Screen):
+//! widgets are painted to a termwiz surface, and termwiz takes care of diffing
+//! and of writing the escape sequences the terminal supports.
+//!
+//! ```ignore
+//! let backend = TermwizBackend::builder()
+//!     .enable_alt_screen()
+//!     .enable_raw_mode()
+//!     .hide_cursor()
+//!     .finish()?;
+//! ```
+//!
+//! Mouse events and bracketed paste are reported once raw mode is enabled.
+use std::time::{Duration, Instant};
+
+use ::termwiz::caps::{Capabilities, ColorLevel};
+use ::termwiz::cell::{CellAttributes as TCellAttributes, Intensity, Underline};
+use ::termwiz::color::ColorAttribute;
+use ::termwiz::input::{
+    InputEvent, KeyCode as TKeyCode, KeyEvent as TKeyEvent, Modifiers, MouseButtons, MouseEvent as TMouseEvent,
+};
+use ::termwiz::surface::{Change, CursorVisibility, Surface};
+use ::termwiz::terminal::buffered::BufferedTerminal;
+use ::termwiz::terminal::{SystemTerminal, Terminal};
+pub use ::termwiz::Error;
+use anathema_geometry::{Pos, Size};
+use anathema_state::Color;
+use anathema_store::tree::{Node, TreeValues};
+use anathema_widgets::components::events::{
+    ClickCounter, Event, KeyCode, KeyEvent, MouseButton, MouseEvent, MouseState, RawEvent,
+};
+use anathema_widgets::debug::Overlay;
+use anathema_widgets::paint::CellAttributes;
+use anathema_widgets::{AttributeStorage, Element, WidgetKind, WidgetRenderer};
+
+use crate::tui::{ansi, Attributes, Style};
+use crate::{ColorDepth, EventSource, Renderer};
+
+/// Backend builder for a termwiz backend.
+pub struct TermwizBackendBuilder {
+    hide_cursor: bool,
+    enable_raw_mode: bool,
+    enable_alt_screen: bool,
+    enable_raw_events: bool,
+    click_interval: Duration,
+}
+
+impl TermwizBackendBuilder {
+    /// Enable an alternative screen.
+    /// The output will not persist once the program exits.
+    pub fn enable_alt_screen(mut self) -> Self {
+        self.enable_alt_screen = true;
+        self
+    }
+
+    /// When raw mode is enabled, every key press is sent to the terminal.
+    /// This also enables mouse reporting and bracketed paste.
+    pub fn enable_raw_mode(mut self) -> Self {
+        self.enable_raw_mode = true;
+        self
+    }
+
+    /// Forward the events anathema doesn't model as [`Event::Raw`],
+    /// instead of dropping them.
+    pub fn enable_raw_events(mut self) -> Self {
+        self.enable_raw_events = true;
+        self
+    }
+
+    /// Hide the text cursor.
+    pub fn hide_cursor(mut self) -> Self {
+        self.hide_cursor = true;
+        self
+    }
+
+    /// The maximum time between two mouse button presses
+    /// for them to count as a double click.
+    /// The default is 500ms.
+    pub fn click_interval(mut self, interval: Duration) -> Self {
+        self.click_interval = interval;
+        self
+    }
+
+    /// Consume self and create the termwiz backend.
+    pub fn finish(self) -> Result<TermwizBackend, Error> {
+        let caps = Capabilities::new_from_env()?;
+        let capabilities = crate::Capabilities {
+            colors: match caps.color_level() {
+                ColorLevel::TrueColor => ColorDepth::TrueColor,
+                ColorLevel::TwoFiftySix => ColorDepth::Ansi256,
+                ColorLevel::Sixteen | ColorLevel::MonoChrome => ColorDepth::Ansi16,
+            },
+            sixel: caps.sixel(),
+            iterm_images: caps.iterm2_image(),
+            ..crate::Capabilities::from_env()
+        };
+        let mut terminal = BufferedTerminal::new(SystemTerminal::new(caps)?)?;
+        // The windows waker can't be sent to another thread
+        #[cfg(unix)]
+        let waker: crate::Waker = {
+            let waker = terminal.terminal().waker();
+            std::sync::Arc::new(move || {
+                let _ = waker.wake();
+            })
+        };
+
+        let backend = TermwizBackend {
+            terminal,
+            #[cfg(unix)]
+            waker,
+            capabilities,
+            clicks: ClickCounter::new(self.click_interval),
+            buttons: MouseButtons::NONE,
+
+            hide_cursor: self.hide_cursor,
+            enable_raw_mode: self.enable_raw_mode,
+            enable_alt_screen: self.enable_alt_screen,
+            enable_raw_events: self.enable_raw_events,
+        };
+
+        Ok(backend)
+    }
+}
+
+/// Terminal backend using termwiz
+pub struct TermwizBackend {
+    terminal: BufferedTerminal<SystemTerminal>,
+    #[cfg(unix)]
+    waker: crate::Waker,
+    capabilities: crate::Capabilities,
+    clicks: ClickCounter,
+    // Termwiz reports which buttons are held, rather than presses and releases
+    buttons: MouseButtons,
+
+    // Settings
+    hide_cursor: bool,
+    enable_raw_mode: bool,
+    enable_alt_screen: bool,
+    enable_raw_events: bool,
+}
+
+impl TermwizBackend {
+    /// Create a new instance of the termwiz backend.
+    pub fn builder() -> TermwizBackendBuilder {
+        TermwizBackendBuilder {
+            hide_cursor: false,
+            enable_raw_mode: false,
+            enable_alt_screen: false,
+            enable_raw_events: false,
+            click_interval: ClickCounter::DEFAULT_INTERVAL,
+        }
+    }
+
+    fn translate(&mut self, event: InputEvent) -> Option<Event> {
+        let event = match event {
+            InputEvent::Key(TKeyEvent {
+                key: TKeyCode::Char('c'),
+                modifiers: Modifiers::CTRL,
+            }) => Event::Stop,
+            InputEvent::Key(key) => match translate_key(key.clone()) {
+                Some(key) => Event::Key(key),
+                None if self.enable_raw_events => Event::Raw(RawEvent::new(key)),
+                None => return None,
+            },
+            InputEvent::Mouse(mouse) => {
+                let mouse = translate_mouse(mouse, &mut self.buttons);
+                Event::Mouse(self.clicks.count(mouse, Instant::now()))
+            }
+            InputEvent::Resized { cols, rows } => Event::Resize(cols as u16, rows as u16),
+            InputEvent::Paste(text) => Event::Paste(text),
+            InputEvent::PixelMouse(mouse) if self.enable_raw_events => Event::Raw(RawEvent::new(mouse)),
+            InputEvent::PixelMouse(_) | InputEvent::Wake => return None,
+        };
+        Some(event)
+    }
+}
+
+impl EventSource for TermwizBackend {
+    fn next_event(&mut self, timeout: Duration) -> Option<Event> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            let event = self.terminal.terminal().poll_input(Some(timeout)).ok()??;
+            if let InputEvent::Wake = event {
+                break None;
+            }
+            if let Some(event) = self.translate(event) {
+                break Some(event);
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    fn waker(&self) -> Option<crate::Waker> {
+        Some(self.waker.clone())
+    }
+}
+
+impl Renderer for TermwizBackend {
+    fn size(&self) -> Size {
+        let (width, height) = self.terminal.dimensions();
+        Size::new(width, height)
+    }
+
+    fn resize(&mut self, new_size: Size) {
+        self.terminal.resize(new_size.width, new_size.height);
+    }
+
+    fn paint<'bp>(
+        &mut self,
+        element: &mut Element<'bp>,
+        children: &[Node],
+        values: &mut TreeValues<WidgetKind<'bp>>,
+        attribute_storage: &AttributeStorage<'bp>,
+        ignore_floats: bool,
+    ) {
+        anathema_widgets::paint::paint(
+            &mut TermwizSurface(&mut self.terminal),
+            element,
+            children,
+            values,
+            attribute_storage,
+            ignore_floats,
+        );
+    }
+
+    fn render(&mut self) {
+        let _ = self.terminal.flush();
+    }
+
+    fn clear(&mut self) {
+        self.terminal.add_change(Change::ClearScreen(ColorAttribute::Default));
+    }
+
+    fn overlay(&mut self, overlay: &dyn Overlay) {
+        overlay.paint(&mut TermwizSurface(&mut self.terminal));
+    }
+
+    fn finalize(&mut self) {
+        let terminal = self.terminal.terminal();
+        if self.enable_raw_mode {
+            let _ = terminal.set_raw_mode();
+        }
+
+        if self.enable_alt_screen {
+            let _ = terminal.enter_alternate_screen();
+        }
+
+        if self.hide_cursor {
+            self.terminal
+                .add_change(Change::CursorVisibility(CursorVisibility::Hidden));
+        }
+
+        let _ = self.terminal.flush();
+    }
+
+    fn set_title(&mut self, title: &str) {
+        self.terminal.add_change(Change::Title(title.into()));
+        let _ = self.terminal.flush();
+    }
+
+    fn capabilities(&self) -> crate::Capabilities {
+        self.capabilities
+    }
+}
+
+impl Drop for TermwizBackend {
+    fn drop(&mut self) {
+        let terminal = self.terminal.terminal();
+        let _ = terminal.render(&[Change::CursorVisibility(CursorVisibility::Visible)]);
+        if self.enable_alt_screen {
+            let _ = terminal.exit_alternate_screen();
+        }
+        if self.enable_raw_mode {
+            let _ = terminal.set_cooked_mode();
+        }
+        let _ = terminal.flush();
+    }
+}
+
+// Paint directly to the cells of the surface
+struct TermwizSurface<'a>(&'a mut Surface);
+
+impl TermwizSurface<'_> {
+    fn with_cell(&mut self, pos: Pos, f: impl FnOnce(&mut ::termwiz::cell::Cell)) {
+        let (Ok(x), Ok(y)) = (usize::try_from(pos.x), usize::try_from(pos.y)) else { return };
+        let mut lines = self.0.screen_cells();
+        if let Some(cell) = lines.get_mut(y).and_then(|line| line.get_mut(x)) {
+            f(cell);
+        }
+    }
+}
+
+impl WidgetRenderer for TermwizSurface<'_> {
+    fn draw_glyph(&mut self, c: char, pos: Pos) {
+        self.with_cell(pos, |cell| {
+            *cell = ::termwiz::cell::Cell::new(c, cell.attrs().clone());
+        });
+    }
+
+    fn draw_cluster(&mut self, glyph: &str, pos: Pos) {
+        let width = anathema_widgets::glyphs::width(glyph);
+        self.with_cell(pos, |cell| {
+            *cell = ::termwiz::cell::Cell::new_grapheme_with_width(glyph, width, cell.attrs().clone());
+        });
+    }
+
+    fn set_attributes(&mut self, attribs: &dyn CellAttributes, pos: Pos) {
+        let style = Style::from_cell_attribs(attribs);
+        self.with_cell(pos, |cell| apply_style(style, cell.attrs_mut()));
+    }
+
+    fn size(&self) -> Size {
+        let (width, height) = self.0.dimensions();
+        Size::new(width, height)
+    }
+}
+
+fn apply_style(style: Style, attrs: &mut TCellAttributes) {
+    if let Some(fg) = style.fg {
+        attrs.set_foreground(color(fg));
+    }
+
+    if let Some(bg) = style.bg {
+        attrs.set_background(color(bg));
+    }
+
+    let intensity = match style.attributes {
+        a if a.contains(Attributes::BOLD) => Intensity::Bold,
+        a if a.contains(Attributes::DIM) => Intensity::Half,
+        _ => Intensity::Normal,
+    };
+    attrs.set_intensity(intensity);
+
+    let underline = match style.attributes {
+        a if a.contains(Attributes::CURLY_UNDERLINED) => Underline::Curly,
+        a if a.contains(Attributes::DOTTED_UNDERLINED) => Underline::Dotted,
+        a if a.contains(Attributes::DASHED_UNDERLINED) => Underline::Dashed,
+        a if a.contains(Attributes::DOUBLE_UNDERLINED) => Underline::Double,
+        a if a.contains(Attributes::UNDERLINED) => Underline::Single,
+        _ => Underline::None,
+    };
+    attrs.set_underline(underline);
+
+    if let Some(underline_color) = style.underline_color {
+        attrs.set_underline_color(color(underline_color));
+    }
+    attrs.set_italic(style.attributes.contains(Attributes::ITALIC));
+    attrs.set_strikethrough(style.attributes.contains(Attributes::CROSSED_OUT));
+    attrs.set_overline(style.attributes.contains(Attributes::OVERLINED));
+    attrs.set_reverse(style.attributes.contains(Attributes::INVERSE));
+}
+
+fn color(color: Color) -> ColorAttribute {
+    match color {
+        Color::Reset => ColorAttribute::Default,
+        Color::Rgb(r, g, b) => ColorAttribute::TrueColorWithDefaultFallback((r, g, b).into()),
+        _ => match ansi::palette_index(color) {
+            Some(index) => ColorAttribute::PaletteIndex(index),
+            None => ColorAttribute::Default,
+        },
+    }
+}
+
+fn translate_key(key: TKeyEvent) -> Option<KeyEvent> {
+    let code = match key.key {
+        TKeyCode::Char(c) => KeyCode::Char(c),
+        TKeyCode::Backspace => KeyCode::Backspace,
+        TKeyCode::Tab if key.modifiers.contains(Modifiers::SHIFT) => KeyCode::BackTab,
+        TKeyCode::Tab => KeyCode::Tab,
+        TKeyCode::Enter => KeyCode::Enter,
+        TKeyCode::Escape => KeyCode::Esc,
+        TKeyCode::LeftArrow | TKeyCode::ApplicationLeftArrow => KeyCode::Left,
+        TKeyCode::RightArrow | TKeyCode::ApplicationRightArrow => KeyCode::Right,
+        TKeyCode::UpArrow | TKeyCode::ApplicationUpArrow => KeyCode::Up,
+        TKeyCode::DownArrow | TKeyCode::ApplicationDownArrow => KeyCode::Down,
+        TKeyCode::Home | TKeyCode::KeyPadHome => KeyCode::Home,
+        TKeyCode::End | TKeyCode::KeyPadEnd => KeyCode::End,
+        TKeyCode::PageUp | TKeyCode::KeyPadPageUp => KeyCode::PageUp,
+        TKeyCode::PageDown | TKeyCode::KeyPadPageDown => KeyCode::PageDown,
+        TKeyCode::KeyPadBegin => KeyCode::KeypadBegin,
+        TKeyCode::Insert => KeyCode::Insert,
+        TKeyCode::Delete => KeyCode::Delete,
+        TKeyCode::Function(n) => KeyCode::F(n),
+        TKeyCode::CapsLock => KeyCode::CapsLock,
+        TKeyCode::ScrollLock => KeyCode::ScrollLock,
+        TKeyCode::NumLock => KeyCode::NumLock,
+        TKeyCode::PrintScreen => KeyCode::PrintScreen,
+        TKeyCode::Pause => KeyCode::Pause,
+        TKeyCode::Menu | TKeyCode::Applications => KeyCode::Menu,
+        _ => return None,
+    };
+
+    let mut event = KeyEvent::press(code);
+    event.ctrl = key.modifiers.contains(Modifiers::CTRL);
+    event.alt = key.modifiers.contains(Modifiers::ALT);
+    event.shift = key.modifiers.contains(Modifiers::SHIFT);
+    event.super_key = key.modifiers.contains(Modifiers::SUPER);
+    Some(event)
+}
+
+// Compare the held buttons to the previously held buttons,
+// to tell presses, releases and drags apart
+fn translate_mouse(mouse: TMouseEvent, held: &mut MouseButtons) -> MouseEvent {
+    let buttons = mouse.mouse_buttons;
+    let positive = buttons.contains(MouseButtons::WHEEL_POSITIVE);
+    let state = if buttons.contains(MouseButtons::VERT_WHEEL) {
+        match positive {
+            true => MouseState::ScrollUp,
+            false => MouseState::ScrollDown,
+        }
+    } else if buttons.contains(MouseButtons::HORZ_WHEEL) {
+        match positive {
+            true => MouseState::ScrollLeft,
+            false => MouseState::ScrollRight,
+        }
+    } else {
+        let pressed = buttons.clone() - held.clone();
+        let released = held.clone() - buttons.clone();
+        let state = match (button(&pressed), button(&released), button(&buttons)) {
+            (Some(button), _, _) => MouseState::Down(button),
+            (None, Some(button), _) => MouseState::Up(button),
+            (None, None, Some(button)) => MouseState::Drag(button),
+            (None, None, None) => MouseState::Move,
+        };
+        *held = buttons;
+        state
+    };
+
+    // Terminals report one-based positions
+    MouseEvent::new(mouse.x.saturating_sub(1), mouse.y.saturating_sub(1), state)
+}
+
+fn button(buttons: &MouseButtons) -> Option<MouseButton> {
+    if buttons.contains(MouseButtons::LEFT) {
+        Some(MouseButton::Left)
+    } else if buttons.contains(MouseButtons::RIGHT) {
+        Some(MouseButton::Right)
+    } else if buttons.contains(MouseButtons::MIDDLE) {
+        Some(MouseButton::Middle)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn mouse(x: u16, y: u16, mouse_buttons: MouseButtons) -> TMouseEvent {
+        TMouseEvent {
+            x,
+            y,
+            mouse_buttons,
+            modifiers: Modifiers::NONE,
+        }
+    }
+
+    #[test]
+    fn press_drag_release() {
+        let mut held = MouseButtons::NONE;
+
+        let down = translate_mouse(mouse(2, 2, MouseButtons::LEFT), &mut held);
+        assert!(down.lsb_down());
+        assert_eq!((down.x, down.y), (1, 1));
+
+        let drag = translate_mouse(mouse(3, 2, MouseButtons::LEFT), &mut held);
+        assert!(matches!(drag.state, MouseState::Drag(MouseButton::Left)));
+
+        let up = translate_mouse(mouse(3, 2, MouseButtons::NONE), &mut held);
+        assert!(up.lsb_up());
+
+        let moved = translate_mouse(mouse(4, 2, MouseButtons::NONE), &mut held);
+        assert!(matches!(moved.state, MouseState::Move));
+    }
+
+    #[test]
+    fn style_to_attributes() {
+        let mut style = Style::new();
+        style.set_fg(Color::LightRed);
+        style.set_bold(true);
+
+        let mut attrs = TCellAttributes::default();
+        apply_style(style, &mut attrs);
+        assert_eq!(attrs.foreground(), ColorAttribute::PaletteIndex(9));
+        assert_eq!(attrs.intensity(), Intensity::Bold);
+    }
+}
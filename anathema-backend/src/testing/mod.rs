@@ -0,0 +1,426 @@
+//! A backend for tests, painting to a grid of cells.
+//!
+//! Every render stores the frame both as text ([`TestBackend::output`])
+//! and as cells with their styles ([`TestBackend::grid`]).
+//!
+//! Frames can be compared with [`assert_frame_eq!`](crate::assert_frame_eq)
+//! and golden files with [`assert_frame_snapshot!`](crate::assert_frame_snapshot).
+use std::collections::VecDeque;
+use std::fmt::Display;
+use std::mem;
+use std::time::Duration;
+
+use anathema_geometry::{Pos, Region, Size};
+use anathema_store::tree::{Node, TreeValues};
+use anathema_widgets::components::events::{
+    ClickCounter, Event, KeyCode, KeyEvent, MouseButton, MouseEvent, MouseState,
+};
+use anathema_widgets::debug::Overlay;
+use anathema_widgets::paint::{CellAttributes, CursorShape};
+use anathema_widgets::time::Instant;
+use anathema_widgets::{AttributeStorage, Element, WidgetKind, WidgetRenderer};
+
+use crate::tui::Style;
+use crate::{EventSource, Renderer};
+
+pub mod snapshot;
+
+pub struct TestBackend {
+    pub surface: TestSurface,
+    /// The last rendered frame, one line per row
+    pub output: String,
+    /// The last rendered frame
+    pub grid: TestSurface,
+    /// The title set through [`Renderer::set_title`]
+    pub title: Option<String>,
+    /// The number of times the bell was rung
+    pub bells: usize,
+    /// The text copied through [`Renderer::copy`]
+    pub clipboard: Option<String>,
+    events: VecDeque<Scripted>,
+    // Only the damaged regions are drawn to the rendered frame
+    track_damage: bool,
+    // Draw the entire surface on the next render
+    redraw: bool,
+    delay: Duration,
+    last_event: Option<Instant>,
+    clicks: ClickCounter,
+}
+
+// A queued event
+struct Scripted {
+    // The time between the previous event and this one
+    delay: Duration,
+    event: Event,
+    // Set the click count like a terminal backend would
+    count_clicks: bool,
+}
+
+impl TestBackend {
+    pub fn new(size: impl Into<Size>) -> Self {
+        let size = size.into();
+        Self {
+            surface: TestSurface::new(size),
+            output: String::new(),
+            grid: TestSurface::new(size),
+            title: None,
+            bells: 0,
+            clipboard: None,
+            events: VecDeque::new(),
+            track_damage: false,
+            redraw: true,
+            delay: Duration::ZERO,
+            last_event: None,
+            clicks: ClickCounter::default(),
+        }
+    }
+
+    /// Only draw the regions damaged by the widgets to the rendered frame,
+    /// like the [`TuiBackend`](crate::tui::TuiBackend) does,
+    /// so cells that are not damaged keep what the previous frame drew.
+    pub fn track_damage(&mut self) -> &mut Self {
+        self.track_damage = true;
+        self
+    }
+
+    /// Queue an event, to be returned by [`EventSource::next_event`]
+    pub fn push_event(&mut self, event: Event) -> &mut Self {
+        self.push(event, false)
+    }
+
+    /// Delay the next queued event.
+    ///
+    /// The event is returned once the duration has passed since
+    /// the previous event was returned (or since the runtime first polled the backend).
+    /// Until then the backend sleeps, like a terminal without input.
+    pub fn wait(&mut self, duration: Duration) -> &mut Self {
+        self.delay += duration;
+        self
+    }
+
+    /// Queue a key event, e.g `KeyEvent::press(KeyCode::Char('s')).with_ctrl()`
+    pub fn push_key(&mut self, key: KeyEvent) -> &mut Self {
+        self.push_event(Event::Key(key))
+    }
+
+    /// Queue a key press without any modifiers
+    pub fn push_press(&mut self, code: KeyCode) -> &mut Self {
+        self.push_key(KeyEvent::press(code))
+    }
+
+    /// Queue a key press for every character in the string
+    pub fn push_str(&mut self, s: &str) -> &mut Self {
+        s.chars().for_each(|c| _ = self.push_press(KeyCode::Char(c)));
+        self
+    }
+
+    /// Queue a mouse event at a position on the screen.
+    /// Consecutive clicks are counted when the event is returned,
+    /// so two clicks in a row without waiting in between is a double click.
+    pub fn push_mouse(&mut self, x: u16, y: u16, state: MouseState) -> &mut Self {
+        let event = Event::Mouse(MouseEvent::new(x, y, state));
+        self.push(event, true)
+    }
+
+    /// Queue a left mouse button press and release at a position on the screen
+    pub fn push_click(&mut self, x: u16, y: u16) -> &mut Self {
+        self.push_mouse(x, y, MouseState::Down(MouseButton::Left))
+            .push_mouse(x, y, MouseState::Up(MouseButton::Left))
+    }
+
+    /// Queue a resize of the terminal
+    pub fn push_resize(&mut self, width: u16, height: u16) -> &mut Self {
+        self.push_event(Event::Resize(width, height))
+    }
+
+    /// Queue text pasted into the terminal
+    pub fn push_paste(&mut self, text: impl Into<String>) -> &mut Self {
+        self.push_event(Event::Paste(text.into()))
+    }
+
+    /// The number of queued events that have not been returned yet
+    pub fn pending_events(&self) -> usize {
+        self.events.len()
+    }
+
+    fn push(&mut self, event: Event, count_clicks: bool) -> &mut Self {
+        let delay = std::mem::take(&mut self.delay);
+        self.events.push_back(Scripted {
+            delay,
+            event,
+            count_clicks,
+        });
+        self
+    }
+}
+
+impl TestBackend {
+    // When the next event is due, given the delay after the previous event
+    fn next_due(&mut self) -> Option<Instant> {
+        let delay = self.events.front()?.delay;
+        Some(*self.last_event.get_or_insert_with(Instant::now) + delay)
+    }
+
+    fn pop_event(&mut self) -> Option<Event> {
+        let scripted = self.events.pop_front()?;
+        let now = Instant::now();
+        self.last_event = Some(now);
+
+        match scripted.event {
+            Event::Mouse(mouse) if scripted.count_clicks => Some(Event::Mouse(self.clicks.count(mouse, now))),
+            event => Some(event),
+        }
+    }
+}
+
+impl EventSource for TestBackend {
+    fn next_event(&mut self, timeout: Duration) -> Option<Event> {
+        let due = self.next_due()?;
+        let now = Instant::now();
+
+        if due > now {
+            let wait = due - now;
+            if wait > timeout {
+                std::thread::sleep(timeout);
+                return None;
+            }
+            std::thread::sleep(wait);
+        }
+
+        self.pop_event()
+    }
+}
+
+/// Once every event is returned this never completes.
+#[cfg(feature = "tokio")]
+impl crate::AsyncEventSource for TestBackend {
+    async fn wait_event(&mut self) -> Option<Event> {
+        let Some(due) = self.next_due() else { return std::future::pending().await };
+        tokio::time::sleep_until(due.into()).await;
+        self.pop_event()
+    }
+}
+
+impl Renderer for TestBackend {
+    fn size(&self) -> Size {
+        self.surface.size
+    }
+
+    fn resize(&mut self, new_size: Size) {
+        self.surface = TestSurface::new(new_size);
+        self.redraw = true;
+    }
+
+    fn paint<'bp>(
+        &mut self,
+        element: &mut Element<'bp>,
+        children: &[Node],
+        values: &mut TreeValues<WidgetKind<'bp>>,
+        attribute_storage: &AttributeStorage<'bp>,
+        ignore_floats: bool,
+    ) {
+        anathema_widgets::paint::paint(
+            &mut self.surface,
+            element,
+            children,
+            values,
+            attribute_storage,
+            ignore_floats,
+        );
+    }
+
+    fn clear(&mut self) {
+        self.surface.clear();
+    }
+
+    fn overlay(&mut self, overlay: &dyn Overlay) {
+        overlay.paint(&mut self.surface);
+    }
+
+    fn render(&mut self) {
+        let damage = mem::take(&mut self.surface.damage);
+        let redraw = mem::take(&mut self.redraw);
+        match self.track_damage && !redraw {
+            true => self.grid.copy_regions(&self.surface, &damage),
+            false => self.grid.clone_from(&self.surface),
+        }
+        self.output = format!("{}", self.grid);
+    }
+
+    fn set_title(&mut self, title: &str) {
+        self.title = Some(title.into());
+    }
+
+    fn bell(&mut self) {
+        self.bells += 1;
+    }
+
+    fn copy(&mut self, text: &str) {
+        self.clipboard = Some(text.into());
+    }
+}
+
+/// A painted character and its style
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TestCell {
+    pub c: char,
+    pub style: Style,
+}
+
+impl TestCell {
+    const EMPTY: Self = Self {
+        c: ' ',
+        style: Style::new(),
+    };
+}
+
+#[derive(Debug, Clone)]
+pub struct TestSurface {
+    size: Size,
+    buffer: Vec<TestCell>,
+    cursor: Option<(Pos, CursorShape)>,
+    // The regions damaged since the last render
+    damage: Vec<Region>,
+}
+
+impl TestSurface {
+    pub fn new(size: impl Into<Size>) -> Self {
+        let size = size.into();
+        let buffer_size = size.width * size.height;
+        Self {
+            buffer: vec![TestCell::EMPTY; buffer_size],
+            size,
+            cursor: None,
+            damage: vec![],
+        }
+    }
+
+    /// The cell at the given position, if the position is inside the surface
+    pub fn get(&self, x: usize, y: usize) -> Option<&TestCell> {
+        if x >= self.size.width {
+            return None;
+        }
+        self.buffer.get(y * self.size.width + x)
+    }
+
+    /// An iterator over the rows of cells
+    pub fn rows(&self) -> impl Iterator<Item = &[TestCell]> {
+        self.buffer.chunks(self.size.width.max(1))
+    }
+
+    /// The position and shape of the cursor, if a widget showed the cursor
+    pub fn cursor(&self) -> Option<(Pos, CursorShape)> {
+        self.cursor
+    }
+
+    fn clear(&mut self) {
+        self.buffer.fill(TestCell::EMPTY);
+        self.cursor = None;
+    }
+
+    fn index(&self, pos: Pos) -> usize {
+        pos.y as usize * self.size.width + pos.x as usize
+    }
+
+    // Copy the cells inside the regions, and the cursor
+    fn copy_regions(&mut self, other: &Self, regions: &[Region]) {
+        for region in regions {
+            let from_x = region.from.x.clamp(0, self.size.width as i32) as usize;
+            let to_x = region.to.x.clamp(0, self.size.width as i32) as usize;
+            let from_y = region.from.y.clamp(0, self.size.height as i32) as usize;
+            let to_y = region.to.y.clamp(0, self.size.height as i32) as usize;
+
+            for y in from_y..to_y {
+                let row = y * self.size.width;
+                self.buffer[row + from_x..row + to_x].copy_from_slice(&other.buffer[row + from_x..row + to_x]);
+            }
+        }
+        self.cursor = other.cursor;
+    }
+}
+
+impl WidgetRenderer for TestSurface {
+    fn draw_glyph(&mut self, c: char, local_pos: Pos) {
+        let index = self.index(local_pos);
+        self.buffer[index].c = c;
+    }
+
+    fn size(&self) -> Size {
+        self.size
+    }
+
+    fn set_attributes(&mut self, attribs: &dyn CellAttributes, local_pos: Pos) {
+        let index = self.index(local_pos);
+        let style = Style::from_cell_attribs(attribs);
+        let cell = &mut self.buffer[index];
+
+        if let fg @ Some(_) = style.fg {
+            cell.style.fg = fg;
+        }
+
+        if let bg @ Some(_) = style.bg {
+            cell.style.bg = bg;
+        }
+
+        if let color @ Some(_) = style.underline_color {
+            cell.style.underline_color = color;
+        }
+
+        cell.style.attributes |= style.attributes;
+    }
+
+    fn set_cursor(&mut self, pos: Pos, shape: CursorShape) {
+        self.cursor = Some((pos, shape));
+    }
+
+    fn damage(&mut self, region: Region) {
+        self.damage.push(region);
+    }
+}
+
+impl Display for TestSurface {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for row in self.rows() {
+            for cell in row {
+                write!(f, "{}", cell.c)?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn clicks(event: Option<Event>) -> u8 {
+        match event {
+            Some(Event::Mouse(mouse)) => mouse.clicks,
+            _ => panic!("not a mouse event"),
+        }
+    }
+
+    #[test]
+    fn scripted_events() {
+        let mut backend = TestBackend::new((1, 1));
+        let interval = ClickCounter::DEFAULT_INTERVAL;
+        backend
+            .push_key(KeyEvent::press(KeyCode::Char('a')).with_ctrl())
+            .push_click(0, 0)
+            .push_mouse(0, 0, MouseState::Down(MouseButton::Left))
+            .wait(interval + Duration::from_millis(10))
+            .push_mouse(0, 0, MouseState::Down(MouseButton::Left));
+
+        assert!(matches!(backend.next_event(Duration::ZERO), Some(Event::Key(key)) if key.ctrl));
+        assert_eq!(clicks(backend.next_event(Duration::ZERO)), 1);
+        assert_eq!(clicks(backend.next_event(Duration::ZERO)), 1);
+        assert_eq!(clicks(backend.next_event(Duration::ZERO)), 2);
+
+        // The last event isn't due yet
+        assert!(backend.next_event(Duration::ZERO).is_none());
+        assert_eq!(backend.pending_events(), 1);
+        assert_eq!(clicks(backend.next_event(interval * 2)), 1);
+    }
+}
@@ -0,0 +1,266 @@
+//! Compare rendered frames with an expected frame, or with a golden file.
+//!
+//! A frame is written as the rows of characters, followed by the styled cells
+//! (if there are any) as one line per run of cells with the same style:
+//!
+//! ```text
+//! hello
+//!   world
+//! -- styles --
+//! 0: 0..5 fg:Red bold
+//! 1: 2..7 bg:#FF00FF underlined
+//! ```
+//!
+//! The row and the columns of a run come first, followed by the style.
+//! Trailing whitespace and trailing empty rows are ignored.
+use std::fmt::Write;
+use std::path::Path;
+
+use anathema_widgets::glyphs;
+
+use super::TestSurface;
+use crate::tui::Style;
+
+const STYLES: &str = "-- styles --";
+
+/// Set this environment variable to overwrite golden files with the current frame
+/// rather than comparing against them.
+pub const UPDATE_SNAPSHOTS: &str = "ANATHEMA_UPDATE_SNAPSHOTS";
+
+/// Assert that a [`TestSurface`](crate::testing::TestSurface) matches the expected frame.
+///
+/// ```ignore
+/// assert_frame_eq!(&backend.grid, "
+/// hello
+/// -- styles --
+/// 0: 0..5 bold
+/// ");
+/// ```
+///
+/// The styles can be left out, in which case only the characters are compared.
+#[macro_export]
+macro_rules! assert_frame_eq {
+    ($surface:expr, $expected:expr $(,)?) => {
+        $crate::testing::snapshot::assert_frame($surface, $expected)
+    };
+}
+
+/// Assert that a [`TestSurface`](crate::testing::TestSurface) matches a golden file,
+/// relative to the manifest directory of the crate running the test.
+///
+/// The golden file is written if it doesn't exist,
+/// or if the [`UPDATE_SNAPSHOTS`](crate::testing::snapshot::UPDATE_SNAPSHOTS) environment variable is set.
+///
+/// ```ignore
+/// assert_frame_snapshot!(&backend.grid, "tests/snapshots/list.frame");
+/// ```
+#[macro_export]
+macro_rules! assert_frame_snapshot {
+    ($surface:expr, $path:expr $(,)?) => {
+        $crate::testing::snapshot::assert_snapshot(
+            $surface,
+            ::std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join($path),
+        )
+    };
+}
+
+impl TestSurface {
+    /// The characters and styles of the surface as text,
+    /// in the format used by [`assert_frame_eq!`](crate::assert_frame_eq).
+    pub fn snapshot(&self) -> String {
+        let mut output = String::new();
+        let mut styles = String::new();
+
+        for (y, row) in self.rows().enumerate() {
+            let line = row.iter().map(|cell| cell.c).collect::<String>();
+            output.push_str(line.trim_end());
+            output.push('\n');
+
+            let mut x = 0;
+            while x < row.len() {
+                let style = row[x].style;
+                let len = row[x..].iter().take_while(|cell| cell.style == style).count();
+                if style != Style::new() {
+                    let _ = writeln!(styles, "{y}: {x}..{} {}", x + len, describe(&style));
+                }
+                x += len;
+            }
+        }
+
+        if !styles.is_empty() {
+            output.push_str(STYLES);
+            output.push('\n');
+            output.push_str(&styles);
+        }
+
+        normalize(&output, true)
+    }
+}
+
+/// Panic with a diff of the frames if the surface doesn't match the expected frame.
+/// Styles are only compared if the expected frame has any.
+#[track_caller]
+pub fn assert_frame(surface: &TestSurface, expected: &str) {
+    let with_styles = expected.lines().any(|line| line.trim_end() == STYLES);
+    let expected = normalize(expected, with_styles);
+    let actual = normalize(&surface.snapshot(), with_styles);
+
+    if expected != actual {
+        panic!("frames are not equal\n{}", diff(&expected, &actual));
+    }
+}
+
+/// Panic with a diff of the frames if the surface doesn't match the golden file.
+/// The golden file is written if it doesn't exist or if [`UPDATE_SNAPSHOTS`] is set.
+#[track_caller]
+pub fn assert_snapshot(surface: &TestSurface, path: impl AsRef<Path>) {
+    let path = path.as_ref();
+    let actual = surface.snapshot();
+
+    if std::env::var_os(UPDATE_SNAPSHOTS).is_some() || !path.exists() {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).expect("failed to create the snapshot directory");
+        }
+        std::fs::write(path, &actual).expect("failed to write the snapshot");
+        return;
+    }
+
+    let expected = std::fs::read_to_string(path).expect("failed to read the snapshot");
+    let expected = normalize(&expected, true);
+    if expected != actual {
+        panic!(
+            "frame doesn't match the snapshot `{}`\n{}\nset {UPDATE_SNAPSHOTS}=1 to update the snapshot",
+            path.display(),
+            diff(&expected, &actual)
+        );
+    }
+}
+
+// fg:Red bg:#FF00FF underline:Blue bold italic
+fn describe(style: &Style) -> String {
+    let mut desc = vec![];
+
+    if let Some(fg) = style.fg {
+        desc.push(format!("fg:{fg}"));
+    }
+
+    if let Some(bg) = style.bg {
+        desc.push(format!("bg:{bg}"));
+    }
+
+    if let Some(color) = style.underline_color {
+        desc.push(format!("underline:{color}"));
+    }
+
+    for (name, _) in style.attributes.iter_names() {
+        desc.push(name.to_lowercase());
+    }
+
+    desc.join(" ")
+}
+
+// Remove trailing whitespace, leading and trailing empty lines,
+// and (unless `with_styles` is true) the styles
+fn normalize(frame: &str, with_styles: bool) -> String {
+    let mut lines = frame
+        .lines()
+        .map(str::trim_end)
+        .take_while(|line| with_styles || *line != STYLES)
+        .skip_while(|line| line.is_empty())
+        .collect::<Vec<_>>();
+
+    while lines.last().is_some_and(|line| line.is_empty()) {
+        lines.pop();
+    }
+
+    // Empty rows before the styles don't count either
+    if let Some(index) = lines.iter().position(|line| *line == STYLES) {
+        let rows = lines[..index].iter().rev().take_while(|line| line.is_empty()).count();
+        lines.drain(index - rows..index);
+    }
+
+    let mut output = lines.join("\n");
+    output.push('\n');
+    output
+}
+
+// The frames side by side, with the lines that differ marked
+fn diff(expected: &str, actual: &str) -> String {
+    let expected = expected.lines().collect::<Vec<_>>();
+    let actual = actual.lines().collect::<Vec<_>>();
+    let width = expected
+        .iter()
+        .map(|line| glyphs::str_width(line))
+        .max()
+        .unwrap_or(0)
+        .max("expected".len());
+
+    let mut output = format!("   {:width$}   actual\n", "expected");
+    for i in 0..expected.len().max(actual.len()) {
+        let (exp, act) = (expected.get(i), actual.get(i));
+        let marker = if exp == act { ' ' } else { '>' };
+        let exp = exp.copied().unwrap_or("");
+        let pad = width - glyphs::str_width(exp);
+        let _ = writeln!(output, "{marker} │{exp}{:pad$}│ │{}│", "", act.copied().unwrap_or(""));
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod test {
+    use anathema_geometry::{Pos, Size};
+    use anathema_state::Color;
+    use anathema_widgets::WidgetRenderer;
+
+    use super::*;
+    use crate::tui::Attributes;
+
+    fn surface() -> TestSurface {
+        let mut surface = TestSurface::new(Size::new(6, 3));
+        for (x, c) in "hi".chars().enumerate() {
+            surface.draw_glyph(c, Pos::new(x as i32, 0));
+        }
+        surface.draw_glyph('!', Pos::new(2, 1));
+
+        let mut style = Style::new();
+        style.set_fg(Color::Red);
+        style.attributes |= Attributes::BOLD | Attributes::ITALIC;
+        surface.set_attributes(&style, Pos::new(0, 0));
+        surface.set_attributes(&style, Pos::new(1, 0));
+        surface
+    }
+
+    #[test]
+    fn snapshot() {
+        let expected = "hi\n  !\n-- styles --\n0: 0..2 fg:Red bold italic\n";
+        assert_eq!(surface().snapshot(), expected);
+    }
+
+    #[test]
+    fn frame_eq() {
+        // Only the characters are compared without the styles
+        assert_frame_eq!(&surface(), "\nhi\n  !\n\n");
+        assert_frame_eq!(&surface(), "hi\n  !\n-- styles --\n0: 0..2 fg:Red bold italic");
+    }
+
+    #[test]
+    #[should_panic(expected = "> │0: 0..2 bold│ │0: 0..2 fg:Red bold italic│")]
+    fn frame_ne() {
+        assert_frame_eq!(&surface(), "hi\n  !\n-- styles --\n0: 0..2 bold");
+    }
+
+    #[test]
+    fn golden_file() {
+        let path = std::env::temp_dir().join(format!("anathema-snapshot-{}.frame", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        // The first run writes the golden file
+        assert_snapshot(&surface(), &path);
+        assert_snapshot(&surface(), &path);
+
+        let result = std::panic::catch_unwind(|| assert_snapshot(&TestSurface::new(Size::new(6, 3)), &path));
+        let _ = std::fs::remove_file(&path);
+        assert!(result.is_err());
+    }
+}
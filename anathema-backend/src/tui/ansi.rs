@@ -0,0 +1,295 @@
+// Escape sequences used to draw a `Buffer`.
+//
+// These are written directly, rather than through a terminal library,
+// so the buffer and the diffing can be shared by all the terminal backends.
+use std::io::{Result, Write};
+
+use anathema_state::Color;
+use anathema_widgets::paint::CursorShape;
+#[cfg(any(feature = "crossterm", feature = "termion", feature = "ssh", test))]
+use base64::engine::general_purpose::STANDARD;
+#[cfg(any(feature = "crossterm", feature = "termion", feature = "ssh", test))]
+use base64::Engine;
+
+use super::ColorDowngrade;
+use crate::ColorDepth;
+
+pub(crate) const RESET: u8 = 0;
+pub(crate) const BOLD: u8 = 1;
+pub(crate) const DIM: u8 = 2;
+pub(crate) const ITALIC: u8 = 3;
+pub(crate) const UNDERLINED: u8 = 4;
+pub(crate) const REVERSE: u8 = 7;
+pub(crate) const CROSSED_OUT: u8 = 9;
+pub(crate) const NORMAL_INTENSITY: u8 = 22;
+pub(crate) const NO_ITALIC: u8 = 23;
+pub(crate) const NO_UNDERLINE: u8 = 24;
+pub(crate) const NO_REVERSE: u8 = 27;
+pub(crate) const NOT_CROSSED_OUT: u8 = 29;
+pub(crate) const OVERLINED: u8 = 53;
+pub(crate) const NOT_OVERLINED: u8 = 55;
+
+// Underline styles, written as `4:<style>`
+pub(crate) const DOUBLE_UNDERLINE: u8 = 2;
+pub(crate) const CURLY_UNDERLINE: u8 = 3;
+pub(crate) const DOTTED_UNDERLINE: u8 = 4;
+pub(crate) const DASHED_UNDERLINE: u8 = 5;
+
+// Synchronized output (mode 2026): the terminal holds off drawing until the update ends
+pub(crate) const BEGIN_SYNCHRONIZED_UPDATE: &[u8] = b"\x1b[?2026h";
+pub(crate) const END_SYNCHRONIZED_UPDATE: &[u8] = b"\x1b[?2026l";
+
+pub(crate) const HIDE_CURSOR: &[u8] = b"\x1b[?25l";
+pub(crate) const SHOW_CURSOR: &[u8] = b"\x1b[?25h";
+#[cfg(any(feature = "crossterm", feature = "termion"))]
+pub(crate) const RESET_CURSOR_SHAPE: &[u8] = b"\x1b[0 q";
+
+#[cfg(any(feature = "crossterm", feature = "termion", feature = "ssh"))]
+pub(crate) const BELL: &[u8] = b"\x07";
+// Save and restore the window title (xterm title stack)
+#[cfg(any(feature = "crossterm", feature = "termion", feature = "ssh"))]
+pub(crate) const PUSH_TITLE: &[u8] = b"\x1b[22;2t";
+#[cfg(any(feature = "crossterm", feature = "termion", feature = "ssh"))]
+pub(crate) const POP_TITLE: &[u8] = b"\x1b[23;2t";
+
+/// Set the window title.
+/// Control characters are left out, as they could end the sequence.
+#[cfg(any(feature = "crossterm", feature = "termion", feature = "ssh", test))]
+pub(crate) fn set_title(mut w: impl Write, title: &str) -> Result<()> {
+    let title = title.chars().filter(|c| !c.is_control()).collect::<String>();
+    write!(w, "\x1b]2;{title}\x07")
+}
+
+/// Copy the text to the clipboard (OSC 52).
+/// Terminals that don't support OSC 52 (or have it disabled) ignore this.
+#[cfg(any(feature = "crossterm", feature = "termion", feature = "ssh", test))]
+pub(crate) fn copy(mut w: impl Write, text: &str) -> Result<()> {
+    write!(w, "\x1b]52;c;{}\x07", STANDARD.encode(text))
+}
+
+/// Move the cursor to a zero based position
+pub(crate) fn move_to(mut w: impl Write, x: u16, y: u16) -> Result<()> {
+    write!(w, "\x1b[{};{}H", y as u32 + 1, x as u32 + 1)
+}
+
+/// Set the shape of the cursor (DECSCUSR), as a blinking cursor
+pub(crate) fn cursor_shape(mut w: impl Write, shape: CursorShape) -> Result<()> {
+    let code = match shape {
+        CursorShape::Block => 1,
+        CursorShape::Underline => 3,
+        CursorShape::Bar => 5,
+    };
+    write!(w, "\x1b[{code} q")
+}
+
+/// Select graphic rendition
+pub(crate) fn sgr(mut w: impl Write, code: u8) -> Result<()> {
+    write!(w, "\x1b[{code}m")
+}
+
+/// Set the style of the underline (one of the `*_UNDERLINE` styles)
+pub(crate) fn underline(mut w: impl Write, style: u8) -> Result<()> {
+    write!(w, "\x1b[4:{style}m")
+}
+
+pub(crate) fn foreground(w: impl Write, color: Color) -> Result<()> {
+    write_color(w, 38, color)
+}
+
+pub(crate) fn background(w: impl Write, color: Color) -> Result<()> {
+    write_color(w, 48, color)
+}
+
+pub(crate) fn underline_color(w: impl Write, color: Color) -> Result<()> {
+    write_color(w, 58, color)
+}
+
+// `base` is 38 for the foreground, 48 for the background and 58 for the underline,
+// and the reset is `base + 1`.
+fn write_color(mut w: impl Write, base: u8, color: Color) -> Result<()> {
+    match color {
+        Color::Reset => sgr(w, base + 1),
+        Color::Rgb(r, g, b) => write!(w, "\x1b[{base};2;{r};{g};{b}m"),
+        _ => match palette_index(color) {
+            Some(index) => write!(w, "\x1b[{base};5;{index}m"),
+            None => Ok(()),
+        },
+    }
+}
+
+/// The index of a color in the 256 color palette.
+/// The reset colour and rgb colours are not part of the palette.
+pub(crate) fn palette_index(color: Color) -> Option<u8> {
+    let index = match color {
+        Color::Reset | Color::Rgb(..) => return None,
+        Color::AnsiVal(val) => val,
+        Color::Black => 0,
+        Color::Red => 1,
+        Color::Green => 2,
+        Color::Yellow => 3,
+        Color::Blue => 4,
+        Color::Magenta => 5,
+        Color::Cyan => 6,
+        Color::Grey => 7,
+        Color::DarkGrey => 8,
+        Color::LightRed => 9,
+        Color::LightGreen => 10,
+        Color::LightYellow => 11,
+        Color::LightBlue => 12,
+        Color::LightMagenta => 13,
+        Color::LightCyan => 14,
+        Color::White => 15,
+    };
+    Some(index)
+}
+
+/// The default xterm colour of a palette index
+pub(crate) fn palette_rgb(index: u8) -> (u8, u8, u8) {
+    const SYSTEM: [(u8, u8, u8); 16] = [
+        (0x00, 0x00, 0x00),
+        (0xcd, 0x00, 0x00),
+        (0x00, 0xcd, 0x00),
+        (0xcd, 0xcd, 0x00),
+        (0x00, 0x00, 0xee),
+        (0xcd, 0x00, 0xcd),
+        (0x00, 0xcd, 0xcd),
+        (0xe5, 0xe5, 0xe5),
+        (0x7f, 0x7f, 0x7f),
+        (0xff, 0x00, 0x00),
+        (0x00, 0xff, 0x00),
+        (0xff, 0xff, 0x00),
+        (0x5c, 0x5c, 0xff),
+        (0xff, 0x00, 0xff),
+        (0x00, 0xff, 0xff),
+        (0xff, 0xff, 0xff),
+    ];
+
+    match index {
+        0..=15 => SYSTEM[index as usize],
+        16..=231 => {
+            let level = |n: u8| match n {
+                0 => 0,
+                n => 55 + n * 40,
+            };
+            let index = index - 16;
+            (level(index / 36), level(index / 6 % 6), level(index % 6))
+        }
+        _ => {
+            let grey = 8 + (index - 232) * 10;
+            (grey, grey, grey)
+        }
+    }
+}
+
+/// Map a colour to the nearest colour in the palette supported by the terminal.
+/// Colours are left as they are with `ColorDepth::TrueColor` or `ColorDowngrade::Never`.
+pub(crate) fn downgrade(color: Color, depth: ColorDepth, strategy: ColorDowngrade) -> Color {
+    let palette = match (depth, strategy) {
+        (ColorDepth::TrueColor, _) | (_, ColorDowngrade::Never) => return color,
+        // The system colours are left out, as they are often changed by the colour scheme
+        (ColorDepth::Ansi256, _) => 16..=255,
+        (ColorDepth::Ansi16, _) => 0..=15,
+    };
+
+    let rgb = match color {
+        Color::Rgb(r, g, b) => (r, g, b),
+        Color::AnsiVal(index) if index > 15 && depth == ColorDepth::Ansi16 => palette_rgb(index),
+        color => return color,
+    };
+
+    let index = palette
+        .min_by_key(|index| distance(strategy, rgb, palette_rgb(*index)))
+        .expect("the palette is never empty");
+
+    Color::AnsiVal(index)
+}
+
+fn distance(strategy: ColorDowngrade, lhs: (u8, u8, u8), rhs: (u8, u8, u8)) -> u32 {
+    let r = lhs.0 as i32 - rhs.0 as i32;
+    let g = lhs.1 as i32 - rhs.1 as i32;
+    let b = lhs.2 as i32 - rhs.2 as i32;
+
+    match strategy {
+        ColorDowngrade::Perceptual => {
+            // "redmean": weigh the channels by how sensitive the eye is to them,
+            // depending on the amount of red
+            let mean = (lhs.0 as i32 + rhs.0 as i32) / 2;
+            ((((512 + mean) * r * r) >> 8) + 4 * g * g + (((767 - mean) * b * b) >> 8)) as u32
+        }
+        ColorDowngrade::Nearest | ColorDowngrade::Never => (r * r + g * g + b * b) as u32,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn escape_sequences() {
+        let mut output = vec![];
+        move_to(&mut output, 0, 2).unwrap();
+        foreground(&mut output, Color::LightRed).unwrap();
+        background(&mut output, Color::Rgb(1, 2, 3)).unwrap();
+        foreground(&mut output, Color::Reset).unwrap();
+        sgr(&mut output, BOLD).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output, "\x1b[3;1H\x1b[38;5;9m\x1b[48;2;1;2;3m\x1b[39m\x1b[1m");
+    }
+
+    #[test]
+    fn title() {
+        let mut output = vec![];
+        set_title(&mut output, "build\x1b\x07 50%").unwrap();
+        assert_eq!(output, b"\x1b]2;build 50%\x07");
+    }
+
+    #[test]
+    fn copy_to_clipboard() {
+        let mut output = vec![];
+        copy(&mut output, "hello\nworld").unwrap();
+        assert_eq!(output, b"\x1b]52;c;aGVsbG8Kd29ybGQ=\x07");
+    }
+
+    #[test]
+    fn downgrade_colors() {
+        let orange = Color::Rgb(0xff, 0x87, 0x00);
+        assert_eq!(
+            downgrade(orange, ColorDepth::TrueColor, ColorDowngrade::Nearest),
+            orange
+        );
+        assert_eq!(downgrade(orange, ColorDepth::Ansi256, ColorDowngrade::Never), orange);
+        assert_eq!(
+            downgrade(orange, ColorDepth::Ansi256, ColorDowngrade::Nearest),
+            Color::AnsiVal(208)
+        );
+        assert_eq!(
+            downgrade(orange, ColorDepth::Ansi16, ColorDowngrade::Nearest),
+            Color::AnsiVal(3)
+        );
+
+        let grey = Color::Rgb(0x30, 0x30, 0x30);
+        assert_eq!(
+            downgrade(grey, ColorDepth::Ansi256, ColorDowngrade::Perceptual),
+            Color::AnsiVal(236)
+        );
+
+        // Palette colours outside of the 16 system colours
+        assert_eq!(
+            downgrade(Color::AnsiVal(196), ColorDepth::Ansi16, ColorDowngrade::Nearest),
+            Color::AnsiVal(9)
+        );
+        assert_eq!(
+            downgrade(Color::AnsiVal(196), ColorDepth::Ansi256, ColorDowngrade::Nearest),
+            Color::AnsiVal(196)
+        );
+        assert_eq!(
+            downgrade(Color::Red, ColorDepth::Ansi16, ColorDowngrade::Nearest),
+            Color::Red
+        );
+        assert_eq!(
+            downgrade(Color::Reset, ColorDepth::Ansi16, ColorDowngrade::Nearest),
+            Color::Reset
+        );
+    }
+}
@@ -0,0 +1,618 @@
+use std::io::{Stdout, Write};
+#[cfg(unix)]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(unix)]
+use std::sync::Arc;
+use std::time::Duration;
+
+use anathema_geometry::Size;
+use anathema_store::tree::{Node, TreeValues};
+use anathema_widgets::components::events::{ClickCounter, Event, KeyCode};
+use anathema_widgets::debug::Overlay;
+use anathema_widgets::{AttributeStorage, Element, WidgetKind, WidgetRenderer};
+use crossterm::terminal::{size, window_size};
+
+use super::events::Events;
+use super::panic_hook::{self, Modes};
+#[cfg(unix)]
+use super::wake::Wake;
+use super::{half_block_event, ColorDowngrade, Screen};
+use crate::{Capabilities, EventSource, Renderer, Waker};
+
+#[cfg(unix)]
+const PROBE_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Backend builder for a tui backend.
+pub struct TuiBackendBuilder {
+    output: Stdout,
+    quit_on_ctrl_c: bool,
+
+    hide_cursor: bool,
+    enable_raw_mode: bool,
+    enable_alt_screen: bool,
+    enable_mouse: bool,
+    enable_kitty_keyboard: bool,
+    enable_bracketed_paste: bool,
+    enable_focus_reporting: bool,
+    enable_suspend: bool,
+    enable_raw_events: bool,
+    enable_ime: bool,
+    panic_hook: bool,
+    probe_capabilities: bool,
+    synchronized_output: bool,
+    track_damage: bool,
+    color_downgrade: ColorDowngrade,
+    click_interval: Duration,
+    half_blocks: bool,
+}
+
+impl TuiBackendBuilder {
+    /// Enable an alternative screen.
+    /// When using this with stdout it means the output will not persist
+    /// once the program exits.
+    pub fn enable_alt_screen(mut self) -> Self {
+        self.enable_alt_screen = true;
+        self
+    }
+
+    /// Enable mouse support.
+    pub fn enable_mouse(mut self) -> Self {
+        self.enable_mouse = true;
+        self
+    }
+
+    /// Enable bracketed paste.
+    /// Pasted text is delivered as a single [`Event::Paste`]
+    /// instead of one key press per character.
+    pub fn enable_bracketed_paste(mut self) -> Self {
+        self.enable_bracketed_paste = true;
+        self
+    }
+
+    /// Report the terminal gaining and losing focus,
+    /// as [`Event::FocusGained`] and [`Event::FocusLost`].
+    pub fn enable_focus_reporting(mut self) -> Self {
+        self.enable_focus_reporting = true;
+        self
+    }
+
+    /// Enable the kitty keyboard protocol, if the terminal supports it.
+    ///
+    /// This reports key repeat and release events, as well as modifiers
+    /// that are otherwise ambiguous (e.g `ctrl+i` and `tab`).
+    /// Terminals without support for the protocol only report key presses.
+    pub fn enable_kitty_keyboard(mut self) -> Self {
+        self.enable_kitty_keyboard = true;
+        self
+    }
+
+    /// Suspend the process on Ctrl+z, or when the process receives `SIGTSTP`.
+    ///
+    /// The terminal is restored before the process is suspended,
+    /// and set up again once the process continues, followed by an [`Event::Resume`].
+    ///
+    /// This is only supported on unix.
+    pub fn enable_suspend(mut self) -> Self {
+        self.enable_suspend = true;
+        self
+    }
+
+    /// Forward the events anathema doesn't model as [`Event::Raw`],
+    /// instead of dropping them.
+    pub fn enable_raw_events(mut self) -> Self {
+        self.enable_raw_events = true;
+        self
+    }
+
+    /// Report text committed by an input method (e.g for CJK input) as a single
+    /// [`ImeEvent::Commit`](anathema_widgets::components::events::ImeEvent::Commit),
+    /// instead of one key press per character.
+    ///
+    /// The terminal draws the text being composed at the terminal cursor,
+    /// so there are no preedit events.
+    /// Key presses of non-ASCII characters are reported as committed text while this is enabled.
+    pub fn enable_ime(mut self) -> Self {
+        self.enable_ime = true;
+        self
+    }
+
+    /// Install a panic hook that restores the terminal before the panic is printed.
+    ///
+    /// Without the hook the panic is printed while the terminal is still in raw mode
+    /// (and possibly on the alternate screen, where it's lost once the terminal is restored).
+    ///
+    /// The previous panic hook is called once the terminal is restored,
+    /// followed by a backtrace unless `RUST_BACKTRACE` is set, as the default hook prints one then.
+    ///
+    /// The hook is only installed once, no matter how many backends are built,
+    /// and the previous hook is put back when the last of the backends is dropped.
+    /// It disables the terminal modes enabled by any of the backends relying on it.
+    pub fn install_panic_hook(mut self) -> Self {
+        self.panic_hook = true;
+        self
+    }
+
+    /// The maximum time between two mouse button presses
+    /// for them to count as a double click.
+    /// The default is 500ms.
+    pub fn click_interval(mut self, interval: Duration) -> Self {
+        self.click_interval = interval;
+        self
+    }
+
+    /// When raw mode is enabled, every key press is sent to the terminal.
+    /// If raw mode is not enabled, the return key has to be pressed to
+    /// send characters to the terminal.
+    pub fn enable_raw_mode(mut self) -> Self {
+        self.enable_raw_mode = true;
+        self
+    }
+
+    /// Hide the text cursor.
+    pub fn hide_cursor(mut self) -> Self {
+        self.hide_cursor = true;
+        self
+    }
+
+    /// Query the terminal for its capabilities when the runtime starts,
+    /// rather than only guessing them from environment variables.
+    ///
+    /// This waits up to 100ms for the terminal to reply,
+    /// and is only supported on unix.
+    pub fn probe_capabilities(mut self) -> Self {
+        self.probe_capabilities = true;
+        self
+    }
+
+    /// Don't wrap the frames in synchronized updates.
+    ///
+    /// By default every frame is drawn as a synchronized update (mode 2026)
+    /// if the terminal supports it, so large repaints don't tear.
+    pub fn disable_synchronized_output(mut self) -> Self {
+        self.synchronized_output = false;
+        self
+    }
+
+    /// Compare the entire screen with the previous frame on every render.
+    ///
+    /// By default only the regions of the widgets that changed are compared,
+    /// which requires everything on the screen to be painted by widgets.
+    pub fn disable_damage_tracking(mut self) -> Self {
+        self.track_damage = false;
+        self
+    }
+
+    /// How 24 bit colours are mapped to the palette,
+    /// when the terminal only supports 256 or 16 colours.
+    /// The default is [`ColorDowngrade::Nearest`].
+    pub fn color_downgrade(mut self, strategy: ColorDowngrade) -> Self {
+        self.color_downgrade = strategy;
+        self
+    }
+
+    /// Draw two rows of cells per row of the terminal using half block characters,
+    /// doubling the vertical resolution.
+    ///
+    /// Every cell is drawn as a single colour: the background of a blank cell
+    /// and the foreground of anything else, so this is meant for canvases
+    /// and images rather than text.
+    /// The size of the screen (and the mouse positions) are in cells, not terminal rows.
+    pub fn half_blocks(mut self) -> Self {
+        self.half_blocks = true;
+        self
+    }
+
+    /// Consume self and create the tui backend.
+    pub fn finish(self) -> Result<TuiBackend, std::io::Error> {
+        // The screen is drawn with escape sequences,
+        // which have to be enabled on Windows
+        #[cfg(windows)]
+        if !crossterm::ansi_support::supports_ansi() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "the terminal does not support virtual terminal sequences",
+            ));
+        }
+
+        let size = size()?;
+        let capabilities = Capabilities::from_env();
+        let mut screen = Screen::new(size);
+        screen.set_color_depth(capabilities.colors, self.color_downgrade);
+        screen.set_synchronized_output(self.synchronized_output && capabilities.synchronized_output);
+        screen.set_track_damage(self.track_damage);
+        screen.set_half_blocks(self.half_blocks);
+
+        let panic_hook = self.panic_hook.then(|| {
+            let modes = Modes {
+                bracketed_paste: self.enable_bracketed_paste,
+                focus_reporting: self.enable_focus_reporting,
+                kitty_keyboard: self.enable_kitty_keyboard,
+            };
+            panic_hook::install(modes, restore_on_panic);
+            modes
+        });
+
+        #[cfg(unix)]
+        let suspend_signal = match self.enable_suspend {
+            true => {
+                let signal = Arc::new(AtomicBool::new(false));
+                signal_hook::flag::register(signal_hook::consts::SIGTSTP, signal.clone())?;
+                Some(signal)
+            }
+            false => None,
+        };
+
+        let mut events = Events::new(self.click_interval);
+        events.forward_raw_events(self.enable_raw_events);
+        events.report_ime_commits(self.enable_ime);
+
+        // Signals wake the events, as they are handled between events
+        #[cfg(unix)]
+        {
+            let mut signals = vec![signal_hook::consts::SIGWINCH];
+            if self.enable_suspend {
+                signals.push(signal_hook::consts::SIGTSTP);
+            }
+            if let Some(wake) = Wake::stdin(&signals)? {
+                events.set_wake(wake);
+            }
+        }
+
+        let backend = TuiBackend {
+            quit_on_ctrl_c: self.quit_on_ctrl_c,
+            screen,
+            output: self.output,
+            events,
+
+            hide_cursor: self.hide_cursor,
+            enable_raw_mode: self.enable_raw_mode,
+            enable_alt_screen: self.enable_alt_screen,
+            enable_mouse: self.enable_mouse,
+            enable_kitty_keyboard: self.enable_kitty_keyboard,
+            enable_bracketed_paste: self.enable_bracketed_paste,
+            enable_focus_reporting: self.enable_focus_reporting,
+            enable_suspend: self.enable_suspend,
+            probe_capabilities: self.probe_capabilities,
+            synchronized_output: self.synchronized_output,
+            half_blocks: self.half_blocks,
+            capabilities,
+            kitty_keyboard: false,
+            title: None,
+            cells_written: 0,
+            panic_hook,
+            #[cfg(unix)]
+            suspend_signal,
+        };
+
+        Ok(backend)
+    }
+}
+
+/// Terminal backend
+pub struct TuiBackend {
+    /// Stop the runtime if Ctrl+c was pressed.
+    pub quit_on_ctrl_c: bool,
+    screen: Screen,
+    output: Stdout,
+    events: Events,
+
+    // Settings
+    hide_cursor: bool,
+    enable_raw_mode: bool,
+    enable_alt_screen: bool,
+    enable_mouse: bool,
+    enable_kitty_keyboard: bool,
+    enable_bracketed_paste: bool,
+    enable_focus_reporting: bool,
+    enable_suspend: bool,
+    probe_capabilities: bool,
+    synchronized_output: bool,
+    half_blocks: bool,
+
+    capabilities: Capabilities,
+    // The keyboard protocol is only enabled if it's supported
+    kitty_keyboard: bool,
+    // The title set by the application.
+    // The original title is restored along with the terminal
+    title: Option<String>,
+    // The number of cells written by the last render
+    cells_written: usize,
+    // The modes restored by the panic hook, if the backend relies on it.
+    // The hook is removed once no backend does
+    panic_hook: Option<Modes>,
+    // Set when the process receives `SIGTSTP`
+    #[cfg(unix)]
+    suspend_signal: Option<Arc<AtomicBool>>,
+}
+
+impl TuiBackend {
+    /// Create a new instance of the tui backend.
+    pub fn builder() -> TuiBackendBuilder {
+        let output = std::io::stdout();
+
+        TuiBackendBuilder {
+            output,
+            quit_on_ctrl_c: true,
+
+            hide_cursor: false,
+            enable_raw_mode: false,
+            enable_alt_screen: false,
+            enable_mouse: false,
+            enable_kitty_keyboard: false,
+            enable_bracketed_paste: false,
+            enable_focus_reporting: false,
+            enable_suspend: false,
+            enable_raw_events: false,
+            enable_ime: false,
+            panic_hook: false,
+            probe_capabilities: false,
+            synchronized_output: true,
+            track_damage: true,
+            color_downgrade: ColorDowngrade::Nearest,
+            click_interval: ClickCounter::DEFAULT_INTERVAL,
+            half_blocks: false,
+        }
+    }
+
+    /// Disable raw mode.
+    pub fn disable_raw_mode(self) -> Self {
+        let _ = Screen::disable_raw_mode();
+        self
+    }
+
+    // Restore the terminal to the state it was in before `finalize`
+    fn restore(&mut self) {
+        if self.enable_bracketed_paste {
+            let _ = Screen::disable_bracketed_paste(&mut self.output);
+        }
+        if self.enable_focus_reporting {
+            let _ = Screen::disable_focus_reporting(&mut self.output);
+        }
+        if self.kitty_keyboard {
+            let _ = Screen::disable_kitty_keyboard(&mut self.output);
+        }
+        if self.title.is_some() {
+            let _ = Screen::restore_title(&mut self.output);
+        }
+        let _ = self.screen.restore(&mut self.output);
+    }
+
+    // Query the terminal, once raw mode is enabled
+    fn probe(&mut self) {
+        #[cfg(unix)]
+        if let Ok(replies) = crate::capabilities::query(&mut self.output, PROBE_TIMEOUT) {
+            self.capabilities.apply_replies(&replies);
+        }
+
+        if self.capabilities.cell_size.is_none() {
+            self.capabilities.cell_size = window_size()
+                .ok()
+                .filter(|size| size.width > 0 && size.columns > 0 && size.rows > 0)
+                .map(|size| Size::new((size.width / size.columns) as usize, (size.height / size.rows) as usize));
+        }
+
+        self.screen
+            .set_synchronized_output(self.synchronized_output && self.capabilities.synchronized_output);
+    }
+
+    // Returns true if the event (or a signal) should suspend the process
+    fn should_suspend(&self, event: Option<&Event>) -> bool {
+        if !self.enable_suspend {
+            return false;
+        }
+
+        #[cfg(unix)]
+        if let Some(signal) = &self.suspend_signal {
+            if signal.swap(false, Ordering::Relaxed) {
+                return true;
+            }
+        }
+
+        matches!(
+            event,
+            Some(Event::Key(key)) if key.ctrl && key.code == KeyCode::Char('z') && !key.is_release()
+        )
+    }
+
+    // Restore the terminal and stop the process.
+    // Once the process continues the terminal is set up again
+    // and everything is redrawn.
+    #[cfg(unix)]
+    fn suspend(&mut self) -> Event {
+        self.restore();
+        let _ = self.output.flush();
+
+        // This blocks until the process receives `SIGCONT`
+        let _ = signal_hook::low_level::emulate_default_handler(signal_hook::consts::SIGTSTP);
+
+        self.finalize();
+        self.screen.resize(self.screen.size());
+        Event::Resume
+    }
+
+    #[cfg(not(unix))]
+    fn suspend(&mut self) -> Event {
+        Event::Noop
+    }
+
+    // Suspend the process, or translate the event for half block mode
+    fn received(&mut self, event: Event) -> Event {
+        match self.should_suspend(Some(&event)) {
+            true => self.suspend(),
+            false if self.half_blocks => half_block_event(event),
+            false => event,
+        }
+    }
+}
+
+impl EventSource for TuiBackend {
+    fn next_event(&mut self, timeout: Duration) -> Option<Event> {
+        if self.should_suspend(None) {
+            return Some(self.suspend());
+        }
+
+        let event = self.events.poll(timeout)?;
+        Some(self.received(event))
+    }
+
+    fn waker(&self) -> Option<Waker> {
+        self.events.waker()
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl crate::AsyncEventSource for TuiBackend {
+    async fn wait_event(&mut self) -> Option<Event> {
+        if self.should_suspend(None) {
+            return Some(self.suspend());
+        }
+
+        let event = self.events.next().await?;
+        Some(self.received(event))
+    }
+}
+
+impl Renderer for TuiBackend {
+    fn size(&self) -> Size {
+        self.screen.size()
+    }
+
+    fn resize(&mut self, new_size: Size) {
+        self.screen.resize(new_size);
+    }
+
+    fn paint<'bp>(
+        &mut self,
+        element: &mut Element<'bp>,
+        children: &[Node],
+        values: &mut TreeValues<WidgetKind<'bp>>,
+        attribute_storage: &AttributeStorage<'bp>,
+        ignore_floats: bool,
+    ) {
+        anathema_widgets::paint::paint(
+            &mut self.screen,
+            element,
+            children,
+            values,
+            attribute_storage,
+            ignore_floats,
+        );
+        // TODO: decide if we need `paint` to return a Result or not
+    }
+
+    fn render(&mut self) {
+        self.cells_written = self.screen.render(&mut self.output).unwrap_or(0);
+    }
+
+    fn cells_written(&self) -> usize {
+        self.cells_written
+    }
+
+    fn clear(&mut self) {
+        self.screen.erase();
+    }
+
+    fn overlay(&mut self, overlay: &dyn Overlay) {
+        overlay.paint(&mut self.screen);
+    }
+
+    fn finalize(&mut self) {
+        if self.hide_cursor {
+            // This is to fix an issue with Windows cmd.exe
+            let _ = Screen::show_cursor(&mut self.output);
+            let _ = Screen::hide_cursor(&mut self.output);
+        }
+
+        if self.enable_raw_mode {
+            let _ = Screen::enable_raw_mode();
+        }
+
+        // This only happens once, and not after the process is resumed
+        if std::mem::take(&mut self.probe_capabilities) {
+            if !self.enable_raw_mode {
+                let _ = Screen::enable_raw_mode();
+            }
+            self.probe();
+            if !self.enable_raw_mode {
+                let _ = Screen::disable_raw_mode();
+            }
+        }
+
+        if self.enable_alt_screen {
+            let _ = Screen::enter_alt_screen(&mut self.output);
+        }
+
+        if self.enable_mouse {
+            let _ = Screen::enable_mouse(&mut self.output);
+        }
+
+        if self.enable_bracketed_paste {
+            let _ = Screen::enable_bracketed_paste(&mut self.output);
+        }
+
+        if self.enable_focus_reporting {
+            let _ = Screen::enable_focus_reporting(&mut self.output);
+        }
+
+        if self.enable_kitty_keyboard {
+            self.kitty_keyboard = Screen::enable_kitty_keyboard(&mut self.output).unwrap_or(false);
+        }
+        self.events.report_key_release(self.kitty_keyboard);
+
+        // The title is restored when the process is suspended
+        if let Some(title) = &self.title {
+            let _ = Screen::set_title(&mut self.output, title, true);
+        }
+
+        let _ = self.output.flush();
+    }
+
+    fn set_title(&mut self, title: &str) {
+        let save = self.title.is_none();
+        let _ = Screen::set_title(&mut self.output, title, save);
+        let _ = self.output.flush();
+        self.title = Some(title.into());
+    }
+
+    fn bell(&mut self) {
+        let _ = Screen::bell(&mut self.output);
+        let _ = self.output.flush();
+    }
+
+    fn copy(&mut self, text: &str) {
+        let _ = Screen::copy(&mut self.output, text);
+        let _ = self.output.flush();
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            kitty_keyboard: self.kitty_keyboard,
+            ..self.capabilities
+        }
+    }
+}
+
+// Restore the terminal before the panic is printed
+fn restore_on_panic(modes: Modes) {
+    let mut output = std::io::stdout();
+    if modes.bracketed_paste {
+        let _ = Screen::disable_bracketed_paste(&mut output);
+    }
+    if modes.focus_reporting {
+        let _ = Screen::disable_focus_reporting(&mut output);
+    }
+    if modes.kitty_keyboard {
+        let _ = Screen::disable_kitty_keyboard(&mut output);
+    }
+    let _ = Screen::restore_terminal(&mut output);
+    let _ = output.flush();
+}
+
+impl Drop for TuiBackend {
+    fn drop(&mut self) {
+        self.restore();
+        if let Some(modes) = self.panic_hook {
+            panic_hook::uninstall(modes);
+        }
+    }
+}
@@ -1,12 +1,14 @@
 #![deny(missing_docs)]
 use std::io::{Result, Write};
+use std::ops::Range;
 
-use anathema_geometry::Size;
-use crossterm::style::Print;
-use crossterm::{cursor, QueueableCommand};
+use anathema_geometry::{Region, Size};
+use anathema_state::Color;
+use anathema_widgets::glyphs::{self, GlyphIndex, GlyphMap};
 use unicode_width::UnicodeWidthChar;
 
-use super::{LocalPos, Style};
+use super::{ansi, ColorDowngrade, LocalPos, Style};
+use crate::ColorDepth;
 
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub(crate) struct Cell {
@@ -53,6 +55,9 @@ pub(crate) enum CellState {
     Empty,
     /// Occupied by a certain character
     Occupied(char),
+    /// Occupied by a glyph made out of more than one char,
+    /// stored in the glyph map of the buffer
+    Cluster(GlyphIndex),
     /// A continuation means this cell is part of another cell
     /// representing a value that spans more than two chars, e.g 💖
     Continuation,
@@ -70,6 +75,7 @@ pub(crate) enum CellState {
 pub struct Buffer {
     size: Size,
     pub(crate) inner: Box<[Cell]>,
+    glyphs: GlyphMap,
 }
 
 impl Buffer {
@@ -79,6 +85,7 @@ impl Buffer {
         Self {
             inner: vec![Cell::empty(); size.width * size.height].into_boxed_slice(),
             size,
+            glyphs: GlyphMap::new(),
         }
     }
 
@@ -88,6 +95,7 @@ impl Buffer {
         Self {
             inner: vec![Cell::reset(); size.width * size.height].into_boxed_slice(),
             size,
+            glyphs: GlyphMap::new(),
         }
     }
 
@@ -99,6 +107,7 @@ impl Buffer {
     /// Resize the buffer, truncating what doesn't fit but keeps what does.
     pub fn resize(&mut self, size: Size) {
         let mut new_buf = Buffer::new(size);
+        new_buf.glyphs = std::mem::take(&mut self.glyphs);
         for (y, line) in self.cell_lines().enumerate() {
             if y >= size.height {
                 break;
@@ -128,6 +137,34 @@ impl Buffer {
         self.put(cell, pos);
     }
 
+    /// Put a glyph (grapheme cluster) with a style at a given position.
+    pub fn put_glyph(&mut self, glyph: &str, pos: LocalPos) {
+        if let Some(c) = glyphs::single_char(glyph) {
+            return self.put_char(c, pos);
+        }
+
+        let style = match self.get(pos) {
+            Some((_, style)) => *style,
+            None => Style::new(),
+        };
+        let index = self.glyphs.insert(glyph);
+        let cell = Cell {
+            style,
+            state: CellState::Cluster(index),
+        };
+        self.put(cell, pos);
+    }
+
+    pub(crate) fn glyphs(&self) -> &GlyphMap {
+        &self.glyphs
+    }
+
+    /// Remove all the glyphs that are no longer referenced by a cell.
+    /// This is only done once every cell is empty.
+    pub(crate) fn clear_glyphs(&mut self) {
+        self.glyphs.clear();
+    }
+
     /// Update the attributes at a given cell.
     /// If there is no character at that cell, then write an empty space into it
     pub fn update_cell(&mut self, style: Style, pos: LocalPos) {
@@ -146,6 +183,10 @@ impl Buffer {
             cell.style.bg = bg;
         }
 
+        if let color @ Some(_) = style.underline_color {
+            cell.style.underline_color = color;
+        }
+
         cell.style.attributes |= style.attributes;
 
         if let CellState::Empty = cell.state {
@@ -154,6 +195,7 @@ impl Buffer {
     }
 
     /// Get a reference to a `char` and [`Style`] at a given position inside the buffer.
+    /// Cells occupied by a glyph made out of more than one char return `None`.
     pub fn get(&self, pos: LocalPos) -> Option<(&char, &Style)> {
         let index = self.index(pos);
         let cell = self.inner.get(index)?;
@@ -175,36 +217,113 @@ impl Buffer {
 
     /// Empty a cell at a given position
     pub fn empty(&mut self, pos: LocalPos) {
+        self.blank_orphan(pos, CellState::Empty);
         let index = self.index(pos);
         self.inner[index] = Cell::empty();
     }
 
-    /// An iterator over all the rows in the buffer
+    /// An iterator over all the rows in the buffer.
+    /// Glyphs made out of more than one char are represented by their first char.
     pub fn rows(&self) -> impl Iterator<Item = impl Iterator<Item = Option<(char, Style)>> + '_> {
         self.cell_lines().map(|chunk| {
             chunk.iter().map(|cell| match cell.state {
                 CellState::Occupied(c) => Some((c, cell.style)),
+                CellState::Cluster(index) => Some((self.glyph(index).chars().next()?, cell.style)),
                 _ => None,
             })
         })
     }
 
+    /// The rows of glyphs and styles as they are drawn:
+    /// empty cells are spaces and continuations of wide characters are skipped.
+    pub(crate) fn text_rows(&self) -> impl Iterator<Item = impl Iterator<Item = (Glyph<'_>, Style)> + '_> {
+        self.cell_lines().map(|chunk| {
+            chunk.iter().filter_map(|cell| match cell.state {
+                CellState::Empty => Some((Glyph::Char(' '), cell.style)),
+                CellState::Occupied(c) => Some((Glyph::Char(c), cell.style)),
+                CellState::Cluster(index) => Some((Glyph::Cluster(self.glyph(index)), cell.style)),
+                CellState::Continuation => None,
+            })
+        })
+    }
+
+    fn glyph(&self, index: GlyphIndex) -> &str {
+        self.glyphs
+            .get(index)
+            .expect("glyphs are only removed once the cells are empty")
+    }
+
+    // True if the cell at the index is the same in both buffers
+    fn same_cell(&self, other: &Buffer, index: usize) -> bool {
+        let (cell, other_cell) = (&self.inner[index], &other.inner[index]);
+        match (cell.state, other_cell.state) {
+            // The buffers have separate glyph maps, so the indices can't be compared
+            (CellState::Cluster(a), CellState::Cluster(b)) => {
+                cell.style == other_cell.style && self.glyph(a) == other.glyph(b)
+            }
+            _ => cell == other_cell,
+        }
+    }
+
     fn index(&self, pos: LocalPos) -> usize {
         pos.y as usize * self.size.width + pos.x as usize
     }
 
+    fn state_width(&self, state: CellState) -> usize {
+        match state {
+            CellState::Occupied(c) => c.width().unwrap_or(0),
+            CellState::Cluster(index) => glyphs::width(self.glyph(index)),
+            CellState::Empty | CellState::Continuation => 1,
+        }
+    }
+
+    // Blank the other half of a wide glyph that is partially overwritten
+    // by a cell with the new state.
+    // The blank keeps the style of the orphaned half so the background stays intact.
+    fn blank_orphan(&mut self, pos: LocalPos, new_state: CellState) {
+        let index = self.index(pos);
+        let current = self.inner[index].state;
+
+        let orphan = match current {
+            // Overwriting the second half leaves the first half on its own
+            CellState::Continuation if new_state != CellState::Continuation && pos.x > 0 => index - 1,
+            // Overwriting the first half leaves the second half on its own,
+            // unless the new glyph is wide as well, in which case
+            // its own continuation takes the place of the old one.
+            _ if self.state_width(current) >= 2 && self.state_width(new_state) < 2 => index + 1,
+            _ => return,
+        };
+
+        let is_orphan = match self.inner.get(orphan).map(|cell| cell.state) {
+            Some(CellState::Continuation) => true,
+            Some(state) => self.state_width(state) >= 2,
+            None => false,
+        };
+
+        if is_orphan {
+            self.inner[orphan].state = CellState::Occupied(' ');
+        }
+    }
+
     fn put(&mut self, mut cell: Cell, pos: LocalPos) {
         let index = self.index(pos);
+        let mut width = self.state_width(cell.state);
 
-        if let CellState::Occupied(c) = cell.state {
-            // If this is a unicode char that is wider than one cell,
-            // add a continuation cell if it fits, this way if we overwrite it
-            // we can set the continuation cell to `Empty`.
-            if pos.x < self.size.width as u16 {
-                if let Some(2..) = c.width() {
-                    self.put(Cell::continuation(cell.style), LocalPos::new(pos.x + 1, pos.y));
-                }
-            }
+        // A glyph that is wider than one cell but doesn't fit
+        // at the end of the line is replaced by a blank,
+        // as only half of it could be drawn.
+        if width >= 2 && pos.x as usize + 1 >= self.size.width {
+            cell.state = CellState::Occupied(' ');
+            width = 1;
+        }
+
+        self.blank_orphan(pos, cell.state);
+
+        // If this is a glyph that is wider than one cell,
+        // add a continuation cell, this way if we overwrite either half
+        // we can blank the other half.
+        if width >= 2 {
+            self.put(Cell::continuation(cell.style), LocalPos::new(pos.x + 1, pos.y));
         }
 
         let current = &mut self.inner[index];
@@ -223,11 +342,41 @@ impl Buffer {
                 if let Some(col) = cell.style.bg {
                     current.style.bg = Some(col);
                 }
+
+                if let Some(col) = cell.style.underline_color {
+                    current.style.underline_color = Some(col);
+                }
             }
             _ => *current = cell,
         }
     }
 
+    /// Composite two rows of the buffer into every row of the output buffer,
+    /// as half blocks with the colour of the top row in the upper half and
+    /// the colour of the bottom row in the lower half.
+    /// Both buffers have the same width.
+    pub(crate) fn composite_half_blocks(&self, output: &mut Buffer) {
+        let width = output.size.width;
+        let pixel_at = |x: usize, y: usize| self.inner.get(y * width + x).and_then(pixel);
+
+        for (y, row) in output.inner.chunks_mut(width).enumerate() {
+            for (x, cell) in row.iter_mut().enumerate() {
+                let (top, bottom) = (pixel_at(x, y * 2), pixel_at(x, y * 2 + 1));
+                let (c, fg, bg) = match (top, bottom) {
+                    (Some(top), Some(bottom)) if top != bottom => ('▀', top, bottom),
+                    (Some(top), None) => ('▀', top, Color::Reset),
+                    (None, Some(bottom)) => ('▄', bottom, Color::Reset),
+                    (color, _) => (' ', Color::Reset, color.unwrap_or(Color::Reset)),
+                };
+
+                let mut style = Style::reset();
+                style.set_fg(fg);
+                style.set_bg(bg);
+                *cell = Cell::new(c, style);
+            }
+        }
+    }
+
     fn cell_lines(&self) -> impl Iterator<Item = &[Cell]> {
         self.inner.chunks(self.size.width)
     }
@@ -240,6 +389,11 @@ impl Buffer {
         self.inner[index]
     }
 
+    /// Get the character at a given position.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cell isn't occupied
     pub fn char_at(&self, x: usize, y: usize) -> char {
         let cell = self.cell_at(x, y);
         match cell.state {
@@ -249,33 +403,111 @@ impl Buffer {
     }
 }
 
+// The colour of a cell drawn as a single pixel:
+// the background of a blank cell and the foreground of anything else
+fn pixel(cell: &Cell) -> Option<Color> {
+    let color = match cell.state {
+        CellState::Empty | CellState::Occupied(' ') => cell.style.bg,
+        _ => cell.style.fg,
+    };
+    color.filter(|color| *color != Color::Reset)
+}
+
+/// A char, or a glyph made out of more than one char
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum Glyph<'a> {
+    Char(char),
+    Cluster(&'a str),
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub(crate) enum Change {
     Remove,
     Insert(char),
+    // A glyph in the glyph map of the new buffer
+    InsertCluster(GlyphIndex),
 }
 
 impl Change {
-    fn width(self) -> usize {
+    fn width(self, glyph_map: &GlyphMap) -> usize {
         match self {
             Change::Remove => 1,
             Change::Insert(c) => c.width().unwrap_or(1),
+            Change::InsertCluster(index) => glyph_map.get(index).map(glyphs::width).unwrap_or(1),
         }
     }
 }
 
 pub(crate) fn diff(old: &Buffer, new: &Buffer, changes: &mut Vec<(LocalPos, Option<Style>, Change)>) -> Result<()> {
-    let mut previous_style = None;
+    let size = new.size();
+    let rows = (0..size.height as u16).map(|y| (y, 0..size.width as u16));
+    diff_rows(old, new, rows, changes)
+}
+
+/// Same as [`diff`] but only the cells inside the regions are compared.
+pub(crate) fn diff_regions(
+    old: &Buffer,
+    new: &Buffer,
+    regions: &[Region],
+    changes: &mut Vec<(LocalPos, Option<Style>, Change)>,
+) -> Result<()> {
+    let size = new.size();
+    let mut rows: Vec<Option<Range<u16>>> = vec![None; size.height];
+
+    for region in regions {
+        // A wide character starting right before the region might overlap it
+        let from_x = region.from.x.saturating_sub(1).clamp(0, size.width as i32) as u16;
+        let to_x = region.to.x.clamp(0, size.width as i32) as u16;
+        let from_y = region.from.y.clamp(0, size.height as i32) as usize;
+        let to_y = region.to.y.clamp(0, size.height as i32) as usize;
+
+        if from_x >= to_x {
+            continue;
+        }
+
+        for row in &mut rows[from_y..to_y] {
+            *row = match row.take() {
+                Some(span) => Some(span.start.min(from_x)..span.end.max(to_x)),
+                None => Some(from_x..to_x),
+            };
+        }
+    }
+
+    let rows = rows
+        .into_iter()
+        .enumerate()
+        .filter_map(|(y, span)| Some((y as u16, span?)));
+    diff_rows(old, new, rows, changes)
+}
 
-    for (y, (old_line, new_line)) in old.cell_lines().zip(new.cell_lines()).enumerate() {
-        for (x, (old_cell, new_cell)) in old_line.iter().zip(new_line).enumerate() {
-            let x = x as u16;
-            let y = y as u16;
+fn diff_rows(
+    old: &Buffer,
+    new: &Buffer,
+    rows: impl Iterator<Item = (u16, Range<u16>)>,
+    changes: &mut Vec<(LocalPos, Option<Style>, Change)>,
+) -> Result<()> {
+    let mut previous_style = None;
+    let width = new.size().width;
 
-            if old_cell == new_cell {
+    for (y, span) in rows {
+        let line = y as usize * width;
+        for x in span {
+            let index = line + x as usize;
+            if new.same_cell(old, index) {
                 continue;
             }
 
+            let (x, index) = match new.inner[index].state {
+                // The second half of a wide glyph changed but the first half didn't
+                // (or it's outside of the span), so the first half is emitted
+                // again to draw over the cell.
+                CellState::Continuation if x > 0 && !emitted(changes, LocalPos::new(x - 1, y)) => (x - 1, index - 1),
+                CellState::Continuation => continue,
+                _ => (x, index),
+            };
+
+            let new_cell = &new.inner[index];
+
             let style = match previous_style {
                 Some(previous) => (previous != new_cell.style).then_some(new_cell.style),
                 None => Some(new_cell.style),
@@ -285,8 +517,10 @@ pub(crate) fn diff(old: &Buffer, new: &Buffer, changes: &mut Vec<(LocalPos, Opti
 
             let change = match new_cell.state {
                 CellState::Empty => Change::Remove,
+                // A continuation without a wide glyph before it
                 CellState::Continuation => continue,
                 CellState::Occupied(c) => Change::Insert(c),
+                CellState::Cluster(index) => Change::InsertCluster(index),
             };
 
             changes.push((LocalPos::new(x, y), style, change));
@@ -296,10 +530,23 @@ pub(crate) fn diff(old: &Buffer, new: &Buffer, changes: &mut Vec<(LocalPos, Opti
     Ok(())
 }
 
+fn emitted(changes: &[(LocalPos, Option<Style>, Change)], pos: LocalPos) -> bool {
+    changes.last().is_some_and(|(last, _, _)| *last == pos)
+}
+
 // -----------------------------------------------------------------------------
 //     - Draw changes -
 // -----------------------------------------------------------------------------
-pub(crate) fn draw_changes(mut w: impl Write, changes: &Vec<(LocalPos, Option<Style>, Change)>) -> Result<()> {
+// Colours are mapped to the palette supported by the terminal as they are written
+//
+// The glyph map is the map of the buffer the changes were made from.
+pub(crate) fn draw_changes(
+    mut w: impl Write,
+    changes: &Vec<(LocalPos, Option<Style>, Change)>,
+    glyph_map: &GlyphMap,
+    depth: ColorDepth,
+    downgrade: ColorDowngrade,
+) -> Result<()> {
     let mut last_y = None;
     let mut next_cell_x = None;
 
@@ -311,29 +558,71 @@ pub(crate) fn draw_changes(mut w: impl Write, changes: &Vec<(LocalPos, Option<St
         };
 
         if should_move {
-            w.queue(cursor::MoveTo(screen_pos.x, screen_pos.y))?;
+            ansi::move_to(&mut w, screen_pos.x, screen_pos.y)?;
         }
 
         last_y = Some(screen_pos.y);
-        next_cell_x = Some(screen_pos.x + change.width() as u16);
+        next_cell_x = Some(screen_pos.x + change.width(glyph_map) as u16);
 
         // Apply style
         if let Some(style) = style {
-            style.write(&mut w)?;
+            style.downgrade(depth, downgrade).write(&mut w)?;
         }
 
         // Draw changes
         match change {
-            Change::Insert(c) => w.queue(Print(c))?,
-            Change::Remove => w.queue(Print(' '))?,
+            Change::Insert(c) => write!(w, "{c}")?,
+            Change::InsertCluster(index) => write!(w, "{}", glyph_map.get(*index).unwrap_or(" "))?,
+            Change::Remove => write!(w, " ")?,
         };
     }
 
     Ok(())
 }
 
+// -----------------------------------------------------------------------------
+//     - Draw lines -
+// -----------------------------------------------------------------------------
+// Draw the entire buffer as lines of text, without moving the cursor.
+// Trailing spaces are trimmed unless the lines are styled.
+pub(crate) fn draw_lines(mut w: impl Write, buffer: &Buffer, styled: bool) -> Result<()> {
+    let mut line = String::new();
+
+    for cells in buffer.text_rows() {
+        let mut last_style = None;
+        line.clear();
+
+        for (glyph, style) in cells {
+            if styled && last_style != Some(style) {
+                let mut sequence = vec![];
+                style.write(&mut sequence)?;
+                line.push_str(&String::from_utf8_lossy(&sequence));
+                last_style = Some(style);
+            }
+
+            match glyph {
+                Glyph::Char(c) => line.push(c),
+                Glyph::Cluster(glyph) => line.push_str(glyph),
+            }
+        }
+
+        match styled {
+            true => {
+                w.write_all(line.as_bytes())?;
+                ansi::sgr(&mut w, ansi::RESET)?;
+            }
+            false => w.write_all(line.trim_end().as_bytes())?,
+        }
+        writeln!(w)?;
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod test {
+    use anathema_geometry::Pos;
+
     use super::*;
 
     #[test]
@@ -363,6 +652,186 @@ mod test {
         assert_eq!(Change::Insert('N'), change_3);
     }
 
+    #[test]
+    fn changes_in_regions() {
+        let mut changes = vec![];
+
+        let old_buffer = Buffer::new((5u16, 3));
+        let mut new_buffer = Buffer::new((5u16, 3));
+        new_buffer.inner[1] = Cell::new('A', Style::reset());
+        new_buffer.inner[12] = Cell::new('B', Style::reset());
+
+        // Only the second row is damaged
+        let regions = [Region::new(Pos::new(0, 1), Pos::new(5, 2))];
+        diff_regions(&old_buffer, &new_buffer, &regions, &mut changes).unwrap();
+        assert!(changes.is_empty());
+
+        // Overlapping regions only report the changes once
+        let regions = [
+            Region::new(Pos::new(0, 2), Pos::new(3, 3)),
+            Region::new(Pos::new(1, 0), Pos::new(4, 3)),
+        ];
+        diff_regions(&old_buffer, &new_buffer, &regions, &mut changes).unwrap();
+        let positions = changes.iter().map(|(pos, _, _)| *pos).collect::<Vec<_>>();
+        assert_eq!(positions, vec![LocalPos::new(1, 0), LocalPos::new(2, 2)]);
+    }
+
+    #[test]
+    fn glyph_changes() {
+        let family = "👨\u{200d}👩\u{200d}👧";
+        let mut changes = vec![];
+
+        let old_buffer = Buffer::new((3u16, 1));
+        let mut new_buffer = Buffer::new((3u16, 1));
+        new_buffer.put_glyph(family, LocalPos::new(0, 0));
+        new_buffer.put_glyph("x", LocalPos::new(2, 0));
+        assert_eq!(new_buffer.inner[1].state, CellState::Continuation);
+
+        diff(&old_buffer, &new_buffer, &mut changes).unwrap();
+        assert_eq!(changes.len(), 2);
+
+        let mut output = vec![];
+        draw_changes(
+            &mut output,
+            &changes,
+            new_buffer.glyphs(),
+            ColorDepth::TrueColor,
+            ColorDowngrade::Nearest,
+        )
+        .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.ends_with(&format!("{family}x")));
+
+        // The same glyph stored at a different index is not a change
+        let mut old_buffer = Buffer::new((3u16, 1));
+        old_buffer.put_glyph("e\u{301}", LocalPos::new(2, 0));
+        old_buffer.put_glyph(family, LocalPos::new(0, 0));
+        changes.clear();
+        diff(&old_buffer, &new_buffer, &mut changes).unwrap();
+        assert_eq!(changes.len(), 1);
+    }
+
+    #[test]
+    fn overwrite_wide_glyphs() {
+        let mut buffer = Buffer::new((4u16, 1));
+
+        // Overwriting the first half blanks the second half
+        buffer.put_char('中', LocalPos::new(0, 0));
+        buffer.put_char('a', LocalPos::new(0, 0));
+        assert_eq!(buffer.char_at(0, 0), 'a');
+        assert_eq!(buffer.char_at(1, 0), ' ');
+
+        // Overwriting the second half blanks the first half
+        buffer.put_char('中', LocalPos::new(0, 0));
+        buffer.put_char('b', LocalPos::new(1, 0));
+        assert_eq!(buffer.char_at(0, 0), ' ');
+        assert_eq!(buffer.char_at(1, 0), 'b');
+
+        // A wide glyph overlapping another one by a cell
+        buffer.put_char('中', LocalPos::new(0, 0));
+        buffer.put_char('文', LocalPos::new(1, 0));
+        assert_eq!(buffer.char_at(0, 0), ' ');
+        assert_eq!(buffer.char_at(1, 0), '文');
+        assert_eq!(buffer.cell_at(2, 0).state, CellState::Continuation);
+
+        // Replacing a wide glyph with another one keeps the continuation
+        buffer.put_glyph("👩\u{200d}💻", LocalPos::new(1, 0));
+        assert!(matches!(buffer.cell_at(1, 0).state, CellState::Cluster(_)));
+        assert_eq!(buffer.cell_at(2, 0).state, CellState::Continuation);
+
+        // Emptying either half blanks the other half
+        buffer.empty(LocalPos::new(2, 0));
+        assert_eq!(buffer.char_at(1, 0), ' ');
+        buffer.put_char('🐇', LocalPos::new(2, 0));
+        buffer.empty(LocalPos::new(2, 0));
+        assert_eq!(buffer.char_at(3, 0), ' ');
+    }
+
+    #[test]
+    fn wide_glyph_at_the_edge() {
+        let mut buffer = Buffer::new((3u16, 2));
+        buffer.put_char('中', LocalPos::new(2, 0));
+        buffer.put_glyph("❤\u{fe0f}", LocalPos::new(2, 1));
+
+        // Neither glyph fits, and nothing spills over onto the next line
+        assert_eq!(buffer.char_at(2, 0), ' ');
+        assert_eq!(buffer.cell_at(0, 1).state, CellState::Empty);
+        assert_eq!(buffer.char_at(2, 1), ' ');
+    }
+
+    #[test]
+    fn overwritten_wide_glyph_changes() {
+        let mut changes = vec![];
+
+        let mut old_buffer = Buffer::new((3u16, 1));
+        old_buffer.put_char('中', LocalPos::new(0, 0));
+        let mut new_buffer = old_buffer.clone();
+        new_buffer.put_char('a', LocalPos::new(1, 0));
+
+        // The orphaned first half is cleared on the terminal
+        diff(&old_buffer, &new_buffer, &mut changes).unwrap();
+        let changes = changes
+            .iter()
+            .map(|(pos, _, change)| (*pos, *change))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            changes,
+            vec![
+                (LocalPos::new(0, 0), Change::Insert(' ')),
+                (LocalPos::new(1, 0), Change::Insert('a')),
+            ]
+        );
+
+        // A changed continuation re-emits the unchanged glyph before it
+        let mut changes = vec![];
+        let mut new_buffer = Buffer::new((3u16, 1));
+        new_buffer.put_char('🐇', LocalPos::new(1, 0));
+        let mut old_buffer = new_buffer.clone();
+        old_buffer.inner[2] = Cell::new('x', Style::reset());
+        let regions = [Region::new(Pos::new(2, 0), Pos::new(3, 1))];
+        diff_regions(&old_buffer, &new_buffer, &regions, &mut changes).unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].0, LocalPos::new(1, 0));
+        assert_eq!(changes[0].2, Change::Insert('🐇'));
+    }
+
+    #[test]
+    fn half_blocks() {
+        let mut red = Style::reset();
+        red.set_bg(Color::Red);
+        let mut blue = Style::reset();
+        blue.set_fg(Color::Blue);
+
+        let mut buffer = Buffer::new((4u16, 2));
+        // Different colours in both halves
+        buffer.inner[0] = Cell::new(' ', red);
+        buffer.inner[4] = Cell::new('█', blue);
+        // The top half only
+        buffer.inner[1] = Cell::new('x', blue);
+        // The bottom half only
+        buffer.inner[6] = Cell::new(' ', red);
+        // The same colour in both halves
+        buffer.inner[3] = Cell::new(' ', red);
+        buffer.inner[7] = Cell::new(' ', red);
+
+        let mut output = Buffer::new((4u16, 1));
+        buffer.composite_half_blocks(&mut output);
+
+        let cells = output
+            .inner
+            .iter()
+            .map(|cell| (cell.state, cell.style.fg, cell.style.bg));
+        assert_eq!(
+            cells.collect::<Vec<_>>(),
+            vec![
+                (CellState::Occupied('▀'), Some(Color::Red), Some(Color::Blue)),
+                (CellState::Occupied('▀'), Some(Color::Blue), Some(Color::Reset)),
+                (CellState::Occupied('▄'), Some(Color::Red), Some(Color::Reset)),
+                (CellState::Occupied(' '), Some(Color::Reset), Some(Color::Red)),
+            ]
+        );
+    }
+
     #[test]
     fn resize() {
         let mut buffer = Buffer::new((2u16, 2));
@@ -375,4 +844,15 @@ mod test {
         assert_eq!(buffer.inner[0], Cell::new('1', Style::reset()));
         assert_eq!(buffer.inner[1], Cell::new('3', Style::reset()));
     }
+
+    #[test]
+    fn lines() {
+        let mut buffer = Buffer::new((3u16, 2));
+        buffer.inner[0] = Cell::new('a', Style::reset());
+        buffer.inner[4] = Cell::new('b', Style::reset());
+
+        let mut output = vec![];
+        draw_lines(&mut output, &buffer, false).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "a\n b\n");
+    }
 }
@@ -1,103 +1,309 @@
-use std::time::Duration;
+#[cfg(feature = "tokio")]
+use std::pin::Pin;
+use std::time::{Duration, Instant};
 
-use anathema_widgets::components::events::{Event, KeyCode, KeyEvent, KeyState, MouseButton, MouseEvent, MouseState};
+use anathema_widgets::components::events::{
+    ClickCounter, Event, ImeEvent, KeyCode, KeyEvent, KeyState, MouseButton, MouseEvent, MouseState, RawEvent,
+};
+#[cfg(feature = "tokio")]
+use crossterm::event::EventStream;
 use crossterm::event::{read, Event as CTEvent, KeyEventKind};
 pub use crossterm::event::{
     KeyCode as CTKeyCode, KeyEvent as CTKeyEvent, KeyEventState, KeyModifiers, MouseButton as CTMouseButton,
     MouseEvent as CTMouseEvent, MouseEventKind,
 };
+#[cfg(feature = "tokio")]
+use futures_core::Stream;
+
+#[cfg(unix)]
+use super::wake::Wake;
+use crate::EventSource;
+#[cfg(unix)]
+use crate::Waker;
 
 /// Event listener
-pub struct Events;
+pub struct Events {
+    clicks: ClickCounter,
+    key_release: bool,
+    raw_events: bool,
+    ime: bool,
+    // An event read while collecting committed text, returned next
+    pending: Option<CTEvent>,
+    // Created the first time events are awaited
+    #[cfg(feature = "tokio")]
+    stream: Option<EventStream>,
+    #[cfg(unix)]
+    wake: Option<Wake>,
+}
 
 impl Events {
+    /// Create a new event listener.
+    /// Mouse buttons pressed within the `click_interval` count as
+    /// double (or triple) clicks.
+    pub fn new(click_interval: Duration) -> Self {
+        Self {
+            clicks: ClickCounter::new(click_interval),
+            key_release: false,
+            raw_events: false,
+            ime: false,
+            pending: None,
+            #[cfg(feature = "tokio")]
+            stream: None,
+            #[cfg(unix)]
+            wake: None,
+        }
+    }
+
+    // Wait for input along with wake ups, so `poll` can be woken
+    #[cfg(unix)]
+    pub(crate) fn set_wake(&mut self, wake: Wake) {
+        self.wake = Some(wake);
+    }
+
+    /// Forward media keys, modifier keys and events that aren't translated
+    /// as [`Event::Raw`], with the crossterm event as the value.
+    pub fn forward_raw_events(&mut self, forward: bool) {
+        self.raw_events = forward;
+    }
+
+    /// Report key release events.
+    ///
+    /// Windows reports every key release, while other platforms only
+    /// report them with the kitty keyboard protocol, so releases are
+    /// ignored unless the protocol is enabled.
+    pub fn report_key_release(&mut self, report: bool) {
+        self.key_release = report;
+    }
+
+    /// Report text committed by an input method as [`ImeEvent::Commit`].
+    ///
+    /// The terminal draws the composition itself and sends the committed text
+    /// as if it was typed, one key press per character.
+    /// With this enabled a key press of a non-ASCII character without modifiers,
+    /// along with the characters the terminal sent at the same time, is reported as committed text.
+    pub fn report_ime_commits(&mut self, report: bool) {
+        self.ime = report;
+    }
+
     /// Poll events given a duration.
     /// If no event is available within the duration
     /// the function will return `None`.
-    pub fn poll(&self, timeout: Duration) -> Option<Event> {
+    pub fn poll(&mut self, timeout: Duration) -> Option<Event> {
+        if let Some(event) = self.pending.take() {
+            return self.translate(event, cfg!(windows));
+        }
+
+        // Input crossterm has already read is not seen by `Wake::wait`
+        #[cfg(unix)]
+        let timeout = match &mut self.wake {
+            Some(wake) if !crossterm::event::poll(Duration::ZERO).ok()? => match wake.wait(timeout) {
+                true => Duration::ZERO,
+                false => return None,
+            },
+            _ => timeout,
+        };
+
         match crossterm::event::poll(timeout).ok()? {
             true => {
-                let event = read().map(Into::into).ok()?;
-
-                let event = match event {
-                    CTEvent::Paste(_) => Event::Noop,
-                    CTEvent::FocusGained => Event::Focus,
-                    CTEvent::FocusLost => Event::Blur,
-                    CTEvent::Key(CTKeyEvent {
-                        kind: KeyEventKind::Press,
-                        code: CTKeyCode::Char('c'),
-                        modifiers: KeyModifiers::CONTROL,
-                        ..
-                    }) => Event::Stop,
-                    CTEvent::Key(key_ev) => Event::Key(key_code_to_key_code(key_ev)),
-                    CTEvent::Mouse(mouse_ev) => Event::Mouse(mouse_to_mouse(mouse_ev)),
-                    CTEvent::Resize(width, height) => Event::Resize(width, height),
-                };
-
-                Some(event)
+                let event = read().ok()?;
+                self.translate(event, cfg!(windows))
             }
             false => None,
         }
     }
+
+    /// Wait for the next event.
+    /// Returns `None` if reading the terminal input fails.
+    #[cfg(feature = "tokio")]
+    pub async fn next(&mut self) -> Option<Event> {
+        if let Some(event) = self
+            .pending
+            .take()
+            .and_then(|event| self.translate(event, cfg!(windows)))
+        {
+            return Some(event);
+        }
+
+        loop {
+            let stream = self.stream.get_or_insert_with(EventStream::new);
+            let event = std::future::poll_fn(|cx| Pin::new(&mut *stream).poll_next(cx))
+                .await?
+                .ok()?;
+            if let Some(event) = self.translate(event, cfg!(windows)) {
+                return Some(event);
+            }
+        }
+    }
+
+    fn translate(&mut self, event: CTEvent, windows: bool) -> Option<Event> {
+        let event = match event {
+            CTEvent::Paste(text) => Event::Paste(text),
+            CTEvent::FocusGained => Event::FocusGained,
+            CTEvent::FocusLost => Event::FocusLost,
+            CTEvent::Key(key_ev) if key_ev.kind == KeyEventKind::Release && !self.key_release => return None,
+            CTEvent::Key(CTKeyEvent {
+                kind: KeyEventKind::Press,
+                code: CTKeyCode::Char('c'),
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            }) => Event::Stop,
+            CTEvent::Key(key_ev)
+                if self.raw_events && matches!(key_ev.code, CTKeyCode::Media(_) | CTKeyCode::Modifier(_)) =>
+            {
+                Event::Raw(RawEvent::new(key_ev))
+            }
+            CTEvent::Key(key_ev) if self.ime && committed_char(&key_ev).is_some_and(|c| !c.is_ascii()) => {
+                Event::Ime(ImeEvent::Commit(self.committed_text(key_ev)))
+            }
+            CTEvent::Key(mut key_ev) => {
+                if windows {
+                    strip_alt_gr(&mut key_ev);
+                }
+                Event::Key(key_code_to_key_code(key_ev))
+            }
+            CTEvent::Mouse(mouse_ev) => Event::Mouse(self.clicks.count(mouse_to_mouse(mouse_ev), Instant::now())),
+            // The Windows console reports the size of the screen buffer,
+            // which can be a lot taller than the window.
+            CTEvent::Resize(width, height) if windows => {
+                let (width, height) = crossterm::terminal::size().unwrap_or((width, height));
+                Event::Resize(width, height)
+            }
+            CTEvent::Resize(width, height) => Event::Resize(width, height),
+            // Kinds of events added in later versions of crossterm
+            #[allow(unreachable_patterns)]
+            event if self.raw_events => Event::Raw(RawEvent::new(event)),
+            #[allow(unreachable_patterns)]
+            _ => return None,
+        };
+
+        Some(event)
+    }
+
+    // Collect the characters that are already available,
+    // as the terminal writes all of the committed text at once.
+    // This doesn't wait for more input, so a character typed afterwards is a separate event.
+    fn committed_text(&mut self, first: CTKeyEvent) -> String {
+        let mut text = String::new();
+        text.extend(committed_char(&first));
+
+        while let Ok(true) = crossterm::event::poll(Duration::ZERO) {
+            let Ok(event) = read() else { break };
+            match &event {
+                CTEvent::Key(key_ev) => match committed_char(key_ev) {
+                    Some(c) => text.push(c),
+                    None => {
+                        self.pending = Some(event);
+                        break;
+                    }
+                },
+                _ => {
+                    self.pending = Some(event);
+                    break;
+                }
+            }
+        }
+
+        text
+    }
 }
 
-fn key_code_to_key_code(from: CTKeyEvent) -> KeyEvent {
-    KeyEvent {
-        ctrl: from.modifiers.contains(KeyModifiers::CONTROL),
-        code: match from.code {
-            CTKeyCode::Backspace => KeyCode::Backspace,
-            CTKeyCode::Enter => KeyCode::Enter,
-            CTKeyCode::Left => KeyCode::Left,
-            CTKeyCode::Right => KeyCode::Right,
-            CTKeyCode::Up => KeyCode::Up,
-            CTKeyCode::Down => KeyCode::Down,
-            CTKeyCode::Home => KeyCode::Home,
-            CTKeyCode::End => KeyCode::End,
-            CTKeyCode::PageUp => KeyCode::PageUp,
-            CTKeyCode::PageDown => KeyCode::PageDown,
-            CTKeyCode::Tab => KeyCode::Tab,
-            CTKeyCode::BackTab => KeyCode::BackTab,
-            CTKeyCode::Delete => KeyCode::Delete,
-            CTKeyCode::Insert => KeyCode::Insert,
-            CTKeyCode::F(key) => KeyCode::F(key),
-            CTKeyCode::Char(c) => KeyCode::Char(c),
-            CTKeyCode::Null => KeyCode::Null,
-            CTKeyCode::Esc => KeyCode::Esc,
-            CTKeyCode::CapsLock => KeyCode::CapsLock,
-            CTKeyCode::ScrollLock => KeyCode::ScrollLock,
-            CTKeyCode::NumLock => KeyCode::NumLock,
-            CTKeyCode::PrintScreen => KeyCode::PrintScreen,
-            CTKeyCode::Pause => KeyCode::Pause,
-            CTKeyCode::Menu => KeyCode::Menu,
-            CTKeyCode::KeypadBegin => KeyCode::KeypadBegin,
-            CTKeyCode::Media(_) => KeyCode::Null,
-            CTKeyCode::Modifier(_) => KeyCode::Null,
-        },
-        state: match from.kind {
-            KeyEventKind::Press => KeyState::Press,
-            KeyEventKind::Repeat => KeyState::Repeat,
-            KeyEventKind::Release => KeyState::Release,
-        },
+// A character typed without modifiers (other than shift)
+fn committed_char(key: &CTKeyEvent) -> Option<char> {
+    match key.code {
+        CTKeyCode::Char(c) if key.kind == KeyEventKind::Press && (key.modifiers - KeyModifiers::SHIFT).is_empty() => {
+            Some(c)
+        }
+        _ => None,
     }
 }
 
-fn mouse_to_mouse(from: CTMouseEvent) -> MouseEvent {
-    MouseEvent {
-        x: from.column,
-        y: from.row,
-        state: match from.kind {
-            MouseEventKind::Down(button) => MouseState::Down(button_to_button(button)),
-            MouseEventKind::Up(button) => MouseState::Up(button_to_button(button)),
-            MouseEventKind::Drag(button) => MouseState::Drag(button_to_button(button)),
-            MouseEventKind::Moved => MouseState::Move,
-            MouseEventKind::ScrollDown => MouseState::ScrollDown,
-            MouseEventKind::ScrollUp => MouseState::ScrollUp,
-            MouseEventKind::ScrollLeft => MouseState::ScrollLeft,
-            MouseEventKind::ScrollRight => MouseState::ScrollRight,
-        },
+impl EventSource for Events {
+    fn next_event(&mut self, timeout: Duration) -> Option<Event> {
+        self.poll(timeout)
+    }
+
+    #[cfg(unix)]
+    fn waker(&self) -> Option<Waker> {
+        self.wake.as_ref().map(Wake::waker)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl crate::AsyncEventSource for Events {
+    async fn wait_event(&mut self) -> Option<Event> {
+        self.next().await
+    }
+}
+
+// Windows reports AltGr as Ctrl+Alt, so characters typed with AltGr
+// (e.g `@` or `{` on many european layouts) would have both modifiers.
+fn strip_alt_gr(key: &mut CTKeyEvent) {
+    let alt_gr = KeyModifiers::CONTROL | KeyModifiers::ALT;
+    if let CTKeyCode::Char(c) = key.code {
+        if key.modifiers.contains(alt_gr) && !c.is_ascii_alphanumeric() {
+            key.modifiers.remove(alt_gr);
+        }
     }
 }
 
+fn key_code_to_key_code(from: CTKeyEvent) -> KeyEvent {
+    let code = match from.code {
+        CTKeyCode::Backspace => KeyCode::Backspace,
+        CTKeyCode::Enter => KeyCode::Enter,
+        CTKeyCode::Left => KeyCode::Left,
+        CTKeyCode::Right => KeyCode::Right,
+        CTKeyCode::Up => KeyCode::Up,
+        CTKeyCode::Down => KeyCode::Down,
+        CTKeyCode::Home => KeyCode::Home,
+        CTKeyCode::End => KeyCode::End,
+        CTKeyCode::PageUp => KeyCode::PageUp,
+        CTKeyCode::PageDown => KeyCode::PageDown,
+        CTKeyCode::Tab => KeyCode::Tab,
+        CTKeyCode::BackTab => KeyCode::BackTab,
+        CTKeyCode::Delete => KeyCode::Delete,
+        CTKeyCode::Insert => KeyCode::Insert,
+        CTKeyCode::F(key) => KeyCode::F(key),
+        CTKeyCode::Char(c) => KeyCode::Char(c),
+        CTKeyCode::Null => KeyCode::Null,
+        CTKeyCode::Esc => KeyCode::Esc,
+        CTKeyCode::CapsLock => KeyCode::CapsLock,
+        CTKeyCode::ScrollLock => KeyCode::ScrollLock,
+        CTKeyCode::NumLock => KeyCode::NumLock,
+        CTKeyCode::PrintScreen => KeyCode::PrintScreen,
+        CTKeyCode::Pause => KeyCode::Pause,
+        CTKeyCode::Menu => KeyCode::Menu,
+        CTKeyCode::KeypadBegin => KeyCode::KeypadBegin,
+        CTKeyCode::Media(_) => KeyCode::Null,
+        CTKeyCode::Modifier(_) => KeyCode::Null,
+    };
+    let state = match from.kind {
+        KeyEventKind::Press => KeyState::Press,
+        KeyEventKind::Repeat => KeyState::Repeat,
+        KeyEventKind::Release => KeyState::Release,
+    };
+
+    let mut key = KeyEvent::new(code, state);
+    key.ctrl = from.modifiers.contains(KeyModifiers::CONTROL);
+    key.alt = from.modifiers.contains(KeyModifiers::ALT);
+    key.shift = from.modifiers.contains(KeyModifiers::SHIFT);
+    key.super_key = from.modifiers.contains(KeyModifiers::SUPER);
+    key
+}
+
+fn mouse_to_mouse(from: CTMouseEvent) -> MouseEvent {
+    let state = match from.kind {
+        MouseEventKind::Down(button) => MouseState::Down(button_to_button(button)),
+        MouseEventKind::Up(button) => MouseState::Up(button_to_button(button)),
+        MouseEventKind::Drag(button) => MouseState::Drag(button_to_button(button)),
+        MouseEventKind::Moved => MouseState::Move,
+        MouseEventKind::ScrollDown => MouseState::ScrollDown,
+        MouseEventKind::ScrollUp => MouseState::ScrollUp,
+        MouseEventKind::ScrollLeft => MouseState::ScrollLeft,
+        MouseEventKind::ScrollRight => MouseState::ScrollRight,
+    };
+    MouseEvent::new(from.column, from.row, state)
+}
+
 fn button_to_button(button: CTMouseButton) -> MouseButton {
     match button {
         CTMouseButton::Left => MouseButton::Left,
@@ -105,3 +311,88 @@ fn button_to_button(button: CTMouseButton) -> MouseButton {
         CTMouseButton::Right => MouseButton::Right,
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crossterm::event::MediaKeyCode;
+
+    use super::*;
+
+    #[test]
+    fn key_release_with_modifiers() {
+        let mut event = CTKeyEvent::new(CTKeyCode::Char('a'), KeyModifiers::ALT | KeyModifiers::SUPER);
+        event.kind = KeyEventKind::Release;
+
+        let key = key_code_to_key_code(event);
+        assert!(key.is_release());
+        assert!(key.alt && key.super_key);
+        assert!(!key.ctrl && !key.shift);
+    }
+
+    #[test]
+    fn ignore_key_release() {
+        let mut events = Events::new(ClickCounter::DEFAULT_INTERVAL);
+        let mut event = CTKeyEvent::new(CTKeyCode::Char('a'), KeyModifiers::NONE);
+        event.kind = KeyEventKind::Release;
+
+        assert!(events.translate(CTEvent::Key(event), true).is_none());
+
+        events.report_key_release(true);
+        let Some(Event::Key(key)) = events.translate(CTEvent::Key(event), true) else { panic!() };
+        assert!(key.is_release());
+    }
+
+    #[test]
+    fn raw_media_keys() {
+        let mut events = Events::new(ClickCounter::DEFAULT_INTERVAL);
+        let event = CTKeyEvent::new(CTKeyCode::Media(MediaKeyCode::Play), KeyModifiers::NONE);
+
+        let Some(Event::Key(key)) = events.translate(CTEvent::Key(event), false) else { panic!() };
+        assert_eq!(key.code, KeyCode::Null);
+
+        events.forward_raw_events(true);
+        let Some(Event::Raw(raw)) = events.translate(CTEvent::Key(event), false) else { panic!() };
+        assert_eq!(raw.get::<CTKeyEvent>(), Some(&event));
+    }
+
+    #[test]
+    fn ime_commits() {
+        let mut events = Events::new(ClickCounter::DEFAULT_INTERVAL);
+        let key = |c| CTEvent::Key(CTKeyEvent::new(CTKeyCode::Char(c), KeyModifiers::NONE));
+
+        let Some(Event::Key(key_ev)) = events.translate(key('你'), false) else { panic!() };
+        assert_eq!(key_ev.code, KeyCode::Char('你'));
+
+        events.report_ime_commits(true);
+        let Some(Event::Ime(ime)) = events.translate(key('你'), false) else { panic!() };
+        assert_eq!(ime, ImeEvent::Commit("你".into()));
+
+        // ASCII is still typed
+        let Some(Event::Key(key_ev)) = events.translate(key('a'), false) else { panic!() };
+        assert_eq!(key_ev.code, KeyCode::Char('a'));
+
+        // and so are shortcuts
+        let ctrl = CTEvent::Key(CTKeyEvent::new(CTKeyCode::Char('é'), KeyModifiers::CONTROL));
+        assert!(matches!(events.translate(ctrl, false), Some(Event::Key(_))));
+    }
+
+    #[test]
+    fn alt_gr_on_windows() {
+        let mut events = Events::new(ClickCounter::DEFAULT_INTERVAL);
+        let alt_gr = KeyModifiers::CONTROL | KeyModifiers::ALT;
+
+        let event = CTEvent::Key(CTKeyEvent::new(CTKeyCode::Char('@'), alt_gr));
+        let Some(Event::Key(key)) = events.translate(event.clone(), true) else { panic!() };
+        assert_eq!(key.code, KeyCode::Char('@'));
+        assert!(!key.ctrl && !key.alt);
+
+        // Only on Windows
+        let Some(Event::Key(key)) = events.translate(event, false) else { panic!() };
+        assert!(key.ctrl && key.alt);
+
+        // Ctrl+Alt+letter is still a shortcut
+        let event = CTEvent::Key(CTKeyEvent::new(CTKeyCode::Char('x'), alt_gr));
+        let Some(Event::Key(key)) = events.translate(event, true) else { panic!() };
+        assert!(key.ctrl && key.alt);
+    }
+}
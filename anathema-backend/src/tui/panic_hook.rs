@@ -0,0 +1,99 @@
+use std::panic::PanicHookInfo;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+// The terminal modes enabled by a backend, that the panic hook has to disable
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct Modes {
+    pub(crate) bracketed_paste: bool,
+    pub(crate) focus_reporting: bool,
+    pub(crate) kitty_keyboard: bool,
+}
+
+impl Modes {
+    const ALL: Self = Self {
+        bracketed_paste: true,
+        focus_reporting: true,
+        kitty_keyboard: true,
+    };
+
+    fn union(self, other: Self) -> Self {
+        Self {
+            bracketed_paste: self.bracketed_paste || other.bracketed_paste,
+            focus_reporting: self.focus_reporting || other.focus_reporting,
+            kitty_keyboard: self.kitty_keyboard || other.kitty_keyboard,
+        }
+    }
+}
+
+type PanicHook = Arc<dyn Fn(&PanicHookInfo<'_>) + Sync + Send + 'static>;
+
+pub(crate) struct Installed {
+    // The hook that was replaced by the hook restoring the terminal
+    prev: PanicHook,
+    // The modes of every backend relying on the hook
+    pub(crate) backends: Vec<Modes>,
+}
+
+impl Installed {
+    fn modes(&self) -> Modes {
+        self.backends.iter().fold(Modes::default(), |modes, m| modes.union(*m))
+    }
+}
+
+// The hook is installed by the first of the backends, and the previous hook
+// is put back when the last of them is dropped.
+static PANIC_HOOK: Mutex<Option<Installed>> = Mutex::new(None);
+
+pub(crate) fn installed() -> MutexGuard<'static, Option<Installed>> {
+    PANIC_HOOK.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+// Call `restore` with the modes of every backend relying on the hook,
+// then call the previous hook
+pub(crate) fn install(modes: Modes, restore: fn(Modes)) {
+    let mut installed = installed();
+    if let Some(installed) = installed.as_mut() {
+        installed.backends.push(modes);
+        return;
+    }
+
+    let prev: PanicHook = Arc::from(std::panic::take_hook());
+    *installed = Some(Installed {
+        prev: prev.clone(),
+        backends: vec![modes],
+    });
+
+    std::panic::set_hook(Box::new(move |info| {
+        // The lock is only held if another thread is installing or removing the hook,
+        // in which case every mode is disabled
+        let modes = match PANIC_HOOK.try_lock() {
+            Ok(installed) => installed.as_ref().map(Installed::modes).unwrap_or_default(),
+            Err(_) => Modes::ALL,
+        };
+        restore(modes);
+
+        prev(info);
+
+        if std::env::var_os("RUST_BACKTRACE").is_none() {
+            eprintln!("{}", std::backtrace::Backtrace::force_capture());
+        }
+    }));
+}
+
+// Put the previous hook back once no backend relies on the hook
+pub(crate) fn uninstall(modes: Modes) {
+    let mut installed = installed();
+    let Some(backends) = installed.as_mut().map(|i| &mut i.backends) else { return };
+    if let Some(index) = backends.iter().position(|m| *m == modes) {
+        backends.remove(index);
+    }
+    // The hook can't be changed while panicking, so it's left in place
+    // if the backend is dropped while unwinding
+    if !backends.is_empty() || std::thread::panicking() {
+        return;
+    }
+
+    let Some(Installed { prev, .. }) = installed.take() else { return };
+    drop(std::panic::take_hook());
+    std::panic::set_hook(Box::new(move |info| prev(info)));
+}
@@ -1,14 +1,23 @@
 use std::io::{Result, Write};
 
-use anathema_geometry::{Pos, Size};
-use anathema_widgets::paint::CellAttributes;
+use anathema_geometry::{Pos, Region, Size};
+#[cfg(any(feature = "crossterm", feature = "termion"))]
+use anathema_widgets::components::events::Event;
+use anathema_widgets::paint::{CellAttributes, CursorShape};
 use anathema_widgets::WidgetRenderer;
-use crossterm::event::EnableMouseCapture;
+#[cfg(feature = "crossterm")]
+use crossterm::event::{
+    DisableBracketedPaste, DisableFocusChange, EnableBracketedPaste, EnableFocusChange, EnableMouseCapture,
+    KeyboardEnhancementFlags, PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+};
+#[cfg(feature = "crossterm")]
 use crossterm::terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen};
+#[cfg(feature = "crossterm")]
 use crossterm::{cursor, ExecutableCommand, QueueableCommand};
 
-use super::buffer::{diff, draw_changes, Buffer, Change};
-use super::{LocalPos, Style};
+use super::buffer::{diff, diff_regions, draw_changes, draw_lines, Buffer, Change};
+use super::{ansi, ColorDowngrade, LocalPos, Style};
+use crate::ColorDepth;
 
 /// The `Screen` is used to draw to some `std::io::Write`able output (generally `stdout`);
 pub struct Screen {
@@ -16,27 +25,23 @@ pub struct Screen {
     pub(crate) new_buffer: Buffer,
     old_buffer: Buffer,
     changes: Vec<(LocalPos, Option<Style>, Change)>,
+    color_depth: ColorDepth,
+    downgrade: ColorDowngrade,
+    synchronized_output: bool,
+    // The cursor requested by the widgets for the next frame,
+    // and the cursor shown by the last frame
+    cursor: Option<(LocalPos, CursorShape)>,
+    last_cursor: Option<(LocalPos, CursorShape)>,
+    // The regions damaged by the widgets since the last render.
+    // `None` if the entire buffer has to be compared
+    damage: Option<Vec<Region>>,
+    track_damage: bool,
+    // The painted buffer composited into half blocks, the size of the terminal.
+    // `None` unless drawing half blocks
+    half_blocks: Option<Buffer>,
 }
 
 impl Screen {
-    /// Hide the cursor
-    pub(super) fn hide_cursor(mut output: impl Write) -> Result<()> {
-        output.queue(cursor::Hide)?;
-        Ok(())
-    }
-
-    /// Show the cursor
-    pub(super) fn show_cursor(mut output: impl Write) -> Result<()> {
-        output.queue(cursor::Show)?;
-        Ok(())
-    }
-
-    /// Enable mouse support
-    pub(super) fn enable_mouse(mut output: impl Write) -> Result<()> {
-        output.queue(EnableMouseCapture)?;
-        Ok(())
-    }
-
     /// Create a new instance of a screen.
     /// The `output` should be a mutable reference to whatever this screen renders to.
     /// The `output` is used initially to move the cursor and hide it.
@@ -47,20 +52,79 @@ impl Screen {
             old_buffer: Buffer::new(size),
             new_buffer: Buffer::new(size),
             changes: vec![],
+            color_depth: ColorDepth::TrueColor,
+            downgrade: ColorDowngrade::Nearest,
+            synchronized_output: false,
+            cursor: None,
+            last_cursor: None,
+            damage: None,
+            track_damage: false,
+            half_blocks: None,
+        }
+    }
+
+    /// Map colours to the palette of a terminal with the given colour depth when rendering.
+    /// The screen writes 24 bit colours by default.
+    #[cfg(any(feature = "crossterm", feature = "termion", test))]
+    pub(crate) fn set_color_depth(&mut self, depth: ColorDepth, downgrade: ColorDowngrade) {
+        self.color_depth = depth;
+        self.downgrade = downgrade;
+    }
+
+    /// Wrap the changes of each render in a synchronized update,
+    /// so the terminal draws them all at once.
+    /// Terminals without support for synchronized output ignore this.
+    #[cfg(any(feature = "crossterm", feature = "termion", test))]
+    pub(crate) fn set_synchronized_output(&mut self, synchronized_output: bool) {
+        self.synchronized_output = synchronized_output;
+    }
+
+    /// Only compare the regions damaged by the widgets with the previous frame when rendering,
+    /// rather than the entire buffer.
+    /// This requires everything drawn to the screen to be painted by widgets.
+    #[cfg(any(feature = "crossterm", feature = "termion", test))]
+    pub(crate) fn set_track_damage(&mut self, track_damage: bool) {
+        self.track_damage = track_damage;
+        self.damage = None;
+    }
+
+    /// Paint to a buffer twice the height of the terminal,
+    /// drawing two rows of cells per row of the terminal with half block characters.
+    /// The size of the screen is the size of the painted buffer.
+    #[cfg(any(feature = "crossterm", feature = "termion", test))]
+    pub(crate) fn set_half_blocks(&mut self, half_blocks: bool) {
+        let size = self.old_buffer.size();
+        self.half_blocks = half_blocks.then(|| Buffer::new(size));
+        match half_blocks {
+            true => self.resize(Size::new(size.width, size.height * 2)),
+            false => self.resize(size),
         }
     }
 
     /// Resize the buffer.
     /// This will empty the underlying buffers so everything will have
     /// to be redrawn.
-    pub(super) fn resize(&mut self, new_size: Size) {
-        self.old_buffer = Buffer::new(new_size);
+    pub(crate) fn resize(&mut self, new_size: Size) {
+        let terminal_size = match self.half_blocks {
+            Some(_) => Size::new(new_size.width, new_size.height.div_ceil(2)),
+            None => new_size,
+        };
+
+        if let Some(half_blocks) = self.half_blocks.as_mut() {
+            *half_blocks = Buffer::new(terminal_size);
+        }
+
+        self.old_buffer = Buffer::new(terminal_size);
         self.new_buffer = Buffer::reset(new_size);
+        self.damage = None;
     }
 
-    /// Erase the entire buffer by writing empty cells
+    /// Erase the entire buffer by writing empty cells.
+    /// This also removes the cursor, so it has to be set again for the next frame.
     pub(crate) fn erase(&mut self) {
+        self.cursor = None;
         self.erase_region(LocalPos::ZERO, self.size());
+        self.new_buffer.clear_glyphs();
     }
 
     /// Erase a specific region.
@@ -83,26 +147,197 @@ impl Screen {
         self.new_buffer.put_char(c, pos);
     }
 
+    /// Same as [`Screen::paint_glyph`] but for a glyph made out of more than one char.
+    pub(crate) fn paint_cluster(&mut self, glyph: &str, pos: LocalPos) {
+        self.new_buffer.put_glyph(glyph, pos);
+    }
+
     pub(crate) fn update_cell(&mut self, style: Style, pos: LocalPos) {
         self.new_buffer.update_cell(style, pos);
     }
 
-    /// Draw the changes to the screen
-    pub(crate) fn render(&mut self, mut output: impl Write) -> Result<()> {
-        diff(&self.old_buffer, &self.new_buffer, &mut self.changes)?;
+    /// Draw the changes to the screen.
+    /// Returns the number of cells written.
+    pub(crate) fn render(&mut self, mut output: impl Write) -> Result<usize> {
+        let damage = match self.track_damage {
+            true => self.damage.replace(vec![]),
+            false => None,
+        };
+
+        // The damaged regions are in the painted buffer,
+        // so the composited half blocks are compared in full
+        let damage = match self.half_blocks.as_mut() {
+            Some(half_blocks) => {
+                self.new_buffer.composite_half_blocks(half_blocks);
+                None
+            }
+            None => damage,
+        };
+
+        let frame = self.half_blocks.as_ref().unwrap_or(&self.new_buffer);
+        match damage {
+            Some(regions) => diff_regions(&self.old_buffer, frame, &regions, &mut self.changes)?,
+            None => diff(&self.old_buffer, frame, &mut self.changes)?,
+        }
+
+        if self.changes.is_empty() && self.cursor == self.last_cursor {
+            return Ok(0);
+        }
 
-        if self.changes.is_empty() {
-            return Ok(());
+        if self.synchronized_output {
+            output.write_all(ansi::BEGIN_SYNCHRONIZED_UPDATE)?;
         }
 
-        draw_changes(&mut output, &self.changes)?;
+        draw_changes(
+            &mut output,
+            &self.changes,
+            frame.glyphs(),
+            self.color_depth,
+            self.downgrade,
+        )?;
+        self.draw_cursor(&mut output)?;
+
+        if self.synchronized_output {
+            output.write_all(ansi::END_SYNCHRONIZED_UPDATE)?;
+        }
 
+        let cells_written = self.changes.len();
         self.changes.clear();
 
         output.flush()?;
 
-        self.old_buffer = self.new_buffer.clone();
+        self.old_buffer = self.half_blocks.as_ref().unwrap_or(&self.new_buffer).clone();
+
+        Ok(cells_written)
+    }
+
+    // Drawing the changes moves the cursor, so it's moved back
+    // even if it's the same as the last frame
+    fn draw_cursor(&mut self, mut output: impl Write) -> Result<()> {
+        match (self.cursor, self.last_cursor) {
+            (Some((pos, shape)), last) => {
+                let y = match self.half_blocks {
+                    Some(_) => pos.y / 2,
+                    None => pos.y,
+                };
+                ansi::move_to(&mut output, pos.x, y)?;
+                if last.map(|(_, shape)| shape) != Some(shape) {
+                    ansi::cursor_shape(&mut output, shape)?;
+                }
+                if last.is_none() {
+                    output.write_all(ansi::SHOW_CURSOR)?;
+                }
+            }
+            (None, Some(_)) => output.write_all(ansi::HIDE_CURSOR)?,
+            (None, None) => {}
+        }
 
+        self.last_cursor = self.cursor;
+        Ok(())
+    }
+
+    /// Draw the entire screen as lines of text, rather than the changes.
+    /// Escape sequences are only written for the styles, if `styled` is true.
+    pub(crate) fn render_lines(&self, output: impl Write, styled: bool) -> Result<()> {
+        draw_lines(output, &self.new_buffer, styled)
+    }
+
+    /// Set the title of the terminal window.
+    /// The current title is saved first if `save` is true, to be restored by [`Screen::restore_title`].
+    #[cfg(any(feature = "crossterm", feature = "termion", feature = "ssh"))]
+    pub(crate) fn set_title(mut output: impl Write, title: &str, save: bool) -> Result<()> {
+        if save {
+            output.write_all(ansi::PUSH_TITLE)?;
+        }
+        ansi::set_title(output, title)
+    }
+
+    /// Restore the title saved by [`Screen::set_title`]
+    #[cfg(any(feature = "crossterm", feature = "termion", feature = "ssh"))]
+    pub(crate) fn restore_title(mut output: impl Write) -> Result<()> {
+        output.write_all(ansi::POP_TITLE)
+    }
+
+    /// Ring the bell
+    #[cfg(any(feature = "crossterm", feature = "termion", feature = "ssh"))]
+    pub(crate) fn bell(mut output: impl Write) -> Result<()> {
+        output.write_all(ansi::BELL)
+    }
+
+    /// Copy the text to the clipboard
+    #[cfg(any(feature = "crossterm", feature = "termion", feature = "ssh"))]
+    pub(crate) fn copy(output: impl Write, text: &str) -> Result<()> {
+        ansi::copy(output, text)
+    }
+
+    /// Hide the cursor
+    pub(crate) fn hide_cursor(mut output: impl Write) -> Result<()> {
+        output.write_all(ansi::HIDE_CURSOR)
+    }
+
+    /// Show the cursor
+    #[cfg(any(feature = "crossterm", feature = "termion"))]
+    pub(crate) fn show_cursor(mut output: impl Write) -> Result<()> {
+        output.write_all(ansi::SHOW_CURSOR)
+    }
+
+    /// Restore the default shape of the cursor
+    #[cfg(any(feature = "crossterm", feature = "termion"))]
+    pub(crate) fn reset_cursor_shape(mut output: impl Write) -> Result<()> {
+        output.write_all(ansi::RESET_CURSOR_SHAPE)
+    }
+}
+
+// Terminal setup, only used by the crossterm backend
+#[cfg(feature = "crossterm")]
+impl Screen {
+    /// Enable mouse support
+    pub(super) fn enable_mouse(mut output: impl Write) -> Result<()> {
+        output.queue(EnableMouseCapture)?;
+        Ok(())
+    }
+
+    /// Enable bracketed paste
+    pub(super) fn enable_bracketed_paste(mut output: impl Write) -> Result<()> {
+        output.queue(EnableBracketedPaste)?;
+        Ok(())
+    }
+
+    /// Disable bracketed paste
+    pub(super) fn disable_bracketed_paste(mut output: impl Write) -> Result<()> {
+        output.execute(DisableBracketedPaste)?;
+        Ok(())
+    }
+
+    /// Enable reporting of the terminal gaining and losing focus
+    pub(super) fn enable_focus_reporting(mut output: impl Write) -> Result<()> {
+        output.queue(EnableFocusChange)?;
+        Ok(())
+    }
+
+    /// Disable reporting of the terminal gaining and losing focus
+    pub(super) fn disable_focus_reporting(mut output: impl Write) -> Result<()> {
+        output.execute(DisableFocusChange)?;
+        Ok(())
+    }
+
+    /// Enable the kitty keyboard protocol, if the terminal supports it.
+    /// Returns `false` if the terminal doesn't support the protocol.
+    pub(super) fn enable_kitty_keyboard(mut output: impl Write) -> Result<bool> {
+        if !crossterm::terminal::supports_keyboard_enhancement()? {
+            return Ok(false);
+        }
+
+        let flags = KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES
+            | KeyboardEnhancementFlags::REPORT_EVENT_TYPES
+            | KeyboardEnhancementFlags::REPORT_ALTERNATE_KEYS;
+        output.queue(PushKeyboardEnhancementFlags(flags))?;
+        Ok(true)
+    }
+
+    /// Disable the kitty keyboard protocol
+    pub(super) fn disable_kitty_keyboard(mut output: impl Write) -> Result<()> {
+        output.execute(PopKeyboardEnhancementFlags)?;
         Ok(())
     }
 
@@ -127,11 +362,17 @@ impl Screen {
 
     /// Restore the terminal by setting the cursor to show, disable raw mode, disable mouse capture
     /// and leave any alternative screens
-    pub fn restore(&mut self, mut output: impl Write) -> Result<()> {
+    pub fn restore(&mut self, output: impl Write) -> Result<()> {
+        Self::restore_terminal(output)
+    }
+
+    // Same as `restore`, for when there is no screen (e.g in a panic hook)
+    pub(super) fn restore_terminal(mut output: impl Write) -> Result<()> {
         disable_raw_mode()?;
         output.execute(LeaveAlternateScreen)?;
-        #[cfg(not(target_os = "windows"))]
-        output.execute(crossterm::event::DisableMouseCapture)?;
+        // This fails on Windows if mouse capture was never enabled
+        let _ = output.execute(crossterm::event::DisableMouseCapture);
+        Self::reset_cursor_shape(&mut output)?;
         output.execute(cursor::Show)?;
         Ok(())
     }
@@ -143,6 +384,11 @@ impl WidgetRenderer for Screen {
         self.paint_glyph(c, screen_pos);
     }
 
+    fn draw_cluster(&mut self, glyph: &str, pos: Pos) {
+        let Ok(screen_pos) = pos.try_into() else { return };
+        self.paint_cluster(glyph, screen_pos);
+    }
+
     fn set_attributes(&mut self, attribs: &dyn CellAttributes, pos: Pos) {
         let Ok(screen_pos) = pos.try_into() else { return };
         let style = Style::from_cell_attribs(attribs);
@@ -152,6 +398,31 @@ impl WidgetRenderer for Screen {
     fn size(&self) -> Size {
         self.new_buffer.size()
     }
+
+    fn set_cursor(&mut self, pos: Pos, shape: CursorShape) {
+        let Ok(screen_pos) = pos.try_into() else { return };
+        self.cursor = Some((screen_pos, shape));
+    }
+
+    fn damage(&mut self, region: Region) {
+        if let Some(damage) = self.damage.as_mut() {
+            damage.push(region);
+        }
+    }
+}
+
+/// The screen is twice the height of the terminal when drawing half blocks,
+/// mouse positions are at the upper half of the cell
+#[cfg(any(feature = "crossterm", feature = "termion"))]
+pub(crate) fn half_block_event(event: Event) -> Event {
+    match event {
+        Event::Resize(width, height) => Event::Resize(width, height.saturating_mul(2)),
+        Event::Mouse(mut mouse) => {
+            mouse.y = mouse.y.saturating_mul(2);
+            Event::Mouse(mouse)
+        }
+        event => event,
+    }
 }
 
 #[cfg(test)]
@@ -177,7 +448,8 @@ mod test {
         let mut render_output = vec![];
         let mut screen = make_screen(Size::new(1, 1));
         screen.paint_glyph('x', LocalPos::ZERO);
-        screen.render(&mut render_output).unwrap();
+        let cells_written = screen.render(&mut render_output).unwrap();
+        assert_eq!(cells_written, 1);
 
         let expected = Cell::new('x', Style::reset());
         let actual = screen.new_buffer.inner[0];
@@ -200,6 +472,109 @@ mod test {
         assert_eq!(Cell::empty(), bottom_right);
     }
 
+    #[test]
+    fn render_half_blocks() {
+        let mut render_output = vec![];
+        let mut screen = Screen::new(Size::new(1, 1));
+        screen.set_half_blocks(true);
+        assert_eq!(screen.size(), Size::new(1, 2));
+
+        let mut style = Style::new();
+        style.set_bg(anathema_state::Color::Red);
+        screen.update_cell(style, LocalPos::ZERO);
+        style.set_bg(anathema_state::Color::Blue);
+        screen.update_cell(style, LocalPos::new(0, 1));
+        screen.set_cursor(Pos::new(0, 1), CursorShape::Block);
+        screen.render(&mut render_output).unwrap();
+
+        let output = String::from_utf8(render_output).unwrap();
+        assert!(output.contains("\x1b[38;5;1m\x1b[48;5;4m"));
+        assert!(output.contains('▀'));
+        // The cursor is in the first row of the terminal
+        assert!(output.contains("\x1b[1;1H"));
+
+        screen.resize(Size::new(2, 4));
+        assert_eq!(screen.old_buffer.size(), Size::new(2, 2));
+    }
+
+    #[test]
+    fn render_downgraded_colors() {
+        let mut render_output = vec![];
+        let mut screen = Screen::new(Size::new(1, 1));
+        screen.set_color_depth(ColorDepth::Ansi256, ColorDowngrade::Nearest);
+        screen.paint_glyph('x', LocalPos::ZERO);
+        let mut style = Style::new();
+        style.set_fg(anathema_state::Color::Rgb(0xff, 0x87, 0x00));
+        screen.update_cell(style, LocalPos::ZERO);
+        screen.render(&mut render_output).unwrap();
+
+        let output = String::from_utf8(render_output).unwrap();
+        assert!(output.contains("\x1b[38;5;208m"));
+    }
+
+    #[test]
+    fn render_synchronized() {
+        let mut render_output = vec![];
+        let mut screen = Screen::new(Size::new(1, 1));
+        screen.set_synchronized_output(true);
+        screen.paint_glyph('x', LocalPos::ZERO);
+        screen.render(&mut render_output).unwrap();
+
+        assert!(render_output.starts_with(ansi::BEGIN_SYNCHRONIZED_UPDATE));
+        assert!(render_output.ends_with(ansi::END_SYNCHRONIZED_UPDATE));
+
+        // Nothing is written without changes
+        render_output.clear();
+        screen.render(&mut render_output).unwrap();
+        assert!(render_output.is_empty());
+    }
+
+    #[test]
+    fn render_cursor() {
+        let mut render_output = vec![];
+        let mut screen = make_screen(Size::new(2, 2));
+        screen.render(&mut render_output).unwrap();
+
+        // The cursor is drawn even if no cells changed
+        render_output.clear();
+        screen.set_cursor(Pos::new(1, 0), CursorShape::Bar);
+        assert_eq!(screen.render(&mut render_output).unwrap(), 0);
+        assert_eq!(render_output, b"\x1b[1;2H\x1b[5 q\x1b[?25h");
+
+        // Nothing changed
+        render_output.clear();
+        screen.render(&mut render_output).unwrap();
+        assert!(render_output.is_empty());
+
+        // The cursor is hidden once it's no longer set
+        screen.erase();
+        render_output.clear();
+        screen.render(&mut render_output).unwrap();
+        assert!(render_output.ends_with(ansi::HIDE_CURSOR));
+    }
+
+    #[test]
+    fn render_damaged_regions() {
+        let mut render_output = vec![];
+        let mut screen = make_screen(Size::new(2, 2));
+        screen.set_track_damage(true);
+
+        // The first frame compares the entire buffer
+        assert_eq!(screen.render(&mut render_output).unwrap(), 4);
+
+        // Changes outside of the damaged regions are not drawn
+        screen.paint_glyph('a', LocalPos::new(0, 0));
+        screen.paint_glyph('b', LocalPos::new(1, 1));
+        screen.damage(Region::new(Pos::new(1, 1), Pos::new(2, 2)));
+        assert_eq!(screen.render(&mut render_output).unwrap(), 1);
+        assert!(render_output.ends_with(b"b"));
+
+        // Resizing compares the entire buffer again
+        screen.resize(Size::new(2, 2));
+        screen.paint_glyph('c', LocalPos::new(0, 0));
+        assert_eq!(screen.render(&mut render_output).unwrap(), 4);
+    }
+
     #[test]
     #[should_panic(expected = "index out of bounds: the len is 1 but the index is 4")]
     fn put_outside_of_screen() {
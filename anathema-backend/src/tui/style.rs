@@ -3,37 +3,9 @@ use std::str::FromStr;
 
 use anathema_state::{Color, Hex};
 use anathema_widgets::paint::CellAttributes;
-pub use crossterm::style::{Attribute as CrossAttrib, Color as CTColor};
-use crossterm::style::{SetAttribute, SetBackgroundColor, SetForegroundColor};
-use crossterm::QueueableCommand;
-
-struct ColorWrapper(Color);
-
-impl From<ColorWrapper> for CTColor {
-    fn from(color: ColorWrapper) -> CTColor {
-        match color.0 {
-            Color::Reset => Self::Reset,
-            Color::Black => Self::Black,
-            Color::Red => Self::DarkRed,
-            Color::Green => Self::DarkGreen,
-            Color::Yellow => Self::DarkYellow,
-            Color::Blue => Self::DarkBlue,
-            Color::Magenta => Self::DarkMagenta,
-            Color::Cyan => Self::DarkCyan,
-            Color::Grey => Self::Grey,
-            Color::DarkGrey => Self::DarkGrey,
-            Color::LightRed => Self::Red,
-            Color::LightGreen => Self::Green,
-            Color::LightYellow => Self::Yellow,
-            Color::LightBlue => Self::Blue,
-            Color::LightMagenta => Self::Magenta,
-            Color::LightCyan => Self::Cyan,
-            Color::White => Self::White,
-            Color::Rgb(r, g, b) => Self::Rgb { r, g, b },
-            Color::AnsiVal(v) => Self::AnsiValue(v),
-        }
-    }
-}
+
+use super::ansi;
+use crate::ColorDepth;
 
 /// The style for a cell in a [`crate::Buffer`]
 /// A style is applied to ever single cell in a [`crate::Buffer`].
@@ -48,6 +20,13 @@ impl From<ColorWrapper> for CTColor {
 ///     text: "hi"
 /// ```
 ///
+/// The `underline` attribute is either a bool, or the style of the underline:
+/// `double`, `curly`, `dotted` or `dashed`.
+///
+/// ```text
+/// text [underline: "curly", underline_color: red]: "mispelled"
+/// ```
+///
 /// In the following example, if the condition is ever true, and then false the text `is_false`
 /// will be rendered with a red foreground.
 ///
@@ -65,6 +44,9 @@ pub struct Style {
     pub fg: Option<Color>,
     /// Background colour.
     pub bg: Option<Color>,
+    /// Underline colour.
+    /// The underline has the same colour as the text if this is not set.
+    pub underline_color: Option<Color>,
     /// Attributes.
     pub attributes: Attributes,
 }
@@ -78,6 +60,7 @@ impl CellAttributes for Style {
         match key {
             "foreground" => self.fg,
             "background" => self.bg,
+            "underline_color" => self.underline_color,
             _ => None,
         }
     }
@@ -90,7 +73,19 @@ impl CellAttributes for Style {
         None
     }
 
-    fn with_str(&self, _: &str, _: &mut dyn FnMut(&str)) {}
+    fn with_str(&self, key: &str, f: &mut dyn FnMut(&str)) {
+        if key != "underline" {
+            return;
+        }
+
+        match self.attributes {
+            a if a.contains(Attributes::CURLY_UNDERLINED) => f("curly"),
+            a if a.contains(Attributes::DOTTED_UNDERLINED) => f("dotted"),
+            a if a.contains(Attributes::DASHED_UNDERLINED) => f("dashed"),
+            a if a.contains(Attributes::DOUBLE_UNDERLINED) => f("double"),
+            _ => {}
+        }
+    }
 
     fn get_bool(&self, key: &str) -> bool {
         match key {
@@ -112,6 +107,7 @@ impl Style {
         Self {
             fg: None,
             bg: None,
+            underline_color: None,
             attributes: Attributes::empty(),
         }
     }
@@ -119,28 +115,9 @@ impl Style {
     /// Create an instance of `Style` from `CellAttributes`.
     pub fn from_cell_attribs(attributes: &dyn CellAttributes) -> Self {
         let mut style = Self::new();
-
-        match attributes.get_color("foreground") {
-            Some(color) => style.fg = Some(color),
-            None => match attributes.get_hex("foreground") {
-                Some(Hex { r, g, b }) => style.fg = Some(Color::from((r, g, b))),
-                None => match attributes.get_u8("foreground") {
-                    Some(ansi) => style.fg = Some(Color::AnsiVal(ansi)),
-                    None => attributes.with_str("foreground", &mut |s| style.fg = Color::from_str(s).ok()),
-                },
-            },
-        }
-
-        match attributes.get_color("background") {
-            Some(color) => style.bg = Some(color),
-            None => match attributes.get_hex("background") {
-                Some(Hex { r, g, b }) => style.bg = Some(Color::from((r, g, b))),
-                None => match attributes.get_u8("background") {
-                    Some(ansi) => style.bg = Some(Color::AnsiVal(ansi)),
-                    None => attributes.with_str("background", &mut |s| style.bg = Color::from_str(s).ok()),
-                },
-            },
-        }
+        style.fg = read_color(attributes, "foreground");
+        style.bg = read_color(attributes, "background");
+        style.underline_color = read_color(attributes, "underline_color");
 
         if attributes.get_bool("bold") {
             style.attributes |= Attributes::BOLD;
@@ -158,6 +135,14 @@ impl Style {
             style.attributes |= Attributes::UNDERLINED;
         }
 
+        attributes.with_str("underline", &mut |s| match s {
+            "double" => style.attributes |= Attributes::DOUBLE_UNDERLINED,
+            "curly" => style.attributes |= Attributes::CURLY_UNDERLINED,
+            "dotted" => style.attributes |= Attributes::DOTTED_UNDERLINED,
+            "dashed" => style.attributes |= Attributes::DASHED_UNDERLINED,
+            _ => {}
+        });
+
         if attributes.get_bool("crossed-out") {
             style.attributes |= Attributes::CROSSED_OUT;
         }
@@ -175,11 +160,11 @@ impl Style {
 
     pub(crate) fn write(&self, w: &mut impl Write) -> Result<()> {
         if let Some(fg) = self.fg {
-            w.queue(SetForegroundColor(ColorWrapper(fg).into()))?;
+            ansi::foreground(&mut *w, fg)?;
         }
 
         if let Some(bg) = self.bg {
-            w.queue(SetBackgroundColor(ColorWrapper(bg).into()))?;
+            ansi::background(&mut *w, bg)?;
         }
 
         // Dim and bold are a special case, as they are both
@@ -187,45 +172,54 @@ impl Style {
         // This means the reset has to happen before setting
         // bold or dim
         if !self.attributes.contains(Attributes::BOLD | Attributes::DIM) {
-            w.queue(SetAttribute(CrossAttrib::NormalIntensity))?;
+            ansi::sgr(&mut *w, ansi::NORMAL_INTENSITY)?;
         }
 
         if self.attributes.contains(Attributes::BOLD) {
-            w.queue(SetAttribute(CrossAttrib::Bold))?;
+            ansi::sgr(&mut *w, ansi::BOLD)?;
         }
 
         if self.attributes.contains(Attributes::DIM) {
-            w.queue(SetAttribute(CrossAttrib::Dim))?;
+            ansi::sgr(&mut *w, ansi::DIM)?;
         }
 
         if self.attributes.contains(Attributes::ITALIC) {
-            w.queue(SetAttribute(CrossAttrib::Italic))?;
+            ansi::sgr(&mut *w, ansi::ITALIC)?;
         } else {
-            w.queue(SetAttribute(CrossAttrib::NoItalic))?;
+            ansi::sgr(&mut *w, ansi::NO_ITALIC)?;
         }
 
-        if self.attributes.contains(Attributes::UNDERLINED) {
-            w.queue(SetAttribute(CrossAttrib::Underlined))?;
-        } else {
-            w.queue(SetAttribute(CrossAttrib::NoUnderline))?;
+        match self.attributes {
+            a if a.contains(Attributes::CURLY_UNDERLINED) => ansi::underline(&mut *w, ansi::CURLY_UNDERLINE)?,
+            a if a.contains(Attributes::DOTTED_UNDERLINED) => ansi::underline(&mut *w, ansi::DOTTED_UNDERLINE)?,
+            a if a.contains(Attributes::DASHED_UNDERLINED) => ansi::underline(&mut *w, ansi::DASHED_UNDERLINE)?,
+            a if a.contains(Attributes::DOUBLE_UNDERLINED) => ansi::underline(&mut *w, ansi::DOUBLE_UNDERLINE)?,
+            a if a.contains(Attributes::UNDERLINED) => ansi::sgr(&mut *w, ansi::UNDERLINED)?,
+            _ => ansi::sgr(&mut *w, ansi::NO_UNDERLINE)?,
+        }
+
+        // The underline colour is only written for underlined cells,
+        // so cells without one don't keep the colour of the previous cell
+        if self.attributes.intersects(Attributes::ANY_UNDERLINE) {
+            ansi::underline_color(&mut *w, self.underline_color.unwrap_or(Color::Reset))?;
         }
 
         if self.attributes.contains(Attributes::OVERLINED) {
-            w.queue(SetAttribute(CrossAttrib::OverLined))?;
+            ansi::sgr(&mut *w, ansi::OVERLINED)?;
         } else {
-            w.queue(SetAttribute(CrossAttrib::NotOverLined))?;
+            ansi::sgr(&mut *w, ansi::NOT_OVERLINED)?;
         }
 
         if self.attributes.contains(Attributes::CROSSED_OUT) {
-            w.queue(SetAttribute(CrossAttrib::CrossedOut))?;
+            ansi::sgr(&mut *w, ansi::CROSSED_OUT)?;
         } else {
-            w.queue(SetAttribute(CrossAttrib::NotCrossedOut))?;
+            ansi::sgr(&mut *w, ansi::NOT_CROSSED_OUT)?;
         }
 
         if self.attributes.contains(Attributes::INVERSE) {
-            w.queue(SetAttribute(CrossAttrib::Reverse))?;
+            ansi::sgr(&mut *w, ansi::REVERSE)?;
         } else {
-            w.queue(SetAttribute(CrossAttrib::NoReverse))?;
+            ansi::sgr(&mut *w, ansi::NO_REVERSE)?;
         }
 
         Ok(())
@@ -241,6 +235,11 @@ impl Style {
         self.bg = Some(bg);
     }
 
+    /// Set the colour of the underline
+    pub fn set_underline_color(&mut self, color: Color) {
+        self.underline_color = Some(color);
+    }
+
     /// Set the style to bold
     pub fn set_bold(&mut self, bold: bool) {
         if bold {
@@ -277,6 +276,20 @@ impl Style {
         }
     }
 
+    /// Set the style of the underline, replacing any previous underline.
+    /// `Underline::None` removes the underline.
+    pub fn set_underline(&mut self, underline: Underline) {
+        self.attributes &= !Attributes::ANY_UNDERLINE;
+        self.attributes |= match underline {
+            Underline::None => Attributes::empty(),
+            Underline::Single => Attributes::UNDERLINED,
+            Underline::Double => Attributes::DOUBLE_UNDERLINED,
+            Underline::Curly => Attributes::CURLY_UNDERLINED,
+            Underline::Dotted => Attributes::DOTTED_UNDERLINED,
+            Underline::Dashed => Attributes::DASHED_UNDERLINED,
+        };
+    }
+
     /// Make the cell overlined as long as it's supported
     pub fn set_overlined(&mut self, overlined: bool) {
         if overlined {
@@ -312,6 +325,19 @@ impl Style {
         style
     }
 
+    /// Map the colours of the style to the palette supported by the terminal.
+    /// Backends do this when writing the style, this is for quantizing colours up front.
+    pub fn downgrade(self, depth: ColorDepth, strategy: ColorDowngrade) -> Self {
+        Self {
+            fg: self.fg.map(|fg| ansi::downgrade(fg, depth, strategy)),
+            bg: self.bg.map(|bg| ansi::downgrade(bg, depth, strategy)),
+            underline_color: self
+                .underline_color
+                .map(|color| ansi::downgrade(color, depth, strategy)),
+            ..self
+        }
+    }
+
     /// Merge two styles:
     /// if `self` has no foreground the foreground from the other style is copied to self.
     /// if `self` has no background the background from the other style is copied to self.
@@ -324,28 +350,98 @@ impl Style {
             self.bg = Some(bg);
         }
 
+        if let (None, Some(color)) = (self.underline_color, other.underline_color) {
+            self.underline_color = Some(color);
+        }
+
         self.attributes |= other.attributes;
     }
 }
 
+// Read a colour from any of the supported value types
+fn read_color(attributes: &dyn CellAttributes, key: &str) -> Option<Color> {
+    if let Some(color) = attributes.get_color(key) {
+        return Some(color);
+    }
+
+    if let Some(Hex { r, g, b }) = attributes.get_hex(key) {
+        return Some(Color::from((r, g, b)));
+    }
+
+    if let Some(ansi) = attributes.get_u8(key) {
+        return Some(Color::AnsiVal(ansi));
+    }
+
+    let mut color = None;
+    attributes.with_str(key, &mut |s| color = Color::from_str(s).ok());
+    color
+}
+
+/// The style of the underline.
+/// Terminals without support for a style draw a single underline instead.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Underline {
+    /// No underline
+    #[default]
+    None,
+    /// A single straight line
+    Single,
+    /// Two straight lines
+    Double,
+    /// A wavy line, e.g for spelling mistakes and diagnostics
+    Curly,
+    /// A dotted line
+    Dotted,
+    /// A dashed line
+    Dashed,
+}
+
+/// How colours are mapped to the palette, when the terminal doesn't support 24 bit colours.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum ColorDowngrade {
+    /// The palette colour with the shortest (euclidean) distance to the colour
+    #[default]
+    Nearest,
+    /// Same as `Nearest`, but the distance is weighted by how sensitive the eye is to each channel.
+    /// This is generally better at preserving hues.
+    Perceptual,
+    /// Write the 24 bit colours regardless of what the terminal supports
+    Never,
+}
+
 bitflags::bitflags! {
     /// Style attributes
     #[derive(Debug, Copy, Clone, PartialEq, Eq)]
-    pub struct Attributes: u8 {
+    pub struct Attributes: u16 {
         /// Make the characters bold (in supported output)
-        const BOLD =        0b0000_0001;
+        const BOLD =              0b0000_0000_0001;
         /// Make the characters dim (in supported output)
-        const DIM =         0b0000_0010;
+        const DIM =               0b0000_0000_0010;
         /// Make the characters italic (in supported output)
-        const ITALIC =      0b0000_0100;
+        const ITALIC =            0b0000_0000_0100;
         /// Make the characters underlined (in supported output)
-        const UNDERLINED =  0b0000_1000;
+        const UNDERLINED =        0b0000_0000_1000;
         /// Make the characters crossed out (in supported output)
-        const CROSSED_OUT = 0b0001_0000;
+        const CROSSED_OUT =       0b0000_0001_0000;
         /// Make the characters overlined (in supported output)
-        const OVERLINED =   0b0010_0000;
+        const OVERLINED =         0b0000_0010_0000;
         /// Make the characters inverse (in supported output)
-        const INVERSE =     0b0100_0000;
+        const INVERSE =           0b0000_0100_0000;
+        /// Make the characters double underlined (in supported output)
+        const DOUBLE_UNDERLINED = 0b0000_1000_0000;
+        /// Make the characters underlined with a wavy line (in supported output)
+        const CURLY_UNDERLINED =  0b0001_0000_0000;
+        /// Make the characters underlined with a dotted line (in supported output)
+        const DOTTED_UNDERLINED = 0b0010_0000_0000;
+        /// Make the characters underlined with a dashed line (in supported output)
+        const DASHED_UNDERLINED = 0b0100_0000_0000;
+
+        /// Any of the underline styles
+        const ANY_UNDERLINE = Self::UNDERLINED.bits()
+            | Self::DOUBLE_UNDERLINED.bits()
+            | Self::CURLY_UNDERLINED.bits()
+            | Self::DOTTED_UNDERLINED.bits()
+            | Self::DASHED_UNDERLINED.bits();
     }
 }
 
@@ -367,4 +463,44 @@ mod tests {
         assert_eq!(left.fg.unwrap(), Color::Red);
         assert_eq!(left.bg.unwrap(), Color::Blue);
     }
+
+    #[test]
+    fn write_underline_styles() {
+        let mut style = Style::new();
+        style.set_underline(Underline::Curly);
+        style.set_underline_color(Color::Red);
+
+        let mut output = vec![];
+        style.write(&mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("\x1b[4:3m"));
+        assert!(output.contains("\x1b[58;5;1m"));
+
+        // Without an underline colour the colour is reset
+        let mut style = Style::new();
+        style.set_underline(Underline::Double);
+        let mut output = vec![];
+        style.write(&mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("\x1b[4:2m"));
+        assert!(output.contains("\x1b[59m"));
+
+        // Removing the underline
+        style.set_underline(Underline::None);
+        let mut output = vec![];
+        style.write(&mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("\x1b[24m"));
+        assert!(!output.contains("\x1b[59m"));
+    }
+
+    #[test]
+    fn underline_from_attributes() {
+        let mut style = Style::new();
+        style.set_underline(Underline::Dotted);
+        style.set_underline_color(Color::Rgb(1, 2, 3));
+
+        let copy = Style::from_cell_attribs(&style);
+        assert_eq!(copy, style);
+    }
 }
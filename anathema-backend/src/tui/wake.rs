@@ -0,0 +1,129 @@
+use std::io::{IsTerminal, Read, Result, Write};
+use std::os::fd::{AsFd, OwnedFd};
+use std::os::unix::net::UnixStream;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use rustix::event::{poll, PollFd, PollFlags};
+use signal_hook::SigId;
+
+use crate::Waker;
+
+// Crossterm can't be interrupted while it waits for input,
+// so the input is waited for along with a socket that wakers (and signals) write to.
+pub(crate) struct Wake {
+    input: OwnedFd,
+    reader: UnixStream,
+    writer: Arc<UnixStream>,
+    // Set once a waker has written to the socket, so repeated wake ups don't write again
+    pending: Arc<AtomicBool>,
+    signals: Vec<SigId>,
+}
+
+impl Wake {
+    // Wait for `input` (the terminal).
+    // The signals wake the events as well (e.g `SIGWINCH`, so a resize is reported)
+    pub(crate) fn new(input: impl AsFd, signals: &[i32]) -> Result<Self> {
+        let (reader, writer) = UnixStream::pair()?;
+        reader.set_nonblocking(true)?;
+        writer.set_nonblocking(true)?;
+
+        let signals = signals
+            .iter()
+            .map(|&signal| signal_hook::low_level::pipe::register(signal, writer.try_clone()?))
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            input: input.as_fd().try_clone_to_owned()?,
+            reader,
+            writer: Arc::new(writer),
+            pending: Arc::new(AtomicBool::new(false)),
+            signals,
+        })
+    }
+
+    // Crossterm reads from `/dev/tty` if stdin is not a terminal,
+    // in which case the events are polled instead
+    pub(crate) fn stdin(signals: &[i32]) -> Result<Option<Self>> {
+        let stdin = std::io::stdin();
+        match stdin.is_terminal() {
+            true => Self::new(stdin, signals).map(Some),
+            false => Ok(None),
+        }
+    }
+
+    pub(crate) fn waker(&self) -> Waker {
+        let writer = self.writer.clone();
+        let pending = self.pending.clone();
+        Arc::new(move || {
+            if !pending.swap(true, Ordering::Relaxed) {
+                // A full socket is already waking the events
+                let _ = (&*writer).write(&[1]);
+            }
+        })
+    }
+
+    // Wait until there is input, the events are woken or the timeout expires.
+    // Returns true if there is input.
+    pub(crate) fn wait(&mut self, timeout: Duration) -> bool {
+        let mut fds = [
+            PollFd::new(&self.input, PollFlags::IN),
+            PollFd::new(&self.reader, PollFlags::IN),
+        ];
+        let timeout = timeout.as_millis().min(i32::MAX as u128) as i32;
+        // Interrupted by a signal
+        if poll(&mut fds, timeout).is_err() {
+            return false;
+        }
+
+        let input = fds[0].revents().contains(PollFlags::IN);
+        if fds[1].revents().contains(PollFlags::IN) {
+            self.pending.store(false, Ordering::Relaxed);
+            let mut buffer = [0; 64];
+            while let Ok(1..) = self.reader.read(&mut buffer) {}
+        }
+
+        input
+    }
+}
+
+impl Drop for Wake {
+    fn drop(&mut self) {
+        for signal in self.signals.drain(..) {
+            signal_hook::low_level::unregister(signal);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Instant;
+
+    use super::*;
+
+    #[test]
+    fn waker_interrupts_wait() {
+        let (input, mut terminal) = UnixStream::pair().unwrap();
+        let mut wake = Wake::new(&input, &[]).unwrap();
+        let waker = wake.waker();
+        let start = Instant::now();
+
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            waker();
+            waker();
+        });
+
+        assert!(!wake.wait(Duration::from_secs(10)));
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        // The wake up was consumed
+        let start = Instant::now();
+        wake.wait(Duration::from_millis(50));
+        assert!(start.elapsed() >= Duration::from_millis(40));
+
+        terminal.write_all(b"a").unwrap();
+        assert!(wake.wait(Duration::from_secs(10)));
+    }
+}
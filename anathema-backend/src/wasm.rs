@@ -0,0 +1,437 @@
+//! A backend for running in the browser, rendering to [xterm.js](https://xtermjs.org).
+//!
+//! The backend writes the same escape sequences as the [`TuiBackend`](crate::tui::TuiBackend),
+//! passing them to a callback rather than stdout, and browser events are
+//! pushed to the backend through [`WasmEvents`].
+//!
+//! ```ignore
+//! // `terminal` is an xterm.js `Terminal`
+//! let backend = WasmBackend::new((terminal.cols(), terminal.rows()), move |data| terminal.write(data));
+//! let events = backend.events();
+//!
+//! // In the `keydown` listener
+//! if events.key_down(&ev.key(), ev.ctrl_key(), ev.alt_key(), ev.shift_key(), ev.meta_key(), ev.repeat()) {
+//!     ev.prevent_default();
+//! }
+//!
+//! // In the `resize` listener of the terminal
+//! events.resize(cols, rows);
+//! ```
+//!
+//! As the browser can't block, the runtime should be driven with [`Frame::poll`]
+//! from `requestAnimationFrame`, rather than `run`.
+//! There is no file system to watch, so templates are not hot reloaded.
+//!
+//! [`Frame::poll`]: ../../anathema_runtime/struct.Frame.html#method.poll
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::time::Duration;
+
+use anathema_geometry::Size;
+use anathema_store::tree::{Node, TreeValues};
+use anathema_widgets::components::events::{
+    Event, ImeEvent, KeyCode, KeyEvent, KeyState, MouseButton, MouseEvent, MouseState, Preedit,
+};
+use anathema_widgets::debug::Overlay;
+use anathema_widgets::{AttributeStorage, Element, WidgetKind, WidgetRenderer};
+
+use crate::tui::Screen;
+use crate::{Capabilities, ColorDepth, EventSource, Renderer};
+
+/// Browser events, translated into anathema events.
+///
+/// This is a handle to the event queue of a [`WasmBackend`],
+/// and can be cloned into event listeners.
+///
+/// Positions are in cells (columns and rows), not pixels.
+#[derive(Debug, Default, Clone)]
+pub struct WasmEvents {
+    queue: Rc<RefCell<VecDeque<Event>>>,
+}
+
+impl WasmEvents {
+    /// Push an event to the queue
+    pub fn push(&self, event: Event) {
+        self.queue.borrow_mut().push_back(event);
+    }
+
+    /// A `keydown` event, where `key` is the value of `KeyboardEvent.key`.
+    ///
+    /// Returns false if the key has no anathema equivalent (e.g a lone modifier key),
+    /// in which case the browser should handle the event.
+    pub fn key_down(&self, key: &str, ctrl: bool, alt: bool, shift: bool, meta: bool, repeat: bool) -> bool {
+        let state = match repeat {
+            true => KeyState::Repeat,
+            false => KeyState::Press,
+        };
+        self.key(key, ctrl, alt, shift, meta, state)
+    }
+
+    /// A `keyup` event, where `key` is the value of `KeyboardEvent.key`
+    pub fn key_up(&self, key: &str, ctrl: bool, alt: bool, shift: bool, meta: bool) -> bool {
+        self.key(key, ctrl, alt, shift, meta, KeyState::Release)
+    }
+
+    fn key(&self, key: &str, ctrl: bool, alt: bool, shift: bool, meta: bool, state: KeyState) -> bool {
+        let Some(code) = key_code(key, shift) else { return false };
+        let mut event = KeyEvent::new(code, state);
+        event.ctrl = ctrl;
+        event.alt = alt;
+        event.shift = shift;
+        event.super_key = meta;
+        self.push(Event::Key(event));
+        true
+    }
+
+    /// A `mousedown` event.
+    /// `button` is the value of `MouseEvent.button`, and `clicks`
+    /// the value of `MouseEvent.detail` (the number of consecutive clicks).
+    pub fn mouse_down(&self, x: u16, y: u16, button: i16, clicks: u8) {
+        let Some(button) = mouse_button(button) else { return };
+        self.mouse(x, y, MouseState::Down(button), clicks);
+    }
+
+    /// A `mouseup` event, see [`WasmEvents::mouse_down`]
+    pub fn mouse_up(&self, x: u16, y: u16, button: i16, clicks: u8) {
+        let Some(button) = mouse_button(button) else { return };
+        self.mouse(x, y, MouseState::Up(button), clicks);
+    }
+
+    /// A `mousemove` event.
+    /// `buttons` is the value of `MouseEvent.buttons`:
+    /// moving the mouse with a button held down is a drag.
+    pub fn mouse_move(&self, x: u16, y: u16, buttons: u16) {
+        let state = match buttons {
+            0 => MouseState::Move,
+            _ if buttons & 1 != 0 => MouseState::Drag(MouseButton::Left),
+            _ if buttons & 2 != 0 => MouseState::Drag(MouseButton::Right),
+            _ => MouseState::Drag(MouseButton::Middle),
+        };
+        self.mouse(x, y, state, 0);
+    }
+
+    /// A `wheel` event, using `WheelEvent.deltaX` and `WheelEvent.deltaY`
+    pub fn wheel(&self, x: u16, y: u16, delta_x: f64, delta_y: f64) {
+        if delta_y < 0.0 {
+            self.mouse(x, y, MouseState::ScrollUp, 0);
+        } else if delta_y > 0.0 {
+            self.mouse(x, y, MouseState::ScrollDown, 0);
+        }
+
+        if delta_x < 0.0 {
+            self.mouse(x, y, MouseState::ScrollLeft, 0);
+        } else if delta_x > 0.0 {
+            self.mouse(x, y, MouseState::ScrollRight, 0);
+        }
+    }
+
+    fn mouse(&self, x: u16, y: u16, state: MouseState, clicks: u8) {
+        self.push(Event::Mouse(MouseEvent::new(x, y, state).with_clicks(clicks)));
+    }
+
+    /// Text pasted into the terminal
+    pub fn paste(&self, text: impl Into<String>) {
+        self.push(Event::Paste(text.into()));
+    }
+
+    /// A `compositionstart` event
+    pub fn composition_start(&self) {
+        self.push(Event::Ime(ImeEvent::Start));
+    }
+
+    /// A `compositionupdate` event, where `text` is the value of `CompositionEvent.data`.
+    ///
+    /// `selection_start` and `selection_end` are the `selectionStart` and `selectionEnd`
+    /// of the composition in the input element, relative to the start of the composition.
+    /// They are UTF-16 offsets, like every string offset in the browser.
+    pub fn composition_update(&self, text: &str, selection_start: u32, selection_end: u32) {
+        let start = utf16_to_byte_offset(text, selection_start);
+        let end = utf16_to_byte_offset(text, selection_end);
+        let mut preedit = Preedit::new(text).with_cursor(end);
+        if start < end {
+            preedit = preedit.with_selection(start..end);
+        }
+        self.push(Event::Ime(ImeEvent::Preedit(preedit)));
+    }
+
+    /// A `compositionend` event, where `text` is the value of `CompositionEvent.data`.
+    /// The browser ends a cancelled composition without any text.
+    pub fn composition_end(&self, text: &str) {
+        match text.is_empty() {
+            true => self.push(Event::Ime(ImeEvent::Cancel)),
+            false => self.push(Event::Ime(ImeEvent::Commit(text.into()))),
+        }
+    }
+
+    /// The terminal was resized to `cols` by `rows` cells
+    pub fn resize(&self, cols: u16, rows: u16) {
+        self.push(Event::Resize(cols, rows));
+    }
+
+    /// The terminal gained or lost focus
+    pub fn focus(&self, focused: bool) {
+        match focused {
+            true => self.push(Event::FocusGained),
+            false => self.push(Event::FocusLost),
+        }
+    }
+
+    fn pop(&self) -> Option<Event> {
+        self.queue.borrow_mut().pop_front()
+    }
+}
+
+// Translate the value of `KeyboardEvent.key`
+fn key_code(key: &str, shift: bool) -> Option<KeyCode> {
+    let code = match key {
+        "Enter" => KeyCode::Enter,
+        "Tab" if shift => KeyCode::BackTab,
+        "Tab" => KeyCode::Tab,
+        "Backspace" => KeyCode::Backspace,
+        "Escape" => KeyCode::Esc,
+        "ArrowLeft" => KeyCode::Left,
+        "ArrowRight" => KeyCode::Right,
+        "ArrowUp" => KeyCode::Up,
+        "ArrowDown" => KeyCode::Down,
+        "Home" => KeyCode::Home,
+        "End" => KeyCode::End,
+        "PageUp" => KeyCode::PageUp,
+        "PageDown" => KeyCode::PageDown,
+        "Delete" => KeyCode::Delete,
+        "Insert" => KeyCode::Insert,
+        "CapsLock" => KeyCode::CapsLock,
+        "ScrollLock" => KeyCode::ScrollLock,
+        "NumLock" => KeyCode::NumLock,
+        "PrintScreen" => KeyCode::PrintScreen,
+        "Pause" => KeyCode::Pause,
+        "ContextMenu" => KeyCode::Menu,
+        "Clear" => KeyCode::KeypadBegin,
+        _ => {
+            if let Some(n) = key.strip_prefix('F').and_then(|n| n.parse::<u8>().ok()) {
+                return Some(KeyCode::F(n));
+            }
+
+            // Single characters are printable keys,
+            // anything else is a modifier or a key without an equivalent
+            let mut chars = key.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => KeyCode::Char(c),
+                _ => return None,
+            }
+        }
+    };
+    Some(code)
+}
+
+// Translate the value of `MouseEvent.button`
+// The byte offset of a UTF-16 offset, clamped to the end of the text
+fn utf16_to_byte_offset(text: &str, offset: u32) -> usize {
+    let mut units = 0;
+    for (index, c) in text.char_indices() {
+        if units >= offset as usize {
+            return index;
+        }
+        units += c.len_utf16();
+    }
+    text.len()
+}
+
+fn mouse_button(button: i16) -> Option<MouseButton> {
+    match button {
+        0 => Some(MouseButton::Left),
+        1 => Some(MouseButton::Middle),
+        2 => Some(MouseButton::Right),
+        _ => None,
+    }
+}
+
+/// Browser backend, see the [module documentation](self)
+pub struct WasmBackend {
+    screen: Screen,
+    output: Box<dyn FnMut(&str)>,
+    buffer: Vec<u8>,
+    events: WasmEvents,
+    cells_written: usize,
+}
+
+impl WasmBackend {
+    /// Create a backend of a given size (in cells).
+    /// Everything rendered is passed to `output`, e.g `Terminal.write` in xterm.js.
+    pub fn new(size: impl Into<Size>, output: impl FnMut(&str) + 'static) -> Self {
+        Self {
+            screen: Screen::new(size),
+            output: Box::new(output),
+            buffer: vec![],
+            events: WasmEvents::default(),
+            cells_written: 0,
+        }
+    }
+
+    /// A handle to push browser events to the backend
+    pub fn events(&self) -> WasmEvents {
+        self.events.clone()
+    }
+
+    fn flush(&mut self) {
+        if self.buffer.is_empty() {
+            return;
+        }
+        (self.output)(&String::from_utf8_lossy(&self.buffer));
+        self.buffer.clear();
+    }
+}
+
+impl EventSource for WasmBackend {
+    // The browser can't block, so the timeout is ignored
+    fn next_event(&mut self, _timeout: Duration) -> Option<Event> {
+        self.events.pop()
+    }
+}
+
+impl Renderer for WasmBackend {
+    fn size(&self) -> Size {
+        self.screen.size()
+    }
+
+    fn resize(&mut self, new_size: Size) {
+        self.screen.resize(new_size);
+    }
+
+    fn paint<'bp>(
+        &mut self,
+        element: &mut Element<'bp>,
+        children: &[Node],
+        values: &mut TreeValues<WidgetKind<'bp>>,
+        attribute_storage: &AttributeStorage<'bp>,
+        ignore_floats: bool,
+    ) {
+        anathema_widgets::paint::paint(
+            &mut self.screen,
+            element,
+            children,
+            values,
+            attribute_storage,
+            ignore_floats,
+        );
+    }
+
+    fn render(&mut self) {
+        self.cells_written = self.screen.render(&mut self.buffer).unwrap_or(0);
+        self.flush();
+    }
+
+    fn cells_written(&self) -> usize {
+        self.cells_written
+    }
+
+    fn clear(&mut self) {
+        self.screen.erase();
+    }
+
+    fn overlay(&mut self, overlay: &dyn Overlay) {
+        overlay.paint(&mut self.screen);
+    }
+
+    fn finalize(&mut self) {
+        let _ = Screen::hide_cursor(&mut self.buffer);
+        self.flush();
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            colors: ColorDepth::TrueColor,
+            ..Capabilities::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use anathema_geometry::LocalPos;
+
+    use super::*;
+
+    fn next_key(events: &WasmEvents) -> KeyEvent {
+        match events.pop() {
+            Some(Event::Key(key)) => key,
+            event => panic!("expected a key event, got {event:?}"),
+        }
+    }
+
+    #[test]
+    fn translate_keys() {
+        let events = WasmEvents::default();
+        assert!(events.key_down("a", true, false, false, false, false));
+        assert!(events.key_down("Tab", false, false, true, false, false));
+        assert!(events.key_down("F5", false, false, false, false, true));
+        assert!(events.key_up("ArrowUp", false, false, false, false));
+        assert!(!events.key_down("Shift", false, false, true, false, false));
+
+        let key = next_key(&events);
+        assert_eq!(key.code, KeyCode::Char('a'));
+        assert!(key.ctrl);
+        assert_eq!(next_key(&events).code, KeyCode::BackTab);
+        let key = next_key(&events);
+        assert_eq!(key.code, KeyCode::F(5));
+        assert!(key.is_repeat());
+        assert!(next_key(&events).is_release());
+        assert!(events.pop().is_none());
+    }
+
+    #[test]
+    fn translate_mouse() {
+        let events = WasmEvents::default();
+        events.mouse_down(1, 2, 0, 2);
+        events.mouse_move(3, 4, 1);
+        events.wheel(0, 0, 0.0, -3.0);
+
+        let Some(Event::Mouse(mouse)) = events.pop() else { panic!() };
+        assert!(mouse.lsb_down());
+        assert!(mouse.is_double_click());
+        assert_eq!((mouse.x, mouse.y), (1, 2));
+
+        let Some(Event::Mouse(mouse)) = events.pop() else { panic!() };
+        assert!(matches!(mouse.state, MouseState::Drag(MouseButton::Left)));
+
+        let Some(Event::Mouse(mouse)) = events.pop() else { panic!() };
+        assert!(matches!(mouse.state, MouseState::ScrollUp));
+    }
+
+    #[test]
+    fn translate_composition() {
+        let events = WasmEvents::default();
+        events.composition_start();
+        // "𠀋" is two UTF-16 units
+        events.composition_update("𠀋ab", 2, 3);
+        events.composition_end("𠀋a");
+        events.composition_end("");
+
+        assert!(matches!(events.pop(), Some(Event::Ime(ImeEvent::Start))));
+        let Some(Event::Ime(ImeEvent::Preedit(preedit))) = events.pop() else { panic!() };
+        assert_eq!(preedit.selected(), Some("a"));
+        assert_eq!(preedit.before_cursor(), "𠀋a");
+        let Some(Event::Ime(ime)) = events.pop() else { panic!() };
+        assert_eq!(ime.commit(), Some("𠀋a"));
+        assert!(matches!(events.pop(), Some(Event::Ime(ImeEvent::Cancel))));
+    }
+
+    #[test]
+    fn render_to_output() {
+        let output = Rc::new(RefCell::new(String::new()));
+        let mut backend = WasmBackend::new((2, 1), {
+            let output = output.clone();
+            move |data| output.borrow_mut().push_str(data)
+        });
+
+        backend.screen.paint_glyph('x', LocalPos::ZERO);
+        backend.render();
+        assert!(output.borrow().contains('x'));
+        assert_eq!(backend.cells_written(), 1);
+
+        // Nothing changed, nothing is written
+        output.borrow_mut().clear();
+        backend.render();
+        assert!(output.borrow().is_empty());
+    }
+}
@@ -0,0 +1,179 @@
+//! A backend writing frames to any [`std::io::Write`], such as a file or a pipe.
+//!
+//! The size is fixed, and the terminal is never touched (no raw mode, alternate screen or cursor
+//! movement). Every frame that differs from the previous one is written in full, as lines of text,
+//! followed by a separator.
+//!
+//! ```ignore
+//! let log = std::fs::File::create("frames.log")?;
+//! let backend = WriteBackend::new(log, (80, 24)).stop_after(1);
+//! ```
+use std::io::Write;
+use std::time::Duration;
+
+use anathema_geometry::Size;
+use anathema_store::tree::{Node, TreeValues};
+use anathema_widgets::components::events::Event;
+use anathema_widgets::debug::Overlay;
+use anathema_widgets::{AttributeStorage, Element, WidgetKind, WidgetRenderer};
+
+use crate::tui::Screen;
+use crate::{EventSource, Renderer};
+
+/// Backend writing each frame as text
+pub struct WriteBackend<W> {
+    output: W,
+    screen: Screen,
+    styled: bool,
+    separator: String,
+    stop_after: Option<usize>,
+    frames: usize,
+    frame: Vec<u8>,
+    last_frame: Vec<u8>,
+}
+
+impl<W: Write> WriteBackend<W> {
+    /// Create a new backend writing frames of a fixed size to the output
+    pub fn new(output: W, size: impl Into<Size>) -> Self {
+        Self {
+            output,
+            screen: Screen::new(size),
+            styled: false,
+            separator: "\n".into(),
+            stop_after: None,
+            frames: 0,
+            frame: vec![],
+            last_frame: vec![],
+        }
+    }
+
+    /// Write colours and attributes as escape sequences.
+    /// Without styles the output is plain text, with trailing spaces trimmed.
+    pub fn styled(mut self) -> Self {
+        self.styled = true;
+        self
+    }
+
+    /// The separator written after every frame.
+    /// The default is an empty line.
+    pub fn separator(mut self, separator: impl Into<String>) -> Self {
+        self.separator = separator.into();
+        self
+    }
+
+    /// Stop the runtime once `frames` frames have been written.
+    /// Without this the runtime only stops once a component stops it.
+    pub fn stop_after(mut self, frames: usize) -> Self {
+        self.stop_after = Some(frames);
+        self
+    }
+
+    /// The number of frames written
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    /// Consume the backend, returning the output
+    pub fn into_inner(self) -> W {
+        self.output
+    }
+}
+
+impl<W: Write> EventSource for WriteBackend<W> {
+    fn next_event(&mut self, timeout: Duration) -> Option<Event> {
+        if self.stop_after.is_some_and(|frames| self.frames >= frames) {
+            return Some(Event::Stop);
+        }
+
+        // There is no input, so the runtime is idle until the timeout
+        std::thread::sleep(timeout);
+        None
+    }
+}
+
+impl<W: Write> Renderer for WriteBackend<W> {
+    fn size(&self) -> Size {
+        self.screen.size()
+    }
+
+    // The size is fixed
+    fn resize(&mut self, _: Size) {}
+
+    fn paint<'bp>(
+        &mut self,
+        element: &mut Element<'bp>,
+        children: &[Node],
+        values: &mut TreeValues<WidgetKind<'bp>>,
+        attribute_storage: &AttributeStorage<'bp>,
+        ignore_floats: bool,
+    ) {
+        anathema_widgets::paint::paint(
+            &mut self.screen,
+            element,
+            children,
+            values,
+            attribute_storage,
+            ignore_floats,
+        );
+    }
+
+    fn render(&mut self) {
+        self.frame.clear();
+        if self.screen.render_lines(&mut self.frame, self.styled).is_err() || self.frame == self.last_frame {
+            return;
+        }
+
+        let _ = self.output.write_all(&self.frame);
+        let _ = self.output.write_all(self.separator.as_bytes());
+        let _ = self.output.flush();
+
+        self.frames += 1;
+        std::mem::swap(&mut self.frame, &mut self.last_frame);
+    }
+
+    fn clear(&mut self) {
+        self.screen.erase();
+    }
+
+    fn overlay(&mut self, overlay: &dyn Overlay) {
+        overlay.paint(&mut self.screen);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use anathema_geometry::LocalPos;
+
+    use super::*;
+
+    #[test]
+    fn write_frames() {
+        let mut backend = WriteBackend::new(vec![], (3, 2)).separator("--\n").stop_after(2);
+
+        backend.screen.paint_glyph('a', LocalPos::ZERO);
+        backend.render();
+        assert!(backend.next_event(Duration::ZERO).is_none());
+
+        // Nothing changed, nothing is written
+        backend.render();
+        assert_eq!(backend.frames(), 1);
+
+        backend.screen.paint_glyph('b', LocalPos::new(1, 1));
+        backend.render();
+        assert!(matches!(backend.next_event(Duration::ZERO), Some(Event::Stop)));
+
+        let output = String::from_utf8(backend.into_inner()).unwrap();
+        assert_eq!(output, "a\n\n--\na\n b\n--\n");
+    }
+
+    #[test]
+    fn styled_frames() {
+        let mut backend = WriteBackend::new(vec![], (1, 1)).styled();
+        backend.screen.paint_glyph('a', LocalPos::ZERO);
+        backend.render();
+
+        let output = String::from_utf8(backend.into_inner()).unwrap();
+        assert!(output.starts_with("\x1b["));
+        assert!(output.ends_with("a\x1b[0m\n\n"));
+    }
+}
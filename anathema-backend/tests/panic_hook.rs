@@ -0,0 +1,64 @@
+// The panic hook is global, so it's tested in a binary of its own
+// where no other test can panic while the hook is replaced.
+use std::cell::Cell;
+use std::sync::Mutex;
+
+use panic_hook::{install, installed, uninstall, Modes};
+
+#[path = "../src/tui/panic_hook.rs"]
+mod panic_hook;
+
+thread_local! {
+    static PANICKED: Cell<bool> = const { Cell::new(false) };
+}
+
+static RESTORED: Mutex<Vec<Modes>> = Mutex::new(vec![]);
+
+fn restore(modes: Modes) {
+    RESTORED.lock().unwrap().push(modes);
+}
+
+#[test]
+fn panic_hook_is_installed_once() {
+    std::panic::set_hook(Box::new(|_| PANICKED.with(|p| p.set(true))));
+
+    let paste = Modes {
+        bracketed_paste: true,
+        ..Modes::default()
+    };
+    let focus = Modes {
+        focus_reporting: true,
+        ..Modes::default()
+    };
+
+    install(paste, restore);
+    install(focus, restore);
+    assert_eq!(installed().as_ref().map(|i| i.backends.len()), Some(2));
+
+    // The modes of both backends are restored
+    let _ = std::panic::catch_unwind(|| panic!("both"));
+    let both = Modes {
+        bracketed_paste: true,
+        focus_reporting: true,
+        kitty_keyboard: false,
+    };
+    assert_eq!(RESTORED.lock().unwrap().pop(), Some(both));
+    assert!(PANICKED.with(|p| p.replace(false)));
+
+    // Only the modes of the remaining backend are restored
+    uninstall(paste);
+    assert!(installed().is_some());
+    let _ = std::panic::catch_unwind(|| panic!("focus"));
+    assert_eq!(RESTORED.lock().unwrap().pop(), Some(focus));
+    assert!(PANICKED.with(|p| p.replace(false)));
+
+    uninstall(focus);
+    assert!(installed().is_none());
+
+    // The previous hook is back in place
+    let _ = std::panic::catch_unwind(|| panic!("restored"));
+    assert!(RESTORED.lock().unwrap().is_empty());
+    assert!(PANICKED.with(Cell::get));
+
+    drop(std::panic::take_hook());
+}
@@ -0,0 +1,28 @@
+use anathema_benches::{nested, texts, LIST};
+use anathema_templates::Document;
+use criterion::{criterion_group, criterion_main, Criterion};
+
+fn compile(c: &mut Criterion) {
+    let mut group = c.benchmark_group("compile");
+
+    let templates = [
+        ("list", LIST.to_string()),
+        ("nested_100", nested(100)),
+        ("texts_1k", texts(1_000).0),
+    ];
+
+    for (name, template) in templates {
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                Document::new(template.as_str())
+                    .compile()
+                    .expect("the template is valid")
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, compile);
+criterion_main!(benches);
@@ -0,0 +1,74 @@
+//! Every benchmark includes the layout and paint of the frame.
+use anathema_benches::{component, list, nested, set_item, set_texts, texts, LIST};
+use anathema_state::Map;
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+
+fn list_10k(c: &mut Criterion) {
+    let mut group = c.benchmark_group("list_10k");
+    group.sample_size(10);
+
+    // Compile the template, build the tree of 10k iterations and paint the first frame
+    group.bench_function("build", |b| {
+        b.iter_batched(
+            || list(10_000),
+            |state| component(LIST, state).run(|_| {}).unwrap(),
+            BatchSize::LargeInput,
+        )
+    });
+
+    // Change a single item that is on the screen
+    group.bench_function("update", |b| {
+        let mut test = component(LIST, list(10_000));
+        test.run(|frame| {
+            let mut value = 0;
+            b.iter(|| {
+                value += 1;
+                frame.with_state(|state| set_item(state, 5, value));
+                frame.tick();
+            })
+        })
+        .unwrap();
+    });
+
+    group.finish();
+}
+
+fn nested_100(c: &mut Criterion) {
+    // Change the text at the bottom of the tree
+    c.bench_function("nested_100/update", |b| {
+        let mut state = Map::empty();
+        state.insert("value", 0usize);
+        let mut test = component(&nested(100), state);
+        test.run(|frame| {
+            let mut value = 0;
+            b.iter(|| {
+                value += 1;
+                frame.with_state(|state| state.to_mut().get_mut("value").expect("the value exists").set(value));
+                frame.tick();
+            })
+        })
+        .unwrap();
+    });
+}
+
+fn state_churn(c: &mut Criterion) {
+    const COUNT: usize = 1_000;
+
+    // Change every value bound to a text on every frame
+    c.bench_function("state_churn_1k", |b| {
+        let (template, state) = texts(COUNT);
+        let mut test = component(&template, state);
+        test.run(|frame| {
+            let mut offset = 0;
+            b.iter(|| {
+                offset += 1;
+                frame.with_state(|state| set_texts(state, COUNT, offset));
+                frame.tick();
+            })
+        })
+        .unwrap();
+    });
+}
+
+criterion_group!(benches, list_10k, nested_100, state_churn);
+criterion_main!(benches);
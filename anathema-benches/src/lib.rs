@@ -0,0 +1,89 @@
+//! Scenarios shared by the benchmarks.
+//!
+//! Run the benchmarks with `cargo bench -p anathema-benches`.
+use std::fmt::Write;
+use std::marker::PhantomData;
+
+use anathema_runtime::testing::ComponentTest;
+use anathema_state::{List, Map, State, Value};
+use anathema_widgets::components::Component;
+
+/// The size of the screen for every scenario
+pub const SIZE: (u16, u16) = (80, 24);
+
+/// A scrollable list of every value in `items`
+pub const LIST: &str = "
+overflow
+    for item in items
+        text item
+";
+
+/// A component that only holds the state, which is changed by the benchmarks
+pub struct Bench<S>(PhantomData<S>);
+
+impl<S> Bench<S> {
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<S: State> Component for Bench<S> {
+    type Message = ();
+    type State = S;
+}
+
+/// Mount a component with the template and state
+pub fn component<S: State>(template: &str, state: S) -> ComponentTest<Bench<S>> {
+    ComponentTest::new(template.to_string(), Bench::new(), state, SIZE).expect("the template is valid")
+}
+
+/// The numbers from zero to `len`, as `items`
+pub fn list(len: usize) -> Value<Map<List<usize>>> {
+    let mut map = Map::empty();
+    map.insert("items", List::from_iter(0..len));
+    map
+}
+
+/// Replace the value of an item of the `list`
+pub fn set_item(state: &mut Value<Map<List<usize>>>, index: usize, value: usize) {
+    let mut map = state.to_mut();
+    let items = map.get_mut("items").expect("the list exists");
+    let mut items = items.to_mut();
+    items.get_mut(index).expect("the index is within the list").set(value);
+}
+
+/// Elements nested `depth` levels deep, with a text bound to `value` at the bottom
+pub fn nested(depth: usize) -> String {
+    let mut template = String::new();
+    for level in 0..depth {
+        let ident = match level % 3 {
+            0 => "vstack",
+            1 => "hstack",
+            _ => "padding [padding: 0]",
+        };
+        _ = writeln!(template, "{:indent$}{ident}", "", indent = level * 4);
+    }
+    _ = writeln!(template, "{:indent$}text value", "", indent = depth * 4);
+    template
+}
+
+/// A text for each of `count` values, bound to `v0`, `v1`, ...
+pub fn texts(count: usize) -> (String, Value<Map<usize>>) {
+    let mut template = String::from("vstack\n");
+    let mut map = Map::empty();
+    for i in 0..count {
+        _ = writeln!(template, "    text [foreground: 'red'] 'value: ' v{i}");
+        map.insert(format!("v{i}"), i);
+    }
+    (template, map)
+}
+
+/// Set every value of the `texts` state
+pub fn set_texts(state: &mut Value<Map<usize>>, count: usize, offset: usize) {
+    let mut map = state.to_mut();
+    for i in 0..count {
+        if let Some(value) = map.get_mut(&format!("v{i}")) {
+            value.set(i + offset);
+        }
+    }
+}
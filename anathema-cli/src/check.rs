@@ -0,0 +1,140 @@
+use std::fmt::{self, Display};
+use std::path::{Path, PathBuf};
+
+use anathema_lsp::{check_document, component_names, Problem};
+use anathema_templates::{Document, SourceKind};
+
+/// A component listed in the manifest
+#[derive(Debug, PartialEq)]
+pub struct Component {
+    pub name: String,
+    /// The template of the component.
+    /// Components without a path are only checked by name.
+    pub path: Option<PathBuf>,
+}
+
+/// A line in the manifest that could not be read
+#[derive(Debug, PartialEq)]
+pub struct ManifestError {
+    pub line: usize,
+    pub message: &'static str,
+}
+
+impl Display for ManifestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// Parse a component manifest.
+/// Each line is either `name = path/to/template.aml` or only the `name`
+/// of a component that exists without a template file.
+/// Paths are relative to `dir`, and `#` starts a comment.
+pub fn parse_manifest(src: &str, dir: &Path) -> Result<Vec<Component>, ManifestError> {
+    let mut components = vec![];
+
+    for (no, line) in src.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let (name, path) = match line.split_once('=') {
+            Some((name, path)) => (name.trim(), Some(path.trim())),
+            None => (line, None),
+        };
+
+        let error = |message| ManifestError { line: no + 1, message };
+
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err(error("invalid component name"));
+        }
+
+        let path = match path {
+            Some("") => return Err(error("missing path")),
+            Some(path) => Some(dir.join(path)),
+            None => None,
+        };
+
+        components.push(Component {
+            name: name.into(),
+            path,
+        });
+    }
+
+    Ok(components)
+}
+
+/// Check a template against the elements and the components in the manifest.
+/// Any component that is not in the manifest is reported as missing.
+pub fn check(src: &str, elements: &[&str], components: &[Component]) -> Vec<Problem> {
+    let mut doc = Document::new(src);
+
+    // Only add the components used by the template, so a broken
+    // component template doesn't fail every other template.
+    let used = component_names(src);
+    for component in components.iter().filter(|c| used.contains(&&*c.name)) {
+        let src = match &component.path {
+            Some(path) => SourceKind::Path(path.clone()),
+            None => SourceKind::Str("text ''".into()),
+        };
+
+        if let Err(err) = doc.add_component(&component.name, src) {
+            return vec![Problem {
+                line: 0,
+                col: 0,
+                message: format!("component `{}`: {err}", component.name),
+                is_error: true,
+            }];
+        }
+    }
+
+    check_document(doc, src, elements)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const ELEMENTS: &[&str] = &["vstack", "text"];
+
+    #[test]
+    fn manifest() {
+        let src = "
+            # components
+            button = widgets/button.aml
+            status # registered in code
+        ";
+
+        let components = parse_manifest(src, Path::new("app")).unwrap();
+        assert_eq!(
+            components,
+            [
+                Component {
+                    name: "button".into(),
+                    path: Some(PathBuf::from("app/widgets/button.aml")),
+                },
+                Component {
+                    name: "status".into(),
+                    path: None,
+                },
+            ]
+        );
+
+        let err = parse_manifest("a = b\nc =", Path::new("")).unwrap_err();
+        assert_eq!(err.line, 2);
+    }
+
+    #[test]
+    fn missing_component() {
+        let src = "vstack\n    @status\n    @other";
+        let components = [Component {
+            name: "status".into(),
+            path: None,
+        }];
+
+        let problems = check(src, ELEMENTS, &components);
+        assert_eq!(problems.len(), 1);
+        assert_eq!((problems[0].line, problems[0].is_error), (2, true));
+    }
+}
@@ -0,0 +1,255 @@
+//! Command line tools for Anathema templates.
+//!
+//! `anathema check` compiles templates against the default widgets
+//! and reports every problem, for use in CI:
+//!
+//! ```text
+//! anathema check [--components <manifest>] [--deny-warnings] <path>...
+//! ```
+//!
+//! Directories are searched for `.aml` files.
+//! The exit code is `0` if all templates are valid, `1` if there are errors
+//! (or warnings with `--deny-warnings`) and `2` if the command could not run.
+//!
+//! `anathema preview` renders a template in the terminal and reloads it
+//! when the template, the templates of its components or the state changes:
+//!
+//! ```text
+//! anathema preview [--state <state.json|state.toml>] [--components <manifest>] <template>
+//! ```
+//!
+//! The state is a JSON or TOML object, used as the state of the template.
+//! Components use the template from the manifest, or `<name>.aml` next to the
+//! template using them. Any other component is drawn as a placeholder.
+//! Components added while previewing require a restart.
+
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use anathema_backend::tui::TuiBackend;
+use anathema_default_widgets::register_default_widgets;
+use anathema_runtime::Runtime;
+use anathema_state::Map;
+use anathema_templates::{Document, SourceKind};
+use anathema_widgets::Factory;
+
+use crate::check::{check, parse_manifest, Component};
+use crate::preview::{MockState, Preview};
+
+mod check;
+mod preview;
+
+const USAGE: &str = "usage: anathema check [--components <manifest>] [--deny-warnings] <path>...
+       anathema preview [--state <state.json|state.toml>] [--components <manifest>] <template>";
+
+enum Command {
+    Check {
+        manifest: Option<PathBuf>,
+        deny_warnings: bool,
+        paths: Vec<PathBuf>,
+    },
+    Preview {
+        manifest: Option<PathBuf>,
+        state: Option<PathBuf>,
+        template: PathBuf,
+    },
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let command = match args.next() {
+        Some(cmd) if cmd == "check" || cmd == "preview" => cmd,
+        Some(cmd) => return Err(format!("unknown command `{cmd}`")),
+        None => return Err("missing command".into()),
+    };
+
+    let mut manifest = None;
+    let mut deny_warnings = false;
+    let mut state = None;
+    let mut paths: Vec<PathBuf> = vec![];
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--components" | "-c" => {
+                let path = args.next().ok_or("missing path to the component manifest")?;
+                manifest = Some(path.into());
+            }
+            "--deny-warnings" if command == "check" => deny_warnings = true,
+            "--state" | "-s" if command == "preview" => {
+                let path = args.next().ok_or("missing path to the state")?;
+                state = Some(path.into());
+            }
+            flag if flag.starts_with('-') => return Err(format!("unknown option `{flag}`")),
+            path => paths.push(path.into()),
+        }
+    }
+
+    match command.as_str() {
+        "check" if paths.is_empty() => Err("no templates to check".into()),
+        "check" => Ok(Command::Check {
+            manifest,
+            deny_warnings,
+            paths,
+        }),
+        _ => match <[PathBuf; 1]>::try_from(paths) {
+            Ok([template]) => Ok(Command::Preview {
+                manifest,
+                state,
+                template,
+            }),
+            Err(_) => Err("preview requires a single template".into()),
+        },
+    }
+}
+
+fn read_manifest(path: Option<&Path>) -> Result<Vec<Component>, String> {
+    let Some(path) = path else { return Ok(vec![]) };
+    let src = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    let dir = path.parent().unwrap_or(Path::new(""));
+    parse_manifest(&src, dir).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+// Collect the templates, searching directories for `.aml` files
+fn templates(path: &Path, output: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    if !path.is_dir() {
+        output.push(path.into());
+        return Ok(());
+    }
+
+    let mut entries = std::fs::read_dir(path)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    entries.sort();
+
+    for path in entries {
+        if path.is_dir() || path.extension().is_some_and(|ext| ext == "aml") {
+            templates(&path, output)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn run_check(manifest: Option<&Path>, deny_warnings: bool, inputs: &[PathBuf]) -> Result<bool, String> {
+    let components = read_manifest(manifest)?;
+
+    let mut paths = vec![];
+    for path in inputs {
+        templates(path, &mut paths).map_err(|e| format!("{}: {e}", path.display()))?;
+    }
+
+    let mut factory = Factory::new();
+    register_default_widgets(&mut factory);
+    let elements = factory.idents().collect::<Vec<_>>();
+
+    let mut errors = 0;
+    let mut warnings = 0;
+
+    for path in &paths {
+        let src = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+
+        for problem in check(&src, &elements, &components) {
+            let level = match problem.is_error {
+                true => {
+                    errors += 1;
+                    "error"
+                }
+                false => {
+                    warnings += 1;
+                    "warning"
+                }
+            };
+
+            let (line, col) = (problem.line + 1, problem.col + 1);
+            println!("{}:{line}:{col}: {level}: {}", path.display(), problem.message);
+        }
+    }
+
+    eprintln!(
+        "checked {} template(s): {errors} error(s), {warnings} warning(s)",
+        paths.len()
+    );
+
+    Ok(errors == 0 && (warnings == 0 || !deny_warnings))
+}
+
+fn run_preview(manifest: Option<&Path>, state_path: Option<&Path>, template: &Path) -> Result<bool, String> {
+    let components = read_manifest(manifest)?;
+    let stubs = preview::components(template, &components).map_err(|e| format!("{}: {e}", template.display()))?;
+
+    // The state is read again whenever the preview is reloaded,
+    // keeping the last valid state if the file can't be read.
+    let mut state = match state_path {
+        Some(path) => MockState::read(path)?,
+        None => MockState::default(),
+    };
+    let path = state_path.map(PathBuf::from);
+    let next_state = move || {
+        if let Some(new_state) = path.as_deref().and_then(|path| MockState::read(path).ok()) {
+            state = new_state;
+        }
+        state.state().unwrap_or_else(|_| Map::empty())
+    };
+
+    let backend = TuiBackend::builder()
+        .enable_alt_screen()
+        .enable_raw_mode()
+        .enable_mouse()
+        .hide_cursor()
+        .finish()
+        .map_err(|e| e.to_string())?;
+
+    let mut builder = Runtime::builder(Document::new("@preview"), backend);
+    builder
+        .register_prototype("preview", SourceKind::Path(template.into()), || Preview, next_state)
+        .map_err(|e| e.to_string())?;
+
+    for stub in stubs {
+        let src = match &stub.path {
+            Some(path) => SourceKind::Path(path.clone()),
+            None => SourceKind::Str(stub.placeholder()),
+        };
+        builder
+            .register_prototype(stub.name, src, || Preview, Map::empty)
+            .map_err(|e| e.to_string())?;
+    }
+
+    if let Some(path) = state_path {
+        builder.watch(path);
+    }
+
+    let mut runtime = builder.finish().map_err(|e| e.to_string())?;
+    runtime.run();
+    Ok(true)
+}
+
+fn main() -> ExitCode {
+    let command = match parse_args(std::env::args().skip(1)) {
+        Ok(command) => command,
+        Err(err) => {
+            eprintln!("{err}\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let result = match &command {
+        Command::Check {
+            manifest,
+            deny_warnings,
+            paths,
+        } => run_check(manifest.as_deref(), *deny_warnings, paths),
+        Command::Preview {
+            manifest,
+            state,
+            template,
+        } => run_preview(manifest.as_deref(), state.as_deref(), template),
+    };
+
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::from(2)
+        }
+    }
+}
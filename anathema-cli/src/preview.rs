@@ -0,0 +1,173 @@
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+
+use anathema_lsp::component_names;
+use anathema_state::{Map, State, Value};
+use anathema_widgets::components::Component;
+
+use crate::check;
+
+/// The component rendering the previewed template and every other component.
+/// It has no behaviour, the state is created from the mock state.
+pub struct Preview;
+
+impl Component for Preview {
+    type Message = ();
+    type State = Value<Map<Box<dyn State>>>;
+}
+
+/// A component used by the previewed template
+#[derive(Debug, PartialEq)]
+pub struct Stub {
+    pub name: String,
+    /// The template of the component.
+    /// Components without a template are drawn as a placeholder.
+    pub path: Option<PathBuf>,
+}
+
+impl Stub {
+    pub fn placeholder(&self) -> String {
+        format!("border\n    text '@{}'", self.name)
+    }
+}
+
+/// Find the components used by the template and the templates of the components.
+///
+/// A component uses the template from the manifest if it's listed there,
+/// otherwise `<name>.aml` next to the template using it, if that exists.
+pub fn components(template: &Path, manifest: &[check::Component]) -> std::io::Result<Vec<Stub>> {
+    let mut stubs: Vec<Stub> = vec![];
+    let mut templates = VecDeque::from([template.to_path_buf()]);
+
+    while let Some(template) = templates.pop_front() {
+        let src = std::fs::read_to_string(&template)?;
+        let dir = template.parent().unwrap_or(Path::new(""));
+
+        for name in component_names(&src) {
+            if stubs.iter().any(|stub| stub.name == name) {
+                continue;
+            }
+
+            let path = match manifest.iter().find(|c| c.name == name) {
+                Some(component) => component.path.clone(),
+                None => Some(dir.join(format!("{name}.aml"))).filter(|path| path.is_file()),
+            };
+
+            if let Some(path) = &path {
+                templates.push_back(path.clone());
+            }
+
+            stubs.push(Stub {
+                name: name.into(),
+                path,
+            });
+        }
+    }
+
+    Ok(stubs)
+}
+
+/// The mock state of the template, a JSON or TOML object.
+/// The source is kept to create the state of every instance of the template.
+#[derive(Debug, Default)]
+pub struct MockState {
+    src: String,
+    toml: bool,
+}
+
+impl MockState {
+    /// Read and validate the state, as TOML if the extension is `.toml`
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let state = Self {
+            src: std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?,
+            toml: path.extension().is_some_and(|ext| ext == "toml"),
+        };
+        state.state().map_err(|e| format!("{}: {e}", path.display()))?;
+        Ok(state)
+    }
+
+    pub fn state(&self) -> Result<Value<Map<Box<dyn State>>>, String> {
+        match (self.toml, self.src.trim().is_empty()) {
+            (_, true) => Ok(Map::empty()),
+            (true, false) => toml::from_str(&self.src).map_err(|e| e.to_string()),
+            (false, false) => serde_json::from_str(&self.src).map_err(|e| e.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use anathema_state::{AnyState, Path as StatePath};
+
+    use super::*;
+
+    fn lookup(state: &dyn AnyState, path: &[StatePath<'_>]) -> Option<String> {
+        let (first, rest) = path.split_first()?;
+        let value = state.state_lookup(*first)?;
+        value.as_state(|state| match rest.is_empty() {
+            true => state.to_common().map(|value| value.to_string()),
+            false => lookup(state, rest),
+        })
+    }
+
+    #[test]
+    fn mock_state() {
+        let json = MockState {
+            src: r#"{"name": "Anathema", "items": ["a", "b"]}"#.into(),
+            toml: false,
+        };
+        let state = json.state().unwrap();
+        assert_eq!(lookup(&state, &["name".into()]).as_deref(), Some("Anathema"));
+        assert_eq!(
+            lookup(&state, &["items".into(), StatePath::Index(1)]).as_deref(),
+            Some("b")
+        );
+
+        let toml = MockState {
+            src: "name = 'Anathema'".into(),
+            toml: true,
+        };
+        let state = toml.state().unwrap();
+        assert_eq!(lookup(&state, &["name".into()]).as_deref(), Some("Anathema"));
+
+        assert!(MockState::default().state().is_ok());
+        let invalid = MockState {
+            src: "[1, 2]".into(),
+            toml: false,
+        };
+        assert!(invalid.state().is_err());
+    }
+
+    #[test]
+    fn find_components() {
+        let dir = std::env::temp_dir().join(format!("anathema-preview-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("main.aml"), "vstack\n    @button\n    @status\n    @button").unwrap();
+        std::fs::write(dir.join("button.aml"), "@icon").unwrap();
+
+        let manifest = [check::Component {
+            name: "status".into(),
+            path: None,
+        }];
+        let stubs = components(&dir.join("main.aml"), &manifest).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            stubs,
+            [
+                Stub {
+                    name: "button".into(),
+                    path: Some(dir.join("button.aml")),
+                },
+                Stub {
+                    name: "status".into(),
+                    path: None,
+                },
+                Stub {
+                    name: "icon".into(),
+                    path: None,
+                },
+            ]
+        );
+    }
+}
@@ -1,5 +1,7 @@
 use std::fmt::{Result, Write};
 
+pub mod profile;
+
 pub trait DebugWriter {
     fn write(&mut self, output: &mut impl Write) -> Result;
 }
@@ -0,0 +1,124 @@
+//! Instrumentation hooks for profilers.
+//!
+//! The runtime marks the stages of a frame (events, changes, layout, paint etc.)
+//! with [`span`]s. To record them, install a [`Profiler`] with [`set_profiler`]:
+//!
+//! ```
+//! use anathema_debug::profile::{set_profiler, span, Profiler};
+//!
+//! struct Print;
+//!
+//! impl Profiler for Print {
+//!     fn begin(&self, name: &'static str) {
+//!         println!("begin {name}");
+//!     }
+//!
+//!     fn end(&self, name: &'static str) {
+//!         println!("end {name}");
+//!     }
+//! }
+//!
+//! set_profiler(Print);
+//! let _span = span("work");
+//! ```
+//!
+//! The profiler is installed for the current thread only.
+//! Without a profiler a span is a no-op.
+use std::cell::RefCell;
+
+thread_local! {
+    static PROFILER: RefCell<Option<Box<dyn Profiler>>> = const { RefCell::new(None) };
+}
+
+/// Receives spans from the runtime.
+pub trait Profiler {
+    /// A span with the given name was entered
+    fn begin(&self, name: &'static str);
+
+    /// The span with the given name was exited.
+    /// Spans are exited in the reverse order they were entered.
+    fn end(&self, name: &'static str);
+
+    /// Called by the runtime at the end of every frame
+    fn end_frame(&self) {}
+}
+
+/// Install a profiler for the current thread, replacing the current one
+pub fn set_profiler(profiler: impl Profiler + 'static) {
+    PROFILER.with_borrow_mut(|current| *current = Some(Box::new(profiler)));
+}
+
+/// Remove the profiler of the current thread
+pub fn remove_profiler() {
+    PROFILER.with_borrow_mut(|current| *current = None);
+}
+
+/// Enter a span. The span is exited when the guard is dropped.
+pub fn span(name: &'static str) -> Span {
+    with_profiler(|profiler| profiler.begin(name));
+    Span(name)
+}
+
+/// Mark the end of a frame
+pub fn end_frame() {
+    with_profiler(|profiler| profiler.end_frame());
+}
+
+fn with_profiler(f: impl FnOnce(&dyn Profiler)) {
+    PROFILER.with_borrow(|profiler| {
+        if let Some(profiler) = profiler {
+            f(profiler.as_ref())
+        }
+    });
+}
+
+/// Guard returned from [`span`]
+#[must_use = "the span is exited when the guard is dropped"]
+pub struct Span(&'static str);
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        with_profiler(|profiler| profiler.end(self.0));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::rc::Rc;
+
+    use super::*;
+
+    struct Log(Rc<RefCell<Vec<String>>>);
+
+    impl Profiler for Log {
+        fn begin(&self, name: &'static str) {
+            self.0.borrow_mut().push(format!("+{name}"));
+        }
+
+        fn end(&self, name: &'static str) {
+            self.0.borrow_mut().push(format!("-{name}"));
+        }
+
+        fn end_frame(&self) {
+            self.0.borrow_mut().push("frame".into());
+        }
+    }
+
+    #[test]
+    fn nested_spans() {
+        let log = Rc::new(RefCell::new(vec![]));
+        set_profiler(Log(log.clone()));
+
+        {
+            let _frame = span("frame");
+            let _layout = span("layout");
+        }
+        end_frame();
+        remove_profiler();
+
+        // Not recorded
+        drop(span("paint"));
+
+        assert_eq!(*log.borrow(), ["+frame", "+layout", "-layout", "-frame", "frame"]);
+    }
+}
@@ -4,7 +4,7 @@ use std::ops::{ControlFlow, Deref};
 
 use anathema_geometry::{LocalPos, Pos, Rect, Size};
 use anathema_widgets::expressions::EvalValue;
-use anathema_widgets::layout::{Constraints, LayoutCtx, PositionCtx};
+use anathema_widgets::layout::{Constraints, ContentWidth, LayoutCtx, Length, PositionCtx};
 use anathema_widgets::paint::{PaintCtx, SizePos};
 use anathema_widgets::{
     AnyWidget, AttributeStorage, Attributes, LayoutChildren, PaintChildren, PositionChildren, Widget, WidgetId,
@@ -140,6 +140,8 @@ impl Display for BorderStyle {
     }
 }
 
+// Kept as `TryFrom`, as changing it to `From` would break code converting with `try_from`
+#[allow(clippy::infallible_try_from)]
 impl TryFrom<&EvalValue<'_>> for BorderStyle {
     type Error = Infallible;
 
@@ -256,13 +258,13 @@ impl BorderPainter {
             start_cap: (border_size.bottom_left > 0).then(|| Brush::new(glyphs[6], border_size.bottom_left)),
             middle: (border_size.bottom > 0).then(|| Brush::new(glyphs[5], border_size.bottom)),
             end_cap: (border_size.bottom_right > 0).then(|| Brush::new(glyphs[4], border_size.bottom_right)),
-            start: LocalPos::new(0, height as u16 - 1),
+            start: LocalPos::new(0, (height as u16).saturating_sub(1)),
             axis: Axis::Horizontal,
             end: size.width as u16,
         };
 
         if bottom.will_draw() {
-            height -= 1;
+            height = height.saturating_sub(1);
         }
 
         let mut offset = 0;
@@ -283,7 +285,7 @@ impl BorderPainter {
             start_cap: None,
             middle: (border_size.right > 0).then(|| Brush::new(glyphs[3], border_size.right)),
             end_cap: None,
-            start: LocalPos::new((size.width - border_size.right as usize) as u16, offset),
+            start: LocalPos::new(size.width.saturating_sub(border_size.right as usize) as u16, offset),
             axis: Axis::Vertical,
             end: height as u16,
         };
@@ -353,11 +355,23 @@ pub struct Border {
     /// This means the top-left corner is `edges[0]`, the top if `edges[1]` and the top right is
     /// `edges[2]` etc.
     edges: [char; 8],
+    /// The baseline of the child, below the top of the border
+    baseline: Option<usize>,
 }
 
 impl Border {
     // The additional size of the border
     // to subtract from the constraint.
+    fn load(&mut self, attributes: &Attributes<'_>) {
+        self.sides = attributes
+            .get_val("sides")
+            .and_then(|s| Sides::try_from(s.deref()).ok())
+            .unwrap_or_default();
+
+        self.border_style = attributes.get_ref(BORDER_STYLE).unwrap_or_default();
+        self.edges = self.border_style.edges();
+    }
+
     fn border_size(&self, sides: Sides) -> BorderSize {
         // Get the size of the border (thickness).
         // This is NOT including the child.
@@ -409,25 +423,48 @@ impl Widget for Border {
         ctx: &mut LayoutCtx<'_, 'bp>,
     ) -> Size {
         let attributes = ctx.attribs.get(id);
-        self.sides = attributes
-            .get_val("sides")
-            .and_then(|s| Sides::try_from(s.deref()).ok())
-            .unwrap_or_default();
-
-        self.border_style = attributes.get_ref(BORDER_STYLE).unwrap_or_default();
-        self.edges = self.border_style.edges();
+        self.load(attributes);
 
+        let (max_width, max_height) = (constraints.max_width(), constraints.max_height());
         let mut layout = BorderLayout {
-            min_width: attributes.get_usize(MIN_WIDTH),
-            min_height: attributes.get_usize(MIN_HEIGHT),
-            max_width: attributes.get_usize(MAX_WIDTH),
-            max_height: attributes.get_usize(MAX_HEIGHT),
-            height: attributes.get_usize(HEIGHT),
-            width: attributes.get_usize(WIDTH),
+            min_width: attributes.get_length(MIN_WIDTH, max_width),
+            min_height: attributes.get_length(MIN_HEIGHT, max_height),
+            max_width: attributes.get_length(MAX_WIDTH, max_width),
+            max_height: attributes.get_length(MAX_HEIGHT, max_height),
+            height: attributes.get_length(HEIGHT, max_height),
+            width: attributes.get_length(WIDTH, max_width),
             border_size: self.border_size(self.sides),
+            baseline: None,
         };
 
-        layout.layout(children, constraints, ctx)
+        let size = layout.layout(children, constraints, ctx);
+        self.baseline = layout.baseline;
+        size
+    }
+
+    fn measure<'bp>(
+        &mut self,
+        mut children: LayoutChildren<'_, '_, 'bp>,
+        id: WidgetId,
+        ctx: &mut LayoutCtx<'_, 'bp>,
+    ) -> ContentWidth {
+        let attributes = ctx.attribs.get(id);
+        if let Some(Length::Cells(width)) = attributes.get(WIDTH) {
+            return ContentWidth::fixed(width);
+        }
+
+        self.load(attributes);
+        let border_size = self.border_size(self.sides);
+        let mut width = ContentWidth::ZERO;
+        children.for_each(|child, children| {
+            width = child.measure(children, ctx);
+            ControlFlow::Break(())
+        });
+        width.pad((border_size.left + border_size.right) as usize)
+    }
+
+    fn baseline(&self) -> Option<usize> {
+        self.baseline
     }
 
     fn position<'bp>(
@@ -509,6 +546,7 @@ pub(crate) fn make(attributes: &Attributes<'_>) -> Box<dyn AnyWidget> {
         sides,
         edges: border_style.edges(),
         border_style,
+        baseline: None,
     };
     Box::new(text)
 }
@@ -168,11 +168,11 @@ impl Widget for Canvas {
     ) -> Size {
         let attribs = ctx.attribs.get(id);
 
-        if let Some(width) = attribs.get_usize(WIDTH) {
+        if let Some(width) = attribs.get_length(WIDTH, constraints.max_width()) {
             constraints.set_max_width(width);
         }
 
-        if let Some(height) = attribs.get_usize(HEIGHT) {
+        if let Some(height) = attribs.get_length(HEIGHT, constraints.max_height()) {
             constraints.set_max_height(height);
         }
 
@@ -1,13 +1,15 @@
 use std::ops::ControlFlow;
 
 use anathema_geometry::Size;
-use anathema_widgets::layout::{Constraints, LayoutCtx, PositionCtx};
+use anathema_widgets::layout::{Constraints, ContentWidth, LayoutCtx, Length, PositionCtx};
 use anathema_widgets::{AttributeStorage, LayoutChildren, PositionChildren, Widget, WidgetId};
 
+use crate::layout::baseline;
 use crate::{HEIGHT, MAX_HEIGHT, MAX_WIDTH, MIN_HEIGHT, MIN_WIDTH, WIDTH};
 
+/// The baseline of the child
 #[derive(Debug, Default)]
-pub struct Container;
+pub struct Container(Option<usize>);
 
 impl Widget for Container {
     fn layout<'bp>(
@@ -20,33 +22,35 @@ impl Widget for Container {
         let mut size = Size::ZERO;
 
         let attribs = ctx.attribs.get(id);
+        let (max_width, max_height) = (constraints.max_width(), constraints.max_height());
 
-        if let Some(width) = attribs.get_usize(WIDTH) {
+        if let Some(width) = attribs.get_length(WIDTH, max_width) {
             constraints.make_width_tight(width);
         }
 
-        if let Some(height) = attribs.get_usize(HEIGHT) {
+        if let Some(height) = attribs.get_length(HEIGHT, max_height) {
             constraints.make_height_tight(height);
         }
 
-        if let Some(width) = attribs.get_usize(MIN_WIDTH) {
+        if let Some(width) = attribs.get_length(MIN_WIDTH, max_width) {
             constraints.min_width = width;
         }
 
-        if let Some(height) = attribs.get_usize(MIN_HEIGHT) {
+        if let Some(height) = attribs.get_length(MIN_HEIGHT, max_height) {
             constraints.min_height = height;
         }
 
-        if let Some(width) = attribs.get_usize(MAX_WIDTH) {
+        if let Some(width) = attribs.get_length(MAX_WIDTH, max_width) {
             constraints.set_max_width(width);
         }
 
-        if let Some(height) = attribs.get_usize(MAX_HEIGHT) {
+        if let Some(height) = attribs.get_length(MAX_HEIGHT, max_height) {
             constraints.set_max_height(height);
         }
 
         children.for_each(|child, children| {
             size = child.layout(children, constraints, ctx);
+            self.0 = baseline(child);
             ControlFlow::Break(())
         });
 
@@ -56,6 +60,28 @@ impl Widget for Container {
         size
     }
 
+    fn measure<'bp>(
+        &mut self,
+        mut children: LayoutChildren<'_, '_, 'bp>,
+        id: WidgetId,
+        ctx: &mut LayoutCtx<'_, 'bp>,
+    ) -> ContentWidth {
+        if let Some(Length::Cells(width)) = ctx.attribs.get(id).get(WIDTH) {
+            return ContentWidth::fixed(width);
+        }
+
+        let mut width = ContentWidth::ZERO;
+        children.for_each(|child, children| {
+            width = child.measure(children, ctx);
+            ControlFlow::Break(())
+        });
+        width
+    }
+
+    fn baseline(&self) -> Option<usize> {
+        self.0
+    }
+
     fn position<'bp>(
         &mut self,
         mut children: PositionChildren<'_, '_, 'bp>,
@@ -90,4 +116,27 @@ mod test {
 
         TestRunner::new(tpl, (6, 2)).instance().render_assert(expected);
     }
+
+    #[test]
+    fn relative_size() {
+        let tpl = "
+            hstack
+                container [width: '50%', height: '1/2']
+                    border
+                        expand
+                container [width: '1/4']
+                    text 'b'
+        ";
+
+        let expected = "
+            ╔════════╗
+            ║┌──┐b   ║
+            ║└──┘    ║
+            ║        ║
+            ║        ║
+            ╚════════╝
+        ";
+
+        TestRunner::new(tpl, (8, 4)).instance().render_assert(expected);
+    }
 }
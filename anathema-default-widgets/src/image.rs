@@ -0,0 +1,255 @@
+// Draw images from the `image` crate on a canvas
+use ::image::imageops::FilterType;
+use ::image::{DynamicImage, RgbaImage};
+use anathema_backend::tui::{ColorDowngrade, Style};
+use anathema_backend::ColorDepth;
+use anathema_geometry::{LocalPos, Size};
+use anathema_state::Color;
+
+use crate::Canvas;
+
+// Pixels that are more transparent than this are not drawn
+const MIN_ALPHA: u8 = 128;
+
+// The braille dot of each pixel in a cell, by row and column
+const BRAILLE_DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+/// How the pixels of an image are drawn with cells
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum ImageCells {
+    /// Two pixels per cell, drawn as `▀` with the upper pixel as the foreground
+    /// and the lower pixel as the background
+    #[default]
+    HalfBlocks,
+    /// Two by four pixels per cell, drawn as braille dots with one colour per cell.
+    /// Dots are set for pixels at least as bright as the average of the image.
+    Braille,
+}
+
+impl ImageCells {
+    // The number of pixels in a cell
+    fn pixels(self) -> (u32, u32) {
+        match self {
+            Self::HalfBlocks => (1, 2),
+            Self::Braille => (2, 4),
+        }
+    }
+}
+
+/// How an image is drawn by [`Canvas::draw_image`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ImageOptions {
+    pub cells: ImageCells,
+    /// Quantize the colours to the palette of the colour depth,
+    /// e.g the colour depth in the capabilities of the backend
+    pub depth: ColorDepth,
+    pub downgrade: ColorDowngrade,
+}
+
+impl Default for ImageOptions {
+    fn default() -> Self {
+        Self {
+            cells: ImageCells::HalfBlocks,
+            depth: ColorDepth::TrueColor,
+            downgrade: ColorDowngrade::Nearest,
+        }
+    }
+}
+
+impl Canvas {
+    /// Scale the image to fit inside `size` (in cells), keeping the aspect ratio,
+    /// and draw it with the top left corner at `pos`.
+    /// Transparent pixels are not drawn.
+    ///
+    /// Returns the size of the drawn image in cells.
+    ///
+    /// ```ignore
+    /// let logo = image::open("logo.png")?;
+    /// canvas.draw_image(&logo, (0, 0), (20, 10), ImageOptions::default());
+    /// ```
+    pub fn draw_image(
+        &mut self,
+        image: &DynamicImage,
+        pos: impl Into<LocalPos>,
+        size: impl Into<Size>,
+        options: ImageOptions,
+    ) -> Size {
+        let pos = pos.into();
+        let size = size.into();
+        let (cell_width, cell_height) = options.cells.pixels();
+        let width = size.width as u32 * cell_width;
+        let height = size.height as u32 * cell_height;
+        if width == 0 || height == 0 {
+            return Size::ZERO;
+        }
+
+        let image = image.resize(width, height, FilterType::Triangle).into_rgba8();
+        let threshold = average_luminance(&image);
+        let cells = Size::new(
+            image.width().div_ceil(cell_width) as usize,
+            image.height().div_ceil(cell_height) as usize,
+        );
+
+        for y in 0..cells.height as u32 {
+            for x in 0..cells.width as u32 {
+                let cell = match options.cells {
+                    ImageCells::HalfBlocks => half_block(&image, x, y),
+                    ImageCells::Braille => braille(&image, x, y, threshold),
+                };
+
+                let Some((c, style)) = cell else { continue };
+                let style = style.downgrade(options.depth, options.downgrade);
+                self.put(c, style, LocalPos::new(pos.x + x as u16, pos.y + y as u16));
+            }
+        }
+
+        cells
+    }
+}
+
+// The colour of an opaque pixel
+fn pixel(image: &RgbaImage, x: u32, y: u32) -> Option<(u8, u8, u8)> {
+    let [r, g, b, a] = image.get_pixel_checked(x, y)?.0;
+    (a >= MIN_ALPHA).then_some((r, g, b))
+}
+
+fn luminance((r, g, b): (u8, u8, u8)) -> u32 {
+    (r as u32 * 299 + g as u32 * 587 + b as u32 * 114) / 1000
+}
+
+fn average_luminance(image: &RgbaImage) -> u32 {
+    let (sum, count) = (0..image.height())
+        .flat_map(|y| (0..image.width()).filter_map(move |x| pixel(image, x, y)))
+        .fold((0, 0), |(sum, count), rgb| (sum + luminance(rgb), count + 1));
+    sum.checked_div(count).unwrap_or(0)
+}
+
+fn half_block(image: &RgbaImage, x: u32, y: u32) -> Option<(char, Style)> {
+    let rgb = |(r, g, b)| Color::Rgb(r, g, b);
+    let mut style = Style::new();
+    let c = match (pixel(image, x, y * 2), pixel(image, x, y * 2 + 1)) {
+        (None, None) => return None,
+        (Some(top), None) => {
+            style.set_fg(rgb(top));
+            '▀'
+        }
+        (None, Some(bottom)) => {
+            style.set_fg(rgb(bottom));
+            '▄'
+        }
+        (Some(top), Some(bottom)) => {
+            style.set_fg(rgb(top));
+            style.set_bg(rgb(bottom));
+            '▀'
+        }
+    };
+    Some((c, style))
+}
+
+fn braille(image: &RgbaImage, x: u32, y: u32, threshold: u32) -> Option<(char, Style)> {
+    let mut dots = 0;
+    let mut sum = (0, 0, 0);
+    let mut count = 0;
+
+    for (row, row_dots) in BRAILLE_DOTS.iter().enumerate() {
+        for (column, dot) in row_dots.iter().enumerate() {
+            let Some(rgb) = pixel(image, x * 2 + column as u32, y * 4 + row as u32) else { continue };
+            if luminance(rgb) < threshold {
+                continue;
+            }
+            dots |= dot;
+            sum = (sum.0 + rgb.0 as u32, sum.1 + rgb.1 as u32, sum.2 + rgb.2 as u32);
+            count += 1;
+        }
+    }
+
+    if count == 0 {
+        return None;
+    }
+
+    let mut style = Style::new();
+    style.set_fg(Color::Rgb(
+        (sum.0 / count) as u8,
+        (sum.1 / count) as u8,
+        (sum.2 / count) as u8,
+    ));
+    let c = char::from_u32(0x2800 + dots).expect("braille patterns are valid chars");
+    Some((c, style))
+}
+
+#[cfg(test)]
+mod test {
+    use ::image::Rgba;
+
+    use super::*;
+
+    const RED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+    const BLUE: Rgba<u8> = Rgba([0, 0, 255, 255]);
+    const CLEAR: Rgba<u8> = Rgba([0, 0, 0, 0]);
+
+    fn image(pixels: &[&[Rgba<u8>]]) -> DynamicImage {
+        let mut image = RgbaImage::new(pixels[0].len() as u32, pixels.len() as u32);
+        for (y, row) in pixels.iter().enumerate() {
+            for (x, pixel) in row.iter().enumerate() {
+                image.put_pixel(x as u32, y as u32, *pixel);
+            }
+        }
+        image.into()
+    }
+
+    #[test]
+    fn half_blocks() {
+        let image = image(&[&[RED, CLEAR], &[BLUE, RED]]);
+        let mut canvas = Canvas::default();
+        let size = canvas.draw_image(&image, (1, 0), (2, 1), ImageOptions::default());
+        assert_eq!(size, Size::new(2, 1));
+
+        let (c, style) = canvas.get((1, 0)).unwrap();
+        assert_eq!(c, '▀');
+        assert_eq!(style.fg, Some(Color::Rgb(255, 0, 0)));
+        assert_eq!(style.bg, Some(Color::Rgb(0, 0, 255)));
+
+        let (c, style) = canvas.get((2, 0)).unwrap();
+        assert_eq!(c, '▄');
+        assert_eq!(style.fg, Some(Color::Rgb(255, 0, 0)));
+        assert_eq!(style.bg, None);
+    }
+
+    #[test]
+    fn braille_dots() {
+        let image = image(&[&[RED, CLEAR], &[CLEAR, RED], &[CLEAR, CLEAR], &[RED, CLEAR]]);
+        let options = ImageOptions {
+            cells: ImageCells::Braille,
+            ..Default::default()
+        };
+        let mut canvas = Canvas::default();
+        let size = canvas.draw_image(&image, (0, 0), (1, 1), options);
+        assert_eq!(size, Size::new(1, 1));
+
+        let (c, style) = canvas.get((0, 0)).unwrap();
+        assert_eq!(c, '⡑');
+        assert_eq!(style.fg, Some(Color::Rgb(255, 0, 0)));
+    }
+
+    #[test]
+    fn quantize_colours() {
+        let image = image(&[&[Rgba([250, 5, 5, 255])]]);
+        let options = ImageOptions {
+            depth: ColorDepth::Ansi16,
+            ..Default::default()
+        };
+        let mut canvas = Canvas::default();
+        canvas.draw_image(&image, (0, 0), (1, 1), options);
+
+        let (_, style) = canvas.get((0, 0)).unwrap();
+        assert_eq!(style.fg, Some(Color::AnsiVal(9)));
+    }
+
+    #[test]
+    fn scale_to_fit() {
+        let image = DynamicImage::new_rgba8(40, 10);
+        let mut canvas = Canvas::default();
+        let size = canvas.draw_image(&image, (0, 0), (8, 8), ImageOptions::default());
+        assert_eq!(size, Size::new(8, 1));
+    }
+}
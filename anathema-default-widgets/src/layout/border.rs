@@ -4,6 +4,7 @@ use anathema_geometry::Size;
 use anathema_widgets::layout::{Constraints, LayoutCtx};
 use anathema_widgets::LayoutChildren;
 
+use super::baseline;
 use crate::border::BorderSize;
 
 pub struct BorderLayout {
@@ -14,6 +15,8 @@ pub struct BorderLayout {
     pub width: Option<usize>,
     pub height: Option<usize>,
     pub border_size: BorderSize,
+    /// The baseline of the child, set by the layout
+    pub baseline: Option<usize>,
 }
 
 impl BorderLayout {
@@ -74,6 +77,7 @@ impl BorderLayout {
             child_constraints.sub_max_width((border_size.left + border_size.right) as usize);
             child_constraints.sub_max_height((border_size.top + border_size.bottom) as usize);
             let mut child_size = child.layout(children, child_constraints, ctx);
+            self.baseline = baseline(child).map(|baseline| baseline + border_size.top as usize);
             child_size += border_size.as_size();
             size.width = child_size.width.max(size.width);
             size.height = child_size.height.max(size.height);
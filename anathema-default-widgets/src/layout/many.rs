@@ -1,10 +1,10 @@
 use std::ops::ControlFlow;
 
 use anathema_geometry::Size;
-use anathema_widgets::layout::{Constraints, LayoutCtx};
+use anathema_widgets::layout::{Constraints, ContentWidth, LayoutCtx};
 use anathema_widgets::LayoutChildren;
 
-use super::{expand, spacers, Axis, Direction};
+use super::{baseline, expand, spacers, Axis, Direction};
 
 pub(crate) struct SizeMod {
     inner: Size,
@@ -44,9 +44,10 @@ impl SizeMod {
     }
 
     fn to_constraints(&self) -> Constraints {
+        let remaining = self.max_size.saturating_sub(self.inner);
         match self.axis {
-            Axis::Horizontal => Constraints::new(self.max_size.width - self.inner.width, self.max_size.height),
-            Axis::Vertical => Constraints::new(self.max_size.width, self.max_size.height - self.inner.height),
+            Axis::Horizontal => Constraints::new(remaining.width, self.max_size.height),
+            Axis::Vertical => Constraints::new(self.max_size.width, remaining.height),
         }
     }
 
@@ -58,6 +59,13 @@ impl SizeMod {
 pub struct Many {
     pub direction: Direction,
     pub axis: Axis,
+    /// Share the width between the children of a horizontal layout
+    /// according to their content, rather than in order
+    pub fit_content: bool,
+    /// Line up the children of a horizontal layout on their baselines
+    pub align_baseline: bool,
+    /// The baseline of the layout, if the children are aligned on their baselines
+    pub baseline: Option<usize>,
     unconstrained: bool,
     pub(crate) used_size: SizeMod,
 }
@@ -67,6 +75,9 @@ impl Many {
         Self {
             direction,
             axis,
+            fit_content: false,
+            align_baseline: false,
+            baseline: None,
             unconstrained,
             used_size: SizeMod::ZERO,
         }
@@ -87,6 +98,16 @@ impl Many {
 
         let mut size = Size::ZERO;
 
+        let widths = match self.fit_content && self.axis == Axis::Horizontal && !self.unconstrained {
+            true => fit_content(&mut children, max_constraints.max_width(), ctx),
+            false => vec![],
+        };
+        let mut index = 0;
+
+        let align_baseline = self.align_baseline && self.axis == Axis::Horizontal;
+        // The most rows above and below the baseline of any child
+        let (mut above, mut below) = (None::<usize>, 0);
+
         children.for_each(|node, children| {
             if ["spacer", "expand"].contains(&node.ident) {
                 return ControlFlow::Continue(());
@@ -100,11 +121,20 @@ impl Many {
                         Axis::Horizontal => constraints.unbound_width(),
                     }
                 }
+                if let Some(width) = widths.get(index) {
+                    constraints.set_max_width(constraints.max_width().min(*width));
+                }
+                index += 1;
                 constraints
             };
 
             let widget_size = node.layout(children, widget_constraints, ctx);
 
+            if let Some(baseline) = baseline(node).filter(|_| align_baseline) {
+                above = Some(above.unwrap_or(0).max(baseline));
+                below = below.max(node.size().height.saturating_sub(baseline + 1));
+            }
+
             self.used_size.apply(widget_size);
 
             match self.used_size.no_space_left() {
@@ -113,6 +143,14 @@ impl Many {
             }
         });
 
+        // The children are moved down to line up their baselines,
+        // so the layout is as tall as the most rows above and below the baseline
+        self.baseline = above;
+        if let Some(above) = above {
+            let height = (above + 1 + below).min(self.used_size.max_size.height);
+            self.used_size.inner.height = self.used_size.inner.height.max(height);
+        }
+
         // Apply spacer and expand if the layout is constrained and we have remaining space
         if !self.unconstrained && !self.used_size.no_space_left() {
             let constraints = self.used_size.to_constraints();
@@ -155,3 +193,78 @@ impl Many {
         size
     }
 }
+
+// Measure the children and give each one at least its min-content width.
+// The remaining width is shared in proportion to how much wider
+// the children would like to be.
+fn fit_content<'bp>(
+    children: &mut LayoutChildren<'_, '_, 'bp>,
+    available: usize,
+    ctx: &mut LayoutCtx<'_, 'bp>,
+) -> Vec<usize> {
+    let mut widths = vec![];
+    children.for_each(|node, children| {
+        if !["spacer", "expand"].contains(&node.ident) {
+            widths.push(node.measure(children, ctx));
+        }
+        ControlFlow::Continue(())
+    });
+
+    distribute(&widths, available)
+}
+
+fn distribute(widths: &[ContentWidth], available: usize) -> Vec<usize> {
+    let min = widths.iter().map(|w| w.min).sum::<usize>();
+    let max = widths.iter().map(|w| w.max).sum::<usize>();
+
+    if max <= available {
+        return widths.iter().map(|w| w.max).collect();
+    }
+
+    if min >= available {
+        return widths.iter().map(|w| w.min).collect();
+    }
+
+    let space = available - min;
+    let wanted = max - min;
+    let mut output = widths
+        .iter()
+        .map(|w| w.min + (w.max - w.min) * space / wanted)
+        .collect::<Vec<_>>();
+
+    // Hand out what is left after rounding down
+    let mut left = available - output.iter().sum::<usize>();
+    for (width, content) in output.iter_mut().zip(widths) {
+        if left == 0 {
+            break;
+        }
+        if *width < content.max {
+            *width += 1;
+            left -= 1;
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn distribute_width() {
+        let widths = [
+            ContentWidth::new(2, 10),
+            ContentWidth::new(4, 4),
+            ContentWidth::new(1, 5),
+        ];
+
+        // Enough space for all the content
+        assert_eq!(distribute(&widths, 20), vec![10, 4, 5]);
+        // Not even enough space for the min-content
+        assert_eq!(distribute(&widths, 5), vec![2, 4, 1]);
+        // 6 cells are shared 8:0:4
+        assert_eq!(distribute(&widths, 13), vec![6, 4, 3]);
+        assert_eq!(distribute(&widths, 14), vec![7, 4, 3]);
+    }
+}
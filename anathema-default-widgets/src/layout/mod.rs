@@ -3,10 +3,13 @@ use std::ops::ControlFlow;
 use anathema::CommonVal;
 use anathema_geometry::Size;
 use anathema_widgets::layout::{Constraints, LayoutCtx};
-use anathema_widgets::LayoutChildren;
+use anathema_widgets::{Element, LayoutChildren};
 
 pub static DIRECTION: &str = "direction";
 pub static AXIS: &str = "axis";
+pub static FIT_CONTENT: &str = "fit_content";
+pub static ALIGN: &str = "align";
+pub static BASELINE: &str = "baseline";
 
 pub(crate) mod alignment;
 pub(crate) mod border;
@@ -29,6 +32,13 @@ pub(crate) fn single_layout<'bp>(
     size
 }
 
+// The baseline of an element that was laid out.
+// Elements without a baseline sit on their bottom row.
+pub(crate) fn baseline(element: &Element<'_>) -> Option<usize> {
+    let last_row = element.size().height.checked_sub(1)?;
+    Some(element.baseline().unwrap_or(last_row).min(last_row))
+}
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum Axis {
     Horizontal,
@@ -54,6 +64,15 @@ pub enum Direction {
     Backward,
 }
 
+impl Direction {
+    pub(crate) fn reverse(self) -> Self {
+        match self {
+            Self::Forward => Self::Backward,
+            Self::Backward => Self::Forward,
+        }
+    }
+}
+
 impl TryFrom<CommonVal<'_>> for Direction {
     type Error = ();
 
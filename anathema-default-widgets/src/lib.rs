@@ -7,9 +7,13 @@ mod border;
 mod canvas;
 mod container;
 mod expand;
+#[cfg(feature = "image")]
+mod image;
 mod layout;
+mod log_view;
 mod overflow;
 mod padding;
+mod portal;
 mod position;
 mod spacer;
 mod stacks;
@@ -33,18 +37,25 @@ pub use alignment::Align;
 pub use border::Border;
 pub use canvas::Canvas;
 pub use expand::Expand;
+pub use log_view::LogView;
 pub use overflow::Overflow;
 pub use padding::Padding;
+pub use portal::Portal;
 pub use position::Position;
 pub use stacks::{Column, HStack, Row, VStack};
 pub use text::Text;
 
+#[cfg(feature = "image")]
+pub use self::image::{ImageCells, ImageOptions};
+
 pub fn register_default_widgets(factory: &mut Factory) {
     factory.register_default::<alignment::Align>("align");
     factory.register_default::<expand::Expand>("expand");
     factory.register_default::<canvas::Canvas>("canvas");
     factory.register_default::<container::Container>("container");
+    factory.register_default::<log_view::LogView>("log_view");
     factory.register_default::<padding::Padding>("padding");
+    factory.register_default::<portal::Portal>("portal");
     factory.register_default::<position::Position>("position");
     factory.register_default::<stacks::Column>("column");
     factory.register_default::<spacer::Spacer>("spacer");
@@ -0,0 +1,149 @@
+use anathema_backend::tui::Style;
+use anathema_geometry::{LocalPos, Size};
+use anathema_state::{AnyState, Color, Path};
+use anathema_widgets::expressions::EvalValue;
+use anathema_widgets::layout::{Constraints, LayoutCtx, PositionCtx};
+use anathema_widgets::paint::{PaintCtx, SizePos};
+use anathema_widgets::{
+    glyphs, AttributeStorage, Attributes, LayoutChildren, PaintChildren, PositionChildren, Widget, WidgetId,
+};
+
+use crate::{HEIGHT, WIDTH};
+
+const RECORDS: &str = "records";
+const LEVEL: &str = "level";
+const FILTER: &str = "filter";
+
+const LEVELS: [&str; 5] = ["error", "warn", "info", "debug", "trace"];
+
+/// Displays log records, with the newest record at the bottom.
+///
+/// The records are a list in the state, where every record has a `level`,
+/// a `target` and a `message`, e.g the state of the log capture in the runtime.
+///
+/// ```text
+/// log_view [records: records, level: "info", filter: "net"]
+/// ```
+///
+/// * `level`: the least severe level to show, defaults to showing all records
+/// * `filter`: only show records where the target or the message contains the filter
+///
+/// One record is shown per line, cut off at the width of the widget.
+#[derive(Debug, Default)]
+pub struct LogView;
+
+impl Widget for LogView {
+    fn layout<'bp>(
+        &mut self,
+        _children: LayoutChildren<'_, '_, 'bp>,
+        mut constraints: Constraints,
+        id: WidgetId,
+        ctx: &mut LayoutCtx<'_, 'bp>,
+    ) -> Size {
+        let attribs = ctx.attribs.get(id);
+
+        if let Some(width) = attribs.get_length(WIDTH, constraints.max_width()) {
+            constraints.set_max_width(width);
+        }
+
+        if let Some(height) = attribs.get_length(HEIGHT, constraints.max_height()) {
+            constraints.set_max_height(height);
+        }
+
+        constraints.max_size()
+    }
+
+    fn position<'bp>(
+        &mut self,
+        _: PositionChildren<'_, '_, 'bp>,
+        _: WidgetId,
+        _: &AttributeStorage<'bp>,
+        _: PositionCtx,
+    ) {
+        // The log view widget has no children
+    }
+
+    fn paint<'bp>(
+        &mut self,
+        _: PaintChildren<'_, '_, 'bp>,
+        id: WidgetId,
+        attribute_storage: &AttributeStorage<'bp>,
+        mut ctx: PaintCtx<'_, SizePos>,
+    ) {
+        let attributes = attribute_storage.get(id);
+        let level = string(attributes, LEVEL)
+            .and_then(|level| severity(&level))
+            .unwrap_or(LEVELS.len());
+        let filter = string(attributes, FILTER).unwrap_or_default();
+
+        let Some(EvalValue::Dyn(records)) = attributes.get_val(RECORDS).map(|value| &**value) else {
+            return;
+        };
+        let Some(records) = records.as_state() else { return };
+
+        let mut lines = vec![];
+        for index in 0..records.count() {
+            let Some(record) = records.state_lookup(Path::Index(index)) else { continue };
+            let (record_level, target, message) = record.as_state(|record| {
+                (
+                    field(record, "level"),
+                    field(record, "target"),
+                    field(record, "message"),
+                )
+            });
+
+            let severity = severity(&record_level).unwrap_or(LEVELS.len());
+            if severity > level || !(target.contains(&filter) || message.contains(&filter)) {
+                continue;
+            }
+
+            lines.push((
+                severity,
+                format!("{:<5} {target}: {message}", record_level.to_uppercase()),
+            ));
+        }
+
+        let height = ctx.local_size.height;
+        let width = ctx.local_size.width;
+        let skip = lines.len().saturating_sub(height);
+
+        for (y, (severity, line)) in lines.into_iter().skip(skip).enumerate() {
+            let mut style = Style::new();
+            match severity {
+                0 => style.set_fg(Color::Red),
+                1 => style.set_fg(Color::Yellow),
+                2 => {}
+                _ => style.set_fg(Color::DarkGrey),
+            }
+
+            let mut x = 0;
+            for glyph in glyphs::glyphs(&line) {
+                let glyph_width = glyphs::width(glyph);
+                if x + glyph_width > width {
+                    break;
+                }
+
+                let pos = LocalPos::new(x as u16, y as u16);
+                ctx.set_attributes(&style, pos);
+                ctx.place_glyphs(glyph, pos);
+                x += glyph_width;
+            }
+        }
+    }
+}
+
+fn string(attributes: &Attributes<'_>, key: &str) -> Option<String> {
+    let value = attributes.get_val(key)?.load_common_val()?;
+    Some(value.to_common()?.to_common_str().to_string())
+}
+
+fn field(record: &dyn AnyState, key: &str) -> String {
+    record
+        .state_lookup(Path::Key(key))
+        .and_then(|value| value.as_state(|value| value.to_common().map(|value| value.to_string())))
+        .unwrap_or_default()
+}
+
+fn severity(level: &str) -> Option<usize> {
+    LEVELS.iter().position(|l| l.eq_ignore_ascii_case(level))
+}
@@ -1,9 +1,9 @@
-use std::ops::ControlFlow;
+use std::ops::{ControlFlow, Range};
 
 use anathema_geometry::{Pos, Size};
 use anathema_widgets::layout::{Constraints, LayoutCtx, PositionCtx};
 use anathema_widgets::paint::{PaintCtx, SizePos};
-use anathema_widgets::{AttributeStorage, LayoutChildren, PositionChildren, Widget, WidgetId};
+use anathema_widgets::{AttributeStorage, Attributes, LayoutChildren, PositionChildren, Widget, WidgetId};
 
 use crate::layout::many::Many;
 use crate::layout::{Axis, Direction, AXIS, DIRECTION};
@@ -11,6 +11,7 @@ use crate::{HEIGHT, WIDTH};
 
 const UNCONSTRAINED: &str = "unconstrained";
 const CLAMP: &str = "clamp";
+const VIRTUAL: &str = "virtual";
 
 #[derive(Debug, Default)]
 pub struct Overflow {
@@ -20,6 +21,15 @@ pub struct Overflow {
 
     direction: Direction,
     is_dirty: bool,
+
+    // The size of the overflow since the last position call
+    viewport: Size,
+    // The iterations generated by a loop inside a virtual overflow,
+    // out of the number of iterations in the loop
+    generated: Range<usize>,
+    count: usize,
+    // The size of one iteration along the axis, measured by the last layout call
+    extent: usize,
 }
 
 impl Overflow {
@@ -126,11 +136,11 @@ impl Widget for Overflow {
             constraints.unbound_height();
         }
 
-        if let Some(width) = attributes.get_usize(WIDTH) {
+        if let Some(width) = attributes.get_length(WIDTH, output_size.width) {
             constraints.make_width_tight(width);
         }
 
-        if let Some(height) = attributes.get_usize(HEIGHT) {
+        if let Some(height) = attributes.get_length(HEIGHT, output_size.height) {
             constraints.make_height_tight(height);
         }
 
@@ -144,6 +154,18 @@ impl Widget for Overflow {
 
         self.inner_size = many.used_size.inner_size();
 
+        // Only the generated iterations are laid out,
+        // but the overflow scrolls through all of them
+        if attributes.get_bool(VIRTUAL) && !self.generated.is_empty() {
+            let generated = self.generated.len();
+            let used = match axis {
+                Axis::Horizontal => &mut self.inner_size.width,
+                Axis::Vertical => &mut self.inner_size.height,
+            };
+            self.extent = used.div_ceil(generated);
+            *used = self.extent * self.count;
+        }
+
         output_size
     }
 
@@ -172,9 +194,21 @@ impl Widget for Overflow {
             }
         }
 
+        self.viewport = ctx.inner_size;
+
+        // The iterations in front of the generated ones are skipped
+        let skipped = match attributes.get_bool(VIRTUAL) {
+            true => (self.generated.start * self.extent) as i32,
+            false => 0,
+        };
+        let skipped = match axis {
+            Axis::Horizontal => Pos::new(skipped, 0),
+            Axis::Vertical => Pos::new(0, skipped),
+        };
+
         let mut pos = match direction {
-            Direction::Forward => pos - self.offset,
-            Direction::Backward => pos + self.offset,
+            Direction::Forward => pos - self.offset + skipped,
+            Direction::Backward => pos + self.offset - skipped,
         };
 
         children.for_each(|node, children| {
@@ -218,6 +252,39 @@ impl Widget for Overflow {
     fn needs_reflow(&self) -> bool {
         self.is_dirty
     }
+
+    fn on_scroll(&mut self, delta: Pos) -> bool {
+        self.scroll(Direction::Forward, delta);
+        true
+    }
+
+    // With `virtual: true` only the iterations of the loop that are scrolled into view are generated.
+    // Every iteration is assumed to be the same size as the ones that are generated.
+    fn visible_iterations(&self, count: usize, attributes: &Attributes<'_>) -> Option<Range<usize>> {
+        if !attributes.get_bool(VIRTUAL) {
+            return None;
+        }
+
+        let (offset, viewport) = match attributes.get(AXIS).unwrap_or(Axis::Vertical) {
+            Axis::Horizontal => (self.offset.x, self.viewport.width),
+            Axis::Vertical => (self.offset.y, self.viewport.height),
+        };
+
+        // Generate a single iteration to measure, until the overflow is laid out
+        if self.extent == 0 || viewport == 0 {
+            return Some(0..count.min(1));
+        }
+
+        let offset = offset.max(0) as usize;
+        let start = (offset / self.extent).min(count);
+        let end = (offset + viewport).div_ceil(self.extent).min(count);
+        Some(start..end)
+    }
+
+    fn generated_iterations(&mut self, generated: Range<usize>, count: usize) {
+        self.generated = generated;
+        self.count = count;
+    }
 }
 
 #[cfg(test)]
@@ -292,4 +359,65 @@ mod test {
             })
             .render_assert(expected_first);
     }
+
+    #[test]
+    fn virtual_overflow() {
+        let tpl = "
+    overflow [virtual: true]
+        for i in [0, 1, 2, 3, 4, 5, 6, 7, 8, 9]
+            text i
+";
+
+        let expected_first = "
+    ╔═╗
+    ║0║
+    ║1║
+    ║2║
+    ╚═╝
+";
+
+        let expected_second = "
+    ╔═╗
+    ║5║
+    ║6║
+    ║7║
+    ╚═╝
+";
+
+        let expected_last = "
+    ╔═╗
+    ║7║
+    ║8║
+    ║9║
+    ╚═╝
+";
+
+        // Only the visible iterations are generated
+        let generated = |mut query: anathema_widgets::Elements<'_, '_>| {
+            let mut count = 0;
+            query.by_tag("text").each(|_, _| count += 1);
+            assert_eq!(count, 3);
+        };
+
+        TestRunner::new(tpl, (1, 3))
+            .instance()
+            .render_assert(expected_first)
+            .with_widget(generated)
+            .with_widget(|mut query| {
+                query.by_tag("overflow").first(|el, _| {
+                    let overflow = el.to::<Overflow>();
+                    overflow.scroll_down_by(5);
+                });
+            })
+            .render_assert(expected_second)
+            .with_widget(generated)
+            .with_widget(|mut query| {
+                query.by_tag("overflow").first(|el, _| {
+                    let overflow = el.to::<Overflow>();
+                    overflow.scroll_down_by(100);
+                });
+            })
+            .render_assert(expected_last)
+            .with_widget(generated);
+    }
 }
@@ -1,32 +1,30 @@
 use std::ops::ControlFlow;
 
-use anathema_geometry::{Pos, Rect, Size};
-use anathema_widgets::layout::{Constraints, LayoutCtx, PositionCtx};
-use anathema_widgets::{AttributeStorage, LayoutChildren, PositionChildren, Widget, WidgetId};
+use anathema_geometry::{Edges, Pos, Rect, Size};
+use anathema_widgets::layout::{Constraints, ContentWidth, LayoutCtx, PositionCtx};
+use anathema_widgets::{AttributeStorage, Attributes, LayoutChildren, PositionChildren, Widget, WidgetId};
 
+use crate::layout::baseline;
 use crate::{BOTTOM, LEFT, RIGHT, TOP};
 
 const PADDING: &str = "padding";
 
-#[derive(Default)]
-struct PaddingValues {
-    top: u16,
-    right: u16,
-    bottom: u16,
-    left: u16,
-}
+// The padding, with each edge overriding the shorthand
+fn load_padding(attributes: &Attributes<'_>) -> Edges {
+    let padding = attributes.get_edges(PADDING).unwrap_or_default();
+    let side = |key, default| attributes.get_usize(key).unwrap_or(default);
 
-impl PaddingValues {
-    fn size(&self) -> Size {
-        Size {
-            height: (self.top + self.bottom) as usize,
-            width: (self.left + self.right) as usize,
-        }
+    Edges {
+        top: side(TOP, padding.top),
+        right: side(RIGHT, padding.right),
+        bottom: side(BOTTOM, padding.bottom),
+        left: side(LEFT, padding.left),
     }
 }
 
+/// The padding and the baseline of the child (below the top padding)
 #[derive(Default)]
-pub struct Padding(PaddingValues);
+pub struct Padding(Edges, Option<usize>);
 
 impl Widget for Padding {
     fn layout<'bp>(
@@ -36,26 +34,12 @@ impl Widget for Padding {
         id: WidgetId,
         ctx: &mut LayoutCtx<'_, 'bp>,
     ) -> Size {
-        let attributes = ctx.attribs.get(id);
         let mut size = Size::ZERO;
-        let padding = attributes.get(PADDING).unwrap_or(0);
-
-        self.0.top = attributes
-            .get_usize(TOP)
-            .and_then(|v| v.try_into().ok())
-            .unwrap_or(padding);
-        self.0.right = attributes
-            .get_usize(RIGHT)
-            .and_then(|v| v.try_into().ok())
-            .unwrap_or(padding);
-        self.0.bottom = attributes
-            .get_usize(BOTTOM)
-            .and_then(|v| v.try_into().ok())
-            .unwrap_or(padding);
-        self.0.left = attributes
-            .get_usize(LEFT)
-            .and_then(|v| v.try_into().ok())
-            .unwrap_or(padding);
+        self.0 = load_padding(ctx.attribs.get(id));
+        // Right to left: the left padding is on the right side
+        if ctx.direction.is_rtl() {
+            self.0.mirror();
+        }
 
         let padding_size = self.0.size();
 
@@ -64,6 +48,7 @@ impl Widget for Padding {
             child_constraints.sub_max_width(padding_size.width);
             child_constraints.sub_max_height(padding_size.height);
             let mut child_size = child.layout(children, child_constraints, ctx);
+            self.1 = baseline(child).map(|baseline| baseline + self.0.top);
             child_size += padding_size;
             size.width = child_size.width.max(size.width);
             size.height = child_size.height.max(size.height);
@@ -77,6 +62,21 @@ impl Widget for Padding {
         size
     }
 
+    fn measure<'bp>(
+        &mut self,
+        mut children: LayoutChildren<'_, '_, 'bp>,
+        id: WidgetId,
+        ctx: &mut LayoutCtx<'_, 'bp>,
+    ) -> ContentWidth {
+        let padding = load_padding(ctx.attribs.get(id));
+        let mut width = ContentWidth::ZERO;
+        children.for_each(|child, children| {
+            width = child.measure(children, ctx);
+            ControlFlow::Break(())
+        });
+        width.pad(padding.horizontal())
+    }
+
     fn position<'bp>(
         &mut self,
         mut children: PositionChildren<'_, '_, 'bp>,
@@ -113,12 +113,12 @@ impl Widget for Padding {
         });
     }
 
-    fn inner_bounds(&self, mut pos: Pos, mut size: Size) -> Rect {
-        pos.x += self.0.left as i32;
-        pos.y += self.0.top as i32;
-        size.width = size.width.saturating_sub(self.0.right as usize);
-        size.height = size.height.saturating_sub(self.0.bottom as usize);
-        Rect::from((pos, size))
+    fn baseline(&self) -> Option<usize> {
+        self.1
+    }
+
+    fn inner_bounds(&self, pos: Pos, size: Size) -> Rect {
+        Rect::from((pos, size)).inset(self.0)
     }
 }
 
@@ -145,6 +145,26 @@ mod test {
         TestRunner::new(tpl, (3, 3)).instance().render_assert(expected);
     }
 
+    #[test]
+    fn padding_shorthand() {
+        let tpl = "
+            hstack
+                padding [padding: '1 2']
+                    text 'a'
+                text '|'
+        ";
+
+        let expected = "
+            ╔══════╗
+            ║     |║
+            ║  a   ║
+            ║      ║
+            ╚══════╝
+        ";
+
+        TestRunner::new(tpl, (6, 3)).instance().render_assert(expected);
+    }
+
     #[test]
     fn padding_top_inclusive() {
         let tpl = "
@@ -0,0 +1,114 @@
+use std::ops::ControlFlow;
+
+use anathema_geometry::Size;
+use anathema_widgets::layout::{Constraints, LayoutCtx, PositionCtx};
+use anathema_widgets::paint::{PaintCtx, SizePos};
+use anathema_widgets::{AttributeStorage, LayoutChildren, PaintChildren, PositionChildren, Widget, WidgetId};
+
+/// Render the children inside an outlet elsewhere in the tree.
+///
+/// The children are laid out, positioned and painted inside the element
+/// with an `outlet` attribute matching the `target` attribute of the portal.
+/// If there is no such outlet the portal behaves like any other floating widget.
+///
+/// The children are still evaluated where the portal is declared,
+/// so they have access to the same state.
+#[derive(Debug, Default)]
+pub struct Portal;
+
+impl Widget for Portal {
+    fn floats(&self) -> bool {
+        true
+    }
+
+    fn layout<'bp>(
+        &mut self,
+        mut children: LayoutChildren<'_, '_, 'bp>,
+        constraints: Constraints,
+        _: WidgetId,
+        ctx: &mut LayoutCtx<'_, 'bp>,
+    ) -> Size {
+        let mut size = Size::ZERO;
+        children.for_each(|child, children| {
+            let child_size = child.layout(children, constraints, ctx);
+            size.width = size.width.max(child_size.width);
+            size.height = size.height.max(child_size.height);
+            ControlFlow::Continue(())
+        });
+        size
+    }
+
+    fn position<'bp>(
+        &mut self,
+        mut children: PositionChildren<'_, '_, 'bp>,
+        _: WidgetId,
+        attribute_storage: &AttributeStorage<'bp>,
+        ctx: PositionCtx,
+    ) {
+        children.for_each(|child, children| {
+            child.position(children, ctx.pos, attribute_storage, ctx.viewport);
+            ControlFlow::Continue(())
+        });
+    }
+
+    fn paint<'bp>(
+        &mut self,
+        mut children: PaintChildren<'_, '_, 'bp>,
+        _id: WidgetId,
+        attribute_storage: &AttributeStorage<'bp>,
+        mut ctx: PaintCtx<'_, SizePos>,
+    ) {
+        children.for_each(|child, children| {
+            let mut ctx = ctx.to_unsized();
+            ctx.clip = None;
+            child.paint(children, ctx, attribute_storage);
+            ControlFlow::Continue(())
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::testing::TestRunner;
+
+    #[test]
+    fn portal_into_outlet() {
+        let tpl = "
+            vstack
+                text 'a'
+                container [outlet: 'overlay']
+                    text '   '
+                portal [target: 'overlay']
+                    text 'b'
+        ";
+
+        let expected = "
+            ╔═══╗
+            ║a  ║
+            ║b  ║
+            ║   ║
+            ╚═══╝
+        ";
+
+        TestRunner::new(tpl, (3, 3)).instance().render_assert(expected);
+    }
+
+    #[test]
+    fn portal_without_outlet() {
+        let tpl = "
+            vstack
+                text 'a'
+                portal [target: 'missing']
+                    text 'b'
+        ";
+
+        let expected = "
+            ╔═══╗
+            ║b  ║
+            ║   ║
+            ╚═══╝
+        ";
+
+        TestRunner::new(tpl, (3, 2)).instance().render_assert(expected);
+    }
+}
@@ -1,16 +1,24 @@
 use std::ops::ControlFlow;
 
 use anathema::CommonVal;
-use anathema_geometry::{Pos, Size};
-use anathema_widgets::layout::{Constraints, LayoutCtx, PositionCtx};
+use anathema_geometry::{Edges, Pos, Size};
+use anathema_widgets::layout::{Constraints, DiagnosticKind, LayoutCtx, PositionCtx};
 use anathema_widgets::paint::{PaintCtx, SizePos};
-use anathema_widgets::{AttributeStorage, LayoutChildren, PaintChildren, PositionChildren, Widget, WidgetId};
+use anathema_widgets::{
+    AttributeStorage, Attributes, LayoutChildren, PaintChildren, PositionChildren, Widget, WidgetId,
+};
 
+use crate::layout::alignment::Alignment;
 use crate::{BOTTOM, LEFT, RIGHT, TOP};
 
 const RELATIVE: &str = "relative";
 const ABSOLUTE: &str = "absolute";
 const PLACEMENT: &str = "placement";
+const ANCHOR: &str = "anchor";
+const MARGIN: &str = "margin";
+const MARGIN_X: &str = "margin_x";
+const MARGIN_Y: &str = "margin_y";
+const STRETCH: &str = "stretch";
 
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum HorzEdge {
@@ -48,11 +56,98 @@ impl TryFrom<CommonVal<'_>> for Placement {
     }
 }
 
+/// The widget is placed at an edge, a corner or the centre of the viewport,
+/// once the rest of the layout is done.
+///
+/// ```text
+/// position [anchor: "bottom_right", margin: 1]
+///     text "saved"
+///
+/// position [anchor: "top_left", margin: "1 2"]
+///     text "status"
+///
+/// position [anchor: "top", stretch: true]
+///     text "menu"
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Anchor {
+    alignment: Alignment,
+    /// The distance from each edge of the viewport
+    margin: Edges,
+    /// Fill the edge the widget is anchored to,
+    /// e.g the entire width for the top and bottom edge
+    stretch: bool,
+}
+
+impl Anchor {
+    fn load(attributes: &Attributes<'_>) -> Option<Self> {
+        let alignment = attributes.get(ANCHOR)?;
+        let mut margin = attributes.get_edges(MARGIN).unwrap_or_default();
+        if let Some(margin_x) = attributes.get_usize(MARGIN_X) {
+            margin.left = margin_x;
+            margin.right = margin_x;
+        }
+        if let Some(margin_y) = attributes.get_usize(MARGIN_Y) {
+            margin.top = margin_y;
+            margin.bottom = margin_y;
+        }
+
+        Some(Self {
+            alignment,
+            margin,
+            stretch: attributes.get_bool(STRETCH),
+        })
+    }
+
+    // The constraints of the child, inside the margins of the viewport
+    fn constraints(&self, viewport: Size) -> Constraints {
+        let mut constraints = Constraints::new(viewport.width, viewport.height);
+        constraints.sub_max_width(self.margin.horizontal());
+        constraints.sub_max_height(self.margin.vertical());
+
+        if self.stretch {
+            match self.alignment {
+                Alignment::Top | Alignment::Bottom => constraints.make_width_tight(constraints.max_width()),
+                Alignment::Left | Alignment::Right => constraints.make_height_tight(constraints.max_height()),
+                _ => {}
+            }
+        }
+
+        constraints
+    }
+
+    // The position of the child in the viewport
+    fn pos(&self, viewport: Size, size: Size) -> Pos {
+        let left = self.margin.left as i32;
+        let right = viewport.width as i32 - size.width as i32 - self.margin.right as i32;
+        let centre_x = (viewport.width as i32 - size.width as i32) / 2;
+        let top = self.margin.top as i32;
+        let bottom = viewport.height as i32 - size.height as i32 - self.margin.bottom as i32;
+        let centre_y = (viewport.height as i32 - size.height as i32) / 2;
+
+        let (x, y) = match self.alignment {
+            Alignment::TopLeft => (left, top),
+            Alignment::Top => (centre_x, top),
+            Alignment::TopRight => (right, top),
+            Alignment::Right => (right, centre_y),
+            Alignment::BottomRight => (right, bottom),
+            Alignment::Bottom => (centre_x, bottom),
+            Alignment::BottomLeft => (left, bottom),
+            Alignment::Left => (left, centre_y),
+            Alignment::Centre => (centre_x, centre_y),
+        };
+
+        Pos::new(x, y)
+    }
+}
+
 #[derive(Debug)]
 pub struct Position {
     horz_edge: HorzEdge,
     vert_edge: VertEdge,
     placement: Placement,
+    /// Anchored to the viewport, rather than placed with the edges
+    anchor: Option<Anchor>,
 }
 
 impl Default for Position {
@@ -61,6 +156,7 @@ impl Default for Position {
             horz_edge: HorzEdge::Left(0),
             vert_edge: VertEdge::Top(0),
             placement: Placement::Relative,
+            anchor: None,
         }
     }
 }
@@ -79,6 +175,17 @@ impl Widget for Position {
     ) -> Size {
         let attribs = ctx.attribs.get(id);
         self.placement = attribs.get(PLACEMENT).unwrap_or_default();
+        self.anchor = Anchor::load(attribs);
+
+        if let Some(anchor) = self.anchor {
+            let constraints = anchor.constraints(ctx.viewport.size());
+            let mut size = Size::ZERO;
+            children.for_each(|child, children| {
+                size = child.layout(children, constraints, ctx);
+                ControlFlow::Break(())
+            });
+            return size;
+        }
 
         self.horz_edge = match attribs.get_int(LEFT) {
             Some(left) => HorzEdge::Left(left as u32),
@@ -121,12 +228,12 @@ impl Widget for Position {
 
         size.width = match self.horz_edge {
             HorzEdge::Left(left) => size.width + left as usize,
-            HorzEdge::Right(right) => constraints.max_width() - right as usize,
+            HorzEdge::Right(right) => offset_from_edge(right, constraints.max_width(), ctx),
         };
 
         size.height = match self.vert_edge {
             VertEdge::Top(top) => size.height + top as usize,
-            VertEdge::Bottom(bottom) => constraints.max_height() - bottom as usize,
+            VertEdge::Bottom(bottom) => offset_from_edge(bottom, constraints.max_height(), ctx),
         };
 
         size
@@ -139,6 +246,15 @@ impl Widget for Position {
         attribute_storage: &AttributeStorage<'bp>,
         mut ctx: PositionCtx,
     ) {
+        if let Some(anchor) = self.anchor {
+            children.for_each(|child, children| {
+                let pos = anchor.pos(ctx.viewport.size(), child.size());
+                child.position(children, pos, attribute_storage, ctx.viewport);
+                ControlFlow::Break(())
+            });
+            return;
+        }
+
         if let Placement::Absolute = self.placement {
             ctx.pos = Pos::ZERO;
         }
@@ -185,6 +301,15 @@ impl Widget for Position {
     }
 }
 
+// The space left by an offset from the right or bottom edge
+fn offset_from_edge(offset: u32, available: usize, ctx: &mut LayoutCtx<'_, '_>) -> usize {
+    let offset = offset as usize;
+    if offset > available {
+        ctx.report(DiagnosticKind::OffsetOutOfBounds { offset, available });
+    }
+    available.saturating_sub(offset)
+}
+
 #[cfg(test)]
 mod test {
     use crate::testing::TestRunner;
@@ -307,4 +432,65 @@ mod test {
 
         TestRunner::new(tpl, (4, 2)).instance().render_assert(expected);
     }
+
+    #[test]
+    fn anchor_bottom_right() {
+        // The anchor is relative to the viewport, which includes the border of the test
+        let tpl = "
+            vstack
+                text 'a'
+                position [anchor: 'bottom_right', margin_x: 2, margin_y: 1]
+                    text 'hi'
+            ";
+
+        let expected = "
+            ╔══════╗
+            ║a     ║
+            ║      ║
+            ║      ║
+            ║   hi ║
+            ╚══════╝
+        ";
+
+        TestRunner::new(tpl, (6, 4)).instance().render_assert(expected);
+    }
+
+    #[test]
+    fn anchor_margin_shorthand() {
+        // The anchor is relative to the viewport, which includes the border of the test
+        let tpl = "
+            position [anchor: 'top_left', margin: '1 2 0 3']
+                text 'hi'
+            ";
+
+        let expected = "
+            ╔══════╗
+            ║  hi  ║
+            ║      ║
+            ║      ║
+            ╚══════╝
+        ";
+
+        TestRunner::new(tpl, (6, 3)).instance().render_assert(expected);
+    }
+
+    #[test]
+    fn anchor_stretch() {
+        let tpl = "
+            position [anchor: 'bottom', stretch: true]
+                border
+                    text 'a'
+            ";
+
+        let expected = "
+            ╔══════╗
+            ║      ║
+            ║      ║
+            ┌──────┐
+            │a     │
+            └──────┘
+        ";
+
+        TestRunner::new(tpl, (6, 4)).instance().render_assert(expected);
+    }
 }
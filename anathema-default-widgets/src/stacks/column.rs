@@ -1,7 +1,7 @@
 use std::ops::ControlFlow;
 
 use anathema_geometry::Size;
-use anathema_widgets::layout::{Constraints, LayoutCtx, PositionCtx};
+use anathema_widgets::layout::{Constraints, ContentWidth, LayoutCtx, PositionCtx};
 use anathema_widgets::{AttributeStorage, LayoutChildren, PositionChildren, Widget, WidgetId};
 
 use crate::layout::Axis;
@@ -11,7 +11,7 @@ pub struct Column(Stack);
 
 impl Default for Column {
     fn default() -> Self {
-        Self(Stack(Axis::Vertical))
+        Self(Stack::new(Axis::Vertical))
     }
 }
 
@@ -26,6 +26,15 @@ impl Widget for Column {
         self.0.layout(children, constraints, id, ctx)
     }
 
+    fn measure<'bp>(
+        &mut self,
+        children: LayoutChildren<'_, '_, 'bp>,
+        id: WidgetId,
+        ctx: &mut LayoutCtx<'_, 'bp>,
+    ) -> ContentWidth {
+        self.0.measure(children, id, ctx)
+    }
+
     fn position<'bp>(
         &mut self,
         mut children: PositionChildren<'_, '_, 'bp>,
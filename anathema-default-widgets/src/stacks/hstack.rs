@@ -1,5 +1,5 @@
 use anathema_geometry::Size;
-use anathema_widgets::layout::{Constraints, LayoutCtx, PositionCtx};
+use anathema_widgets::layout::{Constraints, ContentWidth, LayoutCtx, PositionCtx};
 use anathema_widgets::{AttributeStorage, LayoutChildren, PositionChildren, Widget, WidgetId};
 
 use super::Stack;
@@ -9,7 +9,7 @@ pub struct HStack(Stack);
 
 impl Default for HStack {
     fn default() -> Self {
-        HStack(Stack(Axis::Horizontal))
+        HStack(Stack::new(Axis::Horizontal))
     }
 }
 
@@ -24,6 +24,19 @@ impl Widget for HStack {
         self.0.layout(children, constraints, attributes, ctx)
     }
 
+    fn measure<'bp>(
+        &mut self,
+        children: LayoutChildren<'_, '_, 'bp>,
+        id: WidgetId,
+        ctx: &mut LayoutCtx<'_, 'bp>,
+    ) -> ContentWidth {
+        self.0.measure(children, id, ctx)
+    }
+
+    fn baseline(&self) -> Option<usize> {
+        self.0.baseline
+    }
+
     fn position<'bp>(
         &mut self,
         children: PositionChildren<'_, '_, 'bp>,
@@ -34,3 +47,77 @@ impl Widget for HStack {
         self.0.position(children, attributes, attribute_storage, ctx)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::testing::TestRunner;
+
+    #[test]
+    fn fit_content() {
+        let tpl = "
+            hstack [fit_content: true]
+                text 'one two three'
+                border
+                    text 'four five'
+        ";
+
+        let expected = "
+            ╔════════════════╗
+            ║one two  ┌─────┐║
+            ║three    │four │║
+            ║         │five │║
+            ║         └─────┘║
+            ╚════════════════╝
+        ";
+
+        TestRunner::new(tpl, (16, 4)).instance().render_assert(expected);
+    }
+
+    #[test]
+    fn baseline() {
+        let tpl = "
+            hstack [align: 'baseline']
+                text '██\n ██\n██'
+                border
+                    text 'abc'
+                text 'x'
+        ";
+
+        let expected = "
+            ╔═════════╗
+            ║██       ║
+            ║ ██┌───┐ ║
+            ║██ │abc│x║
+            ║   └───┘ ║
+            ╚═════════╝
+        ";
+
+        TestRunner::new(tpl, (9, 4)).instance().render_assert(expected);
+    }
+
+    #[test]
+    fn right_to_left() {
+        let tpl = "
+            vstack [dir: 'rtl']
+                hstack
+                    text 'a'
+                    text 'b'
+                    text 'c'
+                border [width: 8]
+                    padding [left: 1]
+                        text 'one\nthree'
+        ";
+
+        let expected = "
+            ╔══════════╗
+            ║cba       ║
+            ║┌──────┐  ║
+            ║│  one │  ║
+            ║│three │  ║
+            ║└──────┘  ║
+            ╚══════════╝
+        ";
+
+        TestRunner::new(tpl, (10, 5)).instance().render_assert(expected);
+    }
+}
@@ -1,7 +1,7 @@
 use std::ops::ControlFlow;
 
 use anathema_geometry::Size;
-use anathema_widgets::layout::{Constraints, LayoutCtx, PositionCtx};
+use anathema_widgets::layout::{Constraints, ContentWidth, LayoutCtx, Length, PositionCtx};
 use anathema_widgets::{AttributeStorage, LayoutChildren, PositionChildren, WidgetId};
 
 pub use self::column::Column;
@@ -10,7 +10,7 @@ pub use self::row::Row;
 pub use self::vstack::VStack;
 pub use self::zstack::ZStack;
 use crate::layout::many::Many;
-use crate::layout::{Axis, Direction, DIRECTION};
+use crate::layout::{baseline, Axis, Direction, ALIGN, BASELINE, DIRECTION, FIT_CONTENT};
 use crate::{HEIGHT, MIN_HEIGHT, MIN_WIDTH, WIDTH};
 
 mod column;
@@ -19,9 +19,23 @@ mod row;
 mod vstack;
 mod zstack;
 
-pub struct Stack(Axis);
+pub struct Stack {
+    axis: Axis,
+    /// The baseline, when the children are aligned on their baselines
+    baseline: Option<usize>,
+    /// The layout direction is right to left
+    rtl: bool,
+}
 
 impl Stack {
+    fn new(axis: Axis) -> Self {
+        Self {
+            axis,
+            baseline: None,
+            rtl: false,
+        }
+    }
+
     fn layout<'bp>(
         &mut self,
         children: LayoutChildren<'_, '_, 'bp>,
@@ -30,28 +44,56 @@ impl Stack {
         ctx: &mut LayoutCtx<'_, 'bp>,
     ) -> Size {
         let attributes = ctx.attribs.get(id);
+        let (max_width, max_height) = (constraints.max_width(), constraints.max_height());
 
-        if let Some(width) = attributes.get_usize(MIN_WIDTH) {
+        if let Some(width) = attributes.get_length(MIN_WIDTH, max_width) {
             constraints.min_width = width;
         }
 
-        if let Some(height) = attributes.get_usize(MIN_HEIGHT) {
+        if let Some(height) = attributes.get_length(MIN_HEIGHT, max_height) {
             constraints.min_height = height;
         }
 
-        if let Some(width) = attributes.get_usize(WIDTH) {
+        if let Some(width) = attributes.get_length(WIDTH, max_width) {
             constraints.make_width_tight(width);
         }
 
-        if let Some(height) = attributes.get_usize(HEIGHT) {
+        if let Some(height) = attributes.get_length(HEIGHT, max_height) {
             constraints.make_height_tight(height);
         }
 
         let dir = attributes.get(DIRECTION).unwrap_or_default();
         // Make `unconstrained` an enum instead of a `bool`
         let unconstrained = false;
-        let mut many = Many::new(dir, self.0, unconstrained);
-        many.layout(children, constraints, ctx)
+        let mut many = Many::new(dir, self.axis, unconstrained);
+        many.fit_content = attributes.get_bool(FIT_CONTENT);
+        many.align_baseline = attributes.get_ref::<&str>(ALIGN) == Some(BASELINE);
+        let size = many.layout(children, constraints, ctx);
+        self.baseline = many.baseline;
+        self.rtl = ctx.direction.is_rtl();
+        size
+    }
+
+    fn measure<'bp>(
+        &mut self,
+        mut children: LayoutChildren<'_, '_, 'bp>,
+        id: WidgetId,
+        ctx: &mut LayoutCtx<'_, 'bp>,
+    ) -> ContentWidth {
+        if let Some(Length::Cells(width)) = ctx.attribs.get(id).get(WIDTH) {
+            return ContentWidth::fixed(width);
+        }
+
+        let mut width = ContentWidth::ZERO;
+        children.for_each(|child, children| {
+            let child_width = child.measure(children, ctx);
+            width = match self.axis {
+                Axis::Horizontal => width.beside(child_width),
+                Axis::Vertical => width.widest(child_width),
+            };
+            ControlFlow::Continue(())
+        });
+        width
     }
 
     fn position<'bp>(
@@ -62,29 +104,39 @@ impl Stack {
         ctx: PositionCtx,
     ) {
         let attributes = attribute_storage.get(id);
-        let direction = attributes.get(DIRECTION).unwrap_or_default();
+        let mut direction: Direction = attributes.get(DIRECTION).unwrap_or_default();
+        // Right to left: horizontal stacks place their children from the right
+        if self.rtl && self.axis == Axis::Horizontal {
+            direction = direction.reverse();
+        }
         let mut pos = ctx.pos;
 
         if let Direction::Backward = direction {
-            match self.0 {
+            match self.axis {
                 Axis::Horizontal => pos.x += ctx.inner_size.width as i32,
                 Axis::Vertical => pos.y += ctx.inner_size.height as i32,
             }
         }
 
         children.for_each(|node, children| {
+            // Line up the baseline of the child with the baseline of the stack
+            if let Some(stack_baseline) = self.baseline {
+                let child_baseline = baseline(node).unwrap_or(0);
+                pos.y = ctx.pos.y + stack_baseline.saturating_sub(child_baseline) as i32;
+            }
+
             match direction {
                 Direction::Forward => {
                     node.position(children, pos, attribute_storage, ctx.viewport);
 
-                    match self.0 {
+                    match self.axis {
                         Axis::Horizontal => pos.x += node.size().width as i32,
                         Axis::Vertical => pos.y += node.size().height as i32,
                     }
                 }
                 Direction::Backward => {
-                    match self.0 {
-                        Axis::Horizontal => pos.x += node.size().width as i32,
+                    match self.axis {
+                        Axis::Horizontal => pos.x -= node.size().width as i32,
                         Axis::Vertical => pos.y -= node.size().height as i32,
                     }
 
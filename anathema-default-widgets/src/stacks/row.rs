@@ -1,7 +1,7 @@
 use std::ops::ControlFlow;
 
 use anathema_geometry::Size;
-use anathema_widgets::layout::{Constraints, LayoutCtx, PositionCtx};
+use anathema_widgets::layout::{Constraints, ContentWidth, LayoutCtx, PositionCtx};
 use anathema_widgets::{AttributeStorage, LayoutChildren, PositionChildren, Widget, WidgetId};
 
 use crate::layout::Axis;
@@ -11,7 +11,7 @@ pub struct Row(Stack);
 
 impl Default for Row {
     fn default() -> Self {
-        Self(Stack(Axis::Horizontal))
+        Self(Stack::new(Axis::Horizontal))
     }
 }
 
@@ -26,24 +26,54 @@ impl Widget for Row {
         self.0.layout(children, constraints, id, ctx)
     }
 
+    fn measure<'bp>(
+        &mut self,
+        children: LayoutChildren<'_, '_, 'bp>,
+        id: WidgetId,
+        ctx: &mut LayoutCtx<'_, 'bp>,
+    ) -> ContentWidth {
+        self.0.measure(children, id, ctx)
+    }
+
+    fn baseline(&self) -> Option<usize> {
+        self.0.baseline
+    }
+
     fn position<'bp>(
         &mut self,
         mut children: PositionChildren<'_, '_, 'bp>,
-        _: WidgetId,
+        id: WidgetId,
         attribute_storage: &AttributeStorage<'bp>,
         mut ctx: PositionCtx,
     ) {
+        // Children aligned on their baselines are not centred
+        if self.0.baseline.is_some() {
+            return self.0.position(children, id, attribute_storage, ctx);
+        }
+
         let y_offset = (ctx.inner_size.height / 2) as i32;
 
+        // Right to left: place the children from the right edge
+        let rtl = self.0.rtl;
+        if rtl {
+            ctx.pos.x += ctx.inner_size.width as i32;
+        }
+
         children.for_each(|child, children| {
             let size = child.size();
             let child_height = size.height as i32;
             let y = y_offset - child_height / 2;
 
+            if rtl {
+                ctx.pos.x -= size.width as i32;
+            }
+
             let mut pos = ctx.pos;
             pos.y += y;
             child.position(children, pos, attribute_storage, ctx.viewport);
-            ctx.pos.x += size.width as i32;
+            if !rtl {
+                ctx.pos.x += size.width as i32;
+            }
             ControlFlow::Continue(())
         });
     }
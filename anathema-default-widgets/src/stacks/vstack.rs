@@ -1,5 +1,5 @@
 use anathema_geometry::Size;
-use anathema_widgets::layout::{Constraints, LayoutCtx, PositionCtx};
+use anathema_widgets::layout::{Constraints, ContentWidth, LayoutCtx, PositionCtx};
 use anathema_widgets::{AttributeStorage, LayoutChildren, PositionChildren, Widget, WidgetId};
 
 use super::Stack;
@@ -9,7 +9,7 @@ pub struct VStack(Stack);
 
 impl Default for VStack {
     fn default() -> Self {
-        VStack(Stack(Axis::Vertical))
+        VStack(Stack::new(Axis::Vertical))
     }
 }
 
@@ -24,6 +24,15 @@ impl Widget for VStack {
         self.0.layout(children, constraints, attributes, ctx)
     }
 
+    fn measure<'bp>(
+        &mut self,
+        children: LayoutChildren<'_, '_, 'bp>,
+        id: WidgetId,
+        ctx: &mut LayoutCtx<'_, 'bp>,
+    ) -> ContentWidth {
+        self.0.measure(children, id, ctx)
+    }
+
     fn position<'bp>(
         &mut self,
         children: PositionChildren<'_, '_, 'bp>,
@@ -61,4 +61,22 @@ mod test {
 
         TestRunner::new(tpl, (3, 1)).instance().render_assert(expected);
     }
+
+    #[test]
+    fn z_index() {
+        let tpl = "
+            zstack
+                text [z: 2] '1'
+                text [z: -1] '333'
+                text '22'
+        ";
+
+        let expected = "
+            ╔═══╗
+            ║123║
+            ╚═══╝
+        ";
+
+        TestRunner::new(tpl, (3, 1)).instance().render_assert(expected);
+    }
 }
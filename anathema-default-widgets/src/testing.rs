@@ -1,15 +1,16 @@
 use anathema::{drain_changes, Changes};
-use anathema_backend::test::TestBackend;
-use anathema_backend::{Backend, WidgetCycle};
-use anathema_geometry::Size;
+use anathema_backend::testing::TestBackend;
+use anathema_backend::{Renderer, WidgetCycle};
+use anathema_geometry::{Pos, Size};
 use anathema_state::{State, StateId, States, Value};
 use anathema_templates::blueprints::Blueprint;
 use anathema_templates::{Document, Globals, ToSourceKind};
 use anathema_widgets::components::ComponentRegistry;
 use anathema_widgets::layout::{Constraints, Viewport};
+use anathema_widgets::paint::CursorShape;
 use anathema_widgets::{
-    eval_blueprint, update_tree, AttributeStorage, Components, DirtyWidgets, Elements, EvalContext, Factory,
-    FloatingWidgets, Scope, WidgetRenderer as _, WidgetTree,
+    eval_blueprint, update_loop_windows, update_tree, AttributeStorage, Components, DirtyWidgets, Elements,
+    EvalContext, Factory, FloatingWidgets, Scope, WidgetArena, WidgetRenderer as _, WidgetTree,
 };
 
 use crate::register_default_widgets;
@@ -18,6 +19,7 @@ pub struct TestRunner {
     states: States,
     component_registry: ComponentRegistry,
     factory: Factory,
+    arena: WidgetArena,
     backend: TestBackend,
     blueprint: Blueprint,
     globals: Globals,
@@ -52,6 +54,7 @@ impl TestRunner {
 
         Self {
             factory,
+            arena: WidgetArena::new(),
             backend: TestBackend::new(size),
             states,
             component_registry,
@@ -72,6 +75,7 @@ impl TestRunner {
         let mut ctx = EvalContext::new(
             &self.globals,
             &self.factory,
+            &self.arena,
             &mut scope,
             &mut self.states,
             &mut self.component_registry,
@@ -92,6 +96,7 @@ impl TestRunner {
             viewport,
             dirty_widgets: DirtyWidgets::empty(),
             factory: &self.factory,
+            arena: &self.arena,
             component_registry: &mut self.component_registry,
             components: &mut self.components,
             changes: Changes::empty(),
@@ -109,6 +114,7 @@ pub struct TestInstance<'bp> {
     viewport: Viewport,
     dirty_widgets: DirtyWidgets,
     factory: &'bp Factory,
+    arena: &'bp WidgetArena,
     component_registry: &'bp mut ComponentRegistry,
     components: &'bp mut Components,
     changes: Changes,
@@ -131,6 +137,7 @@ impl TestInstance<'_> {
                 update_tree(
                     self.globals,
                     self.factory,
+                    self.arena,
                     &mut scope,
                     self.states,
                     self.component_registry,
@@ -154,17 +161,47 @@ impl TestInstance<'_> {
         let (width, height) = self.backend.surface.size().into();
         let constraints = Constraints::new(width as usize, height as usize);
 
-        let attribute_storage = &self.attribute_storage;
-
         WidgetCycle::new(
             self.backend,
             &mut self.tree,
             constraints,
-            attribute_storage,
+            &self.attribute_storage,
             &self.floating_widgets,
             self.viewport,
         )
+        .invalidated(self.changes.iter().flat_map(|(subs, _)| subs.iter().map(Into::into)))
         .run();
+        self.changes.clear();
+
+        // Generate the iterations that became visible, and lay them out
+        let mut scope = Scope::new();
+        let changed = update_loop_windows(
+            self.globals,
+            self.factory,
+            self.arena,
+            &mut scope,
+            self.states,
+            self.component_registry,
+            &mut self.tree,
+            &mut self.attribute_storage,
+            &mut self.floating_widgets,
+            self.components,
+        );
+        if !changed.is_empty() {
+            changed.iter().for_each(|widget_id| self.dirty_widgets.push(*widget_id));
+            self.dirty_widgets.apply(&mut self.tree);
+            self.dirty_widgets.clear();
+            WidgetCycle::new(
+                self.backend,
+                &mut self.tree,
+                constraints,
+                &self.attribute_storage,
+                &self.floating_widgets,
+                self.viewport,
+            )
+            .invalidated(changed)
+            .run();
+        }
 
         self.backend.render();
 
@@ -177,6 +214,11 @@ impl TestInstance<'_> {
         self
     }
 
+    /// The cursor shown by the last render, in screen coordinates (including the border)
+    pub fn cursor(&self) -> Option<(Pos, CursorShape)> {
+        self.backend.grid.cursor()
+    }
+
     pub(crate) fn with_widget<F>(&mut self, mut f: F) -> &mut Self
     where
         F: FnMut(Elements<'_, '_>),
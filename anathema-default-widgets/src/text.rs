@@ -2,15 +2,19 @@ use std::ops::ControlFlow;
 
 use anathema_geometry::{LocalPos, Size};
 use anathema_state::CommonVal;
-use anathema_widgets::layout::text::{ProcessResult, Segment, Strings};
-use anathema_widgets::layout::{Constraints, LayoutCtx, PositionCtx};
-use anathema_widgets::paint::{PaintCtx, SizePos};
-use anathema_widgets::{AttributeStorage, LayoutChildren, PaintChildren, PositionChildren, Widget, WidgetId};
+use anathema_widgets::layout::text::{ContentMeasure, ProcessResult, Segment, Strings, Wrap};
+use anathema_widgets::layout::{Constraints, ContentWidth, LayoutCtx, PositionCtx};
+use anathema_widgets::paint::{CursorShape, PaintCtx, SizePos};
+use anathema_widgets::{
+    glyphs, AttributeStorage, Attributes, LayoutChildren, PaintChildren, PositionChildren, Widget, WidgetId,
+};
 
 use crate::{LEFT, RIGHT};
 
 pub(crate) const WRAP: &str = "wrap";
 pub(crate) const TEXT_ALIGN: &str = "text_align";
+pub(crate) const CURSOR: &str = "cursor";
+pub(crate) const CURSOR_SHAPE: &str = "cursor_shape";
 
 /// Text alignment aligns the text inside its parent.
 ///
@@ -38,6 +42,17 @@ pub enum TextAlignment {
     Right,
 }
 
+impl TextAlignment {
+    // The column a line starts at, inside of a widget of the given width
+    fn offset(self, width: usize, line_width: u16) -> u16 {
+        match self {
+            Self::Left => 0,
+            Self::Centre => width as u16 / 2 - line_width / 2,
+            Self::Right => width as u16 - line_width,
+        }
+    }
+}
+
 impl TryFrom<CommonVal<'_>> for TextAlignment {
     type Error = ();
 
@@ -61,14 +76,76 @@ impl TryFrom<CommonVal<'_>> for TextAlignment {
 /// * foreground
 /// * text-align
 /// * wrap
+/// * cursor
+/// * cursor_shape
 /// ```
 ///
+/// Setting `cursor` to the index of a character (grapheme cluster) shows the terminal cursor on that character
+/// (or after the text, if the index is the length of the text).
+/// The text is one cell wider while the `cursor` is set, to make room for the cursor after the text.
+/// The `cursor_shape` is either `block` (the default), `underline` or `bar`.
+///
 /// Note: Spans, unlike other widgets, does not require a widget id
 ///
 /// A `Text` widget will be as wide as its text.
 #[derive(Debug, Default)]
 pub struct Text {
     strings: Strings,
+    // The last line of the text
+    baseline: Option<usize>,
+    // The layout direction is right to left
+    rtl: bool,
+    // The text that was wrapped into `strings`
+    run: Run,
+    // Reused to collect the text on the next layout
+    next_run: Run,
+}
+
+/// The text of a `Text` widget and its spans, and the size of the wrapped lines.
+#[derive(Debug, Default)]
+struct Run {
+    text: String,
+    // The widget id of the style, and the byte offset where the style starts
+    styles: Vec<(WidgetId, usize)>,
+    wrap: Wrap,
+    max: Size,
+    size: Size,
+    // All the text fit within the max size
+    complete: bool,
+}
+
+impl Run {
+    fn clear(&mut self) {
+        self.text.clear();
+        self.styles.clear();
+    }
+
+    fn set_style(&mut self, widget_id: WidgetId) {
+        self.styles.push((widget_id, self.text.len()));
+    }
+
+    // True if the text is the same and wrapping it with the new max size
+    // would produce the same lines
+    fn fits(&self, other: &Run, wrap: Wrap, max: Size) -> bool {
+        if self.text != other.text || self.styles != other.styles || self.wrap != wrap || self.max.width != max.width {
+            return false;
+        }
+
+        // Only the height changed, which doesn't matter as long as no lines were cut off
+        self.max.height == max.height || (self.complete && self.size.height <= max.height)
+    }
+
+    // Wrap the text. Returns false if the text didn't fit
+    fn layout(&self, strings: &mut Strings) -> bool {
+        for (i, &(widget_id, start)) in self.styles.iter().enumerate() {
+            let end = self.styles.get(i + 1).map(|(_, end)| *end).unwrap_or(self.text.len());
+            strings.set_style(widget_id);
+            if let ProcessResult::Break = strings.add_str(&self.text[start..end]) {
+                return false;
+            }
+        }
+        true
+    }
 }
 
 impl Widget for Text {
@@ -81,39 +158,96 @@ impl Widget for Text {
     ) -> Size {
         let attributes = ctx.attribs.get(id);
         let wrap = attributes.get(WRAP).unwrap_or_default();
-        let size = constraints.max_size();
-        self.strings = Strings::new(size, wrap);
-        self.strings.set_style(id);
-
-        // Layout text
+        let has_cursor = attributes.get::<usize>(CURSOR).is_some();
+        let max = constraints.max_size();
+        self.rtl = ctx.direction.is_rtl();
+
+        // Collect the text and the style of each span
+        let mut run = std::mem::take(&mut self.next_run);
+        run.clear();
+        run.set_style(id);
         attributes.value().map(|value| {
-            value.str_iter(|s| match self.strings.add_str(s) {
-                ProcessResult::Break => ControlFlow::Break(()),
-                ProcessResult::Continue => ControlFlow::Continue(()),
+            value.str_iter(|s| {
+                run.text.push_str(s);
+                ControlFlow::Continue(())
             })
         });
 
-        // Layout text of all the sub-nodes
         children.for_each(|child, _| {
             let Some(_span) = child.try_to_ref::<Span>() else {
                 return ControlFlow::Continue(());
             };
-            self.strings.set_style(child.id());
+            run.set_style(child.id());
 
             let attributes = ctx.attribs.get(child.id());
-            if let Some(text) = attributes.value() {
-                text.str_iter(|s| match self.strings.add_str(s) {
-                    ProcessResult::Break => ControlFlow::Break(()),
-                    ProcessResult::Continue => ControlFlow::Continue(()),
-                })?;
+            match attributes.value() {
+                Some(text) => text.str_iter(|s| {
+                    run.text.push_str(s);
+                    ControlFlow::Continue(())
+                }),
+                None => ControlFlow::Break(()),
+            }
+        });
+
+        // Only wrap the text if it changed, or the lines don't fit the constraints
+        match self.run.fits(&run, wrap, max) {
+            true => self.next_run = run,
+            false => {
+                self.strings = Strings::new(max, wrap);
+                let complete = run.layout(&mut self.strings);
+                self.next_run = std::mem::replace(&mut self.run, run);
+                self.run.wrap = wrap;
+                self.run.max = max;
+                self.run.complete = complete;
+                self.run.size = self.strings.finish();
+            }
+        }
+
+        let mut size = self.run.size;
+        self.baseline = size.height.checked_sub(1);
+        // Leave room for the cursor after the text
+        if has_cursor {
+            size.width = (size.width + 1).min(constraints.max_width());
+        }
+        size
+    }
 
+    fn measure<'bp>(
+        &mut self,
+        mut children: LayoutChildren<'_, '_, 'bp>,
+        id: WidgetId,
+        ctx: &mut LayoutCtx<'_, 'bp>,
+    ) -> ContentWidth {
+        let attributes = ctx.attribs.get(id);
+        let mut measure = ContentMeasure::new(attributes.get(WRAP).unwrap_or_default());
+
+        attributes.value().map(|value| {
+            value.str_iter(|s| {
+                measure.add_str(s);
                 ControlFlow::Continue(())
-            } else {
-                ControlFlow::Break(())
+            })
+        });
+
+        children.for_each(|child, _| {
+            if child.try_to_ref::<Span>().is_none() {
+                return ControlFlow::Continue(());
             }
+
+            ctx.attribs.get(child.id()).value().map(|text| {
+                text.str_iter(|s| {
+                    measure.add_str(s);
+                    ControlFlow::Continue(())
+                })
+            });
+            ControlFlow::Continue(())
         });
 
-        self.strings.finish()
+        let width = measure.finish();
+        // Leave room for the cursor after the text
+        match attributes.get::<usize>(CURSOR) {
+            Some(_) => ContentWidth::new(width.min, width.max + 1),
+            None => width,
+        }
     }
 
     fn paint<'bp>(
@@ -124,23 +258,30 @@ impl Widget for Text {
         mut ctx: PaintCtx<'_, SizePos>,
     ) {
         let lines = self.strings.lines();
-        let alignment = attribute_storage.get(id).get(TEXT_ALIGN).unwrap_or_default();
+        let alignment = self.alignment(attribute_storage.get(id));
 
         let mut pos = LocalPos::ZERO;
         let mut style = attribute_storage.get(id);
 
-        for line in lines {
-            let x = match alignment {
-                TextAlignment::Left => 0,
-                TextAlignment::Centre => ctx.local_size.width as u16 / 2 - line.width / 2,
-                TextAlignment::Right => ctx.local_size.width as u16 - line.width,
-            };
+        let cursor = style.get::<usize>(CURSOR);
+        let cursor_shape = style.get::<CursorShape>(CURSOR_SHAPE).unwrap_or_default();
+        // The number of characters before the current segment
+        let mut index = 0;
+        let mut end = pos;
 
-            pos.x = x;
+        for line in lines {
+            pos.x = alignment.offset(ctx.local_size.width, line.width);
 
             for entry in line.entries {
                 match entry {
                     Segment::Str(s) => {
+                        if let Some(offset) = cursor.and_then(|cursor| cursor.checked_sub(index)) {
+                            if let Some(x) = cursor_x(s, offset) {
+                                ctx.set_cursor(LocalPos::new(pos.x + x, pos.y), cursor_shape);
+                            }
+                        }
+                        index += glyphs::glyphs(s).count();
+
                         if let Some(new_pos) = ctx.place_glyphs(s, pos) {
                             // NOTE:
                             // This isn't very nice, but it works for now.
@@ -150,6 +291,7 @@ impl Widget for Text {
                                 ctx.set_attributes(style, (x, pos.y).into());
                             }
                             pos = new_pos;
+                            end = new_pos;
                         }
                     }
                     Segment::SetStyle(attribute_id) => style = attribute_storage.get(attribute_id),
@@ -158,6 +300,32 @@ impl Widget for Text {
             pos.y += 1;
             pos.x = 0;
         }
+
+        if cursor == Some(index) {
+            ctx.set_cursor(end, cursor_shape);
+        }
+    }
+
+    fn baseline(&self) -> Option<usize> {
+        self.baseline
+    }
+
+    fn text(&self, size: Size, attributes: &Attributes<'_>) -> Vec<(u16, String)> {
+        let alignment = self.alignment(attributes);
+        self.strings
+            .lines()
+            .map(|line| {
+                let x = alignment.offset(size.width, line.width);
+                let text = line
+                    .entries
+                    .filter_map(|entry| match entry {
+                        Segment::Str(s) => Some(s),
+                        Segment::SetStyle(_) => None,
+                    })
+                    .collect();
+                (x, text)
+            })
+            .collect()
     }
 
     fn position<'bp>(
@@ -172,6 +340,32 @@ impl Widget for Text {
     }
 }
 
+impl Text {
+    // The alignment of the lines.
+    // Right to left: the text starts on the right
+    fn alignment(&self, attributes: &Attributes<'_>) -> TextAlignment {
+        let alignment = attributes.get(TEXT_ALIGN).unwrap_or_default();
+        match (self.rtl, alignment) {
+            (true, TextAlignment::Left) => TextAlignment::Right,
+            (true, TextAlignment::Right) => TextAlignment::Left,
+            _ => alignment,
+        }
+    }
+}
+
+// The offset of the glyph at `index` from the start of the string,
+// if the glyph is in the string
+fn cursor_x(s: &str, index: usize) -> Option<u16> {
+    let mut x = 0;
+    for (i, glyph) in glyphs::glyphs(s).enumerate() {
+        if i == index {
+            return Some(x);
+        }
+        x += glyphs::width(glyph) as u16;
+    }
+    None
+}
+
 #[derive(Default, Copy, Clone)]
 pub struct Span;
 
@@ -201,6 +395,12 @@ impl Widget for Span {
 
 #[cfg(test)]
 mod test {
+    use anathema_geometry::{Pos, Size};
+    use anathema_widgets::layout::text::{Strings, Wrap};
+    use anathema_widgets::paint::CursorShape;
+    use anathema_widgets::WidgetId;
+
+    use super::Run;
     use crate::testing::TestRunner;
 
     #[test]
@@ -328,4 +528,91 @@ mod test {
 
         TestRunner::new(src, (9, 3)).instance().render_assert(expected);
     }
+
+    #[test]
+    fn cursor() {
+        let src = "text [cursor: 2, cursor_shape: 'bar'] 'one'";
+        let expected = r#"
+               ╔═════╗
+               ║one  ║
+               ╚═════╝
+           "#;
+
+        let mut runner = TestRunner::new(src, (5, 1));
+        let mut instance = runner.instance();
+        instance.render_assert(expected);
+        assert_eq!(instance.cursor(), Some((Pos::new(3, 1), CursorShape::Bar)));
+    }
+
+    #[test]
+    fn cursor_after_glyphs() {
+        // The test backend only draws the first char of a glyph
+        let src = "text [cursor: 2] 'e\u{301}e\u{301}x'";
+        let expected = r#"
+               ╔═════╗
+               ║eex  ║
+               ╚═════╝
+           "#;
+
+        let mut runner = TestRunner::new(src, (5, 1));
+        let mut instance = runner.instance();
+        instance.render_assert(expected);
+        assert_eq!(instance.cursor(), Some((Pos::new(3, 1), CursorShape::Block)));
+    }
+
+    #[test]
+    fn cursor_after_text() {
+        let src = r#"
+            text [cursor: 6] 'one'
+                span 'two'
+        "#;
+        let expected = r#"
+               ╔═══════╗
+               ║onetwo ║
+               ╚═══════╝
+           "#;
+
+        let mut runner = TestRunner::new(src, (7, 1));
+        let mut instance = runner.instance();
+        instance.render_assert(expected);
+        assert_eq!(instance.cursor(), Some((Pos::new(7, 1), CursorShape::Block)));
+    }
+
+    #[test]
+    fn cached_run() {
+        let text = WidgetId::from((0, 0));
+        let span = WidgetId::from((1, 0));
+
+        let mut run = Run::default();
+        run.set_style(text);
+        run.text.push_str("hello ");
+        run.set_style(span);
+        run.text.push_str("world");
+
+        let max = Size::new(5, 10);
+        let mut strings = Strings::new(max, Wrap::Normal);
+        run.complete = run.layout(&mut strings);
+        run.size = strings.finish();
+        run.max = max;
+        assert!(run.complete);
+        assert_eq!(run.size, Size::new(5, 2));
+
+        let mut next = Run::default();
+        next.set_style(text);
+        next.text.push_str("hello ");
+        next.set_style(span);
+        next.text.push_str("world");
+
+        // Same text and width, and all the lines still fit
+        assert!(run.fits(&next, Wrap::Normal, Size::new(5, 2)));
+        // The lines no longer fit
+        assert!(!run.fits(&next, Wrap::Normal, Size::new(5, 1)));
+        // A different width or wrap
+        assert!(!run.fits(&next, Wrap::Normal, Size::new(6, 10)));
+        assert!(!run.fits(&next, Wrap::WordBreak, max));
+
+        // The same text with a different style
+        next.styles[1].1 = 5;
+        assert!(!run.fits(&next, Wrap::Normal, max));
+    }
 }
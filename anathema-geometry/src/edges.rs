@@ -0,0 +1,113 @@
+use std::str::FromStr;
+
+use crate::Size;
+
+/// The distance from each edge of a rect,
+/// e.g padding or a margin.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct Edges {
+    /// Top
+    pub top: usize,
+    /// Right
+    pub right: usize,
+    /// Bottom
+    pub bottom: usize,
+    /// Left
+    pub left: usize,
+}
+
+/// The edges used to shrink a rect, see [`crate::Rect::inset`]
+pub type Insets = Edges;
+
+impl Edges {
+    /// Zero
+    pub const ZERO: Self = Self::new(0, 0, 0, 0);
+
+    /// Create new edges, in the order of top, right, bottom and left
+    pub const fn new(top: usize, right: usize, bottom: usize, left: usize) -> Self {
+        Self {
+            top,
+            right,
+            bottom,
+            left,
+        }
+    }
+
+    /// The same distance on all edges
+    pub const fn uniform(value: usize) -> Self {
+        Self::new(value, value, value, value)
+    }
+
+    /// The sum of the left and right edge
+    pub const fn horizontal(&self) -> usize {
+        self.left + self.right
+    }
+
+    /// The sum of the top and bottom edge
+    pub const fn vertical(&self) -> usize {
+        self.top + self.bottom
+    }
+
+    /// The space taken up by the edges
+    pub const fn size(&self) -> Size {
+        Size::new(self.horizontal(), self.vertical())
+    }
+
+    /// Swap the left and right edge,
+    /// e.g for right to left layouts
+    pub fn mirror(&mut self) {
+        std::mem::swap(&mut self.left, &mut self.right);
+    }
+}
+
+/// Parse the shorthand for edges, where the values are separated by whitespace:
+/// * `"1"`: all edges
+/// * `"1 2"`: top and bottom, left and right
+/// * `"1 2 3"`: top, left and right, bottom
+/// * `"1 2 3 4"`: top, right, bottom, left
+///
+/// ```
+/// # use anathema_geometry::Edges;
+/// assert_eq!("1 2".parse(), Ok(Edges::new(1, 2, 1, 2)));
+/// assert_eq!("1 2 3 4".parse(), Ok(Edges::new(1, 2, 3, 4)));
+/// ```
+impl FromStr for Edges {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut values = [0; 4];
+        let mut len = 0;
+
+        for value in s.split_whitespace() {
+            let slot = values.get_mut(len).ok_or(())?;
+            *slot = value.parse().map_err(|_| ())?;
+            len += 1;
+        }
+
+        match (len, values) {
+            (1, [all, ..]) => Ok(Self::uniform(all)),
+            (2, [vert, horz, ..]) => Ok(Self::new(vert, horz, vert, horz)),
+            (3, [top, horz, bottom, _]) => Ok(Self::new(top, horz, bottom, horz)),
+            (4, [top, right, bottom, left]) => Ok(Self::new(top, right, bottom, left)),
+            _ => Err(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_edges() {
+        assert_eq!("2".parse(), Ok(Edges::uniform(2)));
+        assert_eq!(" 1  2 ".parse(), Ok(Edges::new(1, 2, 1, 2)));
+        assert_eq!("1 2 3".parse(), Ok(Edges::new(1, 2, 3, 2)));
+        assert_eq!("1 2 3 4".parse(), Ok(Edges::new(1, 2, 3, 4)));
+
+        assert_eq!("".parse::<Edges>(), Err(()));
+        assert_eq!("1 2 3 4 5".parse::<Edges>(), Err(()));
+        assert_eq!("1 -2".parse::<Edges>(), Err(()));
+        assert_eq!("a".parse::<Edges>(), Err(()));
+    }
+}
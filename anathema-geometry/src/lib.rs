@@ -1,8 +1,10 @@
+pub use crate::edges::{Edges, Insets};
 pub use crate::position::{LocalPos, Pos};
 pub use crate::rect::Rect;
 pub use crate::region::Region;
 pub use crate::size::Size;
 
+mod edges;
 mod position;
 mod rect;
 mod region;
@@ -24,6 +24,48 @@ impl Pos {
     pub const fn new(x: i32, y: i32) -> Self {
         Self { x, y }
     }
+
+    /// Add two positions, returning `None` on overflow
+    pub const fn checked_add(self, other: Self) -> Option<Self> {
+        match (self.x.checked_add(other.x), self.y.checked_add(other.y)) {
+            (Some(x), Some(y)) => Some(Self::new(x, y)),
+            _ => None,
+        }
+    }
+
+    /// Subtract a position, returning `None` on overflow
+    pub const fn checked_sub(self, other: Self) -> Option<Self> {
+        match (self.x.checked_sub(other.x), self.y.checked_sub(other.y)) {
+            (Some(x), Some(y)) => Some(Self::new(x, y)),
+            _ => None,
+        }
+    }
+
+    /// Add two positions, clamping each coordinate at the numeric bounds
+    pub const fn saturating_add(self, other: Self) -> Self {
+        Self::new(self.x.saturating_add(other.x), self.y.saturating_add(other.y))
+    }
+
+    /// Subtract a position, clamping each coordinate at the numeric bounds
+    pub const fn saturating_sub(self, other: Self) -> Self {
+        Self::new(self.x.saturating_sub(other.x), self.y.saturating_sub(other.y))
+    }
+
+    /// Convert to a local position, clamping negative coordinates to zero
+    /// and coordinates that are too large to `u16::MAX`
+    pub const fn to_local_clamped(self) -> LocalPos {
+        const fn clamp(val: i32) -> u16 {
+            if val < 0 {
+                0
+            } else if val > u16::MAX as i32 {
+                u16::MAX
+            } else {
+                val as u16
+            }
+        }
+
+        LocalPos::new(clamp(self.x), clamp(self.y))
+    }
 }
 
 impl Default for Pos {
@@ -154,6 +196,29 @@ impl LocalPos {
         self.y = self.y.saturating_sub(other.y);
         self
     }
+
+    /// Add two positions, clamping each coordinate at `u16::MAX`
+    pub const fn saturating_add(mut self, other: Self) -> Self {
+        self.x = self.x.saturating_add(other.x);
+        self.y = self.y.saturating_add(other.y);
+        self
+    }
+
+    /// Add two positions, returning `None` on overflow
+    pub const fn checked_add(self, other: Self) -> Option<Self> {
+        match (self.x.checked_add(other.x), self.y.checked_add(other.y)) {
+            (Some(x), Some(y)) => Some(Self::new(x, y)),
+            _ => None,
+        }
+    }
+
+    /// Subtract a position, returning `None` if either coordinate would be negative
+    pub const fn checked_sub(self, other: Self) -> Option<Self> {
+        match (self.x.checked_sub(other.x), self.y.checked_sub(other.y)) {
+            (Some(x), Some(y)) => Some(Self::new(x, y)),
+            _ => None,
+        }
+    }
 }
 
 impl From<(u16, u16)> for LocalPos {
@@ -198,3 +263,30 @@ impl AddAssign for LocalPos {
         self.y += rhs.y;
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn saturating_and_checked() {
+        let pos = Pos::new(i32::MAX, -1);
+        assert_eq!(pos.checked_add(Pos::new(1, 1)), None);
+        assert_eq!(pos.saturating_add(Pos::new(1, 1)), Pos::new(i32::MAX, 0));
+        assert_eq!(
+            Pos::new(0, i32::MIN).saturating_sub(Pos::new(1, 1)),
+            Pos::new(-1, i32::MIN)
+        );
+
+        let local = LocalPos::new(1, u16::MAX);
+        assert_eq!(local.checked_sub(LocalPos::new(2, 0)), None);
+        assert_eq!(local.checked_add(LocalPos::new(0, 1)), None);
+        assert_eq!(local.saturating_add(LocalPos::new(1, 1)), LocalPos::new(2, u16::MAX));
+    }
+
+    #[test]
+    fn clamp_to_local() {
+        assert_eq!(Pos::new(-5, 3).to_local_clamped(), LocalPos::new(0, 3));
+        assert_eq!(Pos::new(70_000, 0).to_local_clamped(), LocalPos::new(u16::MAX, 0));
+    }
+}
@@ -1,16 +1,136 @@
-use crate::{Pos, Size};
+use crate::{Edges, Pos, Size};
 
-#[derive(Debug, Copy, Clone)]
+/// A rectangle in global space, from `start` (inclusive) to `end` (exclusive)
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct Rect {
+    /// The top left corner
     pub start: Pos,
+    /// The bottom right corner (exclusive)
     pub end: Pos,
 }
 
 impl Rect {
+    /// Zero
     pub const ZERO: Self = Self {
         start: Pos::ZERO,
         end: Pos::ZERO,
     };
+
+    /// Create a new instance of a rect
+    pub const fn new(start: Pos, end: Pos) -> Self {
+        Self { start, end }
+    }
+
+    /// The size of the rect.
+    /// A rect where the end is before the start has a size of zero
+    pub fn size(&self) -> Size {
+        Size::new(
+            (self.end.x - self.start.x).max(0) as usize,
+            (self.end.y - self.start.y).max(0) as usize,
+        )
+    }
+
+    /// True if the rect does not cover any cells
+    pub const fn is_empty(&self) -> bool {
+        self.end.x <= self.start.x || self.end.y <= self.start.y
+    }
+
+    /// Check if the rect contains a position
+    pub const fn contains(&self, pos: Pos) -> bool {
+        pos.x >= self.start.x && pos.x < self.end.x && pos.y >= self.start.y && pos.y < self.end.y
+    }
+
+    /// Check if another rect is entirely inside this rect.
+    /// An empty rect is contained by every rect.
+    pub const fn contains_rect(&self, other: &Rect) -> bool {
+        if other.is_empty() {
+            return true;
+        }
+
+        other.start.x >= self.start.x
+            && other.start.y >= self.start.y
+            && other.end.x <= self.end.x
+            && other.end.y <= self.end.y
+    }
+
+    /// The area covered by both rects, or `None` if they don't overlap
+    ///
+    /// ```
+    /// # use anathema_geometry::{Pos, Rect};
+    /// let a = Rect::new(Pos::new(0, 0), Pos::new(4, 4));
+    /// let b = Rect::new(Pos::new(2, 2), Pos::new(6, 6));
+    /// assert_eq!(
+    ///     a.intersection(&b),
+    ///     Some(Rect::new(Pos::new(2, 2), Pos::new(4, 4)))
+    /// );
+    /// ```
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        let rect = Rect::new(
+            Pos::new(self.start.x.max(other.start.x), self.start.y.max(other.start.y)),
+            Pos::new(self.end.x.min(other.end.x), self.end.y.min(other.end.y)),
+        );
+
+        match rect.is_empty() {
+            true => None,
+            false => Some(rect),
+        }
+    }
+
+    /// The smallest rect that contains both rects.
+    /// Empty rects are ignored.
+    pub fn union(&self, other: &Rect) -> Rect {
+        if other.is_empty() {
+            return *self;
+        }
+
+        if self.is_empty() {
+            return *other;
+        }
+
+        Rect::new(
+            Pos::new(self.start.x.min(other.start.x), self.start.y.min(other.start.y)),
+            Pos::new(self.end.x.max(other.end.x), self.end.y.max(other.end.y)),
+        )
+    }
+
+    /// Split the rect into a left and a right rect, `at` cells from the start.
+    /// If `at` is larger than the width the right rect is empty.
+    pub fn split_horizontally(&self, at: usize) -> (Rect, Rect) {
+        let x = (self.start.x as i64 + at as i64).min(self.end.x as i64) as i32;
+        let left = Rect::new(self.start, Pos::new(x, self.end.y));
+        let right = Rect::new(Pos::new(x, self.start.y), self.end);
+        (left, right)
+    }
+
+    /// Split the rect into a top and a bottom rect, `at` cells from the start.
+    /// If `at` is larger than the height the bottom rect is empty.
+    pub fn split_vertically(&self, at: usize) -> (Rect, Rect) {
+        let y = (self.start.y as i64 + at as i64).min(self.end.y as i64) as i32;
+        let top = Rect::new(self.start, Pos::new(self.end.x, y));
+        let bottom = Rect::new(Pos::new(self.start.x, y), self.end);
+        (top, bottom)
+    }
+
+    /// Shrink the rect by the distance from each edge.
+    /// The rect never shrinks past zero, and the start is kept inside the original rect.
+    ///
+    /// ```
+    /// # use anathema_geometry::{Edges, Pos, Rect};
+    /// let rect = Rect::new(Pos::new(0, 0), Pos::new(10, 5));
+    /// let inner = rect.inset(Edges::new(1, 2, 1, 2));
+    /// assert_eq!(inner, Rect::new(Pos::new(2, 1), Pos::new(8, 4)));
+    /// ```
+    pub fn inset(&self, insets: Edges) -> Rect {
+        let size = self.size();
+        let left = insets.left.min(size.width);
+        let top = insets.top.min(size.height);
+        let right = insets.right.min(size.width - left);
+        let bottom = insets.bottom.min(size.height - top);
+
+        let start = Pos::new(self.start.x + left as i32, self.start.y + top as i32);
+        let size = Size::new(size.width - left - right, size.height - top - bottom);
+        Rect::from((start, size))
+    }
 }
 
 impl From<(Pos, Size)> for Rect {
@@ -23,3 +143,57 @@ impl From<(Pos, Size)> for Rect {
         Self { start, end }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn rect(x: i32, y: i32, width: usize, height: usize) -> Rect {
+        Rect::from((Pos::new(x, y), Size::new(width, height)))
+    }
+
+    #[test]
+    fn intersection() {
+        let a = rect(0, 0, 4, 4);
+        assert_eq!(a.intersection(&rect(2, 2, 4, 4)), Some(rect(2, 2, 2, 2)));
+        assert_eq!(a.intersection(&rect(1, 1, 1, 1)), Some(rect(1, 1, 1, 1)));
+        // Touching edges do not intersect
+        assert_eq!(a.intersection(&rect(4, 0, 4, 4)), None);
+        assert_eq!(a.intersection(&rect(-5, -5, 2, 2)), None);
+    }
+
+    #[test]
+    fn union() {
+        let a = rect(0, 0, 2, 2);
+        assert_eq!(a.union(&rect(4, 4, 2, 2)), rect(0, 0, 6, 6));
+        assert_eq!(a.union(&rect(-1, 1, 1, 1)), rect(-1, 0, 3, 2));
+        assert_eq!(a.union(&Rect::ZERO), a);
+    }
+
+    #[test]
+    fn contains_rect() {
+        let a = rect(0, 0, 4, 4);
+        assert!(a.contains_rect(&a));
+        assert!(a.contains_rect(&rect(1, 1, 3, 3)));
+        assert!(!a.contains_rect(&rect(1, 1, 4, 4)));
+        assert!(a.contains_rect(&rect(10, 10, 0, 0)));
+    }
+
+    #[test]
+    fn split() {
+        let a = rect(1, 1, 4, 2);
+        assert_eq!(a.split_horizontally(1), (rect(1, 1, 1, 2), rect(2, 1, 3, 2)));
+        assert_eq!(a.split_horizontally(10), (a, rect(5, 1, 0, 2)));
+        assert_eq!(a.split_vertically(1), (rect(1, 1, 4, 1), rect(1, 2, 4, 1)));
+        assert_eq!(a.split_vertically(0), (rect(1, 1, 4, 0), a));
+    }
+
+    #[test]
+    fn inset() {
+        let a = rect(0, 0, 10, 5);
+        assert_eq!(a.inset(Edges::uniform(1)), rect(1, 1, 8, 3));
+        assert_eq!(a.inset(Edges::new(0, 3, 0, 0)), rect(0, 0, 7, 5));
+        // Insets larger than the rect
+        assert_eq!(a.inset(Edges::new(4, 0, 4, 12)), rect(10, 4, 0, 0));
+    }
+}
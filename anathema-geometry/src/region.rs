@@ -1,7 +1,7 @@
 use crate::{Pos, Size};
 
 /// A region in global space
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Region {
     /// The starting position of the region
     pub from: Pos,
@@ -17,6 +17,44 @@ impl Size {
     pub const fn new(width: usize, height: usize) -> Self {
         Self { width, height }
     }
+
+    /// Add two sizes, returning `None` on overflow
+    pub const fn checked_add(self, other: Self) -> Option<Self> {
+        match (
+            self.width.checked_add(other.width),
+            self.height.checked_add(other.height),
+        ) {
+            (Some(width), Some(height)) => Some(Self::new(width, height)),
+            _ => None,
+        }
+    }
+
+    /// Subtract a size, returning `None` if either the width or the height would be negative
+    pub const fn checked_sub(self, other: Self) -> Option<Self> {
+        match (
+            self.width.checked_sub(other.width),
+            self.height.checked_sub(other.height),
+        ) {
+            (Some(width), Some(height)) => Some(Self::new(width, height)),
+            _ => None,
+        }
+    }
+
+    /// Add two sizes, clamping the width and height at `usize::MAX`
+    pub const fn saturating_add(self, other: Self) -> Self {
+        Self::new(
+            self.width.saturating_add(other.width),
+            self.height.saturating_add(other.height),
+        )
+    }
+
+    /// Subtract a size, clamping the width and height at zero
+    pub const fn saturating_sub(self, other: Self) -> Self {
+        Self::new(
+            self.width.saturating_sub(other.width),
+            self.height.saturating_sub(other.height),
+        )
+    }
 }
 
 impl From<(usize, usize)> for Size {
@@ -25,9 +63,10 @@ impl From<(usize, usize)> for Size {
     }
 }
 
+/// Negative values are clamped to zero
 impl From<(i32, i32)> for Size {
     fn from(parts: (i32, i32)) -> Self {
-        Size::new(parts.0 as usize, parts.1 as usize)
+        Size::new(parts.0.max(0) as usize, parts.1.max(0) as usize)
     }
 }
 
@@ -37,9 +76,11 @@ impl From<(u16, u16)> for Size {
     }
 }
 
+/// Values larger than `i32::MAX` are clamped to `i32::MAX`
 impl From<Size> for (i32, i32) {
     fn from(size: Size) -> Self {
-        (size.width as i32, size.height as i32)
+        let clamp = |val: usize| val.min(i32::MAX as usize) as i32;
+        (clamp(size.width), clamp(size.height))
     }
 }
 
@@ -71,3 +112,25 @@ impl Sub for Size {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn saturating_and_checked() {
+        let small = Size::new(1, 5);
+        let large = Size::new(3, 3);
+        assert_eq!(small.saturating_sub(large), Size::new(0, 2));
+        assert_eq!(small.checked_sub(large), None);
+        assert_eq!(large.checked_sub(Size::new(1, 1)), Some(Size::new(2, 2)));
+        assert_eq!(Size::new(usize::MAX, 0).checked_add(small), None);
+        assert_eq!(Size::new(usize::MAX, 0).saturating_add(small), Size::new(usize::MAX, 5));
+    }
+
+    #[test]
+    fn clamped_conversions() {
+        assert_eq!(Size::from((-1i32, 2i32)), Size::new(0, 2));
+        assert_eq!(<(i32, i32)>::from(Size::new(usize::MAX, 2)), (i32::MAX, 2));
+    }
+}
@@ -0,0 +1,253 @@
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use anathema_templates::blueprints::{Blueprint, Span};
+use anathema_templates::error::Error;
+use anathema_templates::{Document, SourceKind};
+
+/// A problem found in a template.
+/// The line and column start at zero, and the column is counted in characters.
+#[derive(Debug, PartialEq)]
+pub struct Problem {
+    pub line: usize,
+    pub col: usize,
+    pub message: String,
+    pub is_error: bool,
+}
+
+impl Problem {
+    fn error(line: usize, col: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            col,
+            message: message.into(),
+            is_error: true,
+        }
+    }
+
+    fn warning(src: &str, span: Span, message: impl Into<String>) -> Self {
+        let line = span.line.saturating_sub(1);
+        Self {
+            line,
+            col: char_col(src, line, span.col.saturating_sub(1)),
+            message: message.into(),
+            is_error: false,
+        }
+    }
+}
+
+/// What is being written at a position in the template
+#[derive(Debug, PartialEq)]
+pub enum Context<'src> {
+    /// The name of an element
+    Element,
+    /// The name of an attribute of the element
+    Attribute { element: &'src str },
+    /// The name of a component, after the `@`
+    Component,
+    /// Anything else, e.g an attribute value or an expression
+    Other,
+}
+
+/// Compile the template and report any errors,
+/// as well as elements that are not in `elements`.
+///
+/// Components are registered by the application and not by the template,
+/// so every component is treated as if it exists.
+pub fn check(src: &str, elements: &[&str]) -> Vec<Problem> {
+    let mut doc = Document::new(src);
+    for name in component_names(src) {
+        _ = doc.add_component(name, SourceKind::Str("text ''".into()));
+    }
+    check_document(doc, src, elements)
+}
+
+/// Compile a document, with the components already added, and report any errors
+/// as well as elements that are not in `elements`.
+/// `src` is the source of the document.
+pub fn check_document(mut doc: Document, src: &str, elements: &[&str]) -> Vec<Problem> {
+    // A half written template should never bring down the server
+    let result = match catch_unwind(AssertUnwindSafe(|| doc.compile())) {
+        Ok(result) => result,
+        Err(_) => return vec![Problem::error(0, 0, "failed to compile the template")],
+    };
+
+    let blueprint = match result {
+        Ok((blueprint, _)) => blueprint,
+        Err(Error::ParseError(err)) => {
+            let line = err.line.saturating_sub(1);
+            let col = char_col(src, line, err.col.saturating_sub(1));
+            return vec![Problem::error(line, col, err.kind.to_string())];
+        }
+        Err(Error::MissingComponent(name)) => {
+            let (line, col) = find(src, &format!("@{name}")).unwrap_or((0, 0));
+            return vec![Problem::error(line, col, Error::MissingComponent(name).to_string())];
+        }
+        Err(Error::EmptyTemplate) => return vec![],
+        Err(err) => return vec![Problem::error(0, 0, err.to_string())],
+    };
+
+    let mut problems = vec![];
+    unknown_elements(src, &blueprint, elements, &mut problems);
+    problems
+}
+
+// Elements that are not registered.
+// The body of a component is not part of this template.
+fn unknown_elements(src: &str, blueprint: &Blueprint, elements: &[&str], problems: &mut Vec<Problem>) {
+    let children: &[Blueprint] = match blueprint {
+        Blueprint::Single(single) => {
+            if !elements.contains(&&*single.ident) {
+                let msg = format!("`{}` is not a registered element", single.ident);
+                problems.push(Problem::warning(src, single.span, msg));
+            }
+            &single.children
+        }
+        Blueprint::For(for_loop) => &for_loop.body,
+        Blueprint::ControlFlow(flow) => {
+            for body in std::iter::once(&flow.if_node.body).chain(flow.elses.iter().map(|e| &e.body)) {
+                body.iter().for_each(|bp| unknown_elements(src, bp, elements, problems));
+            }
+            return;
+        }
+        Blueprint::Component(_) => return,
+    };
+
+    children
+        .iter()
+        .for_each(|bp| unknown_elements(src, bp, elements, problems));
+}
+
+/// The names of the components used in the template
+pub fn component_names(src: &str) -> Vec<&str> {
+    let mut names = vec![];
+    for line in src.lines() {
+        let Some(name) = line.trim_start().strip_prefix('@') else { continue };
+        let name = &name[..name.find(|c| !is_ident(c)).unwrap_or(name.len())];
+        if !name.is_empty() && !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+/// What is being written at the line and column (in characters)
+pub fn context(src: &str, line: usize, col: usize) -> Context<'_> {
+    let Some(text) = src.lines().nth(line) else { return Context::Element };
+    let prefix = &text[..byte_offset(text, col)];
+    let statement = prefix.trim_start();
+
+    // Inside the attributes, before the colon of the current attribute
+    if let Some(start) = statement.rfind('[') {
+        if !statement[start..].contains(']') {
+            let current = &statement[statement.rfind(',').unwrap_or(start).max(start)..];
+            if current.contains(':') {
+                return Context::Other;
+            }
+            let element = statement.split(|c: char| !is_ident(c)).next().unwrap_or_default();
+            return Context::Attribute { element };
+        }
+    }
+
+    match statement.strip_prefix('@') {
+        Some(name) if name.chars().all(is_ident) => Context::Component,
+        Some(_) => Context::Other,
+        None if statement.chars().all(is_ident) => Context::Element,
+        None => Context::Other,
+    }
+}
+
+/// The word at the line and column (in characters),
+/// including a leading `@` for components.
+pub fn word_at(src: &str, line: usize, col: usize) -> Option<&str> {
+    let text = src.lines().nth(line)?;
+    let mut offset = byte_offset(text, col);
+    if text[offset..].starts_with('@') {
+        offset += 1;
+    }
+    let start = text[..offset].rfind(|c| !is_ident(c)).map(|i| i + 1).unwrap_or(0);
+    let end = text[offset..]
+        .find(|c| !is_ident(c))
+        .map(|i| i + offset)
+        .unwrap_or(text.len());
+    let start = match text[..start].ends_with('@') {
+        true => start - 1,
+        false => start,
+    };
+
+    match &text[start..end] {
+        "" | "@" => None,
+        word => Some(word),
+    }
+}
+
+// The line and column (in characters) of the first occurrence of a string
+fn find(src: &str, needle: &str) -> Option<(usize, usize)> {
+    src.lines()
+        .enumerate()
+        .find_map(|(no, line)| line.find(needle).map(|i| (no, line[..i].chars().count())))
+}
+
+// The column in characters of a column in bytes, as used by the template spans
+fn char_col(src: &str, line: usize, col: usize) -> usize {
+    let text = src.lines().nth(line).unwrap_or_default();
+    text.char_indices().take_while(|(i, _)| *i < col).count()
+}
+
+fn byte_offset(text: &str, col: usize) -> usize {
+    text.char_indices().nth(col).map(|(i, _)| i).unwrap_or(text.len())
+}
+
+fn is_ident(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-'
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const ELEMENTS: &[&str] = &["vstack", "text"];
+
+    #[test]
+    fn parse_error() {
+        let problems = check("vstack\n    text [a: 1", ELEMENTS);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].is_error);
+        assert_eq!(problems[0].line, 1);
+    }
+
+    #[test]
+    fn unknown_element() {
+        let src = "vstack\n    @comp\n    txet 'a'\n    for x in [1]\n        tetx x";
+        let problems = check(src, ELEMENTS);
+        let lines = problems.iter().map(|p| (p.line, p.is_error)).collect::<Vec<_>>();
+        assert_eq!(lines, [(2, false), (4, false)]);
+    }
+
+    #[test]
+    fn columns_are_characters() {
+        // The template counts the column in bytes
+        let problems = check("text [a: 'åäö' b]", ELEMENTS);
+        assert_eq!((problems[0].line, problems[0].col), (0, 14));
+    }
+
+    #[test]
+    fn completion_context() {
+        let src = "vstack\n    text [bold: true, for\n    @inp\n    te";
+        assert_eq!(context(src, 0, 3), Context::Element);
+        assert_eq!(context(src, 1, 14), Context::Attribute { element: "text" });
+        assert_eq!(context(src, 1, 17), Context::Other);
+        assert_eq!(context(src, 1, 24), Context::Attribute { element: "text" });
+        assert_eq!(context(src, 2, 8), Context::Component);
+        assert_eq!(context(src, 3, 6), Context::Element);
+    }
+
+    #[test]
+    fn words() {
+        let src = "vstack\n    @my_comp [a: 1]";
+        assert_eq!(word_at(src, 0, 2), Some("vstack"));
+        assert_eq!(word_at(src, 1, 7), Some("@my_comp"));
+        assert_eq!(word_at(src, 1, 4), Some("@my_comp"));
+        assert_eq!(word_at(src, 1, 14), Some("a"));
+        assert_eq!(word_at(src, 1, 1), None);
+    }
+}
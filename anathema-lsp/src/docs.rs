@@ -0,0 +1,175 @@
+// Documentation for the default widgets and their attributes,
+// used for hover and to complete attribute names.
+
+/// An element and the attributes it reads
+pub struct ElementDocs {
+    pub ident: &'static str,
+    pub docs: &'static str,
+    pub attributes: &'static [&'static str],
+}
+
+/// Attributes read by every element
+pub const COMMON_ATTRIBUTES: &[&str] = &["id", "display", "foreground", "background", "description"];
+
+/// Attributes read by elements that draw text
+pub const TEXT_STYLES: &[&str] = &[
+    "bold",
+    "dim",
+    "italic",
+    "underline",
+    "underline_color",
+    "overline",
+    "inverse",
+];
+
+const SIZE: &[&str] = &["width", "height", "min_width", "min_height", "max_width", "max_height"];
+
+pub const ELEMENTS: &[ElementDocs] = &[
+    ElementDocs {
+        ident: "text",
+        docs: "Draw text, wrapping it to fit the available width.\n\n`text [wrap: \"break\", text_align: \"centre\"] \"hello\"`",
+        attributes: &["wrap", "text_align", "cursor", "cursor_shape"],
+    },
+    ElementDocs {
+        ident: "span",
+        docs: "A run of text inside a `text` element, with its own style.",
+        attributes: &[],
+    },
+    ElementDocs {
+        ident: "border",
+        docs: "Draw a border around a single child.\n\n`border [border_style: \"thick\", sides: \"top left\"]`",
+        attributes: &["border_style", "sides", "width", "height", "min_width", "min_height", "max_width", "max_height"],
+    },
+    ElementDocs {
+        ident: "align",
+        docs: "Align a single child inside the available space.\n\n`align [alignment: \"bottom_right\"]`",
+        attributes: &["alignment"],
+    },
+    ElementDocs {
+        ident: "container",
+        docs: "Constrain the size of a single child.",
+        attributes: SIZE,
+    },
+    ElementDocs {
+        ident: "padding",
+        docs: "Add space around a single child.\n\n`padding [padding: \"1 2\", top: 0]`",
+        attributes: &["padding", "top", "right", "bottom", "left"],
+    },
+    ElementDocs {
+        ident: "position",
+        docs: "Place a single child relative to its parent, or anchored to the viewport.\n\n`position [anchor: \"bottom_right\", margin: 1]`",
+        attributes: &[
+            "placement",
+            "top",
+            "right",
+            "bottom",
+            "left",
+            "anchor",
+            "margin",
+            "margin_x",
+            "margin_y",
+            "stretch",
+        ],
+    },
+    ElementDocs {
+        ident: "vstack",
+        docs: "Lay out the children vertically.",
+        attributes: SIZE,
+    },
+    ElementDocs {
+        ident: "hstack",
+        docs: "Lay out the children horizontally.\n\n`hstack [align: \"baseline\"]`",
+        attributes: &["align", "width", "height", "min_width", "min_height", "max_width", "max_height"],
+    },
+    ElementDocs {
+        ident: "zstack",
+        docs: "Draw the children on top of each other.",
+        attributes: SIZE,
+    },
+    ElementDocs {
+        ident: "column",
+        docs: "Lay out the children vertically, taking up all the available height.",
+        attributes: &[],
+    },
+    ElementDocs {
+        ident: "row",
+        docs: "Lay out the children horizontally, taking up all the available width.",
+        attributes: &[],
+    },
+    ElementDocs {
+        ident: "expand",
+        docs: "Fill the remaining space of a stack, shared with other `expand` elements by their factor.\n\n`expand [factor: 2, axis: \"horz\"]`",
+        attributes: &["factor", "axis", "fill"],
+    },
+    ElementDocs {
+        ident: "spacer",
+        docs: "Take up the remaining space of a stack, pushing the following children to the end.",
+        attributes: &[],
+    },
+    ElementDocs {
+        ident: "overflow",
+        docs: "Lay out the children without constraints along the axis and scroll them.",
+        attributes: &["axis", "direction", "unconstrained", "clamp"],
+    },
+    ElementDocs {
+        ident: "canvas",
+        docs: "Draw individual cells from code.",
+        attributes: &["width", "height"],
+    },
+    ElementDocs {
+        ident: "log_view",
+        docs: "Show log records from the state, newest at the bottom.\n\n`log_view [records: records, level: \"warn\", filter: \"net\"]`",
+        attributes: &["records", "level", "filter", "width", "height"],
+    },
+    ElementDocs {
+        ident: "portal",
+        docs: "Draw the children elsewhere on the screen, above the rest of the layout.",
+        attributes: &[],
+    },
+];
+
+/// The documentation of an element
+pub fn element(ident: &str) -> Option<&'static ElementDocs> {
+    ELEMENTS.iter().find(|el| el.ident == ident)
+}
+
+/// The documentation of an attribute
+pub fn attribute(name: &str) -> Option<&'static str> {
+    let docs = match name {
+        "id" => "An id used to find the element from a component query.",
+        "display" => "`\"show\"`, `\"hide\"` (laid out but not drawn) or `\"exclude\"` (not laid out).",
+        "foreground" => "The text colour, a name (`\"red\"`), a hex value (`#ff0000`) or an index.",
+        "background" => "The background colour, a name (`\"red\"`), a hex value (`#ff0000`) or an index.",
+        "description" => "Read by screen readers when the component containing the element gains focus.",
+        "bold" | "dim" | "italic" | "underline" | "overline" | "inverse" => "Style the text when `true`.",
+        "underline_color" => "The colour of the underline.",
+        "width" | "height" => "The size in cells, a percentage (`\"50%\"`) or a fraction (`\"1/3\"`) of the parent.",
+        "min_width" | "min_height" | "max_width" | "max_height" => "Bound the size of the element, in cells.",
+        "wrap" => "How text is wrapped: `\"normal\"` or `\"break\"` (break words at the edge).",
+        "text_align" => "Align each line of text: `\"left\"`, `\"centre\"` or `\"right\"`.",
+        "cursor" => "Show the terminal cursor at this character offset of the text.",
+        "cursor_shape" => "The shape of the cursor: `\"block\"`, `\"underline\"` or `\"bar\"`.",
+        "border_style" => "`\"thin\"`, `\"thick\"` or a string of eight characters, clockwise from the top left.",
+        "sides" => "Which sides to draw: `\"top\"`, `\"right\"`, `\"bottom\"` and `\"left\"`, or a list.",
+        "alignment" => "`\"top_left\"`, `\"top\"`, `\"top_right\"`, `\"right\"`, `\"bottom_right\"`, `\"bottom\"`, `\"bottom_left\"`, `\"left\"` or `\"centre\"`.",
+        "padding" => "Padding on every edge, or a shorthand: `\"1 2\"`, `\"1 2 3\"` or `\"1 2 3 4\"` (top, right, bottom, left).",
+        "top" | "right" | "bottom" | "left" => "The distance from an edge, in cells.",
+        "placement" => "`\"relative\"` to the parent or `\"absolute\"` on the screen.",
+        "anchor" => "Place the element at an edge, a corner or the centre of the viewport (same values as `alignment`).",
+        "margin" => "The distance from the viewport when anchored, or a shorthand like `\"1 2\"`.",
+        "margin_x" | "margin_y" => "Override the horizontal or vertical margin.",
+        "stretch" => "Fill the edge the element is anchored to.",
+        "align" => "`\"baseline\"` lines up the children on the baseline of their text.",
+        "factor" => "The share of the remaining space.",
+        "axis" => "`\"horz\"` or `\"vert\"`.",
+        "fill" => "A character to fill the space with.",
+        "direction" => "`\"forward\"` or `\"backward\"`.",
+        "unconstrained" => "Lay out the children without constraints on both axes.",
+        "clamp" => "Stop scrolling once the last child is visible.",
+        "records" => "A list of records with a `level`, a `target` and a `message`.",
+        "level" => "The least severe level to show: `\"error\"`, `\"warn\"`, `\"info\"`, `\"debug\"` or `\"trace\"`.",
+        "filter" => "Only show records where the target or the message contains the text.",
+        _ => return None,
+    };
+    Some(docs)
+}
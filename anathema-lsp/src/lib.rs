@@ -0,0 +1,433 @@
+//! A language server for Anathema templates.
+//!
+//! Positions are taken from the spans of the compiled template and the parse errors.
+//! Components are registered in Rust code, so a component reference `@name`
+//! is resolved to the file `name.aml`, first next to the template and then
+//! anywhere in the workspace.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use anathema_widgets::Factory;
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _, PublishDiagnostics,
+};
+use lsp_types::request::{Completion, GotoDefinition, HoverRequest, Request as _};
+use lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionOptions, CompletionResponse, Diagnostic, DiagnosticSeverity,
+    DidChangeTextDocumentParams, DidCloseTextDocumentParams, DidOpenTextDocumentParams, Documentation,
+    GotoDefinitionResponse, Hover, HoverContents, HoverProviderCapability, InitializeParams, Location, MarkupContent,
+    MarkupKind, OneOf, Position, PublishDiagnosticsParams, Range, ServerCapabilities, TextDocumentSyncCapability,
+    TextDocumentSyncKind, Uri,
+};
+
+pub use crate::analysis::{check, check_document, component_names, context, word_at, Context, Problem};
+
+mod analysis;
+pub mod docs;
+
+type Result<T> = std::result::Result<T, Box<dyn Error + Sync + Send>>;
+
+struct Server<'a> {
+    connection: Connection,
+    elements: Vec<&'a str>,
+    documents: HashMap<Uri, String>,
+    root: Option<PathBuf>,
+}
+
+/// Run the language server over stdio until the client shuts it down.
+/// Element names are taken from the factory.
+pub fn run(factory: &Factory) -> Result<()> {
+    let (connection, io_threads) = Connection::stdio();
+
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        completion_provider: Some(CompletionOptions {
+            trigger_characters: Some(vec!["@".into(), "[".into(), ",".into()]),
+            ..Default::default()
+        }),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        definition_provider: Some(OneOf::Left(true)),
+        ..Default::default()
+    };
+
+    let params = connection.initialize(serde_json::to_value(capabilities)?)?;
+    let params: InitializeParams = serde_json::from_value(params)?;
+    #[allow(deprecated)]
+    let root = params
+        .workspace_folders
+        .and_then(|folders| folders.into_iter().next().map(|folder| folder.uri))
+        .or(params.root_uri)
+        .and_then(|uri| to_path(&uri));
+
+    let mut elements = factory.idents().collect::<Vec<_>>();
+    elements.sort_unstable();
+
+    let mut server = Server {
+        connection,
+        elements,
+        documents: HashMap::new(),
+        root,
+    };
+    server.main_loop()?;
+
+    drop(server);
+    io_threads.join()?;
+    Ok(())
+}
+
+impl Server<'_> {
+    fn main_loop(&mut self) -> Result<()> {
+        while let Ok(msg) = self.connection.receiver.recv() {
+            match msg {
+                Message::Request(req) => {
+                    if self.connection.handle_shutdown(&req)? {
+                        return Ok(());
+                    }
+                    self.request(req)?;
+                }
+                Message::Notification(not) => self.notification(not)?,
+                Message::Response(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn request(&mut self, req: Request) -> Result<()> {
+        let resp = match req.method.as_str() {
+            Completion::METHOD => self.handle::<Completion>(req, |server, params| {
+                let pos = params.text_document_position;
+                server.completion(&pos.text_document.uri, pos.position)
+            }),
+            HoverRequest::METHOD => self.handle::<HoverRequest>(req, |server, params| {
+                let pos = params.text_document_position_params;
+                server.hover(&pos.text_document.uri, pos.position)
+            }),
+            GotoDefinition::METHOD => self.handle::<GotoDefinition>(req, |server, params| {
+                let pos = params.text_document_position_params;
+                server.definition(&pos.text_document.uri, pos.position)
+            }),
+            method => Response::new_err(
+                req.id.clone(),
+                ErrorCode::MethodNotFound as i32,
+                format!("unknown request `{method}`"),
+            ),
+        };
+
+        self.connection.sender.send(Message::Response(resp))?;
+        Ok(())
+    }
+
+    // Respond with the result of the request,
+    // or with an error if the parameters are invalid
+    fn handle<R: lsp_types::request::Request>(
+        &self,
+        req: Request,
+        f: impl FnOnce(&Self, R::Params) -> R::Result,
+    ) -> Response {
+        match serde_json::from_value(req.params) {
+            Ok(params) => Response::new_ok(req.id, f(self, params)),
+            Err(err) => Response::new_err(req.id, ErrorCode::InvalidParams as i32, err.to_string()),
+        }
+    }
+
+    // Notifications with invalid parameters are ignored, as there is no response to report the error
+    fn notification(&mut self, not: Notification) -> Result<()> {
+        match not.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let Ok(params) = serde_json::from_value::<DidOpenTextDocumentParams>(not.params) else {
+                    return Ok(());
+                };
+                let doc = params.text_document;
+                self.documents.insert(doc.uri.clone(), doc.text);
+                self.publish_diagnostics(doc.uri)?;
+            }
+            DidChangeTextDocument::METHOD => {
+                let Ok(params) = serde_json::from_value::<DidChangeTextDocumentParams>(not.params) else {
+                    return Ok(());
+                };
+                // Full sync: the last change is the entire document
+                let Some(change) = params.content_changes.into_iter().last() else { return Ok(()) };
+                let uri = params.text_document.uri;
+                self.documents.insert(uri.clone(), change.text);
+                self.publish_diagnostics(uri)?;
+            }
+            DidCloseTextDocument::METHOD => {
+                let Ok(params) = serde_json::from_value::<DidCloseTextDocumentParams>(not.params) else {
+                    return Ok(());
+                };
+                self.documents.remove(&params.text_document.uri);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn publish_diagnostics(&self, uri: Uri) -> Result<()> {
+        let Some(src) = self.documents.get(&uri) else { return Ok(()) };
+
+        let diagnostics = check(src, &self.elements)
+            .into_iter()
+            .map(|problem| {
+                let start = Position::new(problem.line as u32, utf16_col(src, problem.line, problem.col));
+                let end = Position::new(problem.line as u32, u32::MAX);
+                Diagnostic {
+                    range: Range::new(start, end),
+                    severity: Some(match problem.is_error {
+                        true => DiagnosticSeverity::ERROR,
+                        false => DiagnosticSeverity::WARNING,
+                    }),
+                    source: Some("anathema".into()),
+                    message: problem.message,
+                    ..Default::default()
+                }
+            })
+            .collect();
+
+        let params = PublishDiagnosticsParams::new(uri, diagnostics, None);
+        let not = Notification::new(PublishDiagnostics::METHOD.into(), params);
+        self.connection.sender.send(Message::Notification(not))?;
+        Ok(())
+    }
+
+    fn completion(&self, uri: &Uri, pos: Position) -> Option<CompletionResponse> {
+        let src = self.documents.get(uri)?;
+
+        let items = match context(src, pos.line as usize, char_col(src, pos)) {
+            Context::Element => self
+                .elements
+                .iter()
+                .map(|ident| CompletionItem {
+                    label: ident.to_string(),
+                    kind: Some(CompletionItemKind::CLASS),
+                    documentation: docs::element(ident).map(|el| Documentation::MarkupContent(markdown(el.docs))),
+                    ..Default::default()
+                })
+                .collect(),
+            Context::Attribute { element } => {
+                let attributes = docs::element(element).map(|el| el.attributes).unwrap_or_default();
+                let names = attributes
+                    .iter()
+                    .chain(docs::COMMON_ATTRIBUTES)
+                    .chain(docs::TEXT_STYLES)
+                    .collect::<Vec<_>>();
+                names
+                    .into_iter()
+                    .map(|name| CompletionItem {
+                        label: name.to_string(),
+                        kind: Some(CompletionItemKind::PROPERTY),
+                        documentation: docs::attribute(name).map(|docs| Documentation::MarkupContent(markdown(docs))),
+                        ..Default::default()
+                    })
+                    .collect()
+            }
+            Context::Component => {
+                let dir = to_path(uri)?.parent()?.to_path_buf();
+                let mut names = std::fs::read_dir(dir)
+                    .ok()?
+                    .filter_map(|entry| {
+                        let path = entry.ok()?.path();
+                        match path.extension()? == "aml" {
+                            true => Some(path.file_stem()?.to_string_lossy().into_owned()),
+                            false => None,
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                names.sort();
+                names
+                    .into_iter()
+                    .map(|name| CompletionItem {
+                        label: name,
+                        kind: Some(CompletionItemKind::MODULE),
+                        ..Default::default()
+                    })
+                    .collect()
+            }
+            Context::Other => return None,
+        };
+
+        Some(CompletionResponse::Array(items))
+    }
+
+    fn hover(&self, uri: &Uri, pos: Position) -> Option<Hover> {
+        let src = self.documents.get(uri)?;
+        let col = char_col(src, pos);
+        let word = word_at(src, pos.line as usize, col)?;
+
+        let docs = match context(src, pos.line as usize, col) {
+            Context::Attribute { .. } => docs::attribute(word)?,
+            _ => docs::element(word)?.docs,
+        };
+
+        Some(Hover {
+            contents: HoverContents::Markup(markdown(docs)),
+            range: None,
+        })
+    }
+
+    fn definition(&self, uri: &Uri, pos: Position) -> Option<GotoDefinitionResponse> {
+        let src = self.documents.get(uri)?;
+        let name = word_at(src, pos.line as usize, char_col(src, pos))?.strip_prefix('@')?;
+        let file_name = format!("{name}.aml");
+
+        let path = to_path(uri)?
+            .parent()
+            .map(|dir| dir.join(&file_name))
+            .filter(|path| path.is_file())
+            .or_else(|| find_file(self.root.as_deref()?, &file_name))?;
+
+        let uri = to_uri(&path)?;
+        Some(GotoDefinitionResponse::Scalar(Location::new(uri, Range::default())))
+    }
+}
+
+fn markdown(value: &str) -> MarkupContent {
+    MarkupContent {
+        kind: MarkupKind::Markdown,
+        value: value.into(),
+    }
+}
+
+// LSP positions count the column in UTF-16 code units,
+// while the analysis counts characters
+fn char_col(src: &str, pos: Position) -> usize {
+    let text = src.lines().nth(pos.line as usize).unwrap_or_default();
+    let mut units = 0;
+    text.chars()
+        .take_while(|c| {
+            units += c.len_utf16();
+            units <= pos.character as usize
+        })
+        .count()
+}
+
+fn utf16_col(src: &str, line: usize, col: usize) -> u32 {
+    let text = src.lines().nth(line).unwrap_or_default();
+    text.chars().take(col).map(char::len_utf16).sum::<usize>() as u32
+}
+
+// The path of a local `file:` URI
+fn to_path(uri: &Uri) -> Option<PathBuf> {
+    if !uri.scheme()?.as_str().eq_ignore_ascii_case("file") {
+        return None;
+    }
+
+    if let Some(authority) = uri.authority() {
+        let host = authority.host().as_str();
+        if !host.is_empty() && !host.eq_ignore_ascii_case("localhost") {
+            return None;
+        }
+    }
+
+    let path = uri.path().as_estr().decode().into_string().ok()?;
+
+    // `/C:/dir` is `C:\dir`
+    #[cfg(windows)]
+    let path = path.strip_prefix('/')?.replace('/', "\\");
+
+    Some(PathBuf::from(&*path))
+}
+
+// The `file:` URI of an absolute path
+fn to_uri(path: &Path) -> Option<Uri> {
+    if !path.is_absolute() {
+        return None;
+    }
+    let path = path.to_str()?;
+
+    #[cfg(windows)]
+    let path = &format!("/{}", path.replace('\\', "/"));
+
+    let mut uri = String::from("file://");
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~/:@!$&'()*+,;=".contains(&byte) {
+            uri.push(byte as char);
+        } else {
+            uri.push_str(&format!("%{byte:02X}"));
+        }
+    }
+
+    uri.parse().ok()
+}
+
+// Search the directory for a file, skipping build output and hidden directories
+fn find_file(dir: &Path, file_name: &str) -> Option<PathBuf> {
+    let mut dirs = vec![];
+    for entry in std::fs::read_dir(dir).ok()?.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            let name = entry.file_name();
+            if name != "target" && !name.to_string_lossy().starts_with('.') {
+                dirs.push(path);
+            }
+        } else if entry.file_name() == file_name {
+            return Some(path);
+        }
+    }
+
+    dirs.into_iter().find_map(|dir| find_file(&dir, file_name))
+}
+
+#[cfg(test)]
+mod test {
+    use lsp_server::RequestId;
+
+    use super::*;
+
+    fn request(method: &str, params: serde_json::Value) -> Response {
+        let (connection, client) = Connection::memory();
+        let mut server = Server {
+            connection,
+            elements: vec![],
+            documents: HashMap::new(),
+            root: None,
+        };
+
+        let req = Request::new(RequestId::from(1), method.into(), params);
+        server.request(req).unwrap();
+        match client.receiver.recv().unwrap() {
+            Message::Response(resp) => resp,
+            msg => panic!("expected a response, got {msg:?}"),
+        }
+    }
+
+    #[test]
+    fn unknown_request() {
+        let resp = request("textDocument/unknown", serde_json::Value::Null);
+        assert_eq!(resp.error.unwrap().code, ErrorCode::MethodNotFound as i32);
+    }
+
+    #[test]
+    fn invalid_params() {
+        let resp = request(HoverRequest::METHOD, serde_json::json!({ "position": 1 }));
+        assert_eq!(resp.error.unwrap().code, ErrorCode::InvalidParams as i32);
+    }
+
+    #[test]
+    fn utf16_columns() {
+        let src = "text '😀a'";
+        assert_eq!(utf16_col(src, 0, 7), 8);
+        assert_eq!(char_col(src, Position::new(0, 8)), 7);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn file_uri_round_trip() {
+        let path = Path::new("/home/user/my templates/åäö.aml");
+        let uri = to_uri(path).unwrap();
+        assert_eq!(uri.as_str(), "file:///home/user/my%20templates/%C3%A5%C3%A4%C3%B6.aml");
+        assert_eq!(to_path(&uri).unwrap(), path);
+    }
+
+    #[test]
+    fn only_local_files() {
+        let remote: Uri = "file://example.com/index.aml".parse().unwrap();
+        assert!(to_path(&remote).is_none());
+        let http: Uri = "https://example.com/index.aml".parse().unwrap();
+        assert!(to_path(&http).is_none());
+        let local: Uri = "file://localhost/index.aml".parse().unwrap();
+        assert!(to_path(&local).is_some());
+    }
+}
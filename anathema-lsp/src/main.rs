@@ -0,0 +1,8 @@
+use anathema_default_widgets::register_default_widgets;
+use anathema_widgets::Factory;
+
+fn main() -> Result<(), Box<dyn std::error::Error + Sync + Send>> {
+    let mut factory = Factory::new();
+    register_default_widgets(&mut factory);
+    anathema_lsp::run(&factory)
+}
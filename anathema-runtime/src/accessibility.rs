@@ -0,0 +1,271 @@
+//! Announce the focused component, and text from components,
+//! to an output other than the screen, for screen readers and other assistive technology.
+//!
+//! ```ignore
+//! let mut builder = Runtime::builder(document, backend);
+//! if std::env::var_os("ANATHEMA_SCREEN_READER").is_some() {
+//!     builder.accessibility(accessibility::Stderr);
+//! }
+//! ```
+//!
+//! A component is described by [`Component::describe`](anathema_widgets::components::Component::describe),
+//! or the `description` attribute of the component.
+//! Elements inside the focused component are described by their `description` attribute:
+//!
+//! ```text
+//! @save [description: "Save the file"]
+//!
+//! // The template of `save`
+//! hstack
+//!     text "Save"
+//!     text [description: status] status
+//! ```
+//!
+//! Other outputs, e.g a bridge to AT-SPI, implement [`Announcer`].
+use std::fmt::{self, Display};
+use std::io::Write;
+
+use anathema_state::States;
+use anathema_store::tree::{Node, TreeValues};
+use anathema_widgets::{AttributeStorage, Attributes, Components, WidgetId, WidgetKind, WidgetTree};
+
+const DESCRIPTION: &str = "description";
+
+/// Receives the announcements of the runtime.
+///
+/// ```
+/// # use anathema_runtime::accessibility::{Announcement, Announcer};
+/// let mut announcements = vec![];
+/// let mut output = |announcement: &Announcement| announcements.push(announcement.to_string());
+/// output.announce(&Announcement::Message("saved".into()));
+/// assert_eq!(announcements, ["saved"]);
+/// ```
+pub trait Announcer {
+    fn announce(&mut self, announcement: &Announcement);
+}
+
+impl<F: FnMut(&Announcement)> Announcer for F {
+    fn announce(&mut self, announcement: &Announcement) {
+        self(announcement)
+    }
+}
+
+/// Write every announcement as a line to stderr.
+///
+/// Stderr has to be redirected away from the terminal,
+/// e.g `app 2> >(espeak)`, or it will draw over the application.
+#[derive(Debug, Default)]
+pub struct Stderr;
+
+impl Announcer for Stderr {
+    fn announce(&mut self, announcement: &Announcement) {
+        _ = writeln!(std::io::stderr(), "{announcement}");
+    }
+}
+
+/// Log every announcement with the `info` level,
+/// and `anathema::accessibility` as the target.
+#[cfg(feature = "log")]
+#[derive(Debug, Default)]
+pub struct Log;
+
+#[cfg(feature = "log")]
+impl Announcer for Log {
+    fn announce(&mut self, announcement: &Announcement) {
+        log::info!(target: "anathema::accessibility", "{announcement}");
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Announcement {
+    /// A component gained focus.
+    /// The elements are the descriptions of the elements inside the component.
+    Focus { component: String, elements: Vec<String> },
+    /// Text from a component, see [`Context::announce`](anathema_widgets::components::Context::announce)
+    Message(String),
+}
+
+/// The elements are separated by a comma, e.g `Save, Saved 12:00`
+impl Display for Announcement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Focus { component, elements } => {
+                write!(f, "{component}")?;
+                elements.iter().try_for_each(|element| write!(f, ", {element}"))
+            }
+            Self::Message(text) => write!(f, "{text}"),
+        }
+    }
+}
+
+pub(crate) struct Accessibility {
+    output: Box<dyn Announcer>,
+    // The last announced component
+    focused: Option<WidgetId>,
+}
+
+impl Accessibility {
+    pub(crate) fn new(output: impl Announcer + 'static) -> Self {
+        Self {
+            output: Box::new(output),
+            focused: None,
+        }
+    }
+
+    pub(crate) fn message(&mut self, text: String) {
+        self.output.announce(&Announcement::Message(text));
+    }
+
+    // Announce the focused component if the focus changed since the last frame
+    pub(crate) fn frame(
+        &mut self,
+        tree: &mut WidgetTree<'_>,
+        attribute_storage: &AttributeStorage<'_>,
+        states: &States,
+        components: &mut Components,
+    ) {
+        let focused = components.current().filter(|&(widget_id, _)| {
+            matches!(
+                tree.get_ref_by_id(widget_id),
+                Some(WidgetKind::Component(component)) if component.dyn_component.any_accept_focus()
+            )
+        });
+
+        if focused.map(|(widget_id, _)| widget_id) == self.focused {
+            return;
+        }
+        self.focused = focused.map(|(widget_id, _)| widget_id);
+
+        let Some((widget_id, state_id)) = focused else { return };
+        let Some(WidgetKind::Component(component)) = tree.get_ref_by_id(widget_id) else { return };
+        let component = component
+            .dyn_component
+            .any_describe(states.get(state_id))
+            .or_else(|| description(attribute_storage.get(widget_id)))
+            .unwrap_or_else(|| component.name.to_string());
+
+        let mut elements = vec![];
+        tree.with_nodes_and_values(widget_id, |_, children, values| {
+            collect_descriptions(children, values, attribute_storage, &mut elements)
+        });
+
+        self.output.announce(&Announcement::Focus { component, elements });
+    }
+}
+
+fn description(attributes: &Attributes<'_>) -> Option<String> {
+    let value = attributes.get_val(DESCRIPTION)?.load_common_val()?;
+    Some(value.to_common()?.to_common_str().to_string())
+}
+
+// The descriptions of the visible elements, in the order of the tree
+fn collect_descriptions(
+    nodes: &[Node],
+    values: &TreeValues<WidgetKind<'_>>,
+    attribute_storage: &AttributeStorage<'_>,
+    descriptions: &mut Vec<String>,
+) {
+    for node in nodes {
+        let Some((_, widget)) = values.get(node.value()) else { continue };
+        match widget {
+            WidgetKind::Element(el) => descriptions.extend(description(attribute_storage.get(el.id()))),
+            WidgetKind::If(widget) if !widget.show => continue,
+            WidgetKind::Else(widget) if !widget.show => continue,
+            _ => {}
+        }
+
+        collect_descriptions(node.children(), values, attribute_storage, descriptions);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use anathema_backend::testing::TestBackend;
+    use anathema_state::Map;
+    use anathema_templates::{Document, ToSourceKind};
+    use anathema_widgets::components::events::{Event, KeyCode, KeyEvent};
+    use anathema_widgets::components::{Component, Context};
+    use anathema_widgets::Elements;
+
+    use super::*;
+    use crate::testing::test::Nav;
+    use crate::Runtime;
+
+    #[test]
+    fn accessibility() {
+        struct Save;
+
+        impl Component for Save {
+            type Message = String;
+            type State = ();
+
+            fn describe(&self, _: &Self::State) -> Option<String> {
+                Some("Save the file".into())
+            }
+
+            fn message(
+                &mut self,
+                message: Self::Message,
+                _: &mut Self::State,
+                _: Elements<'_, '_>,
+                context: Context<'_, Self::State>,
+            ) {
+                context.announce(message);
+            }
+        }
+
+        let mut document = Document::new("vstack\n    @open [description: 'Open']\n    @status\n    @save");
+        document.hot_reload = false;
+
+        let mut builder = Runtime::builder(document, TestBackend::new((10, 3)));
+        builder
+            .register_component("open", "text [description: 'Ctrl+O'] 'open'".to_template(), Nav, {
+                let mut state = Map::empty();
+                state.insert("count", 0);
+                state
+            })
+            .unwrap();
+        builder
+            .register_component("status", "text 'ok'".to_template(), (), ())
+            .unwrap();
+        let save = builder
+            .register_component("save", "text 'save'".to_template(), Save, ())
+            .unwrap();
+
+        let announcements = Rc::new(RefCell::new(vec![]));
+        builder.accessibility({
+            let announcements = announcements.clone();
+            move |announcement: &Announcement| announcements.borrow_mut().push(announcement.to_string())
+        });
+
+        let mut runtime = builder.finish().unwrap();
+        let emitter = runtime.emitter();
+        let take = || announcements.take();
+
+        runtime
+            .headless(|frame| {
+                frame.tick()?;
+                assert_eq!(take(), ["Open, Ctrl+O"]);
+
+                // Components that don't accept focus are skipped
+                frame
+                    .backend_mut()
+                    .push_event(Event::Key(KeyEvent::press(KeyCode::Tab)));
+                frame.tick()?;
+                assert_eq!(take(), ["Save the file"]);
+
+                // Nothing is announced unless the focus changes
+                frame.tick()?;
+                assert!(take().is_empty());
+
+                emitter.emit(save, "Saved".into()).unwrap();
+                frame.tick()?;
+                assert_eq!(take(), ["Saved"]);
+                Ok(())
+            })
+            .unwrap();
+    }
+}
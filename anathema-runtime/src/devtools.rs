@@ -0,0 +1,423 @@
+// An inspector painted on top of the application,
+// see `GlobalKeymap::bind_devtools`.
+use std::time::Duration;
+
+use anathema_geometry::{Pos, Region};
+use anathema_state::{AnyState, Color, Hex, Path, StateId, States};
+use anathema_store::tree::{Node, TreeValues};
+use anathema_widgets::components::events::{KeyCode, KeyEvent, KeyState};
+use anathema_widgets::debug::Overlay;
+use anathema_widgets::paint::CellAttributes;
+use anathema_widgets::time::Instant;
+use anathema_widgets::{AttributeStorage, WidgetKind, WidgetRenderer, WidgetTree};
+
+use crate::FrameStats;
+
+// The width of the panel, unless the viewport is narrower
+const MIN_WIDTH: usize = 32;
+const PAGE: usize = 10;
+
+/// The devtools between frames
+#[derive(Debug, Default)]
+pub(crate) struct Devtools {
+    pub(crate) visible: bool,
+    selected: usize,
+    // The number of rows in the tree when it was last painted
+    rows: usize,
+    stats: FrameStats,
+    frame_time: Duration,
+    last_paint: Option<Instant>,
+}
+
+impl Devtools {
+    pub(crate) fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    // Returns true if the key was used by the devtools
+    pub(crate) fn key(&mut self, key: KeyEvent) -> bool {
+        if key.state != KeyState::Press {
+            return false;
+        }
+
+        let last = self.rows.saturating_sub(1);
+        self.selected = match key.code {
+            KeyCode::Up | KeyCode::Char('k') => self.selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => (self.selected + 1).min(last),
+            KeyCode::PageUp => self.selected.saturating_sub(PAGE),
+            KeyCode::PageDown => (self.selected + PAGE).min(last),
+            KeyCode::Home | KeyCode::Char('g') => 0,
+            KeyCode::End | KeyCode::Char('G') => last,
+            KeyCode::Esc => {
+                self.visible = false;
+                self.selected
+            }
+            _ => return false,
+        };
+
+        true
+    }
+
+    // Keep the stats of the last painted frame
+    pub(crate) fn record(&mut self, stats: &FrameStats) {
+        if !stats.painted() {
+            return;
+        }
+
+        let now = Instant::now();
+        if let Some(last) = self.last_paint.replace(now) {
+            self.frame_time = now - last;
+        }
+        self.stats = *stats;
+    }
+
+    /// Take a snapshot of the tree for the panel
+    pub(crate) fn panel(
+        &mut self,
+        tree: &mut WidgetTree<'_>,
+        attribute_storage: &AttributeStorage<'_>,
+        states: &States,
+        region: Region,
+    ) -> Panel {
+        let mut rows = vec![];
+        let (nodes, values) = tree.split();
+        collect_rows(nodes, values, 0, &mut rows);
+
+        self.rows = rows.len();
+        self.selected = self.selected.min(self.rows.saturating_sub(1));
+        let selected = rows.get(self.selected);
+
+        let (heading, details) = match selected.map(|row| row.kind) {
+            Some(RowKind::Element(region, widget_id)) => {
+                let attributes = attribute_storage.get(widget_id);
+                let mut details = vec![format!(
+                    "at {},{} size {}x{}",
+                    region.from.x,
+                    region.from.y,
+                    region.to.x - region.from.x,
+                    region.to.y - region.from.y
+                )];
+                details.extend(
+                    attributes
+                        .iter()
+                        .map(|(key, value)| format!("{}: {}", key.to_str(), describe_value(value))),
+                );
+                if let Some(value) = attributes.value() {
+                    details.push(format!("value: {}", describe_value(value)));
+                }
+                ("attributes", details)
+            }
+            Some(RowKind::Component(state_id)) => {
+                let details = match states.get(state_id) {
+                    Some(state) => state
+                        .state_keys()
+                        .iter()
+                        .map(|key| match state.state_lookup(Path::Key(key)) {
+                            Some(value) => format!("{key}: {}", value.as_state(describe_state)),
+                            None => format!("{key}: -"),
+                        })
+                        .collect(),
+                    None => vec![],
+                };
+                ("state", details)
+            }
+            None => ("attributes", vec![]),
+        };
+
+        let fps = match self.frame_time.is_zero() {
+            true => 0,
+            false => (1.0 / self.frame_time.as_secs_f64()).round() as usize,
+        };
+        let stats = [
+            format!("layout {:.1?}  paint {:.1?}", self.stats.layout, self.stats.paint),
+            format!(
+                "widgets {}/{}  laid out {}",
+                self.stats.widgets, self.stats.capacity, self.stats.laid_out
+            ),
+            format!(
+                "cells {}  changes {}  fps {fps}",
+                self.stats.cells_written, self.stats.changes
+            ),
+        ];
+
+        // The details and stats take up at most half the panel,
+        // the rest is the tree, scrolled to show the selected row
+        let height = (region.to.y - region.from.y).max(0) as usize;
+        let bottom = (details.len() + stats.len() + 2).min(height / 2);
+        let tree_height = height.saturating_sub(bottom + 1);
+        let offset = (self.selected + 1).saturating_sub(tree_height);
+
+        let mut lines = vec![Line::heading("widgets")];
+        lines.extend(rows.iter().enumerate().skip(offset).take(tree_height).map(|(i, row)| {
+            let text = format!("{}{}", "  ".repeat(row.depth), row.label);
+            match i == self.selected {
+                true => Line::new(text, SELECTED),
+                false => Line::new(text, NORMAL),
+            }
+        }));
+        lines.resize_with(tree_height + 1, || Line::new(String::new(), NORMAL));
+
+        let details_height = bottom.saturating_sub(stats.len() + 2);
+        lines.push(Line::heading(heading));
+        lines.extend(
+            details
+                .into_iter()
+                .take(details_height)
+                .map(|text| Line::new(text, NORMAL)),
+        );
+        lines.push(Line::heading("frame"));
+        lines.extend(stats.into_iter().map(|text| Line::new(text, NORMAL)));
+
+        Panel {
+            region,
+            highlight: selected.and_then(|row| match row.kind {
+                RowKind::Element(region, _) => Some(region),
+                RowKind::Component(_) => None,
+            }),
+            lines,
+        }
+    }
+
+    /// The region of the panel, along the right edge of the screen
+    pub(crate) fn region(size: anathema_geometry::Size) -> Region {
+        let width = (size.width * 2 / 5).max(MIN_WIDTH).min(size.width);
+        Region::new(
+            Pos::new((size.width - width) as i32, 0),
+            Pos::new(size.width as i32, size.height as i32),
+        )
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+enum RowKind {
+    Element(Region, anathema_widgets::WidgetId),
+    Component(StateId),
+}
+
+#[derive(Debug)]
+struct Row {
+    depth: usize,
+    label: String,
+    kind: RowKind,
+}
+
+// Elements and components that are shown.
+// Loops and control flow don't add any depth to the tree.
+fn collect_rows(nodes: &[Node], values: &TreeValues<WidgetKind<'_>>, depth: usize, rows: &mut Vec<Row>) {
+    for node in nodes {
+        let Some((_, widget)) = values.get(node.value()) else { continue };
+        let depth = match widget {
+            WidgetKind::Element(el) => {
+                rows.push(Row {
+                    depth,
+                    label: el.ident.to_string(),
+                    kind: RowKind::Element(el.region(), el.id()),
+                });
+                depth + 1
+            }
+            WidgetKind::Component(component) => {
+                rows.push(Row {
+                    depth,
+                    label: format!("@{}", component.name),
+                    kind: RowKind::Component(component.state_id),
+                });
+                depth + 1
+            }
+            WidgetKind::If(widget) if !widget.show => continue,
+            WidgetKind::Else(widget) if !widget.show => continue,
+            _ => depth,
+        };
+
+        collect_rows(node.children(), values, depth, rows);
+    }
+}
+
+fn describe_value(value: &anathema_widgets::expressions::EvalValue<'_>) -> String {
+    value
+        .load_common_val()
+        .and_then(|value| value.to_common().map(|value| value.to_string()))
+        .unwrap_or_else(|| "-".into())
+}
+
+fn describe_state(state: &dyn AnyState) -> String {
+    match state.to_common() {
+        Some(value) => value.to_string(),
+        None if state.count() > 0 => format!("[{} values]", state.count()),
+        None => "{..}".into(),
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Style {
+    foreground: Option<Color>,
+    background: Option<Color>,
+    bold: bool,
+}
+
+const NORMAL: Style = Style {
+    foreground: Some(Color::White),
+    background: Some(Color::Black),
+    bold: false,
+};
+
+const SELECTED: Style = Style {
+    foreground: Some(Color::Black),
+    background: Some(Color::Cyan),
+    bold: true,
+};
+
+const HEADING: Style = Style {
+    foreground: Some(Color::Cyan),
+    background: Some(Color::Black),
+    bold: true,
+};
+
+// The selected element is highlighted, keeping its foreground
+const HIGHLIGHT: Style = Style {
+    foreground: None,
+    background: Some(Color::DarkGrey),
+    bold: false,
+};
+
+impl CellAttributes for Style {
+    fn with_str(&self, _: &str, _: &mut dyn FnMut(&str)) {}
+
+    fn get_i64(&self, _: &str) -> Option<i64> {
+        None
+    }
+
+    fn get_u8(&self, _: &str) -> Option<u8> {
+        None
+    }
+
+    fn get_hex(&self, _: &str) -> Option<Hex> {
+        None
+    }
+
+    fn get_color(&self, key: &str) -> Option<Color> {
+        match key {
+            "foreground" => self.foreground,
+            "background" => self.background,
+            _ => None,
+        }
+    }
+
+    fn get_bool(&self, key: &str) -> bool {
+        key == "bold" && self.bold
+    }
+}
+
+#[derive(Debug)]
+struct Line {
+    text: String,
+    style: Style,
+    // Fills the rest of the line
+    fill: char,
+}
+
+impl Line {
+    fn new(text: String, style: Style) -> Self {
+        Self { text, style, fill: ' ' }
+    }
+
+    fn heading(title: &str) -> Self {
+        Self {
+            text: format!("─ {title} "),
+            style: HEADING,
+            fill: '─',
+        }
+    }
+}
+
+/// A snapshot of the devtools, painted on top of the frame
+#[derive(Debug)]
+pub(crate) struct Panel {
+    region: Region,
+    highlight: Option<Region>,
+    lines: Vec<Line>,
+}
+
+impl Overlay for Panel {
+    fn paint(&self, surface: &mut dyn WidgetRenderer) {
+        let size = surface.size();
+        let screen = Region::from((Pos::ZERO, size));
+
+        if let Some(highlight) = self.highlight {
+            for y in highlight.from.y..highlight.to.y {
+                for x in highlight.from.x..highlight.to.x {
+                    let pos = Pos::new(x, y);
+                    if screen.contains(pos) && !self.region.contains(pos) {
+                        surface.set_attributes(&HIGHLIGHT, pos);
+                    }
+                }
+            }
+        }
+
+        let Region { from, to } = self.region;
+        for (y, line) in (from.y..to.y).zip(&self.lines) {
+            let mut chars = line.text.chars();
+            for x in from.x..to.x {
+                let pos = Pos::new(x, y);
+                if !screen.contains(pos) {
+                    continue;
+                }
+
+                let c = match x == from.x {
+                    true => '│',
+                    false => chars.next().unwrap_or(line.fill),
+                };
+                surface.draw_glyph(c, pos);
+                surface.set_attributes(&line.style, pos);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use anathema_widgets::components::events::KeyCode;
+
+    use crate::testing::ComponentTest;
+
+    #[test]
+    fn devtools() {
+        let template = "
+vstack
+    text [bold: true] 'hi'
+    for x in [1]
+        text x
+";
+        let mut test = ComponentTest::new(template, (), (), (40, 16)).unwrap();
+        test.runtime.keymap().bind_devtools("f12").unwrap();
+
+        test.run(|frame| {
+            frame.press(KeyCode::F(12)).tick();
+            let lines = frame.output().lines().map(|line| &line[11..]).collect::<Vec<_>>();
+            assert_eq!(
+                &lines[..5],
+                [
+                    "─ widgets ─────────────────────",
+                    "@component                     ",
+                    "  vstack                       ",
+                    "    text                       ",
+                    "    text                       "
+                ]
+            );
+
+            // Select the first text
+            frame.press(KeyCode::Down).tick().press(KeyCode::Down).tick();
+            let output = frame.output();
+            assert!(output.contains("│at 0,0 size 2x1"));
+            assert!(output.contains("│bold: true"));
+            assert!(output.contains("│─ frame ─"));
+
+            // Close the devtools
+            frame.press(KeyCode::Esc).tick();
+            assert_eq!(
+                frame.output().lines().next(),
+                Some("hi                                      ")
+            );
+        })
+        .unwrap();
+    }
+}
@@ -2,24 +2,30 @@ use std::error::Error as StdError;
 use std::fmt::{self, Display};
 
 use anathema_templates::error::Error as TemplateError;
+use anathema_widgets::components::Shutdown;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Debug)]
 pub enum Error {
     Template(TemplateError),
+    #[cfg(not(target_arch = "wasm32"))]
     Notify(notify::Error),
     Widget(anathema_widgets::error::Error),
-    Stop,
+    Io(std::io::Error),
+    /// The runtime was stopped, see [`Shutdown`]
+    Stop(Shutdown),
 }
 
 impl Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Error::Template(template) => write!(f, "{template}"),
-            Error::Stop => write!(f, "stopping"),
+            Error::Stop(_) => write!(f, "stopping"),
+            #[cfg(not(target_arch = "wasm32"))]
             Error::Notify(err) => write!(f, "{err}"),
             Error::Widget(err) => write!(f, "{err}"),
+            Error::Io(err) => write!(f, "{err}"),
         }
     }
 }
@@ -32,12 +38,19 @@ impl From<TemplateError> for Error {
     }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 impl From<notify::Error> for Error {
     fn from(err: notify::Error) -> Self {
         Self::Notify(err)
     }
 }
 
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
 impl From<anathema_widgets::error::Error> for Error {
     fn from(value: anathema_widgets::error::Error) -> Self {
         Self::Widget(value)
@@ -1,21 +1,31 @@
 use std::borrow::Cow;
-use std::time::{Duration, Instant};
+use std::collections::VecDeque;
+use std::time::Duration;
 
 use anathema_backend::Backend;
 use anathema_geometry::Size;
 use anathema_state::{AnyState, CommonVal, States};
-use anathema_widgets::components::events::{Event, KeyCode, KeyEvent, KeyState};
-use anathema_widgets::components::{AssociatedEvents, ComponentId, Emitter, FocusQueue, UntypedContext};
+use anathema_templates::WidgetComponentId;
+use anathema_widgets::components::events::{Drag, Event, KeyCode, KeyEvent, KeyState, MouseEvent, MouseState};
+use anathema_widgets::components::{
+    AssociatedEvents, ComponentId, Emitter, FocusQueue, FocusRequest, FocusTarget, Keymap, KeymapMatch, MountId,
+    Mounts, ScreenRequest, Shutdown, Timers, UntypedContext,
+};
 use anathema_widgets::layout::{Constraints, Viewport};
-use anathema_widgets::{AttributeStorage, Components, DirtyWidgets, Elements, WidgetKind, WidgetTree};
+use anathema_widgets::time::Instant;
+use anathema_widgets::{
+    AttributeStorage, Components, DirtyWidgets, Elements, Hover, Selection, WidgetKind, WidgetTree,
+};
 
 use crate::error::{Error, Result};
+use crate::keymap::{Action, GlobalKeymap, GlobalMatch, KeySequence, Lookup, Resolved};
+use crate::plugin::{PluginContext, Plugins};
 use crate::tree::Tree;
 
 // -----------------------------------------------------------------------------
 //   - Ctrl-c quit test -
 // -----------------------------------------------------------------------------
-fn is_ctrl_c(event: Event) -> bool {
+fn is_ctrl_c(event: &Event) -> bool {
     matches!(
         event,
         Event::Key(KeyEvent {
@@ -26,6 +36,132 @@ fn is_ctrl_c(event: Event) -> bool {
     )
 }
 
+enum Dir {
+    F,
+    B,
+}
+
+// Returns true if the component at the index is inside the active focus scope
+fn in_scope(event_ctx: &EventCtx<'_, '_, '_>, index: usize) -> bool {
+    match event_ctx.focus_queue.scope() {
+        Some(scope) => event_ctx.components.in_focus_scope(index, scope),
+        None => true,
+    }
+}
+
+// Move focus to the next (or previous) component that accepts focus,
+// within the active focus scope.
+fn move_focus<'bp>(event_ctx: &mut EventCtx<'_, '_, 'bp>, tree: &mut WidgetTree<'bp>, dir: Dir) {
+    let len = event_ctx.components.len();
+    let index = event_ctx.components.tab_index;
+    let mut next = index;
+
+    for _ in 0..len {
+        next = match dir {
+            Dir::F => (next + 1) % len,
+            Dir::B => (next + len - 1) % len,
+        };
+
+        if next == index {
+            break;
+        }
+
+        if !in_scope(event_ctx, next) {
+            continue;
+        }
+
+        let Some((widget_id, state_id)) = event_ctx.components.get(next) else { continue };
+        let accept_focus = tree
+            .with_component(widget_id, state_id, event_ctx, |comp, _| comp.any_accept_focus())
+            .unwrap_or(false);
+
+        if !accept_focus {
+            continue;
+        }
+
+        // -----------------------------------------------------------------------------
+        //   - Blur -
+        // -----------------------------------------------------------------------------
+        if let Some((widget_id, state_id)) = event_ctx.components.current() {
+            tree.with_component(widget_id, state_id, event_ctx, |comp, ctx| comp.any_blur(ctx));
+        }
+
+        // -----------------------------------------------------------------------------
+        //   - Focus -
+        // -----------------------------------------------------------------------------
+        event_ctx.components.tab_index = next;
+        tree.with_component(widget_id, state_id, event_ctx, |comp, ctx| comp.any_focus(ctx));
+        break;
+    }
+}
+
+// Find the first component with the given name
+fn component_by_name(event_ctx: &EventCtx<'_, '_, '_>, tree: &WidgetTree<'_>, name: &str) -> Option<WidgetComponentId> {
+    event_ctx
+        .components
+        .iter()
+        .find_map(|entry| match tree.get_ref_by_id(entry.widget_id) {
+            Some(WidgetKind::Component(component)) if component.name == name => Some(entry.component_id),
+            _ => None,
+        })
+}
+
+// Returns true if the component at the index accepts focus and matches the request
+fn can_focus(
+    event_ctx: &EventCtx<'_, '_, '_>,
+    tree: &WidgetTree<'_>,
+    index: usize,
+    request: &FocusRequest<'_>,
+) -> bool {
+    // Focus can not escape the active focus scope
+    if !in_scope(event_ctx, index) {
+        return false;
+    }
+
+    let Some(entry) = event_ctx.components.iter().nth(index) else { return false };
+    let Some(WidgetKind::Component(component)) = tree.get_ref_by_id(entry.widget_id) else {
+        return false;
+    };
+
+    if !component.dyn_component.any_accept_focus() {
+        return false;
+    }
+
+    let attribs = event_ctx.attribute_storage.get(entry.widget_id);
+
+    let is_match = match &request.target {
+        FocusTarget::Attribute(key, value) => {
+            let Some(val) = attribs.get_val(key) else { return false };
+            let Some(either) = val.load_common_val() else { return false };
+            let Some(cv) = either.to_common() else { return false };
+            *value == cv
+        }
+        FocusTarget::Name(name) => component.name == name,
+        FocusTarget::NextIn(_) => true,
+    };
+
+    is_match && request.filter.as_ref().is_none_or(|filter| filter(attribs))
+}
+
+// Find the index of the component to focus
+fn find_focus_target(
+    event_ctx: &EventCtx<'_, '_, '_>,
+    tree: &WidgetTree<'_>,
+    request: &FocusRequest<'_>,
+) -> Option<usize> {
+    let len = event_ctx.components.len();
+    match &request.target {
+        FocusTarget::NextIn(group) => {
+            let group = component_by_name(event_ctx, tree, group)?;
+            let current = event_ctx.components.tab_index;
+            (1..=len)
+                .map(|offset| (current + offset) % len)
+                .find(|&i| event_ctx.components.in_focus_scope(i, group) && can_focus(event_ctx, tree, i, request))
+        }
+        FocusTarget::Attribute(..) | FocusTarget::Name(_) => (0..len).find(|&i| can_focus(event_ctx, tree, i, request)),
+    }
+}
+
 // If the event is tab/back tab then the event is consumed
 fn tab<'bp>(event_ctx: &mut EventCtx<'_, '_, 'bp>, tree: &mut WidgetTree<'bp>, event: Event) -> Option<Event> {
     // -----------------------------------------------------------------------------
@@ -37,93 +173,148 @@ fn tab<'bp>(event_ctx: &mut EventCtx<'_, '_, 'bp>, tree: &mut WidgetTree<'bp>, e
         ..
     }) = event
     {
-        enum Dir {
-            F,
-            B,
-        }
-
-        let index = event_ctx.components.tab_index;
         let dir = match code {
             KeyCode::Tab => Dir::F,
             KeyCode::BackTab => Dir::B,
             _ => return Some(event),
         };
 
-        loop {
-            // -----------------------------------------------------------------------------
-            //   - Blur -
-            // -----------------------------------------------------------------------------
-            if let Some((widget_id, state_id)) = event_ctx.components.get(event_ctx.components.tab_index) {
-                tree.with_component(widget_id, state_id, event_ctx, |comp, ctx| comp.any_blur(ctx));
-            }
-
-            // -----------------------------------------------------------------------------
-            //   - Change index -
-            // -----------------------------------------------------------------------------
-            match dir {
-                Dir::F => {
-                    event_ctx.components.tab_index += 1;
-                    if event_ctx.components.tab_index >= event_ctx.components.len() {
-                        event_ctx.components.tab_index = 0;
-                    }
-                }
-                Dir::B => match event_ctx.components.tab_index >= 1 {
-                    true => event_ctx.components.tab_index -= 1,
-                    false => event_ctx.components.tab_index = event_ctx.components.len() - 1,
-                },
-            }
-
-            if index == event_ctx.components.tab_index {
-                break;
-            }
-
-            // -----------------------------------------------------------------------------
-            //   - Focus -
-            // -----------------------------------------------------------------------------
-            if let Some((widget_id, state_id)) = event_ctx.components.current() {
-                tree.with_component(widget_id, state_id, event_ctx, |comp, ctx| comp.any_focus(ctx));
-
-                let cont = tree
-                    .with_component(widget_id, state_id, event_ctx, |comp, ctx| {
-                        if !comp.any_accept_focus() {
-                            return true;
-                        }
-                        comp.any_focus(ctx);
-                        false
-                    })
-                    .unwrap_or(true);
-
-                if !cont {
-                    break;
-                }
-            }
-        }
-
+        move_focus(event_ctx, tree, dir);
         return None;
     }
 
-    // Mouse events are global
-    if let Event::Mouse(_) = event {
+    // Mouse events are global, except for scroll events
+    if let Event::Mouse(mouse) = event {
+        if mouse.scroll_delta().is_some() {
+            return Some(event);
+        }
+
         for i in 0..event_ctx.components.len() {
             let (widget_id, state_id) = event_ctx
                 .components
                 .get(i)
                 .expect("components can not change during this call");
 
-            tree.with_component(widget_id, state_id, event_ctx, |comp, ctx| comp.any_event(ctx, event));
+            tree.with_component(widget_id, state_id, event_ctx, |comp, ctx| {
+                comp.any_event(ctx, Event::Mouse(mouse))
+            });
         }
     }
 
     Some(event)
 }
 
+// Track the elements and components under the cursor,
+// and notify the components the cursor entered or left.
+fn hover<'bp>(
+    hover: &mut Hover,
+    drag: &mut Drag,
+    event_ctx: &mut EventCtx<'_, '_, 'bp>,
+    tree: &mut WidgetTree<'bp>,
+    mouse: MouseEvent,
+) {
+    let changes = hover.update(
+        mouse.pos(),
+        tree,
+        event_ctx.components,
+        event_ctx.attribute_storage,
+        event_ctx.dirty_widgets,
+    );
+
+    let left = changes.left.into_iter().map(|ids| (ids, MouseState::Leave));
+    let entered = changes.entered.into_iter().map(|ids| (ids, MouseState::Enter));
+    for ((widget_id, state_id), state) in left.chain(entered) {
+        let event = Event::Mouse(MouseEvent::new(mouse.x, mouse.y, state));
+        tree.with_component(widget_id, state_id, event_ctx, |comp, ctx| comp.any_event(ctx, event));
+    }
+
+    let Some((event, recipients)) = drag.update(mouse, hover) else { return };
+    for (widget_id, state_id) in recipients {
+        tree.with_component(widget_id, state_id, event_ctx, |comp, ctx| comp.any_drag(ctx, event));
+    }
+}
+
+// A shortcut completed by a sequence of keys
+enum Shortcut {
+    Global(Action),
+    Component(WidgetComponentId, Cow<'static, str>),
+}
+
+// Released keys and completed shortcuts, handled in order
+enum Sequenced {
+    Key(KeyEvent),
+    Shortcut(Shortcut),
+}
+
+fn queue(sequenced: &mut VecDeque<Sequenced>, resolved: Resolved<Shortcut>) {
+    sequenced.extend(resolved.released.into_iter().map(Sequenced::Key));
+    sequenced.extend(resolved.shortcut.map(Sequenced::Shortcut));
+}
+
+fn focused_component(components: &Components) -> Option<WidgetComponentId> {
+    components
+        .iter()
+        .nth(components.tab_index)
+        .map(|entry| entry.component_id)
+}
+
+// Look up the keys in the global keymap first, then the keymap of the components
+fn lookup(
+    global: &GlobalKeymap,
+    keymap: Option<&Keymap>,
+    focused: Option<WidgetComponentId>,
+    keys: &[KeyEvent],
+) -> Lookup<Shortcut> {
+    let pending = match global.lookup(keys) {
+        GlobalMatch::Action(action) => return Lookup::Match(Shortcut::Global(action)),
+        GlobalMatch::Pending => true,
+        GlobalMatch::None => false,
+    };
+
+    match keymap.map(|keymap| keymap.lookup(keys, focused)) {
+        Some(KeymapMatch::Shortcut(component_id, name)) => Lookup::Match(Shortcut::Component(component_id, name)),
+        Some(KeymapMatch::Pending) => Lookup::Prefix,
+        Some(KeymapMatch::None) | None if pending => Lookup::Prefix,
+        Some(KeymapMatch::None) | None => Lookup::None,
+    }
+}
+
 pub(super) struct EventHandler<T> {
     global: T,
+    pub(super) keymap: GlobalKeymap,
+    pub(super) plugins: Plugins,
+    hover: Hover,
+    drag: Drag,
+    // Text selected with the mouse, if a copy shortcut is bound
+    pub(super) selection: Selection,
+    // An event received while the runtime was idle,
+    // handled before polling the backend.
+    pub(super) pending_event: Option<Event>,
+    // Key presses held back as the start of a shortcut
+    pub(super) sequence: KeySequence,
+    sequenced: VecDeque<Sequenced>,
+    // Paint the layout debug overlay
+    pub(super) debug_overlay: bool,
+    #[cfg(feature = "devtools")]
+    pub(super) devtools: crate::devtools::Devtools,
 }
 
 impl<T: GlobalEvents> EventHandler<T> {
-    pub fn new(global: T) -> Self {
-        Self { global }
+    pub fn new(global: T, keymap: GlobalKeymap, plugins: Plugins) -> Self {
+        Self {
+            global,
+            keymap,
+            plugins,
+            hover: Hover::new(),
+            drag: Drag::new(),
+            selection: Selection::new(),
+            pending_event: None,
+            sequence: KeySequence::new(),
+            sequenced: VecDeque::new(),
+            debug_overlay: false,
+            #[cfg(feature = "devtools")]
+            devtools: Default::default(),
+        }
     }
 
     pub(super) fn set_initial_focus<'bp>(&mut self, tree: &mut WidgetTree<'bp>, event_ctx: &mut EventCtx<'_, '_, 'bp>) {
@@ -148,6 +339,69 @@ impl<T: GlobalEvents> EventHandler<T> {
         }
     }
 
+    // The keymap of the components, unless the devtools are open
+    fn component_keymap<'k>(&self, keymap: &'k Keymap) -> Option<&'k Keymap> {
+        #[cfg(feature = "devtools")]
+        if self.devtools.visible {
+            return None;
+        }
+        Some(keymap)
+    }
+
+    fn run_shortcut<'bp>(
+        &mut self,
+        shortcut: Shortcut,
+        backend: &mut impl Backend,
+        tree: &mut WidgetTree<'bp>,
+        event_ctx: &mut EventCtx<'_, '_, 'bp>,
+    ) {
+        match shortcut {
+            Shortcut::Global(Action::Named(name)) => {
+                let (nodes, values) = tree.split();
+                let mut elements = Elements::new(nodes, values, event_ctx.attribute_storage, event_ctx.dirty_widgets);
+                let mut global_ctx = GlobalContext {
+                    focus_queue: event_ctx.focus_queue,
+                    mounts: event_ctx.mounts,
+                    emitter: event_ctx.context.emitter,
+                    keymap: &mut self.keymap,
+                };
+                self.global.action(&name, &mut elements, &mut global_ctx);
+            }
+            Shortcut::Global(Action::Message(send)) => send(event_ctx.context.emitter),
+            Shortcut::Global(Action::DebugOverlay) => {
+                self.debug_overlay = !self.debug_overlay;
+                // Repaint the frame with (or without) the overlay
+                if let Some(widget_id) = tree.id(&[0]) {
+                    event_ctx.dirty_widgets.push(widget_id);
+                }
+            }
+            Shortcut::Global(Action::Copy) => {
+                if let Some(text) = self.selection.text(tree, event_ctx.attribute_storage) {
+                    backend.copy(&text);
+                }
+            }
+            #[cfg(feature = "devtools")]
+            Shortcut::Global(Action::Devtools) => {
+                self.devtools.toggle();
+                if let Some(widget_id) = tree.id(&[0]) {
+                    event_ctx.dirty_widgets.push(widget_id);
+                }
+            }
+            Shortcut::Component(component_id, name) => {
+                if let Some((widget_id, state_id)) = event_ctx
+                    .components
+                    .get_by_component_id(component_id)
+                    .map(|entry| (entry.widget_id, entry.state_id))
+                {
+                    tree.with_component(widget_id, state_id, event_ctx, |comp, ctx| {
+                        comp.any_shortcut(ctx, &name)
+                    });
+                }
+            }
+        }
+    }
+
+    // Returns true if any events were handled
     pub(super) fn handle<'bp>(
         &mut self,
         poll_duration: Duration,
@@ -158,36 +412,129 @@ impl<T: GlobalEvents> EventHandler<T> {
         tree: &mut WidgetTree<'bp>,
         constraints: &mut Constraints,
         event_ctx: &mut EventCtx<'_, '_, 'bp>,
-    ) -> Result<()> {
-        while let Some(event) = backend.next_event(poll_duration) {
+    ) -> Result<bool> {
+        let mut handled = false;
+
+        // Release the keys of a shortcut sequence that timed out
+        let focused = focused_component(event_ctx.components);
+        let keymap = self.component_keymap(event_ctx.keymap);
+        let timeout = self.keymap.sequence_timeout();
+        let resolved = self.sequence.expire(Instant::now(), timeout, |keys| {
+            lookup(&self.keymap, keymap, focused, keys)
+        });
+        queue(&mut self.sequenced, resolved);
+
+        loop {
+            let (event, released) = match self.sequenced.pop_front() {
+                Some(Sequenced::Shortcut(shortcut)) => {
+                    handled = true;
+                    self.run_shortcut(shortcut, backend, tree, event_ctx);
+                    continue;
+                }
+                Some(Sequenced::Key(key)) => (Event::Key(key), true),
+                None => match self.pending_event.take().or_else(|| backend.next_event(poll_duration)) {
+                    Some(event) => (event, false),
+                    None => break,
+                },
+            };
+            handled = true;
+
+            // Released keys were already passed to the plugins
+            let plugin_ctx = PluginContext {
+                emitter: event_ctx.context.emitter,
+                viewport: *viewport,
+            };
+            let event = match released {
+                true => event,
+                false => match self.plugins.event(event, &plugin_ctx) {
+                    Some(event) => event,
+                    None => continue,
+                },
+            };
+
+            if let Event::Mouse(mouse) = event {
+                hover(&mut self.hover, &mut self.drag, event_ctx, tree, mouse);
+
+                // Paint the frame with the new selection
+                if self.keymap.can_copy() && self.selection.update(mouse) {
+                    if let Some(widget_id) = tree.id(&[0]) {
+                        event_ctx.dirty_widgets.push(widget_id);
+                    }
+                }
+
+                // Scroll the innermost element under the cursor,
+                // or pass the event to the focused component
+                if let Some(delta) = mouse.scroll_delta() {
+                    if self.hover.scroll(delta, tree, event_ctx.dirty_widgets) {
+                        continue;
+                    }
+                }
+            }
+
             let event = match self.global.enable_tab_navigation() {
                 false => event,
                 true => match tab(event_ctx, tree, event) {
-                    None => return Ok(()),
+                    None => return Ok(true),
                     Some(ev) => ev,
                 },
             };
 
+            // -----------------------------------------------------------------------------
+            //   - Shortcuts -
+            //   Key presses are held back while they are the start of a shortcut,
+            //   from either the global keymap or the keymap of the components.
+            //   Keys that turn out not to be part of a shortcut are released,
+            //   and handled as if there were no shortcuts.
+            // -----------------------------------------------------------------------------
+            let focused = focused_component(event_ctx.components);
+            let keymap = self.component_keymap(event_ctx.keymap);
             let (nodes, values) = tree.split();
             let mut elements = Elements::new(nodes, values, event_ctx.attribute_storage, event_ctx.dirty_widgets);
             let mut global_ctx = GlobalContext {
                 focus_queue: event_ctx.focus_queue,
+                mounts: event_ctx.mounts,
                 emitter: event_ctx.context.emitter,
+                keymap: &mut self.keymap,
             };
 
-            let event = match is_ctrl_c(event) {
+            let event = match !released && is_ctrl_c(&event) {
                 true => self.global.ctrl_c(event, &mut elements, &mut global_ctx),
                 false => Some(event),
             };
 
-            let Some(event) = event else { return Ok(()) };
+            let Some(event) = event else { return Ok(true) };
+
+            if let Event::Key(key) = event {
+                if !released && key.state == KeyState::Press {
+                    let global = &*global_ctx.keymap;
+                    let resolved = self
+                        .sequence
+                        .feed(key, Instant::now(), |keys| lookup(global, keymap, focused, keys));
+                    queue(&mut self.sequenced, resolved);
+                    continue;
+                }
+
+                // The devtools are navigated with the keyboard while they are open
+                #[cfg(feature = "devtools")]
+                if self.devtools.visible && self.devtools.key(key) {
+                    if let Some(widget_id) = tree.id(&[0]) {
+                        event_ctx.dirty_widgets.push(widget_id);
+                    }
+                    continue;
+                }
+            }
+
             let event = self.global.handle(event, &mut elements, &mut global_ctx);
-            let Some(event) = event else { return Ok(()) };
+            let Some(event) = event else { return Ok(true) };
 
-            // Ignore mouse events, as they are handled by global event
-            if !event.is_mouse_event() {
+            // Ignore mouse events, as they are handled by global event,
+            // except for scroll events that no element under the cursor handled
+            let is_scroll = matches!(event, Event::Mouse(mouse) if mouse.scroll_delta().is_some());
+            if !event.is_mouse_event() || is_scroll {
                 if let Some((widget_id, state_id)) = event_ctx.components.get(event_ctx.components.tab_index) {
-                    tree.with_component(widget_id, state_id, event_ctx, |comp, ctx| comp.any_event(ctx, event));
+                    tree.with_component(widget_id, state_id, event_ctx, |comp, ctx| {
+                        comp.any_event(ctx, event.clone())
+                    });
                 }
             }
 
@@ -202,6 +549,12 @@ impl<T: GlobalEvents> EventHandler<T> {
                     // Remember to update the viewport on the context
                     event_ctx.context.viewport = *viewport;
 
+                    // Lay out the tree with the new constraints.
+                    // Only the widgets given different constraints are laid out again
+                    if let Some(widget_id) = tree.id(&[0]) {
+                        event_ctx.dirty_widgets.push(widget_id);
+                    }
+
                     // Notify all components of the resize
                     let len = event_ctx.components.len();
                     for i in 0..len {
@@ -213,8 +566,39 @@ impl<T: GlobalEvents> EventHandler<T> {
                         tree.with_component(widget_id, state_id, event_ctx, |comp, ctx| comp.any_resize(ctx));
                     }
                 }
-                Event::Blur | Event::Focus => (),
-                Event::Stop => return Err(Error::Stop),
+                Event::FocusGained | Event::FocusLost => {
+                    let gained = matches!(event, Event::FocusGained);
+
+                    // Notify all components of the terminal focus change
+                    let len = event_ctx.components.len();
+                    for i in 0..len {
+                        let (widget_id, state_id) = event_ctx
+                            .components
+                            .get(i)
+                            .expect("components can not change during this call");
+
+                        tree.with_component(widget_id, state_id, event_ctx, |comp, ctx| {
+                            comp.any_terminal_focus(ctx, gained)
+                        });
+                    }
+                }
+                Event::Resume => {
+                    // Redraw everything, as the terminal was restored while suspended
+                    if let Some(widget_id) = tree.id(&[0]) {
+                        event_ctx.dirty_widgets.push(widget_id);
+                    }
+
+                    let len = event_ctx.components.len();
+                    for i in 0..len {
+                        let (widget_id, state_id) = event_ctx
+                            .components
+                            .get(i)
+                            .expect("components can not change during this call");
+
+                        tree.with_component(widget_id, state_id, event_ctx, |comp, ctx| comp.any_resume(ctx));
+                    }
+                }
+                Event::Stop => return Err(Error::Stop(Shutdown::default())),
                 _ => {}
             }
 
@@ -235,8 +619,10 @@ impl<T: GlobalEvents> EventHandler<T> {
                     };
 
                     let (widget_id, state_id) = (entry.widget_id, entry.state_id);
+                    let ancestors = event_ctx.components.ancestors(event.parent.into());
 
                     let strings = event_ctx.context.strings;
+                    let internal_ident = strings.get_ref_unchecked(event.internal);
 
                     let mut event_ctx = EventCtx {
                         states,
@@ -244,14 +630,46 @@ impl<T: GlobalEvents> EventHandler<T> {
                         attribute_storage: event_ctx.attribute_storage,
                         assoc_events: event_ctx.assoc_events,
                         focus_queue: event_ctx.focus_queue,
+                        timers: event_ctx.timers,
+                        mounts: event_ctx.mounts,
+                        keymap: event_ctx.keymap,
                         context: event_ctx.context,
                         dirty_widgets: event_ctx.dirty_widgets,
                     };
 
+                    // Discard any stop from a previous event
+                    event_ctx.assoc_events.take_stopped();
+
+                    // Capture phase: from the root down to the ancestor closest to the parent
+                    for &(widget_id, state_id) in &ancestors {
+                        tree.with_component(widget_id, state_id, &mut event_ctx, |comp, ctx| {
+                            comp.any_capture(ctx, internal_ident, common_val)
+                        });
+
+                        if event_ctx.assoc_events.take_stopped() {
+                            return;
+                        }
+                    }
+
                     tree.with_component(widget_id, state_id, &mut event_ctx, |comp, ctx| {
                         let event_ident = strings.get_ref_unchecked(event.external);
                         comp.any_receive(ctx, event_ident, common_val)
                     });
+
+                    if event_ctx.assoc_events.take_stopped() {
+                        return;
+                    }
+
+                    // Bubble phase: from the ancestor closest to the parent up to the root
+                    for &(widget_id, state_id) in ancestors.iter().rev() {
+                        tree.with_component(widget_id, state_id, &mut event_ctx, |comp, ctx| {
+                            comp.any_bubble(ctx, internal_ident, common_val)
+                        });
+
+                        if event_ctx.assoc_events.take_stopped() {
+                            return;
+                        }
+                    }
                 })
             }
         }
@@ -259,48 +677,39 @@ impl<T: GlobalEvents> EventHandler<T> {
         // -----------------------------------------------------------------------------
         //   - Drain focus queue -
         // -----------------------------------------------------------------------------
-        while let Some((key, value)) = event_ctx.focus_queue.pop() {
-            let len = event_ctx.components.len();
-            for i in 0..len {
-                let (widget_id, state_id) = event_ctx
-                    .components
-                    .get(i)
-                    .expect("components can not change during this call");
-
-                let found = tree.with_value_mut(widget_id, |_, widget, _| {
-                    let WidgetKind::Component(component) = widget else { unreachable!() };
-
-                    let attribs = event_ctx.attribute_storage.get(widget_id);
-                    let Some(val) = attribs.get_val(&key) else { return false };
-                    let Some(either) = val.load_common_val() else { return false };
-                    let Some(cv) = either.to_common() else { return false };
-                    if value != cv {
-                        return false;
-                    }
-
-                    if !component.dyn_component.any_accept_focus() {
-                        return false;
-                    }
-
-                    true
-                });
+        while let Some(request) = event_ctx.focus_queue.pop() {
+            let index = find_focus_target(event_ctx, tree, &request);
 
+            if let Some(index) = index {
                 // -----------------------------------------------------------------------------
                 //   - Blur -
                 // -----------------------------------------------------------------------------
-                if let Some((widget_id, state_id)) = event_ctx.components.get(event_ctx.components.tab_index) {
+                if let Some((widget_id, state_id)) = event_ctx.components.current() {
                     tree.with_component(widget_id, state_id, event_ctx, |comp, ctx| comp.any_blur(ctx));
                 }
 
-                if found {
-                    event_ctx.components.tab_index = i;
+                // -----------------------------------------------------------------------------
+                //   - Focus -
+                // -----------------------------------------------------------------------------
+                event_ctx.components.tab_index = index;
+                if let Some((widget_id, state_id)) = event_ctx.components.current() {
                     tree.with_component(widget_id, state_id, event_ctx, |comp, ctx| comp.any_focus(ctx));
-                    break;
                 }
             }
+
+            if let Some(on_result) = request.on_result {
+                on_result(index.is_some());
+            }
         }
 
-        Ok(())
+        // -----------------------------------------------------------------------------
+        //   - Move focus into the active focus scope -
+        // -----------------------------------------------------------------------------
+        if !in_scope(event_ctx, event_ctx.components.tab_index) {
+            move_focus(event_ctx, tree, Dir::F);
+        }
+
+        Ok(handled)
     }
 }
 
@@ -313,12 +722,17 @@ pub(crate) struct EventCtx<'a, 'rt, 'bp> {
     pub attribute_storage: &'a mut AttributeStorage<'bp>,
     pub assoc_events: &'a mut AssociatedEvents,
     pub focus_queue: &'a mut FocusQueue<'static>,
+    pub timers: &'a mut Timers,
+    pub mounts: &'a mut Mounts,
+    pub keymap: &'a mut Keymap,
     pub context: UntypedContext<'rt>,
 }
 
 pub struct GlobalContext<'rt> {
     emitter: &'rt Emitter,
     focus_queue: &'rt mut FocusQueue<'static>,
+    mounts: &'rt mut Mounts,
+    keymap: &'rt mut GlobalKeymap,
 }
 
 impl<'rt> GlobalContext<'rt> {
@@ -326,7 +740,7 @@ impl<'rt> GlobalContext<'rt> {
     pub fn emit<M: 'static + Send + Sync>(&self, recipient: ComponentId<M>, value: M) {
         self.emitter
             .emit(recipient, value)
-            .expect("this will not fail unless the runtime is droped")
+            .expect("this will not fail unless the runtime is dropped")
     }
 
     /// Queue a focus call to a component that might have
@@ -334,6 +748,56 @@ impl<'rt> GlobalContext<'rt> {
     pub fn set_focus(&mut self, key: impl Into<Cow<'static, str>>, value: impl Into<CommonVal<'static>>) {
         self.focus_queue.push(key.into(), value.into());
     }
+
+    /// Queue a focus call to the first component with the given name.
+    pub fn focus_by_name(&mut self, name: impl Into<Cow<'static, str>>) -> &mut FocusRequest<'static> {
+        self.focus_queue.push_request(FocusTarget::Name(name.into()))
+    }
+
+    /// Queue a focus call to the next component that accepts focus
+    /// inside the component with the given name.
+    pub fn focus_next_in(&mut self, group: impl Into<Cow<'static, str>>) -> &mut FocusRequest<'static> {
+        self.focus_queue.push_request(FocusTarget::NextIn(group.into()))
+    }
+
+    /// Mount a component by name at the end of the element with a matching `outlet` attribute.
+    /// See [`Context::mount`](anathema_widgets::components::Context::mount).
+    pub fn mount(&mut self, component: impl Into<Cow<'static, str>>, outlet: impl Into<Cow<'static, str>>) -> MountId {
+        self.mounts.mount(component.into(), outlet.into())
+    }
+
+    /// Remove a mounted component
+    pub fn unmount(&mut self, id: MountId) {
+        self.mounts.unmount(id)
+    }
+
+    /// Show a screen on top of the current screen.
+    /// See [`Context::push_screen`](anathema_widgets::components::Context::push_screen).
+    pub fn push_screen(&mut self, name: impl Into<Cow<'static, str>>) {
+        self.mounts.screen(ScreenRequest::Push(name.into()))
+    }
+
+    /// Go back to the previous screen
+    pub fn pop_screen(&mut self) {
+        self.mounts.screen(ScreenRequest::Pop)
+    }
+
+    /// Replace the current screen
+    pub fn replace_screen(&mut self, name: impl Into<Cow<'static, str>>) {
+        self.mounts.screen(ScreenRequest::Replace(name.into()))
+    }
+
+    /// The global shortcuts, e.g to rebind an action
+    pub fn keymap(&mut self) -> &mut GlobalKeymap {
+        self.keymap
+    }
+
+    /// Stop the runtime with an exit code, or a [`Shutdown`] with a reason
+    pub fn shutdown(&self, shutdown: impl Into<Shutdown>) {
+        self.emitter
+            .shutdown(shutdown)
+            .expect("this will not fail unless the runtime is dropped")
+    }
 }
 
 pub trait GlobalEvents {
@@ -347,6 +811,10 @@ pub trait GlobalEvents {
     fn enable_tab_navigation(&mut self) -> bool {
         true
     }
+
+    /// Called when a shortcut bound to a named action is pressed.
+    /// See [`GlobalKeymap`].
+    fn action(&mut self, _action: &str, _: &mut Elements<'_, '_>, _: &mut GlobalContext<'_>) {}
 }
 
 impl GlobalEvents for () {
@@ -354,3 +822,229 @@ impl GlobalEvents for () {
         Some(event)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use anathema_backend::assert_frame_eq;
+    use anathema_backend::tui::Attributes;
+    use anathema_state::{Map, Value};
+    use anathema_widgets::components::events::{
+        Event, KeyCode, KeyEvent, MouseButton, MouseEvent, MouseState, RawEvent, UserEvent,
+    };
+    use anathema_widgets::components::{Component, Context};
+    use anathema_widgets::Elements;
+
+    use crate::testing::test::{count, Counter};
+    use crate::testing::ComponentTest;
+
+    #[test]
+    fn user_events() {
+        let mut state = Map::empty();
+        state.insert("count", 0);
+
+        let mut test = ComponentTest::new("text count", Counter, state, (5, 1)).unwrap();
+        test.run(|frame| {
+            frame.user_event(UserEvent::new(2usize)).tick();
+            assert_eq!(frame.with_state(count), 2);
+
+            frame.user_event(UserEvent::broadcast(3usize)).tick();
+            assert_eq!(frame.with_state(count), 5);
+
+            // Events of a different type are ignored
+            frame.user_event(UserEvent::new("nope")).tick();
+            assert_eq!(frame.output(), "5    \n");
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn resume_redraws() {
+        let mut state = Map::empty();
+        state.insert("count", 0);
+
+        let mut test = ComponentTest::new("text count", Counter, state, (5, 1)).unwrap();
+        test.run(|frame| {
+            frame.frame.runtime.backend.output.clear();
+            frame.tick();
+            assert_eq!(frame.output(), "");
+
+            frame.event(Event::Resume).tick();
+            assert_eq!(frame.output(), "0    \n");
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn debug_overlay() {
+        let template = "
+border
+    padding [padding: 2]
+        text 'hi'
+";
+        let mut test = ComponentTest::new(template, (), (), (10, 7)).unwrap();
+        test.runtime.keymap().bind_debug_overlay("ctrl+l").unwrap();
+        let ctrl_l = KeyEvent::press(KeyCode::Char('l')).with_ctrl();
+
+        test.run(|frame| {
+            frame.key(ctrl_l).tick();
+            assert_frame_eq!(
+                frame.grid(),
+                "
+┌border┐
+│┌padd┐│
+││····││
+││·hi·││
+││····││
+│└────┘│
+└──────┘
+"
+            );
+
+            frame.key(ctrl_l).tick();
+            assert_frame_eq!(
+                frame.grid(),
+                "
+┌──────┐
+│      │
+│      │
+│  hi  │
+│      │
+│      │
+└──────┘
+"
+            );
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn scroll_overflow_under_cursor() {
+        let template = "
+vstack
+    container [height: 2]
+        overflow
+            for i in [1, 2, 3]
+                text i
+    text 'x'
+";
+        let scroll = |y| Event::Mouse(MouseEvent::new(0, y, MouseState::ScrollDown));
+
+        let mut test = ComponentTest::new(template, Counter, Map::empty(), (1, 3)).unwrap();
+        test.run(|frame| {
+            assert_eq!(frame.output(), "1\n2\nx\n");
+
+            frame.event(scroll(0)).tick();
+            assert_eq!(frame.output(), "2\n3\nx\n");
+
+            // Not over the overflow
+            frame.event(scroll(2)).tick();
+            assert_eq!(frame.output(), "2\n3\nx\n");
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn terminal_focus_gained() {
+        let mut state = Map::empty();
+        state.insert("count", 0);
+
+        let mut test = ComponentTest::new("text count", Counter, state, (5, 1)).unwrap();
+        test.run(|frame| {
+            frame.event(Event::FocusGained).tick();
+            assert_eq!(frame.with_state(count), 1000);
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn terminal_focus_lost() {
+        let mut state = Map::empty();
+        state.insert("count", 0);
+
+        let mut test = ComponentTest::new("text count", Counter, state, (5, 1)).unwrap();
+        test.run(|frame| {
+            frame.event(Event::FocusLost).tick();
+            assert_eq!(frame.with_state(count), 100);
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn select_and_copy() {
+        let template = "
+vstack
+    hstack
+        text 'one'
+        text ' two'
+    border
+        text 'three'
+";
+        let mut test = ComponentTest::new(template, (), (), (10, 4)).unwrap();
+        test.runtime.keymap().bind_copy("ctrl+y").unwrap();
+        let ctrl_y = KeyEvent::press(KeyCode::Char('y')).with_ctrl();
+        let left = MouseButton::Left;
+
+        test.run(|frame| {
+            frame
+                .mouse(1, 0, MouseState::Down(left))
+                .mouse(3, 2, MouseState::Drag(left))
+                .mouse(3, 2, MouseState::Up(left))
+                .tick();
+
+            let grid = frame.grid();
+            assert!(!grid.get(0, 0).unwrap().style.attributes.contains(Attributes::INVERSE));
+            assert!(grid.get(1, 0).unwrap().style.attributes.contains(Attributes::INVERSE));
+            assert!(grid.get(9, 1).unwrap().style.attributes.contains(Attributes::INVERSE));
+            assert!(!grid.get(4, 2).unwrap().style.attributes.contains(Attributes::INVERSE));
+
+            // The border is not part of the text
+            frame.key(ctrl_y).tick();
+            assert_eq!(frame.frame.runtime.backend.clipboard.as_deref(), Some("ne two\n\nthr"));
+
+            // Pressing the button clears the selection
+            frame.mouse(0, 3, MouseState::Down(left)).tick();
+            assert!(!frame
+                .grid()
+                .get(1, 0)
+                .unwrap()
+                .style
+                .attributes
+                .contains(Attributes::INVERSE));
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn raw_events() {
+        struct Player;
+
+        impl Component for Player {
+            type Message = ();
+            type State = Value<Map<String>>;
+
+            fn on_raw(
+                &mut self,
+                event: &RawEvent,
+                state: &mut Self::State,
+                _: Elements<'_, '_>,
+                _: Context<'_, Self::State>,
+            ) {
+                if let Some(&key) = event.get::<&str>() {
+                    let mut map = state.to_mut();
+                    map.get_mut("key")
+                        .expect("the key is set by the test")
+                        .set(key.to_string());
+                }
+            }
+        }
+
+        let mut state = Map::empty();
+        state.insert("key", String::new());
+        let mut test = ComponentTest::new("text key", Player, state, (5, 1)).unwrap();
+        test.run(|frame| {
+            frame.event(Event::Raw(RawEvent::new("play"))).tick();
+            assert_eq!(frame.output(), "play \n");
+        })
+        .unwrap();
+    }
+}
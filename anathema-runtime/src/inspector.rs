@@ -0,0 +1,480 @@
+// A debug server streaming snapshots of the runtime to external inspectors,
+// see `RuntimeBuilder::inspector`.
+use std::collections::HashSet;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anathema_geometry::Region;
+use anathema_state::{AnyState, CommonVal, Path, States};
+use anathema_store::tree::{Node, TreeValues};
+use anathema_widgets::expressions::EvalValue;
+use anathema_widgets::{AttributeStorage, DirtyWidgets, WidgetId, WidgetKind, WidgetTree};
+use serde_json::{json, Map, Value};
+
+use crate::FrameStats;
+
+// A client that doesn't read its snapshots is disconnected
+// rather than blocking the runtime
+const WRITE_TIMEOUT: Duration = Duration::from_millis(100);
+
+// Nested state is only included to this depth
+const MAX_DEPTH: usize = 4;
+
+type Clients = Arc<Mutex<Vec<Box<dyn Write + Send>>>>;
+
+#[derive(Debug)]
+enum Command {
+    Snapshot,
+    Select(Option<WidgetId>),
+    SetAttribute { id: WidgetId, key: String, value: Value },
+    Error(String),
+}
+
+impl Command {
+    fn parse(line: &str) -> Self {
+        let value = match serde_json::from_str::<Value>(line) {
+            Ok(value) => value,
+            Err(err) => return Self::Error(err.to_string()),
+        };
+
+        let id = value.get("id").and_then(Value::as_u64).map(WidgetId::from);
+
+        match value.get("command").and_then(Value::as_str) {
+            Some("snapshot") => Self::Snapshot,
+            Some("select") => Self::Select(id),
+            Some("set_attribute") => {
+                let key = value.get("key").and_then(Value::as_str);
+                match (id, key, value.get("value")) {
+                    (Some(id), Some(key), Some(value)) => Self::SetAttribute {
+                        id,
+                        key: key.into(),
+                        value: value.clone(),
+                    },
+                    _ => Self::Error("set_attribute requires an id, a key and a value".into()),
+                }
+            }
+            Some(cmd) => Self::Error(format!("unknown command `{cmd}`")),
+            None => Self::Error("missing command".into()),
+        }
+    }
+}
+
+/// The runtime side of the debug server
+pub(crate) struct Inspector {
+    clients: Clients,
+    commands: flume::Receiver<Command>,
+    sender: flume::Sender<Command>,
+    selected: Option<WidgetId>,
+    // Attributes borrow their keys and strings from the templates,
+    // so the strings set by inspectors live for the rest of the program
+    strings: HashSet<&'static str>,
+    snapshot_pending: bool,
+}
+
+impl Inspector {
+    pub(crate) fn new() -> Self {
+        let (sender, commands) = flume::unbounded();
+        Self {
+            clients: Arc::default(),
+            commands,
+            sender,
+            selected: None,
+            strings: HashSet::new(),
+            snapshot_pending: false,
+        }
+    }
+
+    /// Accept inspectors on a TCP socket
+    pub(crate) fn listen_tcp(&self, addr: impl ToSocketAddrs) -> std::io::Result<SocketAddr> {
+        let listener = TcpListener::bind(addr)?;
+        let addr = listener.local_addr()?;
+        let clients = self.clients.clone();
+        let sender = self.sender.clone();
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let Ok(writer) = stream.try_clone() else { continue };
+                _ = writer.set_write_timeout(Some(WRITE_TIMEOUT));
+                accept(&clients, &sender, Box::new(writer), stream);
+            }
+        });
+
+        Ok(addr)
+    }
+
+    /// Accept inspectors on a unix socket.
+    /// The socket file is replaced if it exists.
+    #[cfg(unix)]
+    pub(crate) fn listen_unix(&self, path: &std::path::Path) -> std::io::Result<()> {
+        use std::os::unix::net::UnixListener;
+
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+
+        let listener = UnixListener::bind(path)?;
+        let clients = self.clients.clone();
+        let sender = self.sender.clone();
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let Ok(writer) = stream.try_clone() else { continue };
+                _ = writer.set_write_timeout(Some(WRITE_TIMEOUT));
+                accept(&clients, &sender, Box::new(writer), stream);
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Apply the commands from the inspectors
+    pub(crate) fn apply_commands<'bp>(
+        &mut self,
+        tree: &WidgetTree<'bp>,
+        attribute_storage: &mut AttributeStorage<'bp>,
+        dirty_widgets: &mut DirtyWidgets,
+    ) {
+        while let Ok(command) = self.commands.try_recv() {
+            self.snapshot_pending = true;
+            match command {
+                Command::Snapshot => {}
+                Command::Select(id) => self.selected = id,
+                Command::SetAttribute { id, key, value } => {
+                    let Some(WidgetKind::Element(_)) = tree.get_ref_by_id(id) else {
+                        self.send(&json!({ "type": "error", "message": "no element with the id" }));
+                        continue;
+                    };
+
+                    let Some(value) = self.common_val(&value) else {
+                        let msg = "the value has to be a bool, number or string";
+                        self.send(&json!({ "type": "error", "message": msg }));
+                        continue;
+                    };
+
+                    let key = self.intern(&key);
+                    attribute_storage.get_mut(id).set(key, value);
+                    dirty_widgets.push(id);
+                }
+                Command::Error(message) => self.send(&json!({ "type": "error", "message": message })),
+            }
+        }
+    }
+
+    /// Send a snapshot to the inspectors, if the frame was painted
+    /// or a command was applied
+    pub(crate) fn frame<'bp>(
+        &mut self,
+        stats: &FrameStats,
+        tree: &mut WidgetTree<'bp>,
+        attribute_storage: &AttributeStorage<'bp>,
+        states: &States,
+    ) {
+        let pending = std::mem::take(&mut self.snapshot_pending);
+        if !stats.painted() && !pending {
+            return;
+        }
+
+        if self.clients.lock().map(|clients| clients.is_empty()).unwrap_or(true) {
+            return;
+        }
+
+        let mut nodes = vec![];
+        let (tree_nodes, values) = tree.split();
+        collect_nodes(tree_nodes, values, 0, states, &mut nodes);
+
+        let selected = self
+            .selected
+            .filter(|id| matches!(tree.get_ref_by_id(*id), Some(WidgetKind::Element(_))))
+            .map(|id| {
+                let attributes = attribute_storage.get(id);
+                let values = attributes
+                    .iter()
+                    .map(|(key, value)| (key.to_str().to_string(), eval_value(value)))
+                    .collect::<Map<_, _>>();
+                json!({
+                    "id": u64::from(id),
+                    "attributes": values,
+                    "value": attributes.value().map(|value| eval_value(value)),
+                })
+            });
+
+        let snapshot = json!({
+            "type": "frame",
+            "stats": {
+                "layout_us": stats.layout.as_micros() as u64,
+                "paint_us": stats.paint.as_micros() as u64,
+                "cells_written": stats.cells_written,
+                "widgets": stats.widgets,
+                "capacity": stats.capacity,
+                "laid_out": stats.laid_out,
+                "changes": stats.changes,
+                "interrupted": stats.interrupted,
+            },
+            "tree": nodes,
+            "selected": selected,
+        });
+
+        self.send(&snapshot);
+    }
+
+    // Write a message as a single line to every inspector,
+    // dropping the inspectors that can't be written to
+    fn send(&self, message: &Value) {
+        let mut line = message.to_string();
+        line.push('\n');
+
+        let Ok(mut clients) = self.clients.lock() else { return };
+        clients.retain_mut(|client| client.write_all(line.as_bytes()).and_then(|_| client.flush()).is_ok());
+    }
+
+    fn intern(&mut self, s: &str) -> &'static str {
+        match self.strings.get(s) {
+            Some(s) => s,
+            None => {
+                let s: &'static str = Box::leak(s.into());
+                self.strings.insert(s);
+                s
+            }
+        }
+    }
+
+    fn common_val(&mut self, value: &Value) -> Option<CommonVal<'static>> {
+        let value = match value {
+            Value::Bool(b) => CommonVal::Bool(*b),
+            Value::Number(n) => match n.as_i64() {
+                Some(n) => CommonVal::Int(n),
+                None => CommonVal::Float(n.as_f64()?),
+            },
+            Value::String(s) => CommonVal::Str(self.intern(s)),
+            _ => return None,
+        };
+        Some(value)
+    }
+}
+
+fn accept(
+    clients: &Clients,
+    sender: &flume::Sender<Command>,
+    writer: Box<dyn Write + Send>,
+    reader: impl Read + Send + 'static,
+) {
+    let Ok(mut clients) = clients.lock() else { return };
+    clients.push(writer);
+    _ = sender.send(Command::Snapshot);
+
+    let sender = sender.clone();
+    std::thread::spawn(move || {
+        for line in BufReader::new(reader).lines() {
+            let Ok(line) = line else { break };
+            if line.trim().is_empty() {
+                continue;
+            }
+            if sender.send(Command::parse(&line)).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+// Elements and components, with the state of the components.
+// Loops and control flow don't add any depth.
+fn collect_nodes(
+    nodes: &[Node],
+    values: &TreeValues<WidgetKind<'_>>,
+    depth: usize,
+    states: &States,
+    output: &mut Vec<Value>,
+) {
+    for node in nodes {
+        let Some((_, widget)) = values.get(node.value()) else { continue };
+        let id = u64::from(node.value());
+        let depth = match widget {
+            WidgetKind::Element(el) => {
+                let Region { from, to } = el.region();
+                output.push(json!({
+                    "id": id,
+                    "depth": depth,
+                    "kind": "element",
+                    "ident": el.ident,
+                    "region": [from.x, from.y, to.x - from.x, to.y - from.y],
+                }));
+                depth + 1
+            }
+            WidgetKind::Component(component) => {
+                let state = states
+                    .get(component.state_id)
+                    .map(|state| state_value(state, 0))
+                    .unwrap_or(Value::Null);
+                output.push(json!({
+                    "id": id,
+                    "depth": depth,
+                    "kind": "component",
+                    "name": component.name,
+                    "state": state,
+                }));
+                depth + 1
+            }
+            WidgetKind::If(widget) if !widget.show => continue,
+            WidgetKind::Else(widget) if !widget.show => continue,
+            _ => depth,
+        };
+
+        collect_nodes(node.children(), values, depth, states, output);
+    }
+}
+
+fn common_value(value: CommonVal<'_>) -> Value {
+    match value {
+        CommonVal::Bool(b) => Value::Bool(b),
+        CommonVal::Int(n) => Value::from(n),
+        CommonVal::Float(n) => Value::from(n),
+        CommonVal::Str(s) => Value::from(s),
+        value => Value::from(value.to_string()),
+    }
+}
+
+fn eval_value(value: &EvalValue<'_>) -> Value {
+    value
+        .load_common_val()
+        .and_then(|value| value.to_common().map(common_value))
+        .unwrap_or(Value::Null)
+}
+
+// Derived states become objects and collections become arrays.
+// Maps can't list their keys and are left out.
+fn state_value(state: &dyn AnyState, depth: usize) -> Value {
+    if let Some(value) = state.to_common() {
+        return common_value(value);
+    }
+
+    if depth == MAX_DEPTH {
+        return Value::Null;
+    }
+
+    let lookup = |path| {
+        state
+            .state_lookup(path)
+            .map(|value| value.as_state(|state| state_value(state, depth + 1)))
+            .unwrap_or(Value::Null)
+    };
+
+    let keys = state.state_keys();
+    match keys.is_empty() {
+        false => Value::Object(
+            keys.iter()
+                .map(|key| (key.to_string(), lookup(Path::Key(key))))
+                .collect(),
+        ),
+        true => Value::Array((0..state.count()).map(|i| lookup(Path::Index(i))).collect()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::TcpStream;
+
+    use anathema_backend::testing::TestBackend;
+    use anathema_templates::Document;
+
+    use super::*;
+    use crate::{Frame, Runtime};
+
+    // Tick the runtime until the messages received by the inspector
+    // satisfy the predicate
+    fn messages(
+        frame: &mut Frame<'_, '_, TestBackend, ()>,
+        messages: &flume::Receiver<Value>,
+        predicate: impl Fn(&[Value]) -> bool,
+    ) -> Vec<Value> {
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        let mut output = vec![];
+        while !predicate(&output) {
+            assert!(std::time::Instant::now() < deadline, "received {output:?}");
+            frame.tick().unwrap();
+            output.extend(messages.try_iter());
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        output
+    }
+
+    #[test]
+    fn inspect_and_set_attribute() {
+        let mut document = Document::new("text [bold: false] 'hi'");
+        document.hot_reload = false;
+        let mut builder = Runtime::builder(document, TestBackend::new((5, 1)));
+        let addr = builder.inspector("127.0.0.1:0").unwrap();
+        let mut runtime = builder.finish().unwrap();
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        let (sender, receiver) = flume::unbounded();
+        let reader = BufReader::new(stream.try_clone().unwrap());
+        std::thread::spawn(move || {
+            for line in reader.lines() {
+                let Ok(line) = line else { break };
+                _ = sender.send(serde_json::from_str::<Value>(&line).unwrap());
+            }
+        });
+
+        runtime
+            .headless(|frame| {
+                // Connecting sends a snapshot
+                let snapshot = messages(frame, &receiver, |msgs| !msgs.is_empty()).remove(0);
+                assert_eq!(snapshot["type"], "frame");
+                assert_eq!(snapshot["tree"][0]["ident"], "text");
+                assert_eq!(snapshot["tree"][0]["region"], json!([0, 0, 2, 1]));
+                assert_eq!(snapshot["selected"], Value::Null);
+
+                let id = snapshot["tree"][0]["id"].as_u64().unwrap();
+                writeln!(stream, r#"{{"command": "select", "id": {id}}}"#).unwrap();
+                writeln!(
+                    stream,
+                    r#"{{"command": "set_attribute", "id": {id}, "key": "bold", "value": true}}"#
+                )
+                .unwrap();
+                writeln!(stream, "nope").unwrap();
+
+                // The commands are applied over one or more frames
+                let messages = messages(frame, &receiver, |msgs| {
+                    msgs.iter().any(|msg| msg["type"] == "error")
+                        && msgs.iter().any(|msg| msg["selected"]["attributes"]["bold"] == true)
+                });
+
+                let frame = messages.iter().rev().find(|msg| msg["type"] == "frame").unwrap();
+                let selected = &frame["selected"];
+                assert_eq!(selected["id"], id);
+                assert_eq!(selected["attributes"]["bold"], true);
+                assert_eq!(selected["value"], "hi");
+                Ok(())
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn parse_commands() {
+        assert!(matches!(
+            Command::parse(r#"{"command": "snapshot"}"#),
+            Command::Snapshot
+        ));
+        assert!(matches!(
+            Command::parse(r#"{"command": "select", "id": 3}"#),
+            Command::Select(Some(id)) if u64::from(id) == 3
+        ));
+        assert!(matches!(
+            Command::parse(r#"{"command": "select"}"#),
+            Command::Select(None)
+        ));
+
+        let cmd = Command::parse(r#"{"command": "set_attribute", "id": 1, "key": "bold", "value": true}"#);
+        let Command::SetAttribute { key, value, .. } = cmd else { panic!("{cmd:?}") };
+        assert_eq!((key.as_str(), value), ("bold", Value::Bool(true)));
+
+        assert!(matches!(
+            Command::parse(r#"{"command": "set_attribute", "id": 1}"#),
+            Command::Error(_)
+        ));
+        assert!(matches!(Command::parse(r#"{"command": "nope"}"#), Command::Error(_)));
+        assert!(matches!(Command::parse("not json"), Command::Error(_)));
+    }
+}
@@ -0,0 +1,516 @@
+use std::borrow::Cow;
+use std::rc::Rc;
+use std::time::Duration;
+
+use anathema_widgets::components::events::{KeyEvent, KeyState};
+use anathema_widgets::components::{ComponentId, Emitter, Shortcut};
+use anathema_widgets::time::Instant;
+
+use crate::error::Result;
+
+/// What a global shortcut does
+#[derive(Clone)]
+pub(crate) enum Action {
+    /// Passed to [`GlobalEvents::action`](crate::GlobalEvents::action)
+    Named(Cow<'static, str>),
+    /// Sends a message to a component
+    Message(Rc<dyn Fn(&Emitter)>),
+    /// Toggles the layout debug overlay
+    DebugOverlay,
+    /// Copies the mouse selection to the clipboard
+    Copy,
+    /// Toggles the devtools
+    #[cfg(feature = "devtools")]
+    Devtools,
+}
+
+/// The outcome of looking up a sequence of keys in the [`GlobalKeymap`]
+pub(crate) enum GlobalMatch {
+    Action(Action),
+    Pending,
+    None,
+}
+
+// How long a sequence of keys waits for the next key
+const SEQUENCE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// App-wide shortcuts, consulted before the event is passed to
+/// [`GlobalEvents::handle`](crate::GlobalEvents::handle) and the focused component.
+///
+/// A shortcut either triggers a named action,
+/// passed to [`GlobalEvents::action`](crate::GlobalEvents::action),
+/// or sends a message to a component.
+///
+/// ```ignore
+/// let keymap = builder.keymap();
+/// keymap.bind("ctrl+s", "save")?;
+/// keymap.bind("g g", "top")?;
+/// keymap.bind_message("ctrl+r", counter, CounterMsg::Reset)?;
+///
+/// // Later, e.g from `GlobalEvents::action`
+/// ctx.keymap().rebind("save", "ctrl+w")?;
+/// ```
+///
+/// Every shortcut has a single binding: binding a shortcut again replaces the previous binding.
+///
+/// Keys that are the start of a shortcut, e.g the first `g` of `g g`, are held back.
+/// If the next key doesn't continue the shortcut, or no key is pressed
+/// within the [sequence timeout](Self::set_sequence_timeout), the keys are handled
+/// as if they were never part of a shortcut.
+pub struct GlobalKeymap {
+    bindings: Vec<(Shortcut, Action)>,
+    sequence_timeout: Duration,
+}
+
+impl Default for GlobalKeymap {
+    fn default() -> Self {
+        Self {
+            bindings: vec![],
+            sequence_timeout: SEQUENCE_TIMEOUT,
+        }
+    }
+}
+
+impl GlobalKeymap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How long keys that are the start of a shortcut are held back,
+    /// waiting for the next key of the shortcut (one second by default).
+    /// This applies to the shortcuts of the components as well.
+    pub fn set_sequence_timeout(&mut self, timeout: Duration) {
+        self.sequence_timeout = timeout;
+    }
+
+    /// Bind a shortcut to a named action
+    pub fn bind(&mut self, shortcut: &str, action: impl Into<Cow<'static, str>>) -> Result<()> {
+        let shortcut = Shortcut::parse(shortcut)?;
+        self.insert(shortcut, Action::Named(action.into()));
+        Ok(())
+    }
+
+    /// Bind a shortcut to a message, sent to the component
+    pub fn bind_message<M>(&mut self, shortcut: &str, recipient: ComponentId<M>, message: M) -> Result<()>
+    where
+        M: 'static + Send + Sync + Clone,
+    {
+        let shortcut = Shortcut::parse(shortcut)?;
+        let send = move |emitter: &Emitter| _ = emitter.emit(recipient, message.clone());
+        self.insert(shortcut, Action::Message(Rc::new(send)));
+        Ok(())
+    }
+
+    /// Bind a shortcut that toggles the layout debug overlay,
+    /// which paints the boundaries, padding and idents of the elements on top of the frame.
+    /// See [`LayoutOverlay`](anathema_widgets::debug::LayoutOverlay).
+    ///
+    /// ```ignore
+    /// builder.keymap().bind_debug_overlay("ctrl+l")?;
+    /// ```
+    pub fn bind_debug_overlay(&mut self, shortcut: &str) -> Result<()> {
+        let shortcut = Shortcut::parse(shortcut)?;
+        self.insert(shortcut, Action::DebugOverlay);
+        Ok(())
+    }
+
+    /// Bind a shortcut that copies the text selected with the mouse to the clipboard,
+    /// using OSC 52 (terminals that don't support it ignore the copy).
+    ///
+    /// Selecting text is only enabled once a copy shortcut is bound:
+    /// dragging with the left button held down highlights the text,
+    /// spanning any number of widgets, and pressing the left button again clears the selection.
+    /// See [`Selection`](anathema_widgets::Selection).
+    ///
+    /// Mouse events are still passed on to the components while selecting.
+    /// The backend has to enable the mouse, e.g `TuiBackendBuilder::enable_mouse`.
+    ///
+    /// ```ignore
+    /// builder.keymap().bind_copy("ctrl+y")?;
+    /// ```
+    pub fn bind_copy(&mut self, shortcut: &str) -> Result<()> {
+        let shortcut = Shortcut::parse(shortcut)?;
+        self.insert(shortcut, Action::Copy);
+        Ok(())
+    }
+
+    /// Bind a shortcut that toggles the devtools: a panel along the right edge of the screen
+    /// showing the widget tree, the attributes of the selected element,
+    /// the state of the selected component and the frame stats.
+    ///
+    /// While the devtools are open:
+    /// * `up` / `k` and `down` / `j` select the previous and next widget
+    /// * `page up` and `page down` move the selection ten widgets at a time
+    /// * `home` / `g` and `end` / `G` select the first and the last widget
+    /// * `esc` closes the devtools
+    ///
+    /// These keys are not passed on to the application while the devtools are open.
+    /// Component state lists the fields of derived states.
+    ///
+    /// ```ignore
+    /// builder.keymap().bind_devtools("f12")?;
+    /// ```
+    #[cfg(feature = "devtools")]
+    pub fn bind_devtools(&mut self, shortcut: &str) -> Result<()> {
+        let shortcut = Shortcut::parse(shortcut)?;
+        self.insert(shortcut, Action::Devtools);
+        Ok(())
+    }
+
+    /// Replace the shortcut of a named action
+    pub fn rebind(&mut self, action: &str, shortcut: &str) -> Result<()> {
+        let shortcut = Shortcut::parse(shortcut)?;
+        self.bindings
+            .retain(|(_, bound)| !matches!(bound, Action::Named(name) if name == action));
+        self.insert(shortcut, Action::Named(Cow::Owned(action.to_string())));
+        Ok(())
+    }
+
+    /// Remove the binding of a shortcut
+    pub fn unbind(&mut self, shortcut: &str) -> Result<()> {
+        let shortcut = Shortcut::parse(shortcut)?;
+        self.bindings.retain(|(bound, _)| *bound != shortcut);
+        Ok(())
+    }
+
+    /// The shortcut bound to a named action
+    pub fn shortcut(&self, action: &str) -> Option<&Shortcut> {
+        self.bindings
+            .iter()
+            .find(|(_, bound)| matches!(bound, Action::Named(name) if name == action))
+            .map(|(shortcut, _)| shortcut)
+    }
+
+    // Text can only be selected while a copy shortcut is bound
+    pub(crate) fn can_copy(&self) -> bool {
+        self.bindings.iter().any(|(_, action)| matches!(action, Action::Copy))
+    }
+
+    pub(crate) fn sequence_timeout(&self) -> Duration {
+        self.sequence_timeout
+    }
+
+    fn insert(&mut self, shortcut: Shortcut, action: Action) {
+        self.bindings.retain(|(bound, _)| *bound != shortcut);
+        self.bindings.push((shortcut, action));
+    }
+
+    // Look up a sequence of key presses
+    pub(crate) fn lookup(&self, keys: &[KeyEvent]) -> GlobalMatch {
+        if keys.is_empty() || keys.iter().any(|key| key.state != KeyState::Press) {
+            return GlobalMatch::None;
+        }
+
+        match self.bindings.iter().find(|(shortcut, _)| shortcut.is_match(keys)) {
+            Some((_, action)) => GlobalMatch::Action(action.clone()),
+            None if self.any_started(keys) => GlobalMatch::Pending,
+            None => GlobalMatch::None,
+        }
+    }
+
+    fn any_started(&self, keys: &[KeyEvent]) -> bool {
+        self.bindings.iter().any(|(shortcut, _)| shortcut.starts_with(keys))
+    }
+}
+
+/// The outcome of looking up the keys of a [`KeySequence`]
+pub(crate) enum Lookup<T> {
+    /// The keys are a shortcut
+    Match(T),
+    /// The keys are the start of a shortcut
+    Prefix,
+    None,
+}
+
+/// Keys released from a [`KeySequence`], followed by the shortcut completed by the keys, if any
+pub(crate) struct Resolved<T> {
+    pub(crate) released: Vec<KeyEvent>,
+    pub(crate) shortcut: Option<T>,
+}
+
+/// The key presses that are the start of a shortcut.
+///
+/// This buffer is shared by the global keymap and the keymap of the components.
+/// Once the keys can't be the start of a shortcut, or the sequence times out,
+/// the first key is released, to be handled as if there were no shortcuts,
+/// and the remaining keys are looked up again.
+#[derive(Default)]
+pub(crate) struct KeySequence {
+    keys: Vec<KeyEvent>,
+    last_key: Option<Instant>,
+}
+
+impl KeySequence {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn feed<T>(
+        &mut self,
+        key: KeyEvent,
+        now: Instant,
+        lookup: impl FnMut(&[KeyEvent]) -> Lookup<T>,
+    ) -> Resolved<T> {
+        self.keys.push(key);
+        self.last_key = Some(now);
+        self.resolve(vec![], lookup)
+    }
+
+    // Release the first key if the sequence timed out
+    pub(crate) fn expire<T>(
+        &mut self,
+        now: Instant,
+        timeout: Duration,
+        lookup: impl FnMut(&[KeyEvent]) -> Lookup<T>,
+    ) -> Resolved<T> {
+        match self.deadline(timeout) {
+            Some(deadline) if deadline <= now => {
+                self.last_key = Some(now);
+                let released = vec![self.keys.remove(0)];
+                self.resolve(released, lookup)
+            }
+            _ => Resolved {
+                released: vec![],
+                shortcut: None,
+            },
+        }
+    }
+
+    // When the sequence times out, if there are any keys
+    pub(crate) fn deadline(&self, timeout: Duration) -> Option<Instant> {
+        match self.keys.is_empty() {
+            true => None,
+            false => self.last_key.map(|last| last + timeout),
+        }
+    }
+
+    fn resolve<T>(
+        &mut self,
+        mut released: Vec<KeyEvent>,
+        mut lookup: impl FnMut(&[KeyEvent]) -> Lookup<T>,
+    ) -> Resolved<T> {
+        while !self.keys.is_empty() {
+            match lookup(&self.keys) {
+                Lookup::Match(shortcut) => {
+                    self.keys.clear();
+                    return Resolved {
+                        released,
+                        shortcut: Some(shortcut),
+                    };
+                }
+                Lookup::Prefix => break,
+                Lookup::None => released.push(self.keys.remove(0)),
+            }
+        }
+
+        Resolved {
+            released,
+            shortcut: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use anathema_state::{Map, Value};
+    use anathema_widgets::components::events::{Event, KeyCode, KeyEvent};
+    use anathema_widgets::components::{Component, Context};
+    use anathema_widgets::Elements;
+
+    use super::*;
+    use crate::testing::test::{add, count, Counter};
+    use crate::testing::ComponentTest;
+
+    fn key(c: char) -> KeyEvent {
+        KeyEvent::press(KeyCode::Char(c))
+    }
+
+    fn action(keymap: &GlobalKeymap, keys: &[KeyEvent]) -> Option<Cow<'static, str>> {
+        match keymap.lookup(keys) {
+            GlobalMatch::Action(Action::Named(name)) => Some(name),
+            _ => None,
+        }
+    }
+
+    fn lookup(keymap: &GlobalKeymap) -> impl FnMut(&[KeyEvent]) -> Lookup<Cow<'static, str>> + '_ {
+        |keys| match keymap.lookup(keys) {
+            GlobalMatch::Action(Action::Named(name)) => Lookup::Match(name),
+            GlobalMatch::Action(_) => unreachable!(),
+            GlobalMatch::Pending => Lookup::Prefix,
+            GlobalMatch::None => Lookup::None,
+        }
+    }
+
+    fn chars(keys: &[KeyEvent]) -> String {
+        keys.iter().filter_map(KeyEvent::get_char).collect()
+    }
+
+    #[test]
+    fn bind_and_rebind() {
+        let mut keymap = GlobalKeymap::new();
+        keymap.bind("q", "quit").unwrap();
+        keymap.bind("g g", "top").unwrap();
+
+        assert_eq!(action(&keymap, &[key('q')]).as_deref(), Some("quit"));
+        assert!(matches!(keymap.lookup(&[key('g')]), GlobalMatch::Pending));
+        assert_eq!(action(&keymap, &[key('g'), key('g')]).as_deref(), Some("top"));
+        assert!(matches!(keymap.lookup(&[key('x')]), GlobalMatch::None));
+
+        keymap.rebind("quit", "x").unwrap();
+        assert!(action(&keymap, &[key('q')]).is_none());
+        assert_eq!(action(&keymap, &[key('x')]).as_deref(), Some("quit"));
+        assert_eq!(keymap.shortcut("quit"), Some(&Shortcut::parse("x").unwrap()));
+
+        // Binding the same shortcut replaces the action
+        keymap.bind("x", "exit").unwrap();
+        assert_eq!(action(&keymap, &[key('x')]).as_deref(), Some("exit"));
+
+        keymap.unbind("x").unwrap();
+        assert!(action(&keymap, &[key('x')]).is_none());
+    }
+
+    #[test]
+    fn release_broken_sequence() {
+        let mut keymap = GlobalKeymap::new();
+        keymap.bind("g g", "top").unwrap();
+        keymap.bind("x", "cut").unwrap();
+        let mut sequence = KeySequence::new();
+        let now = Instant::now();
+
+        let resolved = sequence.feed(key('g'), now, lookup(&keymap));
+        assert!(resolved.released.is_empty() && resolved.shortcut.is_none());
+
+        // The `g` is released, and the `x` is a shortcut on its own
+        let resolved = sequence.feed(key('x'), now, lookup(&keymap));
+        assert_eq!(chars(&resolved.released), "g");
+        assert_eq!(resolved.shortcut.as_deref(), Some("cut"));
+
+        sequence.feed(key('g'), now, lookup(&keymap));
+        let resolved = sequence.feed(key('a'), now, lookup(&keymap));
+        assert_eq!(chars(&resolved.released), "ga");
+        assert!(resolved.shortcut.is_none());
+    }
+
+    #[test]
+    fn release_timed_out_sequence() {
+        let mut keymap = GlobalKeymap::new();
+        keymap.bind("g g", "top").unwrap();
+        let mut sequence = KeySequence::new();
+        let now = Instant::now();
+        let timeout = keymap.sequence_timeout();
+
+        sequence.feed(key('g'), now, lookup(&keymap));
+        assert_eq!(sequence.deadline(timeout), Some(now + timeout));
+        assert!(sequence.expire(now, timeout, lookup(&keymap)).released.is_empty());
+
+        let resolved = sequence.expire(now + timeout, timeout, lookup(&keymap));
+        assert_eq!(chars(&resolved.released), "g");
+        assert_eq!(sequence.deadline(timeout), None);
+    }
+
+    #[test]
+    fn global_shortcut_message() {
+        let mut state = Map::empty();
+        state.insert("count", 0);
+
+        let mut test = ComponentTest::new("text count", Counter, state, (5, 1)).unwrap();
+        let component_id = test.component_id;
+        test.runtime.keymap().bind_message("ctrl+r", component_id, 7).unwrap();
+
+        test.run(|frame| {
+            let ctrl_r = KeyEvent::press(KeyCode::Char('r')).with_ctrl();
+            // The shortcut is consumed and not passed on to the component,
+            // and the message is handled on the next frame
+            frame.event(Event::Key(ctrl_r)).tick().tick();
+            assert_eq!(frame.output(), "7    \n");
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn broken_shortcut_sequence() {
+        let mut state = Map::empty();
+        state.insert("count", 0);
+
+        let mut test = ComponentTest::new("text count", Counter, state, (5, 1)).unwrap();
+        let component_id = test.component_id;
+        test.runtime.keymap().bind_message("g g", component_id, 7).unwrap();
+
+        test.run(|frame| {
+            // The first key is held back as the start of the shortcut
+            frame.type_str("g").tick();
+            assert_eq!(frame.with_state(count), 0);
+
+            // Both keys are passed on to the component once the sequence is broken
+            frame.type_str("x").tick();
+            assert_eq!(frame.with_state(count), 2);
+
+            frame.type_str("gg").tick().tick();
+            assert_eq!(frame.with_state(count), 9);
+        })
+        .unwrap();
+    }
+
+    // Registers the shortcut `g g` once it receives a message
+    struct Jumper;
+
+    impl Component for Jumper {
+        type Message = ();
+        type State = Value<Map<usize>>;
+
+        fn on_key(&mut self, _: KeyEvent, state: &mut Self::State, _: Elements<'_, '_>, _: Context<'_, Self::State>) {
+            add(state, 1);
+        }
+
+        fn message(&mut self, _: (), _: &mut Self::State, _: Elements<'_, '_>, mut context: Context<'_, Self::State>) {
+            context.register_global_shortcut("top", "g g").unwrap();
+        }
+
+        fn on_shortcut(&mut self, _: &str, state: &mut Self::State, _: Elements<'_, '_>, _: Context<'_, Self::State>) {
+            add(state, 7);
+        }
+    }
+
+    #[test]
+    fn broken_component_shortcut_sequence() {
+        let mut state = Map::empty();
+        state.insert("count", 0);
+
+        let mut test = ComponentTest::new("text count", Jumper, state, (5, 1)).unwrap();
+        test.run(|frame| {
+            frame.send(()).tick();
+
+            frame.type_str("g").tick();
+            assert_eq!(frame.with_state(count), 0);
+
+            frame.type_str("x").tick();
+            assert_eq!(frame.with_state(count), 2);
+
+            frame.type_str("gg").tick();
+            assert_eq!(frame.with_state(count), 9);
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn timed_out_shortcut_sequence() {
+        let mut state = Map::empty();
+        state.insert("count", 0);
+
+        let mut test = ComponentTest::new("text count", Counter, state, (5, 1)).unwrap();
+        let component_id = test.component_id;
+        test.runtime.keymap().bind_message("g g", component_id, 7).unwrap();
+        test.runtime.keymap().set_sequence_timeout(Duration::ZERO);
+
+        test.run(|frame| {
+            // The key is released on the frame after the sequence timed out
+            frame.type_str("g").tick();
+            assert_eq!(frame.with_state(count), 0);
+            frame.tick();
+            assert_eq!(frame.with_state(count), 1);
+        })
+        .unwrap();
+    }
+}
@@ -17,38 +17,90 @@
 //
 // -----------------------------------------------------------------------------
 
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::fmt::Write;
+use std::future::Future;
+use std::panic;
+use std::path::PathBuf;
+use std::pin::pin;
+use std::rc::Rc;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::time::{Duration, Instant};
+use std::task::{Poll, Waker};
+use std::time::Duration;
 
-use anathema_backend::{Backend, WidgetCycle};
+#[cfg(feature = "tokio")]
+use anathema_backend::AsyncEventSource;
+use anathema_backend::{Backend, LayoutPanic, WidgetCycle};
+use anathema_debug::profile::{end_frame, span};
 use anathema_default_widgets::register_default_widgets;
 use anathema_state::{
-    clear_all_changes, clear_all_futures, clear_all_subs, drain_changes, drain_futures, Changes, FutureValues, States,
+    clear_all_changes, clear_all_futures, clear_all_subs, drain_changes, drain_futures, Changes, FutureValues,
+    StateStore, States,
 };
-use anathema_store::tree::root_node;
+use anathema_store::tree::{root_node, AsNodePath};
 use anathema_templates::blueprints::Blueprint;
-use anathema_templates::{Document, Globals, ToSourceKind};
+use anathema_templates::{ComponentBlueprints, Document, Globals, ToSourceKind};
+use anathema_widgets::components::events::UserEventTarget;
 use anathema_widgets::components::{
-    AssociatedEvents, Component, ComponentId, ComponentKind, ComponentRegistry, Emitter, FocusQueue, UntypedContext,
-    ViewMessage,
+    AssociatedEvents, Component, ComponentId, ComponentKind, ComponentRegistry, Emitter, FocusQueue, FocusTarget,
+    Keymap, Message, MountId, MountRequest, Mounts, Services, Shutdown, TerminalRequest, Timers, UntypedContext,
 };
-use anathema_widgets::layout::{Constraints, Viewport};
+use anathema_widgets::layout::{Constraints, Diagnostic, LayoutDirection, Viewport};
+use anathema_widgets::time::Instant;
 use anathema_widgets::{
-    eval_blueprint, try_resolve_future_values, update_tree, AttributeStorage, Components, DirtyWidgets, EvalContext,
-    Factory, FloatingWidgets, Scope, WidgetKind, WidgetTree,
+    damage_removed, eval_blueprint, fallback_to_boundary, panic_message, try_resolve_future_values,
+    update_loop_windows, update_tree, AttributeStorage, Attributes, Components, DirtyWidgets, EvalContext, Factory,
+    FloatingWidgets, Scope, WidgetArena, WidgetId, WidgetKind, WidgetTree,
 };
 use events::{EventCtx, EventHandler};
+use messages::MessageQueue;
+#[cfg(not(target_arch = "wasm32"))]
 use notify::{recommended_watcher, Event, RecommendedWatcher, RecursiveMode, Watcher};
+use plugin::Plugins;
+use router::Router;
+use scheduler::{Scheduler, Wait};
 use tree::Tree;
 
 pub use self::events::{GlobalContext, GlobalEvents};
+pub use self::keymap::GlobalKeymap;
+pub use self::plugin::{BuildContext, PluginContext, RuntimePlugin};
+pub use self::stats::FrameStats;
 pub use crate::error::{Error, Result};
 
 static REBUILD: AtomicBool = AtomicBool::new(false);
 
+type ExitHook = Box<dyn FnOnce(&Shutdown)>;
+
+// How long an idle runtime waits for events without a timer.
+// The backends add this to the current time, so it can't be `Duration::MAX`.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+#[cfg(not(target_arch = "wasm32"))]
+type FileWatcher = RecommendedWatcher;
+#[cfg(target_arch = "wasm32")]
+type FileWatcher = ();
+
+// The `State` derive used by the tests refers to `::anathema::state`
+#[cfg(test)]
+extern crate anathema_state as anathema;
+
+pub mod accessibility;
+#[cfg(feature = "devtools")]
+mod devtools;
 mod error;
 mod events;
+#[cfg(feature = "inspector")]
+mod inspector;
+mod keymap;
+#[cfg(feature = "log")]
+pub mod logs;
+mod messages;
+mod plugin;
+mod router;
+mod scheduler;
+mod stats;
+pub mod testing;
 mod tree;
 
 pub struct RuntimeBuilder<T, G> {
@@ -56,9 +108,21 @@ pub struct RuntimeBuilder<T, G> {
     component_registry: ComponentRegistry,
     backend: T,
     factory: Factory,
-    message_receiver: flume::Receiver<ViewMessage>,
+    message_receiver: flume::Receiver<Message>,
     emitter: Emitter,
     global_events: G,
+    services: Services,
+    timers: Timers,
+    keymap: GlobalKeymap,
+    router: Option<Router>,
+    exit_hooks: Vec<ExitHook>,
+    plugins: Plugins,
+    direction: LayoutDirection,
+    diagnostics: Option<flume::Sender<Diagnostic>>,
+    watched: Vec<PathBuf>,
+    accessibility: Option<accessibility::Accessibility>,
+    #[cfg(feature = "inspector")]
+    inspector: Option<inspector::Inspector>,
 }
 
 impl<T, G: GlobalEvents> RuntimeBuilder<T, G> {
@@ -90,6 +154,18 @@ impl<T, G: GlobalEvents> RuntimeBuilder<T, G> {
             message_receiver: self.message_receiver,
             emitter: self.emitter,
             global_events,
+            services: self.services,
+            timers: self.timers,
+            keymap: self.keymap,
+            router: self.router,
+            exit_hooks: self.exit_hooks,
+            plugins: self.plugins,
+            direction: self.direction,
+            diagnostics: self.diagnostics,
+            watched: self.watched,
+            accessibility: self.accessibility,
+            #[cfg(feature = "inspector")]
+            inspector: self.inspector,
         }
     }
 
@@ -113,6 +189,34 @@ impl<T, G: GlobalEvents> RuntimeBuilder<T, G> {
         Ok(())
     }
 
+    /// Registers a [Component] as a prototype with the [Runtime],
+    /// where the state of each instance is created from the attributes of the instance.
+    ///
+    /// ```ignore
+    /// // Template: `@counter [initial: 10]`
+    /// runtime.register_prototype_with_attributes("counter", "counter.aml", || Counter, |attributes| {
+    ///     let initial = attributes.get_int("initial").unwrap_or(0);
+    ///     CounterState::new(initial)
+    /// });
+    /// ```
+    pub fn register_prototype_with_attributes<FC, FS, C>(
+        &mut self,
+        ident: impl Into<String>,
+        template: impl ToSourceKind,
+        proto: FC,
+        state: FS,
+    ) -> Result<()>
+    where
+        FC: 'static + Fn() -> C,
+        FS: 'static + FnMut(&Attributes<'_>) -> C::State,
+        C: Component + 'static,
+    {
+        let ident = ident.into();
+        let id = self.document.add_component(ident, template.to_source_kind())?.into();
+        self.component_registry.add_prototype_with_attributes(id, proto, state);
+        Ok(())
+    }
+
     /// Registers a [Component] with the runtime as long as the component and the associated state
     /// implements default.
     ///
@@ -146,18 +250,194 @@ impl<T, G: GlobalEvents> RuntimeBuilder<T, G> {
         self.emitter.clone()
     }
 
-    fn set_watcher(&mut self) -> Result<RecommendedWatcher> {
+    /// Add a shared service, such as a database handle or an api client.
+    /// Components can access the service by type through the context:
+    /// ```ignore
+    /// runtime.add_service(Database::connect());
+    ///
+    /// // Inside a component
+    /// let db = context.service::<Database>().unwrap();
+    /// ```
+    ///
+    /// Adding a service of the same type replaces the previous service.
+    pub fn add_service<S: 'static>(&mut self, service: S) {
+        self.services.insert(service);
+    }
+
+    /// Schedule messages to components and [`UserEvent`](anathema_widgets::components::events::UserEvent)s,
+    /// once or repeatedly.
+    ///
+    /// ```ignore
+    /// let counter = runtime.register_default::<Counter>("counter", "counter.aml")?;
+    /// runtime.timers().set_interval(counter, Duration::from_secs(1), CounterMsg::Increment);
+    /// runtime.timers().set_event_timeout(UserEventTarget::All, Duration::from_secs(5), Autosave);
+    /// ```
+    ///
+    /// Unlike timers created by components, these timers survive a reload of the templates.
+    pub fn timers(&mut self) -> &mut Timers {
+        &mut self.timers
+    }
+
+    /// App-wide shortcuts, bound to named actions or component messages.
+    ///
+    /// ```ignore
+    /// builder.keymap().bind("ctrl+s", "save")?;
+    /// builder.keymap().bind_message("ctrl+r", counter, CounterMsg::Reset)?;
+    /// ```
+    ///
+    /// Named actions are passed to [`GlobalEvents::action`].
+    /// Shortcuts are matched before the event reaches [`GlobalEvents::handle`],
+    /// so keys that are part of a shortcut are consumed.
+    pub fn keymap(&mut self) -> &mut GlobalKeymap {
+        &mut self.keymap
+    }
+
+    /// Show one screen at a time in the element with a matching `outlet` attribute,
+    /// starting with the `initial` screen.
+    /// Screens are components, registered like any other component.
+    ///
+    /// ```ignore
+    /// // Template: `vstack [outlet: "screens"]`
+    /// runtime.register_default::<Home>("home", "home.aml")?;
+    /// runtime.register_default::<Settings>("settings", "settings.aml")?;
+    /// runtime.router("screens", "home");
+    ///
+    /// // Inside a component
+    /// context.push_screen("settings");
+    /// context.pop_screen();
+    /// ```
+    ///
+    /// Screens that are not shown keep their state, unless they are prototypes.
+    pub fn router(&mut self, outlet: impl Into<Cow<'static, str>>, initial: impl Into<Cow<'static, str>>) {
+        self.router = Some(Router::new(outlet.into(), initial.into()));
+    }
+
+    /// Register a function that runs once when the runtime stops,
+    /// e.g to flush state to disk.
+    ///
+    /// The hooks run in the order they were registered, before the terminal is restored.
+    /// If the runtime is dropped without being stopped the hooks run with [`Shutdown::default`].
+    pub fn on_exit(&mut self, hook: impl FnOnce(&Shutdown) + 'static) {
+        self.exit_hooks.push(Box::new(hook));
+    }
+
+    /// Set the layout direction of the whole application.
+    /// Elements can still override it with the `dir` attribute.
+    /// ```ignore
+    /// builder.layout_direction(LayoutDirection::Rtl);
+    /// ```
+    pub fn layout_direction(&mut self, direction: LayoutDirection) {
+        self.direction = direction;
+    }
+
+    /// Receive the problems found during the layout,
+    /// such as a widget that is larger than its constraints.
+    ///
+    /// ```ignore
+    /// let diagnostics = builder.diagnostics();
+    /// std::thread::spawn(move || {
+    ///     for diagnostic in diagnostics {
+    ///         eprintln!("{diagnostic}");
+    ///     }
+    /// });
+    /// ```
+    ///
+    /// Without a receiver the diagnostics are discarded.
+    pub fn diagnostics(&mut self) -> flume::Receiver<Diagnostic> {
+        let (sender, receiver) = flume::unbounded();
+        self.diagnostics = Some(sender);
+        receiver
+    }
+
+    /// Rebuild the tree when the file changes, in addition to the templates.
+    /// This is useful for files that are read when creating the state of a component.
+    ///
+    /// This has no effect if hot reload is disabled on the [Document].
+    pub fn watch(&mut self, path: impl Into<PathBuf>) {
+        self.watched.push(path.into());
+    }
+
+    /// Announce the focused component, and the text of [`Context::announce`](anathema_widgets::components::Context::announce),
+    /// to an output for assistive technology such as screen readers.
+    /// See [`accessibility`] for how components and elements are described.
+    ///
+    /// ```ignore
+    /// builder.accessibility(accessibility::Stderr);
+    /// ```
+    pub fn accessibility(&mut self, output: impl accessibility::Announcer + 'static) {
+        self.accessibility = Some(accessibility::Accessibility::new(output));
+    }
+
+    /// Start a debug server on a TCP socket, for external inspectors.
+    /// Returns the address of the socket, e.g to find the port when binding to port zero.
+    ///
+    /// Every connected inspector receives a snapshot as a single line of JSON
+    /// for every painted frame, and when it connects:
+    ///
+    /// ```text
+    /// {"type": "frame", "stats": {..}, "tree": [{"id": 1, "depth": 0, "kind": "element", "ident": "text", "region": [0, 0, 5, 1]}, ..], "selected": null}
+    /// ```
+    ///
+    /// Components in the tree include their state.
+    ///
+    /// Inspectors send commands as single lines of JSON:
+    /// * `{"command": "snapshot"}`: send a snapshot
+    /// * `{"command": "select", "id": 1}`: include the attributes of the element in the snapshots
+    /// * `{"command": "set_attribute", "id": 1, "key": "bold", "value": true}`: set an attribute of an element
+    ///
+    /// Invalid commands are answered with `{"type": "error", "message": ".."}`.
+    ///
+    /// ```ignore
+    /// let addr = builder.inspector("127.0.0.1:0")?;
+    /// eprintln!("inspector listening on {addr}");
+    /// ```
+    #[cfg(feature = "inspector")]
+    pub fn inspector(&mut self, addr: impl std::net::ToSocketAddrs) -> Result<std::net::SocketAddr> {
+        let addr = self
+            .inspector
+            .get_or_insert_with(inspector::Inspector::new)
+            .listen_tcp(addr)?;
+        Ok(addr)
+    }
+
+    /// Start a debug server on a unix socket, see [`RuntimeBuilder::inspector`].
+    /// An existing file at the path is replaced.
+    #[cfg(all(feature = "inspector", unix))]
+    pub fn inspector_unix(&mut self, path: impl AsRef<std::path::Path>) -> Result<()> {
+        self.inspector
+            .get_or_insert_with(inspector::Inspector::new)
+            .listen_unix(path.as_ref())?;
+        Ok(())
+    }
+
+    /// Register a [`RuntimePlugin`]
+    pub fn plugin(&mut self, plugin: impl RuntimePlugin + 'static) {
+        self.plugins.push(plugin);
+    }
+
+    // The waker wakes an idle runtime once a template changed
+    #[cfg(not(target_arch = "wasm32"))]
+    fn set_watcher(&mut self, waker: Option<anathema_backend::Waker>) -> Result<FileWatcher> {
         let paths = self
             .document
             .template_paths()
+            .chain(&self.watched)
             .filter_map(|p| p.canonicalize().ok())
             .collect::<Vec<_>>();
+        let parents = paths
+            .iter()
+            .filter_map(|p| p.parent())
+            .map(PathBuf::from)
+            .collect::<Vec<_>>();
 
         let mut watcher = recommended_watcher(move |event: std::result::Result<Event, _>| match event {
             Ok(event) => match event.kind {
                 notify::EventKind::Create(_) | notify::EventKind::Remove(_) | notify::EventKind::Modify(_) => {
                     if paths.iter().any(|p| event.paths.contains(p)) {
                         REBUILD.store(true, Ordering::Relaxed);
+                        if let Some(waker) = &waker {
+                            waker();
+                        }
                     }
                 }
                 notify::EventKind::Any | notify::EventKind::Access(_) | notify::EventKind::Other => (),
@@ -165,38 +445,61 @@ impl<T, G: GlobalEvents> RuntimeBuilder<T, G> {
             Err(_err) => (),
         })?;
 
-        for path in self.document.template_paths() {
-            let path = path.canonicalize().unwrap();
-
-            if let Some(parent) = path.parent() {
-                watcher.watch(parent, RecursiveMode::NonRecursive)?;
-            }
+        for parent in parents {
+            watcher.watch(&parent, RecursiveMode::NonRecursive)?;
         }
 
         Ok(watcher)
     }
 
+    // There is no file system to watch in the browser
+    #[cfg(target_arch = "wasm32")]
+    fn set_watcher(&mut self, _: Option<anathema_backend::Waker>) -> Result<FileWatcher> {
+        Ok(())
+    }
+
     /// Builds the [Runtime].
     /// Fails if compiling the [Document] or creating the file watcher fails.
+    ///
+    /// The runtime takes the state values of the current thread (e.g the states of the
+    /// registered components) into its own [`StateStore`], isolating it from other runtimes.
+    /// Values created after this are not part of the runtime.
     pub fn finish(mut self) -> Result<Runtime<T, G>>
     where
         T: Backend,
     {
         let (blueprint, globals) = self.document.compile()?;
+        let waker = self.backend.waker();
         let watcher = match self.document.hot_reload {
             false => None,
-            true => Some(self.set_watcher()?),
+            true => Some(self.set_watcher(waker.clone())?),
         };
 
         let (width, height) = self.backend.size().into();
         let constraints = Constraints::new(width as usize, height as usize);
+        let mut viewport = Viewport::new((width, height));
+        viewport.set_direction(self.direction);
+
+        self.plugins.build(&mut BuildContext {
+            emitter: &self.emitter,
+            keymap: &mut self.keymap,
+            timers: &mut self.timers,
+            services: &mut self.services,
+        });
 
         let inst = Runtime {
             _watcher: watcher,
+            wakeable: waker.is_some(),
             backend: self.backend,
             emitter: self.emitter,
             message_receiver: self.message_receiver,
+            messages: MessageQueue::default(),
             fps: 30,
+            layout_budget: None,
+            tree_capacity: 0,
+            arena_limit: WidgetArena::DEFAULT_LIMIT,
+            arena: WidgetArena::new(),
+            layout_pending: false,
             constraints,
             blueprint,
             factory: self.factory,
@@ -206,11 +509,25 @@ impl<T, G: GlobalEvents> RuntimeBuilder<T, G> {
             component_registry: self.component_registry,
             globals,
             document: self.document,
-            viewport: Viewport::new((width, height)),
+            viewport,
             floating_widgets: FloatingWidgets::empty(),
             components: Components::new(),
             dirty_widgets: DirtyWidgets::empty(),
-            event_handler: EventHandler::new(self.global_events),
+            timers: Timers::new(),
+            runtime_timers: self.timers,
+            mounts: Mounts::new(),
+            keymap: Keymap::new(),
+            mounted: HashMap::new(),
+            event_handler: EventHandler::new(self.global_events, self.keymap, self.plugins),
+            services: self.services,
+            router: self.router,
+            shutdown: None,
+            exit_hooks: self.exit_hooks,
+            diagnostics: self.diagnostics,
+            accessibility: self.accessibility,
+            #[cfg(feature = "inspector")]
+            inspector: self.inspector,
+            store: Rc::new(StateStore::detach()),
         };
 
         Ok(inst)
@@ -222,16 +539,35 @@ impl<T, G: GlobalEvents> RuntimeBuilder<T, G> {
 /// ```
 /// # use anathema_runtime::Runtime;
 /// # use anathema_templates::Document;
-/// # use anathema_backend::test::TestBackend;
+/// # use anathema_backend::testing::TestBackend;
 /// # let backend = TestBackend::new((10, 10));
 /// let document = Document::new("border");
 /// let mut runtime = Runtime::builder(document, backend).finish().unwrap();
 /// ```
 pub struct Runtime<T, G> {
     pub fps: u16,
-
-    _watcher: Option<RecommendedWatcher>,
-    message_receiver: flume::Receiver<ViewMessage>,
+    /// The time layout is allowed to take each frame.
+    /// If the layout takes longer the frame is not painted,
+    /// and the layout resumes where it left off on the next frame.
+    pub layout_budget: Option<Duration>,
+    /// The number of widgets the tree has room for when it's built.
+    /// When the tree is rebuilt (e.g by a hot reload) this grows to the capacity of the previous tree,
+    /// so the new tree doesn't have to grow its storage one widget at a time.
+    pub tree_capacity: usize,
+    /// The number of bytes of widgets allocated in the arena before widgets are boxed instead.
+    /// Widgets removed from the tree keep their memory in the arena until the tree is rebuilt,
+    /// so this bounds the memory of a tree that keeps replacing its widgets.
+    pub arena_limit: usize,
+    // The widgets of the tree are allocated in the arena,
+    // which is reset rather than freed when the tree is rebuilt
+    arena: WidgetArena,
+
+    _watcher: Option<FileWatcher>,
+    // The backend is woken by messages and template changes, so it can wait until the next timer
+    wakeable: bool,
+    message_receiver: flume::Receiver<Message>,
+    // Received messages, waiting to be handled
+    messages: MessageQueue,
     emitter: Emitter,
     blueprint: Blueprint,
     factory: Factory,
@@ -255,6 +591,27 @@ pub struct Runtime<T, G> {
     // * Event handling
     components: Components,
     dirty_widgets: DirtyWidgets,
+    // The previous layout ran out of time
+    layout_pending: bool,
+    timers: Timers,
+    // Timers scheduled by the application, these are not reset
+    runtime_timers: Timers,
+    mounts: Mounts,
+    keymap: Keymap,
+    mounted: HashMap<MountId, WidgetId>,
+    router: Option<Router>,
+    services: Services,
+    // Set when a shutdown message is received
+    shutdown: Option<Shutdown>,
+    exit_hooks: Vec<ExitHook>,
+    // Receives the layout diagnostics, if anything is listening
+    diagnostics: Option<flume::Sender<Diagnostic>>,
+    // Announces the focused component, if enabled
+    accessibility: Option<accessibility::Accessibility>,
+    #[cfg(feature = "inspector")]
+    inspector: Option<inspector::Inspector>,
+    // The values of this runtime, entered for the duration of a frame
+    store: Rc<StateStore>,
     // tab_indices: TabIndices,
 
     // -----------------------------------------------------------------------------
@@ -282,14 +639,33 @@ where
         let (message_sender, message_receiver) = flume::unbounded();
         register_default_widgets(&mut factory);
 
+        // Messages wake the backend, so an idle runtime can wait for events
+        let emitter = Emitter::from(message_sender);
+        let emitter = match backend.waker() {
+            Some(waker) => emitter.with_waker(waker),
+            None => emitter,
+        };
+
         RuntimeBuilder {
             backend,
             document,
             component_registry: ComponentRegistry::new(),
             factory,
-            emitter: message_sender.into(),
+            emitter,
             message_receiver,
             global_events: (),
+            services: Services::new(),
+            timers: Timers::new(),
+            keymap: GlobalKeymap::new(),
+            router: None,
+            exit_hooks: vec![],
+            plugins: Plugins::default(),
+            direction: LayoutDirection::Ltr,
+            diagnostics: None,
+            watched: vec![],
+            accessibility: None,
+            #[cfg(feature = "inspector")]
+            inspector: None,
         }
     }
 }
@@ -303,9 +679,44 @@ where
         self.emitter.clone()
     }
 
+    /// Schedule messages to components and user events,
+    /// see [`RuntimeBuilder::timers`].
+    pub fn timers(&mut self) -> &mut Timers {
+        &mut self.runtime_timers
+    }
+
+    /// App-wide shortcuts, see [`RuntimeBuilder::keymap`]
+    pub fn keymap(&mut self) -> &mut GlobalKeymap {
+        &mut self.event_handler.keymap
+    }
+
+    // Send the layout diagnostics to the receiver
+    fn report(&self, diagnostics: Vec<Diagnostic>) {
+        let Some(sender) = self.diagnostics.as_ref() else { return };
+        for diagnostic in diagnostics {
+            // The receiver is allowed to stop listening
+            let _ = sender.send(diagnostic);
+        }
+    }
+
+    // The time at which the next timer expires, if any
+    fn next_deadline(&self) -> Option<Instant> {
+        let timeout = self.event_handler.keymap.sequence_timeout();
+        [
+            self.timers.next_deadline(),
+            self.runtime_timers.next_deadline(),
+            // Keys held back as the start of a shortcut are released on timeout
+            self.event_handler.sequence.deadline(timeout),
+        ]
+        .into_iter()
+        .flatten()
+        .min()
+    }
+
     fn apply_futures<'bp>(
         &mut self,
         globals: &'bp Globals,
+        arena: &'bp WidgetArena,
         tree: &mut WidgetTree<'bp>,
         states: &mut States,
         attribute_storage: &mut AttributeStorage<'bp>,
@@ -324,6 +735,7 @@ where
             try_resolve_future_values(
                 globals,
                 &self.factory,
+                arena,
                 &mut scope,
                 states,
                 &mut self.component_registry,
@@ -340,6 +752,7 @@ where
     fn apply_changes<'bp>(
         &mut self,
         globals: &'bp Globals,
+        arena: &'bp WidgetArena,
         tree: &mut WidgetTree<'bp>,
         states: &mut States,
         attribute_storage: &mut AttributeStorage<'bp>,
@@ -359,6 +772,7 @@ where
                 update_tree(
                     globals,
                     &self.factory,
+                    arena,
                     &mut scope,
                     states,
                     &mut self.component_registry,
@@ -374,11 +788,237 @@ where
         });
     }
 
+    // Loops that only generate the iterations the widget above them shows (e.g `overflow [virtual: true]`)
+    // generate the iterations that became visible during the layout.
+    // The widgets above the loops are laid out again on the next frame.
+    fn apply_loop_windows<'bp>(
+        &mut self,
+        globals: &'bp Globals,
+        arena: &'bp WidgetArena,
+        tree: &mut WidgetTree<'bp>,
+        states: &mut States,
+        attribute_storage: &mut AttributeStorage<'bp>,
+    ) {
+        let mut scope = Scope::new();
+        let changed = update_loop_windows(
+            globals,
+            &self.factory,
+            arena,
+            &mut scope,
+            states,
+            &mut self.component_registry,
+            tree,
+            attribute_storage,
+            &mut self.floating_widgets,
+            &mut self.components,
+        );
+        changed
+            .into_iter()
+            .for_each(|widget_id| self.dirty_widgets.push(widget_id));
+    }
+
+    fn apply_mounts<'bp>(
+        &mut self,
+        globals: &'bp Globals,
+        arena: &'bp WidgetArena,
+        component_blueprints: &'bp ComponentBlueprints,
+        tree: &mut WidgetTree<'bp>,
+        states: &mut States,
+        attribute_storage: &mut AttributeStorage<'bp>,
+        focus_queue: &mut FocusQueue<'static>,
+    ) -> Result<()> {
+        if self.mounts.is_empty() {
+            return Ok(());
+        }
+
+        let requests = self.mounts.drain().collect::<Vec<_>>();
+        for request in requests {
+            match request {
+                MountRequest::Mount { id, component, outlet } => {
+                    let mounted = self.mount(
+                        globals,
+                        arena,
+                        component_blueprints,
+                        &component,
+                        &outlet,
+                        tree,
+                        states,
+                        attribute_storage,
+                    )?;
+                    if let Some(widget_id) = mounted {
+                        self.mounted.insert(id, widget_id);
+                    }
+                }
+                MountRequest::Unmount(id) => {
+                    let Some(widget_id) = self.mounted.remove(&id) else { continue };
+                    self.unmount(widget_id, tree);
+                }
+                MountRequest::Screen(request) => {
+                    let Some(router) = self.router.as_mut() else { continue };
+                    if !router.apply(request) {
+                        continue;
+                    }
+
+                    let name = router.current().to_string();
+                    self.show_screen(globals, arena, component_blueprints, tree, states, attribute_storage)?;
+                    focus_queue.push_request(FocusTarget::NextIn(name.into()));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Mount a component as the last child of the element with a matching `outlet` attribute.
+    // The component is compiled the first time it's mounted.
+    // Returns the widget id of the component.
+    //
+    // If the component fails to compile or evaluate, the error is caught by the closest
+    // error boundary above the outlet. Without an error boundary the error is returned.
+    fn mount<'bp>(
+        &mut self,
+        globals: &'bp Globals,
+        arena: &'bp WidgetArena,
+        component_blueprints: &'bp ComponentBlueprints,
+        component: &str,
+        outlet: &str,
+        tree: &mut WidgetTree<'bp>,
+        states: &mut States,
+        attribute_storage: &mut AttributeStorage<'bp>,
+    ) -> Result<Option<WidgetId>> {
+        let Some(outlet) = attribute_storage.outlet(outlet) else { return Ok(None) };
+        let Some(path) = tree.try_path(outlet) else { return Ok(None) };
+        let child_count = |tree: &mut WidgetTree<'bp>| {
+            tree.get_node_by_path(&path)
+                .map(|(node, _)| node.children().len())
+                .unwrap_or(0)
+        };
+        let index = child_count(tree);
+        let mut child_path = path.to_vec();
+        child_path.push(index as u16);
+
+        let compiled = self
+            .document
+            .compile_component(component, component_blueprints, globals);
+        let res = match compiled {
+            Ok(Some(blueprint)) => {
+                let mut scope = Scope::new();
+                let mut ctx = EvalContext::new(
+                    globals,
+                    &self.factory,
+                    arena,
+                    &mut scope,
+                    states,
+                    &mut self.component_registry,
+                    attribute_storage,
+                    &mut self.floating_widgets,
+                    &mut self.components,
+                );
+                eval_blueprint(blueprint, &mut ctx, &path, tree).map_err(Error::from)
+            }
+            Ok(None) => return Ok(None),
+            Err(err) => Err(err.into()),
+        };
+
+        self.dirty_widgets.push(outlet);
+
+        if let Err(err) = res {
+            // Remove whatever was evaluated before the error occurred
+            if tree.get_ref_by_path(&child_path).is_some() {
+                damage_removed(&child_path, tree);
+                tree.remove(&child_path);
+            }
+
+            let mut scope = Scope::new();
+            let caught = fallback_to_boundary(
+                globals,
+                &self.factory,
+                arena,
+                &mut scope,
+                states,
+                &mut self.component_registry,
+                err.to_string(),
+                &child_path,
+                tree,
+                attribute_storage,
+                &mut self.floating_widgets,
+                &mut self.components,
+            );
+
+            return match caught {
+                true => Ok(None),
+                false => Err(err),
+            };
+        }
+
+        if child_count(tree) <= index {
+            return Ok(None);
+        }
+
+        Ok(tree.id(&child_path))
+    }
+
+    // Remove a widget (and its children) from the tree
+    fn unmount<'bp>(&mut self, widget_id: WidgetId, tree: &mut WidgetTree<'bp>) -> Option<WidgetKind<'bp>> {
+        let path = tree.try_path(widget_id)?;
+        if let Some(parent) = path.parent().and_then(|parent| tree.id(parent)) {
+            self.dirty_widgets.push(parent);
+        }
+        damage_removed(&path, tree);
+        tree.remove(&path)
+    }
+
+    // Replace the active screen with the screen on top of the router's stack.
+    // The component of the previous screen is returned to the registry,
+    // so its state is retained until the screen is shown again.
+    fn show_screen<'bp>(
+        &mut self,
+        globals: &'bp Globals,
+        arena: &'bp WidgetArena,
+        component_blueprints: &'bp ComponentBlueprints,
+        tree: &mut WidgetTree<'bp>,
+        states: &mut States,
+        attribute_storage: &mut AttributeStorage<'bp>,
+    ) -> Result<()> {
+        let Some(router) = self.router.as_mut() else { return Ok(()) };
+
+        if let Some(widget_id) = router.active.take() {
+            if let Some(WidgetKind::Component(comp)) = self.unmount(widget_id, tree) {
+                if let ComponentKind::Instance = comp.kind {
+                    let state = states.remove(comp.state_id);
+                    self.component_registry
+                        .return_component(comp.component_id, comp.dyn_component, state);
+                }
+            }
+        }
+
+        let Some(router) = self.router.as_ref() else { return Ok(()) };
+        let screen = router.current().to_string();
+        let outlet = router.outlet().to_string();
+
+        let active = self.mount(
+            globals,
+            arena,
+            component_blueprints,
+            &screen,
+            &outlet,
+            tree,
+            states,
+            attribute_storage,
+        )?;
+        if let Some(router) = self.router.as_mut() {
+            router.active = active;
+        }
+
+        Ok(())
+    }
+
     // Handles component messages for (ideally) at most half of a tick
     fn handle_messages<'bp>(
         &mut self,
         fps_now: Instant,
         sleep_micros: u128,
+        component_blueprints: &ComponentBlueprints,
         tree: &mut WidgetTree<'bp>,
         states: &mut States,
         attribute_storage: &mut AttributeStorage<'bp>,
@@ -389,11 +1029,15 @@ where
             emitter: &self.emitter,
             viewport: self.viewport,
             strings: &mut self.document.strings,
+            services: &self.services,
         };
 
         let mut event_ctx = EventCtx {
             components: &mut self.components,
             dirty_widgets: &mut self.dirty_widgets,
+            timers: &mut self.timers,
+            mounts: &mut self.mounts,
+            keymap: &mut self.keymap,
             states,
             attribute_storage,
             assoc_events,
@@ -401,15 +1045,60 @@ where
             context,
         };
 
-        while let Ok(msg) = self.message_receiver.try_recv() {
-            if let Some((widget_id, state_id)) = event_ctx
-                .components
-                .get_by_component_id(msg.recipient())
-                .map(|e| (e.widget_id, e.state_id))
-            {
-                tree.with_component(widget_id, state_id, &mut event_ctx, |a, b| {
-                    a.any_message(msg.payload(), b)
-                });
+        loop {
+            // Messages sent while handling a message are queued as well,
+            // so a high priority message is handled before a backlog of other messages
+            self.message_receiver.try_iter().for_each(|msg| self.messages.push(msg));
+            let Some(msg) = self.messages.pop() else { break };
+
+            match msg {
+                Message::View(msg) => {
+                    if let Some((widget_id, state_id)) = event_ctx
+                        .components
+                        .get_by_component_id(msg.recipient())
+                        .map(|e| (e.widget_id, e.state_id))
+                    {
+                        tree.with_component(widget_id, state_id, &mut event_ctx, |a, b| {
+                            a.any_message(msg.payload(), b)
+                        });
+                    }
+                }
+                Message::Broadcast(msg) => {
+                    let Some(component_id) = component_blueprints.id(msg.component()) else { continue };
+                    let instances = event_ctx.components.instances(component_id).collect::<Vec<_>>();
+                    for (widget_id, state_id) in instances {
+                        tree.with_component(widget_id, state_id, &mut event_ctx, |a, b| {
+                            a.any_message(msg.payload(), b)
+                        });
+                    }
+                }
+                Message::Shutdown(shutdown) => {
+                    self.shutdown = Some(shutdown);
+                    break;
+                }
+                Message::Terminal(TerminalRequest::SetTitle(title)) => self.backend.set_title(&title),
+                Message::Terminal(TerminalRequest::Bell) => self.backend.bell(),
+                Message::Terminal(TerminalRequest::Copy(text)) => self.backend.copy(&text),
+                Message::Announce(text) => {
+                    if let Some(accessibility) = &mut self.accessibility {
+                        accessibility.message(text);
+                    }
+                }
+                Message::User(event) => {
+                    let recipients = match event.target() {
+                        UserEventTarget::Focused => event_ctx
+                            .components
+                            .get(event_ctx.components.tab_index)
+                            .into_iter()
+                            .collect::<Vec<_>>(),
+                        UserEventTarget::All => (0..event_ctx.components.len())
+                            .filter_map(|i| event_ctx.components.get(i))
+                            .collect(),
+                    };
+                    for (widget_id, state_id) in recipients {
+                        tree.with_component(widget_id, state_id, &mut event_ctx, |a, b| a.any_user_event(b, &event));
+                    }
+                }
             }
 
             // Make sure event handling isn't holding up the rest of the event loop.
@@ -421,13 +1110,22 @@ where
         fps_now.elapsed()
     }
 
-    /// Start the runtime
-    pub fn run(&mut self) {
+    /// Start the runtime.
+    /// Returns the [`Shutdown`] that stopped the runtime, once the exit hooks have run.
+    ///
+    /// ```ignore
+    /// let shutdown = runtime.run();
+    /// std::process::exit(shutdown.code());
+    /// ```
+    pub fn run(&mut self) -> Shutdown {
         self.backend.finalize();
         loop {
             match self.internal_run() {
                 Ok(()) => (),
-                Err(Error::Stop) => return,
+                Err(Error::Stop(shutdown)) => {
+                    self.exit(&shutdown);
+                    return shutdown;
+                }
                 Err(err) => self.show_error(err),
             }
         }
@@ -440,20 +1138,102 @@ where
     // 5 - Recursively calls [Self::internal_run].
     // TODO: We should move this into a loop in [Self::run].
     fn internal_run(&mut self) -> Result<()> {
-        let mut fps_now = Instant::now();
-        let sleep_micros = ((1.0 / self.fps as f64) * 1000.0 * 1000.0) as u128;
-        let mut tree = WidgetTree::empty();
+        let mut scheduler = Scheduler::new(self.fps);
+        let sleep_micros = scheduler.budget().as_micros();
+
+        self.with_frame(sleep_micros, |frame| loop {
+            let fps_now = Instant::now();
+            let stats = frame.tick_at(fps_now, true)?;
+
+            if REBUILD.swap(false, Ordering::Relaxed) {
+                return Ok(());
+            }
+
+            match scheduler.next(stats.active, fps_now, frame.runtime.next_deadline()) {
+                Wait::None => (),
+                Wait::Sleep(sleep) => std::thread::sleep(sleep),
+                Wait::Idle(deadline) => frame.idle(deadline, scheduler.budget()),
+            }
+        })
+    }
+
+    /// Drive the runtime frame by frame, without setting up the terminal.
+    ///
+    /// The tree is built and painted before the closure is called,
+    /// and every call to [`Frame::tick`] handles the queued events and messages
+    /// and paints the tree if anything changed.
+    /// Combined with the [`TestBackend`](anathema_backend::testing::TestBackend) this renders
+    /// to memory, e.g for tests, screenshots or rendering on a server.
+    /// Use [`Frame::poll`] to drive the runtime from another event loop.
+    ///
+    /// ```
+    /// # use anathema_runtime::Runtime;
+    /// # use anathema_templates::Document;
+    /// # use anathema_backend::testing::TestBackend;
+    /// let mut document = Document::new("text 'headless'");
+    /// document.hot_reload = false;
+    ///
+    /// let mut runtime = Runtime::builder(document, TestBackend::new((8, 1)))
+    ///     .finish()
+    ///     .unwrap();
+    /// runtime
+    ///     .headless(|frame| {
+    ///         assert_eq!(frame.backend().output, "headless\n");
+    ///         frame.tick()?;
+    ///         Ok(())
+    ///     })
+    ///     .unwrap();
+    /// ```
+    pub fn headless<F>(&mut self, f: F) -> Result<()>
+    where
+        F: FnOnce(&mut Frame<'_, '_, T, G>) -> Result<()>,
+    {
+        let sleep_micros = Scheduler::new(self.fps).budget().as_micros();
+        self.with_frame(sleep_micros, f)
+    }
+
+    // Build the tree and pass a [`Frame`] to the closure, to drive the runtime.
+    // The runtime is reset once the closure returns, unless it returns an error.
+    fn with_frame<F>(&mut self, sleep_micros: u128, f: F) -> Result<()>
+    where
+        F: FnOnce(&mut Frame<'_, '_, T, G>) -> Result<()>,
+    {
+        // The future never awaits anything, so it's done after the first poll
+        let future = pin!(self.with_frame_async(sleep_micros, async |frame| f(frame)));
+        match future.poll(&mut std::task::Context::from_waker(Waker::noop())) {
+            Poll::Ready(res) => res,
+            Poll::Pending => unreachable!("the frame closure is not async"),
+        }
+    }
+
+    // Same as `with_frame` but the closure is async
+    async fn with_frame_async<F>(&mut self, sleep_micros: u128, f: F) -> Result<()>
+    where
+        F: AsyncFnOnce(&mut Frame<'_, '_, T, G>) -> Result<()>,
+    {
+        // Declared first, so the store is left once everything else is dropped
+        let store = self.store.clone();
+        let _entered = store.enter();
+
+        // Declared before the tree, so the widgets are dropped first
+        let globals = self.globals.take();
+        let blueprint = self.blueprint.clone();
+        let component_blueprints = self.document.component_blueprints();
+        let mut arena = std::mem::take(&mut self.arena);
+        arena.set_limit(self.arena_limit);
+
+        let mut tree = WidgetTree::with_capacity(self.tree_capacity);
         let mut attribute_storage = AttributeStorage::empty();
         let mut assoc_events = AssociatedEvents::new();
         let mut focus_queue = FocusQueue::new();
 
         let mut states = States::new();
         let mut scope = Scope::new();
-        let globals = self.globals.take();
 
         let mut ctx = EvalContext::new(
             &globals,
             &self.factory,
+            &arena,
             &mut scope,
             &mut states,
             &mut self.component_registry,
@@ -462,26 +1242,49 @@ where
             &mut self.components,
         );
 
-        let blueprint = self.blueprint.clone();
-
         // First build the tree
         let res = eval_blueprint(&blueprint, &mut ctx, root_node(), &mut tree);
 
         match res {
             Ok(_) => (),
             Err(err) => {
-                match self.reset(tree, &mut states) {
-                    Ok(()) => (),
-                    Err(err) => return Err(err),
-                }
+                let res = self.reset(tree, &mut states);
+                self.restore_arena(arena);
+                res?;
                 return Err(err.into());
             }
         }
 
-        let mut dt = Instant::now();
+        // Show the screen on top of the router's stack
+        if let Some(router) = self.router.as_mut() {
+            router.active = None;
+            let res = self.show_screen(
+                &globals,
+                &arena,
+                &component_blueprints,
+                &mut tree,
+                &mut states,
+                &mut attribute_storage,
+            );
+
+            if let Err(err) = res {
+                let res = self.reset(tree, &mut states);
+                self.restore_arena(arena);
+                res?;
+                return Err(err);
+            }
+        }
+
+        self.notify_error_boundaries(
+            &mut tree,
+            &mut states,
+            &mut attribute_storage,
+            &mut assoc_events,
+            &mut focus_queue,
+        );
 
         // Initial layout, position and paint
-        WidgetCycle::new(
+        let mut cycle = WidgetCycle::new(
             &mut self.backend,
             &mut tree,
             self.constraints,
@@ -489,20 +1292,54 @@ where
             &self.floating_widgets,
             self.viewport,
         )
-        .run();
-        self.backend.render();
+        .debug_overlay(self.event_handler.debug_overlay)
+        .catch_layout_panics(true);
+        cycle.run();
+        let interrupted = cycle.interrupted();
+        let layout_panic = cycle.take_layout_panic();
+        let diagnostics = cycle.take_diagnostics();
+        self.report(diagnostics);
+        if !interrupted {
+            self.backend.render();
+        }
         self.backend.clear();
+        self.layout_pending = interrupted;
+        if !interrupted {
+            self.apply_loop_windows(&globals, &arena, &mut tree, &mut states, &mut attribute_storage);
+        }
+
+        if let Some(layout_panic) = layout_panic {
+            self.catch_layout_panic(
+                layout_panic,
+                &globals,
+                &arena,
+                &mut tree,
+                &mut states,
+                &mut attribute_storage,
+            );
+            self.notify_error_boundaries(
+                &mut tree,
+                &mut states,
+                &mut attribute_storage,
+                &mut assoc_events,
+                &mut focus_queue,
+            );
+        }
 
         // Try to set focus on the first available component
         let context = UntypedContext {
             emitter: &self.emitter,
             viewport: self.viewport,
             strings: &self.document.strings,
+            services: &self.services,
         };
 
         let mut event_ctx = EventCtx {
             components: &mut self.components,
             dirty_widgets: &mut self.dirty_widgets,
+            timers: &mut self.timers,
+            mounts: &mut self.mounts,
+            keymap: &mut self.keymap,
             states: &mut states,
             attribute_storage: &mut attribute_storage,
             assoc_events: &mut assoc_events,
@@ -512,27 +1349,37 @@ where
 
         self.event_handler.set_initial_focus(&mut tree, &mut event_ctx);
 
-        loop {
-            self.tick(
-                fps_now,
-                &mut dt,
-                sleep_micros,
-                &mut tree,
-                &mut states,
-                &mut attribute_storage,
-                &globals,
-                &mut assoc_events,
-                &mut focus_queue,
-            )?;
+        let mut frame = Frame {
+            runtime: self,
+            tree: &mut tree,
+            states: &mut states,
+            attribute_storage: &mut attribute_storage,
+            assoc_events: &mut assoc_events,
+            focus_queue: &mut focus_queue,
+            globals: &globals,
+            arena: &arena,
+            component_blueprints: &component_blueprints,
+            dt: Instant::now(),
+            sleep_micros,
+        };
 
-            if REBUILD.swap(false, Ordering::Relaxed) {
-                break;
+        let res = match f(&mut frame).await {
+            Ok(()) => self.reset(tree, &mut states),
+            Err(err) => {
+                drop(tree);
+                Err(err)
             }
+        };
+        self.restore_arena(arena);
 
-            fps_now = Instant::now();
-        }
+        res
+    }
 
-        self.reset(tree, &mut states)
+    // The widgets were dropped with the tree,
+    // and the memory of the arena is kept for the next tree
+    fn restore_arena(&mut self, mut arena: WidgetArena) {
+        arena.reset();
+        self.arena = arena;
     }
 
     pub fn show_error(&mut self, err: Error) {
@@ -566,12 +1413,18 @@ where
         clear_all_subs();
 
         self.components = Components::new();
+        self.timers = Timers::new();
+        self.mounts = Mounts::new();
+        self.keymap = Keymap::new();
+        self.mounted.clear();
         self.floating_widgets = FloatingWidgets::empty();
 
         // The only way we can get here is if we break the loop
         // as a result of the hot_reload triggering or when building the first tree fails.
         self.document.reload_templates()?;
 
+        self.tree_capacity = self.tree_capacity.max(tree.capacity());
+
         // Move all components from the tree back to the registry.
         for (_, widget) in tree.values().into_iter() {
             let WidgetKind::Component(comp) = widget else { continue };
@@ -588,44 +1441,79 @@ where
         Ok(())
     }
 
+    // The stats are active if the frame did any work:
+    // handled messages or events, or painted the tree.
     fn tick<'bp>(
         &mut self,
         fps_now: Instant,
+        block: bool,
         dt: &mut Instant,
         sleep_micros: u128,
         tree: &mut WidgetTree<'bp>,
         states: &mut States,
         attribute_storage: &mut AttributeStorage<'bp>,
         globals: &'bp Globals,
+        arena: &'bp WidgetArena,
+        component_blueprints: &'bp ComponentBlueprints,
         assoc_events: &mut AssociatedEvents,
         focus_queue: &mut FocusQueue<'static>,
-    ) -> Result<()> {
+    ) -> Result<FrameStats> {
+        let frame_span = span("frame");
+        let plugin_ctx = PluginContext {
+            emitter: &self.emitter,
+            viewport: self.viewport,
+        };
+        self.event_handler.plugins.before_frame(&plugin_ctx);
+
         // Clear the text buffer
         // self.string_storage.clear();
 
+        // Expired timers are delivered as messages
+        let now = Instant::now();
+        self.timers.tick(now, &self.emitter);
+        self.runtime_timers.tick(now, &self.emitter);
+        let has_messages = !self.messages.is_empty() || !self.message_receiver.is_empty();
+
         // Pull and keep consuming events while there are events present in the queue.
+        let messages_span = span("messages");
         let poll_duration = self.handle_messages(
             fps_now,
             sleep_micros,
+            component_blueprints,
             tree,
             states,
             attribute_storage,
             assoc_events,
             focus_queue,
         );
+        drop(messages_span);
+        let poll_duration = match block {
+            true => poll_duration,
+            false => Duration::ZERO,
+        };
+
+        if let Some(shutdown) = self.shutdown.take() {
+            return Err(Error::Stop(shutdown));
+        }
 
         // Call the `tick` function on all components
+        let tick_span = span("tick components");
         self.tick_components(tree, states, attribute_storage, dt.elapsed(), assoc_events, focus_queue);
+        drop(tick_span);
 
         let context = UntypedContext {
             emitter: &self.emitter,
             viewport: self.viewport,
             strings: &self.document.strings,
+            services: &self.services,
         };
 
         let mut event_ctx = EventCtx {
             components: &mut self.components,
             dirty_widgets: &mut self.dirty_widgets,
+            timers: &mut self.timers,
+            mounts: &mut self.mounts,
+            keymap: &mut self.keymap,
             states,
             attribute_storage,
             assoc_events,
@@ -633,7 +1521,8 @@ where
             focus_queue,
         };
 
-        self.event_handler.handle(
+        let events_span = span("events");
+        let has_events = self.event_handler.handle(
             poll_duration,
             fps_now,
             sleep_micros,
@@ -643,12 +1532,34 @@ where
             &mut self.constraints,
             &mut event_ctx,
         )?;
+        drop(events_span);
 
         *dt = Instant::now();
 
-        self.apply_futures(globals, tree, states, attribute_storage);
+        #[cfg(feature = "inspector")]
+        if let Some(inspector) = &mut self.inspector {
+            inspector.apply_commands(tree, attribute_storage, &mut self.dirty_widgets);
+        }
+
+        let changes_span = span("changes");
+        self.apply_futures(globals, arena, tree, states, attribute_storage);
 
-        self.apply_changes(globals, tree, states, attribute_storage);
+        self.apply_changes(globals, arena, tree, states, attribute_storage);
+        drop(changes_span);
+
+        let mounts_span = span("mounts");
+        self.apply_mounts(
+            globals,
+            arena,
+            component_blueprints,
+            tree,
+            states,
+            attribute_storage,
+            focus_queue,
+        )?;
+
+        self.notify_error_boundaries(tree, states, attribute_storage, assoc_events, focus_queue);
+        drop(mounts_span);
 
         // -----------------------------------------------------------------------------
         //   - Update dirty widgets -
@@ -661,14 +1572,32 @@ where
             attribute_storage.try_remove(key);
             self.floating_widgets.try_remove(key);
             // TODO: this function is rubbish and has to be rewritten
-            self.components.dodgy_remove(key);
+            if let Some(component_id) = self.components.dodgy_remove(key) {
+                // Prototypes share the component id, so only remove the timers
+                // once the last instance is gone
+                if self.components.get_by_component_id(component_id).is_none() {
+                    self.timers.cancel_all(component_id);
+                    self.keymap.unregister_all(component_id);
+                    focus_queue.remove_scope(component_id);
+                }
+            }
         }
 
         // -----------------------------------------------------------------------------
         //   - Layout, position and paint -
         // -----------------------------------------------------------------------------
-        let needs_reflow = !self.changes.is_empty() || !self.dirty_widgets.is_empty();
+        let mut stats = FrameStats {
+            widgets: tree.len(),
+            capacity: tree.capacity(),
+            arena_allocated: arena.allocated(),
+            arena_capacity: arena.capacity(),
+            changes: self.changes.len(),
+            ..FrameStats::default()
+        };
+
+        let needs_reflow = self.layout_pending || !self.changes.is_empty() || !self.dirty_widgets.is_empty();
         if needs_reflow {
+            let deadline = self.layout_budget.map(|budget| Instant::now() + budget);
             let mut cycle = WidgetCycle::new(
                 &mut self.backend,
                 tree,
@@ -676,21 +1605,75 @@ where
                 attribute_storage,
                 &self.floating_widgets,
                 self.viewport,
-            );
-            cycle.run();
+            )
+            .layout_deadline(deadline)
+            .debug_overlay(self.event_handler.debug_overlay)
+            .catch_layout_panics(true)
+            .invalidated(self.changes.iter().flat_map(|(subs, _)| subs.iter().map(Into::into)));
+            let timings = cycle.run();
+            self.layout_pending = cycle.interrupted();
+            let layout_panic = cycle.take_layout_panic();
+            let diagnostics = cycle.take_diagnostics();
+            self.report(diagnostics);
+
+            if !self.event_handler.selection.is_empty() && !self.layout_pending {
+                self.backend.overlay(&self.event_handler.selection);
+            }
 
-            self.backend.render();
+            #[cfg(feature = "devtools")]
+            if self.event_handler.devtools.visible && !self.layout_pending {
+                let region = devtools::Devtools::region(self.viewport.size());
+                let panel = self
+                    .event_handler
+                    .devtools
+                    .panel(tree, attribute_storage, states, region);
+                self.backend.overlay(&panel);
+            }
+
+            stats.layout = timings.layout;
+            stats.paint = timings.paint;
+            stats.laid_out = timings.laid_out;
+            stats.interrupted = self.layout_pending;
+
+            // An interrupted layout leaves the previous frame on screen
+            if !self.layout_pending {
+                let render_span = span("render");
+                self.backend.render();
+                drop(render_span);
+                stats.cells_written = self.backend.cells_written();
+            }
             self.backend.clear();
             self.changes.clear();
             self.dirty_widgets.clear();
-        }
 
-        let sleep = sleep_micros.saturating_sub(fps_now.elapsed().as_micros()) as u64;
-        if sleep > 0 {
-            std::thread::sleep(Duration::from_micros(sleep));
+            if !self.layout_pending {
+                self.apply_loop_windows(globals, arena, tree, states, attribute_storage);
+            }
+
+            if let Some(layout_panic) = layout_panic {
+                self.catch_layout_panic(layout_panic, globals, arena, tree, states, attribute_storage);
+                self.notify_error_boundaries(tree, states, attribute_storage, assoc_events, focus_queue);
+            }
         }
 
-        Ok(())
+        stats.active = has_messages || has_events || needs_reflow;
+        if let Some(accessibility) = &mut self.accessibility {
+            accessibility.frame(tree, attribute_storage, states, &mut self.components);
+        }
+        #[cfg(feature = "devtools")]
+        self.event_handler.devtools.record(&stats);
+        #[cfg(feature = "inspector")]
+        if let Some(inspector) = &mut self.inspector {
+            inspector.frame(&stats, tree, attribute_storage, states);
+        }
+        let plugin_ctx = PluginContext {
+            emitter: &self.emitter,
+            viewport: self.viewport,
+        };
+        self.event_handler.plugins.after_frame(&stats, &plugin_ctx);
+        drop(frame_span);
+        end_frame();
+        Ok(stats)
     }
 
     fn tick_components<'bp>(
@@ -706,6 +1689,7 @@ where
             emitter: &self.emitter,
             viewport: self.viewport,
             strings: &self.document.strings,
+            services: &self.services,
         };
 
         for i in 0..self.components.len() {
@@ -717,6 +1701,9 @@ where
             let mut event_ctx = EventCtx {
                 components: &mut self.components,
                 dirty_widgets: &mut self.dirty_widgets,
+                timers: &mut self.timers,
+                mounts: &mut self.mounts,
+                keymap: &mut self.keymap,
                 states,
                 attribute_storage,
                 assoc_events,
@@ -727,4 +1714,935 @@ where
             tree.with_component(widget_id, state_id, &mut event_ctx, |a, b| a.any_tick(b, dt));
         }
     }
+
+    // Replace the closest error boundary above the element that panicked during the layout
+    // with its fallback. The panic resumes if there is no error boundary to catch it.
+    fn catch_layout_panic<'bp>(
+        &mut self,
+        layout_panic: LayoutPanic,
+        globals: &'bp Globals,
+        arena: &'bp WidgetArena,
+        tree: &mut WidgetTree<'bp>,
+        states: &mut States,
+        attribute_storage: &mut AttributeStorage<'bp>,
+    ) {
+        let LayoutPanic { widget_id, payload } = layout_panic;
+        let Some(path) = widget_id.and_then(|widget_id| tree.try_path(widget_id)) else {
+            panic::resume_unwind(payload)
+        };
+
+        let mut scope = Scope::new();
+        let caught = fallback_to_boundary(
+            globals,
+            &self.factory,
+            arena,
+            &mut scope,
+            states,
+            &mut self.component_registry,
+            panic_message(&*payload),
+            &path,
+            tree,
+            attribute_storage,
+            &mut self.floating_widgets,
+            &mut self.components,
+        );
+
+        if !caught {
+            panic::resume_unwind(payload);
+        }
+
+        // The layout is finished on the next frame, with the fallback in place
+        self.layout_pending = true;
+    }
+
+    // Call `on_error` on every error boundary that caught an error
+    // since the last time this was called.
+    // The error boundaries are laid out again, as their body was replaced.
+    fn notify_error_boundaries<'bp>(
+        &mut self,
+        tree: &mut WidgetTree<'bp>,
+        states: &mut States,
+        attribute_storage: &mut AttributeStorage<'bp>,
+        assoc_events: &mut AssociatedEvents,
+        focus_queue: &mut FocusQueue<'static>,
+    ) {
+        let errors = self.components.take_errors();
+        if errors.is_empty() {
+            return;
+        }
+
+        let context = UntypedContext {
+            emitter: &self.emitter,
+            viewport: self.viewport,
+            strings: &self.document.strings,
+            services: &self.services,
+        };
+
+        let mut event_ctx = EventCtx {
+            components: &mut self.components,
+            dirty_widgets: &mut self.dirty_widgets,
+            timers: &mut self.timers,
+            mounts: &mut self.mounts,
+            keymap: &mut self.keymap,
+            states,
+            attribute_storage,
+            assoc_events,
+            focus_queue,
+            context,
+        };
+
+        for (widget_id, state_id, error) in errors {
+            event_ctx.dirty_widgets.push(widget_id);
+            tree.with_component(widget_id, state_id, &mut event_ctx, |comp, ctx| {
+                comp.any_error(ctx, &error)
+            });
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<T, G> Runtime<T, G>
+where
+    T: Backend + AsyncEventSource,
+    G: GlobalEvents,
+{
+    /// Start the runtime on the current Tokio runtime.
+    ///
+    /// Instead of sleeping between frames the runtime awaits the next event,
+    /// message or timer, whichever comes first, so it can run next to other tasks
+    /// without a thread of its own.
+    ///
+    /// This requires Tokio's time driver to be enabled.
+    ///
+    /// ```ignore
+    /// let local = tokio::task::LocalSet::new();
+    /// local.run_until(runtime.run_async()).await;
+    /// ```
+    pub async fn run_async(&mut self) -> Shutdown {
+        self.backend.finalize();
+        loop {
+            match self.internal_run_async().await {
+                Ok(()) => (),
+                Err(Error::Stop(shutdown)) => {
+                    self.exit(&shutdown);
+                    return shutdown;
+                }
+                Err(err) => self.show_error(err),
+            }
+        }
+    }
+
+    async fn internal_run_async(&mut self) -> Result<()> {
+        let mut scheduler = Scheduler::new(self.fps);
+        let sleep_micros = scheduler.budget().as_micros();
+
+        self.with_frame_async(sleep_micros, async |frame| loop {
+            let fps_now = Instant::now();
+            // Events are awaited between frames, so the frame only takes the queued events
+            let stats = frame.tick_at(fps_now, false)?;
+
+            if REBUILD.swap(false, Ordering::Relaxed) {
+                return Ok(());
+            }
+
+            match scheduler.next(stats.active, fps_now, frame.runtime.next_deadline()) {
+                Wait::None => (),
+                Wait::Sleep(sleep) => tokio::time::sleep(sleep).await,
+                Wait::Idle(deadline) => {
+                    // Changed templates can't wake the runtime, so look for them once per frame
+                    let deadline = match frame.runtime.document.hot_reload {
+                        true => {
+                            let next_frame = fps_now + scheduler.budget();
+                            Some(deadline.map_or(next_frame, |deadline| deadline.min(next_frame)))
+                        }
+                        false => deadline,
+                    };
+                    frame.wait(deadline).await;
+                }
+            }
+        })
+        .await
+    }
+}
+
+impl<T, G> Runtime<T, G> {
+    // Run the exit hooks. Hooks only run once, as they are consumed.
+    fn exit(&mut self, shutdown: &Shutdown) {
+        let store = self.store.clone();
+        let _entered = store.enter();
+        for hook in self.exit_hooks.drain(..) {
+            hook(shutdown);
+        }
+    }
+}
+
+impl<T, G> Drop for Runtime<T, G> {
+    fn drop(&mut self) {
+        self.exit(&Shutdown::default());
+
+        // Drop the component states while the store of the runtime is entered,
+        // so the values are removed from the right store
+        let store = self.store.clone();
+        let _entered = store.enter();
+        self.component_registry = ComponentRegistry::new();
+        self.services = Services::new();
+    }
+}
+
+/// A running runtime, driven one frame at a time.
+/// See [`Runtime::headless`].
+pub struct Frame<'rt, 'bp, T, G> {
+    runtime: &'rt mut Runtime<T, G>,
+    tree: &'rt mut WidgetTree<'bp>,
+    states: &'rt mut States,
+    attribute_storage: &'rt mut AttributeStorage<'bp>,
+    assoc_events: &'rt mut AssociatedEvents,
+    focus_queue: &'rt mut FocusQueue<'static>,
+    globals: &'bp Globals,
+    arena: &'bp WidgetArena,
+    component_blueprints: &'bp ComponentBlueprints,
+    dt: Instant,
+    // The time budget of a frame, for handling messages and events
+    sleep_micros: u128,
+}
+
+impl<T, G> Frame<'_, '_, T, G>
+where
+    T: Backend,
+    G: GlobalEvents,
+{
+    /// Handle messages, events and changes, then layout and paint if needed.
+    /// Returns the [`FrameStats`] of the frame, e.g for a debug overlay.
+    pub fn tick(&mut self) -> Result<FrameStats> {
+        self.tick_at(Instant::now(), true)
+    }
+
+    /// Run a frame without waiting for events, for embedding the runtime
+    /// in an external loop (e.g a game loop) that owns the timing.
+    ///
+    /// Returns `Poll::Ready` with the time left of the frame budget if the frame did any work.
+    /// The host should poll again once that time has passed.
+    ///
+    /// Returns `Poll::Pending` if there was nothing to do.
+    /// The host should poll again on the next input, or at the [`Frame::next_deadline`].
+    ///
+    /// ```ignore
+    /// runtime.headless(|frame| loop {
+    ///     host.update();
+    ///     match frame.poll()? {
+    ///         Poll::Ready(budget_left) => host.sleep(budget_left),
+    ///         Poll::Pending => host.wait_for_input(frame.next_deadline()),
+    ///     }
+    /// })?;
+    /// ```
+    pub fn poll(&mut self) -> Result<Poll<Duration>> {
+        let fps_now = Instant::now();
+        let stats = self.tick_at(fps_now, false)?;
+        match stats.active {
+            true => {
+                let budget = Duration::from_micros(self.sleep_micros as u64);
+                Ok(Poll::Ready(budget.saturating_sub(fps_now.elapsed())))
+            }
+            false => Ok(Poll::Pending),
+        }
+    }
+
+    /// The time at which the next timer expires, if any
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.runtime.next_deadline()
+    }
+
+    /// The backend of the runtime
+    pub fn backend(&self) -> &T {
+        &self.runtime.backend
+    }
+
+    /// The backend of the runtime, e.g to queue events
+    pub fn backend_mut(&mut self) -> &mut T {
+        &mut self.runtime.backend
+    }
+
+    /// An [`Emitter`] to send messages to components
+    pub fn emitter(&self) -> Emitter {
+        self.runtime.emitter.clone()
+    }
+
+    // Same as `tick`, where `fps_now` is the start of the frame.
+    // If `block` is false the backend is polled without waiting for events.
+    pub(crate) fn tick_at(&mut self, fps_now: Instant, block: bool) -> Result<FrameStats> {
+        self.runtime.tick(
+            fps_now,
+            block,
+            &mut self.dt,
+            self.sleep_micros,
+            self.tree,
+            self.states,
+            self.attribute_storage,
+            self.globals,
+            self.arena,
+            self.component_blueprints,
+            self.assoc_events,
+            self.focus_queue,
+        )
+    }
+
+    // Block until an event or a message is received, the deadline is reached
+    // or the runtime should rebuild.
+    //
+    // A backend without a waker is polled in slices of `slice`, as messages can't interrupt it.
+    fn idle(&mut self, deadline: Option<Instant>, slice: Duration) {
+        let slice = match self.runtime.wakeable {
+            true => IDLE_TIMEOUT,
+            false => slice,
+        };
+
+        loop {
+            if REBUILD.load(Ordering::Relaxed)
+                || !self.runtime.messages.is_empty()
+                || !self.runtime.message_receiver.is_empty()
+            {
+                return;
+            }
+
+            let timeout = match deadline {
+                Some(deadline) => match deadline.checked_duration_since(Instant::now()) {
+                    Some(remaining) if !remaining.is_zero() => remaining.min(slice),
+                    _ => return,
+                },
+                None => slice,
+            };
+
+            if let Some(event) = self.runtime.backend.next_event(timeout) {
+                self.runtime.event_handler.pending_event = Some(event);
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<T, G> Frame<'_, '_, T, G>
+where
+    T: Backend + AsyncEventSource,
+    G: GlobalEvents,
+{
+    // Wait until an event or a message is received, or the deadline is reached.
+    // Without a deadline this waits for an event or a message.
+    async fn wait(&mut self, deadline: Option<Instant>) {
+        let runtime = &mut *self.runtime;
+        let mut message = pin!(runtime.message_receiver.recv_async());
+        let mut event = pin!(runtime.backend.wait_event());
+        let mut sleep = pin!(async {
+            match deadline {
+                Some(deadline) => tokio::time::sleep_until(deadline.into()).await,
+                None => std::future::pending().await,
+            }
+        });
+
+        let (message, event) = std::future::poll_fn(|cx| {
+            if let Poll::Ready(msg) = message.as_mut().poll(cx) {
+                return Poll::Ready((msg.ok(), None));
+            }
+            if let Poll::Ready(event) = event.as_mut().poll(cx) {
+                return Poll::Ready((None, event));
+            }
+            sleep.as_mut().poll(cx).map(|()| (None, None))
+        })
+        .await;
+
+        if let Some(msg) = message {
+            runtime.messages.push(msg);
+        }
+
+        if let Some(event) = event {
+            runtime.event_handler.pending_event = Some(event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::{Cell, RefCell};
+    use std::rc::Rc;
+    use std::time::Duration;
+
+    use anathema_backend::testing::TestBackend;
+    use anathema_geometry::Size;
+    use anathema_state::{List, Map};
+    use anathema_widgets::components::events::{Event, KeyCode, KeyEvent, UserEventTarget};
+    use anathema_widgets::components::{Context, MountId};
+    use anathema_widgets::layout::{LayoutCtx, PositionCtx};
+    use anathema_widgets::{AttributeStorage, Elements, LayoutChildren, PositionChildren, Widget, WidgetId};
+
+    use super::*;
+    #[cfg(feature = "tokio")]
+    use crate::testing::test::Frames;
+    use crate::testing::test::{Counter, Labelled, Nav};
+    use crate::testing::{ComponentTest, FRAME_BUDGET};
+
+    // Records the errors it catches
+    struct Boundary(Rc<RefCell<Vec<String>>>);
+
+    impl Component for Boundary {
+        type Message = ();
+        type State = Labelled<usize>;
+
+        fn error_boundary(&self) -> bool {
+            true
+        }
+
+        fn accept_focus(&self) -> bool {
+            false
+        }
+
+        fn on_error(&mut self, error: &str, _: &mut Self::State, _: Elements<'_, '_>, _: Context<'_, Self::State>) {
+            self.0.borrow_mut().push(error.into());
+        }
+    }
+
+    // Panics when it's laid out
+    #[derive(Default)]
+    struct Faulty;
+
+    impl Widget for Faulty {
+        fn layout<'bp>(
+            &mut self,
+            _: LayoutChildren<'_, '_, 'bp>,
+            _: Constraints,
+            _: WidgetId,
+            _: &mut LayoutCtx<'_, 'bp>,
+        ) -> Size {
+            panic!("faulty layout")
+        }
+
+        fn position<'bp>(
+            &mut self,
+            _: PositionChildren<'_, '_, 'bp>,
+            _: WidgetId,
+            _: &AttributeStorage<'bp>,
+            _: PositionCtx,
+        ) {
+        }
+    }
+
+    fn boundary_test(template: &str, errors: Rc<RefCell<Vec<String>>>) -> ComponentTest<Boundary> {
+        let mut document = Document::new("@boundary\n    $fallback\n        text 'oops'");
+        document.hot_reload = false;
+
+        let mut builder = Runtime::builder(document, TestBackend::new((4, 2)));
+        builder.factory.register_default::<Faulty>("faulty");
+        let state = Labelled {
+            label: String::new().into(),
+            items: List::empty(),
+        };
+        let component_id = builder
+            .register_component("boundary", template.to_template(), Boundary(errors), state)
+            .unwrap();
+
+        ComponentTest {
+            runtime: builder.finish().unwrap(),
+            component_id,
+        }
+    }
+
+    #[test]
+    fn error_boundary_update() {
+        let errors = Rc::new(RefCell::new(vec![]));
+        let template = "vstack\n    text 'a'\n    for item in items\n        missing";
+        let mut test = boundary_test(template, errors.clone());
+        test.run(|frame| {
+            assert_eq!(frame.output(), "a   \n    \n");
+
+            // The body of the loop is evaluated when the item is inserted
+            frame.with_state(|state: &mut Labelled<usize>| state.items.push(1));
+            frame.tick();
+            assert_eq!(frame.output(), "oops\n    \n");
+        })
+        .unwrap();
+
+        assert_eq!(*errors.borrow(), ["element `missing` does not exist"]);
+    }
+
+    #[test]
+    fn error_boundary_layout() {
+        let errors = Rc::new(RefCell::new(vec![]));
+        let mut test = boundary_test("vstack\n    text 'a'\n    faulty", errors.clone());
+        test.run(|frame| {
+            frame.tick();
+            assert_eq!(frame.output(), "oops\n    \n");
+        })
+        .unwrap();
+
+        assert_eq!(*errors.borrow(), ["faulty layout"]);
+    }
+
+    #[test]
+    fn terminal_requests() {
+        let mut state = Map::empty();
+        state.insert("count", 0);
+
+        let mut test = ComponentTest::new("text count", Nav, state, (5, 1)).unwrap();
+        test.run(|frame| {
+            // Messages are handled before events, so the requests arrive on the next tick
+            frame.press(KeyCode::Char('a')).press(KeyCode::Char('t')).tick().tick();
+            assert_eq!(frame.frame.runtime.backend.title.as_deref(), Some("count: 1"));
+
+            frame.press(KeyCode::Char('b')).press(KeyCode::Char('b')).tick().tick();
+            assert_eq!(frame.frame.runtime.backend.bells, 2);
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn widget_arena() {
+        let mut state = Map::empty();
+        state.insert("count", 0);
+
+        let mut test = ComponentTest::new("vstack\n    text count", Counter, state, (3, 1)).unwrap();
+        let mut first = FrameStats::default();
+        test.run(|frame| first = frame.frame.tick().unwrap()).unwrap();
+        assert!(first.arena_allocated > 0);
+        assert!(first.arena_capacity >= first.arena_allocated);
+
+        // The arena is reset when the tree is rebuilt, keeping its memory
+        test.run(|frame| {
+            let stats = frame.frame.tick().unwrap();
+            assert_eq!(stats.arena_allocated, first.arena_allocated);
+            assert_eq!(stats.arena_capacity, first.arena_capacity);
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn arena_is_kept_when_the_frame_fails() {
+        let mut test = ComponentTest::new("text 'a'", (), (), (3, 1)).unwrap();
+        let res = test.runtime.headless(|frame| {
+            frame.tick()?;
+            Err(std::io::Error::other("failed").into())
+        });
+        assert!(res.is_err());
+        assert!(test.runtime.arena.capacity() > 0);
+    }
+
+    #[test]
+    fn widgets_are_boxed_past_the_arena_limit() {
+        let mut test = ComponentTest::new("text 'a'", (), (), (3, 1)).unwrap();
+        test.runtime.arena_limit = 0;
+        test.run(|frame| {
+            let stats = frame.frame.tick().unwrap();
+            assert_eq!(stats.arena_allocated, 0);
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn layout_diagnostics() {
+        let template = "
+container [min_width: 20]
+    text 'hi'
+position [right: 20]
+    text 'x'
+";
+        let mut test = ComponentTest::new(template, (), (), (10, 1)).unwrap();
+        let (sender, receiver) = flume::unbounded();
+        test.runtime.diagnostics = Some(sender);
+
+        test.run(|_| {}).unwrap();
+        let reported = receiver
+            .try_iter()
+            .map(|diagnostic| diagnostic.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            reported,
+            [
+                "text (line 3, col 5): the min size 20x0 is larger than the max size 10x1",
+                "container (line 2, col 1): the size 20x1 exceeds the max size 10x1",
+                "position (line 4, col 1): the offset 20 is larger than the available space 10",
+            ]
+        );
+    }
+
+    #[test]
+    fn floating_z_index() {
+        let template = "
+vstack
+    text '_'
+    position [z: 1]
+        text 'a'
+    position
+        text 'b'
+";
+        let mut test = ComponentTest::new(template, (), (), (1, 1)).unwrap();
+        test.run(|frame| assert_eq!(frame.output(), "a\n")).unwrap();
+    }
+
+    #[test]
+    fn runtime_timers() {
+        let mut state = Map::empty();
+        state.insert("count", 0);
+
+        let mut test = ComponentTest::new("text count", Counter, state, (5, 1)).unwrap();
+        let component_id = test.component_id;
+        let timers = test.runtime.timers();
+        timers.set_timeout(component_id, Duration::ZERO, 10usize);
+        timers.set_event_timeout(UserEventTarget::Focused, Duration::ZERO, 2usize);
+
+        test.run(|frame| {
+            std::thread::sleep(Duration::from_millis(20));
+            frame.tick();
+            assert_eq!(frame.output(), "12   \n");
+        })
+        .unwrap();
+    }
+
+    // Mounts `a` and `b` on the first key press, and unmounts `a` on the next
+    #[derive(Default)]
+    struct Mounter(Option<MountId>);
+
+    impl Component for Mounter {
+        type Message = ();
+        type State = ();
+
+        fn on_key(&mut self, _: KeyEvent, _: &mut (), _: Elements<'_, '_>, mut context: Context<'_, ()>) {
+            match self.0.take() {
+                None => {
+                    self.0 = Some(context.mount("a", "panel"));
+                    context.mount("b", "panel");
+                }
+                Some(id) => context.unmount(id),
+            }
+        }
+    }
+
+    #[test]
+    fn damage_removed_widget() {
+        let mut document = Document::new("vstack\n    @mounter\n    vstack [outlet: 'panel', width: 5, height: 2]");
+        document.hot_reload = false;
+
+        let mut backend = TestBackend::new((5, 3));
+        backend.track_damage();
+        let mut builder = Runtime::builder(document, backend);
+        builder
+            .register_component("mounter", "text 'm'".to_template(), Mounter::default(), ())
+            .unwrap();
+        builder
+            .register_component("a", "text 'aaaa'".to_template(), (), ())
+            .unwrap();
+        builder
+            .register_component("b", "text 'b'".to_template(), (), ())
+            .unwrap();
+        let mut runtime = builder.finish().unwrap();
+
+        runtime
+            .with_frame(FRAME_BUDGET.as_micros(), |frame| {
+                let mut press = || {
+                    frame.runtime.backend.push_press(KeyCode::Char('x'));
+                    frame.tick().unwrap();
+                    frame.tick().unwrap();
+                    frame.runtime.backend.output.clone()
+                };
+
+                assert_eq!(press(), "m    \naaaa \nb    \n");
+                // `b` is laid out again as `a` is removed,
+                // and the cells painted by `a` are drawn again
+                assert_eq!(press(), "m    \nb    \n     \n");
+                Ok(())
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn mount_error() {
+        let mut document = Document::new("vstack\n    @mounter\n    vstack [outlet: 'panel']");
+        document.hot_reload = false;
+
+        let mut builder = Runtime::builder(document, TestBackend::new((5, 2)));
+        builder
+            .register_component("mounter", "text 'm'".to_template(), Mounter::default(), ())
+            .unwrap();
+        builder
+            .register_component("a", "vstack\n    missing".to_template(), (), ())
+            .unwrap();
+        builder
+            .register_component("b", "text 'b'".to_template(), (), ())
+            .unwrap();
+        // Components are compiled when they are mounted,
+        // so a component that is never mounted can't fail the runtime
+        builder
+            .register_component("unused", "text [".to_template(), (), ())
+            .unwrap();
+        let mut runtime = builder.finish().unwrap();
+
+        runtime
+            .with_frame(FRAME_BUDGET.as_micros(), |frame| {
+                frame.runtime.backend.push_press(KeyCode::Char('x'));
+                let err = frame.tick().unwrap_err();
+                assert_eq!(err.to_string(), "element `missing` does not exist");
+                Ok(())
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn mount_error_boundary() {
+        let errors = Rc::new(RefCell::new(vec![]));
+        let mut document = Document::new("@boundary\n    $fallback\n        text 'oops'");
+        document.hot_reload = false;
+
+        let mut builder = Runtime::builder(document, TestBackend::new((4, 2)));
+        let state = Labelled {
+            label: String::new().into(),
+            items: List::empty(),
+        };
+        let template = "vstack\n    @mounter\n    vstack [outlet: 'panel']";
+        builder
+            .register_component("boundary", template.to_template(), Boundary(errors.clone()), state)
+            .unwrap();
+        builder
+            .register_component("mounter", "text 'm'".to_template(), Mounter::default(), ())
+            .unwrap();
+        builder.register_component("a", "text [".to_template(), (), ()).unwrap();
+        builder
+            .register_component("b", "text 'b'".to_template(), (), ())
+            .unwrap();
+        let mut runtime = builder.finish().unwrap();
+
+        runtime
+            .with_frame(FRAME_BUDGET.as_micros(), |frame| {
+                assert_eq!(frame.runtime.backend.output, "m   \n    \n");
+
+                frame.runtime.backend.push_press(KeyCode::Char('x'));
+                frame.tick().unwrap();
+                frame.tick().unwrap();
+                assert_eq!(frame.runtime.backend.output, "oops\n    \n");
+                Ok(())
+            })
+            .unwrap();
+
+        assert_eq!(errors.borrow().len(), 1);
+    }
+
+    #[test]
+    fn resize_reflow() {
+        let template = "border\n    expand\n        text 'x'";
+        let mut test = ComponentTest::new(template, (), (), (3, 3)).unwrap();
+        test.run(|frame| {
+            assert_eq!(frame.output(), "┌─┐\n│x│\n└─┘\n");
+            frame.resize(4, 3).tick();
+            assert_eq!(frame.output(), "┌──┐\n│x │\n└──┘\n");
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn poll_without_blocking() {
+        let mut document = Document::new("@counter");
+        document.hot_reload = false;
+
+        let mut state = Map::empty();
+        state.insert("count", 0);
+
+        let mut builder = Runtime::builder(document, TestBackend::new((3, 1)));
+        builder
+            .register_component("counter", "text count".to_template(), Counter, state)
+            .unwrap();
+        let mut runtime = builder.finish().unwrap();
+
+        runtime
+            .headless(|frame| {
+                assert!(frame.poll()?.is_pending());
+
+                frame
+                    .backend_mut()
+                    .push_event(Event::Key(KeyEvent::press(KeyCode::Char('a'))));
+                assert!(frame.poll()?.is_ready());
+                assert_eq!(frame.backend().output, "1  \n");
+
+                assert!(frame.poll()?.is_pending());
+                assert!(frame.next_deadline().is_none());
+                Ok(())
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn shutdown_runs_exit_hooks_once() {
+        let mut document = Document::new("@nav");
+        document.hot_reload = false;
+
+        let mut builder = Runtime::builder(document, TestBackend::new((1, 1)));
+        let mut state = Map::empty();
+        state.insert("count", 0);
+        builder
+            .register_component("nav", "text count".to_template(), Nav, state)
+            .unwrap();
+
+        let exits = Rc::new(Cell::new(0));
+        builder.on_exit({
+            let exits = exits.clone();
+            move |shutdown| {
+                assert_eq!(shutdown.code(), 2);
+                exits.set(exits.get() + 1);
+            }
+        });
+
+        let mut runtime = builder.finish().unwrap();
+        runtime
+            .backend
+            .push_event(Event::Key(KeyEvent::press(KeyCode::Char('q'))));
+
+        let shutdown = runtime.run();
+        assert_eq!(shutdown.code(), 2);
+        assert_eq!(shutdown.reason::<&str>(), Some(&"done"));
+
+        drop(runtime);
+        assert_eq!(exits.get(), 1);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn run_async() {
+        let mut document = Document::new("text 'a'");
+        document.hot_reload = false;
+        let mut runtime = Runtime::builder(document, TestBackend::new((1, 1))).finish().unwrap();
+        runtime.backend.push_event(Event::Stop);
+
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        rt.block_on(runtime.run_async());
+
+        assert_eq!(runtime.backend.output, "a\n");
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn run_async_awaits_events() {
+        let mut document = Document::new("@nav");
+        document.hot_reload = false;
+
+        let mut builder = Runtime::builder(document, TestBackend::new((1, 1)));
+        let mut state = Map::empty();
+        state.insert("count", 0);
+        builder
+            .register_component("nav", "text count".to_template(), Nav, state)
+            .unwrap();
+        let frames = Rc::new(Cell::new(0));
+        builder.plugin(Frames(frames.clone()));
+
+        let mut runtime = builder.finish().unwrap();
+        runtime
+            .backend
+            .wait(Duration::from_millis(300))
+            .push_event(Event::Key(KeyEvent::press(KeyCode::Char('q'))));
+
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        let shutdown = rt.block_on(runtime.run_async());
+        assert_eq!(shutdown.code(), 2);
+
+        // The idle runtime waits for the event instead of waking up every frame
+        assert!(frames.get() < 5, "{} frames", frames.get());
+    }
+
+    #[cfg(feature = "tokio")]
+    struct Spawner;
+
+    #[cfg(feature = "tokio")]
+    impl Component for Spawner {
+        type Message = String;
+        type State = ();
+
+        fn on_key(&mut self, _: KeyEvent, _: &mut Self::State, _: Elements<'_, '_>, context: Context<'_, Self::State>) {
+            context.spawn::<Self>(async { String::from("fetched") });
+        }
+
+        fn message(
+            &mut self,
+            message: Self::Message,
+            _: &mut Self::State,
+            _: Elements<'_, '_>,
+            context: Context<'_, Self::State>,
+        ) {
+            context.shutdown(Shutdown::new(3).with_reason(message));
+        }
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn spawned_future_output_is_a_message() {
+        let mut document = Document::new("@spawner");
+        document.hot_reload = false;
+
+        let mut builder = Runtime::builder(document, TestBackend::new((1, 1)));
+        builder
+            .register_component("spawner", "text 'a'".to_template(), Spawner, ())
+            .unwrap();
+        let mut runtime = builder.finish().unwrap();
+        runtime
+            .backend
+            .push_event(Event::Key(KeyEvent::press(KeyCode::Char('s'))));
+
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        let shutdown = rt.block_on(runtime.run_async());
+        assert_eq!(shutdown.code(), 3);
+        assert_eq!(shutdown.reason::<String>().map(String::as_str), Some("fetched"));
+    }
+
+    // Counts the calls to `next_event`, which wait until woken
+    struct Wakeable {
+        calls: Rc<Cell<usize>>,
+        woken: flume::Receiver<()>,
+        wake: flume::Sender<()>,
+    }
+
+    impl anathema_backend::EventSource for Wakeable {
+        fn next_event(&mut self, timeout: Duration) -> Option<Event> {
+            self.calls.set(self.calls.get() + 1);
+            let _ = self.woken.recv_timeout(timeout);
+            None
+        }
+
+        fn waker(&self) -> Option<anathema_backend::Waker> {
+            let wake = self.wake.clone();
+            Some(std::sync::Arc::new(move || {
+                let _ = wake.send(());
+            }))
+        }
+    }
+
+    #[test]
+    fn idle_runtime_waits_until_woken() {
+        let mut document = Document::new("text 'a'");
+        document.hot_reload = false;
+
+        let calls = Rc::new(Cell::new(0));
+        let (wake, woken) = flume::unbounded();
+        let events = Wakeable {
+            calls: calls.clone(),
+            woken,
+            wake,
+        };
+        let backend = anathema_backend::Composed::new(events, TestBackend::new((1, 1)));
+        let mut runtime = Runtime::builder(document, backend).finish().unwrap();
+
+        let emitter = runtime.emitter();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(300));
+            emitter.shutdown(2).unwrap();
+        });
+
+        let shutdown = runtime.run();
+        assert_eq!(shutdown.code(), 2);
+
+        // Polling once per frame would take about ten calls
+        assert!(calls.get() < 3, "{} calls", calls.get());
+    }
 }
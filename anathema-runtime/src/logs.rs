@@ -0,0 +1,238 @@
+//! Capture the records of the [`log`] crate in the state of a component,
+//! as printing to the terminal would draw over the application.
+//!
+//! ```ignore
+//! let mut builder = Runtime::builder(Document::new("@logs"), backend);
+//! let logs = builder.register_component(
+//!     "logs",
+//!     LogCapture::TEMPLATE.to_template(),
+//!     LogCapture::new(1000),
+//!     LogState::new(),
+//! )?;
+//! capture(builder.emitter(), logs, log::LevelFilter::Info)?;
+//! ```
+//!
+//! The records are shown with the `log_view` widget, or any other template
+//! using the `records` of the [`LogState`]:
+//!
+//! ```text
+//! log_view [records: records, level: "warn"]
+//! ```
+use anathema_state::{CommonVal, List, Path, PendingValue, State, Subscriber, Value, ValueRef};
+use anathema_widgets::components::{Component, ComponentId, Context, Emitter};
+use anathema_widgets::Elements;
+use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
+
+/// Capture log records up to the level and send them to the component.
+///
+/// This installs the global logger, so it can only be called once.
+pub fn capture(emitter: Emitter, component: ComponentId<Captured>, level: LevelFilter) -> Result<(), SetLoggerError> {
+    log::set_boxed_logger(Box::new(Logger { emitter, component }))?;
+    log::set_max_level(level);
+    Ok(())
+}
+
+struct Logger {
+    emitter: Emitter,
+    component: ComponentId<Captured>,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let captured = Captured {
+            level: record.level(),
+            target: record.target().into(),
+            message: record.args().to_string(),
+        };
+
+        // The runtime has stopped, there is nowhere to show the record
+        _ = self.emitter.emit(self.component, captured);
+    }
+
+    fn flush(&self) {}
+}
+
+/// A log record sent from the logger to the [`LogCapture`] component
+#[derive(Debug)]
+pub struct Captured {
+    pub level: log::Level,
+    pub target: String,
+    pub message: String,
+}
+
+/// A log record in the state
+#[derive(Debug)]
+pub struct LogRecord {
+    pub level: Value<String>,
+    pub target: Value<String>,
+    pub message: Value<String>,
+}
+
+impl State for LogRecord {
+    fn state_get(&self, path: Path<'_>, sub: Subscriber) -> Option<ValueRef> {
+        let Path::Key(key) = path else { return None };
+        match key {
+            "level" => Some(self.level.value_ref(sub)),
+            "target" => Some(self.target.value_ref(sub)),
+            "message" => Some(self.message.value_ref(sub)),
+            _ => None,
+        }
+    }
+
+    fn state_lookup(&self, path: Path<'_>) -> Option<PendingValue> {
+        let Path::Key(key) = path else { return None };
+        match key {
+            "level" => Some(self.level.to_pending()),
+            "target" => Some(self.target.to_pending()),
+            "message" => Some(self.message.to_pending()),
+            _ => None,
+        }
+    }
+
+    fn to_common(&self) -> Option<CommonVal<'_>> {
+        None
+    }
+
+    fn state_keys(&self) -> &'static [&'static str] {
+        &["level", "target", "message"]
+    }
+}
+
+/// The state of the [`LogCapture`] component
+#[derive(Debug)]
+pub struct LogState {
+    pub records: Value<List<LogRecord>>,
+}
+
+impl LogState {
+    pub fn new() -> Self {
+        Self { records: List::empty() }
+    }
+}
+
+impl State for LogState {
+    fn state_get(&self, path: Path<'_>, sub: Subscriber) -> Option<ValueRef> {
+        match path {
+            Path::Key("records") => Some(self.records.value_ref(sub)),
+            _ => None,
+        }
+    }
+
+    fn state_lookup(&self, path: Path<'_>) -> Option<PendingValue> {
+        match path {
+            Path::Key("records") => Some(self.records.to_pending()),
+            _ => None,
+        }
+    }
+
+    fn to_common(&self) -> Option<CommonVal<'_>> {
+        None
+    }
+
+    fn state_keys(&self) -> &'static [&'static str] {
+        &["records"]
+    }
+}
+
+/// Keeps the most recent log records in a list.
+/// The oldest records are removed once the list is at capacity.
+#[derive(Debug)]
+pub struct LogCapture {
+    capacity: usize,
+}
+
+impl LogCapture {
+    /// A template showing every record
+    pub const TEMPLATE: &'static str = "log_view [records: records]";
+
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity }
+    }
+}
+
+impl Component for LogCapture {
+    type Message = Captured;
+    type State = LogState;
+
+    fn message(
+        &mut self,
+        message: Self::Message,
+        state: &mut Self::State,
+        _: Elements<'_, '_>,
+        _: Context<'_, Self::State>,
+    ) {
+        state.records.push_back(LogRecord {
+            level: message.level.as_str().to_lowercase().into(),
+            target: message.target.into(),
+            message: message.message.into(),
+        });
+
+        while state.records.len() > self.capacity {
+            state.records.pop_front();
+        }
+    }
+
+    fn accept_focus(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use log::Level;
+
+    use super::*;
+    use crate::testing::ComponentTest;
+
+    #[test]
+    fn log_capture() {
+        let record = |level, message: &str| Captured {
+            level,
+            target: "app".into(),
+            message: message.into(),
+        };
+
+        let template = "log_view [records: records, level: 'info']";
+        let mut test = ComponentTest::new(template, LogCapture::new(4), LogState::new(), (20, 3)).unwrap();
+
+        test.run(|frame| {
+            frame
+                .send(record(Level::Info, "started"))
+                .send(record(Level::Debug, "hidden"))
+                .send(record(Level::Warn, "slow"))
+                .send(record(Level::Error, "failed"))
+                .tick();
+            assert_eq!(
+                frame.output(),
+                "INFO  app: started  \nWARN  app: slow     \nERROR app: failed   \n"
+            );
+
+            // Only the last four records are kept
+            frame.send(record(Level::Info, "restarted")).tick();
+            assert_eq!(
+                frame.output(),
+                "WARN  app: slow     \nERROR app: failed   \nINFO  app: restarted\n"
+            );
+        })
+        .unwrap();
+
+        let template = "log_view [records: records, filter: 'fail']";
+        let mut test = ComponentTest::new(template, LogCapture::new(4), LogState::new(), (12, 2)).unwrap();
+        test.run(|frame| {
+            frame
+                .send(record(Level::Debug, "failed"))
+                .send(record(Level::Warn, "slow"))
+                .tick();
+            assert_eq!(frame.output(), "DEBUG app: f\n            \n");
+        })
+        .unwrap();
+    }
+}
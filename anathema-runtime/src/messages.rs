@@ -0,0 +1,84 @@
+use std::collections::VecDeque;
+
+use anathema_widgets::components::{Message, Priority};
+
+/// Messages waiting to be handled, ordered by priority.
+///
+/// A tagged view message replaces the payload of a queued message
+/// with the same recipient and tag, keeping its place in the queue.
+#[derive(Default)]
+pub(crate) struct MessageQueue {
+    high: VecDeque<Message>,
+    normal: VecDeque<Message>,
+    low: VecDeque<Message>,
+}
+
+impl MessageQueue {
+    pub(crate) fn push(&mut self, message: Message) {
+        let queue = match message.priority() {
+            Priority::High => &mut self.high,
+            Priority::Normal => &mut self.normal,
+            Priority::Low => &mut self.low,
+        };
+
+        let Message::View(newer) = message else { return queue.push_back(message) };
+        let Some(tag) = newer.tag() else { return queue.push_back(Message::View(newer)) };
+
+        let queued = queue.iter_mut().find_map(|queued| match queued {
+            Message::View(queued) if queued.recipient() == newer.recipient() && queued.tag() == Some(tag) => {
+                Some(queued)
+            }
+            _ => None,
+        });
+
+        match queued {
+            Some(queued) => queued.replace(newer),
+            None => queue.push_back(Message::View(newer)),
+        }
+    }
+
+    pub(crate) fn pop(&mut self) -> Option<Message> {
+        self.high
+            .pop_front()
+            .or_else(|| self.normal.pop_front())
+            .or_else(|| self.low.pop_front())
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.high.is_empty() && self.normal.is_empty() && self.low.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use anathema_templates::WidgetComponentId;
+    use anathema_widgets::components::{ComponentId, Emitter};
+
+    use super::*;
+
+    fn payload(message: Message) -> usize {
+        match message {
+            Message::View(msg) => *msg.payload().downcast::<usize>().unwrap(),
+            _ => panic!("expected a view message"),
+        }
+    }
+
+    #[test]
+    fn priority_and_coalescing() {
+        let (sender, receiver) = flume::unbounded();
+        let emitter = Emitter::from(sender);
+        let component: ComponentId<usize> = WidgetComponentId::from(0usize).into();
+
+        emitter.emit_latest(component, "progress", 1).unwrap();
+        emitter.emit(component, 2).unwrap();
+        emitter.emit_latest(component, "progress", 3).unwrap();
+        emitter.emit_with_priority(component, 4, Priority::High).unwrap();
+
+        let mut queue = MessageQueue::default();
+        receiver.try_iter().for_each(|msg| queue.push(msg));
+
+        let order = std::iter::from_fn(|| queue.pop()).map(payload).collect::<Vec<_>>();
+        assert_eq!(order, [4, 2, 3]);
+        assert!(queue.is_empty());
+    }
+}
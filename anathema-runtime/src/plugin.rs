@@ -0,0 +1,132 @@
+use anathema_widgets::components::events::Event;
+use anathema_widgets::components::{Emitter, Services, Timers};
+use anathema_widgets::layout::Viewport;
+
+use crate::{FrameStats, GlobalKeymap};
+
+/// Extends the runtime with hooks into the runtime loop,
+/// e.g for metrics, logging or devtools.
+///
+/// Plugins are registered with [`RuntimeBuilder::plugin`](crate::RuntimeBuilder::plugin)
+/// and called in the order they were registered.
+///
+/// ```ignore
+/// struct SlowFrames;
+///
+/// impl RuntimePlugin for SlowFrames {
+///     fn after_frame(&mut self, stats: &FrameStats, _: &PluginContext<'_>) {
+///         if stats.layout + stats.paint > Duration::from_millis(16) {
+///             log::warn!("slow frame: {stats:?}");
+///         }
+///     }
+/// }
+///
+/// builder.plugin(SlowFrames);
+/// ```
+pub trait RuntimePlugin {
+    /// Called once, when the runtime is built
+    fn on_build(&mut self, _ctx: &mut BuildContext<'_>) {}
+
+    /// Called at the start of every frame
+    fn before_frame(&mut self, _ctx: &PluginContext<'_>) {}
+
+    /// Called at the end of every frame that didn't fail
+    fn after_frame(&mut self, _stats: &FrameStats, _ctx: &PluginContext<'_>) {}
+
+    /// Called for every event from the backend, before the event is handled by the runtime.
+    /// Return `None` to consume the event.
+    fn on_event(&mut self, event: Event, _ctx: &PluginContext<'_>) -> Option<Event> {
+        Some(event)
+    }
+}
+
+/// Passed to [`RuntimePlugin::on_build`], to register shortcuts, timers and services.
+pub struct BuildContext<'rt> {
+    pub emitter: &'rt Emitter,
+    pub keymap: &'rt mut GlobalKeymap,
+    pub timers: &'rt mut Timers,
+    pub services: &'rt mut Services,
+}
+
+/// Passed to the frame and event hooks of a [`RuntimePlugin`]
+pub struct PluginContext<'rt> {
+    pub emitter: &'rt Emitter,
+    pub viewport: Viewport,
+}
+
+#[derive(Default)]
+pub(crate) struct Plugins(Vec<Box<dyn RuntimePlugin>>);
+
+impl Plugins {
+    pub(crate) fn push(&mut self, plugin: impl RuntimePlugin + 'static) {
+        self.0.push(Box::new(plugin));
+    }
+
+    pub(crate) fn build(&mut self, ctx: &mut BuildContext<'_>) {
+        self.0.iter_mut().for_each(|plugin| plugin.on_build(ctx));
+    }
+
+    pub(crate) fn before_frame(&mut self, ctx: &PluginContext<'_>) {
+        self.0.iter_mut().for_each(|plugin| plugin.before_frame(ctx));
+    }
+
+    pub(crate) fn after_frame(&mut self, stats: &FrameStats, ctx: &PluginContext<'_>) {
+        self.0.iter_mut().for_each(|plugin| plugin.after_frame(stats, ctx));
+    }
+
+    // Pass the event through all plugins, until a plugin consumes it
+    pub(crate) fn event(&mut self, event: Event, ctx: &PluginContext<'_>) -> Option<Event> {
+        self.0
+            .iter_mut()
+            .try_fold(event, |event, plugin| plugin.on_event(event, ctx))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use anathema_backend::testing::TestBackend;
+    use anathema_state::Map;
+    use anathema_templates::{Document, ToSourceKind};
+    use anathema_widgets::components::events::{Event, KeyCode, KeyEvent};
+
+    use crate::testing::test::{Counter, Frames};
+    use crate::testing::FRAME_BUDGET;
+    use crate::Runtime;
+
+    #[test]
+    fn plugins() {
+        let mut document = Document::new("@counter");
+        document.hot_reload = false;
+
+        let mut state = Map::empty();
+        state.insert("count", 0);
+
+        let frames = Rc::new(Cell::new(0));
+        let mut builder = Runtime::builder(document, TestBackend::new((3, 1)));
+        builder
+            .register_component("counter", "text count".to_template(), Counter, state)
+            .unwrap();
+        builder.plugin(Frames(frames.clone()));
+        let mut runtime = builder.finish().unwrap();
+        assert_eq!(runtime.services.get::<&str>(), Some(&"frames"));
+
+        runtime
+            .with_frame(FRAME_BUDGET.as_micros(), |frame| {
+                for c in ['x', 'a'] {
+                    frame
+                        .runtime
+                        .backend
+                        .push_event(Event::Key(KeyEvent::press(KeyCode::Char(c))));
+                }
+                frame.tick().unwrap();
+                assert_eq!(frame.backend().output, "1  \n");
+                Ok(())
+            })
+            .unwrap();
+
+        assert_eq!(frames.get(), 1);
+    }
+}
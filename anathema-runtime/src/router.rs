@@ -0,0 +1,119 @@
+use std::borrow::Cow;
+
+use anathema_widgets::components::ScreenRequest;
+use anathema_widgets::WidgetId;
+
+/// A stack of screens, where only the screen on top is in the tree.
+pub(crate) struct Router {
+    outlet: Cow<'static, str>,
+    stack: Vec<Cow<'static, str>>,
+    // The widget id of the screen in the tree
+    pub(crate) active: Option<WidgetId>,
+}
+
+impl Router {
+    pub(crate) fn new(outlet: Cow<'static, str>, initial: Cow<'static, str>) -> Self {
+        Self {
+            outlet,
+            stack: vec![initial],
+            active: None,
+        }
+    }
+
+    /// The name of the outlet the screens are mounted in
+    pub(crate) fn outlet(&self) -> &str {
+        &self.outlet
+    }
+
+    /// The name of the screen on top of the stack
+    pub(crate) fn current(&self) -> &str {
+        self.stack.last().expect("the first screen can not be popped")
+    }
+
+    /// Update the stack.
+    /// Returns true if the screen on top of the stack changed.
+    pub(crate) fn apply(&mut self, request: ScreenRequest) -> bool {
+        match request {
+            ScreenRequest::Push(name) => self.stack.push(name),
+            ScreenRequest::Pop if self.stack.len() > 1 => _ = self.stack.pop(),
+            ScreenRequest::Pop => return false,
+            ScreenRequest::Replace(name) => {
+                let current = self.stack.last_mut().expect("the first screen can not be popped");
+                if *current == name {
+                    return false;
+                }
+                *current = name;
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use anathema_backend::testing::TestBackend;
+    use anathema_state::Map;
+    use anathema_templates::{Document, ToSourceKind};
+    use anathema_widgets::components::events::{Event, KeyCode, KeyEvent};
+
+    use super::*;
+    use crate::testing::test::Nav;
+    use crate::testing::FRAME_BUDGET;
+    use crate::Runtime;
+
+    #[test]
+    fn screen_stack() {
+        let mut router = Router::new("screens".into(), "home".into());
+        assert_eq!(router.current(), "home");
+
+        assert!(!router.apply(ScreenRequest::Pop));
+        assert!(router.apply(ScreenRequest::Push("settings".into())));
+        assert_eq!(router.current(), "settings");
+
+        assert!(!router.apply(ScreenRequest::Replace("settings".into())));
+        assert!(router.apply(ScreenRequest::Replace("about".into())));
+        assert_eq!(router.current(), "about");
+
+        assert!(router.apply(ScreenRequest::Pop));
+        assert_eq!(router.current(), "home");
+    }
+
+    #[test]
+    fn router_retains_screen_state() {
+        let mut document = Document::new("container [outlet: 'screens']");
+        document.hot_reload = false;
+
+        let mut state = Map::empty();
+        state.insert("count", 0);
+
+        let mut builder = Runtime::builder(document, TestBackend::new((3, 1)));
+        builder
+            .register_component("first", "text count".to_template(), Nav, state)
+            .unwrap();
+        builder
+            .register_component("second", "text 'b'".to_template(), Nav, Map::empty())
+            .unwrap();
+        builder.router("screens", "first");
+        let mut runtime = builder.finish().unwrap();
+
+        runtime
+            .with_frame(FRAME_BUDGET.as_micros(), |frame| {
+                let mut press = |c| {
+                    frame
+                        .runtime
+                        .backend
+                        .push_event(Event::Key(KeyEvent::press(KeyCode::Char(c))));
+                    frame.tick().unwrap();
+                    frame.tick().unwrap();
+                    frame.runtime.backend.output.clone()
+                };
+
+                assert_eq!(press('x'), "1  \n");
+                assert_eq!(press('n'), "b  \n");
+                assert_eq!(press('p'), "1  \n");
+                assert_eq!(press('x'), "2  \n");
+                Ok(())
+            })
+            .unwrap();
+    }
+}
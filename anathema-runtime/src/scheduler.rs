@@ -0,0 +1,80 @@
+use std::time::Duration;
+
+use anathema_widgets::time::Instant;
+
+/// What the runtime should do after a frame
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Wait {
+    /// Start the next frame straight away
+    None,
+    /// Sleep for the rest of the frame budget
+    Sleep(Duration),
+    /// Nothing happened during the frame.
+    /// Wait for the next event, message or timer (if there is one).
+    Idle(Option<Instant>),
+}
+
+/// Decides how long the runtime waits between frames.
+///
+/// A frame following an idle period runs immediately,
+/// so events and messages are rendered without delay.
+/// Only when frames keep changing the tree (e.g an animation)
+/// is the frame rate capped by the frame budget.
+pub(crate) struct Scheduler {
+    budget: Duration,
+    active: bool,
+}
+
+impl Scheduler {
+    pub(crate) fn new(fps: u16) -> Self {
+        Self {
+            budget: Duration::from_secs(1) / fps.max(1) as u32,
+            active: false,
+        }
+    }
+
+    /// The time budget of a frame
+    pub(crate) fn budget(&self) -> Duration {
+        self.budget
+    }
+
+    /// Given if the last frame did any work, when it started,
+    /// and when the next timer expires, decide how long to wait.
+    pub(crate) fn next(&mut self, active: bool, frame_start: Instant, deadline: Option<Instant>) -> Wait {
+        let sustained = std::mem::replace(&mut self.active, active);
+
+        match (active, sustained) {
+            (false, _) => Wait::Idle(deadline),
+            (true, false) => Wait::None,
+            (true, true) => match self.budget.checked_sub(frame_start.elapsed()) {
+                Some(sleep) if !sleep.is_zero() => Wait::Sleep(sleep),
+                _ => Wait::None,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn render_immediately_then_cap() {
+        let mut scheduler = Scheduler::new(10);
+        let now = Instant::now();
+
+        // Idle
+        assert_eq!(scheduler.next(false, now, None), Wait::Idle(None));
+
+        // The first active frame is not held back
+        assert_eq!(scheduler.next(true, now, None), Wait::None);
+
+        // Sustained change is capped by the frame budget
+        let Wait::Sleep(sleep) = scheduler.next(true, now, None) else { panic!("expected sleep") };
+        assert!(sleep <= Duration::from_millis(100));
+
+        // Back to idle, until the next timer
+        let deadline = now + Duration::from_secs(1);
+        assert_eq!(scheduler.next(false, now, Some(deadline)), Wait::Idle(Some(deadline)));
+    }
+}
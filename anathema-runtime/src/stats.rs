@@ -0,0 +1,173 @@
+use std::time::Duration;
+
+/// Metrics of a single frame, returned from [`Frame::tick`](crate::Frame::tick).
+///
+/// Layout, paint and cells written are zero if the tree wasn't painted.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct FrameStats {
+    /// Time spent on layout and position
+    pub layout: Duration,
+    /// Time spent painting the widgets
+    pub paint: Duration,
+    /// Cells written to the output by the backend.
+    /// Only backends that diff their output report this.
+    pub cells_written: usize,
+    /// The number of widgets in the tree
+    pub widgets: usize,
+    /// The number of widgets the tree can hold before its storage has to grow,
+    /// see [`Runtime::tree_capacity`](crate::Runtime::tree_capacity)
+    pub capacity: usize,
+    /// The number of bytes used by the widgets in the [`WidgetArena`](anathema_widgets::WidgetArena)
+    pub arena_allocated: usize,
+    /// The number of bytes reserved by the [`WidgetArena`](anathema_widgets::WidgetArena).
+    /// The memory is kept when the tree is rebuilt, so this only grows
+    pub arena_capacity: usize,
+    /// The number of widgets laid out
+    pub laid_out: usize,
+    /// The number of state changes applied
+    pub changes: usize,
+    /// True if the frame handled messages or events, or painted the tree
+    pub active: bool,
+    /// True if the layout exceeded the [layout budget](crate::Runtime::layout_budget).
+    /// The previous frame stays on screen and the layout resumes on the next frame.
+    pub interrupted: bool,
+}
+
+impl FrameStats {
+    /// True if the tree was painted during the frame
+    pub fn painted(&self) -> bool {
+        !self.interrupted && (!self.layout.is_zero() || !self.paint.is_zero())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use anathema_backend::assert_frame_eq;
+    use anathema_state::Map;
+    use anathema_widgets::components::events::KeyCode;
+
+    use crate::testing::test::Counter;
+    use crate::testing::ComponentTest;
+
+    #[test]
+    fn frame_stats() {
+        let mut state = Map::empty();
+        state.insert("count", 0);
+
+        let mut test = ComponentTest::new("text count", Counter, state, (5, 1)).unwrap();
+        test.run(|frame| {
+            let stats = frame.frame.tick().unwrap();
+            assert!(!stats.active);
+            assert!(!stats.painted());
+            assert_eq!(stats.changes, 0);
+
+            frame.press(KeyCode::Char('a'));
+            let stats = frame.frame.tick().unwrap();
+            assert!(stats.active);
+            assert_eq!(stats.changes, 1);
+            // The component and the text
+            assert_eq!(stats.widgets, 2);
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn layout_cache() {
+        let mut state = Map::empty();
+        state.insert("count", 0);
+
+        let template = "
+hstack
+    text count
+    border
+        vstack
+            text 'a'
+            text 'b'
+";
+        let mut test = ComponentTest::new(template, Counter, state, (6, 4)).unwrap();
+        test.run(|frame| {
+            frame.press(KeyCode::Char('a'));
+            let stats = frame.frame.tick().unwrap();
+            assert_frame_eq!(frame.grid(), "1┌─┐\n │a│\n │b│\n └─┘");
+            // The text and its parent, as the border is given the same constraints
+            assert_eq!(stats.laid_out, 2);
+
+            // A wider text leaves less room for the border
+            frame.send(9);
+            let stats = frame.frame.tick().unwrap();
+            assert_frame_eq!(frame.grid(), "10┌─┐\n  │a│\n  │b│\n  └─┘");
+            assert_eq!(stats.laid_out, 6);
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn layout_subtree() {
+        let mut state = Map::empty();
+        state.insert("count", 0);
+
+        let template = "
+vstack
+    text 'a'
+    border [width: 3, height: 3]
+        text count
+    text 'b'
+";
+        let mut test = ComponentTest::new(template, Counter, state, (3, 5)).unwrap();
+        test.run(|frame| {
+            frame.press(KeyCode::Char('a'));
+            let stats = frame.frame.tick().unwrap();
+            assert_frame_eq!(frame.grid(), "a\n┌─┐\n│1│\n└─┘\nb");
+            // The size of the border is the same, so the vstack is not laid out again
+            assert_eq!(stats.laid_out, 2);
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn tree_capacity() {
+        let mut state = Map::empty();
+        state.insert("count", 0);
+
+        let mut test = ComponentTest::new("vstack\n    text count", Counter, state, (3, 1)).unwrap();
+        test.runtime.tree_capacity = 64;
+        test.run(|frame| {
+            frame.press(KeyCode::Char('a'));
+            let stats = frame.frame.tick().unwrap();
+            assert!(stats.widgets < 64);
+            assert!(stats.capacity >= 64);
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn layout_budget() {
+        let mut state = Map::empty();
+        state.insert("count", 0);
+
+        let template = "vstack\n    text count\n    text count\n    text count";
+        let mut test = ComponentTest::new(template, Counter, state, (1, 3)).unwrap();
+        // Every frame runs out of time after laying out a single widget
+        test.runtime.layout_budget = Some(Duration::ZERO);
+        test.run(|frame| {
+            frame.press(KeyCode::Char('a'));
+
+            let mut frames = 0;
+            loop {
+                let stats = frame.frame.tick().unwrap();
+                frames += 1;
+                if !stats.interrupted {
+                    break;
+                }
+                assert!(!stats.painted());
+                assert_eq!(frame.output(), "0\n0\n0\n");
+            }
+
+            assert!(frames > 1);
+            assert_eq!(frame.output(), "1\n1\n1\n");
+        })
+        .unwrap();
+    }
+}
@@ -0,0 +1,523 @@
+//! Test a single component without a terminal.
+//!
+//! ```
+//! # use anathema_runtime::testing::ComponentTest;
+//! # use anathema_widgets::components::events::KeyCode;
+//! let mut test = ComponentTest::new("text 'hello'", (), (), (10, 1)).unwrap();
+//! test.run(|frame| {
+//!     frame.press(KeyCode::Enter).tick();
+//!     assert_eq!(frame.output().trim(), "hello");
+//! })
+//! .unwrap();
+//! ```
+use std::time::Duration;
+
+use anathema_backend::testing::{TestBackend, TestSurface};
+use anathema_geometry::Size;
+use anathema_templates::{Document, ToSourceKind, WidgetComponentId};
+use anathema_widgets::components::events::{Event, ImeEvent, KeyCode, KeyEvent, MouseState, UserEvent};
+use anathema_widgets::components::{Component, ComponentId};
+
+use crate::{Frame, Result, Runtime};
+
+// The budget for handling messages and events during a frame.
+// This is more than enough to drain the queues in a test.
+pub(crate) const FRAME_BUDGET: Duration = Duration::from_secs(1);
+
+/// Mounts a component as the root of a document,
+/// rendering to a [`TestBackend`].
+pub struct ComponentTest<C: Component> {
+    pub(crate) runtime: Runtime<TestBackend, ()>,
+    pub(crate) component_id: ComponentId<C::Message>,
+}
+
+impl<C: Component + 'static> ComponentTest<C> {
+    /// Create a test for a component with the given template, state and size of the backend.
+    /// Strings are treated as the template source, not as a path.
+    pub fn new(template: impl ToSourceKind, component: C, state: C::State, size: impl Into<Size>) -> Result<Self> {
+        let mut document = Document::new("@component");
+        document.hot_reload = false;
+
+        let mut builder = Runtime::builder(document, TestBackend::new(size));
+        let component_id = builder.register_component("component", template.to_template(), component, state)?;
+
+        Ok(Self {
+            runtime: builder.finish()?,
+            component_id,
+        })
+    }
+
+    /// Build the component tree and pass a [`TestFrame`] to the closure.
+    /// The initial frame is painted before the closure is called.
+    pub fn run<F>(&mut self, f: F) -> Result<()>
+    where
+        F: FnOnce(&mut TestFrame<'_, '_, '_, C>),
+    {
+        let component_id = self.component_id;
+        self.runtime.with_frame(FRAME_BUDGET.as_micros(), |frame| {
+            f(&mut TestFrame { frame, component_id });
+            Ok(())
+        })
+    }
+}
+
+/// Drives the runtime of a [`ComponentTest`], one frame at a time.
+///
+/// Events and messages are queued and handled on the next [`TestFrame::tick`].
+pub struct TestFrame<'a, 'rt, 'bp, C: Component> {
+    pub(crate) frame: &'a mut Frame<'rt, 'bp, TestBackend, ()>,
+    component_id: ComponentId<C::Message>,
+}
+
+impl<C: Component + 'static> TestFrame<'_, '_, '_, C> {
+    /// Queue an event
+    pub fn event(&mut self, event: Event) -> &mut Self {
+        self.frame.runtime.backend.push_event(event);
+        self
+    }
+
+    /// Queue a key press
+    pub fn press(&mut self, code: KeyCode) -> &mut Self {
+        self.event(Event::Key(KeyEvent::press(code)))
+    }
+
+    /// Queue a key press for every character in the string
+    pub fn type_str(&mut self, s: &str) -> &mut Self {
+        s.chars().for_each(|c| _ = self.press(KeyCode::Char(c)));
+        self
+    }
+
+    /// Queue a key event, e.g a key press with modifiers
+    pub fn key(&mut self, key: KeyEvent) -> &mut Self {
+        self.event(Event::Key(key))
+    }
+
+    /// Queue a mouse event at a position on the screen.
+    /// Consecutive clicks are counted, see [`TestBackend::push_mouse`].
+    pub fn mouse(&mut self, x: u16, y: u16, state: MouseState) -> &mut Self {
+        self.frame.runtime.backend.push_mouse(x, y, state);
+        self
+    }
+
+    /// Queue a left mouse button press and release at a position on the screen
+    pub fn click(&mut self, x: u16, y: u16) -> &mut Self {
+        self.frame.runtime.backend.push_click(x, y);
+        self
+    }
+
+    /// Queue a resize of the terminal
+    pub fn resize(&mut self, width: u16, height: u16) -> &mut Self {
+        self.event(Event::Resize(width, height))
+    }
+
+    /// Queue text pasted into the terminal
+    pub fn paste(&mut self, text: impl Into<String>) -> &mut Self {
+        self.event(Event::Paste(text.into()))
+    }
+
+    /// Queue an input method composition event
+    pub fn ime(&mut self, ime: ImeEvent) -> &mut Self {
+        self.event(Event::Ime(ime))
+    }
+
+    /// Delay the next queued event by the duration, see [`TestBackend::wait`]
+    pub fn wait(&mut self, duration: Duration) -> &mut Self {
+        self.frame.runtime.backend.wait(duration);
+        self
+    }
+
+    /// Queue a message to the component
+    pub fn send(&mut self, message: C::Message) -> &mut Self
+    where
+        C::Message: Send + Sync,
+    {
+        self.frame
+            .runtime
+            .emitter
+            .emit(self.component_id, message)
+            .expect("the receiver is owned by the runtime");
+        self
+    }
+
+    /// Queue a user event
+    pub fn user_event(&mut self, event: UserEvent) -> &mut Self {
+        self.frame
+            .runtime
+            .emitter
+            .emit_user_event(event)
+            .expect("the receiver is owned by the runtime");
+        self
+    }
+
+    /// Handle all queued events and messages, apply changes and paint if needed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the runtime returns an error
+    pub fn tick(&mut self) -> &mut Self {
+        if let Err(err) = self.frame.tick() {
+            panic!("failed to tick the runtime: {err}");
+        }
+        self
+    }
+
+    /// Tick until every queued event has been handled,
+    /// and once more for any messages sent while handling the last event.
+    pub fn tick_all(&mut self) -> &mut Self {
+        while self.frame.runtime.backend.pending_events() > 0 {
+            self.tick();
+        }
+        self.tick()
+    }
+
+    /// The output of the last painted frame
+    pub fn output(&self) -> &str {
+        &self.frame.runtime.backend.output
+    }
+
+    /// The characters and styles of the last painted frame
+    pub fn grid(&self) -> &TestSurface {
+        &self.frame.runtime.backend.grid
+    }
+
+    /// Access the state of the component.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the component is not in the tree
+    pub fn with_state<F, U>(&mut self, f: F) -> U
+    where
+        F: FnOnce(&mut C::State) -> U,
+    {
+        let state_id = self
+            .frame
+            .runtime
+            .components
+            .get_by_component_id(WidgetComponentId::from(self.component_id))
+            .map(|entry| entry.state_id)
+            .expect("the component is not in the tree");
+
+        let state = self
+            .frame
+            .states
+            .get_mut(state_id)
+            .and_then(|state| state.to_any_mut().downcast_mut::<C::State>())
+            .expect("components always have a state");
+
+        f(state)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use anathema_backend::assert_frame_eq;
+    use anathema_backend::tui::Attributes;
+    use anathema_state::{Color, List, Map, State, Value};
+    use anathema_widgets::components::events::{ClickCounter, KeyEvent, MouseEvent, Preedit};
+    use anathema_widgets::components::{Context, Shutdown};
+    use anathema_widgets::Elements;
+
+    use super::*;
+    use crate::{BuildContext, FrameStats, PluginContext, RuntimePlugin};
+
+    // The components and plugins below are shared with the tests of the other modules
+    pub(crate) struct Counter;
+
+    impl Component for Counter {
+        type Message = usize;
+        type State = Value<Map<usize>>;
+
+        fn on_key(&mut self, _: KeyEvent, state: &mut Self::State, _: Elements<'_, '_>, _: Context<'_, Self::State>) {
+            add(state, 1);
+        }
+
+        fn message(&mut self, value: usize, state: &mut Self::State, _: Elements<'_, '_>, _: Context<'_, Self::State>) {
+            add(state, value);
+        }
+
+        fn on_paste(&mut self, text: &str, state: &mut Self::State, _: Elements<'_, '_>, _: Context<'_, Self::State>) {
+            add(state, text.len());
+        }
+
+        fn on_mouse(
+            &mut self,
+            mouse: MouseEvent,
+            state: &mut Self::State,
+            _: Elements<'_, '_>,
+            _: Context<'_, Self::State>,
+        ) {
+            if mouse.is_double_click() {
+                add(state, 10);
+            }
+        }
+
+        fn on_ime(
+            &mut self,
+            ime: &ImeEvent,
+            state: &mut Self::State,
+            _: Elements<'_, '_>,
+            _: Context<'_, Self::State>,
+        ) {
+            if let Some(text) = ime.commit() {
+                add(state, text.chars().count());
+            }
+        }
+
+        fn on_terminal_focus_gained(
+            &mut self,
+            state: &mut Self::State,
+            _: Elements<'_, '_>,
+            _: Context<'_, Self::State>,
+        ) {
+            add(state, 1000);
+        }
+
+        fn on_terminal_focus_lost(
+            &mut self,
+            state: &mut Self::State,
+            _: Elements<'_, '_>,
+            _: Context<'_, Self::State>,
+        ) {
+            add(state, 100);
+        }
+
+        fn on_user_event(
+            &mut self,
+            event: &UserEvent,
+            state: &mut Self::State,
+            _: Elements<'_, '_>,
+            _: Context<'_, Self::State>,
+        ) {
+            if let Some(&value) = event.get::<usize>() {
+                add(state, value);
+            }
+        }
+    }
+
+    // Navigates between screens
+    pub(crate) struct Nav;
+
+    impl Component for Nav {
+        type Message = ();
+        type State = Value<Map<usize>>;
+
+        fn on_key(
+            &mut self,
+            key: KeyEvent,
+            state: &mut Self::State,
+            _: Elements<'_, '_>,
+            mut context: Context<'_, Self::State>,
+        ) {
+            match key.code {
+                KeyCode::Char('n') => context.push_screen("second"),
+                KeyCode::Char('p') => context.pop_screen(),
+                KeyCode::Char('q') => context.shutdown(Shutdown::new(2).with_reason("done")),
+                KeyCode::Char('t') => context.set_title(format!("count: {}", count(state))),
+                KeyCode::Char('b') => context.bell(),
+                _ => add(state, 1),
+            }
+        }
+    }
+
+    pub(crate) fn add(state: &mut Value<Map<usize>>, value: usize) {
+        let mut map = state.to_mut();
+        let count = map.get_mut("count").expect("the count is set by the test");
+        let value = count.copy_value() + value;
+        count.set(value);
+    }
+
+    pub(crate) fn count(state: &mut Value<Map<usize>>) -> usize {
+        state.to_ref().get("count").map(|count| count.copy_value()).unwrap()
+    }
+
+    // Counts frames and consumes the `x` key
+    pub(crate) struct Frames(pub(crate) Rc<Cell<usize>>);
+
+    impl RuntimePlugin for Frames {
+        fn on_build(&mut self, ctx: &mut BuildContext<'_>) {
+            ctx.services.insert("frames");
+        }
+
+        fn after_frame(&mut self, _: &FrameStats, _: &PluginContext<'_>) {
+            self.0.set(self.0.get() + 1);
+        }
+
+        fn on_event(&mut self, event: Event, _: &PluginContext<'_>) -> Option<Event> {
+            match event {
+                Event::Key(key) if key.code == KeyCode::Char('x') => None,
+                event => Some(event),
+            }
+        }
+    }
+
+    #[test]
+    fn drive_component() {
+        let mut state = Map::empty();
+        state.insert("count", 0);
+
+        let mut test = ComponentTest::new("text count", Counter, state, (5, 1)).unwrap();
+        test.run(|frame| {
+            assert_eq!(frame.output(), "0    \n");
+
+            frame.press(KeyCode::Char('a')).type_str("bc").tick();
+            assert_eq!(frame.with_state(count), 3);
+            assert_eq!(frame.output(), "3    \n");
+
+            frame.send(10).tick();
+            assert_eq!(frame.with_state(count), 13);
+            assert_eq!(frame.output(), "13   \n");
+
+            frame.event(Event::Paste("a\nb".into())).tick();
+            assert_eq!(frame.with_state(count), 16);
+
+            frame.ime(ImeEvent::Preedit(Preedit::new("ni").with_cursor(2))).tick();
+            assert_eq!(frame.with_state(count), 16);
+            frame.ime(ImeEvent::Commit("你".into())).tick();
+            assert_eq!(frame.with_state(count), 17);
+
+            frame.with_state(|state: &mut Value<Map<usize>>| add(state, 3));
+            frame.tick();
+            assert_eq!(frame.output(), "20   \n");
+        })
+        .unwrap();
+    }
+
+    #[derive(State)]
+    pub(crate) struct Labelled<T> {
+        pub(crate) label: Value<String>,
+        pub(crate) items: Value<List<T>>,
+    }
+
+    struct Viewer<T>(std::marker::PhantomData<T>);
+
+    impl<T: State> Component for Viewer<T> {
+        type Message = ();
+        type State = Labelled<T>;
+    }
+
+    #[test]
+    fn generic_state() {
+        let state = Labelled {
+            label: String::from("nums").into(),
+            items: List::from_iter([1, 2]),
+        };
+        let tpl = "vstack\n    text label\n    for item in items\n        text item";
+
+        let mut test = ComponentTest::new(tpl, Viewer::<usize>(std::marker::PhantomData), state, (4, 3)).unwrap();
+        test.run(|frame| {
+            assert_eq!(frame.output(), "nums\n1   \n2   \n");
+
+            frame.with_state(|state: &mut Labelled<usize>| state.label.set("ab".into()));
+            frame.tick();
+            assert_eq!(frame.output(), "ab  \n1   \n2   \n");
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn scripted_interaction() {
+        let mut state = Map::empty();
+        state.insert("count", 0);
+
+        let mut test = ComponentTest::new("text count", Counter, state, (5, 1)).unwrap();
+        test.run(|frame| {
+            // Two clicks with enough time between them are not a double click
+            frame
+                .click(0, 0)
+                .wait(ClickCounter::DEFAULT_INTERVAL + Duration::from_millis(50))
+                .click(0, 0)
+                .tick_all();
+            assert_eq!(frame.output(), "0    \n");
+
+            // Two clicks in a row are
+            frame.click(1, 0).click(1, 0).tick_all();
+            assert_eq!(frame.output(), "10   \n");
+
+            frame
+                .key(KeyEvent::press(KeyCode::Char('s')).with_ctrl())
+                .paste("abc")
+                .resize(3, 1)
+                .tick_all();
+            assert_eq!(frame.output(), "14 \n");
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn isolated_runtimes() {
+        let counter = || {
+            let mut state = Map::empty();
+            state.insert("count", 0);
+            ComponentTest::new("text count", Counter, state, (5, 1)).unwrap()
+        };
+
+        let mut a = counter();
+        let mut b = counter();
+
+        a.run(|frame| _ = frame.press(KeyCode::Char('a')).tick()).unwrap();
+        b.run(|frame| _ = frame.type_str("bc").tick()).unwrap();
+
+        a.run(|frame| assert_eq!(frame.with_state(count), 1)).unwrap();
+        b.run(|frame| assert_eq!(frame.with_state(count), 2)).unwrap();
+
+        drop(a);
+        b.run(|frame| assert_eq!(frame.output(), "2    \n")).unwrap();
+    }
+
+    #[test]
+    fn styled_grid() {
+        let template = "hstack\n    text [foreground: 'red', bold: true] 'a'\n    text 'b'";
+        let mut test = ComponentTest::new(template, (), (), (3, 1)).unwrap();
+        test.run(|frame| {
+            let grid = frame.grid();
+            assert_eq!(grid.to_string(), "ab \n");
+
+            let a = grid.get(0, 0).unwrap();
+            assert_eq!(a.style.fg, Some(Color::Red));
+            assert!(a.style.attributes.contains(Attributes::BOLD));
+
+            let b = grid.get(1, 0).unwrap();
+            assert_eq!(b.c, 'b');
+            assert_eq!(b.style.fg, None);
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn styled_underline() {
+        let template = "text [underline: 'curly', underline_color: 'red'] 'a'";
+        let mut test = ComponentTest::new(template, (), (), (1, 1)).unwrap();
+        test.run(|frame| {
+            let a = frame.grid().get(0, 0).unwrap();
+            assert!(a.style.attributes.contains(Attributes::CURLY_UNDERLINED));
+            assert_eq!(a.style.underline_color, Some(Color::Red));
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn styled_frame() {
+        let template = "
+vstack
+    text [bold: true] 'hello'
+    text [foreground: 'red'] '  world'
+";
+        let mut test = ComponentTest::new(template, (), (), (7, 3)).unwrap();
+        test.run(|frame| {
+            assert_frame_eq!(
+                frame.grid(),
+                "
+hello
+  world
+-- styles --
+0: 0..5 bold
+1: 0..7 fg:Red
+"
+            );
+        })
+        .unwrap();
+    }
+}
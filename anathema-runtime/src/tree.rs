@@ -39,11 +39,15 @@ impl<'bp> Tree<'bp> for WidgetTree<'bp> {
             let state = event_ctx.states.get_mut(state_id);
 
             let component_ctx = ComponentContext::new(
+                component.component_id,
                 state_id,
                 component.parent,
                 component.assoc_functions,
                 event_ctx.assoc_events,
                 event_ctx.focus_queue,
+                event_ctx.timers,
+                event_ctx.mounts,
+                event_ctx.keymap,
                 component.external_state.as_ref(),
             );
 
@@ -1,6 +1,6 @@
 use manyhow::{ensure, manyhow, Result};
 use quote_use::quote_use as quote;
-use syn::{self, Data, DeriveInput, Fields};
+use syn::{self, parse_quote, Data, DeriveInput, Fields};
 
 static STATE_IGNORE: &str = "state_ignore";
 
@@ -28,10 +28,17 @@ pub fn state_derive(input: DeriveInput) -> Result {
         .map(|f| (f, f.to_string()))
         .unzip();
 
+    // Generic parameters have to be `State` as well, e.g `Value<T>`
+    let mut generics = input.generics.clone();
+    for param in generics.type_params_mut() {
+        param.bounds.push(parse_quote!(::anathema::state::State));
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
     Ok(quote! {
         # use ::anathema::state::{self, Value, ValueRef, PendingValue, Path, state, Subscriber, CommonVal};
         # use ::std::any::Any;
-        impl state::State for #name {
+        impl #impl_generics state::State for #name #ty_generics #where_clause {
             fn state_get(&self, path: Path<'_>, sub: Subscriber) -> Option<ValueRef> {
                 let Path::Key(key) = path else { return None };
                 match key {
@@ -59,6 +66,10 @@ pub fn state_derive(input: DeriveInput) -> Result {
             fn to_common(&self) -> Option<CommonVal<'_>> {
                 None
             }
+
+            fn state_keys(&self) -> &'static [&'static str] {
+                &[#(#field_names),*]
+            }
         }
     })
 }
@@ -256,6 +256,28 @@ impl_try_from_int!(i8);
 impl_try_from_float!(f64);
 impl_try_from_float!(f32);
 
+impl TryFrom<CommonVal<'_>> for char {
+    type Error = ();
+
+    fn try_from(value: CommonVal<'_>) -> Result<Self, Self::Error> {
+        match value {
+            CommonVal::Char(c) => Ok(c),
+            _ => Err(()),
+        }
+    }
+}
+
+impl TryFrom<CommonVal<'_>> for String {
+    type Error = ();
+
+    fn try_from(value: CommonVal<'_>) -> Result<Self, Self::Error> {
+        match value {
+            CommonVal::Str(s) => Ok(s.to_string()),
+            _ => Err(()),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use std::rc::Rc;
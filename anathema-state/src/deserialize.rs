@@ -0,0 +1,206 @@
+// Deserialize arbitrary data (e.g JSON or TOML) into state.
+//
+// Objects become a `Map`, arrays become a `List` and
+// everything else becomes the closest primitive.
+use std::fmt;
+
+use serde::de::{Deserialize, Deserializer, Error, MapAccess, SeqAccess, Visitor};
+
+use crate::{List, Map, State, Value};
+
+// TOML dates and times are deserialized as a map with a single entry
+const TOML_DATETIME: &str = "$__toml_private_datetime";
+
+struct StateVisitor;
+
+impl<'de> Visitor<'de> for StateVisitor {
+    type Value = Box<dyn State>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("any value")
+    }
+
+    fn visit_bool<E: Error>(self, value: bool) -> Result<Self::Value, E> {
+        Ok(Box::new(value))
+    }
+
+    fn visit_i64<E: Error>(self, value: i64) -> Result<Self::Value, E> {
+        Ok(Box::new(value))
+    }
+
+    fn visit_u64<E: Error>(self, value: u64) -> Result<Self::Value, E> {
+        match i64::try_from(value) {
+            Ok(value) => Ok(Box::new(value)),
+            Err(_) => Ok(Box::new(value as f64)),
+        }
+    }
+
+    fn visit_f64<E: Error>(self, value: f64) -> Result<Self::Value, E> {
+        Ok(Box::new(value))
+    }
+
+    fn visit_char<E: Error>(self, value: char) -> Result<Self::Value, E> {
+        Ok(Box::new(value))
+    }
+
+    fn visit_str<E: Error>(self, value: &str) -> Result<Self::Value, E> {
+        Ok(Box::new(value.to_string()))
+    }
+
+    fn visit_string<E: Error>(self, value: String) -> Result<Self::Value, E> {
+        Ok(Box::new(value))
+    }
+
+    fn visit_none<E: Error>(self) -> Result<Self::Value, E> {
+        Ok(Box::new(None::<bool>))
+    }
+
+    fn visit_unit<E: Error>(self) -> Result<Self::Value, E> {
+        self.visit_none()
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+        Ok(Box::new(ListVisitor.visit_seq(seq)?))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut output = Map::empty();
+
+        while let Some(key) = map.next_key::<String>()? {
+            if key == TOML_DATETIME {
+                return Ok(Box::new(map.next_value::<String>()?));
+            }
+            output.insert(key, map.next_value::<Box<dyn State>>()?);
+        }
+
+        Ok(Box::new(output))
+    }
+}
+
+struct ListVisitor;
+
+impl<'de> Visitor<'de> for ListVisitor {
+    type Value = Value<List<Box<dyn State>>>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a list")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut list = List::empty();
+        while let Some(value) = seq.next_element::<Box<dyn State>>()? {
+            list.push(value);
+        }
+        Ok(list)
+    }
+}
+
+struct MapVisitor;
+
+impl<'de> Visitor<'de> for MapVisitor {
+    type Value = Value<Map<Box<dyn State>>>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a map")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut output = Map::empty();
+        while let Some((key, value)) = map.next_entry::<String, Box<dyn State>>()? {
+            output.insert(key, value);
+        }
+        Ok(output)
+    }
+}
+
+/// Any value, e.g `serde_json::from_str::<Box<dyn State>>(src)`.
+/// Objects become a [`Map`], arrays become a [`List`] and `null` becomes `None`.
+impl<'de> Deserialize<'de> for Box<dyn State> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(StateVisitor)
+    }
+}
+
+/// A map of arbitrary values, to use as the state of a component.
+///
+/// ```
+/// # use anathema_state::{Map, State, Value};
+/// let state: Value<Map<Box<dyn State>>> =
+///     serde_json::from_str(r#"{"title": "dashboard", "cpus": [12, 40]}"#).unwrap();
+/// ```
+impl<'de> Deserialize<'de> for Value<Map<Box<dyn State>>> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(MapVisitor)
+    }
+}
+
+/// A list of arbitrary values
+impl<'de> Deserialize<'de> for Value<List<Box<dyn State>>> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_seq(ListVisitor)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{AnyState, Path};
+
+    fn lookup(state: &dyn AnyState, path: &[Path<'_>]) -> Option<String> {
+        let (first, rest) = path.split_first()?;
+        let value = state.state_lookup(*first)?;
+        value.as_state(|state| match rest.is_empty() {
+            true => state.to_common().map(|value| value.to_string()),
+            false => lookup(state, rest),
+        })
+    }
+
+    #[test]
+    fn json() {
+        let src = r#"{"name": "cpu", "load": [0.5, 12], "host": {"up": true, "note": null}}"#;
+        let state: Value<Map<Box<dyn State>>> = serde_json::from_str(src).unwrap();
+
+        let lookup = |path: &[Path<'_>]| lookup(&state, path);
+        assert_eq!(lookup(&["name".into()]).as_deref(), Some("cpu"));
+        assert_eq!(lookup(&["load".into(), Path::Index(0)]).as_deref(), Some("0.5000"));
+        assert_eq!(lookup(&["load".into(), Path::Index(1)]).as_deref(), Some("12"));
+        assert_eq!(lookup(&["host".into(), "up".into()]).as_deref(), Some("true"));
+        assert_eq!(lookup(&["host".into(), "note".into()]), None);
+    }
+
+    #[test]
+    fn toml() {
+        let src = "
+            title = 'servers'
+            updated = 2024-01-02T03:04:05Z
+
+            [[servers]]
+            name = 'a'
+            ports = [80, 443]
+        ";
+        let state: Value<Map<Box<dyn State>>> = toml::from_str(src).unwrap();
+
+        let lookup = |path: &[Path<'_>]| lookup(&state, path);
+        assert_eq!(lookup(&["title".into()]).as_deref(), Some("servers"));
+        assert_eq!(lookup(&["updated".into()]).as_deref(), Some("2024-01-02T03:04:05Z"));
+        assert_eq!(
+            lookup(&["servers".into(), Path::Index(0), "name".into()]).as_deref(),
+            Some("a")
+        );
+        assert_eq!(
+            lookup(&["servers".into(), Path::Index(0), "ports".into(), Path::Index(1)]).as_deref(),
+            Some("443")
+        );
+    }
+
+    #[test]
+    fn not_a_map() {
+        assert!(serde_json::from_str::<Value<Map<Box<dyn State>>>>("[1, 2]").is_err());
+        let list: Value<List<Box<dyn State>>> = serde_json::from_str("[1, 2]").unwrap();
+        assert_eq!(list.to_ref().len(), 2);
+    }
+}
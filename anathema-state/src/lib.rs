@@ -7,12 +7,14 @@ pub use crate::numbers::Number;
 pub use crate::states::{AnyState, State, StateId, States};
 pub use crate::store::{
     clear_all_changes, clear_all_futures, clear_all_subs, debug, drain_changes, drain_futures, register_future, Change,
-    Changes, FutureValues, Subscriber,
+    Changes, EnteredStore, FutureValues, StateStore, Subscriber,
 };
 pub use crate::value::{List, Map, PendingValue, SharedState, Value, ValueRef};
 
 mod colors;
 mod common;
+#[cfg(feature = "serde")]
+mod deserialize;
 mod numbers;
 mod states;
 mod store;
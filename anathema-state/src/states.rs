@@ -1,5 +1,6 @@
 use std::any::Any;
 use std::fmt::Debug;
+use std::panic::{self, AssertUnwindSafe};
 use std::rc::Rc;
 
 use anathema_store::slab::Slab;
@@ -41,6 +42,8 @@ pub trait AnyState: 'static {
     fn to_bool(&self) -> bool;
 
     fn count(&self) -> usize;
+
+    fn state_keys(&self) -> &'static [&'static str];
 }
 
 impl AnyState for Box<dyn AnyState> {
@@ -75,6 +78,10 @@ impl AnyState for Box<dyn AnyState> {
     fn count(&self) -> usize {
         self.as_ref().count()
     }
+
+    fn state_keys(&self) -> &'static [&'static str] {
+        self.as_ref().state_keys()
+    }
 }
 
 impl<T: State> AnyState for T {
@@ -109,6 +116,10 @@ impl<T: State> AnyState for T {
     fn count(&self) -> usize {
         <Self as State>::count(self)
     }
+
+    fn state_keys(&self) -> &'static [&'static str] {
+        <Self as State>::state_keys(self)
+    }
 }
 
 pub trait State: 'static {
@@ -141,6 +152,13 @@ pub trait State: 'static {
     }
 
     fn to_common(&self) -> Option<CommonVal<'_>>;
+
+    /// The names of the values that can be looked up with [`State::state_lookup`],
+    /// e.g to list the fields of a state in an inspector.
+    /// Derived states return the names of their fields.
+    fn state_keys(&self) -> &'static [&'static str] {
+        &[]
+    }
 }
 
 impl State for Box<dyn State> {
@@ -167,6 +185,10 @@ impl State for Box<dyn State> {
     fn count(&self) -> usize {
         self.as_ref().count()
     }
+
+    fn state_keys(&self) -> &'static [&'static str] {
+        self.as_ref().state_keys()
+    }
 }
 
 impl<T: 'static + State> State for Value<T> {
@@ -193,6 +215,10 @@ impl<T: 'static + State> State for Value<T> {
     fn count(&self) -> usize {
         self.to_ref().count()
     }
+
+    fn state_keys(&self) -> &'static [&'static str] {
+        self.to_ref().state_keys()
+    }
 }
 
 impl Debug for dyn State {
@@ -358,9 +384,9 @@ impl States {
         F: FnOnce(&mut dyn AnyState, &mut Self) -> U,
     {
         let mut ticket = self.inner.checkout(index.into());
-        let ret = f(&mut *ticket, self);
+        let ret = panic::catch_unwind(AssertUnwindSafe(|| f(&mut *ticket, self)));
         self.inner.restore(ticket);
-        ret
+        ret.unwrap_or_else(|payload| panic::resume_unwind(payload))
     }
 
     /// Remove and return a given state.
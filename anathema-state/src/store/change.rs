@@ -1,7 +1,7 @@
 use anathema_store::stack::Stack;
 
 use super::subscriber::{SubKey, Subscribers};
-use super::{CHANGES, SUBSCRIBERS};
+use super::{CHANGES, LATEST_CHANGES, SUBSCRIBERS};
 use crate::PendingValue;
 
 pub type Changes = Stack<(Subscribers, Change)>;
@@ -16,20 +16,75 @@ pub enum Change {
 
 /// Drain the current changes into a local value.
 pub fn drain_changes(local_changes: &mut Changes) {
+    LATEST_CHANGES.with_borrow_mut(|latest| latest.clear());
     CHANGES.with_borrow_mut(|changes| changes.drain_into(local_changes));
 }
 
 /// Clear all changes
 pub fn clear_all_changes() {
+    LATEST_CHANGES.with_borrow_mut(|latest| latest.clear());
     CHANGES.with_borrow_mut(|changes| changes.clear());
 }
 
+// The most recent changes to a single value, since the changes were drained
+#[derive(Debug, Default)]
+pub(crate) struct Latest {
+    changed: Option<usize>,
+    inserted: Option<usize>,
+}
+
 pub(crate) fn changed(subkey: SubKey, change: Change) {
     let subscribers = SUBSCRIBERS.with_borrow(|subs| subs.get(subkey));
     if subscribers.is_empty() {
         return;
     }
     CHANGES.with_borrow_mut(|changes| {
-        changes.push((subscribers, change));
+        LATEST_CHANGES.with_borrow_mut(|latest| {
+            let latest = latest.entry(subkey).or_default();
+
+            match change {
+                // The value changed again, but the subscribers only have to be notified once.
+                // The subscribers are replaced, as they could have changed since.
+                Change::Changed => match latest.changed {
+                    Some(index) if changes.get(index).is_some() => _ = changes.swap(index, (subscribers, change)),
+                    _ => {
+                        latest.changed = Some(changes.next_index());
+                        changes.push((subscribers, change));
+                    }
+                },
+                // The value that was just inserted was removed again.
+                // The value could have changed after the insert, which is kept
+                Change::Removed(removed)
+                    if latest.inserted.is_some_and(|index| {
+                        let queued = match latest.changed {
+                            Some(changed) if changed == index + 1 => 2,
+                            _ => 1,
+                        };
+                        index + queued == changes.len()
+                            && matches!(changes.get(index), Some((_, Change::Inserted(inserted, _))) if *inserted == removed)
+                    }) =>
+                {
+                    let changed = latest.changed.and_then(|_| changes.pop());
+                    _ = changes.pop();
+                    latest.inserted = None;
+                    if let Some(changed) = changed {
+                        latest.changed = Some(changes.next_index());
+                        changes.push(changed);
+                    }
+                }
+                // A later change can't be coalesced with a change queued before this one,
+                // as it would be applied ahead of it
+                Change::Inserted(..) => {
+                    latest.changed = None;
+                    latest.inserted = Some(changes.next_index());
+                    changes.push((subscribers, change));
+                }
+                _ => {
+                    latest.changed = None;
+                    latest.inserted = None;
+                    changes.push((subscribers, change));
+                }
+            }
+        })
     });
 }
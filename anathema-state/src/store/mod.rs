@@ -1,24 +1,31 @@
 use std::cell::RefCell;
+use std::collections::BTreeMap;
 
 use anathema_store::stack::Stack;
 use anathema_store::store::{Owned, OwnedKey, Shared};
 
-pub(crate) use self::change::changed;
+pub(crate) use self::change::{changed, Latest};
 pub use self::change::{clear_all_changes, drain_changes, Change, Changes};
+pub use self::state_store::{EnteredStore, StateStore};
 pub use self::subscriber::{FutureValues, Subscriber};
 use self::subscriber::{SubKey, SubscriberMap};
 use crate::states::AnyState;
 
 mod change;
 pub mod debug;
+mod state_store;
 pub(crate) mod subscriber;
 pub(crate) mod values;
 
+// The indices in `CHANGES` of the most recent changes to each value
+pub(crate) type LatestChanges = BTreeMap<SubKey, Latest>;
+
 thread_local! {
     static OWNED: Owned<Box<dyn AnyState>> = const { Owned::empty() };
     static SHARED: Shared<Box<dyn AnyState>> = const { Shared::empty() };
     static SUBSCRIBERS: RefCell<SubscriberMap> = const { RefCell::new(SubscriberMap::empty()) };
     static CHANGES: RefCell<Changes> = const { RefCell::new(Stack::empty()) };
+    static LATEST_CHANGES: RefCell<LatestChanges> = const { RefCell::new(BTreeMap::new()) };
     static FUTURE_VALUES: RefCell<FutureValues> = const { RefCell::new(Stack::empty()) };
 }
 
@@ -76,6 +83,7 @@ pub(crate) mod testing {
             });
             changes.clear();
         });
+        super::LATEST_CHANGES.with_borrow_mut(|latest| latest.clear());
 
         ret
     }
@@ -0,0 +1,244 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use anathema_store::stack::Stack;
+use anathema_store::store::{Owned, Shared};
+
+use super::subscriber::SubscriberMap;
+use super::{Changes, FutureValues, LatestChanges, CHANGES, FUTURE_VALUES, LATEST_CHANGES, OWNED, SHARED, SUBSCRIBERS};
+use crate::states::AnyState;
+
+/// The values, subscribers and changes of a runtime.
+///
+/// Values are stored per thread. Detaching the values of the thread
+/// into a `StateStore` keeps them apart from any values created afterwards,
+/// which makes it possible to have multiple runtimes on the same thread.
+///
+/// The values of a store are only accessible while the store is entered.
+///
+/// ```
+/// # use anathema_state::{StateStore, Value};
+/// let a = Value::new(1);
+/// let store = StateStore::detach();
+///
+/// // The thread has a new, empty store
+/// let b = Value::new(2);
+///
+/// let entered = store.enter();
+/// assert_eq!(a.copy_value(), 1);
+/// drop(a);
+/// drop(entered);
+///
+/// assert_eq!(b.copy_value(), 2);
+/// ```
+pub struct StateStore(Rc<Values>);
+
+// The values of a store. These are in the thread locals while the store is current,
+// and in the store otherwise.
+struct Values {
+    owned: Owned<Box<dyn AnyState>>,
+    shared: Shared<Box<dyn AnyState>>,
+    subscribers: RefCell<SubscriberMap>,
+    changes: RefCell<Changes>,
+    latest_changes: RefCell<LatestChanges>,
+    future_values: RefCell<FutureValues>,
+}
+
+impl Values {
+    const fn empty() -> Self {
+        Self {
+            owned: Owned::empty(),
+            shared: Shared::empty(),
+            subscribers: RefCell::new(SubscriberMap::empty()),
+            changes: RefCell::new(Stack::empty()),
+            latest_changes: RefCell::new(LatestChanges::new()),
+            future_values: RefCell::new(Stack::empty()),
+        }
+    }
+
+    // Swap the values with the thread locals
+    fn swap(&self) {
+        OWNED.with(|owned| owned.swap(&self.owned));
+        SHARED.with(|shared| shared.swap(&self.shared));
+        SUBSCRIBERS.with(|subscribers| subscribers.swap(&self.subscribers));
+        CHANGES.with(|changes| changes.swap(&self.changes));
+        LATEST_CHANGES.with(|latest| latest.swap(&self.latest_changes));
+        FUTURE_VALUES.with(|futures| futures.swap(&self.future_values));
+    }
+}
+
+thread_local! {
+    // The values of the thread while a store is entered
+    static THREAD: Rc<Values> = Rc::new(Values::empty());
+    // The entered stores, by guard id. The last store is the current store.
+    static ENTERED: RefCell<Vec<(usize, Rc<Values>)>> = const { RefCell::new(vec![]) };
+    static NEXT_GUARD_ID: Cell<usize> = const { Cell::new(0) };
+}
+
+// Make `to` the current store instead of `from`
+fn switch(from: &Values, to: &Values) {
+    if std::ptr::eq(from, to) {
+        return;
+    }
+    from.swap();
+    to.swap();
+}
+
+fn current(entered: &[(usize, Rc<Values>)]) -> Rc<Values> {
+    match entered.last() {
+        Some((_, values)) => values.clone(),
+        None => THREAD.with(Rc::clone),
+    }
+}
+
+impl StateStore {
+    /// Create an empty store
+    pub fn empty() -> Self {
+        Self(Rc::new(Values::empty()))
+    }
+
+    /// Move all values of the current store of the thread into a new store,
+    /// leaving the current store empty.
+    pub fn detach() -> Self {
+        let store = Self::empty();
+        store.0.swap();
+        store
+    }
+
+    /// Make this the store of the current thread, until the guard is dropped.
+    ///
+    /// Entering a store that is already entered makes it the current store again,
+    /// and dropping the guard switches back to the store that was current before.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any value of the current store is borrowed.
+    pub fn enter(&self) -> EnteredStore<'_> {
+        let id = NEXT_GUARD_ID.with(|id| id.replace(id.get() + 1));
+        ENTERED.with_borrow_mut(|entered| {
+            switch(&current(entered), &self.0);
+            entered.push((id, self.0.clone()));
+        });
+        EnteredStore { _store: self, id }
+    }
+}
+
+impl Drop for StateStore {
+    fn drop(&mut self) {
+        // A store that is still entered (the guard was forgotten) keeps its values
+        if Rc::strong_count(&self.0) > 1 {
+            return;
+        }
+
+        // Dropping a value removes it, and any values it holds, from the current store,
+        // so the values are dropped with this store entered
+        let _entered = self.enter();
+        SHARED.with(|shared| shared.swap(&Shared::empty()));
+        let keys = OWNED.with(|owned| owned.occupied_keys());
+        for key in keys {
+            let value = OWNED.with(|owned| owned.try_remove(key));
+            drop(value);
+        }
+    }
+}
+
+/// Guard returned from [`StateStore::enter`].
+/// Restores the previous store of the thread when dropped.
+///
+/// Guards can be dropped in any order: dropping a guard of a store
+/// that is not the current store leaves the current store in place.
+pub struct EnteredStore<'a> {
+    _store: &'a StateStore,
+    id: usize,
+}
+
+impl Drop for EnteredStore<'_> {
+    fn drop(&mut self) {
+        ENTERED.with_borrow_mut(|entered| {
+            let Some(index) = entered.iter().position(|(id, _)| *id == self.id) else { return };
+            let (_, values) = entered.remove(index);
+            if index == entered.len() {
+                switch(&values, &current(entered));
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{List, Value};
+
+    #[test]
+    fn nested_stores() {
+        let a = Value::new(1usize);
+        let store_a = StateStore::detach();
+        let b = Value::new(2usize);
+        let store_b = StateStore::detach();
+
+        {
+            let _a = store_a.enter();
+            assert_eq!(a.copy_value(), 1);
+
+            {
+                let _b = store_b.enter();
+                assert_eq!(b.copy_value(), 2);
+
+                // Entering an entered store switches back to it
+                let again = store_a.enter();
+                assert_eq!(a.copy_value(), 1);
+                drop(again);
+
+                assert_eq!(b.copy_value(), 2);
+            }
+
+            assert_eq!(a.copy_value(), 1);
+            drop(a);
+        }
+
+        let _b = store_b.enter();
+        drop(b);
+    }
+
+    #[test]
+    fn guards_dropped_out_of_order() {
+        let a = Value::new(1usize);
+        let store_a = StateStore::detach();
+        let b = Value::new(2usize);
+        let store_b = StateStore::detach();
+        let c = Value::new(3usize);
+
+        let entered_a = store_a.enter();
+        let entered_b = store_b.enter();
+
+        // Store b is still the current store
+        drop(entered_a);
+        assert_eq!(b.copy_value(), 2);
+
+        // Back to the values of the thread
+        drop(entered_b);
+        assert_eq!(c.copy_value(), 3);
+
+        let _a = store_a.enter();
+        drop(a);
+        let _b = store_b.enter();
+        drop(b);
+    }
+
+    #[test]
+    fn drop_store_with_values() {
+        let list = List::<usize>::from_iter([1, 2]);
+        let store = StateStore::detach();
+        // The values of the list are only dropped along with the store
+        std::mem::forget(list);
+
+        // The same keys as the values in the store
+        let values = [Value::new(3usize), Value::new(4), Value::new(5)];
+
+        // The values of the list are removed from the dropped store,
+        // not from the current store
+        drop(store);
+        OWNED.with(|owned| assert_eq!(owned.count_occupied(), 3));
+        assert_eq!(values[2].copy_value(), 5);
+    }
+}
@@ -34,7 +34,7 @@ impl KeyIndex {
 }
 
 // The key associated with the value that is being subscribed to.
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub(crate) struct SubKey(u32);
 
 impl From<SubKey> for usize {
@@ -252,6 +252,7 @@ mod test {
     }
 
     fn drain_changes() -> Vec<(Subscribers, Change)> {
+        crate::store::LATEST_CHANGES.with_borrow_mut(|latest| latest.clear());
         crate::store::CHANGES.with_borrow_mut(|changes| changes.drain().collect())
     }
 
@@ -103,7 +103,8 @@ pub(crate) fn return_shared(key: SharedKey) {
 
 // Remove a value and it's associated subscribers
 pub(crate) fn drop_value(key: ValueKey) {
-    let _ = OWNED.with(|owned| owned.remove(key.0));
+    // The value is already removed if it belonged to a store that was dropped
+    let _ = OWNED.with(|owned| owned.try_remove(key.0));
     let _ = SUBSCRIBERS.with_borrow_mut(|subscribers| subscribers.remove(key.1));
 }
 
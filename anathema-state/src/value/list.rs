@@ -227,6 +227,51 @@ mod test {
         assert!(matches!(change, Change::Inserted(_, _)));
     }
 
+    #[test]
+    fn coalesce_insert_remove() {
+        let mut map = setup_map("a", 1, 2);
+
+        let mut list = map.to_mut();
+        let list = list.get_mut("a").unwrap();
+        let _vr = list.value_ref(Subscriber::ZERO);
+        list.push_back(3);
+        list.pop_back();
+
+        let changes = drain_changes();
+        assert!(!changes
+            .iter()
+            .any(|(_, change)| matches!(change, Change::Inserted(..) | Change::Removed(_))));
+    }
+
+    #[test]
+    fn coalesce_changed() {
+        let mut value = Value::new(0usize);
+        let _vr = value.value_ref(Subscriber::ZERO);
+        value.set(1);
+        value.set(2);
+        value.set(3);
+
+        let changes = drain_changes();
+        assert_eq!(changes.len(), 1);
+        assert!(matches!(changes[0], (_, Change::Changed)));
+    }
+
+    #[test]
+    fn changed_after_dropped() {
+        let mut value = Value::new(0usize);
+        let _vr = value.value_ref(Subscriber::ZERO);
+        value.set(1);
+        changed(value.key.sub(), Change::Dropped);
+        value.set(2);
+
+        // The last change is not moved ahead of the drop
+        let changes = drain_changes();
+        assert!(matches!(
+            changes[..],
+            [(_, Change::Changed), (_, Change::Dropped), (_, Change::Changed)]
+        ));
+    }
+
     #[test]
     fn notify_remove() {
         let mut map = setup_map("a", 1, 2);
@@ -1,4 +1,4 @@
-use super::Ticket;
+use super::{Error, Ticket};
 
 // -----------------------------------------------------------------------------
 //   - Entry -
@@ -14,7 +14,7 @@ impl<I, T> Entry<I, T> {
     // Insert an Occupied entry in place of a vacant one.
     fn swap(&mut self, value: T) {
         debug_assert!(matches!(self, Entry::Vacant(_)));
-        std::mem::swap(self, &mut Entry::Occupied(value));
+        *self = Entry::Occupied(value);
     }
 
     // Create a new occupied entry
@@ -42,6 +42,7 @@ impl<I, T> Entry<I, T> {
 pub struct Slab<I, T> {
     next_id: Option<I>,
     inner: Vec<Entry<I, T>>,
+    len: usize,
 }
 
 impl<I, T> Slab<I, T>
@@ -56,9 +57,36 @@ where
         Self {
             next_id: None,
             inner: vec![],
+            len: 0,
         }
     }
 
+    /// Reserve capacity, this does not fill the underlying storage
+    /// with vacant entries.
+    pub fn with_capacity(cap: usize) -> Self {
+        Self {
+            next_id: None,
+            inner: Vec::with_capacity(cap),
+            len: 0,
+        }
+    }
+
+    /// The number of values in the slab, including checked out values
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if there are no values in the slab
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The number of entries the slab can hold without reallocating,
+    /// including vacant entries
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
     // If there is a `self.next_id` then `take` the id (making it None)
     // and replace the vacant entry at the given index.
     //
@@ -66,6 +94,7 @@ where
     // finally replace the vacant entry with the occupied value
     /// Insert a value into the slab, returning the index
     pub fn insert(&mut self, value: T) -> I {
+        self.len += 1;
         match self.next_id.take() {
             Some(index) => {
                 let entry = &mut self.inner[index.into()];
@@ -107,6 +136,7 @@ where
                 self.inner.push(entry);
             }
             self.inner.push(Entry::Occupied(value));
+            self.len += 1;
         // If the index is inside the current length:
         } else {
             let entry = self
@@ -116,7 +146,10 @@ where
 
             match entry {
                 Entry::CheckedOut(_) => panic!("value is checked out"),
-                Entry::Vacant(None) => *entry = Entry::Occupied(value),
+                Entry::Vacant(None) => {
+                    *entry = Entry::Occupied(value);
+                    self.len += 1;
+                }
                 Entry::Occupied(val) => *val = value,
                 &mut Entry::Vacant(Some(next_free)) => {
                     // Find the values that points to `index`
@@ -149,6 +182,7 @@ where
 
                     // Insert new value
                     self.inner[idx] = Entry::Occupied(value);
+                    self.len += 1;
                 }
             }
         }
@@ -179,7 +213,10 @@ where
         std::mem::swap(&mut self.inner[index.into()], &mut entry);
 
         match entry {
-            Entry::Occupied(val) => val,
+            Entry::Occupied(val) => {
+                self.len -= 1;
+                val
+            }
             Entry::Vacant(_) | Entry::CheckedOut(_) => panic!("removal of vacant entry"),
         }
     }
@@ -197,6 +234,7 @@ where
                 let mut entry = Entry::Vacant(self.next_id.take());
                 std::mem::swap(old, &mut entry);
                 self.next_id = Some(index);
+                self.len -= 1;
                 let Entry::Occupied(val) = entry else { unreachable!() };
                 Some(val)
             }
@@ -225,6 +263,7 @@ where
                 let mut entry = Entry::Vacant(self.next_id.take());
                 std::mem::swap(old, &mut entry);
                 self.next_id = Some(index);
+                self.len -= 1;
                 let Entry::Occupied(val) = entry else { unreachable!() };
                 Some(val)
             }
@@ -273,6 +312,50 @@ where
         }
     }
 
+    /// Get a reference to a value, or an error describing
+    /// why there is no value at the given index
+    pub fn get_checked(&self, index: I) -> Result<&T, Error> {
+        let idx = index.into();
+        match self.inner.get(idx) {
+            Some(Entry::Occupied(val)) => Ok(val),
+            Some(Entry::Vacant(_)) => Err(Error::Vacant(idx)),
+            Some(Entry::CheckedOut(_)) => Err(Error::CheckedOut(idx)),
+            None => Err(Error::OutOfBounds(idx)),
+        }
+    }
+
+    /// Get a mutable reference to a value, or an error describing
+    /// why there is no value at the given index
+    pub fn get_mut_checked(&mut self, index: I) -> Result<&mut T, Error> {
+        let idx = index.into();
+        match self.inner.get_mut(idx) {
+            Some(Entry::Occupied(val)) => Ok(val),
+            Some(Entry::Vacant(_)) => Err(Error::Vacant(idx)),
+            Some(Entry::CheckedOut(_)) => Err(Error::CheckedOut(idx)),
+            None => Err(Error::OutOfBounds(idx)),
+        }
+    }
+
+    /// Retain only the values where the closure returns true.
+    /// Every other value is removed from the slab.
+    ///
+    /// Checked out values are always retained.
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(I, &mut T) -> bool,
+    {
+        for idx in 0..self.inner.len() {
+            let entry = &mut self.inner[idx];
+            let Entry::Occupied(val) = entry else { continue };
+            if f(idx.into(), val) {
+                continue;
+            }
+            *entry = Entry::Vacant(self.next_id.take());
+            self.next_id = Some(idx.into());
+            self.len -= 1;
+        }
+    }
+
     /// Check out a value from the slab.
     /// The value has to be manually returned using `Self::restore`.
     ///
@@ -343,6 +426,11 @@ where
         })
     }
 
+    /// Iterator over the keys of the occupied entries
+    pub fn keys(&self) -> impl Iterator<Item = I> + '_ {
+        self.iter().map(|(key, _)| key)
+    }
+
     /// Iterator over the keys and elements
     pub fn iter(&self) -> impl Iterator<Item = (I, &T)> + '_ {
         self.inner.iter().enumerate().filter_map(|(i, e)| match e {
@@ -356,6 +444,7 @@ where
     /// but rather drain the underlying storage.
     pub fn consume(&mut self) -> impl Iterator<Item = T> + '_ {
         self.next_id = None;
+        self.len = 0;
         self.inner.drain(..).filter_map(|e| match e {
             Entry::Occupied(val) => Some(val),
             Entry::Vacant(_) | Entry::CheckedOut(_) => None,
@@ -470,4 +559,38 @@ mod test {
         assert_eq!(Some(2), slab.next_id);
         assert_eq!(Entry::Vacant(Some(0)), slab.inner[2]);
     }
+
+    #[test]
+    fn len_and_retain() {
+        let mut slab = Slab::<usize, _>::empty();
+        for i in 0..5 {
+            slab.insert(i);
+        }
+        slab.remove(0);
+        assert_eq!(slab.len(), 4);
+
+        slab.retain(|_, val| *val % 2 == 0);
+        assert_eq!(slab.len(), 2);
+        assert_eq!(slab.keys().collect::<Vec<_>>(), vec![2, 4]);
+
+        // Removed entries are reused
+        slab.insert(10);
+        slab.insert(11);
+        slab.insert(12);
+        assert_eq!(slab.len(), 5);
+        assert_eq!(slab.keys().max(), Some(4));
+    }
+
+    #[test]
+    fn get_checked() {
+        let mut slab = Slab::<usize, _>::empty();
+        let index = slab.insert(1);
+        slab.insert(2);
+        slab.remove(index);
+        let _ticket = slab.checkout(1);
+
+        assert_eq!(slab.get_checked(0), Err(Error::Vacant(0)));
+        assert_eq!(slab.get_checked(1), Err(Error::CheckedOut(1)));
+        assert_eq!(slab.get_checked(2), Err(Error::OutOfBounds(2)));
+    }
 }
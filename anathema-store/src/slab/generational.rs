@@ -1,7 +1,8 @@
 use std::fmt::{self, Debug};
 use std::ops::Deref;
+use std::panic::{self, AssertUnwindSafe};
 
-use super::{Index, Ticket};
+use super::{Error, Index, Ticket};
 
 /// A generation associated with a key.
 /// The generation is used to ensure that the same key can be reused without retaining
@@ -85,7 +86,7 @@ impl From<(usize, usize)> for Key {
     fn from((index, gen): (usize, usize)) -> Self {
         let gen = (gen as u64) << Self::INDEX_BITS;
         let index = (index as u64) << Self::GEN_BITS >> Self::GEN_BITS;
-        Self(gen & index)
+        Self(gen | index)
     }
 }
 
@@ -95,6 +96,20 @@ impl From<(usize, Gen)> for Key {
     }
 }
 
+/// The index and generation of the key as a single number,
+/// e.g to refer to a widget outside of the process
+impl From<Key> for u64 {
+    fn from(key: Key) -> Self {
+        key.0
+    }
+}
+
+impl From<u64> for Key {
+    fn from(bits: u64) -> Self {
+        Self(bits)
+    }
+}
+
 impl From<Key> for Index {
     fn from(value: Key) -> Self {
         value.index().into()
@@ -115,7 +130,7 @@ impl<T> Entry<T> {
     // Insert an Occupied entry in place of a vacant one.
     fn swap(&mut self, value: T, gen: Gen) {
         debug_assert!(matches!(self, Entry::Vacant(_)));
-        std::mem::swap(self, &mut Entry::Occupied(value, gen));
+        *self = Entry::Occupied(value, gen);
     }
 
     // Create a new occupied entry
@@ -145,6 +160,7 @@ impl<T: Debug> Debug for Entry<T> {
 pub struct GenSlab<T> {
     next_id: Option<Key>,
     inner: Vec<Entry<T>>,
+    len: usize,
 }
 
 impl<T> GenSlab<T> {
@@ -153,6 +169,7 @@ impl<T> GenSlab<T> {
         Self {
             next_id: None,
             inner: vec![],
+            len: 0,
         }
     }
 
@@ -162,9 +179,26 @@ impl<T> GenSlab<T> {
         Self {
             next_id: None,
             inner: Vec::with_capacity(cap),
+            len: 0,
         }
     }
 
+    /// The number of entries the slab can hold without reallocating,
+    /// including vacant entries
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    /// The number of values in the slab, including checked out values
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if there are no values in the slab
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
     /// Replace an existing value with a new one.
     /// This will bump the generation.
     pub fn replace(&mut self, key: Key, mut new_value: T) -> Option<(Key, T)> {
@@ -179,15 +213,17 @@ impl<T> GenSlab<T> {
         }
     }
 
-    /// Closure over a mutable reference to T
+    /// Closure over a mutable reference to T.
+    ///
+    /// The value is restored even if the closure panics.
     pub fn with_mut<F, U>(&mut self, key: Key, f: F) -> U
     where
         F: FnOnce(&mut T, &mut Self) -> U,
     {
         let mut ticket = self.checkout(key);
-        let ret = f(&mut ticket, self);
+        let ret = panic::catch_unwind(AssertUnwindSafe(|| f(&mut ticket, self)));
         self.restore(ticket);
-        ret
+        ret.unwrap_or_else(|payload| panic::resume_unwind(payload))
     }
 
     pub(crate) fn checkout(&mut self, key: Key) -> Ticket<Key, T> {
@@ -233,6 +269,7 @@ impl<T> GenSlab<T> {
     // finally replace the vacant entry with the occupied value
     /// Insert a value into the slab
     pub fn insert(&mut self, value: T) -> Key {
+        self.len += 1;
         match self.next_id.take() {
             Some(key) => {
                 let entry = &mut self.inner[key.index()];
@@ -276,6 +313,7 @@ impl<T> GenSlab<T> {
                 self.inner.push(entry);
             }
             self.inner.push(Entry::Occupied(value, key.gen()));
+            self.len += 1;
         // If the index is inside the current length:
         } else {
             let entry = self
@@ -285,7 +323,10 @@ impl<T> GenSlab<T> {
 
             match entry {
                 Entry::CheckedOut(_) => panic!("value is checked out"),
-                Entry::Vacant(None) => *entry = Entry::Occupied(value, key.gen()),
+                Entry::Vacant(None) => {
+                    *entry = Entry::Occupied(value, key.gen());
+                    self.len += 1;
+                }
                 Entry::Occupied(val, gen) => {
                     *val = value;
                     *gen = key.gen();
@@ -318,6 +359,7 @@ impl<T> GenSlab<T> {
 
                     // Insert new value
                     self.inner[key.index()] = Entry::Occupied(value, key.gen());
+                    self.len += 1;
                 }
             }
         }
@@ -329,6 +371,9 @@ impl<T> GenSlab<T> {
         let mut entry = Entry::Vacant(self.next_id.take());
         // Increment the generation
         std::mem::swap(&mut self.inner[key.index()], &mut entry);
+        if let Entry::Occupied(..) = entry {
+            self.len -= 1;
+        }
 
         let ret = match entry {
             Entry::Occupied(val, gen) if gen == key.gen() => val,
@@ -349,6 +394,9 @@ impl<T> GenSlab<T> {
         let mut entry = Entry::Vacant(self.next_id.take());
         // Increment the generation
         std::mem::swap(&mut self.inner[key.index()], &mut entry);
+        if let Entry::Occupied(..) = entry {
+            self.len -= 1;
+        }
 
         let ret = match entry {
             Entry::Occupied(val, gen) if gen == key.gen() => val,
@@ -377,6 +425,62 @@ impl<T> GenSlab<T> {
         }
     }
 
+    /// Get a reference to a value in the slab, or an error describing
+    /// why the key does not point to a value
+    pub fn get_checked(&self, key: Key) -> Result<&T, Error> {
+        let index = key.index();
+        match self.inner.get(index) {
+            Some(Entry::Occupied(val, gen)) if key.gen() == *gen => Ok(val),
+            Some(Entry::Occupied(_, gen)) => Err(Error::Generation {
+                index,
+                current: *gen,
+                key: key.gen(),
+            }),
+            Some(Entry::Vacant(_)) => Err(Error::Vacant(index)),
+            Some(Entry::CheckedOut(_)) => Err(Error::CheckedOut(index)),
+            None => Err(Error::OutOfBounds(index)),
+        }
+    }
+
+    /// Get a mutable reference to a value in the slab, or an error describing
+    /// why the key does not point to a value
+    pub fn get_mut_checked(&mut self, key: Key) -> Result<&mut T, Error> {
+        let index = key.index();
+        match self.inner.get_mut(index) {
+            Some(Entry::Occupied(val, gen)) if key.gen() == *gen => Ok(val),
+            Some(Entry::Occupied(_, gen)) => Err(Error::Generation {
+                index,
+                current: *gen,
+                key: key.gen(),
+            }),
+            Some(Entry::Vacant(_)) => Err(Error::Vacant(index)),
+            Some(Entry::CheckedOut(_)) => Err(Error::CheckedOut(index)),
+            None => Err(Error::OutOfBounds(index)),
+        }
+    }
+
+    /// Retain only the values where the closure returns true.
+    /// Every other value is removed from the slab, and the generation
+    /// of the key is bumped.
+    ///
+    /// Checked out values are always retained.
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(Key, &mut T) -> bool,
+    {
+        for index in 0..self.inner.len() {
+            let entry = &mut self.inner[index];
+            let Entry::Occupied(val, gen) = entry else { continue };
+            let key = Key::from((index, *gen));
+            if f(key, val) {
+                continue;
+            }
+            *entry = Entry::Vacant(self.next_id.take());
+            self.next_id = Some(key.bump());
+            self.len -= 1;
+        }
+    }
+
     /// Be aware that this will only ever be as performant as
     /// the underlying vector if all entries are occupied.
     ///
@@ -410,6 +514,11 @@ impl<T> GenSlab<T> {
         })
     }
 
+    /// Iterate over the keys of the occupied entries
+    pub fn keys(&self) -> impl Iterator<Item = Key> + '_ {
+        self.iter_keys().map(|(key, _)| key)
+    }
+
     /// Iterate over the keys and elements
     pub fn iter_keys(&self) -> impl Iterator<Item = (Key, &T)> + '_ {
         self.inner.iter().enumerate().filter_map(|(i, e)| match e {
@@ -543,4 +652,39 @@ mod test {
 
         assert_eq!(index.gen().0, 0);
     }
+
+    #[test]
+    fn get_checked() {
+        let mut slab = GenSlab::empty();
+        let key_1 = slab.insert(1);
+        slab.remove(key_1).unwrap();
+        let key_2 = slab.insert(2);
+
+        assert_eq!(*slab.get_checked(key_2).unwrap(), 2);
+        assert_eq!(
+            slab.get_checked(key_1),
+            Err(Error::Generation {
+                index: 0,
+                current: key_2.gen(),
+                key: key_1.gen()
+            })
+        );
+        assert_eq!(slab.get_checked(Key::ONE), Err(Error::OutOfBounds(1)));
+    }
+
+    #[test]
+    fn retain() {
+        let mut slab = GenSlab::empty();
+        let keys = (0..4).map(|i| slab.insert(i)).collect::<Vec<_>>();
+
+        slab.retain(|_, val| *val >= 2);
+        assert_eq!(slab.len(), 2);
+        assert_eq!(slab.keys().collect::<Vec<_>>(), &keys[2..]);
+        assert!(slab.get(keys[0]).is_none());
+
+        // The generation of a removed key is bumped
+        let key = slab.insert(5);
+        assert!(slab.get(keys[1]).is_none());
+        assert_eq!(*slab.get(key).unwrap(), 5);
+    }
 }
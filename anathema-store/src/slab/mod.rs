@@ -8,6 +8,7 @@
 //! A slab has two immediate advantages:
 //! * Reduce allocations
 //! * Fixed indices
+use std::fmt::{self, Display};
 use std::ops::{Deref, DerefMut};
 
 pub use self::basic::Slab;
@@ -51,6 +52,43 @@ impl From<Index> for usize {
     }
 }
 
+/// An error returned when a value can not be accessed in a slab
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The index is outside of the slab
+    OutOfBounds(usize),
+    /// There is no value at the index
+    Vacant(usize),
+    /// The value at the index is currently checked out
+    CheckedOut(usize),
+    /// The value at the index belongs to a different generation than the key
+    Generation {
+        /// The index of the value
+        index: usize,
+        /// The generation of the value in the slab
+        current: Gen,
+        /// The generation of the key
+        key: Gen,
+    },
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::OutOfBounds(index) => write!(f, "index {index} is out of bounds"),
+            Error::Vacant(index) => write!(f, "no value at index {index}"),
+            Error::CheckedOut(index) => write!(f, "the value at index {index} is checked out"),
+            Error::Generation { index, current, key } => write!(
+                f,
+                "invalid generation at index {index}, current: {} | key: {}",
+                **current, **key
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
 /// A ticket used when checkout an entry out of the slab.
 #[derive(Debug)]
 pub struct Ticket<I, T> {
@@ -1,43 +1,61 @@
+use std::collections::HashMap;
 use std::fmt::{self, Display};
+use std::rc::Rc;
 
-use super::Storage;
-
+/// String interner.
+///
+/// Every string is stored once, no matter how many times it's pushed,
+/// and the same allocation is shared by everything that gets the string
+/// (e.g. the ident of every `text` element in a template).
 pub struct Strings {
-    inner: Storage<StringId, String, ()>,
+    inner: Vec<Rc<str>>,
+    index: HashMap<Rc<str>, StringId>,
 }
 
 impl Strings {
     pub fn empty() -> Self {
         Self {
-            inner: Storage::empty(),
+            inner: vec![],
+            index: HashMap::new(),
         }
     }
 
     pub fn push(&mut self, string: impl Into<String>) -> StringId {
-        self.inner.push(string, ())
+        let string = string.into();
+        if let Some(string_id) = self.lookup(&string) {
+            return string_id;
+        }
+
+        let string: Rc<str> = string.into();
+        let string_id = StringId(self.inner.len());
+        self.inner.push(string.clone());
+        self.index.insert(string, string_id);
+        string_id
     }
 
     pub fn lookup(&self, string: &str) -> Option<StringId> {
-        self.inner.iter().find_map(|(i, (k, _))| match k == string {
-            true => Some(i),
-            false => None,
-        })
+        self.index.get(string).copied()
     }
 
     pub fn get(&self, string_id: StringId) -> Option<&str> {
-        self.inner.get(string_id).map(|(k, _v)| k.as_str())
+        self.inner.get(string_id.0).map(|s| &**s)
     }
 
-    pub fn get_unchecked(&self, string_id: StringId) -> String {
-        self.inner.get_unchecked(string_id).0.clone()
+    /// Get a shared reference to the interned string.
+    ///
+    /// # Panics
+    ///
+    /// If the string doesn't exist
+    pub fn get_unchecked(&self, string_id: StringId) -> Rc<str> {
+        self.inner[string_id.0].clone()
     }
 
     pub fn get_ref_unchecked(&self, string_id: StringId) -> &str {
-        &self.inner.get_unchecked(string_id).0
+        &self.inner[string_id.0]
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct StringId(usize);
 
 impl From<usize> for StringId {
@@ -57,3 +75,22 @@ impl Display for StringId {
         write!(f, "<sid {}>", self.0)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn intern_strings() {
+        let mut strings = Strings::empty();
+        let a = strings.push("text");
+        let b = strings.push("border");
+        let c = strings.push(String::from("text"));
+
+        assert_eq!(a, c);
+        assert_ne!(a, b);
+        assert_eq!(strings.lookup("border"), Some(b));
+        assert_eq!(strings.get(b), Some("border"));
+        assert!(Rc::ptr_eq(&strings.get_unchecked(a), &strings.get_unchecked(c)));
+    }
+}
@@ -59,6 +59,11 @@ impl<T> Owned<T> {
         }
     }
 
+    /// Swap the values with another storage
+    pub fn swap(&self, other: &Self) {
+        self.inner.swap(&other.inner);
+    }
+
     pub fn get_shared_key(&self, key: OwnedKey) -> Option<SharedKey> {
         match self.inner.borrow().get(key)? {
             OwnedEntry::Shared(key) => Some(*key),
@@ -137,6 +142,29 @@ impl<T> Owned<T> {
         }
     }
 
+    /// Remove the value from the storage, if the value exists
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the value is borrowed
+    pub fn try_remove(&self, key: OwnedKey) -> Option<T> {
+        match self.inner.borrow_mut().try_remove(key)? {
+            OwnedEntry::Occupied(value) => Some(value),
+            OwnedEntry::Unique => panic!("invalid state (U)"),
+            OwnedEntry::Shared(_) => panic!("invalid state"),
+        }
+    }
+
+    /// The keys of the values that are not borrowed
+    pub fn occupied_keys(&self) -> Vec<OwnedKey> {
+        self.inner
+            .borrow()
+            .iter()
+            .filter(|(_, entry)| entry.is_occupied())
+            .map(|(key, _)| key)
+            .collect()
+    }
+
     // Return a value to the storage.
     // The value can be either a
     // * Unique borrow
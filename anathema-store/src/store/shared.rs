@@ -44,6 +44,11 @@ impl<T> Shared<T> {
             .expect("the value exists because the shared key exists")
     }
 
+    /// Swap the values with another storage
+    pub fn swap(&self, other: &Self) {
+        self.inner.swap(&other.inner);
+    }
+
     pub fn insert(&self, owned_key: OwnedKey, value: T) -> SharedKey {
         let key = self.inner.borrow_mut().insert(value);
         SharedKey(key, owned_key)
@@ -1,4 +1,5 @@
 use std::ops::ControlFlow;
+use std::slice::Iter;
 
 use super::{Node, TreeValues, ValueId};
 
@@ -18,7 +19,7 @@ impl<'a, 'filter, T, Fil> TreeForEach<'a, 'filter, T, Fil> {
         F: FnMut(&mut Fil::Output, TreeForEach<'_, '_, T, Fil>) -> ControlFlow<()>,
         Fil: TreeFilter<Input = T>,
     {
-        self.inner_for_each(&mut f);
+        let _ = self.inner_for_each(&mut f);
     }
 
     /// Apply to the first element that matches the filter
@@ -28,7 +29,7 @@ impl<'a, 'filter, T, Fil> TreeForEach<'a, 'filter, T, Fil> {
         Fil: TreeFilter<Input = T>,
     {
         for node in self.nodes {
-            self.values.with_mut(node.value(), |(_, value), values| {
+            let _ = self.values.with_mut(node.value(), |(_, value), values| {
                 let filter = self.filter.filter(node.value(), value, node.children(), values);
 
                 match filter {
@@ -56,6 +57,10 @@ impl<'a, 'filter, T, Fil> TreeForEach<'a, 'filter, T, Fil> {
         F: FnMut(&mut Fil::Output, TreeForEach<'_, '_, T, Fil>) -> ControlFlow<()>,
         Fil: TreeFilter<Input = T>,
     {
+        if self.filter.ordered() {
+            return self.ordered_for_each(f);
+        }
+
         for node in self.nodes {
             self.values.with_mut(node.value(), |(_, value), values| {
                 let filter = self.filter.filter(node.value(), value, node.children(), values);
@@ -84,6 +89,55 @@ impl<'a, 'filter, T, Fil> TreeForEach<'a, 'filter, T, Fil> {
 
         ControlFlow::Continue(())
     }
+
+    // Visit the outputs in the order of their sort keys
+    fn ordered_for_each<F>(&mut self, f: &mut F) -> ControlFlow<()>
+    where
+        F: FnMut(&mut Fil::Output, TreeForEach<'_, '_, T, Fil>) -> ControlFlow<()>,
+        Fil: TreeFilter<Input = T>,
+    {
+        let mut outputs = vec![];
+        collect_outputs(self.nodes, self.values, self.filter, &mut outputs);
+
+        // The sort is stable, so outputs with the same key keep the order of the tree
+        outputs.sort_by_key(|(key, _)| *key);
+
+        for (_, node) in outputs {
+            self.values.with_mut(node.value(), |(_, value), values| {
+                match self.filter.filter(node.value(), value, node.children(), values) {
+                    ControlFlow::Continue(Some(val)) => {
+                        let each = TreeForEach {
+                            nodes: node.children(),
+                            values,
+                            filter: self.filter,
+                        };
+                        f(val, each)
+                    }
+                    _ => ControlFlow::Continue(()),
+                }
+            })?;
+        }
+
+        ControlFlow::Continue(())
+    }
+}
+
+// The nodes of the outputs, along with their sort keys, in the order of the tree
+fn collect_outputs<'a, T, Fil: TreeFilter<Input = T>>(
+    nodes: &'a [Node],
+    values: &mut TreeValues<T>,
+    filter: &Fil,
+    outputs: &mut Vec<(i64, &'a Node)>,
+) {
+    for node in nodes {
+        values.with_mut(node.value(), |(_, value), values| {
+            match filter.filter(node.value(), value, node.children(), values) {
+                ControlFlow::Break(()) => {}
+                ControlFlow::Continue(None) => collect_outputs(node.children(), values, filter, outputs),
+                ControlFlow::Continue(Some(val)) => outputs.push((filter.sort_key(node.value(), val), node)),
+            }
+        });
+    }
 }
 
 pub trait TreeFilter {
@@ -97,4 +151,95 @@ pub trait TreeFilter {
         children: &[Node],
         values: &mut TreeValues<Self::Input>,
     ) -> ControlFlow<(), Option<&'val mut Self::Output>>;
+
+    /// Visit the outputs in the order of their [`TreeFilter::sort_key`],
+    /// rather than in the order of the tree
+    fn ordered(&self) -> bool {
+        false
+    }
+
+    /// The key of an output when the filter is [`TreeFilter::ordered`], lowest first.
+    /// Outputs with the same key are visited in the order of the tree.
+    fn sort_key(&self, _value_id: ValueId, _output: &Self::Output) -> i64 {
+        0
+    }
+}
+
+/// An iterator over the values of a tree, visiting each parent before its children.
+///
+/// Each item consists of the id, the path and the value of a node.
+/// See [`super::Tree::pre_order`].
+pub struct PreOrder<'a, T> {
+    stack: Vec<Iter<'a, Node>>,
+    values: &'a TreeValues<T>,
+}
+
+impl<'a, T> PreOrder<'a, T> {
+    pub(super) fn new(nodes: &'a [Node], values: &'a TreeValues<T>) -> Self {
+        Self {
+            stack: vec![nodes.iter()],
+            values,
+        }
+    }
+}
+
+impl<'a, T> Iterator for PreOrder<'a, T> {
+    type Item = (ValueId, &'a [u16], &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let Some(node) = self.stack.last_mut()?.next() else {
+                self.stack.pop();
+                continue;
+            };
+
+            // Nodes without a value (e.g checked out values) are skipped along with their children
+            let Some((path, value)) = self.values.get(node.value()) else { continue };
+            self.stack.push(node.children().iter());
+            break Some((node.value(), path, value));
+        }
+    }
+}
+
+/// An iterator over the values of a tree, visiting each parent after its children.
+///
+/// Each item consists of the id, the path and the value of a node.
+/// See [`super::Tree::post_order`].
+pub struct PostOrder<'a, T> {
+    stack: Vec<(Option<&'a Node>, Iter<'a, Node>)>,
+    values: &'a TreeValues<T>,
+}
+
+impl<'a, T> PostOrder<'a, T> {
+    pub(super) fn new(nodes: &'a [Node], values: &'a TreeValues<T>) -> Self {
+        Self {
+            stack: vec![(None, nodes.iter())],
+            values,
+        }
+    }
+}
+
+impl<'a, T> Iterator for PostOrder<'a, T> {
+    type Item = (ValueId, &'a [u16], &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (parent, children) = self.stack.last_mut()?;
+            match children.next() {
+                Some(node) => {
+                    // Nodes without a value (e.g checked out values) are skipped along with their children
+                    if self.values.get(node.value()).is_some() {
+                        self.stack.push((Some(node), node.children().iter()));
+                    }
+                }
+                None => {
+                    let parent = parent.take();
+                    self.stack.pop();
+                    let Some(node) = parent else { continue };
+                    let Some((path, value)) = self.values.get(node.value()) else { continue };
+                    break Some((node.value(), path, value));
+                }
+            }
+        }
+    }
 }
@@ -1,6 +1,7 @@
 use std::ops::{ControlFlow, Deref};
+use std::panic::{self, AssertUnwindSafe};
 
-pub use self::iter::{TreeFilter, TreeForEach};
+pub use self::iter::{PostOrder, PreOrder, TreeFilter, TreeForEach};
 pub use self::nodepath::{new_node_path, root_node, AsNodePath};
 pub use self::pathfinder::PathFinder;
 pub use self::transactions::InsertTransaction;
@@ -47,6 +48,17 @@ impl<T> Tree<T> {
         (&self.layout, &mut self.values)
     }
 
+    /// The number of values in the tree.
+    /// This iterates over the value store, see [`GenSlab::iter`].
+    pub fn len(&self) -> usize {
+        self.values.iter().count()
+    }
+
+    /// True if the tree holds no values
+    pub fn is_empty(&self) -> bool {
+        self.values.iter().next().is_none()
+    }
+
     /// Give a capacity to the underlying value store.
     /// This will not affect the storage of the layout.
     pub fn with_capacity(cap: usize) -> Self {
@@ -57,6 +69,12 @@ impl<T> Tree<T> {
         }
     }
 
+    /// The number of values the tree can hold before the value store has to grow.
+    /// Removed values leave room for new ones.
+    pub fn capacity(&self) -> usize {
+        self.values.capacity()
+    }
+
     /// The root node
     pub fn root(&self) -> &Node {
         &self.layout[0]
@@ -167,9 +185,9 @@ impl<T> Tree<T> {
 
     /// Remove a `Node` and value from the tree.
     /// This will also remove all the children and associated values.
-    pub fn remove(&mut self, path: &[u16]) {
-        // This will not return the value that was removed, as it will also
-        // remove all the children under that node.
+    ///
+    /// Only the value of the removed node is returned, not the values of the children.
+    pub fn remove(&mut self, path: &[u16]) -> Option<T> {
         let (path, index) = path.split_parent().expect("a value will always exist within the tree");
 
         let node = self.layout.with_mut(path, |nodes| {
@@ -190,14 +208,14 @@ impl<T> Tree<T> {
             node
         });
 
-        if let Some(mut node) = node {
-            let value_key = node.value();
-            let _ = self
-                .values
-                .remove(value_key)
-                .expect("a node is always associated with a value");
-            node.children.clear(&mut self.values, &mut self.removed_values);
-        }
+        let mut node = node?;
+        let value_key = node.value();
+        let (_, value) = self
+            .values
+            .remove(value_key)
+            .expect("a node is always associated with a value");
+        node.children.clear(&mut self.values, &mut self.removed_values);
+        Some(value)
     }
 
     /// Remove the children of a `Node`. This
@@ -232,15 +250,17 @@ impl<T> Tree<T> {
     ///
     /// # Panics
     ///
-    /// This will panic if the value is already checked out
+    /// This will panic if the value is already checked out.
+    /// If `F` panics the value is restored before the panic resumes.
     pub fn with_value_mut<F, V>(&mut self, value_id: ValueId, f: F) -> V
     where
         F: FnOnce(&[u16], &mut T, &mut Self) -> V,
     {
         let mut ticket = self.values.checkout(value_id);
-        let value = f(&ticket.value.0, &mut ticket.value.1, self);
+        let (path, value) = &mut ticket.value;
+        let value = panic::catch_unwind(AssertUnwindSafe(|| f(path, value, self)));
         self.values.restore(ticket);
-        value
+        value.unwrap_or_else(|payload| panic::resume_unwind(payload))
     }
 
     /// Get mutable access to a node value along with the children
@@ -254,8 +274,11 @@ impl<T> Tree<T> {
             .layout
             .get_by_path(&ticket.value.0)
             .expect("the value and the node exists at the same time");
-        f(&mut ticket.value.1, node.children(), &mut self.values);
+        let ret = panic::catch_unwind(AssertUnwindSafe(|| {
+            f(&mut ticket.value.1, node.children(), &mut self.values)
+        }));
         self.values.restore(ticket);
+        ret.unwrap_or_else(|payload| panic::resume_unwind(payload))
     }
 
     /// Apply function to each child of a parent path.
@@ -292,8 +315,9 @@ impl<T> Tree<T> {
     }
 
     /// Apply the [`PathFinder`].
-    pub fn apply_path_finder(&mut self, node_path: &[u16], path_finder: impl PathFinder<T>) {
-        apply_path_finder(self, node_path, path_finder);
+    /// Returns the output of the path finder, or `None` if there is no node at the path.
+    pub fn apply_path_finder<P: PathFinder<T>>(&mut self, node_path: &[u16], path_finder: P) -> Option<P::Output> {
+        apply_path_finder(self, node_path, path_finder)
     }
 
     /// Apply the [`NodeWalker`].
@@ -301,9 +325,48 @@ impl<T> Tree<T> {
         apply_walker(&self.layout, &mut self.values, path, walker)
     }
 
+    /// Iterate over the values of the tree, depth first,
+    /// where each parent comes before its children.
+    ///
+    /// ```
+    /// # use anathema_store::tree::{root_node, Tree};
+    /// let mut tree = Tree::empty();
+    /// let parent = tree.insert(root_node()).commit_child(1).unwrap();
+    /// let path = tree.path(parent);
+    /// tree.insert(&path).commit_child(2);
+    /// tree.insert(root_node()).commit_child(3);
+    ///
+    /// let values = tree.pre_order().map(|(_, _, val)| *val).collect::<Vec<_>>();
+    /// assert_eq!(values, [1, 2, 3]);
+    /// ```
+    pub fn pre_order(&self) -> PreOrder<'_, T> {
+        PreOrder::new(&self.layout, &self.values)
+    }
+
+    /// Iterate over the values of the tree, depth first,
+    /// where each parent comes after its children.
+    ///
+    /// ```
+    /// # use anathema_store::tree::{root_node, Tree};
+    /// let mut tree = Tree::empty();
+    /// let parent = tree.insert(root_node()).commit_child(1).unwrap();
+    /// let path = tree.path(parent);
+    /// tree.insert(&path).commit_child(2);
+    /// tree.insert(root_node()).commit_child(3);
+    ///
+    /// let values = tree
+    ///     .post_order()
+    ///     .map(|(_, _, val)| *val)
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(values, [2, 1, 3]);
+    /// ```
+    pub fn post_order(&self) -> PostOrder<'_, T> {
+        PostOrder::new(&self.layout, &self.values)
+    }
+
     /// Apply a [`NodeVisitor`], depth first
     pub fn apply_visitor<V: NodeVisitor<T>>(&mut self, visitor: &mut V) {
-        apply_visitor(&self.layout, &mut self.values, visitor);
+        let _ = apply_visitor(&self.layout, &mut self.values, visitor);
     }
 
     /// Split the tree giving access to the layout and the values.
@@ -316,26 +379,29 @@ impl<T> Tree<T> {
     }
 }
 
-fn apply_path_finder<T>(tree: &mut Tree<T>, node_path: &[u16], mut path_finder: impl PathFinder<T>) {
+fn apply_path_finder<T, P: PathFinder<T>>(
+    tree: &mut Tree<T>,
+    node_path: &[u16],
+    mut path_finder: P,
+) -> Option<P::Output> {
     let mut path: &[u16] = node_path;
     let mut nodes: &[_] = &tree.layout.inner;
     let values = &mut tree.values;
 
     loop {
         match path {
-            [] => break,
+            [] => break None,
             [i] => {
                 // Found the node
-                let node = &nodes[*i as usize];
-                tree.with_value_mut(node.value(), |path, widget, tree| {
-                    path_finder.apply(widget, path, tree);
-                });
-                break;
+                let node = nodes.get(*i as usize)?;
+                let output =
+                    tree.with_value_mut(node.value(), |path, widget, tree| path_finder.apply(widget, path, tree));
+                break Some(output);
             }
             [i, sub_path @ ..] => {
                 let index = *i as usize;
                 if index >= nodes.len() {
-                    break;
+                    break None;
                 }
                 path = sub_path;
                 let node = &nodes[index];
@@ -447,7 +513,7 @@ impl Nodes {
     pub fn iter_with_values<'a, T>(
         &'a self,
         values: &'a TreeValues<T>,
-    ) -> impl Iterator<Item = (&Node, &Box<[u16]>, &T)> {
+    ) -> impl Iterator<Item = (&'a Node, &'a Box<[u16]>, &'a T)> {
         self.inner.iter().filter_map(|node| {
             let (path, value) = values.get(node.value)?;
             Some((node, path, value))
@@ -618,6 +684,18 @@ mod test {
         assert!(tree.get_ref_by_id(node_id).is_none());
     }
 
+    #[test]
+    fn restore_value_on_panic() {
+        let mut tree = Tree::<u32>::empty();
+        let node_id = tree.insert(root_node()).commit_child(1).unwrap();
+
+        let res = panic::catch_unwind(AssertUnwindSafe(|| {
+            tree.with_value_mut(node_id, |_, _, _| panic!("oh no"));
+        }));
+        assert!(res.is_err());
+        assert_eq!(*tree.get_ref_by_id(node_id).unwrap(), 1);
+    }
+
     #[test]
     fn get_by_path() {
         let mut tree = Tree::empty();
@@ -632,6 +710,32 @@ mod test {
         assert_eq!(*two, 2);
     }
 
+    #[test]
+    fn pre_and_post_order() {
+        // 0
+        //   1
+        //     2
+        //   3
+        // 4
+        let mut tree = Tree::empty();
+        let zero = tree.insert(root_node()).commit_child(0).unwrap();
+        let zero: Box<_> = tree.path_ref(zero).into();
+        let one = tree.insert(&zero).commit_child(1).unwrap();
+        let one: Box<_> = tree.path_ref(one).into();
+        tree.insert(&one).commit_child(2);
+        tree.insert(&zero).commit_child(3);
+        tree.insert(root_node()).commit_child(4);
+
+        let pre = tree.pre_order().map(|(_, _, val)| *val).collect::<Vec<_>>();
+        assert_eq!(pre, [0, 1, 2, 3, 4]);
+
+        let post = tree.post_order().map(|(_, _, val)| *val).collect::<Vec<_>>();
+        assert_eq!(post, [2, 1, 3, 0, 4]);
+
+        let paths = tree.pre_order().map(|(_, path, _)| path).collect::<Vec<_>>();
+        assert_eq!(paths, [&[0][..], &[0, 0], &[0, 0, 0], &[0, 1], &[1]]);
+    }
+
     #[test]
     fn with_node_id() {
         let mut tree = Tree::empty();
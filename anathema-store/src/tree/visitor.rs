@@ -3,15 +3,24 @@ use std::ops::ControlFlow;
 
 use super::ValueId;
 
+/// Visit the values of a tree, depth first, with mutable access to each value.
+/// See [`super::Tree::apply_visitor`].
+///
+/// For read-only traversal see [`super::Tree::pre_order`] and [`super::Tree::post_order`].
 pub trait NodeVisitor<T> {
+    /// Visit a value, before any of its children.
+    ///
     /// Return control flow.
     /// * `ControlFlow::Continue(())` continue
-    /// * `ControlFlow::Break(false)` stop iterating over the children of the current node
+    /// * `ControlFlow::Break(false)` stop iterating over the current node, its children and its
+    ///   remaining siblings
     /// * `ControlFlow::Break(true)` stop iterating
     fn visit(&mut self, value: &mut T, path: &[u16], value_id: ValueId) -> ControlFlow<bool>;
 
+    /// Called before visiting the children of the most recently visited value
     fn push(&mut self) {}
 
+    /// Called after visiting the children of the most recently visited value
     fn pop(&mut self) {}
 }
 
@@ -12,6 +12,37 @@ pub struct Single {
     pub children: Vec<Blueprint>,
     pub attributes: SmallMap<Rc<str>, Expression>,
     pub value: Option<Expression>,
+    /// Where the element is declared in the template
+    pub span: Span,
+}
+
+/// The line and column of an element in the template source.
+/// Both start at one, as they are meant to be read by a person.
+///
+/// The span is not part of the structure of a template,
+/// so two spans are always equal.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct Span {
+    pub line: usize,
+    pub col: usize,
+}
+
+impl Span {
+    pub const fn new(line: usize, col: usize) -> Self {
+        Self { line, col }
+    }
+}
+
+impl PartialEq for Span {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl std::fmt::Display for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}, col {}", self.line, self.col)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -42,11 +73,19 @@ pub struct Else {
 #[derive(Debug, Clone, PartialEq)]
 pub struct Component {
     pub id: WidgetComponentId,
+    pub name: Rc<str>,
     pub body: Vec<Blueprint>,
+    /// Replaces the body if the component is an error boundary
+    /// and the evaluation of the body fails
+    pub fallback: Vec<Blueprint>,
     pub attributes: SmallMap<Rc<str>, Expression>,
     pub state: Option<Rc<HashMap<Rc<str>, Expression>>>,
     pub assoc_functions: Vec<(StringId, StringId)>,
     pub parent: Option<WidgetComponentId>,
+    /// Set if the component is used inside its own template.
+    /// The body is empty and the body of the closest instance of the component
+    /// is used instead, as long as it's nested less than the limit.
+    pub recursion_limit: Option<usize>,
 }
 
 /// A blueprint represents what widget should be built from the information
@@ -66,6 +105,7 @@ macro_rules! single {
             children: vec![],
             attributes: SmallMap::empty(),
             value: None,
+            span: $crate::blueprints::Span::default(),
         })
     };
     ($ident:expr, $children:expr) => {
@@ -74,6 +114,7 @@ macro_rules! single {
             children: $children,
             attributes: SmallMap::empty(),
             value: None,
+            span: $crate::blueprints::Span::default(),
         })
     };
 }
@@ -99,9 +99,15 @@ impl From<usize> for WidgetComponentId {
     }
 }
 
+/// The default number of times a component can be nested inside itself
+pub const DEFAULT_RECURSION_LIMIT: usize = 16;
+
 pub(crate) struct ComponentTemplates {
-    dependencies: Stack<WidgetComponentId>,
+    /// The components being compiled, and whether the component was
+    /// used inside a for-loop
+    dependencies: Stack<(WidgetComponentId, bool)>,
     components: Storage<WidgetComponentId, String, ComponentSource>,
+    recursion_limit: usize,
 }
 
 impl ComponentTemplates {
@@ -109,6 +115,7 @@ impl ComponentTemplates {
         Self {
             dependencies: Stack::empty(),
             components: Storage::empty(),
+            recursion_limit: DEFAULT_RECURSION_LIMIT,
         }
     }
 
@@ -121,38 +128,77 @@ impl ComponentTemplates {
         self.components.insert(ident, template)
     }
 
+    pub(crate) fn name(&self, id: WidgetComponentId) -> Option<&str> {
+        self.components.get(id).map(|(name, _)| name.as_str())
+    }
+
+    // Ids and names of all the components with a template
+    pub(crate) fn ids(&self) -> Vec<(WidgetComponentId, String)> {
+        self.components
+            .iter()
+            .filter_map(|(id, (name, src))| match src {
+                ComponentSource::File { .. } | ComponentSource::InMemory(_) => Some((id, name.clone())),
+                ComponentSource::Empty => None,
+            })
+            .collect()
+    }
+
+    pub(crate) fn set_recursion_limit(&mut self, limit: usize) {
+        self.recursion_limit = limit;
+    }
+
+    /// Check if a component is used inside itself (directly or through other components).
+    ///
+    /// The first time a component is nested inside itself it's compiled like any other
+    /// component. Beyond that the body would be the same as the one of the nested instance,
+    /// so the component is not compiled again, and the recursion limit is returned instead.
+    /// The body is then resolved when the component is evaluated, which means the
+    /// recursion is driven by the data.
+    ///
+    /// A component that uses itself outside of a for-loop would never stop
+    /// and is a circular dependency.
+    pub(crate) fn recursion(&self, component_id: WidgetComponentId, in_loop: bool) -> Result<Option<usize>> {
+        // Only the components between the closest instance and this one are part of the cycle
+        let mut depth = 0;
+        let mut guarded = in_loop;
+        for &(id, id_in_loop) in self.dependencies.iter().rev() {
+            match id == component_id {
+                true => depth += 1,
+                false if depth == 0 => guarded |= id_in_loop,
+                false => (),
+            }
+        }
+
+        if depth == 0 {
+            return Ok(None);
+        }
+
+        if !guarded {
+            return Err(Error::CircularDependency);
+        }
+
+        match depth >= 2 || depth >= self.recursion_limit {
+            true => Ok(Some(self.recursion_limit)),
+            false => Ok(None),
+        }
+    }
+
     pub(crate) fn load(
         &mut self,
         parent_id: WidgetComponentId,
         globals: &mut Variables,
         slots: SmallMap<StringId, Vec<Blueprint>>,
         strings: &mut Strings,
+        in_loop: bool,
     ) -> Result<Vec<Blueprint>> {
-        if self.dependencies.contains(&parent_id) {
-            return Err(Error::CircularDependency);
-        }
-
-        self.dependencies.push(parent_id);
-
-        let ret = match self.components.remove(parent_id) {
-            Some((key, component_src)) => {
-                let template = match &component_src {
-                    ComponentSource::File { template, .. } => template,
-                    ComponentSource::InMemory(template) => template,
-                    ComponentSource::Empty => return Err(Error::MissingComponent(key)),
-                };
-                let ret = self.compile(template, globals, slots, strings, parent_id);
-                // This will re-insert the component in the same location
-                // as it was removed from since nothing else has
-                // written to the component storage since the component
-                // was removed.
-                let new_id = self.components.insert(key, component_src);
-                assert_eq!(parent_id, new_id);
-                ret
-            }
-            _ => unreachable!("a component entry exists if it's mentioned in the template, even if the component it self doesn't exist"),
+        let template = match self.components.get(parent_id) {
+            Some((_, ComponentSource::File { template, .. } | ComponentSource::InMemory(template))) => template.clone(),
+            Some((key, ComponentSource::Empty)) => return Err(Error::MissingComponent(key.clone())),
+            None => unreachable!("a component entry exists if it's mentioned in the template, even if the component it self doesn't exist"),
         };
 
+        self.dependencies.push((parent_id, in_loop));
+        let ret = self.compile(&template, globals, slots, strings, parent_id);
         self.dependencies.pop();
 
         ret
@@ -1,10 +1,13 @@
+use std::cell::OnceCell;
+use std::collections::HashMap;
 use std::fs::read_to_string;
 use std::path::PathBuf;
+use std::rc::Rc;
 
 use anathema_store::smallmap::SmallMap;
 use anathema_store::storage::strings::Strings;
 
-use crate::blueprints::Blueprint;
+use crate::blueprints::{Blueprint, Component};
 use crate::components::{ComponentSource, ComponentTemplates, SourceKind};
 use crate::error::{Error, Result};
 use crate::statements::eval::Scope;
@@ -12,7 +15,28 @@ use crate::statements::parser::Parser;
 use crate::statements::{Context, Statements};
 use crate::token::Tokens;
 use crate::variables::Variables;
-use crate::{Globals, Lexer};
+use crate::{Globals, Lexer, WidgetComponentId};
+
+/// Blueprints of the components that can be mounted at runtime.
+///
+/// A component is compiled the first time it's mounted (see [`Document::compile_component`]),
+/// so components that are never mounted are never compiled.
+#[derive(Debug, Default, Clone)]
+pub struct ComponentBlueprints {
+    inner: HashMap<Rc<str>, (WidgetComponentId, OnceCell<Blueprint>)>,
+}
+
+impl ComponentBlueprints {
+    /// The id of the component with the given name
+    pub fn id(&self, name: &str) -> Option<WidgetComponentId> {
+        self.inner.get(name).map(|(id, _)| *id)
+    }
+
+    /// The blueprint of the component, if it has been compiled
+    pub fn get(&self, name: &str) -> Option<&Blueprint> {
+        self.inner.get(name).and_then(|(_, blueprint)| blueprint.get())
+    }
+}
 
 /// A document containing templates and components
 /// ```
@@ -55,6 +79,19 @@ impl Document {
         Ok(id.into())
     }
 
+    /// Set the maximum number of times a component can be nested inside itself
+    /// (directly or through other components).
+    ///
+    /// A recursive component is expanded when it's evaluated, driven by the data,
+    /// so it has to be used inside a for-loop.
+    /// Nesting the component deeper than the limit is an error.
+    ///
+    /// Recursive components have to be prototypes,
+    /// as each level is a new instance of the component.
+    pub fn set_recursion_limit(&mut self, limit: usize) {
+        self.components.set_recursion_limit(limit);
+    }
+
     pub fn compile(&mut self) -> Result<(Blueprint, Globals)> {
         self.strings = Strings::empty();
         self.globals = Variables::default();
@@ -71,13 +108,80 @@ impl Document {
             components: &mut self.components,
             slots: SmallMap::empty(),
             current_component_parent: None,
+            loops: 0,
         };
 
         let mut blueprints = Scope::new(statements).eval(&mut context)?;
-        match blueprints.is_empty() {
-            true => Err(Error::EmptyTemplate),
-            false => Ok((blueprints.remove(0), self.globals.take().into())),
+
+        if blueprints.is_empty() {
+            return Err(Error::EmptyTemplate);
         }
+
+        // The declarations are kept, as components compiled
+        // when they are mounted can refer to them
+        let mut globals = Globals::from(self.globals.clone());
+        globals.components = self
+            .components
+            .ids()
+            .into_iter()
+            .map(|(id, _)| (id, OnceCell::new()))
+            .collect();
+
+        Ok((blueprints.remove(0), globals))
+    }
+
+    /// Blueprints of every component, used to mount components at runtime.
+    /// The blueprints are empty until they are compiled with [`Document::compile_component`].
+    pub fn component_blueprints(&self) -> ComponentBlueprints {
+        let inner = self
+            .components
+            .ids()
+            .into_iter()
+            .map(|(id, name)| (name.into(), (id, OnceCell::new())))
+            .collect();
+        ComponentBlueprints { inner }
+    }
+
+    /// Compile a component on its own, to mount it at runtime.
+    /// The component is only compiled once, after that the same blueprint is returned.
+    ///
+    /// Declarations in the template of the component are added to the `globals`,
+    /// which have to be the globals returned by [`Document::compile`].
+    ///
+    /// Returns `None` if there is no component with the given name.
+    pub fn compile_component<'a>(
+        &mut self,
+        name: &str,
+        blueprints: &'a ComponentBlueprints,
+        globals: &Globals,
+    ) -> Result<Option<&'a Blueprint>> {
+        let Some((id, blueprint)) = blueprints.inner.get(name) else { return Ok(None) };
+        if let Some(blueprint) = blueprint.get() {
+            return Ok(Some(blueprint));
+        }
+
+        let mut variables = self.globals.clone();
+        let body = self
+            .components
+            .load(*id, &mut variables, SmallMap::empty(), &mut self.strings, false)?;
+
+        if let Some(vars) = globals.components.get(id) {
+            let _ = vars.set(variables.into());
+        }
+
+        let component = Component {
+            id: *id,
+            name: name.into(),
+            body,
+            fallback: vec![],
+            attributes: SmallMap::empty(),
+            state: None,
+            assoc_functions: vec![],
+            parent: None,
+            recursion_limit: None,
+        };
+
+        Ok(Some(blueprint.get_or_init(|| Blueprint::Component(component))))
     }
 
     pub fn template_paths(&self) -> impl Iterator<Item = &PathBuf> {
@@ -48,27 +48,7 @@ impl Display for ParseError {
         let start_line = self.line;
         let lines = self.src.lines().enumerate().skip(start_line.saturating_sub(2)).take(3);
 
-        let msg = match &self.kind {
-            ParseErrorKind::UnterminatedString => "unterminated string".into(),
-            ParseErrorKind::UnterminatedAttributes => "unterminated attributes (missing `]`)".into(),
-            ParseErrorKind::UnterminatedAssociation => "unterminated association (missing `)`)".into(),
-            ParseErrorKind::UnterminatedElement => "unterminated element".into(),
-            ParseErrorKind::InvalidToken { expected } => {
-                format!("invalid token (expected: \"{expected}\")")
-            }
-            ParseErrorKind::InvalidNumber => "invalid number".into(),
-            ParseErrorKind::InvalidIndex => "invalid index".into(),
-            ParseErrorKind::InvalidPath => "invalid path".into(),
-            ParseErrorKind::InvalidHexValue => "invalid hex value".into(),
-            ParseErrorKind::UnexpectedEof => "unexpected end of file".into(),
-            ParseErrorKind::TrailingPipe => "trailing pipe character".into(),
-            ParseErrorKind::InvalidDedent => "dedent does not match previous indentation levels".into(),
-            ParseErrorKind::InvalidOperator(_op) => "invalid operator: {op}".into(),
-            ParseErrorKind::UnexpectedToken(_msg) => "unexpected token: {msg}".into(),
-            ParseErrorKind::InvalidKey => todo!(),
-        };
-
-        writeln!(f, "error on line {start_line}: {msg}")?;
+        writeln!(f, "error on line {start_line}: {}", self.kind)?;
 
         for (no, line) in lines {
             let no = no + 1;
@@ -107,3 +87,25 @@ pub enum ParseErrorKind {
     UnexpectedToken(String),
     InvalidKey,
 }
+
+impl Display for ParseErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseErrorKind::UnterminatedString => write!(f, "unterminated string"),
+            ParseErrorKind::UnterminatedAttributes => write!(f, "unterminated attributes (missing `]`)"),
+            ParseErrorKind::UnterminatedAssociation => write!(f, "unterminated association (missing `)`)"),
+            ParseErrorKind::UnterminatedElement => write!(f, "unterminated element"),
+            ParseErrorKind::InvalidToken { expected } => write!(f, "invalid token (expected: \"{expected}\")"),
+            ParseErrorKind::InvalidNumber => write!(f, "invalid number"),
+            ParseErrorKind::InvalidIndex => write!(f, "invalid index"),
+            ParseErrorKind::InvalidPath => write!(f, "invalid path"),
+            ParseErrorKind::InvalidHexValue => write!(f, "invalid hex value"),
+            ParseErrorKind::UnexpectedEof => write!(f, "unexpected end of file"),
+            ParseErrorKind::TrailingPipe => write!(f, "trailing pipe character"),
+            ParseErrorKind::InvalidDedent => write!(f, "dedent does not match previous indentation levels"),
+            ParseErrorKind::InvalidOperator(op) => write!(f, "invalid operator: {op}"),
+            ParseErrorKind::UnexpectedToken(msg) => write!(f, "unexpected token: {msg}"),
+            ParseErrorKind::InvalidKey => write!(f, "invalid key"),
+        }
+    }
+}
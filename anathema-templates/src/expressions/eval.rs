@@ -1,5 +1,4 @@
 use std::collections::HashMap;
-use std::rc::Rc;
 
 use anathema_store::storage::strings::Strings;
 
@@ -12,14 +11,8 @@ use crate::token::Operator;
 pub fn eval(expr: Expr, strings: &Strings) -> Result<Expression, ParseErrorKind> {
     let output = match expr {
         Expr::Primitive(val) => Expression::Primitive(val),
-        Expr::Ident(string_id) => {
-            let string = strings.get_unchecked(string_id);
-            Expression::Ident(Rc::from(string))
-        }
-        Expr::Str(string_id) => {
-            let string = strings.get_unchecked(string_id);
-            Expression::Str(Rc::from(string))
-        }
+        Expr::Ident(string_id) => Expression::Ident(strings.get_unchecked(string_id)),
+        Expr::Str(string_id) => Expression::Str(strings.get_unchecked(string_id)),
         Expr::Array { lhs, index } => {
             let lhs = eval(*lhs, strings)?;
             let index = eval(*index, strings)?;
@@ -37,7 +30,7 @@ pub fn eval(expr: Expr, strings: &Strings) -> Result<Expression, ParseErrorKind>
                     }
                 };
                 let rhs = strings.get_unchecked(string_id);
-                Expression::Index(lhs, Expression::Str(rhs.into()).into())
+                Expression::Index(lhs, Expression::Str(rhs).into())
             }
             Operator::Mul | Operator::Plus | Operator::Minus | Operator::Div | Operator::Mod => {
                 let (lhs, rhs) = (eval(*lhs, strings)?.into(), eval(*rhs, strings)?.into());
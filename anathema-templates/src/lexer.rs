@@ -414,7 +414,7 @@ mod test {
             else {
                 panic!("invalid token")
             };
-            let actual = strings.get_unchecked(string_id);
+            let actual = strings.get_ref_unchecked(string_id);
             assert_eq!(actual, expected);
         }
     }
@@ -1,5 +1,5 @@
-pub use crate::components::{SourceKind, ToSourceKind, WidgetComponentId};
-pub use crate::document::Document;
+pub use crate::components::{SourceKind, ToSourceKind, WidgetComponentId, DEFAULT_RECURSION_LIMIT};
+pub use crate::document::{ComponentBlueprints, Document};
 pub use crate::expressions::Expression;
 pub use crate::lexer::Lexer;
 pub use crate::primitives::Primitive;
@@ -5,11 +5,13 @@ use anathema_store::storage::strings::StringId;
 
 use super::const_eval::const_eval;
 use super::{Context, Statement, Statements};
-use crate::blueprints::{Blueprint, Component, ControlFlow, Else, For, If, Single};
+use crate::blueprints::{Blueprint, Component, ControlFlow, Else, For, If, Single, Span};
 use crate::error::{Error, Result};
 use crate::expressions::Expression;
 use crate::WidgetComponentId;
 
+const FALLBACK_SLOT: &str = "fallback";
+
 pub(crate) struct Scope {
     statements: Statements,
 }
@@ -24,7 +26,7 @@ impl Scope {
 
         while let Some(statement) = self.statements.next() {
             match statement {
-                Statement::Node(ident) => output.push(self.eval_node(ident, ctx)?),
+                Statement::Node(ident, span) => output.push(self.eval_node(ident, span, ctx)?),
                 Statement::Component(component_id) => output.push(self.eval_component(component_id, ctx)?),
                 Statement::For { binding, data } => output.push(self.eval_for(binding, data, ctx)?),
                 Statement::If(cond) => output.push(self.eval_if(cond, ctx)?),
@@ -55,17 +57,18 @@ impl Scope {
         Ok(output)
     }
 
-    fn eval_node(&mut self, ident: StringId, ctx: &mut Context<'_>) -> Result<Blueprint> {
+    fn eval_node(&mut self, ident: StringId, span: Span, ctx: &mut Context<'_>) -> Result<Blueprint> {
         let ident = ctx.strings.get_unchecked(ident);
         let attributes = self.eval_attributes(ctx)?;
         let value = self.statements.take_value().map(|v| const_eval(v, ctx));
         let children = self.consume_scope(ctx)?;
 
         let node = Blueprint::Single(Single {
-            ident: ident.into(),
+            ident,
             children,
             attributes,
             value,
+            span,
         });
         Ok(node)
     }
@@ -73,11 +76,13 @@ impl Scope {
     fn eval_for(&mut self, binding: StringId, data: Expression, ctx: &mut Context<'_>) -> Result<Blueprint> {
         let data = const_eval(data, ctx);
         let binding = ctx.strings.get_unchecked(binding);
-        let body = self.consume_scope(ctx)?;
+        ctx.loops += 1;
+        let body = self.consume_scope(ctx);
+        ctx.loops -= 1;
         let node = Blueprint::For(For {
-            binding: binding.into(),
+            binding,
             data,
-            body,
+            body: body?,
         });
         Ok(node)
     }
@@ -93,7 +98,7 @@ impl Scope {
         for (key, value) in self.statements.take_attributes() {
             let value = const_eval(value, ctx);
             let key = ctx.strings.get_unchecked(key);
-            hm.set(key.into(), value);
+            hm.set(key, value);
         }
 
         Ok(hm)
@@ -123,6 +128,7 @@ impl Scope {
 
     fn eval_component(&mut self, component_id: WidgetComponentId, ctx: &mut Context<'_>) -> Result<Blueprint> {
         let parent = ctx.component_parent();
+        let name = ctx.component_name(component_id);
 
         // Associated functions
         let assoc_functions = self.statements.take_assoc_functions();
@@ -149,11 +155,28 @@ impl Scope {
             slots.set(slot_id, body);
         }
 
-        let body = ctx.load_component(component_id, slots)?;
+        // The `$fallback` slot replaces the body of error boundaries.
+        // Whether or not a component is an error boundary is only known at runtime,
+        // so the slot is still passed on to the component template.
+        let fallback = ctx
+            .strings
+            .lookup(FALLBACK_SLOT)
+            .and_then(|slot_id| slots.get(&slot_id).cloned())
+            .unwrap_or_default();
+
+        // A recursive component gets its body when it's evaluated
+        let recursion_limit = ctx.component_recursion(component_id)?;
+        let body = match recursion_limit {
+            Some(_) => vec![],
+            None => ctx.load_component(component_id, slots)?,
+        };
 
         let component = Component {
             id: component_id,
+            name,
             body,
+            fallback,
+            recursion_limit,
             attributes,
             state,
             assoc_functions,
@@ -189,6 +212,17 @@ mod test {
         assert_eq!(blueprint, single!("a", vec![single!("b")]));
     }
 
+    #[test]
+    fn eval_node_span() {
+        let src = "a\n    b";
+        let mut doc = Document::new(src);
+        let (blueprint, _) = doc.compile().unwrap();
+        let Blueprint::Single(a) = blueprint else { panic!() };
+        let Some(Blueprint::Single(b)) = a.children.first() else { panic!() };
+        assert_eq!((a.span.line, a.span.col), (1, 1));
+        assert_eq!((b.span.line, b.span.col), (2, 5));
+    }
+
     #[test]
     fn eval_nested_nodes() {
         let src = "
@@ -220,7 +254,8 @@ mod test {
         let mut doc = Document::new(src);
         doc.add_component("comp", comp_src.to_template()).unwrap();
         let (blueprint, _) = doc.compile().unwrap();
-        assert!(matches!(blueprint, Blueprint::Component(Component { .. })));
+        let Blueprint::Component(component) = blueprint else { panic!() };
+        assert_eq!(&*component.name, "comp");
     }
 
     #[test]
@@ -245,6 +280,154 @@ mod test {
         assert!(matches!(blueprint, Blueprint::Component(Component { .. })));
     }
 
+    #[test]
+    fn eval_component_fallback() {
+        let src = "
+            @comp
+                $fallback
+                    node 'oh no'
+        ";
+
+        let mut doc = Document::new(src);
+        doc.add_component("comp", "node".to_template()).unwrap();
+        let (blueprint, _) = doc.compile().unwrap();
+        let Blueprint::Component(component) = blueprint else { panic!() };
+        assert_eq!(component.body, vec![single!("node")]);
+        assert_eq!(component.fallback.len(), 1);
+    }
+
+    #[test]
+    fn eval_component_keeps_fallback_slot() {
+        let src = "
+            @comp
+                $fallback
+                    node 'oh no'
+        ";
+
+        let mut doc = Document::new(src);
+        doc.add_component("comp", "$fallback".to_template()).unwrap();
+        let (blueprint, _) = doc.compile().unwrap();
+        let Blueprint::Component(component) = blueprint else { panic!() };
+        assert_eq!(component.body, component.fallback);
+        assert_eq!(component.body.len(), 1);
+    }
+
+    fn recursive_body(component: &Component) -> &Component {
+        let Some(Blueprint::Single(node)) = component.body.first() else { panic!() };
+        let Blueprint::For(for_loop) = &node.children[0] else { panic!() };
+        let Some(Blueprint::Component(component)) = for_loop.body.first() else { panic!() };
+        component
+    }
+
+    #[test]
+    fn eval_recursive_component() {
+        let comp_src = "
+            node
+                for child in children
+                    @comp
+        ";
+
+        let mut doc = Document::new("@comp");
+        doc.add_component("comp", comp_src.to_template()).unwrap();
+        doc.set_recursion_limit(3);
+        let (blueprint, _) = doc.compile().unwrap();
+        let Blueprint::Component(component) = blueprint else { panic!() };
+        assert!(component.recursion_limit.is_none());
+
+        // The component is compiled once inside itself
+        let nested = recursive_body(&component);
+        assert!(nested.recursion_limit.is_none());
+
+        // After that the body is resolved when the component is evaluated
+        let recursive = recursive_body(nested);
+        assert_eq!(recursive.recursion_limit, Some(3));
+        assert!(recursive.body.is_empty());
+    }
+
+    #[test]
+    fn eval_branching_recursive_component() {
+        let comp_src = "
+            node
+                for child in left
+                    @comp
+                for child in right
+                    @comp
+        ";
+
+        let mut doc = Document::new("@comp");
+        doc.add_component("comp", comp_src.to_template()).unwrap();
+        let (blueprint, _) = doc.compile().unwrap();
+        let Blueprint::Component(component) = blueprint else { panic!() };
+
+        let Blueprint::Single(node) = &component.body[0] else { panic!() };
+        for child in &node.children {
+            let Blueprint::For(for_loop) = child else { panic!() };
+            let Blueprint::Component(nested) = &for_loop.body[0] else { panic!() };
+            let Blueprint::Single(node) = &nested.body[0] else { panic!() };
+            for child in &node.children {
+                let Blueprint::For(for_loop) = child else { panic!() };
+                let Blueprint::Component(recursive) = &for_loop.body[0] else { panic!() };
+                assert!(recursive.body.is_empty());
+                assert!(recursive.recursion_limit.is_some());
+            }
+        }
+    }
+
+    #[test]
+    fn eval_circular_component() {
+        let mut doc = Document::new("@comp");
+        doc.add_component("comp", "node\n    @comp".to_template()).unwrap();
+        assert!(matches!(doc.compile(), Err(Error::CircularDependency)));
+    }
+
+    #[test]
+    fn eval_circular_components() {
+        let mut doc = Document::new("@a");
+        doc.add_component("a", "for x in xs\n    @b".to_template()).unwrap();
+        doc.add_component("b", "@a".to_template()).unwrap();
+        assert!(doc.compile().is_ok());
+
+        // If / else doesn't stop the recursion, as every branch is evaluated
+        let mut doc = Document::new("@a");
+        doc.add_component("a", "if x\n    @b".to_template()).unwrap();
+        doc.add_component("b", "@a".to_template()).unwrap();
+        assert!(matches!(doc.compile(), Err(Error::CircularDependency)));
+    }
+
+    #[test]
+    fn component_blueprints() {
+        let mut doc = Document::new("node");
+        doc.add_component("comp", "node 'a'".to_template()).unwrap();
+        doc.add_component("unused", "node [".to_template()).unwrap();
+        let (_, globals) = doc.compile().unwrap();
+
+        // Components are only compiled when they are mounted
+        let blueprints = doc.component_blueprints();
+        assert!(blueprints.get("comp").is_none());
+
+        let compiled = doc.compile_component("comp", &blueprints, &globals).unwrap();
+        let Some(Blueprint::Component(component)) = compiled else { panic!() };
+        assert_eq!(&*component.name, "comp");
+        assert_eq!(component.body.len(), 1);
+        assert!(blueprints.get("comp").is_some());
+
+        assert!(doc.compile_component("unused", &blueprints, &globals).is_err());
+        let missing = doc.compile_component("missing", &blueprints, &globals).unwrap();
+        assert!(missing.is_none());
+    }
+
+    #[test]
+    fn component_blueprint_declarations() {
+        let mut doc = Document::new("node");
+        doc.add_component("comp", "let a = 1\nnode a".to_template()).unwrap();
+        let (_, globals) = doc.compile().unwrap();
+        assert!(globals.get("a").is_none());
+
+        let blueprints = doc.component_blueprints();
+        doc.compile_component("comp", &blueprints, &globals).unwrap();
+        assert!(globals.get("a").is_some());
+    }
+
     #[test]
     fn eval_two_identical_components() {
         let src = "
@@ -1,7 +1,9 @@
+use std::rc::Rc;
+
 use anathema_store::smallmap::SmallMap;
 use anathema_store::storage::strings::{StringId, Strings};
 
-use crate::blueprints::Blueprint;
+use crate::blueprints::{Blueprint, Span};
 use crate::components::ComponentTemplates;
 use crate::error::Result;
 use crate::expressions::Expression;
@@ -18,6 +20,8 @@ pub(crate) struct Context<'vars> {
     pub(crate) strings: &'vars mut Strings,
     pub(crate) slots: SmallMap<StringId, Vec<Blueprint>>,
     pub(crate) current_component_parent: Option<WidgetComponentId>,
+    /// The number of for-loops the current statement is inside of
+    pub(crate) loops: usize,
 }
 
 impl<'vars> Context<'vars> {
@@ -34,6 +38,7 @@ impl<'vars> Context<'vars> {
             strings,
             slots,
             current_component_parent,
+            loops: 0,
         }
     }
 }
@@ -43,17 +48,26 @@ impl Context<'_> {
         self.current_component_parent
     }
 
+    fn component_name(&self, component_id: WidgetComponentId) -> Rc<str> {
+        self.components.name(component_id).unwrap_or_default().into()
+    }
+
     fn fetch(&self, key: &str) -> Option<Expression> {
         self.globals.fetch(key)
     }
 
+    fn component_recursion(&self, component_id: WidgetComponentId) -> Result<Option<usize>> {
+        self.components.recursion(component_id, self.loops > 0)
+    }
+
     fn load_component(
         &mut self,
         parent_component_id: WidgetComponentId,
         slots: SmallMap<StringId, Vec<Blueprint>>,
     ) -> Result<Vec<Blueprint>> {
+        let in_loop = self.loops > 0;
         self.components
-            .load(parent_component_id, self.globals, slots, self.strings)
+            .load(parent_component_id, self.globals, slots, self.strings, in_loop)
     }
 }
 
@@ -64,7 +78,7 @@ pub(crate) enum Statement {
     AssociatedFunction { internal: StringId, external: StringId },
     Component(WidgetComponentId),
     ComponentSlot(StringId),
-    Node(StringId),
+    Node(StringId, Span),
     For { binding: StringId, data: Expression },
     Declaration { binding: StringId, value: Expression },
     If(Expression),
@@ -199,6 +213,7 @@ where
         components: &mut components,
         slots: SmallMap::empty(),
         current_component_parent: None,
+        loops: 0,
     };
 
     f(context)
@@ -238,7 +253,7 @@ mod test {
     }
 
     pub(crate) fn node(id: impl Into<StringId>) -> Statement {
-        Statement::Node(id.into())
+        Statement::Node(id.into(), Span::default())
     }
 
     pub(crate) fn for_loop(binding: impl Into<StringId>, data: impl Into<Expression>) -> Statement {
@@ -1,6 +1,7 @@
 use anathema_store::storage::strings::{StringId, Strings};
 
 use super::Statement;
+use crate::blueprints::Span;
 use crate::components::ComponentTemplates;
 use crate::error::{src_line_no, ParseError, ParseErrorKind, Result};
 use crate::expressions::parser::parse_expr;
@@ -243,10 +244,11 @@ impl<'src, 'strings, 'view> Parser<'src, 'strings, 'view> {
         }
 
         let ident = self.read_ident()?;
+        let (line, col) = src_line_no(self.tokens.previous().1, self.src);
 
         self.tokens.consume_indent();
         self.next_state();
-        Ok(Some(Statement::Node(ident)))
+        Ok(Some(Statement::Node(ident, Span::new(line, col))))
     }
 
     fn parse_for(&mut self) -> Result<Option<Statement>, ParseError> {
@@ -334,7 +336,7 @@ impl<'src, 'strings, 'view> Parser<'src, 'strings, 'view> {
         self.tokens.consume_indent();
 
         let ident = self.read_ident()?;
-        let ident = self.strings.get_unchecked(ident);
+        let ident = self.strings.get_ref_unchecked(ident);
         let component_id = self.components.insert_id(ident.to_owned());
         self.tokens.consume_indent();
 
@@ -1,23 +1,37 @@
+use std::cell::OnceCell;
 use std::collections::HashMap;
 use std::rc::Rc;
 
-#[cfg(not(target_os = "windows"))]
-use anathema_debug::DebugWriter;
 use anathema_store::slab::Slab;
 
 use crate::expressions::Expression;
-use crate::primitives::Primitive;
+use crate::WidgetComponentId;
 
 #[derive(Debug, Default, Clone)]
-pub struct Globals(HashMap<Rc<str>, Expression>);
+pub struct Globals {
+    vars: HashMap<Rc<str>, Expression>,
+    // Declarations of the components compiled after the document,
+    // see `Document::compile_component`
+    pub(crate) components: HashMap<WidgetComponentId, OnceCell<HashMap<Rc<str>, Expression>>>,
+}
 
 impl Globals {
     pub fn new(hm: HashMap<Rc<str>, Expression>) -> Self {
-        Self(hm)
+        Self {
+            vars: hm,
+            components: HashMap::new(),
+        }
     }
 
+    /// Declarations of the document come before the declarations of components
+    /// that were compiled when they were mounted.
     pub fn get(&self, ident: &str) -> Option<&Expression> {
-        self.0.get(ident)
+        self.vars.get(ident).or_else(|| {
+            self.components
+                .values()
+                .filter_map(OnceCell::get)
+                .find_map(|vars| vars.get(ident))
+        })
     }
 
     pub fn take(&mut self) -> Self {
@@ -27,7 +41,7 @@ impl Globals {
 
 impl From<Variables> for Globals {
     fn from(value: Variables) -> Self {
-        Self(value.into())
+        Self::new(value.into())
     }
 }
 
@@ -46,24 +60,6 @@ impl From<VarId> for usize {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
-pub enum Variable {
-    Static(Primitive),
-    Str(Rc<str>),
-}
-
-impl From<&str> for Variable {
-    fn from(value: &str) -> Self {
-        Self::Str(value.into())
-    }
-}
-
-impl From<Primitive> for Variable {
-    fn from(value: Primitive) -> Self {
-        Self::Static(value)
-    }
-}
-
 /// The scope id acts as a path made up of indices
 /// into the scope tree.
 /// E.g `[0, 1, 0]` would point to `root.children[0].children[1].children[0]`.
@@ -137,7 +133,7 @@ impl<const N: usize> From<[u16; N]> for ScopeId {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct RootScope(Scope);
 
 impl Default for RootScope {
@@ -205,7 +201,7 @@ impl RootScope {
 }
 
 /// A scope stores versioned values
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Scope {
     variables: HashMap<Rc<str>, Vec<VarId>>,
     id: ScopeId,
@@ -242,7 +238,7 @@ impl Scope {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct Declarations(HashMap<Rc<str>, Vec<(ScopeId, VarId)>>);
 
 impl Declarations {
@@ -276,7 +272,7 @@ impl Declarations {
 
 /// Variable access, declaration and assignment
 /// during the compilation step.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Variables {
     root: RootScope,
     current: ScopeId,
@@ -371,57 +367,6 @@ impl From<Variables> for HashMap<Rc<str>, Expression> {
     }
 }
 
-#[cfg(not(target_os = "windows"))]
-pub struct ScopeDebug<'a> {
-    level: usize,
-    scope: &'a Scope,
-    store: &'a Slab<VarId, Expression>,
-}
-
-#[cfg(not(target_os = "windows"))]
-impl DebugWriter for ScopeDebug<'_> {
-    fn write(&mut self, output: &mut impl std::fmt::Write) -> std::fmt::Result {
-        let indent = " ".repeat(self.level * 4);
-
-        for (key, var_ids) in self.scope.variables.iter() {
-            let iter = var_ids.iter().filter_map(|id| self.store.get(*id));
-
-            for (cntr, val) in iter.enumerate() {
-                if cntr > 0 {
-                    writeln!(output, ", ")?;
-                }
-                writeln!(output, "{indent}{key}: {val:?}")?;
-            }
-        }
-
-        for child in &self.scope.children {
-            ScopeDebug {
-                level: self.level + 1,
-                scope: child,
-                store: self.store,
-            }
-            .write(output)?;
-        }
-
-        Ok(())
-    }
-}
-
-#[cfg(not(target_os = "windows"))]
-pub struct VariablesDebug<'a>(pub(crate) &'a Variables);
-
-#[cfg(not(target_os = "windows"))]
-impl DebugWriter for VariablesDebug<'_> {
-    fn write(&mut self, output: &mut impl std::fmt::Write) -> std::fmt::Result {
-        ScopeDebug {
-            level: 0,
-            scope: &self.0.root.0,
-            store: &self.0.store,
-        }
-        .write(output)
-    }
-}
-
 #[cfg(test)]
 mod test {
     use super::*;
@@ -0,0 +1,163 @@
+use anathema_geometry::Pos;
+use anathema_state::StateId;
+
+use super::{MouseButton, MouseEvent, MouseState};
+use crate::{Hover, WidgetId};
+
+/// The phase of a drag
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DragPhase {
+    /// The cursor moved with the button held down for the first time
+    Start,
+    /// The cursor moved while dragging
+    Over,
+    /// The button was released
+    Drop,
+}
+
+/// A drag, from the button being pressed until it's released.
+///
+/// Elements can be found with [`Elements::by_id`](crate::Elements::by_id).
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct DragEvent {
+    pub phase: DragPhase,
+    pub button: MouseButton,
+    /// Where the button was pressed
+    pub start: Pos,
+    /// The current position of the cursor
+    pub pos: Pos,
+    /// The innermost element under the cursor when the button was pressed
+    pub source: Option<WidgetId>,
+    /// The innermost element currently under the cursor
+    pub target: Option<WidgetId>,
+}
+
+impl DragEvent {
+    /// The distance between the start of the drag and the cursor
+    pub fn offset(&self) -> Pos {
+        self.pos - self.start
+    }
+}
+
+#[derive(Copy, Clone)]
+struct Pressed {
+    button: MouseButton,
+    start: Pos,
+    source: Option<WidgetId>,
+    component: Option<(WidgetId, StateId)>,
+    dragging: bool,
+}
+
+/// Turns mouse events into drag events.
+///
+/// Drag events are delivered to the component the drag started in,
+/// and to the component under the cursor (if it's a different component).
+#[derive(Default)]
+pub struct Drag {
+    pressed: Option<Pressed>,
+}
+
+impl Drag {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns true if a drag is in progress
+    pub fn is_dragging(&self) -> bool {
+        self.pressed.as_ref().is_some_and(|pressed| pressed.dragging)
+    }
+
+    /// Feed a mouse event to the drag.
+    /// The hover state should be updated with the event first.
+    ///
+    /// Returns the drag event and the widget and state ids of the components receiving it.
+    pub fn update(&mut self, mouse: MouseEvent, hover: &Hover) -> Option<(DragEvent, Vec<(WidgetId, StateId)>)> {
+        let (phase, pressed) = match mouse.state {
+            MouseState::Down(button) => {
+                self.pressed = Some(Pressed {
+                    button,
+                    start: mouse.pos(),
+                    source: hover.element(),
+                    component: hover.component(),
+                    dragging: false,
+                });
+                return None;
+            }
+            MouseState::Drag(button) => {
+                let pressed = self.pressed.as_mut().filter(|pressed| pressed.button == button)?;
+                let phase = match std::mem::replace(&mut pressed.dragging, true) {
+                    false => DragPhase::Start,
+                    true => DragPhase::Over,
+                };
+                (phase, *pressed)
+            }
+            MouseState::Up(button) => {
+                let pressed = self
+                    .pressed
+                    .take()
+                    .filter(|pressed| pressed.button == button && pressed.dragging)?;
+                (DragPhase::Drop, pressed)
+            }
+            _ => return None,
+        };
+
+        let event = DragEvent {
+            phase,
+            button: pressed.button,
+            start: pressed.start,
+            pos: mouse.pos(),
+            source: pressed.source,
+            target: hover.element(),
+        };
+
+        let mut recipients = pressed.component.into_iter().collect::<Vec<_>>();
+        if let Some(component) = hover.component() {
+            if !recipients.contains(&component) {
+                recipients.push(component);
+            }
+        }
+
+        Some((event, recipients))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn mouse(state: MouseState, x: u16) -> MouseEvent {
+        MouseEvent {
+            x,
+            y: 0,
+            state,
+            clicks: 0,
+        }
+    }
+
+    fn phase(drag: &mut Drag, state: MouseState, x: u16) -> Option<DragPhase> {
+        drag.update(mouse(state, x), &Hover::new())
+            .map(|(event, _)| event.phase)
+    }
+
+    #[test]
+    fn drag_lifecycle() {
+        let left = MouseButton::Left;
+        let mut drag = Drag::new();
+
+        assert_eq!(phase(&mut drag, MouseState::Down(left), 1), None);
+        assert!(!drag.is_dragging());
+        assert_eq!(phase(&mut drag, MouseState::Drag(left), 2), Some(DragPhase::Start));
+        assert_eq!(phase(&mut drag, MouseState::Drag(MouseButton::Right), 3), None);
+
+        let (event, _) = drag.update(mouse(MouseState::Drag(left), 4), &Hover::new()).unwrap();
+        assert_eq!(event.phase, DragPhase::Over);
+        assert_eq!(event.offset(), Pos::new(3, 0));
+
+        assert_eq!(phase(&mut drag, MouseState::Up(left), 4), Some(DragPhase::Drop));
+        assert!(!drag.is_dragging());
+
+        // A click is not a drag
+        assert_eq!(phase(&mut drag, MouseState::Down(left), 1), None);
+        assert_eq!(phase(&mut drag, MouseState::Up(left), 1), None);
+    }
+}
@@ -0,0 +1,103 @@
+use std::ops::Range;
+
+/// Input method (IME) composition, e.g for CJK input.
+///
+/// While composing, the [`Preedit`] text should be rendered at the cursor
+/// without being inserted into the text.
+/// Once the composition is committed the text should be inserted.
+///
+/// Terminals draw the preedit text themselves, at the terminal cursor,
+/// so terminal backends only report the committed text (see `TuiBackendBuilder::enable_ime`).
+/// Place the cursor with `PaintCtx::set_cursor` for the preedit text to show up in the right place.
+///
+/// ```
+/// # use anathema_widgets::components::events::{ImeEvent, Preedit};
+/// let preedit = Preedit::new("nihao").with_cursor(5).with_selection(0..2);
+/// assert_eq!(preedit.selected(), Some("ni"));
+///
+/// let mut text = String::new();
+/// for event in [
+///     ImeEvent::Start,
+///     ImeEvent::Preedit(preedit),
+///     ImeEvent::Commit("你好".into()),
+/// ] {
+///     if let ImeEvent::Commit(committed) = event {
+///         text.push_str(&committed);
+///     }
+/// }
+/// assert_eq!(text, "你好");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImeEvent {
+    /// Composition started
+    Start,
+    /// The preedit text changed
+    Preedit(Preedit),
+    /// The composition finished and the text should be inserted
+    Commit(String),
+    /// The composition was cancelled, and the preedit text should be removed
+    Cancel,
+}
+
+impl ImeEvent {
+    /// The preedit text, if the composition is ongoing
+    pub fn preedit(&self) -> Option<&Preedit> {
+        match self {
+            Self::Preedit(preedit) => Some(preedit),
+            _ => None,
+        }
+    }
+
+    /// The committed text, if the composition finished
+    pub fn commit(&self) -> Option<&str> {
+        match self {
+            Self::Commit(text) => Some(text),
+            _ => None,
+        }
+    }
+}
+
+/// The text being composed.
+///
+/// The cursor and the selection are byte offsets into the text,
+/// and always fall on a char boundary.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Preedit {
+    pub text: String,
+    /// Where the cursor is drawn. `None` hides the cursor
+    pub cursor: Option<usize>,
+    /// The part of the text being converted, usually highlighted
+    pub selection: Option<Range<usize>>,
+}
+
+impl Preedit {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            cursor: None,
+            selection: None,
+        }
+    }
+
+    pub fn with_cursor(mut self, cursor: usize) -> Self {
+        self.cursor = Some(cursor);
+        self
+    }
+
+    pub fn with_selection(mut self, selection: Range<usize>) -> Self {
+        self.selection = Some(selection);
+        self
+    }
+
+    /// The text before the cursor, e.g to find the width of the text in front of the cursor.
+    /// This is the entire text if the cursor is hidden.
+    pub fn before_cursor(&self) -> &str {
+        let cursor = self.cursor.unwrap_or(self.text.len());
+        self.text.get(..cursor).unwrap_or(&self.text)
+    }
+
+    /// The selected text, if any
+    pub fn selected(&self) -> Option<&str> {
+        self.text.get(self.selection.clone()?)
+    }
+}
@@ -1,18 +1,79 @@
-#[derive(Debug, Copy, Clone)]
+/// Repeat and release events are only reported by terminals supporting
+/// the kitty keyboard protocol, see `TuiBackendBuilder::enable_kitty_keyboard`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum KeyState {
     Press,
     Repeat,
     Release,
 }
 
+/// Key events are created with [`KeyEvent::new`] or [`KeyEvent::press`],
+/// as more modifiers can be added without a major version.
 #[derive(Debug, Copy, Clone)]
+#[non_exhaustive]
 pub struct KeyEvent {
     pub code: KeyCode,
     pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    /// The super key (Windows / Command key).
+    /// Only reported by terminals supporting the kitty keyboard protocol.
+    pub super_key: bool,
     pub state: KeyState,
 }
 
 impl KeyEvent {
+    /// A key event without any modifiers
+    pub fn new(code: KeyCode, state: KeyState) -> Self {
+        Self {
+            code,
+            ctrl: false,
+            alt: false,
+            shift: false,
+            super_key: false,
+            state,
+        }
+    }
+
+    /// A key press without any modifiers
+    pub fn press(code: KeyCode) -> Self {
+        Self::new(code, KeyState::Press)
+    }
+
+    /// The same key with the control key held down
+    pub fn with_ctrl(mut self) -> Self {
+        self.ctrl = true;
+        self
+    }
+
+    /// The same key with the alt key held down
+    pub fn with_alt(mut self) -> Self {
+        self.alt = true;
+        self
+    }
+
+    /// The same key with the shift key held down
+    pub fn with_shift(mut self) -> Self {
+        self.shift = true;
+        self
+    }
+
+    /// The same key with the super key held down
+    pub fn with_super(mut self) -> Self {
+        self.super_key = true;
+        self
+    }
+
+    /// Returns true if the key was released
+    pub fn is_release(&self) -> bool {
+        self.state == KeyState::Release
+    }
+
+    /// Returns true if the key is held down and repeating
+    pub fn is_repeat(&self) -> bool {
+        self.state == KeyState::Repeat
+    }
+
     pub fn get_char(&self) -> Option<char> {
         match self.code {
             KeyCode::Char(c) => Some(c),
@@ -21,7 +82,7 @@ impl KeyEvent {
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum KeyCode {
     Char(char),
     Tab,
@@ -1,26 +1,43 @@
+pub use self::drag::{Drag, DragEvent, DragPhase};
+pub use self::ime::{ImeEvent, Preedit};
 pub use self::key::{KeyCode, KeyEvent, KeyState};
-pub use self::mouse::{MouseButton, MouseEvent, MouseState};
+pub use self::mouse::{ClickCounter, MouseButton, MouseEvent, MouseState};
+pub use self::raw::RawEvent;
+pub use self::user::{UserEvent, UserEventTarget};
 
+mod drag;
+mod ime;
 mod key;
 mod mouse;
+mod raw;
+mod user;
 
 /// An event
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Clone)]
 pub enum Event {
     /// No op
     Noop,
     /// Stop the runtime
     Stop,
-    /// Terminal lost focus (not widely supported)
-    Blur,
     /// Terminal gained focus (not widely supported)
-    Focus,
+    FocusGained,
+    /// Terminal lost focus (not widely supported)
+    FocusLost,
     /// Key event
     Key(KeyEvent),
     /// Mouse event
     Mouse(MouseEvent),
+    /// Text pasted into the terminal (requires bracketed paste)
+    Paste(String),
+    /// Input method composition (the terminal backends only report committed text)
+    Ime(ImeEvent),
     /// Window was resized
     Resize(u16, u16),
+    /// The process continued after being suspended.
+    /// The runtime redraws everything.
+    Resume,
+    /// An event without an equivalent in anathema (requires raw events to be enabled in the backend)
+    Raw(RawEvent),
 }
 
 impl Event {
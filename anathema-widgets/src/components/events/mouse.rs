@@ -1,13 +1,35 @@
+use std::time::Duration;
+
 use anathema_geometry::Pos;
 
+use crate::time::Instant;
+
+/// Mouse events are created with [`MouseEvent::new`],
+/// as more fields can be added without a major version.
 #[derive(Debug, Copy, Clone)]
+#[non_exhaustive]
 pub struct MouseEvent {
     pub x: u16,
     pub y: u16,
     pub state: MouseState,
+    /// Number of consecutive clicks at the same position,
+    /// e.g `2` for a double click.
+    /// This is zero for anything but button down and up events.
+    pub clicks: u8,
 }
 
 impl MouseEvent {
+    /// A mouse event at the position, without any clicks
+    pub fn new(x: u16, y: u16, state: MouseState) -> Self {
+        Self { x, y, state, clicks: 0 }
+    }
+
+    /// The same event with the number of consecutive clicks
+    pub fn with_clicks(mut self, clicks: u8) -> Self {
+        self.clicks = clicks;
+        self
+    }
+
     /// Translate the x and y position into a `Position`
     pub fn pos(&self) -> Pos {
         (self.x, self.y).into()
@@ -33,9 +55,33 @@ impl MouseEvent {
     pub fn lsb_up(&self) -> bool {
         matches!(self.state, MouseState::Up(MouseButton::Left))
     }
+
+    /// The direction of a scroll event, or `None` if this isn't a scroll event
+    pub fn scroll_delta(&self) -> Option<Pos> {
+        match self.state {
+            MouseState::ScrollUp => Some(Pos::new(0, -1)),
+            MouseState::ScrollDown => Some(Pos::new(0, 1)),
+            MouseState::ScrollLeft => Some(Pos::new(-1, 0)),
+            MouseState::ScrollRight => Some(Pos::new(1, 0)),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the button was pressed twice in a row
+    pub fn is_double_click(&self) -> bool {
+        matches!(self.state, MouseState::Down(_)) && self.clicks == 2
+    }
+
+    /// Returns true if the button was pressed three times in a row
+    pub fn is_triple_click(&self) -> bool {
+        matches!(self.state, MouseState::Down(_)) && self.clicks == 3
+    }
 }
 
+/// New states can be added without a major version,
+/// so matches on the state need a wildcard arm.
 #[derive(Debug, Copy, Clone)]
+#[non_exhaustive]
 pub enum MouseState {
     Down(MouseButton),
     Up(MouseButton),
@@ -45,11 +91,108 @@ pub enum MouseState {
     ScrollDown,
     ScrollLeft,
     ScrollRight,
+    /// The cursor entered the component
+    Enter,
+    /// The cursor left the component
+    Leave,
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum MouseButton {
     Left,
     Middle,
     Right,
 }
+
+/// Counts consecutive clicks.
+///
+/// A button press counts as another click if it's the same button,
+/// at the same position, within the interval of the previous press.
+/// Backends use this to set [`MouseEvent::clicks`].
+#[derive(Debug)]
+pub struct ClickCounter {
+    interval: Duration,
+    last: Option<(Instant, MouseButton, u16, u16)>,
+    clicks: u8,
+}
+
+impl ClickCounter {
+    /// The default interval between two clicks
+    pub const DEFAULT_INTERVAL: Duration = Duration::from_millis(500);
+
+    /// Create a new click counter with the given interval
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last: None,
+            clicks: 0,
+        }
+    }
+
+    /// Set the click count of the event.
+    pub fn count(&mut self, mut event: MouseEvent, now: Instant) -> MouseEvent {
+        event.clicks = match event.state {
+            MouseState::Down(button) => {
+                let is_repeat = self.last.is_some_and(|(prev, prev_button, x, y)| {
+                    now.saturating_duration_since(prev) <= self.interval
+                        && prev_button == button
+                        && (x, y) == (event.x, event.y)
+                });
+
+                self.clicks = match is_repeat {
+                    true => self.clicks.saturating_add(1),
+                    false => 1,
+                };
+                self.last = Some((now, button, event.x, event.y));
+                self.clicks
+            }
+            MouseState::Up(_) => self.clicks,
+            _ => 0,
+        };
+        event
+    }
+}
+
+impl Default for ClickCounter {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_INTERVAL)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const MS: Duration = Duration::from_millis(1);
+
+    fn down(x: u16) -> MouseEvent {
+        MouseEvent {
+            x,
+            y: 0,
+            state: MouseState::Down(MouseButton::Left),
+            clicks: 0,
+        }
+    }
+
+    #[test]
+    fn count_clicks() {
+        let mut counter = ClickCounter::new(MS * 100);
+        let now = Instant::now();
+
+        assert_eq!(counter.count(down(0), now).clicks, 1);
+        assert!(counter.count(down(0), now + MS * 50).is_double_click());
+        assert!(counter.count(down(0), now + MS * 100).is_triple_click());
+
+        // Too slow
+        assert_eq!(counter.count(down(0), now + MS * 300).clicks, 1);
+
+        // Different position
+        assert_eq!(counter.count(down(1), now + MS * 310).clicks, 1);
+
+        let up = MouseEvent {
+            state: MouseState::Up(MouseButton::Left),
+            ..down(1)
+        };
+        assert_eq!(counter.count(up, now + MS * 320).clicks, 1);
+    }
+}
@@ -0,0 +1,41 @@
+use std::any::Any;
+use std::sync::Arc;
+
+/// An event from the backend that doesn't have an equivalent [`Event`](super::Event),
+/// e.g a media key, or an input event the terminal library added in a later version.
+///
+/// The value is the event of the backend, e.g a `crossterm::event::KeyEvent`.
+/// Backends only forward raw events when enabled, see `TuiBackendBuilder::enable_raw_events`.
+///
+/// ```
+/// # use anathema_widgets::components::events::RawEvent;
+/// struct MediaKey(&'static str);
+///
+/// let event = RawEvent::new(MediaKey("play"));
+/// assert_eq!(event.get::<MediaKey>().map(|key| key.0), Some("play"));
+/// assert!(event.get::<String>().is_none());
+/// ```
+#[derive(Clone)]
+pub struct RawEvent(Arc<dyn Any + Send + Sync>);
+
+impl RawEvent {
+    pub fn new<T: 'static + Send + Sync>(value: T) -> Self {
+        Self(Arc::new(value))
+    }
+
+    /// Get the value of the event if it's of type `T`
+    pub fn get<T: 'static>(&self) -> Option<&T> {
+        self.0.downcast_ref()
+    }
+
+    /// Returns true if the value of the event is of type `T`
+    pub fn is<T: 'static>(&self) -> bool {
+        self.0.is::<T>()
+    }
+}
+
+impl std::fmt::Debug for RawEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RawEvent").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,73 @@
+use std::any::Any;
+
+/// The components receiving a [`UserEvent`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum UserEventTarget {
+    /// The component that currently has focus
+    Focused,
+    /// Every component in the tree
+    All,
+}
+
+/// An event created by the application, outside of the runtime.
+///
+/// User events are sent through the [`Emitter`](crate::components::Emitter)
+/// and received by [`Component::on_user_event`](crate::components::Component::on_user_event).
+///
+/// ```
+/// # use anathema_widgets::components::events::UserEvent;
+/// struct Connected(u16);
+///
+/// let event = UserEvent::new(Connected(8080));
+/// assert_eq!(event.get::<Connected>().map(|c| c.0), Some(8080));
+/// assert!(event.get::<String>().is_none());
+/// ```
+pub struct UserEvent {
+    target: UserEventTarget,
+    payload: Box<dyn Any + Send + Sync>,
+}
+
+impl UserEvent {
+    /// Create an event for the focused component
+    pub fn new<T: 'static + Send + Sync>(value: T) -> Self {
+        Self {
+            target: UserEventTarget::Focused,
+            payload: Box::new(value),
+        }
+    }
+
+    /// Create an event for every component
+    pub fn broadcast<T: 'static + Send + Sync>(value: T) -> Self {
+        Self {
+            target: UserEventTarget::All,
+            payload: Box::new(value),
+        }
+    }
+
+    pub(crate) fn from_payload(target: UserEventTarget, payload: Box<dyn Any + Send + Sync>) -> Self {
+        Self { target, payload }
+    }
+
+    /// The components receiving the event
+    pub fn target(&self) -> UserEventTarget {
+        self.target
+    }
+
+    /// Get the value of the event if it's of type `T`
+    pub fn get<T: 'static>(&self) -> Option<&T> {
+        self.payload.downcast_ref()
+    }
+
+    /// Returns true if the value of the event is of type `T`
+    pub fn is<T: 'static>(&self) -> bool {
+        self.payload.is::<T>()
+    }
+}
+
+impl std::fmt::Debug for UserEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UserEvent")
+            .field("target", &self.target)
+            .finish_non_exhaustive()
+    }
+}
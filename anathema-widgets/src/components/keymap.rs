@@ -0,0 +1,302 @@
+use std::borrow::Cow;
+
+use anathema_templates::WidgetComponentId;
+
+use super::events::{KeyCode, KeyEvent, KeyState};
+use crate::error::{Error, Result};
+
+/// A single key press, with or without `ctrl`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Chord {
+    pub code: KeyCode,
+    pub ctrl: bool,
+}
+
+impl Chord {
+    fn parse(src: &str, chord: &str) -> Result<Self> {
+        let error = || Error::InvalidShortcut(src.to_string());
+
+        let (ctrl, key) = match chord.rsplit_once('+') {
+            // `ctrl++` or `+`
+            Some((modifier, "")) if modifier.is_empty() || modifier.ends_with('+') => {
+                (modifier.trim_end_matches('+'), "+")
+            }
+            Some((modifier, key)) => (modifier, key),
+            None => ("", chord),
+        };
+
+        let ctrl = match ctrl {
+            "" => false,
+            "ctrl" => true,
+            _ => return Err(error()),
+        };
+
+        let code = match key {
+            "enter" => KeyCode::Enter,
+            "esc" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "backspace" => KeyCode::Backspace,
+            "delete" => KeyCode::Delete,
+            "insert" => KeyCode::Insert,
+            "space" => KeyCode::Char(' '),
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            key => {
+                let mut chars = key.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => KeyCode::Char(c),
+                    (Some('f'), Some(_)) => key[1..].parse().map(KeyCode::F).map_err(|_| error())?,
+                    _ => return Err(error()),
+                }
+            }
+        };
+
+        Ok(Self { code, ctrl })
+    }
+
+    fn matches(&self, key: &KeyEvent) -> bool {
+        self.code == key.code && self.ctrl == key.ctrl
+    }
+}
+
+/// A sequence of one or more chords, separated by whitespace.
+///
+/// ```
+/// # use anathema_widgets::components::Shortcut;
+/// let save = Shortcut::parse("ctrl+s").unwrap();
+/// let top = Shortcut::parse("g g").unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Shortcut(Box<[Chord]>);
+
+impl Shortcut {
+    /// Parse a shortcut
+    pub fn parse(src: &str) -> Result<Self> {
+        let chords = src
+            .split_whitespace()
+            .map(|chord| Chord::parse(src, chord))
+            .collect::<Result<Box<[_]>>>()?;
+
+        match chords.is_empty() {
+            true => Err(Error::InvalidShortcut(src.to_string())),
+            false => Ok(Self(chords)),
+        }
+    }
+
+    /// True if the keys are the start of the shortcut, or the entire shortcut
+    pub fn starts_with(&self, keys: &[KeyEvent]) -> bool {
+        self.0.len() >= keys.len() && self.0.iter().zip(keys).all(|(chord, key)| chord.matches(key))
+    }
+
+    /// True if the keys are the entire shortcut
+    pub fn is_match(&self, keys: &[KeyEvent]) -> bool {
+        self.0.len() == keys.len() && self.starts_with(keys)
+    }
+}
+
+/// Decides when a shortcut is active
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ShortcutScope {
+    /// Only while the component has focus
+    Focused,
+    /// Regardless of which component has focus
+    Global,
+}
+
+struct Binding {
+    component_id: WidgetComponentId,
+    name: Cow<'static, str>,
+    shortcut: Shortcut,
+    scope: ShortcutScope,
+}
+
+/// The outcome of looking up a sequence of keys in the [`Keymap`]
+#[derive(Debug, PartialEq)]
+pub enum KeymapMatch {
+    /// The keys are a shortcut belonging to the component
+    Shortcut(WidgetComponentId, Cow<'static, str>),
+    /// The keys are the start of a longer shortcut
+    Pending,
+    /// The keys are not a shortcut
+    None,
+}
+
+/// Shortcuts registered by components.
+///
+/// The keymap doesn't hold on to any keys: the runtime buffers the keys
+/// that are the start of a shortcut, and looks up the buffered keys.
+/// Shortcuts belonging to the focused component take
+/// priority over global shortcuts.
+#[derive(Default)]
+pub struct Keymap {
+    bindings: Vec<Binding>,
+}
+
+impl Keymap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a shortcut for a component.
+    /// Registering the same name twice replaces the previous shortcut.
+    pub fn register(
+        &mut self,
+        component_id: WidgetComponentId,
+        name: Cow<'static, str>,
+        shortcut: Shortcut,
+        scope: ShortcutScope,
+    ) {
+        self.unregister(component_id, &name);
+        self.bindings.push(Binding {
+            component_id,
+            name,
+            shortcut,
+            scope,
+        });
+    }
+
+    /// Remove a shortcut by name
+    pub fn unregister(&mut self, component_id: WidgetComponentId, name: &str) {
+        self.bindings
+            .retain(|binding| binding.component_id != component_id || binding.name != name);
+    }
+
+    /// Remove all shortcuts belonging to a component
+    pub fn unregister_all(&mut self, component_id: WidgetComponentId) {
+        self.bindings.retain(|binding| binding.component_id != component_id);
+    }
+
+    /// Look up a sequence of key presses.
+    /// Only key presses are considered, anything else resolves to [`KeymapMatch::None`].
+    pub fn lookup(&self, keys: &[KeyEvent], focused: Option<WidgetComponentId>) -> KeymapMatch {
+        if keys.is_empty() || keys.iter().any(|key| key.state != KeyState::Press) {
+            return KeymapMatch::None;
+        }
+
+        let complete = |binding: &&Binding| is_active(binding, focused) && binding.shortcut.is_match(keys);
+        let binding = self
+            .bindings
+            .iter()
+            .filter(complete)
+            .min_by_key(|binding| binding.scope == ShortcutScope::Global);
+
+        match binding {
+            Some(binding) => KeymapMatch::Shortcut(binding.component_id, binding.name.clone()),
+            None if self.any_active(keys, focused) => KeymapMatch::Pending,
+            None => KeymapMatch::None,
+        }
+    }
+
+    fn any_active(&self, keys: &[KeyEvent], focused: Option<WidgetComponentId>) -> bool {
+        self.bindings
+            .iter()
+            .any(|binding| is_active(binding, focused) && binding.shortcut.starts_with(keys))
+    }
+}
+
+fn is_active(binding: &Binding, focused: Option<WidgetComponentId>) -> bool {
+    match binding.scope {
+        ShortcutScope::Global => true,
+        ShortcutScope::Focused => focused == Some(binding.component_id),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn key(c: char) -> KeyEvent {
+        KeyEvent::press(KeyCode::Char(c))
+    }
+
+    fn ctrl(c: char) -> KeyEvent {
+        key(c).with_ctrl()
+    }
+
+    fn keymap(shortcuts: &[(usize, &'static str, &str, ShortcutScope)]) -> Keymap {
+        let mut keymap = Keymap::new();
+        for &(id, name, shortcut, scope) in shortcuts {
+            keymap.register(id.into(), name.into(), Shortcut::parse(shortcut).unwrap(), scope);
+        }
+        keymap
+    }
+
+    #[test]
+    fn parse_shortcuts() {
+        let shortcut = Shortcut::parse("ctrl+s").unwrap();
+        assert_eq!(
+            &*shortcut.0,
+            &[Chord {
+                code: KeyCode::Char('s'),
+                ctrl: true
+            }]
+        );
+
+        let shortcut = Shortcut::parse("g  g f12 ctrl++").unwrap();
+        let codes = shortcut.0.iter().map(|chord| chord.code).collect::<Vec<_>>();
+        assert_eq!(
+            codes,
+            vec![
+                KeyCode::Char('g'),
+                KeyCode::Char('g'),
+                KeyCode::F(12),
+                KeyCode::Char('+')
+            ]
+        );
+
+        assert!(Shortcut::parse("").is_err());
+        assert!(Shortcut::parse("alt+x").is_err());
+        assert!(Shortcut::parse("ctrl+nope").is_err());
+    }
+
+    #[test]
+    fn single_chord() {
+        let keymap = keymap(&[(0, "save", "ctrl+s", ShortcutScope::Global)]);
+        assert_eq!(keymap.lookup(&[key('s')], None), KeymapMatch::None);
+        assert_eq!(
+            keymap.lookup(&[ctrl('s')], None),
+            KeymapMatch::Shortcut(0.into(), "save".into())
+        );
+    }
+
+    #[test]
+    fn sequence() {
+        let keymap = keymap(&[(0, "top", "g g", ShortcutScope::Global)]);
+        assert_eq!(keymap.lookup(&[key('g')], None), KeymapMatch::Pending);
+        assert_eq!(
+            keymap.lookup(&[key('g'), key('g')], None),
+            KeymapMatch::Shortcut(0.into(), "top".into())
+        );
+
+        // An unrelated key breaks the sequence
+        assert_eq!(keymap.lookup(&[key('g'), key('x')], None), KeymapMatch::None);
+        assert_eq!(keymap.lookup(&[key('g'), key('g'), key('g')], None), KeymapMatch::None);
+    }
+
+    #[test]
+    fn focused_shortcuts() {
+        let mut keymap = keymap(&[
+            (0, "global", "x", ShortcutScope::Global),
+            (1, "focused", "x", ShortcutScope::Focused),
+        ]);
+
+        assert_eq!(
+            keymap.lookup(&[key('x')], None),
+            KeymapMatch::Shortcut(0.into(), "global".into())
+        );
+        assert_eq!(
+            keymap.lookup(&[key('x')], Some(1.into())),
+            KeymapMatch::Shortcut(1.into(), "focused".into())
+        );
+
+        keymap.unregister_all(0.into());
+        assert_eq!(keymap.lookup(&[key('x')], None), KeymapMatch::None);
+    }
+}
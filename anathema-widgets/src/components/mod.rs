@@ -1,8 +1,10 @@
 use std::any::Any;
 use std::borrow::Cow;
 use std::collections::VecDeque;
+use std::fmt::{self, Debug};
 use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
 use std::time::Duration;
 
 use anathema_state::{AnyState, CommonVal, SharedState, State, StateId, Value};
@@ -11,17 +13,27 @@ use anathema_store::storage::strings::{StringId, Strings};
 use anathema_templates::WidgetComponentId;
 use flume::SendError;
 
-use self::events::{Event, KeyEvent, MouseEvent};
+use self::events::{DragEvent, Event, ImeEvent, KeyEvent, MouseEvent, RawEvent, UserEvent};
+pub use self::keymap::{Keymap, KeymapMatch, Shortcut, ShortcutScope};
+pub use self::mounts::{MountId, MountRequest, Mounts, ScreenRequest};
+pub use self::services::Services;
+pub use self::shutdown::Shutdown;
+pub use self::timers::{TimerId, Timers};
 use crate::expressions::Either;
 use crate::layout::Viewport;
 use crate::nodes::ExternalState;
 use crate::widget::Parent;
-use crate::Elements;
+use crate::{Attributes, Elements};
 
 pub mod events;
+mod keymap;
+mod mounts;
+mod services;
+mod shutdown;
+mod timers;
 
 pub type ComponentFn = dyn Fn() -> Box<dyn AnyComponent>;
-pub type StateFn = dyn FnMut() -> Box<dyn AnyState>;
+pub type StateFn = dyn FnMut(&Attributes<'_>) -> Box<dyn AnyState>;
 
 enum ComponentType {
     Component(Option<Box<dyn AnyComponent>>, Option<Box<dyn AnyState>>),
@@ -57,8 +69,21 @@ impl ComponentRegistry {
         C: Component + 'static,
         S: State + 'static,
     {
-        let comp_type =
-            ComponentType::Prototype(Box::new(move || Box::new(proto())), Box::new(move || Box::new(state())));
+        self.add_prototype_with_attributes(id, proto, move |_: &Attributes<'_>| state());
+    }
+
+    /// Same as `add_prototype` except the state is created from the attributes of the instance
+    pub fn add_prototype_with_attributes<FC, FS, C, S>(&mut self, id: WidgetComponentId, proto: FC, mut state: FS)
+    where
+        FC: 'static + Fn() -> C,
+        FS: 'static + FnMut(&Attributes<'_>) -> S,
+        C: Component + 'static,
+        S: State + 'static,
+    {
+        let comp_type = ComponentType::Prototype(
+            Box::new(move || Box::new(proto())),
+            Box::new(move |attributes| Box::new(state(attributes))),
+        );
 
         self.0.insert_at(id, comp_type);
     }
@@ -67,11 +92,15 @@ impl ComponentRegistry {
     ///
     /// Panics if the component isn't registered.
     /// This shouldn't happen as the statement eval should catch this.
-    pub fn get(&mut self, id: WidgetComponentId) -> Option<(ComponentKind, Box<dyn AnyComponent>, Box<dyn AnyState>)> {
+    pub fn get(
+        &mut self,
+        id: WidgetComponentId,
+        attributes: &Attributes<'_>,
+    ) -> Option<(ComponentKind, Box<dyn AnyComponent>, Box<dyn AnyState>)> {
         match self.0.get_mut(id) {
             Some(component) => match component {
                 ComponentType::Component(comp, state) => Some((ComponentKind::Instance, comp.take()?, state.take()?)),
-                ComponentType::Prototype(proto, state) => Some((ComponentKind::Prototype, proto(), state())),
+                ComponentType::Prototype(proto, state) => Some((ComponentKind::Prototype, proto(), state(attributes))),
             },
             None => panic!(),
         }
@@ -110,6 +139,12 @@ impl<T> From<WidgetComponentId> for ComponentId<T> {
     }
 }
 
+impl<T> From<ComponentId<T>> for WidgetComponentId {
+    fn from(value: ComponentId<T>) -> Self {
+        value.0
+    }
+}
+
 impl<T> Clone for ComponentId<T> {
     fn clone(&self) -> Self {
         *self
@@ -118,53 +153,288 @@ impl<T> Clone for ComponentId<T> {
 
 impl<T> Copy for ComponentId<T> {}
 
+/// The order in which queued messages are handled.
+/// Messages of the same priority are handled in the order they were sent.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    /// Handled before any other message
+    High,
+    #[default]
+    Normal,
+    /// Handled once there are no other messages, e.g for background updates
+    Low,
+}
+
 pub struct ViewMessage {
-    pub(super) payload: Box<dyn Any + Send + Sync>,
+    pub(super) payload: Box<dyn Any + Send>,
     pub(super) recipient: WidgetComponentId,
+    priority: Priority,
+    tag: Option<Cow<'static, str>>,
 }
 
 impl ViewMessage {
+    pub(crate) fn new(recipient: WidgetComponentId, payload: Box<dyn Any + Send>) -> Self {
+        Self {
+            payload,
+            recipient,
+            priority: Priority::Normal,
+            tag: None,
+        }
+    }
+
     pub fn recipient(&self) -> WidgetComponentId {
         self.recipient
     }
 
-    pub fn payload(self) -> Box<dyn Any + Send + Sync> {
+    pub fn priority(&self) -> Priority {
+        self.priority
+    }
+
+    /// Queued messages with the same recipient and tag are coalesced,
+    /// so only the latest is handled
+    pub fn tag(&self) -> Option<&str> {
+        self.tag.as_deref()
+    }
+
+    /// Replace the payload with the payload of a newer message
+    pub fn replace(&mut self, newer: ViewMessage) {
+        self.payload = newer.payload;
+    }
+
+    pub fn payload(self) -> Box<dyn Any + Send> {
         self.payload
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct Emitter(pub(crate) flume::Sender<ViewMessage>);
+type BroadcastFn = dyn Fn() -> Box<dyn Any + Send + Sync> + Send + Sync;
 
-impl From<flume::Sender<ViewMessage>> for Emitter {
-    fn from(value: flume::Sender<ViewMessage>) -> Self {
-        Self(value)
+/// A message for every instance of a component
+pub struct BroadcastMessage {
+    component: Cow<'static, str>,
+    payload: Box<BroadcastFn>,
+}
+
+impl BroadcastMessage {
+    /// Name of the component
+    pub fn component(&self) -> &str {
+        &self.component
+    }
+
+    /// Create a new copy of the message
+    pub fn payload(&self) -> Box<dyn Any + Send + Sync> {
+        (self.payload)()
+    }
+}
+
+/// A message sent through the [`Emitter`]
+pub enum Message {
+    View(ViewMessage),
+    Broadcast(BroadcastMessage),
+    User(UserEvent),
+    Shutdown(Shutdown),
+    Terminal(TerminalRequest),
+    /// Text for assistive technology, see [`Context::announce`]
+    Announce(String),
+}
+
+/// A request to the terminal, handled by the backend.
+/// Backends without a terminal ignore the requests.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TerminalRequest {
+    /// Set the title of the terminal window
+    SetTitle(String),
+    /// Ring the bell, which terminals generally show as a notification
+    /// (or an urgency hint) when the window isn't focused
+    Bell,
+    /// Copy the text to the clipboard
+    Copy(String),
+}
+
+impl Message {
+    /// The priority of the message.
+    /// Only view messages can have a priority other than [`Priority::Normal`],
+    /// except for shutdown requests, which are always handled first.
+    pub fn priority(&self) -> Priority {
+        match self {
+            Message::View(msg) => msg.priority,
+            Message::Shutdown(_) => Priority::High,
+            Message::Broadcast(_) | Message::User(_) | Message::Terminal(_) | Message::Announce(_) => Priority::Normal,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Emitter {
+    sender: flume::Sender<Message>,
+    // Wakes the runtime if it's waiting for events
+    waker: Option<Arc<dyn Fn() + Send + Sync>>,
+}
+
+impl From<flume::Sender<Message>> for Emitter {
+    fn from(sender: flume::Sender<Message>) -> Self {
+        Self { sender, waker: None }
+    }
+}
+
+impl Debug for Emitter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Emitter")
+            .field("sender", &self.sender)
+            .finish_non_exhaustive()
     }
 }
 
 impl Emitter {
+    /// Call `waker` after every message, e.g to wake a runtime that is waiting for events
+    pub fn with_waker(self, waker: Arc<dyn Fn() + Send + Sync>) -> Self {
+        Self {
+            waker: Some(waker),
+            ..self
+        }
+    }
+
     pub fn emit<T: 'static + Send + Sync>(
         &self,
         component_id: ComponentId<T>,
         value: T,
+    ) -> Result<(), SendError<ViewMessage>> {
+        self.send(ViewMessage::new(component_id.0, Box::new(value)))
+    }
+
+    /// Send a message with a [`Priority`], e.g to handle a message before
+    /// a backlog of other messages.
+    pub fn emit_with_priority<T: 'static + Send + Sync>(
+        &self,
+        component_id: ComponentId<T>,
+        value: T,
+        priority: Priority,
     ) -> Result<(), SendError<ViewMessage>> {
         let msg = ViewMessage {
-            payload: Box::new(value),
-            recipient: component_id.0,
+            priority,
+            ..ViewMessage::new(component_id.0, Box::new(value))
         };
-        self.0.send(msg)
+        self.send(msg)
     }
 
-    pub async fn emit_async<T: 'static + Send + Sync>(
+    /// Send a low priority message, where only the latest message
+    /// with the same tag is handled.
+    ///
+    /// Use this for frequent background updates (e.g progress),
+    /// so they can't hold up other messages and input.
+    ///
+    /// ```ignore
+    /// for percent in 0..=100 {
+    ///     emitter.emit_latest(progress_bar, "progress", percent)?;
+    /// }
+    /// ```
+    pub fn emit_latest<T: 'static + Send + Sync>(
         &self,
         component_id: ComponentId<T>,
+        tag: impl Into<Cow<'static, str>>,
         value: T,
     ) -> Result<(), SendError<ViewMessage>> {
         let msg = ViewMessage {
-            payload: Box::new(value),
-            recipient: component_id.0,
+            priority: Priority::Low,
+            tag: Some(tag.into()),
+            ..ViewMessage::new(component_id.0, Box::new(value))
         };
-        self.0.send_async(msg).await
+        self.send(msg)
+    }
+
+    pub async fn emit_async<T: 'static + Send + Sync>(
+        &self,
+        component_id: ComponentId<T>,
+        value: T,
+    ) -> Result<(), SendError<ViewMessage>> {
+        let msg = ViewMessage::new(component_id.0, Box::new(value));
+        self.sender.send_async(Message::View(msg)).await.map_err(view_error)?;
+        self.wake();
+        Ok(())
+    }
+
+    /// Send a copy of the message to every instance of the component with the given name,
+    /// e.g every instance of a prototype.
+    ///
+    /// ```ignore
+    /// emitter.emit_broadcast("list_item", Refresh).unwrap();
+    /// ```
+    pub fn emit_broadcast<T: 'static + Send + Sync + Clone>(
+        &self,
+        component: impl Into<Cow<'static, str>>,
+        value: T,
+    ) -> Result<(), SendError<BroadcastMessage>> {
+        let msg = BroadcastMessage {
+            component: component.into(),
+            payload: Box::new(move || Box::new(value.clone())),
+        };
+        self.send_message(Message::Broadcast(msg))
+            .map_err(|SendError(msg)| match msg {
+                Message::Broadcast(msg) => SendError(msg),
+                _ => unreachable!("the message is returned as it was sent"),
+            })
+    }
+
+    /// Send an event created outside of the runtime to either the focused component
+    /// or every component, depending on the target of the event.
+    ///
+    /// ```ignore
+    /// let emitter = runtime.emitter();
+    /// std::thread::spawn(move || {
+    ///     let status = poll_server();
+    ///     emitter.emit_user_event(UserEvent::broadcast(status)).unwrap();
+    /// });
+    /// ```
+    pub fn emit_user_event(&self, event: UserEvent) -> Result<(), SendError<Message>> {
+        self.send_message(Message::User(event))
+    }
+
+    /// Stop the runtime once the current frame is done.
+    /// See [`Shutdown`].
+    pub fn shutdown(&self, shutdown: impl Into<Shutdown>) -> Result<(), SendError<Message>> {
+        self.send_message(Message::Shutdown(shutdown.into()))
+    }
+
+    /// Set the title of the terminal window
+    pub fn set_title(&self, title: impl Into<String>) -> Result<(), SendError<Message>> {
+        self.send_message(Message::Terminal(TerminalRequest::SetTitle(title.into())))
+    }
+
+    /// Ring the terminal bell
+    pub fn bell(&self) -> Result<(), SendError<Message>> {
+        self.send_message(Message::Terminal(TerminalRequest::Bell))
+    }
+
+    /// Copy the text to the clipboard
+    pub fn copy(&self, text: impl Into<String>) -> Result<(), SendError<Message>> {
+        self.send_message(Message::Terminal(TerminalRequest::Copy(text.into())))
+    }
+
+    pub fn announce(&self, text: impl Into<String>) -> Result<(), SendError<Message>> {
+        self.send_message(Message::Announce(text.into()))
+    }
+
+    pub(crate) fn send(&self, msg: ViewMessage) -> Result<(), SendError<ViewMessage>> {
+        self.send_message(Message::View(msg)).map_err(view_error)
+    }
+
+    fn send_message(&self, msg: Message) -> Result<(), SendError<Message>> {
+        self.sender.send(msg)?;
+        self.wake();
+        Ok(())
+    }
+
+    fn wake(&self) {
+        if let Some(waker) = &self.waker {
+            waker();
+        }
+    }
+}
+
+// A view message that could not be sent, as it was sent
+fn view_error(SendError(msg): SendError<Message>) -> SendError<ViewMessage> {
+    match msg {
+        Message::View(msg) => SendError(msg),
+        _ => unreachable!("the message is returned as it was sent"),
     }
 }
 
@@ -205,6 +475,7 @@ impl<'rt, T: 'static> Context<'rt, T> {
         self.component_ctx.assoc_events.push(
             self.component_ctx.state_id,
             parent,
+            internal,
             *external,
             Box::new(move |state: &dyn AnyState| -> SharedState<'_> {
                 let state = state
@@ -222,6 +493,28 @@ impl<'rt, T: 'static> Context<'rt, T> {
         );
     }
 
+    /// Stop the associated event that is currently being delivered
+    /// from propagating any further.
+    ///
+    /// Called from [`Component::on_capture`] the event never reaches the parent,
+    /// called from [`Component::receive`] or [`Component::on_bubble`] the event
+    /// doesn't bubble any further.
+    pub fn stop_propagation(&mut self) {
+        self.component_ctx.assoc_events.stop_propagation();
+    }
+
+    /// Publish a typed event.
+    /// Same as [`Context::publish`] but the name and the type of the value
+    /// are declared by the [`TypedEvent`].
+    ///
+    /// # Panics
+    ///
+    /// This will panic if the shared value is exclusively borrowed
+    /// at the time of the invocation.
+    pub fn publish_event<E: TypedEvent>(&mut self, f: impl FnMut(&T) -> &Value<E::Value> + 'static) {
+        self.publish(E::NAME, f)
+    }
+
     /// Get a value from external state
     pub fn get_external<'a>(&'a self, key: &str) -> Option<Either<'a>> {
         let val = self.component_ctx.external_state?.get(key);
@@ -232,7 +525,51 @@ impl<'rt, T: 'static> Context<'rt, T> {
     pub fn emit<M: 'static + Send + Sync>(&self, recipient: ComponentId<M>, value: M) {
         self.emitter
             .emit(recipient, value)
-            .expect("this will not fail unless the runtime is droped")
+            .expect("this will not fail unless the runtime is dropped")
+    }
+
+    /// Send a copy of the message to every instance of the component with the given name
+    pub fn broadcast<M: 'static + Send + Sync + Clone>(&self, component: impl Into<Cow<'static, str>>, value: M) {
+        self.emitter
+            .emit_broadcast(component, value)
+            .expect("this will not fail unless the runtime is dropped")
+    }
+
+    /// Run a future on the current Tokio runtime and send the output
+    /// as a message to this component.
+    /// The message is received by [`Component::message`].
+    ///
+    /// The context only knows the state of the component, so the component
+    /// is named to make sure the output is the component's message type.
+    ///
+    /// ```ignore
+    /// // Inside a component where `type Message = String`
+    /// context.spawn::<Self>(async move {
+    ///     match reqwest::get(url).await {
+    ///         Ok(response) => response.text().await.unwrap_or_default(),
+    ///         Err(err) => err.to_string(),
+    ///     }
+    /// });
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if this is called outside of a Tokio runtime
+    #[cfg(feature = "tokio")]
+    pub fn spawn<C>(
+        &self,
+        future: impl std::future::Future<Output = C::Message> + Send + 'static,
+    ) -> tokio::task::JoinHandle<()>
+    where
+        C: Component<State = T>,
+        C::Message: Send + 'static,
+    {
+        let emitter = self.emitter.clone();
+        let recipient = self.component_ctx.component_id;
+        tokio::spawn(async move {
+            let payload = future.await;
+            let _ = emitter.send(ViewMessage::new(recipient, Box::new(payload)));
+        })
     }
 
     /// Queue a focus call to a component that might have
@@ -240,6 +577,163 @@ impl<'rt, T: 'static> Context<'rt, T> {
     pub fn set_focus(&mut self, key: impl Into<Cow<'static, str>>, value: impl Into<CommonVal<'static>>) {
         self.component_ctx.focus_queue.push(key.into(), value.into());
     }
+
+    /// Queue a focus call to the first component with the given name.
+    ///
+    /// ```ignore
+    /// context
+    ///     .focus_by_name("editor")
+    ///     .filter(|attributes| attributes.get::<usize>("index") == Some(2))
+    ///     .on_result(|found| { /* ... */ });
+    /// ```
+    pub fn focus_by_name(&mut self, name: impl Into<Cow<'static, str>>) -> &mut FocusRequest<'static> {
+        self.component_ctx
+            .focus_queue
+            .push_request(FocusTarget::Name(name.into()))
+    }
+
+    /// Queue a focus call to the next component that accepts focus
+    /// inside the component with the given name.
+    /// This wraps around to the first component inside the group.
+    pub fn focus_next_in(&mut self, group: impl Into<Cow<'static, str>>) -> &mut FocusRequest<'static> {
+        self.component_ctx
+            .focus_queue
+            .push_request(FocusTarget::NextIn(group.into()))
+    }
+
+    /// Restrict tab navigation to this component and its children,
+    /// e.g when showing a dialog.
+    ///
+    /// If the focused component is outside of the scope,
+    /// focus moves to the first component inside the scope that accepts focus.
+    pub fn push_focus_scope(&mut self) {
+        self.component_ctx
+            .focus_queue
+            .push_scope(self.component_ctx.component_id);
+    }
+
+    /// Remove the focus scope pushed by this component.
+    /// Returns `false` if the active scope belongs to a different component.
+    pub fn pop_focus_scope(&mut self) -> bool {
+        match self.component_ctx.focus_queue.scope() {
+            Some(id) if id == self.component_ctx.component_id => {
+                self.component_ctx.focus_queue.pop_scope();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Send a message to this component once the duration has passed.
+    /// The message is received by [`Component::message`].
+    ///
+    /// The message has to be the same type as the component's message
+    /// or it will be discarded.
+    pub fn set_timeout<M: 'static + Send + Sync>(&mut self, duration: Duration, message: M) -> TimerId {
+        self.component_ctx
+            .timers
+            .set_timeout(self.component_ctx.component_id, duration, message)
+    }
+
+    /// Send a message to this component every time the duration has passed,
+    /// until the timer is cancelled with [`Context::cancel_timer`].
+    /// The message is received by [`Component::message`].
+    pub fn set_interval<M: 'static + Send + Sync + Clone>(&mut self, duration: Duration, message: M) -> TimerId {
+        self.component_ctx
+            .timers
+            .set_interval(self.component_ctx.component_id, duration, message)
+    }
+
+    /// Cancel a timer created by either [`Context::set_timeout`] or [`Context::set_interval`].
+    /// Returns `false` if the timer no longer exists.
+    pub fn cancel_timer(&mut self, timer: TimerId) -> bool {
+        self.component_ctx.timers.cancel(timer)
+    }
+
+    /// Mount a component by name at the end of the element with a matching `outlet` attribute.
+    ///
+    /// ```ignore
+    /// // Template: `vstack [outlet: "panels"]`
+    /// let panel = context.mount("plugin_panel", "panels");
+    /// // ...
+    /// context.unmount(panel);
+    /// ```
+    ///
+    /// The component is mounted at the end of the frame.
+    /// Prototypes can be mounted any number of times,
+    /// other components can only be used once.
+    pub fn mount(&mut self, component: impl Into<Cow<'static, str>>, outlet: impl Into<Cow<'static, str>>) -> MountId {
+        self.component_ctx.mounts.mount(component.into(), outlet.into())
+    }
+
+    /// Remove a component mounted with [`Context::mount`]
+    pub fn unmount(&mut self, id: MountId) {
+        self.component_ctx.mounts.unmount(id)
+    }
+
+    /// Show the screen with the given name on top of the current screen.
+    /// Requires a router, see `RuntimeBuilder::router`.
+    ///
+    /// The current screen is removed from the tree but keeps its state,
+    /// which is restored when the screen is shown again.
+    pub fn push_screen(&mut self, name: impl Into<Cow<'static, str>>) {
+        self.component_ctx.mounts.screen(ScreenRequest::Push(name.into()))
+    }
+
+    /// Go back to the previous screen
+    pub fn pop_screen(&mut self) {
+        self.component_ctx.mounts.screen(ScreenRequest::Pop)
+    }
+
+    /// Replace the current screen with the screen with the given name
+    pub fn replace_screen(&mut self, name: impl Into<Cow<'static, str>>) {
+        self.component_ctx.mounts.screen(ScreenRequest::Replace(name.into()))
+    }
+
+    /// Register a shortcut that is active while this component has focus.
+    /// The name of the shortcut is passed to [`Component::on_shortcut`].
+    ///
+    /// ```ignore
+    /// context.register_shortcut("save", "ctrl+s")?;
+    /// context.register_shortcut("top", "g g")?;
+    /// ```
+    pub fn register_shortcut(
+        &mut self,
+        name: impl Into<Cow<'static, str>>,
+        shortcut: &str,
+    ) -> crate::error::Result<()> {
+        self.add_shortcut(name.into(), shortcut, ShortcutScope::Focused)
+    }
+
+    /// Register a shortcut that is active regardless of which component has focus.
+    /// Shortcuts of the focused component take priority.
+    pub fn register_global_shortcut(
+        &mut self,
+        name: impl Into<Cow<'static, str>>,
+        shortcut: &str,
+    ) -> crate::error::Result<()> {
+        self.add_shortcut(name.into(), shortcut, ShortcutScope::Global)
+    }
+
+    /// Remove a shortcut registered by this component
+    pub fn unregister_shortcut(&mut self, name: &str) {
+        self.component_ctx
+            .keymap
+            .unregister(self.component_ctx.component_id, name)
+    }
+
+    fn add_shortcut(
+        &mut self,
+        name: Cow<'static, str>,
+        shortcut: &str,
+        scope: ShortcutScope,
+    ) -> crate::error::Result<()> {
+        let shortcut = Shortcut::parse(shortcut)?;
+        self.component_ctx
+            .keymap
+            .register(self.component_ctx.component_id, name, shortcut, scope);
+        Ok(())
+    }
 }
 
 impl<'rt, T> Deref for Context<'rt, T> {
@@ -268,32 +762,96 @@ pub struct UntypedContext<'rt> {
     pub emitter: &'rt Emitter,
     pub viewport: Viewport,
     pub strings: &'rt Strings,
+    pub services: &'rt Services,
+}
+
+impl<'rt> UntypedContext<'rt> {
+    /// Get a service registered with the runtime builder, by type.
+    /// See [`Services`].
+    pub fn service<S: 'static>(&self) -> Option<&'rt S> {
+        self.services.get()
+    }
+
+    /// Stop the runtime with an exit code, or a [`Shutdown`] with a reason.
+    ///
+    /// ```ignore
+    /// context.shutdown(Shutdown::new(1).with_reason(SaveFailed));
+    /// ```
+    pub fn shutdown(&self, shutdown: impl Into<Shutdown>) {
+        self.emitter
+            .shutdown(shutdown)
+            .expect("this will not fail unless the runtime is dropped")
+    }
+
+    /// Set the title of the terminal window, e.g to show the progress of a long running task.
+    /// The title is restored when the runtime stops, if the terminal supports it.
+    pub fn set_title(&self, title: impl Into<String>) {
+        self.emitter
+            .set_title(title)
+            .expect("this will not fail unless the runtime is dropped")
+    }
+
+    /// Ring the terminal bell, e.g to alert the user once a long running task is done
+    pub fn bell(&self) {
+        self.emitter
+            .bell()
+            .expect("this will not fail unless the runtime is dropped")
+    }
+
+    /// Copy the text to the clipboard of the terminal (OSC 52).
+    /// Terminals that don't support it ignore this.
+    pub fn copy(&self, text: impl Into<String>) {
+        self.emitter
+            .copy(text)
+            .expect("this will not fail unless the runtime is dropped")
+    }
+
+    /// Announce the text to assistive technology such as a screen reader,
+    /// e.g once a long running task is done.
+    /// This does nothing unless the runtime has an accessibility output.
+    pub fn announce(&self, text: impl Into<String>) {
+        self.emitter
+            .announce(text)
+            .expect("this will not fail unless the runtime is dropped")
+    }
 }
 
 pub struct ComponentContext<'rt> {
+    pub component_id: WidgetComponentId,
     pub parent: Option<Parent>,
     pub state_id: StateId,
     pub assoc_functions: &'rt [(StringId, StringId)],
     pub assoc_events: &'rt mut AssociatedEvents,
     focus_queue: &'rt mut FocusQueue<'static>,
+    timers: &'rt mut Timers,
+    mounts: &'rt mut Mounts,
+    keymap: &'rt mut Keymap,
     external_state: Option<&'rt ExternalState<'rt>>,
 }
 
 impl<'rt> ComponentContext<'rt> {
     pub fn new(
+        component_id: WidgetComponentId,
         state_id: StateId,
         parent: Option<WidgetComponentId>,
         assoc_functions: &'rt [(StringId, StringId)],
         assoc_events: &'rt mut AssociatedEvents,
         focus_queue: &'rt mut FocusQueue<'static>,
+        timers: &'rt mut Timers,
+        mounts: &'rt mut Mounts,
+        keymap: &'rt mut Keymap,
         external_state: Option<&'rt ExternalState<'rt>>,
     ) -> Self {
         Self {
+            component_id,
             parent: parent.map(Into::into),
             state_id,
             assoc_functions,
             assoc_events,
             focus_queue,
+            timers,
+            mounts,
+            keymap,
             external_state,
         }
     }
@@ -302,6 +860,9 @@ impl<'rt> ComponentContext<'rt> {
 pub struct AssociatedEvent {
     pub state: StateId,
     pub parent: Parent,
+    /// The name of the event as published by the component
+    pub internal: StringId,
+    /// The name of the event as received by the parent
     pub external: StringId,
     pub f: Box<dyn FnMut(&dyn AnyState) -> SharedState<'_> + 'static>,
 }
@@ -311,23 +872,29 @@ pub struct AssociatedEvent {
 // child's state while this is happening.
 pub struct AssociatedEvents {
     inner: Vec<AssociatedEvent>,
+    stopped: bool,
 }
 
 impl AssociatedEvents {
     pub fn new() -> Self {
-        Self { inner: vec![] }
+        Self {
+            inner: vec![],
+            stopped: false,
+        }
     }
 
     fn push(
         &mut self,
         state: StateId,
         parent: Parent,
+        internal: StringId,
         external: StringId,
         f: Box<dyn FnMut(&dyn AnyState) -> SharedState<'_>>,
     ) {
         self.inner.push(AssociatedEvent {
             state,
             parent,
+            internal,
             external,
             f,
         })
@@ -336,26 +903,149 @@ impl AssociatedEvents {
     pub fn next(&mut self) -> Option<AssociatedEvent> {
         self.inner.pop()
     }
+
+    /// Stop the event that is currently being delivered from propagating any further
+    pub fn stop_propagation(&mut self) {
+        self.stopped = true;
+    }
+
+    /// Returns true if the propagation was stopped since the last call
+    pub fn take_stopped(&mut self) -> bool {
+        std::mem::take(&mut self.stopped)
+    }
+}
+
+/// What component to focus
+pub enum FocusTarget<'rt> {
+    /// The first component with an attribute matching the key and value pair
+    Attribute(Cow<'static, str>, CommonVal<'rt>),
+    /// The first component with the given name
+    Name(Cow<'static, str>),
+    /// The next component inside the component with the given name,
+    /// after the component that currently has focus
+    NextIn(Cow<'static, str>),
+}
+
+/// A queued focus call
+pub struct FocusRequest<'rt> {
+    pub target: FocusTarget<'rt>,
+    pub filter: Option<Box<dyn Fn(&Attributes<'_>) -> bool>>,
+    pub on_result: Option<Box<dyn FnOnce(bool)>>,
+}
+
+impl<'rt> FocusRequest<'rt> {
+    fn new(target: FocusTarget<'rt>) -> Self {
+        Self {
+            target,
+            filter: None,
+            on_result: None,
+        }
+    }
+
+    /// Only focus a component if the attributes of the component
+    /// passes the filter.
+    pub fn filter(&mut self, filter: impl Fn(&Attributes<'_>) -> bool + 'static) -> &mut Self {
+        self.filter = Some(Box::new(filter));
+        self
+    }
+
+    /// Callback with the result of the focus call:
+    /// `true` if a component received focus, otherwise `false`.
+    pub fn on_result(&mut self, f: impl FnOnce(bool) + 'static) -> &mut Self {
+        self.on_result = Some(Box::new(f));
+        self
+    }
 }
 
 pub struct FocusQueue<'rt> {
-    focus_queue: VecDeque<(Cow<'static, str>, CommonVal<'rt>)>,
+    focus_queue: VecDeque<FocusRequest<'rt>>,
+    scopes: Vec<WidgetComponentId>,
 }
 
 impl<'rt> FocusQueue<'rt> {
     pub fn new() -> Self {
         Self {
             focus_queue: VecDeque::new(),
+            scopes: vec![],
         }
     }
 
     pub fn push(&mut self, key: Cow<'static, str>, value: CommonVal<'rt>) {
-        self.focus_queue.push_back((key, value));
+        self.push_request(FocusTarget::Attribute(key, value));
     }
 
-    pub fn pop(&mut self) -> Option<(Cow<'static, str>, CommonVal<'rt>)> {
+    pub fn push_request(&mut self, target: FocusTarget<'rt>) -> &mut FocusRequest<'rt> {
+        self.focus_queue.push_back(FocusRequest::new(target));
+        self.focus_queue.back_mut().expect("a request was just inserted")
+    }
+
+    pub fn pop(&mut self) -> Option<FocusRequest<'rt>> {
         self.focus_queue.pop_front()
     }
+
+    /// Push a focus scope.
+    /// While the scope is active, focus can only be given to the component
+    /// that owns the scope or any of its descendants.
+    pub fn push_scope(&mut self, component_id: WidgetComponentId) {
+        self.scopes.push(component_id);
+    }
+
+    /// Pop the active focus scope
+    pub fn pop_scope(&mut self) -> Option<WidgetComponentId> {
+        self.scopes.pop()
+    }
+
+    /// The active focus scope
+    pub fn scope(&self) -> Option<WidgetComponentId> {
+        self.scopes.last().copied()
+    }
+
+    /// Remove all focus scopes owned by a component
+    pub fn remove_scope(&mut self, component_id: WidgetComponentId) {
+        self.scopes.retain(|id| *id != component_id);
+    }
+}
+
+/// An associated event where the name and the type of the value are declared once.
+///
+/// ```
+/// # use anathema_widgets::components::TypedEvent;
+/// # use anathema_state::CommonVal;
+/// struct Click;
+///
+/// impl TypedEvent for Click {
+///     type Value = String;
+///
+///     const NAME: &'static str = "click";
+/// }
+///
+/// // Publishing component:
+/// // context.publish_event::<Click>(|state| &state.caption);
+///
+/// // Receiving component, given the template `@button (click->click)`:
+/// let caption = Click::receive("click", CommonVal::Str("lark"));
+/// assert_eq!(caption.as_deref(), Some("lark"));
+/// assert!(Click::receive("hover", CommonVal::Str("lark")).is_none());
+/// ```
+///
+/// The receiving component gets the name from the right hand side
+/// of the association (`@button (click->increment)` is received as `increment`),
+/// so the receiver can declare its own event with the same value type.
+pub trait TypedEvent {
+    /// The name of the event
+    const NAME: &'static str;
+
+    /// The state value published with the event
+    type Value: AnyState + for<'a> TryFrom<CommonVal<'a>>;
+
+    /// Convert the arguments of [`Component::receive`] into the value of the event.
+    /// Returns `None` if the name doesn't match or if the value is of a different type.
+    fn receive(ident: &str, value: CommonVal<'_>) -> Option<Self::Value> {
+        if ident != Self::NAME {
+            return None;
+        }
+        Self::Value::try_from(value).ok()
+    }
 }
 
 pub trait Component {
@@ -400,6 +1090,100 @@ pub trait Component {
     ) {
     }
 
+    /// Called when a drag starts in the component, or while the cursor is
+    /// over the component during a drag.
+    /// See [`DragEvent`].
+    #[allow(unused_variables, unused_mut)]
+    fn on_drag(
+        &mut self,
+        drag: DragEvent,
+        state: &mut Self::State,
+        mut elements: Elements<'_, '_>,
+        mut context: Context<'_, Self::State>,
+    ) {
+    }
+
+    /// Called on every component when the terminal window gains focus.
+    /// This is not the same as the component gaining focus, see [`Component::on_focus`].
+    ///
+    /// Requires focus reporting to be enabled in the backend.
+    #[allow(unused_variables, unused_mut)]
+    fn on_terminal_focus_gained(
+        &mut self,
+        state: &mut Self::State,
+        mut elements: Elements<'_, '_>,
+        mut context: Context<'_, Self::State>,
+    ) {
+    }
+
+    /// Called on every component when the terminal window loses focus,
+    /// e.g to pause animations.
+    /// This is not the same as the component losing focus, see [`Component::on_blur`].
+    ///
+    /// Requires focus reporting to be enabled in the backend.
+    #[allow(unused_variables, unused_mut)]
+    fn on_terminal_focus_lost(
+        &mut self,
+        state: &mut Self::State,
+        mut elements: Elements<'_, '_>,
+        mut context: Context<'_, Self::State>,
+    ) {
+    }
+
+    /// Called on every component when the process continues after being suspended (Ctrl+z).
+    /// The runtime redraws the entire screen after this.
+    #[allow(unused_variables, unused_mut)]
+    fn on_resume(
+        &mut self,
+        state: &mut Self::State,
+        mut elements: Elements<'_, '_>,
+        mut context: Context<'_, Self::State>,
+    ) {
+    }
+
+    /// Called with the pasted text when text is pasted into the terminal
+    /// while the component has focus.
+    /// This requires bracketed paste to be enabled in the backend.
+    #[allow(unused_variables, unused_mut)]
+    fn on_paste(
+        &mut self,
+        text: &str,
+        state: &mut Self::State,
+        mut elements: Elements<'_, '_>,
+        mut context: Context<'_, Self::State>,
+    ) {
+    }
+
+    /// Called on the focused component during input method composition, e.g for CJK input.
+    /// See [`ImeEvent`].
+    #[allow(unused_variables, unused_mut)]
+    fn on_ime(
+        &mut self,
+        ime: &ImeEvent,
+        state: &mut Self::State,
+        mut elements: Elements<'_, '_>,
+        mut context: Context<'_, Self::State>,
+    ) {
+    }
+
+    /// Called on the focused component with events that anathema doesn't model,
+    /// e.g media keys.
+    /// This requires raw events to be enabled in the backend, see [`RawEvent`].
+    #[allow(unused_variables, unused_mut)]
+    fn on_raw(
+        &mut self,
+        event: &RawEvent,
+        state: &mut Self::State,
+        mut elements: Elements<'_, '_>,
+        mut context: Context<'_, Self::State>,
+    ) {
+    }
+
+    /// Called once per frame while the runtime is active.
+    ///
+    /// An idle runtime (no events, messages or changes) stops ticking,
+    /// so animate with [`Context::set_interval`] rather than counting time in `tick`.
+    /// Changing the state keeps the runtime active.
     #[allow(unused_variables, unused_mut)]
     fn tick(
         &mut self,
@@ -440,9 +1224,98 @@ pub trait Component {
     ) {
     }
 
+    /// Called when a shortcut registered by the component is pressed.
+    /// See [`Context::register_shortcut`].
+    #[allow(unused_variables, unused_mut)]
+    fn on_shortcut(
+        &mut self,
+        shortcut: &str,
+        state: &mut Self::State,
+        mut elements: Elements<'_, '_>,
+        mut context: Context<'_, Self::State>,
+    ) {
+    }
+
+    /// Capture phase of an associated event.
+    ///
+    /// Called on every ancestor of the component receiving the event,
+    /// starting from the root, before the event is passed to [`Component::receive`].
+    /// The ident is the name the event was published with.
+    ///
+    /// Call [`Context::stop_propagation`] to intercept the event.
+    #[allow(unused_variables, unused_mut)]
+    fn on_capture(
+        &mut self,
+        ident: &str,
+        value: CommonVal<'_>,
+        state: &mut Self::State,
+        mut elements: Elements<'_, '_>,
+        mut context: Context<'_, Self::State>,
+    ) {
+    }
+
+    /// Bubble phase of an associated event.
+    ///
+    /// Called on every ancestor of the component receiving the event,
+    /// starting from the closest one, after the event was passed to [`Component::receive`].
+    /// The ident is the name the event was published with.
+    ///
+    /// Call [`Context::stop_propagation`] to stop the event from bubbling further.
+    #[allow(unused_variables, unused_mut)]
+    fn on_bubble(
+        &mut self,
+        ident: &str,
+        value: CommonVal<'_>,
+        state: &mut Self::State,
+        mut elements: Elements<'_, '_>,
+        mut context: Context<'_, Self::State>,
+    ) {
+    }
+
+    /// Called when the application sends a [`UserEvent`] through the [`Emitter`].
+    /// See [`Emitter::emit_user_event`].
+    #[allow(unused_variables, unused_mut)]
+    fn on_user_event(
+        &mut self,
+        event: &UserEvent,
+        state: &mut Self::State,
+        mut elements: Elements<'_, '_>,
+        mut context: Context<'_, Self::State>,
+    ) {
+    }
+
     fn accept_focus(&self) -> bool {
         true
     }
+
+    /// A description of the component, announced to assistive technology
+    /// when the component gains focus.
+    /// Defaults to the `description` attribute of the component.
+    #[allow(unused_variables)]
+    fn describe(&self, state: &Self::State) -> Option<String> {
+        None
+    }
+
+    /// An error boundary catches errors and panics raised while evaluating,
+    /// updating or laying out the body of the component.
+    ///
+    /// When that happens the body is replaced with the `$fallback` slot
+    /// (or nothing, if there is no fallback) and [`Component::on_error`] is called.
+    ///
+    /// Errors raised by the fallback are caught by the next error boundary up the tree.
+    fn error_boundary(&self) -> bool {
+        false
+    }
+
+    #[allow(unused_variables, unused_mut)]
+    fn on_error(
+        &mut self,
+        error: &str,
+        state: &mut Self::State,
+        mut elements: Elements<'_, '_>,
+        mut context: Context<'_, Self::State>,
+    ) {
+    }
 }
 
 impl Component for () {
@@ -473,9 +1346,29 @@ pub trait AnyComponent {
 
     fn any_resize(&mut self, ctx: AnyEventCtx<'_, '_, '_>);
 
+    fn any_terminal_focus(&mut self, ctx: AnyEventCtx<'_, '_, '_>, gained: bool);
+
+    fn any_resume(&mut self, ctx: AnyEventCtx<'_, '_, '_>);
+
     fn any_receive(&mut self, ctx: AnyEventCtx<'_, '_, '_>, name: &str, value: CommonVal<'_>);
 
+    fn any_capture(&mut self, ctx: AnyEventCtx<'_, '_, '_>, name: &str, value: CommonVal<'_>);
+
+    fn any_bubble(&mut self, ctx: AnyEventCtx<'_, '_, '_>, name: &str, value: CommonVal<'_>);
+
+    fn any_shortcut(&mut self, ctx: AnyEventCtx<'_, '_, '_>, shortcut: &str);
+
+    fn any_user_event(&mut self, ctx: AnyEventCtx<'_, '_, '_>, event: &UserEvent);
+
+    fn any_drag(&mut self, ctx: AnyEventCtx<'_, '_, '_>, drag: DragEvent);
+
     fn any_accept_focus(&self) -> bool;
+
+    fn any_describe(&self, state: Option<&dyn AnyState>) -> Option<String>;
+
+    fn any_error_boundary(&self) -> bool;
+
+    fn any_error(&mut self, ctx: AnyEventCtx<'_, '_, '_>, error: &str);
 }
 
 impl<T> AnyComponent for T
@@ -490,18 +1383,67 @@ where
             .expect("components always have a state");
         let context = Context::<T::State>::new(ctx.context, ctx.component_ctx);
         match event {
-            Event::Blur | Event::Focus => (), // Application focus, not component focus.
+            Event::FocusGained | Event::FocusLost => (), // Terminal focus, not component focus.
+            Event::Resume => (),                         // Every component is notified through `on_resume`
             Event::Key(ev) => self.on_key(ev, state, ctx.elements, context),
             Event::Mouse(ev) => self.on_mouse(ev, state, ctx.elements, context),
+            Event::Paste(ref text) => self.on_paste(text, state, ctx.elements, context),
+            Event::Ime(ref ime) => self.on_ime(ime, state, ctx.elements, context),
+            Event::Raw(ref raw) => self.on_raw(raw, state, ctx.elements, context),
             Event::Resize(_, _) | Event::Noop | Event::Stop => (),
         }
         event
     }
 
+    fn any_shortcut(&mut self, ctx: AnyEventCtx<'_, '_, '_>, shortcut: &str) {
+        let state = ctx
+            .state
+            .and_then(|s| s.to_any_mut().downcast_mut::<T::State>())
+            .expect("components always have a state");
+        let context = Context::<T::State>::new(ctx.context, ctx.component_ctx);
+        self.on_shortcut(shortcut, state, ctx.elements, context);
+    }
+
+    fn any_user_event(&mut self, ctx: AnyEventCtx<'_, '_, '_>, event: &UserEvent) {
+        let state = ctx
+            .state
+            .and_then(|s| s.to_any_mut().downcast_mut::<T::State>())
+            .expect("components always have a state");
+        let context = Context::<T::State>::new(ctx.context, ctx.component_ctx);
+        self.on_user_event(event, state, ctx.elements, context);
+    }
+
+    fn any_drag(&mut self, ctx: AnyEventCtx<'_, '_, '_>, drag: DragEvent) {
+        let state = ctx
+            .state
+            .and_then(|s| s.to_any_mut().downcast_mut::<T::State>())
+            .expect("components always have a state");
+        let context = Context::<T::State>::new(ctx.context, ctx.component_ctx);
+        self.on_drag(drag, state, ctx.elements, context);
+    }
+
     fn any_accept_focus(&self) -> bool {
         self.accept_focus()
     }
 
+    fn any_describe(&self, state: Option<&dyn AnyState>) -> Option<String> {
+        let state = state.and_then(|s| s.to_any_ref().downcast_ref::<T::State>())?;
+        self.describe(state)
+    }
+
+    fn any_error_boundary(&self) -> bool {
+        self.error_boundary()
+    }
+
+    fn any_error(&mut self, ctx: AnyEventCtx<'_, '_, '_>, error: &str) {
+        let state = ctx
+            .state
+            .and_then(|s| s.to_any_mut().downcast_mut::<T::State>())
+            .expect("components always have a state");
+        let context = Context::<T::State>::new(ctx.context, ctx.component_ctx);
+        self.on_error(error, state, ctx.elements, context);
+    }
+
     fn any_message(&mut self, message: Box<dyn Any>, ctx: AnyEventCtx<'_, '_, '_>) {
         let state = ctx
             .state
@@ -548,6 +1490,27 @@ where
         self.resize(state, ctx.elements, context);
     }
 
+    fn any_terminal_focus(&mut self, ctx: AnyEventCtx<'_, '_, '_>, gained: bool) {
+        let state = ctx
+            .state
+            .and_then(|s| s.to_any_mut().downcast_mut::<T::State>())
+            .expect("components always have a state");
+        let context = Context::<T::State>::new(ctx.context, ctx.component_ctx);
+        match gained {
+            true => self.on_terminal_focus_gained(state, ctx.elements, context),
+            false => self.on_terminal_focus_lost(state, ctx.elements, context),
+        }
+    }
+
+    fn any_resume(&mut self, ctx: AnyEventCtx<'_, '_, '_>) {
+        let state = ctx
+            .state
+            .and_then(|s| s.to_any_mut().downcast_mut::<T::State>())
+            .expect("components always have a state");
+        let context = Context::<T::State>::new(ctx.context, ctx.component_ctx);
+        self.on_resume(state, ctx.elements, context);
+    }
+
     fn any_receive(&mut self, ctx: AnyEventCtx<'_, '_, '_>, name: &str, value: CommonVal<'_>) {
         let state = ctx
             .state
@@ -558,6 +1521,24 @@ where
 
         self.receive(name, value, state, ctx.elements, context);
     }
+
+    fn any_capture(&mut self, ctx: AnyEventCtx<'_, '_, '_>, name: &str, value: CommonVal<'_>) {
+        let state = ctx
+            .state
+            .and_then(|s| s.to_any_mut().downcast_mut::<T::State>())
+            .expect("components always have a state");
+        let context = Context::<T::State>::new(ctx.context, ctx.component_ctx);
+        self.on_capture(name, value, state, ctx.elements, context);
+    }
+
+    fn any_bubble(&mut self, ctx: AnyEventCtx<'_, '_, '_>, name: &str, value: CommonVal<'_>) {
+        let state = ctx
+            .state
+            .and_then(|s| s.to_any_mut().downcast_mut::<T::State>())
+            .expect("components always have a state");
+        let context = Context::<T::State>::new(ctx.context, ctx.component_ctx);
+        self.on_bubble(name, value, state, ctx.elements, context);
+    }
 }
 
 impl std::fmt::Debug for dyn AnyComponent {
@@ -0,0 +1,78 @@
+use std::borrow::Cow;
+
+/// Identifies a component mounted at runtime.
+/// Used to unmount the component.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct MountId(usize);
+
+/// A request to either mount or unmount a component
+#[derive(Debug)]
+pub enum MountRequest {
+    /// Mount the component with the given name
+    /// as the last child of the element with a matching `outlet` attribute
+    Mount {
+        id: MountId,
+        component: Cow<'static, str>,
+        outlet: Cow<'static, str>,
+    },
+    /// Remove a mounted component
+    Unmount(MountId),
+    /// Change the active screen
+    Screen(ScreenRequest),
+}
+
+/// A request to change the active screen of the router.
+///
+/// Screens are components, shown one at a time
+/// in the element with the `outlet` attribute of the router.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScreenRequest {
+    /// Show the screen on top of the current one
+    Push(Cow<'static, str>),
+    /// Go back to the previous screen.
+    /// The first screen can not be popped.
+    Pop,
+    /// Replace the current screen
+    Replace(Cow<'static, str>),
+}
+
+/// Queue of components to mount and unmount.
+/// The runtime drains the queue once per frame.
+#[derive(Debug, Default)]
+pub struct Mounts {
+    next_id: usize,
+    queue: Vec<MountRequest>,
+}
+
+impl Mounts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue a component to be mounted
+    pub fn mount(&mut self, component: Cow<'static, str>, outlet: Cow<'static, str>) -> MountId {
+        let id = MountId(self.next_id);
+        self.next_id += 1;
+        self.queue.push(MountRequest::Mount { id, component, outlet });
+        id
+    }
+
+    /// Queue a mounted component to be removed
+    pub fn unmount(&mut self, id: MountId) {
+        self.queue.push(MountRequest::Unmount(id));
+    }
+
+    /// Queue a screen change
+    pub fn screen(&mut self, request: ScreenRequest) {
+        self.queue.push(MountRequest::Screen(request));
+    }
+
+    /// Drain all the requests, in the order they were made
+    pub fn drain(&mut self) -> impl Iterator<Item = MountRequest> + '_ {
+        self.queue.drain(..)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
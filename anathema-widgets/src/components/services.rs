@@ -0,0 +1,67 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+
+/// A typed container of shared services, such as database handles or api clients.
+///
+/// There can be at most one service per type.
+/// Components access the services through the context:
+///
+/// ```ignore
+/// let db = context.service::<Database>().expect("the database was not registered");
+/// ```
+#[derive(Default)]
+pub struct Services {
+    inner: HashMap<TypeId, Box<dyn Any>>,
+}
+
+impl Services {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert a service, returning the previous service of the same type (if any)
+    pub fn insert<S: 'static>(&mut self, service: S) -> Option<S> {
+        self.inner
+            .insert(TypeId::of::<S>(), Box::new(service))
+            .and_then(|prev| prev.downcast().ok())
+            .map(|prev| *prev)
+    }
+
+    /// Get a service by type
+    pub fn get<S: 'static>(&self) -> Option<&S> {
+        self.inner.get(&TypeId::of::<S>())?.downcast_ref()
+    }
+
+    /// Remove a service by type
+    pub fn remove<S: 'static>(&mut self) -> Option<S> {
+        self.inner
+            .remove(&TypeId::of::<S>())
+            .and_then(|service| service.downcast().ok())
+            .map(|service| *service)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct Database(&'static str);
+
+    #[test]
+    fn services_by_type() {
+        let mut services = Services::new();
+        assert!(services.insert(Database("a")).is_none());
+        services.insert(123u32);
+
+        assert_eq!(services.get::<Database>().unwrap().0, "a");
+        assert_eq!(*services.get::<u32>().unwrap(), 123);
+        assert!(services.get::<String>().is_none());
+
+        let prev = services.insert(Database("b")).unwrap();
+        assert_eq!(prev.0, "a");
+        assert_eq!(services.get::<Database>().unwrap().0, "b");
+
+        assert!(services.remove::<u32>().is_some());
+        assert!(services.get::<u32>().is_none());
+    }
+}
@@ -0,0 +1,59 @@
+use std::any::Any;
+use std::fmt::{self, Debug};
+
+/// A request to stop the runtime, with an exit code and an optional reason.
+///
+/// ```
+/// # use anathema_widgets::components::Shutdown;
+/// struct Saved(usize);
+///
+/// let shutdown = Shutdown::new(0).with_reason(Saved(3));
+/// assert_eq!(shutdown.code(), 0);
+/// assert_eq!(shutdown.reason::<Saved>().map(|saved| saved.0), Some(3));
+/// ```
+///
+/// Stopping the runtime through an event (e.g Ctrl+c) results in [`Shutdown::default`],
+/// with the exit code zero and no reason.
+#[derive(Default)]
+pub struct Shutdown {
+    code: i32,
+    reason: Option<Box<dyn Any + Send>>,
+}
+
+impl Shutdown {
+    /// Create a shutdown request with an exit code
+    pub fn new(code: i32) -> Self {
+        Self { code, reason: None }
+    }
+
+    /// Attach a reason, retrieved by type with [`Shutdown::reason`]
+    pub fn with_reason<R: Any + Send>(mut self, reason: R) -> Self {
+        self.reason = Some(Box::new(reason));
+        self
+    }
+
+    /// The exit code
+    pub fn code(&self) -> i32 {
+        self.code
+    }
+
+    /// The reason, if it is of type `R`
+    pub fn reason<R: Any>(&self) -> Option<&R> {
+        self.reason.as_ref()?.downcast_ref()
+    }
+}
+
+impl From<i32> for Shutdown {
+    fn from(code: i32) -> Self {
+        Self::new(code)
+    }
+}
+
+impl Debug for Shutdown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Shutdown")
+            .field("code", &self.code)
+            .field("reason", &self.reason.as_ref().map(|_| ".."))
+            .finish()
+    }
+}
@@ -0,0 +1,362 @@
+use std::any::Any;
+use std::time::Duration;
+
+use anathema_templates::WidgetComponentId;
+
+use super::events::{UserEvent, UserEventTarget};
+use super::{Emitter, ViewMessage};
+use crate::time::Instant;
+
+const DEFAULT_SLOTS: usize = 64;
+const DEFAULT_RESOLUTION: Duration = Duration::from_millis(10);
+
+type MessageFn = dyn Fn() -> Box<dyn Any + Send + Sync>;
+
+/// Identifies a scheduled timer.
+/// Used to cancel a timer before it fires.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TimerId(usize);
+
+enum TimerKind {
+    Timeout(Box<dyn Any + Send + Sync>),
+    Interval(Duration, Box<MessageFn>),
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum Recipient {
+    Component(WidgetComponentId),
+    Event(UserEventTarget),
+}
+
+struct Timer {
+    id: TimerId,
+    rounds: usize,
+    recipient: Recipient,
+    kind: TimerKind,
+}
+
+/// A hashed timer wheel.
+///
+/// Every slot covers `resolution` amount of time.
+/// Timers that are further away than a full rotation of the wheel
+/// keep track of the number of remaining rounds.
+///
+/// Expired timers are delivered as messages to the recipient,
+/// the same way as messages sent through the [`Emitter`],
+/// or as [`UserEvent`]s.
+pub struct Timers {
+    slots: Box<[Vec<Timer>]>,
+    resolution: Duration,
+    cursor: usize,
+    last_tick: Instant,
+    next_id: usize,
+    len: usize,
+}
+
+impl Timers {
+    /// Create a timer wheel with a default resolution of 10ms
+    pub fn new() -> Self {
+        Self::with_resolution(DEFAULT_RESOLUTION, DEFAULT_SLOTS)
+    }
+
+    /// Create a timer wheel with a given resolution and number of slots.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of slots or the resolution is zero
+    pub fn with_resolution(resolution: Duration, slots: usize) -> Self {
+        assert!(slots > 0, "a timer wheel needs at least one slot");
+        assert!(!resolution.is_zero(), "the resolution can not be zero");
+
+        Self {
+            slots: (0..slots).map(|_| vec![]).collect(),
+            resolution,
+            cursor: 0,
+            last_tick: Instant::now(),
+            next_id: 0,
+            len: 0,
+        }
+    }
+
+    /// Schedule a message to be delivered once, after `delay`.
+    ///
+    /// The recipient is either a [`ComponentId`](super::ComponentId)
+    /// or the id of the component scheduling the timer.
+    pub fn set_timeout<M: 'static + Send + Sync>(
+        &mut self,
+        recipient: impl Into<WidgetComponentId>,
+        delay: Duration,
+        message: M,
+    ) -> TimerId {
+        let recipient = Recipient::Component(recipient.into());
+        self.schedule(recipient, delay, TimerKind::Timeout(Box::new(message)))
+    }
+
+    /// Schedule a message to be delivered repeatedly, every `interval`,
+    /// until the timer is cancelled
+    pub fn set_interval<M: 'static + Send + Sync + Clone>(
+        &mut self,
+        recipient: impl Into<WidgetComponentId>,
+        interval: Duration,
+        message: M,
+    ) -> TimerId {
+        let recipient = Recipient::Component(recipient.into());
+        self.schedule(recipient, interval, TimerKind::Interval(interval, message_fn(message)))
+    }
+
+    /// Schedule a [`UserEvent`] to be delivered once, after `delay`
+    pub fn set_event_timeout<T: 'static + Send + Sync>(
+        &mut self,
+        target: UserEventTarget,
+        delay: Duration,
+        value: T,
+    ) -> TimerId {
+        self.schedule(Recipient::Event(target), delay, TimerKind::Timeout(Box::new(value)))
+    }
+
+    /// Schedule a [`UserEvent`] to be delivered repeatedly, every `interval`,
+    /// until the timer is cancelled
+    pub fn set_event_interval<T: 'static + Send + Sync + Clone>(
+        &mut self,
+        target: UserEventTarget,
+        interval: Duration,
+        value: T,
+    ) -> TimerId {
+        let kind = TimerKind::Interval(interval, message_fn(value));
+        self.schedule(Recipient::Event(target), interval, kind)
+    }
+
+    /// Cancel a timer.
+    /// Returns `false` if the timer has already fired (or was cancelled).
+    pub fn cancel(&mut self, id: TimerId) -> bool {
+        for slot in self.slots.iter_mut() {
+            if let Some(index) = slot.iter().position(|timer| timer.id == id) {
+                slot.swap_remove(index);
+                self.len -= 1;
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Remove all timers belonging to a component
+    pub fn cancel_all(&mut self, recipient: WidgetComponentId) {
+        let recipient = Recipient::Component(recipient);
+        for slot in self.slots.iter_mut() {
+            slot.retain(|timer| timer.recipient != recipient);
+        }
+        self.len = self.slots.iter().map(Vec::len).sum();
+    }
+
+    /// Number of scheduled timers
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if there are no scheduled timers
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The time at which the next timer expires,
+    /// or `None` if there are no scheduled timers.
+    ///
+    /// The runtime uses this to decide for how long it can sleep when idle.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        if self.len == 0 {
+            return None;
+        }
+
+        let slots = self.slots.len();
+        let ticks = (1..=slots)
+            .flat_map(|offset| {
+                let slot = &self.slots[(self.cursor + offset) % slots];
+                slot.iter().map(move |timer| offset + timer.rounds * slots)
+            })
+            .min()?;
+
+        Some(self.last_tick + self.resolution * ticks as u32)
+    }
+
+    /// Advance the wheel up until `now`, sending the messages
+    /// of all expired timers through the emitter.
+    pub fn tick(&mut self, now: Instant, emitter: &Emitter) {
+        while now.saturating_duration_since(self.last_tick) >= self.resolution {
+            self.last_tick += self.resolution;
+            self.cursor = (self.cursor + 1) % self.slots.len();
+
+            // Nothing to do but moving the cursor
+            if self.len == 0 {
+                continue;
+            }
+
+            let slot = std::mem::take(&mut self.slots[self.cursor]);
+            for mut timer in slot {
+                if timer.rounds > 0 {
+                    timer.rounds -= 1;
+                    self.slots[self.cursor].push(timer);
+                    continue;
+                }
+
+                self.len -= 1;
+
+                match timer.kind {
+                    TimerKind::Timeout(payload) => deliver(emitter, timer.recipient, payload),
+                    TimerKind::Interval(interval, f) => {
+                        deliver(emitter, timer.recipient, f());
+                        self.insert(timer.id, timer.recipient, interval, TimerKind::Interval(interval, f));
+                    }
+                }
+            }
+        }
+    }
+
+    fn next_id(&mut self) -> TimerId {
+        let id = TimerId(self.next_id);
+        self.next_id += 1;
+        id
+    }
+
+    fn schedule(&mut self, recipient: Recipient, delay: Duration, kind: TimerKind) -> TimerId {
+        let id = self.next_id();
+        self.insert(id, recipient, delay, kind);
+        id
+    }
+
+    fn insert(&mut self, id: TimerId, recipient: Recipient, delay: Duration, kind: TimerKind) {
+        // A timer always fires on the next tick at the earliest
+        let ticks = (delay.as_nanos().div_ceil(self.resolution.as_nanos()) as usize).max(1);
+        let slot = (self.cursor + ticks) % self.slots.len();
+        let rounds = (ticks - 1) / self.slots.len();
+
+        self.slots[slot].push(Timer {
+            id,
+            rounds,
+            recipient,
+            kind,
+        });
+        self.len += 1;
+    }
+}
+
+fn message_fn<M: 'static + Send + Sync + Clone>(message: M) -> Box<MessageFn> {
+    Box::new(move || Box::new(message.clone()))
+}
+
+fn deliver(emitter: &Emitter, recipient: Recipient, payload: Box<dyn Any + Send + Sync>) {
+    match recipient {
+        Recipient::Component(recipient) => _ = emitter.send(ViewMessage::new(recipient, payload)),
+        Recipient::Event(target) => _ = emitter.emit_user_event(UserEvent::from_payload(target, payload)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::components::Message;
+
+    const MS: Duration = Duration::from_millis(1);
+
+    fn setup() -> (Timers, Emitter, flume::Receiver<Message>, Instant) {
+        let (tx, rx) = flume::unbounded();
+        let timers = Timers::with_resolution(MS, 4);
+        let now = timers.last_tick;
+        (timers, tx.into(), rx, now)
+    }
+
+    fn payloads(rx: &flume::Receiver<Message>) -> Vec<u32> {
+        rx.try_iter()
+            .map(|msg| match msg {
+                Message::View(msg) => *msg.payload().downcast::<u32>().unwrap(),
+                Message::Broadcast(_)
+                | Message::User(_)
+                | Message::Shutdown(_)
+                | Message::Terminal(_)
+                | Message::Announce(_) => {
+                    panic!("timers only send view messages")
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn timeout() {
+        let (mut timers, emitter, rx, now) = setup();
+        timers.set_timeout(WidgetComponentId::from(0usize), MS * 2, 1u32);
+
+        timers.tick(now + MS, &emitter);
+        assert!(payloads(&rx).is_empty());
+
+        timers.tick(now + MS * 2, &emitter);
+        assert_eq!(payloads(&rx), vec![1]);
+        assert!(timers.is_empty());
+    }
+
+    #[test]
+    fn timeout_longer_than_a_rotation() {
+        let (mut timers, emitter, rx, now) = setup();
+        timers.set_timeout(WidgetComponentId::from(0usize), MS * 10, 1u32);
+
+        timers.tick(now + MS * 9, &emitter);
+        assert!(payloads(&rx).is_empty());
+
+        timers.tick(now + MS * 10, &emitter);
+        assert_eq!(payloads(&rx), vec![1]);
+    }
+
+    #[test]
+    fn interval() {
+        let (mut timers, emitter, rx, now) = setup();
+        timers.set_interval(WidgetComponentId::from(0usize), MS * 3, 2u32);
+
+        timers.tick(now + MS * 9, &emitter);
+        assert_eq!(payloads(&rx), vec![2, 2, 2]);
+        assert_eq!(timers.len(), 1);
+    }
+
+    #[test]
+    fn event_interval() {
+        let (mut timers, emitter, rx, now) = setup();
+        timers.set_event_interval(UserEventTarget::All, MS * 2, 3u32);
+
+        timers.tick(now + MS * 4, &emitter);
+        let values = rx
+            .try_iter()
+            .map(|msg| match msg {
+                Message::User(event) => *event.get::<u32>().unwrap(),
+                Message::View(_)
+                | Message::Broadcast(_)
+                | Message::Shutdown(_)
+                | Message::Terminal(_)
+                | Message::Announce(_) => {
+                    panic!("expected user events")
+                }
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(values, vec![3, 3]);
+    }
+
+    #[test]
+    fn next_deadline() {
+        let (mut timers, _, _, now) = setup();
+        assert_eq!(timers.next_deadline(), None);
+
+        timers.set_timeout(WidgetComponentId::from(0usize), MS * 10, 1u32);
+        assert_eq!(timers.next_deadline(), Some(now + MS * 10));
+
+        timers.set_timeout(WidgetComponentId::from(0usize), MS * 4, 2u32);
+        assert_eq!(timers.next_deadline(), Some(now + MS * 4));
+    }
+
+    #[test]
+    fn cancel() {
+        let (mut timers, emitter, rx, now) = setup();
+        let id = timers.set_interval(WidgetComponentId::from(0usize), MS, 1u32);
+        timers.tick(now + MS, &emitter);
+        assert!(timers.cancel(id));
+        assert!(!timers.cancel(id));
+
+        timers.tick(now + MS * 5, &emitter);
+        assert_eq!(payloads(&rx), vec![1]);
+    }
+}
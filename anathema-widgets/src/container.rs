@@ -1,35 +1,101 @@
-use anathema_geometry::{LocalPos, Pos, Rect, Size};
+use anathema_geometry::{LocalPos, Pos, Rect, Region, Size};
 
-use crate::layout::{Constraints, LayoutCtx, PositionCtx, Viewport};
+use crate::layout::{
+    Constraints, ContentWidth, DiagnosticKind, LayoutCtx, LayoutDirection, PositionCtx, Viewport, DIR,
+};
 use crate::paint::{PaintCtx, Unsized};
-use crate::widget::{AnyWidget, PositionChildren};
+use crate::widget::{PositionChildren, WidgetBox};
 use crate::{AttributeStorage, LayoutChildren, PaintChildren, WidgetId};
 
 #[derive(Debug)]
-pub struct Container {
-    pub inner: Box<dyn AnyWidget>,
+pub struct Container<'bp> {
+    pub inner: WidgetBox<'bp>,
     pub id: WidgetId,
     pub size: Size,
     pub pos: Pos,
     pub inner_bounds: Rect,
     pub needs_layout: bool,
+    /// The constraints of the last layout.
+    /// Unless the widget changed, the layout is skipped when the constraints are the same
+    pub constraints: Option<Constraints>,
     pub needs_position: bool,
+    /// The widget changed and has to be painted again,
+    /// rather than only laid out because a descendant changed
+    pub needs_paint: bool,
+    /// The region covered by the widget when it was last painted.
+    /// `None` if the widget was never painted, or has to damage the entire screen
+    pub painted_region: Option<Region>,
+    /// The regions painted by descendants that were removed since the last paint
+    pub removed_regions: Vec<Region>,
+    pub hovered: bool,
+    /// The layout direction of the widget, set by the `dir` attribute or inherited from the parent
+    pub direction: LayoutDirection,
+    /// The parent measured the content of the widget as part of its layout,
+    /// so a change to the widget can change the layout of the parent, even if the size is the same
+    pub measured: bool,
 }
 
-impl Container {
-    pub fn layout<'bp>(
+impl<'bp> Container<'bp> {
+    pub fn layout(
         &mut self,
         children: LayoutChildren<'_, '_, 'bp>,
         constraints: Constraints,
         ctx: &mut LayoutCtx<'_, 'bp>,
     ) -> Size {
-        if !self.needs_layout {
-            return self.size;
+        let direction = ctx.attribs.get(self.id).get(DIR).unwrap_or(ctx.direction);
+
+        // Neither the widget, the constraints nor the direction changed since the last layout,
+        // so the size is the same
+        let unchanged = !self.needs_layout && self.constraints == Some(constraints) && self.direction == direction;
+
+        // Out of time: keep the previous size and resume on the next pass
+        if unchanged || ctx.should_yield() {
+            return match self.inner.any_floats() {
+                true => Size::ZERO,
+                false => self.size,
+            };
         }
+
         self.needs_layout = false;
         self.needs_position = true;
+        self.constraints = Some(constraints);
+
+        let mut constraints = constraints;
+        if constraints.min_width > constraints.max_width() || constraints.min_height > constraints.max_height() {
+            ctx.report(DiagnosticKind::ImpossibleConstraints(constraints));
+            constraints.min_width = constraints.min_width.min(constraints.max_width());
+            constraints.min_height = constraints.min_height.min(constraints.max_height());
+        }
 
+        let parent_direction = ctx.direction;
+        ctx.direction = direction;
+        self.direction = direction;
+
+        let completed = ctx.completed;
         self.size = self.inner.any_layout(children, constraints, self.id, ctx);
+        ctx.direction = parent_direction;
+
+        let overflows = self.size.width > constraints.max_width() || self.size.height > constraints.max_height();
+        if overflows && !ctx.interrupted() {
+            ctx.report(DiagnosticKind::Overflow {
+                constraints,
+                size: self.size,
+            });
+        }
+
+        // A descendant was interrupted, so this widget has to be laid out again
+        match ctx.interrupted() {
+            true => self.needs_layout = true,
+            false => {
+                // No descendant was laid out, so the change is in this widget
+                // (e.g a child was removed, or a span of a text changed)
+                if ctx.completed == completed {
+                    self.needs_paint = true;
+                }
+                ctx.complete();
+            }
+        }
+
         // Floating widgets always report a zero size
         // as they should not affect their parents
         match self.inner.any_floats() {
@@ -38,7 +104,17 @@ impl Container {
         }
     }
 
-    pub fn position<'bp>(
+    pub fn measure(&mut self, children: LayoutChildren<'_, '_, 'bp>, ctx: &mut LayoutCtx<'_, 'bp>) -> ContentWidth {
+        self.measured = true;
+
+        // Floating widgets don't take up any space in their parent
+        match self.inner.any_floats() {
+            true => ContentWidth::ZERO,
+            false => self.inner.any_measure(children, self.id, ctx),
+        }
+    }
+
+    pub fn position(
         &mut self,
         children: PositionChildren<'_, '_, 'bp>,
         pos: Pos,
@@ -60,7 +136,7 @@ impl Container {
         self.inner_bounds = self.inner.any_inner_bounds(self.pos, self.size);
     }
 
-    pub fn paint<'bp>(
+    pub fn paint(
         &mut self,
         children: PaintChildren<'_, '_, 'bp>,
         ctx: PaintCtx<'_, Unsized>,
@@ -73,6 +149,22 @@ impl Container {
         let region = ctx.create_region();
         ctx.set_clip_region(region);
 
+        match self.painted_region {
+            // The widget is new or replaced other widgets,
+            // and those could have been painted anywhere
+            None => ctx.damage_screen(),
+            // Both the area the widget covered before and the area it covers now
+            // have to be drawn again if the widget changed or moved
+            Some(previous) if self.needs_paint || previous != region => {
+                ctx.damage(previous);
+                ctx.damage(region);
+            }
+            Some(_) => {}
+        }
+        self.removed_regions.drain(..).for_each(|removed| ctx.damage(removed));
+        self.needs_paint = false;
+        self.painted_region = Some(region);
+
         let attrs = attribute_storage.get(self.id);
 
         // Apply all attributes
@@ -2,13 +2,17 @@ use std::fmt::Write;
 use std::ops::ControlFlow;
 
 use anathema_debug::DebugWriter;
+use anathema_geometry::{Pos, Rect, Region};
 use anathema_store::tree::visitor::NodeVisitor;
-use anathema_store::tree::ValueId;
+use anathema_store::tree::{Node, TreeValues, ValueId};
 
 use crate::expressions::EvalValue;
+use crate::layout::Display;
 use crate::nodes::element::Element;
 use crate::nodes::loops::{For, Iteration};
+use crate::widget::WidgetRenderer;
 use crate::{AttributeStorage, WidgetKind, WidgetTree};
+
 struct EvalValueDebug<'a>(&'a EvalValue<'a>);
 
 impl DebugWriter for EvalValueDebug<'_> {
@@ -170,3 +174,144 @@ impl<O: std::fmt::Write> NodeVisitor<WidgetKind<'_>> for DebugWidgetsVisitor<'_,
         self.level -= 1;
     }
 }
+
+// -----------------------------------------------------------------------------
+//   - Layout overlay -
+// -----------------------------------------------------------------------------
+
+/// Painted on top of the widgets once they are painted,
+/// e.g the [`LayoutOverlay`] or an inspector.
+pub trait Overlay {
+    /// Draw the overlay to the surface
+    fn paint(&self, surface: &mut dyn WidgetRenderer);
+}
+
+/// The outline of an element, as painted by the [`LayoutOverlay`]
+#[derive(Debug, Copy, Clone)]
+pub struct OverlayBox<'bp> {
+    pub ident: &'bp str,
+    /// The region of the element
+    pub region: Region,
+    /// The bounds of the children, inside of any border or padding
+    pub inner: Rect,
+}
+
+/// Paints the boundaries, padding and idents of the visible elements
+/// on top of the frame, using the positions from the most recent layout.
+///
+/// ```text
+/// ┌border────┐
+/// │┌padding─┐│
+/// ││········││
+/// ││·text···││
+/// │└────────┘│
+/// └──────────┘
+/// ```
+///
+/// The outlines are drawn with parents before their children.
+/// Elements that are only one cell wide or high are not outlined,
+/// as the outline would cover the entire element.
+#[derive(Debug, Default)]
+pub struct LayoutOverlay<'bp> {
+    boxes: Vec<OverlayBox<'bp>>,
+}
+
+impl<'bp> LayoutOverlay<'bp> {
+    /// Collect the outlines of the elements that are shown
+    pub fn new(tree: &mut WidgetTree<'bp>, attribute_storage: &AttributeStorage<'bp>) -> Self {
+        let mut boxes = vec![];
+        let (nodes, values) = tree.split_mut();
+        collect_boxes(nodes, values, attribute_storage, &mut boxes);
+        Self { boxes }
+    }
+
+    /// The outlines, parents before their children
+    pub fn boxes(&self) -> &[OverlayBox<'bp>] {
+        &self.boxes
+    }
+
+    /// Draw the outlines to the surface
+    pub fn paint(&self, surface: &mut (impl WidgetRenderer + ?Sized)) {
+        let size = surface.size();
+        let screen = Region::from((Pos::ZERO, size));
+
+        let mut draw = |c: char, pos: Pos| {
+            if screen.contains(pos) {
+                surface.draw_glyph(c, pos);
+            }
+        };
+
+        for overlay_box in &self.boxes {
+            let Region { from, to } = overlay_box.region;
+            if to.x - from.x < 2 || to.y - from.y < 2 {
+                continue;
+            }
+
+            let (right, bottom) = (to.x - 1, to.y - 1);
+            let inner = overlay_box.inner;
+
+            for y in from.y..to.y {
+                for x in from.x..to.x {
+                    let pos = Pos::new(x, y);
+                    let c = match (x, y) {
+                        (x, y) if x == from.x && y == from.y => '┌',
+                        (x, y) if x == right && y == from.y => '┐',
+                        (x, y) if x == from.x && y == bottom => '└',
+                        (x, y) if x == right && y == bottom => '┘',
+                        (_, y) if y == from.y || y == bottom => '─',
+                        (x, _) if x == from.x || x == right => '│',
+                        // Padding
+                        (x, y) if x < inner.start.x || x >= inner.end.x || y < inner.start.y || y >= inner.end.y => '·',
+                        _ => continue,
+                    };
+                    draw(c, pos);
+                }
+            }
+
+            // Ident
+            let max = (right - from.x - 1) as usize;
+            for (x, c) in overlay_box.ident.chars().take(max).enumerate() {
+                draw(c, Pos::new(from.x + 1 + x as i32, from.y));
+            }
+        }
+    }
+}
+
+impl Overlay for LayoutOverlay<'_> {
+    fn paint(&self, surface: &mut dyn WidgetRenderer) {
+        LayoutOverlay::paint(self, surface)
+    }
+}
+
+fn collect_boxes<'bp>(
+    nodes: &[Node],
+    values: &TreeValues<WidgetKind<'bp>>,
+    attribute_storage: &AttributeStorage<'bp>,
+    boxes: &mut Vec<OverlayBox<'bp>>,
+) {
+    for node in nodes {
+        let Some((_, widget)) = values.get(node.value()) else { continue };
+        match widget {
+            WidgetKind::Element(el) => {
+                let display = attribute_storage
+                    .get(el.id())
+                    .get::<Display>("display")
+                    .unwrap_or_default();
+                if display != Display::Show {
+                    continue;
+                }
+
+                boxes.push(OverlayBox {
+                    ident: el.ident,
+                    region: el.region(),
+                    inner: el.inner_bounds(),
+                });
+            }
+            WidgetKind::If(widget) if !widget.show => continue,
+            WidgetKind::Else(widget) if !widget.show => continue,
+            _ => {}
+        }
+
+        collect_boxes(node.children(), values, attribute_storage, boxes);
+    }
+}
@@ -7,6 +7,9 @@ pub enum Error {
     InvalidElement(String),
     TreeTransactionFailed,
     ComponentConsumed,
+    InvalidSelector(String),
+    InvalidShortcut(String),
+    RecursionLimit(String),
 }
 
 impl Display for Error {
@@ -18,6 +21,9 @@ impl Display for Error {
                 "failed to insert into the widget tree (most likely the parent was removed)"
             ),
             Error::ComponentConsumed => write!(f, "this component has already been used"),
+            Error::InvalidSelector(selector) => write!(f, "invalid selector `{selector}`"),
+            Error::InvalidShortcut(shortcut) => write!(f, "invalid shortcut `{shortcut}`"),
+            Error::RecursionLimit(name) => write!(f, "`@{name}` is nested inside itself too many times"),
         }
     }
 }
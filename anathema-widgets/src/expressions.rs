@@ -237,7 +237,7 @@ impl<'bp> EvalValue<'bp> {
             ControlFlow::Continue(())
         };
 
-        match self.internal_str_iter(&mut wrapped_f) {
+        let _ = match self.internal_str_iter(&mut wrapped_f) {
             Some(control_flow) => control_flow,
             None => ControlFlow::Break(()),
         };
@@ -260,7 +260,7 @@ impl<'bp> EvalValue<'bp> {
         let val = match self {
             EvalValue::ExprList(list) => {
                 for value in list.iter() {
-                    value.internal_str_iter(f)?;
+                    let _ = value.internal_str_iter(f)?;
                 }
                 ControlFlow::Continue(())
             }
@@ -556,7 +556,8 @@ impl<'bp> ValueResolver<'bp> {
     }
 
     fn resolve(&mut self, expr: &'bp Expression, scope: &Scope<'bp>, states: &States) -> EvalValue<'bp> {
-        use {EvalValue as V, Expression as E};
+        use EvalValue as V;
+        use Expression as E;
 
         match expr {
             // -----------------------------------------------------------------------------
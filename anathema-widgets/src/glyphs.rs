@@ -0,0 +1,146 @@
+//! Grapheme clusters.
+//!
+//! A glyph is what the user perceives as a single character,
+//! e.g `é` written as an `e` followed by a combining accent,
+//! or a family emoji made out of several emojis joined by zero width joiners.
+//!
+//! Text is laid out and painted one glyph at a time, so a glyph is never split between cells or lines.
+use std::collections::HashMap;
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthChar;
+
+const VARIATION_SELECTOR_16: char = '\u{fe0f}';
+
+/// Iterate over the glyphs of a string
+pub fn glyphs(s: &str) -> impl Iterator<Item = &str> {
+    s.graphemes(true)
+}
+
+/// The number of cells a glyph occupies in the terminal.
+///
+/// The width of a glyph is the width of the first character,
+/// except for emoji presentation (VS16) and flags which are always two cells wide.
+pub fn width(glyph: &str) -> usize {
+    let mut chars = glyph.chars();
+    let Some(first) = chars.next() else { return 0 };
+
+    let wide = glyph.contains(VARIATION_SELECTOR_16)
+        || is_regional_indicator(first) && chars.next().is_some_and(is_regional_indicator);
+
+    match wide {
+        true => 2,
+        false => first.width().unwrap_or(0),
+    }
+}
+
+/// The number of cells a string occupies in the terminal
+pub fn str_width(s: &str) -> usize {
+    glyphs(s).map(width).sum()
+}
+
+/// Returns the char if the glyph is a single char
+pub fn single_char(glyph: &str) -> Option<char> {
+    let mut chars = glyph.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c),
+        _ => None,
+    }
+}
+
+fn is_regional_indicator(c: char) -> bool {
+    ('\u{1f1e6}'..='\u{1f1ff}').contains(&c)
+}
+
+/// Index of a glyph in a [`GlyphMap`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct GlyphIndex(u32);
+
+/// Storage for glyphs made out of more than one char,
+/// so they can be referenced by a `Copy` index.
+///
+/// The same glyph always has the same index, until the map is cleared.
+#[derive(Debug, Default, Clone)]
+pub struct GlyphMap {
+    glyphs: Vec<Box<str>>,
+    indices: HashMap<Box<str>, GlyphIndex>,
+}
+
+impl GlyphMap {
+    /// Create an empty glyph map
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store a glyph, returning the index of the glyph.
+    pub fn insert(&mut self, glyph: &str) -> GlyphIndex {
+        if let Some(index) = self.indices.get(glyph) {
+            return *index;
+        }
+
+        let index = GlyphIndex(self.glyphs.len() as u32);
+        self.glyphs.push(glyph.into());
+        self.indices.insert(glyph.into(), index);
+        index
+    }
+
+    /// Get a glyph by index
+    pub fn get(&self, index: GlyphIndex) -> Option<&str> {
+        self.glyphs.get(index.0 as usize).map(|glyph| &**glyph)
+    }
+
+    /// Remove all the glyphs, invalidating all indices
+    pub fn clear(&mut self) {
+        self.glyphs.clear();
+        self.indices.clear();
+    }
+
+    /// The number of glyphs in the map
+    pub fn len(&self) -> usize {
+        self.glyphs.len()
+    }
+
+    /// Returns true if there are no glyphs in the map
+    pub fn is_empty(&self) -> bool {
+        self.glyphs.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn split_glyphs() {
+        let family = "👨\u{200d}👩\u{200d}👧";
+        let accent = "e\u{301}";
+        let flag = "🇸🇪";
+        let text = format!("a{family}{accent}{flag}");
+
+        let glyphs = glyphs(&text).collect::<Vec<_>>();
+        assert_eq!(glyphs, vec!["a", family, accent, flag]);
+    }
+
+    #[test]
+    fn glyph_width() {
+        assert_eq!(width("a"), 1);
+        assert_eq!(width("🐇"), 2);
+        assert_eq!(width("👨\u{200d}👩\u{200d}👧"), 2);
+        assert_eq!(width("e\u{301}"), 1);
+        assert_eq!(width("🇸🇪"), 2);
+        assert_eq!(width("❤\u{fe0f}"), 2);
+        assert_eq!(width("\r\n"), 0);
+        assert_eq!(str_width("a👨\u{200d}👩\u{200d}👧"), 3);
+    }
+
+    #[test]
+    fn insert_glyphs() {
+        let mut map = GlyphMap::new();
+        let a = map.insert("e\u{301}");
+        let b = map.insert("🇸🇪");
+        assert_eq!(a, map.insert("e\u{301}"));
+        assert_ne!(a, b);
+        assert_eq!(map.get(b), Some("🇸🇪"));
+        assert_eq!(map.len(), 2);
+    }
+}
@@ -0,0 +1,92 @@
+use std::fmt::{self, Display, Formatter};
+
+use anathema_geometry::Size;
+use anathema_templates::blueprints::Span;
+
+use super::Constraints;
+
+/// A problem found during layout.
+///
+/// The layout carries on regardless: impossible constraints are relaxed,
+/// and anything outside of the constraints is clipped when painted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    /// The ident of the element, e.g `border`
+    pub ident: String,
+    /// Where the element is declared in the template
+    pub span: Span,
+    pub kind: DiagnosticKind,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum DiagnosticKind {
+    /// The min size is larger than the max size.
+    /// The min size is lowered to the max size.
+    ImpossibleConstraints(Constraints),
+    /// The widget is larger than the max size of the constraints
+    Overflow { constraints: Constraints, size: Size },
+    /// An offset from an edge (e.g `right` of a `position`) is larger than the available space.
+    /// The offset is lowered to the available space.
+    OffsetOutOfBounds { offset: usize, available: usize },
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({}): ", self.ident, self.span)?;
+        match self.kind {
+            DiagnosticKind::ImpossibleConstraints(constraints) => write!(
+                f,
+                "the min size {}x{} is larger than the max size {}x{}",
+                constraints.min_width,
+                constraints.min_height,
+                DisplayMax(constraints.max_width()),
+                DisplayMax(constraints.max_height()),
+            ),
+            DiagnosticKind::Overflow { constraints, size } => write!(
+                f,
+                "the size {}x{} exceeds the max size {}x{}",
+                size.width,
+                size.height,
+                DisplayMax(constraints.max_width()),
+                DisplayMax(constraints.max_height()),
+            ),
+            DiagnosticKind::OffsetOutOfBounds { offset, available } => {
+                write!(f, "the offset {offset} is larger than the available space {available}")
+            }
+        }
+    }
+}
+
+// Unbounded sizes are written as `∞` rather than `usize::MAX`
+struct DisplayMax(usize);
+
+impl Display for DisplayMax {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            usize::MAX => write!(f, "∞"),
+            max => write!(f, "{max}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn display_diagnostic() {
+        let diagnostic = Diagnostic {
+            ident: "border".into(),
+            span: Span::new(3, 5),
+            kind: DiagnosticKind::Overflow {
+                constraints: Constraints::new(10, None),
+                size: Size::new(12, 4),
+            },
+        };
+
+        assert_eq!(
+            diagnostic.to_string(),
+            "border (line 3, col 5): the size 12x4 exceeds the max size 10x∞"
+        );
+    }
+}
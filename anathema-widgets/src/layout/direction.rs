@@ -0,0 +1,47 @@
+use anathema_state::CommonVal;
+
+/// The attribute that sets the layout direction of an element and its descendants
+pub const DIR: &str = "dir";
+
+/// The direction of the layout, inherited from the parent.
+/// In a right-to-left layout horizontal stacks place their children from the right,
+/// text is aligned to the right and the left and right padding trade places.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum LayoutDirection {
+    /// Left to right
+    #[default]
+    Ltr,
+    /// Right to left
+    Rtl,
+}
+
+impl LayoutDirection {
+    /// Returns true if the layout is right to left
+    pub fn is_rtl(&self) -> bool {
+        matches!(self, Self::Rtl)
+    }
+}
+
+impl TryFrom<CommonVal<'_>> for LayoutDirection {
+    type Error = ();
+
+    fn try_from(value: CommonVal<'_>) -> Result<Self, Self::Error> {
+        let dir = match value.to_common_str().as_ref() {
+            "ltr" => Self::Ltr,
+            "rtl" => Self::Rtl,
+            _ => return Err(()),
+        };
+        Ok(dir)
+    }
+}
+
+impl From<LayoutDirection> for CommonVal<'_> {
+    fn from(value: LayoutDirection) -> Self {
+        let s = match value {
+            LayoutDirection::Ltr => "ltr",
+            LayoutDirection::Rtl => "rtl",
+        };
+
+        CommonVal::Str(s)
+    }
+}
@@ -0,0 +1,93 @@
+use anathema_state::CommonVal;
+
+/// A width or height given as a number of cells, a percentage (`"50%"`)
+/// or a fraction (`"1/3"`) of the parent constraint.
+/// ```
+/// # use anathema_widgets::layout::Length;
+/// assert_eq!(Length::Percent(50.0).resolve(10), Some(5));
+/// assert_eq!(Length::Fraction(1, 3).resolve(10), Some(3));
+/// assert_eq!(Length::Cells(4).resolve(10), Some(4));
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Length {
+    Cells(usize),
+    Percent(f64),
+    Fraction(usize, usize),
+}
+
+impl Length {
+    /// Resolve the length against the max width / height of the parent.
+    /// A percentage or fraction of an unbounded constraint is `None`.
+    pub fn resolve(self, max: usize) -> Option<usize> {
+        match self {
+            Self::Cells(cells) => Some(cells),
+            _ if max == usize::MAX => None,
+            Self::Percent(percent) => Some((max as f64 * percent / 100.0) as usize),
+            Self::Fraction(num, den) => Some(max * num / den),
+        }
+    }
+}
+
+impl TryFrom<CommonVal<'_>> for Length {
+    type Error = ();
+
+    fn try_from(value: CommonVal<'_>) -> Result<Self, Self::Error> {
+        match value {
+            CommonVal::Int(cells) => Ok(Self::Cells(cells.max(0) as usize)),
+            CommonVal::Float(cells) => Ok(Self::Cells(cells.max(0.0) as usize)),
+            CommonVal::Str(s) => s.parse(),
+            _ => Err(()),
+        }
+    }
+}
+
+impl std::str::FromStr for Length {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        if let Some(percent) = s.strip_suffix('%') {
+            let percent = percent.trim().parse::<f64>().map_err(|_| ())?;
+            return match percent >= 0.0 {
+                true => Ok(Self::Percent(percent)),
+                false => Err(()),
+            };
+        }
+
+        if let Some((num, den)) = s.split_once('/') {
+            let num = num.trim().parse().map_err(|_| ())?;
+            let den = den.trim().parse().map_err(|_| ())?;
+            return match den {
+                0 => Err(()),
+                _ => Ok(Self::Fraction(num, den)),
+            };
+        }
+
+        s.parse().map(Self::Cells).map_err(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_length() {
+        assert_eq!("12".parse(), Ok(Length::Cells(12)));
+        assert_eq!("50%".parse(), Ok(Length::Percent(50.0)));
+        assert_eq!("12.5 %".parse(), Ok(Length::Percent(12.5)));
+        assert_eq!("2/3".parse(), Ok(Length::Fraction(2, 3)));
+        assert_eq!("1/0".parse::<Length>(), Err(()));
+        assert_eq!("-5%".parse::<Length>(), Err(()));
+        assert_eq!("wide".parse::<Length>(), Err(()));
+    }
+
+    #[test]
+    fn resolve_length() {
+        assert_eq!(Length::Percent(25.0).resolve(10), Some(2));
+        assert_eq!(Length::Fraction(2, 3).resolve(9), Some(6));
+        assert_eq!(Length::Percent(50.0).resolve(usize::MAX), None);
+        assert_eq!(Length::Cells(3).resolve(usize::MAX), Some(3));
+    }
+}
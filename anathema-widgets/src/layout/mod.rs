@@ -2,25 +2,84 @@ use std::ops::ControlFlow;
 
 use anathema_geometry::{Pos, Size};
 use anathema_store::tree::{Node, TreeFilter, TreeForEach, TreeValues};
+use anathema_templates::blueprints::Span;
 
 pub use self::constraints::Constraints;
+pub use self::diagnostics::{Diagnostic, DiagnosticKind};
+pub use self::direction::{LayoutDirection, DIR};
 pub use self::display::Display;
+pub use self::length::Length;
 use crate::nodes::element::Element;
+use crate::time::Instant;
 use crate::{AttributeStorage, WidgetId, WidgetKind};
 
 mod constraints;
+mod diagnostics;
+mod direction;
 mod display;
+mod length;
 pub mod text;
 
+/// The width of the content of a widget, measured before the layout.
+/// Layouts use this to size columns to their content rather than to the constraints alone.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct ContentWidth {
+    /// The narrowest the content can be without overflowing, e.g. the longest word of a text
+    pub min: usize,
+    /// The width of the content when it's never wrapped
+    pub max: usize,
+}
+
+impl ContentWidth {
+    pub const ZERO: Self = Self { min: 0, max: 0 };
+
+    pub fn new(min: usize, max: usize) -> Self {
+        Self { min, max }
+    }
+
+    /// A content width where the min and max are the same
+    pub fn fixed(width: usize) -> Self {
+        Self::new(width, width)
+    }
+
+    /// The widest of the two, for content that is stacked
+    pub fn widest(self, other: Self) -> Self {
+        Self::new(self.min.max(other.min), self.max.max(other.max))
+    }
+
+    /// The sum of the two, for content that is placed side by side
+    pub fn beside(self, other: Self) -> Self {
+        Self::new(self.min + other.min, self.max + other.max)
+    }
+
+    /// Add the same width to the min and max, e.g. padding or a border
+    pub fn pad(self, width: usize) -> Self {
+        Self::new(self.min + width, self.max + width)
+    }
+}
+
 #[derive(Debug, Copy, Clone)]
 /// A viewport represents the available space in the root
 pub struct Viewport {
     size: Size,
+    direction: LayoutDirection,
 }
 
 impl Viewport {
     pub fn new(size: impl Into<Size>) -> Self {
-        Self { size: size.into() }
+        Self {
+            size: size.into(),
+            direction: LayoutDirection::Ltr,
+        }
+    }
+
+    /// The layout direction of elements without a `dir` attribute on themselves or an ancestor
+    pub fn direction(&self) -> LayoutDirection {
+        self.direction
+    }
+
+    pub fn set_direction(&mut self, direction: LayoutDirection) {
+        self.direction = direction;
     }
 
     pub fn size(&self) -> Size {
@@ -94,11 +153,88 @@ impl<'frame, 'bp> TreeFilter for LayoutFilter<'frame, 'bp> {
 pub struct LayoutCtx<'a, 'bp> {
     pub attribs: &'a AttributeStorage<'bp>,
     pub viewport: &'a Viewport,
+    /// The layout direction, inherited from the parent
+    pub direction: LayoutDirection,
+    deadline: Option<Instant>,
+    // The number of widgets laid out
+    pub(crate) completed: usize,
+    interrupted: bool,
+    // The element being laid out, that diagnostics are reported for
+    pub(crate) element: Option<(&'bp str, Span, WidgetId)>,
+    diagnostics: Vec<Diagnostic>,
 }
 
 impl<'a, 'bp> LayoutCtx<'a, 'bp> {
     pub fn new(attribs: &'a AttributeStorage<'bp>, viewport: &'a Viewport) -> Self {
-        Self { attribs, viewport }
+        Self {
+            attribs,
+            viewport,
+            direction: viewport.direction(),
+            deadline: None,
+            completed: 0,
+            interrupted: false,
+            element: None,
+            diagnostics: vec![],
+        }
+    }
+
+    /// Stop laying out widgets once the deadline has passed.
+    /// Widgets that were not laid out keep their previous size and
+    /// are laid out on the next pass.
+    pub fn with_deadline(mut self, deadline: Option<Instant>) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    /// True if the layout was cut short by the deadline
+    pub fn interrupted(&self) -> bool {
+        self.interrupted
+    }
+
+    // At least one widget has to complete its layout before yielding,
+    // otherwise a pass that always runs out of time would never make progress.
+    pub(crate) fn should_yield(&mut self) -> bool {
+        if self.interrupted {
+            return true;
+        }
+
+        match self.deadline {
+            Some(deadline) if self.completed > 0 && Instant::now() >= deadline => {
+                self.interrupted = true;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Report a problem with the layout of the element that is being laid out
+    pub fn report(&mut self, kind: DiagnosticKind) {
+        let (ident, span) = self.element.map(|(ident, span, _)| (ident, span)).unwrap_or_default();
+        self.diagnostics.push(Diagnostic {
+            ident: ident.into(),
+            span,
+            kind,
+        });
+    }
+
+    /// The problems reported during the layout, see [`Diagnostic`]
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.diagnostics)
+    }
+
+    /// The element being laid out.
+    /// If the layout panics, this is the innermost element that was being laid out.
+    pub fn laying_out(&self) -> Option<WidgetId> {
+        self.element.map(|(_, _, widget_id)| widget_id)
+    }
+
+    /// The number of widgets laid out with this context
+    pub fn completed(&self) -> usize {
+        self.completed
+    }
+
+    pub(crate) fn complete(&mut self) {
+        self.completed += 1;
     }
 }
 
@@ -115,6 +251,32 @@ pub fn layout_widget<'bp>(
     element.layout(children, constraints, ctx);
 }
 
+/// Lay out the element again, in place, using the constraints of its previous layout.
+///
+/// Returns `true` if the parent is unaffected by the layout and doesn't have to be laid out again.
+/// Returns `false` if the element was never laid out, floats, was measured by its parent,
+/// ran out of time or changed size (or baseline).
+pub fn relayout_widget<'bp>(
+    element: &mut Element<'bp>,
+    children: &[Node],
+    values: &mut TreeValues<WidgetKind<'bp>>,
+    ctx: &mut LayoutCtx<'_, 'bp>,
+) -> bool {
+    let container = &mut element.container;
+    container.needs_layout = true;
+
+    let Some(constraints) = container.constraints else { return false };
+    if container.measured || container.inner.any_floats() {
+        return false;
+    }
+
+    let size = element.size();
+    let baseline = element.baseline();
+    layout_widget(element, children, values, constraints, ctx, true);
+
+    !ctx.interrupted() && size == element.size() && baseline == element.baseline()
+}
+
 pub fn position_widget<'bp>(
     pos: Pos,
     element: &mut Element<'bp>,
@@ -3,9 +3,9 @@ use std::ops::{AddAssign, Deref};
 use anathema_geometry::Size;
 use anathema_state::CommonVal;
 use anathema_store::tree::ValueId;
-use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
-use crate::WidgetId;
+use crate::layout::ContentWidth;
+use crate::{glyphs, WidgetId};
 
 /// Word wrapping strategy
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
@@ -171,8 +171,8 @@ impl Strings {
 
         for word in s.split_inclusive(char::is_whitespace) {
             self.bytes.extend(word.bytes());
-            for c in word.chars() {
-                if let res @ ProcessResult::Break = self.chomp(c) {
+            for glyph in glyphs::glyphs(word) {
+                if let res @ ProcessResult::Break = self.chomp(glyph) {
                     self.bytes.truncate(self.chomper.index());
                     self.freeze();
                     return res;
@@ -213,10 +213,10 @@ impl Strings {
     /// Finalize the layout, converting entries to lines
     pub fn finish(&mut self) -> Size {
         self.frozen = true;
-        self.layout.sort_by(|a, b| a.0.cmp(&b.0));
+        self.layout.sort_by_key(|a| a.0);
 
         let last_line = self.line(self.bytes.len());
-        let last_line_width = last_line.width();
+        let last_line_width = glyphs::str_width(last_line);
         self.layout
             .push((self.bytes.len() as u32, Entry::LineWidth(last_line_width as u16)));
 
@@ -275,7 +275,7 @@ impl Strings {
                 word_boundary,
                 current_index,
             } => {
-                let diff = self.line(current_index).width() - self.line(word_boundary).width();
+                let diff = glyphs::str_width(self.line(current_index)) - glyphs::str_width(self.line(word_boundary));
                 let width = *self.current_width - diff;
                 self.layout.push((word_boundary as u32, Entry::LineWidth(width as u16)));
                 self.layout.push((word_boundary as u32, Entry::Newline));
@@ -298,22 +298,23 @@ impl Strings {
         self.size.width = self.size.width.max(*self.current_width);
     }
 
-    fn chomp(&mut self, c: char) -> ProcessResult {
-        let width = c.width().unwrap_or(0);
+    // Chomp a glyph (grapheme cluster) rather than a char,
+    // so a glyph is never split between two lines
+    fn chomp(&mut self, glyph: &str) -> ProcessResult {
+        let width = glyphs::width(glyph);
+        let is_whitespace = glyph.starts_with(char::is_whitespace);
 
         // NOTE
-        // Special case: the character is too wide to ever fit so it's removed,
-        // e.g a character width of two with a max width of one.
+        // Special case: the glyph is too wide to ever fit so it's removed,
+        // e.g a glyph width of two with a max width of one.
         if width > self.max.width {
-            for _ in 0..c.len_utf8() {
-                self.bytes.pop();
-            }
+            self.bytes.truncate(self.bytes.len() - glyph.len());
             return ProcessResult::Continue;
         }
 
         // NOTE
         // If newline characters are handled then pop the bytes and insert a newline
-        if c == '\n' {
+        if glyph == "\n" {
             self.bytes.pop();
 
             if self.size.height >= self.max.height {
@@ -328,14 +329,12 @@ impl Strings {
         // NOTE
         // If the trailing whitespace should be removed, do so here
         while width + *self.current_width > self.max.width {
-            if c.is_whitespace() {
+            if is_whitespace {
                 // 1. Make this the next word boundary
                 // 2. Insert a newline here
                 // 3. Remove the bytes representing this whitespace
 
-                for _ in 0..c.len_utf8() {
-                    self.bytes.pop();
-                }
+                self.bytes.truncate(self.bytes.len() - glyph.len());
 
                 self.chomper.force_word_boundary();
                 self.newline();
@@ -350,7 +349,7 @@ impl Strings {
             self.newline();
         }
 
-        self.chomper.chomp(c, self.wrap);
+        self.chomper.chomp(glyph.len(), is_whitespace, self.wrap);
         self.current_width += width;
 
         ProcessResult::Continue
@@ -363,6 +362,83 @@ impl Default for Strings {
     }
 }
 
+/// Measure the size of a string once it's wrapped to `max_width`,
+/// using the same layout as the `text` widget.
+/// ```
+/// # use anathema_widgets::layout::text::{measure_text, Wrap};
+/// # use anathema_geometry::Size;
+/// let size = measure_text("hello to the world", 5, Wrap::Normal);
+/// assert_eq!(size, Size::new(5, 4));
+/// ```
+pub fn measure_text(text: &str, max_width: usize, wrap: Wrap) -> Size {
+    let mut strings = Strings::new(Size::new(max_width, usize::MAX), wrap);
+    strings.add_str(text);
+    strings.finish()
+}
+
+/// Measure the [`ContentWidth`] of text that is split over any number of strings,
+/// e.g. the value of a text and its spans.
+/// ```
+/// # use anathema_widgets::layout::text::{ContentMeasure, Wrap};
+/// # use anathema_widgets::layout::ContentWidth;
+/// let mut measure = ContentMeasure::new(Wrap::Normal);
+/// measure.add_str("a short");
+/// measure.add_str("er line\nbye");
+/// assert_eq!(measure.finish(), ContentWidth::new(7, 14));
+/// ```
+#[derive(Debug)]
+pub struct ContentMeasure {
+    wrap: Wrap,
+    width: ContentWidth,
+    line: usize,
+    word: usize,
+}
+
+impl ContentMeasure {
+    pub fn new(wrap: Wrap) -> Self {
+        Self {
+            wrap,
+            width: ContentWidth::ZERO,
+            line: 0,
+            word: 0,
+        }
+    }
+
+    /// Measure another string slice
+    pub fn add_str(&mut self, s: &str) {
+        for glyph in glyphs::glyphs(s) {
+            if glyph == "\n" {
+                self.end_word();
+                self.width.max = self.width.max.max(self.line);
+                self.line = 0;
+                continue;
+            }
+
+            let width = glyphs::width(glyph);
+            self.line += width;
+
+            match self.wrap {
+                // Whitespace is removed when the text wraps on it
+                Wrap::Normal if glyph.starts_with(char::is_whitespace) => self.end_word(),
+                Wrap::Normal => self.word += width,
+                Wrap::WordBreak => self.width.min = self.width.min.max(width),
+            }
+        }
+    }
+
+    /// The content width of all the strings
+    pub fn finish(mut self) -> ContentWidth {
+        self.end_word();
+        self.width.max = self.width.max.max(self.line);
+        self.width
+    }
+
+    fn end_word(&mut self) {
+        self.width.min = self.width.min.max(self.word);
+        self.word = 0;
+    }
+}
+
 // TODO: move this into string2
 #[derive(Debug)]
 pub(crate) enum Chomper {
@@ -387,10 +463,8 @@ impl Chomper {
         }
     }
 
-    pub(crate) fn chomp(&mut self, c: char, wrap: Wrap) {
-        let c_len = c.len_utf8();
-
-        if c.is_whitespace() && wrap.is_word_wrap() {
+    pub(crate) fn chomp(&mut self, c_len: usize, is_whitespace: bool, wrap: Wrap) {
+        if is_whitespace && wrap.is_word_wrap() {
             match self {
                 Chomper::Continuous(idx) | Chomper::WordBoundary { current_index: idx, .. } => {
                     let new_index = *idx + c_len;
@@ -467,6 +541,15 @@ mod test {
         }
     }
 
+    #[test]
+    fn glyph_layout() {
+        let family = "👨\u{200d}👩\u{200d}👧";
+        let accent = "e\u{301}";
+        let input = format!("ab{family}{accent}{accent}{accent}");
+        let expected = format!("ab\n{family}{accent}\n{accent}{accent}");
+        test_layout(Size::new(3, 10), &[&input], &expected, Wrap::Normal);
+    }
+
     #[test]
     fn outliers() {
         let inputs: &[(&[&str], &str)] = &[
@@ -512,4 +595,32 @@ mod test {
     fn limited_space() {
         test_layout(Size::new(58, 0), &["meh"], "", Wrap::Normal);
     }
+
+    #[test]
+    fn measure_content() {
+        let mut measure = ContentMeasure::new(Wrap::Normal);
+        measure.add_str("one 🐇🐇🐇\ntwo ");
+        assert_eq!(measure.finish(), ContentWidth::new(6, 10));
+
+        let mut measure = ContentMeasure::new(Wrap::WordBreak);
+        measure.add_str("one 🐇🐇🐇");
+        assert_eq!(measure.finish(), ContentWidth::new(2, 10));
+    }
+
+    #[test]
+    fn measure_text_size() {
+        assert_eq!(measure_text("", 10, Wrap::Normal), Size::ZERO);
+        assert_eq!(
+            measure_text(
+                "one two
+three",
+                100,
+                Wrap::Normal
+            ),
+            Size::new(7, 2)
+        );
+        assert_eq!(measure_text("123 4567", 5, Wrap::WordBreak), Size::new(5, 2));
+        assert_eq!(measure_text("🐇🐇🐇", 5, Wrap::Normal), Size::new(4, 2));
+        assert_eq!(measure_text("abc", 0, Wrap::Normal), Size::ZERO);
+    }
 }
@@ -2,11 +2,15 @@ pub use scope::{DebugScope, Scope};
 pub use values::ValueIndex;
 
 pub use crate::nodes::eval::EvalContext;
-pub use crate::nodes::{eval_blueprint, try_resolve_future_values, update_tree, Element, Stringify, WidgetKind};
+pub use crate::nodes::{
+    damage_removed, eval_blueprint, fallback_to_boundary, panic_message, try_resolve_future_values,
+    update_loop_windows, update_tree, Element, Stringify, WidgetKind,
+};
 pub use crate::values::{Value, Values};
 pub use crate::widget::{
-    AnyWidget, AttributeStorage, Attributes, ComponentParents, Components, DirtyWidgets, Elements, Factory,
-    FloatingWidgets, LayoutChildren, PaintChildren, PositionChildren, Widget, WidgetId, WidgetRenderer, WidgetTree,
+    Ancestor, AnyWidget, AttributeStorage, Attributes, ComponentParents, Components, DirtyWidgets, Elements, Factory,
+    FloatingWidgets, Hover, HoverChanges, LayoutChildren, PaintChildren, PositionChildren, Selection, Selector, Widget,
+    WidgetArena, WidgetId, WidgetRenderer, WidgetTree,
 };
 
 pub mod components;
@@ -14,11 +18,13 @@ mod container;
 pub mod debug;
 pub mod error;
 pub mod expressions;
+pub mod glyphs;
 pub mod layout;
 mod nodes;
 pub mod paint;
 mod scope;
 #[cfg(test)]
 mod testing;
+pub mod time;
 mod values;
 mod widget;
@@ -12,7 +12,9 @@ pub type ExternalState<'bp> = SmallMap<&'bp str, (ValueIndex, Value<'bp, EvalVal
 
 #[derive(Debug)]
 pub struct Component<'bp> {
+    pub name: &'bp str,
     pub body: &'bp [Blueprint],
+    pub fallback: &'bp [Blueprint],
     pub dyn_component: Box<dyn AnyComponent>,
     pub state_id: StateId,
     pub external_state: Option<ExternalState<'bp>>,
@@ -20,11 +22,16 @@ pub struct Component<'bp> {
     pub parent: Option<WidgetComponentId>,
     pub kind: ComponentKind,
     pub assoc_functions: &'bp [(StringId, StringId)],
+    /// The body of the error boundary was replaced with the fallback.
+    /// Errors raised by the fallback are routed to the next error boundary up the tree.
+    pub(crate) fallback_active: bool,
 }
 
 impl<'bp> Component<'bp> {
     pub fn new(
+        name: &'bp str,
         body: &'bp [Blueprint],
+        fallback: &'bp [Blueprint],
         dyn_component: Box<dyn AnyComponent>,
         state_id: StateId,
         external_state: Option<ExternalState<'bp>>,
@@ -34,7 +41,9 @@ impl<'bp> Component<'bp> {
         parent: Option<WidgetComponentId>,
     ) -> Self {
         Self {
+            name,
             body,
+            fallback,
             dyn_component,
             state_id,
             external_state,
@@ -42,6 +51,7 @@ impl<'bp> Component<'bp> {
             kind,
             assoc_functions,
             parent,
+            fallback_active: false,
         }
     }
 
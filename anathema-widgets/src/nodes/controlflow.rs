@@ -75,7 +75,7 @@ mod test {
     use crate::nodes::stringify::Stringify;
     use crate::scope::Scope;
     use crate::testing::setup_test_factory;
-    use crate::{eval_blueprint, AttributeStorage, Components, EvalContext, FloatingWidgets};
+    use crate::{eval_blueprint, AttributeStorage, Components, EvalContext, FloatingWidgets, WidgetArena};
 
     #[test]
     fn if_stmt() {
@@ -94,6 +94,7 @@ mod test {
 
         let mut doc = Document::new(tpl);
         let (blueprint, globals) = doc.compile().unwrap();
+        let arena = WidgetArena::new();
         let mut widget_tree = Tree::<_>::empty();
         let mut attribute_storage = AttributeStorage::empty();
         let mut floating_widgets = FloatingWidgets::empty();
@@ -108,6 +109,7 @@ mod test {
         let mut ctx = EvalContext::new(
             &globals,
             &factory,
+            &arena,
             &mut scope,
             &mut states,
             &mut component_registry,
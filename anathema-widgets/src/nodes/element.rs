@@ -1,7 +1,8 @@
-use anathema_geometry::{Pos, Rect, Size};
+use anathema_geometry::{Pos, Rect, Region, Size};
+use anathema_templates::blueprints::Span;
 
 use crate::container::Container;
-use crate::layout::{Constraints, LayoutCtx, Viewport};
+use crate::layout::{Constraints, ContentWidth, LayoutCtx, LayoutDirection, Viewport};
 use crate::paint::{PaintCtx, Unsized};
 use crate::widget::{PaintChildren, PositionChildren};
 use crate::{AttributeStorage, LayoutChildren, WidgetId};
@@ -9,7 +10,8 @@ use crate::{AttributeStorage, LayoutChildren, WidgetId};
 #[derive(Debug)]
 pub struct Element<'bp> {
     pub ident: &'bp str,
-    pub(crate) container: Container,
+    pub(crate) container: Container<'bp>,
+    span: Span,
 }
 
 impl<'bp> Element<'bp> {
@@ -17,8 +19,13 @@ impl<'bp> Element<'bp> {
         self.container.id
     }
 
-    pub(crate) fn new(ident: &'bp str, container: Container) -> Self {
-        Self { ident, container }
+    pub(crate) fn new(ident: &'bp str, span: Span, container: Container<'bp>) -> Self {
+        Self { ident, container, span }
+    }
+
+    /// Where the element is declared in the template
+    pub fn span(&self) -> Span {
+        self.span
     }
 
     pub fn layout(
@@ -27,7 +34,16 @@ impl<'bp> Element<'bp> {
         constraints: Constraints,
         ctx: &mut LayoutCtx<'_, 'bp>,
     ) -> Size {
-        self.container.layout(children, constraints, ctx)
+        // Diagnostics are reported for this element until the layout returns
+        let parent = ctx.element.replace((self.ident, self.span, self.container.id));
+        let size = self.container.layout(children, constraints, ctx);
+        ctx.element = parent;
+        size
+    }
+
+    /// Measure the width of the content, without laying it out
+    pub fn measure(&mut self, children: LayoutChildren<'_, '_, 'bp>, ctx: &mut LayoutCtx<'_, 'bp>) -> ContentWidth {
+        self.container.measure(children, ctx)
     }
 
     /// Position the element
@@ -55,6 +71,16 @@ impl<'bp> Element<'bp> {
         self.container.size
     }
 
+    /// The row of the text baseline, as reported by the widget after the layout
+    pub fn baseline(&self) -> Option<usize> {
+        self.container.inner.any_baseline()
+    }
+
+    /// The layout direction of the element, from the most recent layout
+    pub fn direction(&self) -> LayoutDirection {
+        self.container.direction
+    }
+
     pub fn inner_bounds(&self) -> Rect {
         self.container.inner_bounds
     }
@@ -91,4 +117,16 @@ impl<'bp> Element<'bp> {
     pub fn get_pos(&self) -> Pos {
         self.container.pos
     }
+
+    /// The region the element occupied during the most recent layout and position pass,
+    /// in global space.
+    /// This can be used for hit-testing, or to anchor one element to another.
+    pub fn region(&self) -> Region {
+        Region::from((self.container.pos, self.container.size))
+    }
+
+    /// Returns true if the mouse cursor is over the element
+    pub fn is_hovered(&self) -> bool {
+        self.container.hovered
+    }
 }
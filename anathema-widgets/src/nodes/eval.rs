@@ -1,24 +1,28 @@
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+
 use anathema_geometry::{Pos, Rect, Size};
-use anathema_state::{AnyState, States, Value};
+use anathema_state::{AnyState, States};
 use anathema_store::smallmap::{SmallIndex, SmallMap};
-use anathema_templates::blueprints::{Component, ControlFlow, Else, For, If, Single};
+use anathema_templates::blueprints::{Blueprint, Component, ControlFlow, Else, For, If, Single};
 use anathema_templates::{Globals, WidgetComponentId};
 
 use super::element::Element;
-use super::loops::{Iteration, LOOP_INDEX};
 use super::{component, controlflow};
 use crate::components::{AnyComponent, ComponentKind, ComponentRegistry};
 use crate::container::Container;
 use crate::error::{Error, Result};
 use crate::expressions::{eval, eval_collection};
+use crate::layout::LayoutDirection;
 use crate::values::{ValueId, ValueIndex};
 use crate::widget::{Attributes, Components, FloatingWidgets, ValueKey};
-use crate::{eval_blueprint, AttributeStorage, Factory, Scope, WidgetKind, WidgetTree};
+use crate::{eval_blueprint, AttributeStorage, Factory, Scope, WidgetArena, WidgetKind, WidgetTree};
 
 /// Evaluation context
 pub struct EvalContext<'a, 'b, 'bp> {
     pub(super) globals: &'bp Globals,
     pub(super) factory: &'a Factory,
+    pub(super) arena: &'bp WidgetArena,
     pub(super) scope: &'b mut Scope<'bp>,
     pub(super) states: &'b mut States,
     pub(super) component_registry: &'b mut ComponentRegistry,
@@ -31,6 +35,7 @@ impl<'a, 'b, 'bp> EvalContext<'a, 'b, 'bp> {
     pub fn new(
         globals: &'bp Globals,
         factory: &'a Factory,
+        arena: &'bp WidgetArena,
         scope: &'b mut Scope<'bp>,
         states: &'b mut States,
         component_registry: &'b mut ComponentRegistry,
@@ -41,6 +46,7 @@ impl<'a, 'b, 'bp> EvalContext<'a, 'b, 'bp> {
         Self {
             globals,
             factory,
+            arena,
             scope,
             states,
             component_registry,
@@ -53,8 +59,9 @@ impl<'a, 'b, 'bp> EvalContext<'a, 'b, 'bp> {
     fn get_component(
         &mut self,
         component_id: WidgetComponentId,
+        attributes: &Attributes<'_>,
     ) -> Option<(ComponentKind, Box<dyn AnyComponent>, Box<dyn AnyState>)> {
-        self.component_registry.get(component_id)
+        self.component_registry.get(component_id, attributes)
     }
 }
 
@@ -104,7 +111,7 @@ impl Evaluator for SingleEval {
             });
         }
 
-        let widget = ctx.factory.make(&single.ident, &attributes)?;
+        let widget = ctx.factory.make(&single.ident, &attributes, ctx.arena)?;
 
         // Is the widget a floating widget?
         if widget.any_floats() {
@@ -121,11 +128,18 @@ impl Evaluator for SingleEval {
             size: Size::ZERO,
             inner_bounds: Rect::ZERO,
             needs_layout: true,
+            constraints: None,
             needs_position: false,
+            needs_paint: true,
+            painted_region: None,
+            removed_regions: vec![],
+            hovered: false,
+            direction: LayoutDirection::Ltr,
+            measured: false,
         };
 
         // Widget
-        let widget = WidgetKind::Element(Element::new(&single.ident, container));
+        let widget = WidgetKind::Element(Element::new(&single.ident, single.span, container));
 
         transaction.commit_child(widget).ok_or(Error::TreeTransactionFailed)?;
 
@@ -141,44 +155,6 @@ impl Evaluator for SingleEval {
 
 pub(super) struct ForLoopEval;
 
-impl ForLoopEval {
-    pub(super) fn eval_body<'bp>(
-        &self,
-        for_loop: &super::loops::For<'bp>,
-        ctx: &mut EvalContext<'_, '_, 'bp>,
-        parent: &[u16],
-        tree: &mut WidgetTree<'bp>,
-    ) -> Result<()> {
-        for index in 0..for_loop.collection.count() {
-            ctx.scope.push();
-            for_loop.scope_value(ctx.scope, index);
-
-            let iter_id = tree
-                .insert(parent)
-                .commit_child(WidgetKind::Iteration(Iteration {
-                    loop_index: Value::new(index as i64),
-                    binding: for_loop.binding,
-                }))
-                .ok_or(Error::TreeTransactionFailed)?;
-
-            // Scope the iteration value
-            tree.with_value_mut(iter_id, |parent, widget, tree| {
-                let WidgetKind::Iteration(iter) = widget else { unreachable!() };
-                ctx.scope.scope_pending(LOOP_INDEX, iter.loop_index.to_pending());
-
-                for bp in for_loop.body {
-                    eval_blueprint(bp, ctx, parent, tree)?;
-                }
-                Ok(())
-            })?;
-
-            ctx.scope.pop();
-        }
-
-        Ok(())
-    }
-}
-
 impl Evaluator for ForLoopEval {
     type Input<'bp> = &'bp For;
 
@@ -196,16 +172,16 @@ impl Evaluator for ForLoopEval {
             binding: &for_loop.binding,
             collection: eval_collection(&for_loop.data, ctx.globals, ctx.scope, ctx.states, value_id),
             body: &for_loop.body,
+            window: None,
         };
 
         let widget = WidgetKind::For(for_loop);
 
         let for_loop_id = transaction.commit_child(widget).ok_or(Error::TreeTransactionFailed)?;
 
-        tree.with_value_mut(for_loop_id, move |parent, widget, tree| {
+        tree.with_value_mut(for_loop_id, move |path, widget, tree| {
             let WidgetKind::For(for_loop) = widget else { unreachable!() };
-            self.eval_body(for_loop, ctx, parent, tree)?;
-            Ok(())
+            for_loop.generate(ctx, path, tree)
         })?;
 
         Ok(())
@@ -340,6 +316,17 @@ impl Evaluator for ElseEval {
     }
 }
 
+/// The message of a panic payload, as caught by `catch_unwind`.
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    match payload.downcast_ref::<String>() {
+        Some(msg) => msg.clone(),
+        None => match payload.downcast_ref::<&str>() {
+            Some(msg) => msg.to_string(),
+            None => "unknown panic".into(),
+        },
+    }
+}
+
 pub(super) struct ComponentEval;
 
 impl Evaluator for ComponentEval {
@@ -352,6 +339,16 @@ impl Evaluator for ComponentEval {
         parent: &[u16],
         tree: &mut WidgetTree<'bp>,
     ) -> Result<()> {
+        // A recursive component uses the body of the closest instance of itself
+        let body: &'bp [Blueprint] = match input.recursion_limit {
+            None => &input.body,
+            Some(limit) => match ctx.scope.component(input.id) {
+                Some((_, depth)) if depth >= limit => return Err(Error::RecursionLimit(input.name.to_string())),
+                Some((body, _)) => body,
+                None => unreachable!("a recursive component is always evaluated inside an instance of itself"),
+            },
+        };
+
         let transaction = tree.insert(parent);
 
         let external_state = match &input.state {
@@ -367,11 +364,25 @@ impl Evaluator for ComponentEval {
             None => None,
         };
 
+        // Attributes are evaluated before the state is created,
+        // as prototypes can create their state from the attributes
+        let widget_id = transaction.node_id();
+        let mut attributes = Attributes::empty(widget_id);
+        for (key, expr) in input.attributes.iter() {
+            attributes.insert_with(ValueKey::Attribute(key), |value_index| {
+                eval(expr, ctx.globals, ctx.scope, ctx.states, (widget_id, value_index))
+            });
+        }
+
         let component_id = usize::from(input.id).into();
-        let (kind, component, state) = ctx.get_component(component_id).ok_or(Error::ComponentConsumed)?;
+        let (kind, component, state) = ctx
+            .get_component(component_id, &attributes)
+            .ok_or(Error::ComponentConsumed)?;
         let state_id = ctx.states.insert(state);
         let comp_widget = component::Component::new(
-            &input.body,
+            &input.name,
+            body,
+            &input.fallback,
             component,
             state_id,
             external_state,
@@ -385,13 +396,6 @@ impl Evaluator for ComponentEval {
             .commit_child(WidgetKind::Component(comp_widget))
             .ok_or(Error::TreeTransactionFailed)?;
 
-        // Attributes
-        let mut attributes = Attributes::empty(widget_id);
-        for (key, expr) in input.attributes.iter() {
-            attributes.insert_with(ValueKey::Attribute(key), |value_index| {
-                eval(expr, ctx.globals, ctx.scope, ctx.states, (widget_id, value_index))
-            });
-        }
         ctx.attribute_storage.insert(widget_id, attributes);
 
         let path = tree.path(widget_id);
@@ -399,11 +403,13 @@ impl Evaluator for ComponentEval {
 
         tree.with_value_mut(widget_id, move |parent, widget, tree| {
             let WidgetKind::Component(component) = widget else { unreachable!() };
+            let level = ctx.scope.level();
             ctx.scope.push();
 
             // Insert internal state
             let state_id = component.state_id();
             ctx.scope.insert_state(state_id);
+            ctx.scope.insert_component(component.component_id, body);
 
             // Insert external state (if there is one)
             if let Some(state) = &component.external_state {
@@ -413,8 +419,38 @@ impl Evaluator for ComponentEval {
                 }
             }
 
-            for bp in &input.body {
-                eval_blueprint(bp, ctx, parent, tree)?;
+            if !component.dyn_component.any_error_boundary() {
+                for bp in body {
+                    eval_blueprint(bp, ctx, parent, tree)?;
+                }
+                ctx.scope.pop();
+                return Ok(());
+            }
+
+            let res = panic::catch_unwind(AssertUnwindSafe(|| -> Result<()> {
+                for bp in body {
+                    eval_blueprint(bp, ctx, parent, tree)?;
+                }
+                Ok(())
+            }));
+
+            let error = match res {
+                Ok(Ok(())) => None,
+                Ok(Err(err)) => Some(err.to_string()),
+                Err(payload) => Some(panic_message(&*payload)),
+            };
+
+            if let Some(error) = error {
+                // Discard whatever was evaluated before the error occurred
+                // and replace it with the fallback
+                ctx.scope.pop_to(level + 1);
+                tree.remove_children(parent);
+                component.fallback_active = true;
+                ctx.components.push_error(widget_id, state_id, error);
+
+                for bp in &input.fallback {
+                    eval_blueprint(bp, ctx, parent, tree)?;
+                }
             }
 
             ctx.scope.pop();
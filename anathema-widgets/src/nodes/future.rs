@@ -4,19 +4,19 @@ use anathema_templates::Globals;
 
 use super::element::Element;
 use super::eval::EvalContext;
-use super::loops::LOOP_INDEX;
 use super::update::scope_value;
 use crate::components::ComponentRegistry;
-use crate::error::{Error, Result};
+use crate::error::Result;
 use crate::expressions::{eval, eval_collection};
-use crate::values::{Collection, ValueId};
+use crate::values::ValueId;
 use crate::widget::{Components, FloatingWidgets};
-use crate::{AttributeStorage, Factory, Scope, WidgetKind, WidgetTree};
+use crate::{AttributeStorage, Factory, Scope, WidgetArena, WidgetKind, WidgetTree};
 
 struct ResolveFutureValues<'a, 'b, 'bp> {
     globals: &'bp Globals,
     value_id: ValueId,
     factory: &'a Factory,
+    arena: &'bp WidgetArena,
     scope: &'b mut Scope<'bp>,
     states: &'b mut States,
     component_registry: &'b mut ComponentRegistry,
@@ -33,6 +33,7 @@ impl<'a, 'b, 'bp> PathFinder<WidgetKind<'bp>> for ResolveFutureValues<'a, 'b, 'b
         let mut ctx = EvalContext::new(
             self.globals,
             self.factory,
+            self.arena,
             self.scope,
             self.states,
             self.component_registry,
@@ -54,6 +55,7 @@ impl<'a, 'b, 'bp> PathFinder<WidgetKind<'bp>> for ResolveFutureValues<'a, 'b, 'b
 pub fn try_resolve_future_values<'bp>(
     globals: &'bp Globals,
     factory: &Factory,
+    arena: &'bp WidgetArena,
     scope: &mut Scope<'bp>,
     states: &mut States,
     component_registry: &mut ComponentRegistry,
@@ -68,6 +70,7 @@ pub fn try_resolve_future_values<'bp>(
         globals,
         value_id,
         factory,
+        arena,
         scope,
         states,
         component_registry,
@@ -116,65 +119,7 @@ fn try_resolve_value<'bp>(
 
             tree.remove_children(path);
 
-            let collection = &for_loop.collection;
-            let binding = &for_loop.binding;
-            let body = for_loop.body;
-            let parent = path;
-
-            for index in 0..collection.count() {
-                ctx.scope.push();
-
-                match collection.inner() {
-                    Collection::Static(expressions) => {
-                        let downgrade = expressions[index].downgrade();
-                        ctx.scope.scope_downgrade(binding, downgrade)
-                    }
-                    Collection::Dyn(value_ref) => {
-                        let value = value_ref
-                            .as_state()
-                            .and_then(|state| state.state_lookup(index.into()))
-                            .expect("the collection has a value since it has a length");
-                        ctx.scope.scope_pending(binding, value)
-                    }
-                    Collection::Index(collection, _) => match &**collection {
-                        Collection::Static(expressions) => {
-                            let downgrade = expressions[index].downgrade();
-                            ctx.scope.scope_downgrade(binding, downgrade)
-                        }
-                        Collection::Dyn(value_ref) => {
-                            let value = value_ref
-                                .as_state()
-                                .and_then(|state| state.state_lookup(index.into()))
-                                .expect("the collection has a value since it has a length");
-                            ctx.scope.scope_pending(binding, value)
-                        }
-                        Collection::Future => {}
-                        Collection::Index(_, _) => unreachable!("maaybe it's not?"),
-                    },
-                    Collection::Future => {}
-                }
-
-                let iter_id = tree
-                    .insert(parent)
-                    .commit_child(WidgetKind::Iteration(super::loops::Iteration {
-                        loop_index: anathema_state::Value::new(index as i64),
-                        binding,
-                    }))
-                    .ok_or(Error::TreeTransactionFailed)?;
-
-                // Scope the iteration value
-                tree.with_value_mut(iter_id, |parent, widget, tree| {
-                    let WidgetKind::Iteration(iter) = widget else { unreachable!() };
-                    ctx.scope.scope_pending(LOOP_INDEX, iter.loop_index.to_pending());
-
-                    for bp in body {
-                        crate::eval_blueprint(bp, ctx, parent, tree)?;
-                    }
-                    Ok(())
-                })?;
-
-                ctx.scope.pop();
-            }
+            for_loop.generate(ctx, path, tree)?;
         }
         WidgetKind::If(widget) => {
             if let Some(expr) = widget.cond.expr {
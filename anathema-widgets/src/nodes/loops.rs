@@ -1,5 +1,7 @@
+use std::ops::Range;
+
 use anathema_state::Change;
-use anathema_store::tree::new_node_path;
+use anathema_store::tree::{new_node_path, AsNodePath};
 use anathema_templates::blueprints::Blueprint;
 
 use super::WidgetKind;
@@ -8,7 +10,7 @@ use crate::expressions::eval_collection;
 use crate::nodes::EvalContext;
 use crate::scope::Scope;
 use crate::values::{Collection, ValueId};
-use crate::{eval_blueprint, Value, WidgetTree};
+use crate::{damage_removed, eval_blueprint, AttributeStorage, Value, WidgetTree};
 
 pub(super) const LOOP_INDEX: &str = "loop";
 
@@ -17,6 +19,10 @@ pub struct For<'bp> {
     pub(super) binding: &'bp str,
     pub(super) collection: Value<'bp, Collection<'bp>>,
     pub(super) body: &'bp [Blueprint],
+    /// The generated iterations, if the widget above the loop only shows some of them
+    /// (see [`Widget::visible_iterations`](crate::Widget::visible_iterations)).
+    /// `None` if every iteration is generated.
+    pub(super) window: Option<Range<usize>>,
 }
 
 impl<'bp> For<'bp> {
@@ -28,6 +34,121 @@ impl<'bp> For<'bp> {
         self.collection.inner()
     }
 
+    /// The index in the collection of the iteration at the given child index
+    pub(super) fn index(&self, child: usize) -> usize {
+        match &self.window {
+            Some(window) => window.start + child,
+            None => child,
+        }
+    }
+
+    /// Generate the iterations visible through the widget above the loop,
+    /// or every iteration if the widget shows all of them.
+    pub(super) fn generate(
+        &mut self,
+        ctx: &mut EvalContext<'_, '_, 'bp>,
+        path: &[u16],
+        tree: &mut WidgetTree<'bp>,
+    ) -> Result<()> {
+        let count = self.collection.count();
+        self.window = visible_iterations(path, count, tree, ctx.attribute_storage);
+
+        let iterations = self.window.clone().unwrap_or(0..count);
+        for (child, index) in iterations.enumerate() {
+            self.generate_iteration(ctx, path, child, index, tree)?;
+        }
+
+        self.generated_iterations(path, tree);
+        Ok(())
+    }
+
+    // Insert the iteration for the value at `index` in the collection at the given child index
+    fn generate_iteration(
+        &self,
+        ctx: &mut EvalContext<'_, '_, 'bp>,
+        path: &[u16],
+        child: usize,
+        index: usize,
+        tree: &mut WidgetTree<'bp>,
+    ) -> Result<()> {
+        ctx.scope.push();
+        self.scope_value(ctx.scope, index);
+
+        let iter_id = tree
+            .insert(&new_node_path(path, child as u16))
+            .commit_at(WidgetKind::Iteration(Iteration {
+                loop_index: anathema_state::Value::new(index as i64),
+                binding: self.binding,
+            }))
+            .ok_or(Error::TreeTransactionFailed)?;
+
+        // Scope the iteration value
+        tree.with_value_mut(iter_id, |parent, widget, tree| -> Result<()> {
+            let WidgetKind::Iteration(iter) = widget else { unreachable!() };
+            ctx.scope.scope_pending(LOOP_INDEX, iter.loop_index.to_pending());
+
+            for bp in self.body {
+                eval_blueprint(bp, ctx, parent, tree)?;
+            }
+
+            Ok(())
+        })?;
+
+        ctx.scope.pop();
+        Ok(())
+    }
+
+    // Keep the iterations that are still visible through the widget above the loop,
+    // remove the ones that are not, and generate the ones that became visible.
+    fn update_window(
+        &mut self,
+        ctx: &mut EvalContext<'_, '_, 'bp>,
+        path: &[u16],
+        tree: &mut WidgetTree<'bp>,
+    ) -> Result<()> {
+        let Some(window) = self.window.clone() else { return Ok(()) };
+        let count = self.collection.count();
+        let Some(visible) = visible_iterations(path, count, tree, ctx.attribute_storage) else {
+            return Ok(());
+        };
+        if visible == window {
+            return Ok(());
+        }
+
+        let kept = window.start.max(visible.start)..window.end.min(visible.end);
+        let kept = match kept.is_empty() {
+            true => visible.start..visible.start,
+            false => kept,
+        };
+
+        // Remove from the back, so the child index of the
+        // iterations that are yet to be removed doesn't change
+        for index in window.clone().rev().filter(|index| !kept.contains(index)) {
+            let child = new_node_path(path, (index - window.start) as u16);
+            damage_removed(&child, tree);
+            tree.remove(&child);
+        }
+
+        for index in (visible.start..kept.start).chain(kept.end..visible.end) {
+            self.generate_iteration(ctx, path, index - visible.start, index, tree)?;
+        }
+
+        self.window = Some(visible);
+        self.generated_iterations(path, tree);
+        Ok(())
+    }
+
+    // Tell the widget above the loop which iterations are generated
+    fn generated_iterations(&self, path: &[u16], tree: &mut WidgetTree<'bp>) {
+        let Some(window) = self.window.clone() else { return };
+        let Some(parent) = path.parent() else { return };
+        if let Some(WidgetKind::Element(el)) = tree.get_mut_by_path(parent) {
+            el.container
+                .inner
+                .any_generated_iterations(window, self.collection.count());
+        }
+    }
+
     pub(crate) fn update(
         &mut self,
         ctx: &mut EvalContext<'_, '_, 'bp>,
@@ -45,14 +166,36 @@ impl<'bp> For<'bp> {
                 // 5. Scope new value
                 // 6. Eval body
 
+                // Iterations inserted outside of the generated ones
+                // are generated once they are visible
+                let index = *index as usize;
+                let child = match &mut self.window {
+                    None => Some(index),
+                    Some(window) if index < window.start => {
+                        *window = window.start + 1..window.end + 1;
+                        shift_loop_index(path, 1, tree);
+                        None
+                    }
+                    Some(window) if index < window.end => {
+                        window.end += 1;
+                        Some(index - window.start)
+                    }
+                    Some(_) => None,
+                };
+
+                let Some(child) = child else {
+                    self.generated_iterations(path, tree);
+                    return Ok(());
+                };
+
                 ctx.scope.push();
                 ctx.scope.scope_pending(self.binding, *value);
 
-                let insert_at = new_node_path(path, *index as u16);
+                let insert_at = new_node_path(path, child as u16);
                 let iter_id = tree
                     .insert(&insert_at)
                     .commit_at(WidgetKind::Iteration(Iteration {
-                        loop_index: anathema_state::Value::new(*index as i64),
+                        loop_index: anathema_state::Value::new(index as i64),
                         binding: self.binding,
                     }))
                     .unwrap(); // TODO unwrap
@@ -98,10 +241,38 @@ impl<'bp> For<'bp> {
                 })?;
 
                 ctx.scope.pop();
+                self.generated_iterations(path, tree);
             }
             Change::Removed(index) => {
-                let child_to_remove = new_node_path(path, *index as u16);
-                tree.remove(&child_to_remove);
+                let index = *index as usize;
+                let child = match &mut self.window {
+                    None => Some(index),
+                    Some(window) if index < window.start => {
+                        *window = window.start - 1..window.end - 1;
+                        shift_loop_index(path, -1, tree);
+                        None
+                    }
+                    Some(window) if index < window.end => {
+                        window.end -= 1;
+                        Some(index - window.start)
+                    }
+                    Some(_) => None,
+                };
+
+                if let Some(child) = child {
+                    let child_to_remove = new_node_path(path, child as u16);
+                    // The loop index of the generated iterations has to match the collection,
+                    // as it's used to scope the values of the iterations that are generated later
+                    if self.window.is_some() {
+                        tree.children_after(&child_to_remove, |node, values| {
+                            let Some((_, WidgetKind::Iteration(iter))) = values.get_mut(node.value()) else { return };
+                            *iter.loop_index.to_mut() -= 1;
+                        });
+                    }
+                    damage_removed(&child_to_remove, tree);
+                    tree.remove(&child_to_remove);
+                }
+                self.generated_iterations(path, tree);
             }
             Change::Dropped => {
                 tree.remove_children(path);
@@ -115,34 +286,13 @@ impl<'bp> For<'bp> {
                     value_id,
                 );
 
-                for index in 0..self.collection.count() {
-                    self.scope_value(ctx.scope, index);
-                    ctx.scope.push();
-
-                    let iter_id = tree
-                        .insert(path)
-                        .commit_child(WidgetKind::Iteration(Iteration {
-                            loop_index: anathema_state::Value::new(index as i64),
-                            binding: self.binding,
-                        }))
-                        .ok_or(Error::TreeTransactionFailed)?;
-
-                    // Scope the iteration value
-                    tree.with_value_mut(iter_id, |parent, widget, tree| -> Result<()> {
-                        let WidgetKind::Iteration(iter) = widget else { unreachable!() };
-                        ctx.scope.scope_pending(LOOP_INDEX, iter.loop_index.to_pending());
-
-                        for bp in self.body {
-                            eval_blueprint(bp, ctx, parent, tree)?;
-                        }
-
-                        Ok(())
-                    })?;
-
-                    ctx.scope.pop();
-                }
+                self.generate(ctx, path, tree)?;
             }
             Change::Changed => {
+                // Loops that only generate the visible iterations are
+                // changed after the layout, e.g when the widget above the loop scrolled
+                self.update_window(ctx, path, tree)?;
+
                 // TODO implement this as an optimisation once the runtime is done.
                 //      Use this to flag the element as needs-layout.
                 //      Every element that needs layout should apply
@@ -156,6 +306,28 @@ impl<'bp> For<'bp> {
     }
 }
 
+/// The iterations to generate out of `count`, if the widget above the loop
+/// only shows some of them (see [`Widget::visible_iterations`](crate::Widget::visible_iterations))
+pub(super) fn visible_iterations(
+    path: &[u16],
+    count: usize,
+    tree: &WidgetTree<'_>,
+    attribute_storage: &AttributeStorage<'_>,
+) -> Option<Range<usize>> {
+    let WidgetKind::Element(el) = tree.get_ref_by_path(path.parent()?)? else { return None };
+    el.container
+        .inner
+        .any_visible_iterations(count, attribute_storage.get(el.id()))
+}
+
+// Move the loop index of every generated iteration
+fn shift_loop_index(path: &[u16], by: i64, tree: &mut WidgetTree<'_>) {
+    tree.children_of(path, |node, values| {
+        let Some((_, WidgetKind::Iteration(iter))) = values.get_mut(node.value()) else { return };
+        *iter.loop_index.to_mut() += by;
+    });
+}
+
 #[derive(Debug)]
 pub struct Iteration<'bp> {
     pub loop_index: anathema_state::Value<i64>,
@@ -171,9 +343,9 @@ mod test {
     use super::*;
     use crate::components::ComponentRegistry;
     use crate::nodes::stringify::Stringify;
-    use crate::nodes::{eval_blueprint, update_tree};
+    use crate::nodes::{eval_blueprint, update_loop_windows, update_tree};
     use crate::testing::setup_test_factory;
-    use crate::{AttributeStorage, Components, FloatingWidgets};
+    use crate::{AttributeStorage, Components, FloatingWidgets, WidgetArena};
 
     #[test]
     fn loop_remove() {
@@ -192,6 +364,8 @@ mod test {
         ";
 
         let (blueprint, globals) = Document::new(tpl).compile().unwrap();
+
+        let arena = WidgetArena::new();
         let mut widget_tree = WidgetTree::empty();
         let mut attribute_storage = AttributeStorage::empty();
         let mut floating_widgets = FloatingWidgets::empty();
@@ -205,6 +379,7 @@ mod test {
         let mut ctx = EvalContext::new(
             &globals,
             &factory,
+            &arena,
             &mut scope,
             &mut states,
             &mut component_registry,
@@ -250,6 +425,7 @@ mod test {
                 update_tree(
                     &globals,
                     &factory,
+                    &arena,
                     &mut scope,
                     &mut states,
                     &mut component_registry,
@@ -275,19 +451,19 @@ mod test {
 
         let expected = "
 <for>
-    <iter binding = x, index = 2>
+    <iter binding = x, index = 1>
         test Int(9)
-    <iter binding = x, index = 3>
+    <iter binding = x, index = 2>
         test Int(1)
-    <iter binding = x, index = 4>
+    <iter binding = x, index = 3>
         test Int(2)
-    <iter binding = x, index = 5>
+    <iter binding = x, index = 4>
         test Int(3)
-    <iter binding = x, index = 6>
+    <iter binding = x, index = 5>
         test Int(100)
-    <iter binding = x, index = 7>
+    <iter binding = x, index = 6>
         test Int(101)
-    <iter binding = x, index = 8>
+    <iter binding = x, index = 7>
         test Int(102)";
         assert_eq!(expected.trim(), output.trim());
     }
@@ -308,6 +484,7 @@ mod test {
                 test x
         ";
         let (blueprint, globals) = Document::new(tpl).compile().unwrap();
+        let arena = WidgetArena::new();
         let mut tree = WidgetTree::empty();
         let mut attribute_storage = AttributeStorage::empty();
         let mut floating_widgets = FloatingWidgets::empty();
@@ -321,6 +498,7 @@ mod test {
         let mut ctx = EvalContext::new(
             &globals,
             &factory,
+            &arena,
             &mut scope,
             &mut states,
             &mut component_reg,
@@ -351,4 +529,127 @@ mod test {
 ";
         assert_eq!(expected.trim(), output.trim());
     }
+
+    #[test]
+    fn loop_window() {
+        let mut list = List::empty();
+        for i in 0..6u32 {
+            list.push_back(i);
+        }
+        let mut map = Map::<List<_>>::empty();
+        map.insert("a", list);
+
+        let tpl = "
+        window [start: 2, end: 4]
+            for x in a
+                test x
+        ";
+        let (blueprint, globals) = Document::new(tpl).compile().unwrap();
+        let arena = WidgetArena::new();
+        let mut tree = WidgetTree::empty();
+        let mut attribute_storage = AttributeStorage::empty();
+        let mut floating_widgets = FloatingWidgets::empty();
+        let mut components = Components::new();
+        let factory = setup_test_factory();
+        let mut component_reg = ComponentRegistry::new();
+        let mut states = States::new();
+        let state_id = states.insert(Box::new(map));
+        let mut scope = Scope::new();
+        scope.insert_state(state_id);
+        let mut ctx = EvalContext::new(
+            &globals,
+            &factory,
+            &arena,
+            &mut scope,
+            &mut states,
+            &mut component_reg,
+            &mut attribute_storage,
+            &mut floating_widgets,
+            &mut components,
+        );
+        eval_blueprint(&blueprint, &mut ctx, root_node(), &mut tree).unwrap();
+
+        let mut stringify = Stringify::new(&attribute_storage);
+        tree.apply_visitor(&mut stringify);
+        let output = stringify.finish();
+
+        // Only the iterations shown by the window are generated
+        let expected = "
+window[start: Int(2), end: Int(4)]
+    <for>
+        <iter binding = x, index = 2>
+            test Int(2)
+        <iter binding = x, index = 3>
+            test Int(3)
+";
+        assert_eq!(expected.trim(), output.trim());
+
+        {
+            let map = states.get_mut(StateId::ZERO).unwrap();
+            let map = map
+                .to_any_mut()
+                .downcast_mut::<anathema_state::Value<Map<List<u32>>>>()
+                .unwrap();
+            let mut map = map.to_mut();
+            let list = map.get_mut("a").unwrap();
+            list.insert(0, 10); // 10, 0, 1, 2, 3, 4, 5
+            list.remove(4); // 10, 0, 1, 2, 4, 5
+            list.push_back(6); // 10, 0, 1, 2, 4, 5, 6
+        }
+
+        let mut local_changes = Changes::empty();
+        drain_changes(&mut local_changes);
+        local_changes.drain().rev().for_each(|(subs, change)| {
+            subs.with(|sub| {
+                let mut scope = Scope::with_capacity(10);
+                let Some(widget_path) = tree.try_path(sub) else { return };
+                update_tree(
+                    &globals,
+                    &factory,
+                    &arena,
+                    &mut scope,
+                    &mut states,
+                    &mut component_reg,
+                    &change,
+                    sub,
+                    &widget_path,
+                    &mut tree,
+                    &mut attribute_storage,
+                    &mut floating_widgets,
+                    &mut components,
+                );
+            });
+        });
+
+        // The inserted value moved the generated iterations, the removed value left only
+        // one of them, and the change to the list that follows generated the missing one
+        let mut stringify = Stringify::new(&attribute_storage);
+        tree.apply_visitor(&mut stringify);
+        let output = stringify.finish();
+        let expected = "
+window[start: Int(2), end: Int(4)]
+    <for>
+        <iter binding = x, index = 2>
+            test Int(1)
+        <iter binding = x, index = 3>
+            test Int(2)
+";
+        assert_eq!(expected.trim(), output.trim());
+
+        let mut scope = Scope::new();
+        let changed = update_loop_windows(
+            &globals,
+            &factory,
+            &arena,
+            &mut scope,
+            &mut states,
+            &mut component_reg,
+            &mut tree,
+            &mut attribute_storage,
+            &mut floating_widgets,
+            &mut components,
+        );
+        // The generated iterations are already the ones shown by the window
+        assert!(changed.is_empty());
+    }
 }
@@ -2,10 +2,11 @@ use anathema_templates::blueprints::Blueprint;
 
 pub use self::component::ExternalState;
 pub use self::element::Element;
+pub use self::eval::panic_message;
 use self::eval::{ComponentEval, ControlFlowEval, EvalContext, Evaluator, ForLoopEval, SingleEval};
 pub use self::future::try_resolve_future_values;
 pub use self::stringify::Stringify;
-pub use self::update::update_tree;
+pub use self::update::{damage_removed, fallback_to_boundary, update_loop_windows, update_tree};
 use crate::error::Result;
 use crate::WidgetTree;
 
@@ -29,6 +30,32 @@ pub enum WidgetKind<'bp> {
     Component(component::Component<'bp>),
 }
 
+impl<'bp> WidgetKind<'bp> {
+    /// The element, if the widget is an element.
+    ///
+    /// Useful for filtering a traversal of the [`WidgetTree`]:
+    /// `tree.pre_order().filter_map(|(id, _, widget)| Some((id, widget.as_element()?)))`
+    pub fn as_element(&self) -> Option<&Element<'bp>> {
+        match self {
+            Self::Element(el) => Some(el),
+            _ => None,
+        }
+    }
+
+    /// The element, if the widget is an element
+    pub fn as_element_mut(&mut self) -> Option<&mut Element<'bp>> {
+        match self {
+            Self::Element(el) => Some(el),
+            _ => None,
+        }
+    }
+
+    /// True if the widget is a component
+    pub fn is_component(&self) -> bool {
+        matches!(self, Self::Component(_))
+    }
+}
+
 pub fn eval_blueprint<'bp>(
     blueprint: &'bp Blueprint,
     ctx: &mut EvalContext<'_, '_, 'bp>,
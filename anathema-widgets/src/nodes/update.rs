@@ -1,21 +1,25 @@
+use std::panic::{self, AssertUnwindSafe};
+
+use anathema_geometry::Region;
 use anathema_state::{Change, States};
-use anathema_store::tree::PathFinder;
+use anathema_store::tree::{Node, PathFinder, TreeValues};
 use anathema_templates::Globals;
 
 use super::element::Element;
-use super::eval::EvalContext;
-use super::loops::LOOP_INDEX;
+use super::eval::{panic_message, EvalContext};
+use super::loops::{visible_iterations, LOOP_INDEX};
 use crate::components::ComponentRegistry;
 use crate::error::Result;
-use crate::values::ValueId;
+use crate::values::{ValueId, ValueIndex};
 use crate::widget::{Components, FloatingWidgets};
-use crate::{AttributeStorage, Factory, Scope, WidgetKind, WidgetTree};
+use crate::{eval_blueprint, AttributeStorage, Factory, Scope, WidgetArena, WidgetId, WidgetKind, WidgetTree};
 
 struct UpdateTree<'a, 'b, 'bp> {
     globals: &'bp Globals,
     value_id: ValueId,
     change: &'a Change,
     factory: &'a Factory,
+    arena: &'bp WidgetArena,
     scope: &'b mut Scope<'bp>,
     states: &'b mut States,
     component_registry: &'b mut ComponentRegistry,
@@ -28,14 +32,26 @@ impl<'a, 'b, 'bp> PathFinder<WidgetKind<'bp>> for UpdateTree<'a, 'b, 'bp> {
     type Output = Result<()>;
 
     fn apply(&mut self, node: &mut WidgetKind<'bp>, path: &[u16], tree: &mut WidgetTree<'bp>) -> Self::Output {
-        if let WidgetKind::Element(el) = node {
-            el.container.needs_layout = true;
+        match node {
+            WidgetKind::Element(el) => {
+                el.container.needs_layout = true;
+                el.container.needs_paint = true;
+            }
+            // Loops and conditionals add and remove widgets,
+            // so the screen under the closest element has to be painted again
+            _ => repaint_closest_element(path, tree),
         }
 
+        // NOTE
+        // The ancestors are not marked as needing layout here.
+        // Only the closest element above the node is laid out again,
+        // and the layout only continues up the tree if its size changed (see `relayout_widget`).
+
         scope_value(node, self.scope, &[]);
         let mut ctx = EvalContext::new(
             self.globals,
             self.factory,
+            self.arena,
             self.scope,
             self.states,
             self.component_registry,
@@ -49,18 +65,65 @@ impl<'a, 'b, 'bp> PathFinder<WidgetKind<'bp>> for UpdateTree<'a, 'b, 'bp> {
     }
 
     fn parent(&mut self, parent: &mut WidgetKind<'bp>, children: &[u16]) {
-        if let WidgetKind::Element(el) = parent {
-            el.container.needs_layout = true;
-        }
         scope_value(parent, self.scope, children);
     }
 }
 
+fn repaint_closest_element(mut path: &[u16], tree: &mut WidgetTree<'_>) {
+    while let [parent @ .., _] = path {
+        if let Some(WidgetKind::Element(el)) = tree.get_mut_by_path(parent) {
+            el.container.painted_region = None;
+            return;
+        }
+        path = parent;
+    }
+}
+
+/// Damage the regions painted by a widget and its descendants,
+/// as the widget is about to be removed from the tree.
+/// The regions are damaged by the closest element above the widget when it's painted.
+pub fn damage_removed(path: &[u16], tree: &mut WidgetTree<'_>) {
+    let mut regions = vec![];
+    if let Some((node, values)) = tree.get_node_by_path(path) {
+        painted_regions(node, values, &mut regions);
+    }
+
+    if regions.is_empty() {
+        return;
+    }
+
+    let mut path = path;
+    while let [parent @ .., _] = path {
+        if let Some(WidgetKind::Element(el)) = tree.get_mut_by_path(parent) {
+            el.container.removed_regions.extend(regions);
+            return;
+        }
+        path = parent;
+    }
+}
+
+// Floating widgets are painted outside of the region of the parent,
+// so the regions of all the descendants are collected
+fn painted_regions(node: &Node, values: &TreeValues<WidgetKind<'_>>, regions: &mut Vec<Region>) {
+    if let Some((_, WidgetKind::Element(el))) = values.get(node.value()) {
+        regions.extend(el.container.painted_region);
+    }
+
+    for child in node.children() {
+        painted_regions(child, values, regions);
+    }
+}
+
 /// Scan the widget tree using the node path.
 /// Build up the scope from the parent nodes.
+///
+/// Errors and panics raised by the update are caught by the closest error boundary
+/// above the node. Errors outside of an error boundary are discarded,
+/// and panics outside of an error boundary resume.
 pub fn update_tree<'bp>(
     globals: &'bp Globals,
     factory: &Factory,
+    arena: &'bp WidgetArena,
     scope: &mut Scope<'bp>,
     states: &mut States,
     component_registry: &mut ComponentRegistry,
@@ -72,11 +135,200 @@ pub fn update_tree<'bp>(
     floating_widgets: &mut FloatingWidgets,
     components: &mut Components,
 ) {
-    let update = UpdateTree {
+    let res = panic::catch_unwind(AssertUnwindSafe(|| {
+        let update = UpdateTree {
+            globals,
+            value_id,
+            change,
+            factory,
+            arena,
+            scope: &mut *scope,
+            states: &mut *states,
+            component_registry: &mut *component_registry,
+            attribute_storage: &mut *attribute_storage,
+            floating_widgets: &mut *floating_widgets,
+            components: &mut *components,
+        };
+        tree.apply_path_finder(path, update)
+    }));
+
+    let (error, payload) = match res {
+        Ok(None | Some(Ok(()))) => return,
+        Ok(Some(Err(err))) => (err.to_string(), None),
+        Err(payload) => (panic_message(&*payload), Some(payload)),
+    };
+
+    scope.clear();
+    let caught = fallback_to_boundary(
         globals,
-        value_id,
-        change,
         factory,
+        arena,
+        scope,
+        states,
+        component_registry,
+        error,
+        path,
+        tree,
+        attribute_storage,
+        floating_widgets,
+        components,
+    );
+
+    if let (false, Some(payload)) = (caught, payload) {
+        panic::resume_unwind(payload);
+    }
+}
+
+/// Generate the iterations that became visible, and remove the ones that are no longer visible,
+/// of the loops that only generate the iterations the widget above them shows
+/// (see [`Widget::visible_iterations`](crate::Widget::visible_iterations)).
+/// Call this after the layout, as the visible iterations usually depend on it.
+///
+/// Returns the loops that changed, as the widgets above them have to be laid out again.
+pub fn update_loop_windows<'bp>(
+    globals: &'bp Globals,
+    factory: &Factory,
+    arena: &'bp WidgetArena,
+    scope: &mut Scope<'bp>,
+    states: &mut States,
+    component_registry: &mut ComponentRegistry,
+    tree: &mut WidgetTree<'bp>,
+    attribute_storage: &mut AttributeStorage<'bp>,
+    floating_widgets: &mut FloatingWidgets,
+    components: &mut Components,
+) -> Vec<WidgetId> {
+    let windows = tree
+        .iter_mut()
+        .filter_map(|(path, widget)| match widget {
+            WidgetKind::For(for_loop) => Some((path.clone(), for_loop.window.clone()?, for_loop.collection.count())),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    let mut changed = vec![];
+    for (path, window, count) in windows {
+        match visible_iterations(&path, count, tree, attribute_storage) {
+            Some(visible) if visible != window => {}
+            _ => continue,
+        }
+
+        let Some(widget_id) = tree.id(&path) else { continue };
+        scope.clear();
+        update_tree(
+            globals,
+            factory,
+            arena,
+            scope,
+            states,
+            component_registry,
+            &Change::Changed,
+            ValueId::from((widget_id, ValueIndex::ZERO)),
+            &path,
+            tree,
+            attribute_storage,
+            floating_widgets,
+            components,
+        );
+        changed.push(widget_id);
+    }
+
+    changed
+}
+
+struct FallbackTree<'a, 'b, 'bp> {
+    globals: &'bp Globals,
+    widget_id: WidgetId,
+    error: String,
+    factory: &'a Factory,
+    arena: &'bp WidgetArena,
+    scope: &'b mut Scope<'bp>,
+    states: &'b mut States,
+    component_registry: &'b mut ComponentRegistry,
+    attribute_storage: &'b mut AttributeStorage<'bp>,
+    floating_widgets: &'b mut FloatingWidgets,
+    components: &'b mut Components,
+}
+
+impl<'a, 'b, 'bp> PathFinder<WidgetKind<'bp>> for FallbackTree<'a, 'b, 'bp> {
+    type Output = Result<()>;
+
+    fn apply(&mut self, node: &mut WidgetKind<'bp>, path: &[u16], tree: &mut WidgetTree<'bp>) -> Self::Output {
+        scope_value(node, self.scope, &[]);
+        let WidgetKind::Component(component) = node else { return Ok(()) };
+
+        damage_removed(path, tree);
+        tree.remove_children(path);
+        repaint_closest_element(path, tree);
+
+        component.fallback_active = true;
+        let error = std::mem::take(&mut self.error);
+        self.components.push_error(self.widget_id, component.state_id(), error);
+
+        let mut ctx = EvalContext::new(
+            self.globals,
+            self.factory,
+            self.arena,
+            self.scope,
+            self.states,
+            self.component_registry,
+            self.attribute_storage,
+            self.floating_widgets,
+            self.components,
+        );
+
+        for bp in component.fallback {
+            eval_blueprint(bp, &mut ctx, path, tree)?;
+        }
+
+        Ok(())
+    }
+
+    fn parent(&mut self, parent: &mut WidgetKind<'bp>, children: &[u16]) {
+        scope_value(parent, self.scope, children);
+    }
+}
+
+// The closest error boundary above the path that is not already showing its fallback.
+fn error_boundary<'a>(mut path: &'a [u16], tree: &WidgetTree<'_>) -> Option<&'a [u16]> {
+    while let [parent @ .., _] = path {
+        if let Some(WidgetKind::Component(component)) = tree.get_ref_by_path(parent) {
+            if component.dyn_component.any_error_boundary() && !component.fallback_active {
+                return Some(parent);
+            }
+        }
+        path = parent;
+    }
+    None
+}
+
+/// Replace the body of the closest error boundary above the node path
+/// with the `$fallback` slot of the boundary, and record the error for [`Components::take_errors`].
+///
+/// This is used for errors raised outside of evaluation, such as during an update or layout.
+/// Returns `false` if there is no error boundary to catch the error.
+pub fn fallback_to_boundary<'bp>(
+    globals: &'bp Globals,
+    factory: &Factory,
+    arena: &'bp WidgetArena,
+    scope: &mut Scope<'bp>,
+    states: &mut States,
+    component_registry: &mut ComponentRegistry,
+    error: String,
+    path: &[u16],
+    tree: &mut WidgetTree<'bp>,
+    attribute_storage: &mut AttributeStorage<'bp>,
+    floating_widgets: &mut FloatingWidgets,
+    components: &mut Components,
+) -> bool {
+    let Some(boundary) = error_boundary(path, tree) else { return false };
+    let Some(widget_id) = tree.id(boundary) else { return false };
+
+    let fallback = FallbackTree {
+        globals,
+        widget_id,
+        error,
+        factory,
+        arena,
         scope,
         states,
         component_registry,
@@ -84,7 +336,10 @@ pub fn update_tree<'bp>(
         floating_widgets,
         components,
     };
-    tree.apply_path_finder(path, update);
+
+    // Errors raised by the fallback itself are discarded, the same way update errors are
+    let _ = tree.apply_path_finder(boundary, fallback);
+    true
 }
 
 fn update_widget<'bp>(
@@ -128,13 +383,12 @@ fn update_widget<'bp>(
 
 pub(super) fn scope_value<'bp>(widget: &WidgetKind<'bp>, scope: &mut Scope<'bp>, children: &[u16]) {
     match widget {
-        WidgetKind::For(for_loop) => match children {
-            [next, ..] => {
-                let index = *next as usize;
+        WidgetKind::For(for_loop) => {
+            if let [next, ..] = children {
+                let index = for_loop.index(*next as usize);
                 for_loop.collection.scope(scope, for_loop.binding, index);
             }
-            [] => {}
-        },
+        }
         WidgetKind::Iteration(iter) => {
             scope.scope_pending(LOOP_INDEX, iter.loop_index.to_pending());
         }
@@ -148,6 +402,7 @@ pub(super) fn scope_value<'bp>(widget: &WidgetKind<'bp>, scope: &mut Scope<'bp>,
             // Insert internal state
             let state_id = component.state_id();
             scope.insert_state(state_id);
+            scope.insert_component(component.component_id, component.body);
         }
         WidgetKind::ControlFlow(_) | WidgetKind::Element(Element { .. }) | WidgetKind::If(_) | WidgetKind::Else(_) => {}
     }
@@ -1,14 +1,15 @@
+use std::cell::RefCell;
 use std::ops::{ControlFlow, Deref};
 
 use anathema_geometry::{LocalPos, Pos, Region, Size};
-use anathema_state::{Color, Hex};
+use anathema_state::{Color, CommonVal, Hex};
 use anathema_store::tree::{Node, TreeFilter, TreeForEach, TreeValues};
 use unicode_width::UnicodeWidthChar;
 
 use crate::layout::Display;
 use crate::nodes::element::Element;
 use crate::widget::WidgetRenderer;
-use crate::{AttributeStorage, WidgetId, WidgetKind};
+use crate::{glyphs, AttributeStorage, WidgetId, WidgetKind};
 
 pub trait CellAttributes {
     fn with_str(&self, key: &str, f: &mut dyn FnMut(&str));
@@ -24,9 +25,37 @@ pub trait CellAttributes {
     fn get_bool(&self, key: &str) -> bool;
 }
 
+/// The shape of the terminal cursor.
+/// The cursor blinks, if the terminal is configured to.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum CursorShape {
+    /// A block covering the cell
+    #[default]
+    Block,
+    /// A line under the cell
+    Underline,
+    /// A vertical line on the left side of the cell
+    Bar,
+}
+
+impl TryFrom<CommonVal<'_>> for CursorShape {
+    type Error = ();
+
+    fn try_from(value: CommonVal<'_>) -> Result<Self, Self::Error> {
+        match value {
+            CommonVal::Str("block") => Ok(Self::Block),
+            CommonVal::Str("underline") => Ok(Self::Underline),
+            CommonVal::Str("bar") => Ok(Self::Bar),
+            _ => Err(()),
+        }
+    }
+}
+
 pub struct PaintFilter<'frame, 'bp> {
     attributes: &'frame AttributeStorage<'bp>,
     ignore_floats: bool,
+    // The regions of widgets that were painted on the last frame but are now hidden
+    hidden: RefCell<Vec<Region>>,
 }
 
 impl<'frame, 'bp> PaintFilter<'frame, 'bp> {
@@ -34,6 +63,7 @@ impl<'frame, 'bp> PaintFilter<'frame, 'bp> {
         Self {
             attributes,
             ignore_floats,
+            hidden: RefCell::new(vec![]),
         }
     }
 }
@@ -58,15 +88,39 @@ impl<'frame, 'bp> TreeFilter for PaintFilter<'frame, 'bp> {
                 .unwrap_or_default()
             {
                 Display::Show => ControlFlow::Continue(Some(el)),
-                Display::Hide | Display::Exclude => ControlFlow::Continue(None),
+                Display::Hide | Display::Exclude => {
+                    if let Some(region) = el.container.painted_region.take() {
+                        self.hidden.borrow_mut().push(region);
+                    }
+                    ControlFlow::Continue(None)
+                }
             },
             WidgetKind::If(widget) if !widget.show => ControlFlow::Break(()),
             WidgetKind::Else(widget) if !widget.show => ControlFlow::Break(()),
             _ => ControlFlow::Continue(None),
         }
     }
+
+    // Siblings are painted in the order of their `z` attribute
+    fn ordered(&self) -> bool {
+        true
+    }
+
+    fn sort_key(&self, widget_id: WidgetId, _: &Self::Output) -> i64 {
+        z_index(self.attributes, widget_id)
+    }
+}
+
+/// The paint order of an element among its siblings (and of a floating widget
+/// among the other floating widgets), from the `z` attribute.
+/// Elements with a higher `z` are painted on top. The default is zero,
+/// and elements with the same `z` are painted in the order of the template.
+pub fn z_index(attribute_storage: &AttributeStorage<'_>, widget_id: WidgetId) -> i64 {
+    attribute_storage.get(widget_id).get_int(Z).unwrap_or(0)
 }
 
+pub const Z: &str = "z";
+
 pub fn paint<'bp>(
     surface: &mut impl WidgetRenderer,
     element: &mut Element<'bp>,
@@ -79,6 +133,10 @@ pub fn paint<'bp>(
     let children = TreeForEach::new(children, values, &filter);
     let ctx = PaintCtx::new(surface, None);
     element.paint(children, ctx, attribute_storage);
+
+    for region in filter.hidden.take() {
+        surface.damage(region);
+    }
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -203,10 +261,14 @@ impl<'screen> PaintCtx<'screen, SizePos> {
         }
     }
 
+    /// Place a string one glyph (grapheme cluster) at a time,
+    /// returning the next position in local space.
     pub fn place_glyphs(&mut self, s: &str, mut pos: LocalPos) -> Option<LocalPos> {
-        for c in s.chars() {
-            let p = self.place_glyph(c, pos)?;
-            pos = p;
+        for glyph in glyphs::glyphs(s) {
+            pos = match glyphs::single_char(glyph) {
+                Some(c) => self.place_glyph(c, pos)?,
+                None => self.place_cluster(glyph, pos)?,
+            };
         }
         Some(pos)
     }
@@ -227,6 +289,30 @@ impl<'screen> PaintCtx<'screen, SizePos> {
         self.surface.set_attributes(attrs, screen_pos);
     }
 
+    // Report a region in global space as changed
+    pub(crate) fn damage(&mut self, region: Region) {
+        self.surface.damage(region);
+    }
+
+    // Report the entire surface as changed
+    pub(crate) fn damage_screen(&mut self) {
+        let region = Region::from((Pos::ZERO, self.surface.size()));
+        self.surface.damage(region);
+    }
+
+    /// Show the terminal cursor at a position in local space.
+    /// The cursor is not shown if the position is clipped or outside of the screen.
+    pub fn set_cursor(&mut self, pos: LocalPos, shape: CursorShape) {
+        if let Some(clip) = self.clip.as_ref() {
+            if !self.clip(pos, clip) {
+                return;
+            }
+        }
+
+        let Some(screen_pos) = self.translate_to_global(pos) else { return };
+        self.surface.set_cursor(screen_pos, shape);
+    }
+
     // Place a char on the screen buffer, return the next cursor position in local space.
     //
     // The `input_pos` is the position, in local space, where the character
@@ -235,6 +321,25 @@ impl<'screen> PaintCtx<'screen, SizePos> {
     // The `output_pos` is the same as the `input_pos` unless clipping has been applied.
     pub fn place_glyph(&mut self, c: char, input_pos: LocalPos) -> Option<LocalPos> {
         let width = c.width().unwrap_or(0);
+        self.place(width, c == '\n', input_pos, |surface, pos| surface.draw_glyph(c, pos))
+    }
+
+    /// Same as [`PaintCtx::place_glyph`] but for a glyph made out of more than one char,
+    /// e.g an emoji joined with zero width joiners, or a letter followed by a combining mark.
+    pub fn place_cluster(&mut self, glyph: &str, input_pos: LocalPos) -> Option<LocalPos> {
+        let width = glyphs::width(glyph);
+        self.place(width, glyph == "\r\n", input_pos, |surface, pos| {
+            surface.draw_cluster(glyph, pos)
+        })
+    }
+
+    fn place(
+        &mut self,
+        width: usize,
+        newline: bool,
+        input_pos: LocalPos,
+        draw: impl FnOnce(&mut dyn WidgetRenderer, Pos),
+    ) -> Option<LocalPos> {
         let next = LocalPos {
             x: input_pos.x + width as u16,
             y: input_pos.y,
@@ -248,7 +353,7 @@ impl<'screen> PaintCtx<'screen, SizePos> {
         }
 
         // 1. Newline (yes / no)
-        if c == '\n' {
+        if newline {
             return self.newline(input_pos);
         }
 
@@ -262,7 +367,20 @@ impl<'screen> PaintCtx<'screen, SizePos> {
             Some(pos) => pos,
             None => return Some(next),
         };
-        self.surface.draw_glyph(c, screen_pos);
+
+        // A wide glyph that straddles the edge of the clipping region
+        // is replaced by a blank, as only half of it would be visible
+        let straddles_clip = width > 1
+            && self.clip.as_ref().is_some_and(|clip| {
+                let last = LocalPos::new(input_pos.x + width as u16 - 1, input_pos.y);
+                !self.clip(last, clip)
+            });
+
+        if straddles_clip {
+            self.surface.draw_glyph(' ', screen_pos);
+        } else {
+            draw(self.surface, screen_pos);
+        }
 
         // 4. Advance the cursor (which might trigger another newline)
         if input_pos.x >= self.local_size.width as u16 {
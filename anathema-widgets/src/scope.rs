@@ -2,6 +2,8 @@ use std::fmt::{self, Debug, Write};
 
 use anathema_debug::DebugWriter;
 use anathema_state::{Path, PendingValue, StateId, States};
+use anathema_templates::blueprints::Blueprint;
+use anathema_templates::WidgetComponentId;
 
 use crate::expressions::{Downgraded, EvalValue};
 use crate::values::ValueId;
@@ -29,6 +31,8 @@ enum Entry<'bp> {
     Downgraded(Path<'bp>, Downgraded<'bp>),
     Pending(Path<'bp>, PendingValue),
     State(StateId),
+    /// The body of a component, used by recursive components
+    Component(WidgetComponentId, &'bp [Blueprint]),
     /// This is marking the entry as free, and another entry can be written here.
     /// This is not indicative of a missing value
     #[default]
@@ -53,6 +57,7 @@ impl Debug for Entry<'_> {
             Entry::Pending(path, pending_value) => f.debug_tuple("Pending").field(path).field(pending_value).finish(),
             Entry::Downgraded(path, value) => f.debug_tuple("Downgraded").field(path).field(value).finish(),
             Entry::State(state) => f.debug_tuple("State").field(&state).finish(),
+            Entry::Component(id, _) => f.debug_tuple("Component").field(id).finish(),
             Entry::Empty => f.debug_tuple("Empty").finish(),
         }
     }
@@ -163,6 +168,26 @@ impl<'bp> Scope<'bp> {
         self.insert_entry(entry);
     }
 
+    pub(crate) fn insert_component(&mut self, component_id: WidgetComponentId, body: &'bp [Blueprint]) {
+        let entry = Entry::Component(component_id, body);
+        self.insert_entry(entry);
+    }
+
+    /// The body of the closest instance of a component,
+    /// and the number of instances the scope is inside of.
+    pub(crate) fn component(&self, component_id: WidgetComponentId) -> Option<(&'bp [Blueprint], usize)> {
+        let mut instances = self.storage[..self.storage_index]
+            .iter()
+            .rev()
+            .filter_map(|entry| match entry {
+                Entry::Component(id, body) if *id == component_id => Some(*body),
+                _ => None,
+            });
+
+        let body = instances.next()?;
+        Some((body, instances.count() + 1))
+    }
+
     pub(crate) fn push(&mut self) {
         self.insert_entry(Entry::Scope(self.current_scope_size));
         self.current_scope_size = 0;
@@ -182,6 +207,17 @@ impl<'bp> Scope<'bp> {
         self.level -= 1;
     }
 
+    pub(crate) fn level(&self) -> usize {
+        self.level
+    }
+
+    /// Pop scopes until the scope is back at the given level
+    pub(crate) fn pop_to(&mut self, level: usize) {
+        while self.level > level {
+            self.pop();
+        }
+    }
+
     pub(crate) fn scope_pending(&mut self, key: &'bp str, iter_value: PendingValue) {
         let entry = Entry::Pending(Path::from(key), iter_value);
         self.insert_entry(entry);
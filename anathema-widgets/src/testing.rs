@@ -1,4 +1,5 @@
 use std::marker::PhantomData;
+use std::ops::Range;
 
 use anathema_geometry::Size;
 use anathema_state::{Map, State, StateId, States};
@@ -9,7 +10,7 @@ use crate::expressions::{eval, EvalValue};
 use crate::layout::{Constraints, LayoutCtx, LayoutFilter, PositionCtx};
 use crate::scope::{Scope, ScopeLookup};
 use crate::values::{ValueId, ValueIndex};
-use crate::{AttributeStorage, Factory, PositionChildren, Value, Widget, WidgetId, WidgetKind};
+use crate::{AttributeStorage, Attributes, Factory, PositionChildren, Value, Widget, WidgetId, WidgetKind};
 
 pub struct NoExpr;
 pub struct WithExpr(Expression);
@@ -109,8 +110,41 @@ impl Widget for TestWidget {
     }
 }
 
+// Only shows the iterations from the `start` attribute to the `end` attribute
+#[derive(Debug, Default)]
+struct WindowWidget;
+
+impl Widget for WindowWidget {
+    fn layout(
+        &mut self,
+        _children: TreeForEach<'_, '_, WidgetKind<'_>, LayoutFilter<'_, '_>>,
+        _: Constraints,
+        _: WidgetId,
+        _: &mut LayoutCtx<'_, '_>,
+    ) -> Size {
+        todo!()
+    }
+
+    fn position<'bp>(
+        &mut self,
+        _children: PositionChildren<'_, '_, 'bp>,
+        _: WidgetId,
+        _: &AttributeStorage<'bp>,
+        _ctx: PositionCtx,
+    ) {
+        todo!()
+    }
+
+    fn visible_iterations(&self, count: usize, attributes: &Attributes<'_>) -> Option<Range<usize>> {
+        let start = attributes.get_usize("start")?.min(count);
+        let end = attributes.get_usize("end")?.min(count);
+        Some(start..end.max(start))
+    }
+}
+
 pub(crate) fn setup_test_factory() -> Factory {
     let mut fac = Factory::new();
     fac.register_default::<TestWidget>("test");
+    fac.register_default::<WindowWidget>("window");
     fac
 }
@@ -0,0 +1,110 @@
+//! The clock used by timers, layout deadlines and the runtime.
+//!
+//! `std::time::Instant::now` panics on `wasm32-unknown-unknown`,
+//! so in the browser the time is read from `Date.now()` instead.
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use std::time::Instant;
+
+#[cfg(target_arch = "wasm32")]
+pub use self::browser::Instant;
+
+// Compiled natively for the tests
+#[cfg(any(target_arch = "wasm32", test))]
+#[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+mod browser {
+    use std::ops::{Add, AddAssign, Sub, SubAssign};
+    use std::time::Duration;
+
+    /// A point in time, as the duration since the Unix epoch.
+    ///
+    /// The browser clock can be adjusted, so unlike `std::time::Instant`
+    /// this is not guaranteed to be monotonic, and durations between
+    /// two instants saturate at zero.
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct Instant(Duration);
+
+    impl Instant {
+        #[cfg(target_arch = "wasm32")]
+        pub fn now() -> Self {
+            Self(Duration::from_secs_f64(js_sys::Date::now().max(0.0) / 1000.0))
+        }
+
+        pub fn duration_since(&self, earlier: Instant) -> Duration {
+            self.saturating_duration_since(earlier)
+        }
+
+        pub fn checked_duration_since(&self, earlier: Instant) -> Option<Duration> {
+            self.0.checked_sub(earlier.0)
+        }
+
+        pub fn saturating_duration_since(&self, earlier: Instant) -> Duration {
+            self.0.saturating_sub(earlier.0)
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        pub fn elapsed(&self) -> Duration {
+            Self::now().saturating_duration_since(*self)
+        }
+
+        pub fn checked_add(&self, duration: Duration) -> Option<Instant> {
+            self.0.checked_add(duration).map(Self)
+        }
+
+        pub fn checked_sub(&self, duration: Duration) -> Option<Instant> {
+            self.0.checked_sub(duration).map(Self)
+        }
+    }
+
+    impl Add<Duration> for Instant {
+        type Output = Instant;
+
+        fn add(self, rhs: Duration) -> Self::Output {
+            Self(self.0 + rhs)
+        }
+    }
+
+    impl AddAssign<Duration> for Instant {
+        fn add_assign(&mut self, rhs: Duration) {
+            self.0 += rhs;
+        }
+    }
+
+    impl Sub<Duration> for Instant {
+        type Output = Instant;
+
+        fn sub(self, rhs: Duration) -> Self::Output {
+            Self(self.0.saturating_sub(rhs))
+        }
+    }
+
+    impl SubAssign<Duration> for Instant {
+        fn sub_assign(&mut self, rhs: Duration) {
+            self.0 = self.0.saturating_sub(rhs);
+        }
+    }
+
+    impl Sub<Instant> for Instant {
+        type Output = Duration;
+
+        fn sub(self, rhs: Instant) -> Self::Output {
+            self.saturating_duration_since(rhs)
+        }
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::*;
+
+        #[test]
+        fn durations_saturate() {
+            let earlier = Instant(Duration::from_secs(10));
+            let later = earlier + Duration::from_millis(500);
+
+            assert_eq!(later - earlier, Duration::from_millis(500));
+            assert_eq!(earlier - later, Duration::ZERO);
+            assert_eq!(earlier.checked_duration_since(later), None);
+            assert!(earlier < later);
+        }
+    }
+}
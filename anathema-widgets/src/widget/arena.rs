@@ -0,0 +1,157 @@
+use std::fmt::{self, Debug};
+use std::ops::{Deref, DerefMut};
+
+use bumpalo::Bump;
+
+use super::{AnyWidget, Widget};
+
+/// Storage for the widgets of a [`WidgetTree`](crate::WidgetTree).
+///
+/// Widgets registered with [`Factory::register_default`](crate::Factory::register_default)
+/// are allocated next to each other in a bump arena, rather than boxed one at a time.
+/// The arena is reset when the tree is rebuilt, keeping the memory for the next tree.
+///
+/// Widgets removed from the tree don't give their memory back until the arena is reset,
+/// so once the widgets use more than the limit, new widgets are boxed instead.
+pub struct WidgetArena {
+    bump: Bump,
+    limit: usize,
+}
+
+impl WidgetArena {
+    /// The default limit of the arena, in bytes
+    pub const DEFAULT_LIMIT: usize = 16 * 1024 * 1024;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an arena with room for `capacity` bytes of widgets
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            bump: Bump::with_capacity(capacity),
+            limit: Self::DEFAULT_LIMIT,
+        }
+    }
+
+    /// Set the number of bytes of widgets allocated in the arena
+    /// before widgets are boxed instead
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+    }
+
+    /// The number of bytes reserved for widgets
+    pub fn capacity(&self) -> usize {
+        self.bump.allocated_bytes()
+    }
+
+    /// The number of bytes used by widgets
+    pub fn allocated(&self) -> usize {
+        self.bump.allocated_bytes() - self.bump.chunk_capacity()
+    }
+
+    /// Free all the widgets.
+    /// The memory is kept for the next tree, so a tree of the same size doesn't allocate.
+    pub fn reset(&mut self) {
+        self.bump.reset();
+    }
+
+    pub(crate) fn alloc<W: 'static + Widget>(&self, widget: W) -> WidgetBox<'_> {
+        if self.allocated() >= self.limit {
+            return WidgetBox::Heap(Box::new(widget));
+        }
+
+        let widget: &mut dyn AnyWidget = self.bump.alloc(widget);
+        // SAFETY: the widget was just allocated, so the box is the only reference to it,
+        // and the box can't outlive the arena as it borrows it
+        WidgetBox::Arena(unsafe { bumpalo::boxed::Box::from_raw(widget) })
+    }
+}
+
+impl Default for WidgetArena {
+    fn default() -> Self {
+        Self {
+            bump: Bump::new(),
+            limit: Self::DEFAULT_LIMIT,
+        }
+    }
+}
+
+impl Debug for WidgetArena {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WidgetArena")
+            .field("allocated", &self.allocated())
+            .field("capacity", &self.capacity())
+            .field("limit", &self.limit)
+            .finish()
+    }
+}
+
+/// A widget allocated in the [`WidgetArena`],
+/// or boxed by a factory registered with [`Factory::register_widget`](crate::Factory::register_widget)
+pub(crate) enum WidgetBox<'bp> {
+    Arena(bumpalo::boxed::Box<'bp, dyn AnyWidget>),
+    Heap(Box<dyn AnyWidget>),
+}
+
+impl Deref for WidgetBox<'_> {
+    type Target = dyn AnyWidget;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Self::Arena(widget) => &**widget,
+            Self::Heap(widget) => &**widget,
+        }
+    }
+}
+
+impl DerefMut for WidgetBox<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match self {
+            Self::Arena(widget) => &mut **widget,
+            Self::Heap(widget) => &mut **widget,
+        }
+    }
+}
+
+impl Debug for WidgetBox<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::setup_test_factory;
+    use crate::{Attributes, WidgetId};
+
+    #[test]
+    fn default_widgets_are_allocated_in_the_arena() {
+        let factory = setup_test_factory();
+        let attribs = Attributes::empty(WidgetId::ZERO);
+        let mut arena = WidgetArena::with_capacity(1024);
+        let capacity = arena.capacity();
+        assert!(capacity >= 1024);
+
+        let widget = factory.make("test", &attribs, &arena).unwrap();
+        assert!(matches!(widget, WidgetBox::Arena(_)));
+        drop(widget);
+
+        // The memory is kept for the next tree
+        arena.reset();
+        assert_eq!(arena.allocated(), 0);
+        assert_eq!(arena.capacity(), capacity);
+    }
+
+    #[test]
+    fn widgets_are_boxed_past_the_limit() {
+        let factory = setup_test_factory();
+        let attribs = Attributes::empty(WidgetId::ZERO);
+        let mut arena = WidgetArena::new();
+        arena.set_limit(0);
+
+        let widget = factory.make("test", &attribs, &arena).unwrap();
+        assert!(matches!(widget, WidgetBox::Heap(_)));
+    }
+}
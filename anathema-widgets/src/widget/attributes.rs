@@ -1,15 +1,20 @@
 use std::ops::Deref;
 
+use anathema_geometry::Edges;
 use anathema_state::{CommonVal, PendingValue};
 use anathema_store::slab::{Gen, SecondaryMap};
 use anathema_store::smallmap::SmallIndex;
 
 use crate::expressions::EvalValue;
+use crate::layout::Length;
 use crate::paint::CellAttributes;
 use crate::values::Values;
 use crate::widget::ValueKey;
 use crate::{Value, WidgetId};
 
+const PORTAL_TARGET: &str = "target";
+const OUTLET: &str = "outlet";
+
 #[derive(Debug)]
 pub struct AttributeStorage<'bp>(SecondaryMap<WidgetId, (Gen, Attributes<'bp>)>);
 
@@ -42,6 +47,39 @@ impl<'bp> AttributeStorage<'bp> {
     pub fn try_remove(&mut self, id: WidgetId) {
         let _ = self.0.remove_if(id, |(current_gen, _)| *current_gen == id.gen());
     }
+
+    /// Find the outlet of a portal.
+    ///
+    /// If the widget has a `target` attribute, return the id of the
+    /// first widget with an `outlet` attribute of the same value.
+    /// ```
+    /// # use anathema_widgets::{AttributeStorage, Attributes, WidgetId};
+    /// # let (portal, outlet) = (WidgetId::ZERO, WidgetId::ONE);
+    /// let mut storage = AttributeStorage::empty();
+    ///
+    /// let mut attributes = Attributes::empty(portal);
+    /// attributes.set("target", "overlay");
+    /// storage.insert(portal, attributes);
+    ///
+    /// let mut attributes = Attributes::empty(outlet);
+    /// attributes.set("outlet", "overlay");
+    /// storage.insert(outlet, attributes);
+    ///
+    /// assert_eq!(storage.portal_outlet(portal), Some(outlet));
+    /// ```
+    pub fn portal_outlet(&self, id: WidgetId) -> Option<WidgetId> {
+        let target = self.0.get(id)?.1.get_ref::<&str>(PORTAL_TARGET)?;
+        self.outlet(target).filter(|outlet| *outlet != id)
+    }
+
+    /// Find the first widget with an `outlet` attribute matching the name
+    pub fn outlet(&self, name: &str) -> Option<WidgetId> {
+        self.0
+            .iter()
+            .map(|(_, attributes)| attributes)
+            .find(|attributes| attributes.get_ref::<&str>(OUTLET) == Some(name))
+            .map(|attributes| attributes.widget_id)
+    }
 }
 
 #[derive(Debug)]
@@ -49,6 +87,8 @@ pub struct Attributes<'bp> {
     pub(crate) values: Values<'bp>,
     pub(crate) value: Option<SmallIndex>,
     widget_id: WidgetId,
+    // Set when an attribute is changed through `set` or `remove`
+    changed: bool,
 }
 
 impl<'bp> Attributes<'bp> {
@@ -58,13 +98,23 @@ impl<'bp> Attributes<'bp> {
             values: Values::empty(),
             value: None,
             widget_id,
+            changed: false,
         }
     }
 
-    /// Set the value
+    /// Set the value.
+    ///
+    /// When this is called on the attributes of an element from a query
+    /// the element is marked as dirty and will be laid out again.
+    /// ```ignore
+    /// elements.by_tag("text").each(|_, attributes| {
+    ///     attributes.set("foreground", Color::Red);
+    /// });
+    /// ```
     pub fn set(&mut self, key: &'bp str, value: impl Into<CommonVal<'bp>>) {
         let value = value.into().into();
         self.values.set(ValueKey::Attribute(key), value);
+        self.changed = true;
     }
 
     /// Resolve the value from a state and track it from the attributes.
@@ -92,9 +142,20 @@ impl<'bp> Attributes<'bp> {
         self.values.insert_with(key, f)
     }
 
+    /// Remove a value.
+    ///
+    /// Same as [`Attributes::set`] this will mark the element as dirty
+    /// when called from a query.
     pub fn remove(&mut self, key: &'bp str) -> Option<Value<'_, EvalValue<'_>>> {
         let key = ValueKey::Attribute(key);
-        self.values.remove(&key)
+        let value = self.values.remove(&key)?;
+        self.changed = true;
+        Some(value)
+    }
+
+    /// Returns true if any attribute was changed since the last call
+    pub(crate) fn take_changed(&mut self) -> bool {
+        std::mem::take(&mut self.changed)
     }
 
     /// Get the `Value` out of attributes.
@@ -159,6 +220,46 @@ impl<'bp> Attributes<'bp> {
             .and_then(|e| e.load_number().map(|n| n.as_uint()))
     }
 
+    /// Get a width or height in cells.
+    /// Percentages (`"50%"`) and fractions (`"1/3"`) are resolved against `max`,
+    /// the max width or height of the parent constraint.
+    /// ```
+    /// # use anathema_widgets::{Attributes, WidgetId};
+    /// let mut attributes = Attributes::empty(WidgetId::ZERO);
+    /// attributes.set("width", "50%");
+    /// assert_eq!(attributes.get_length("width", 10), Some(5));
+    /// ```
+    pub fn get_length(&self, key: &'bp str, max: usize) -> Option<usize> {
+        self.get::<Length>(key)?.resolve(max)
+    }
+
+    /// Get the distance from each edge, e.g for padding or a margin.
+    /// A number applies to every edge, and a string is parsed as the shorthand
+    /// for edges (see [`Edges`]).
+    /// ```
+    /// # use anathema_geometry::Edges;
+    /// # use anathema_widgets::{Attributes, WidgetId};
+    /// let mut attributes = Attributes::empty(WidgetId::ZERO);
+    /// attributes.set("padding", "1 2");
+    /// attributes.set("margin", 3);
+    /// assert_eq!(
+    ///     attributes.get_edges("padding"),
+    ///     Some(Edges::new(1, 2, 1, 2))
+    /// );
+    /// assert_eq!(attributes.get_edges("margin"), Some(Edges::uniform(3)));
+    /// ```
+    pub fn get_edges(&self, key: &'bp str) -> Option<Edges> {
+        let value = self.get_val(key)?.load_common_val()?;
+        if let Some(number) = value.load_number() {
+            return Some(Edges::uniform(number.as_int().max(0) as usize));
+        }
+
+        match value.to_common()? {
+            CommonVal::Str(s) => s.parse().ok(),
+            _ => None,
+        }
+    }
+
     pub(crate) fn get_mut_with_index(&mut self, index: SmallIndex) -> Option<&mut Value<'bp, EvalValue<'bp>>> {
         self.values.get_mut_with_index(index)
     }
@@ -261,4 +362,21 @@ mod test {
         attributes.set("num", 123u32);
         assert!(attributes.contains("num"));
     }
+
+    #[test]
+    fn changed_attributes() {
+        let mut attributes = Attributes::empty(WidgetId::ZERO);
+        assert!(!attributes.take_changed());
+
+        attributes.set("num", 123u32);
+        assert!(attributes.take_changed());
+        assert!(!attributes.take_changed());
+
+        attributes.remove("num");
+        assert!(attributes.take_changed());
+
+        // Removing a missing attribute is not a change
+        attributes.remove("num");
+        assert!(!attributes.take_changed());
+    }
 }
@@ -1,26 +1,52 @@
 use std::collections::HashMap;
 
+use super::arena::{WidgetArena, WidgetBox};
 use super::{AnyWidget, Widget};
 use crate::error::{Error, Result};
 use crate::Attributes;
 
-pub struct Factory(HashMap<Box<str>, Box<dyn Fn(&Attributes<'_>) -> Box<dyn AnyWidget>>>);
+enum Make {
+    // Allocated in the arena
+    Default(for<'bp> fn(&'bp WidgetArena) -> WidgetBox<'bp>),
+    Boxed(Box<dyn Fn(&Attributes<'_>) -> Box<dyn AnyWidget>>),
+}
+
+pub struct Factory(HashMap<Box<str>, Make>);
 
 impl Factory {
     pub fn new() -> Self {
         Self(HashMap::new())
     }
 
-    pub(crate) fn make(&self, ident: &str, attribs: &Attributes<'_>) -> Result<Box<dyn AnyWidget>> {
-        let f = self.0.get(ident).ok_or(Error::InvalidElement(ident.to_string()))?;
-        Ok((f)(attribs))
+    pub(crate) fn make<'bp>(
+        &self,
+        ident: &str,
+        attribs: &Attributes<'_>,
+        arena: &'bp WidgetArena,
+    ) -> Result<WidgetBox<'bp>> {
+        let make = self.0.get(ident).ok_or(Error::InvalidElement(ident.to_string()))?;
+        let widget = match make {
+            Make::Default(f) => f(arena),
+            Make::Boxed(f) => WidgetBox::Heap(f(attribs)),
+        };
+        Ok(widget)
     }
 
+    /// Register a widget made from its attributes.
+    /// The widget is boxed, unlike widgets registered with [`Factory::register_default`]
     pub fn register_widget(&mut self, ident: &str, factory: impl Fn(&Attributes<'_>) -> Box<dyn AnyWidget> + 'static) {
-        self.0.insert(ident.into(), Box::new(factory));
+        self.0.insert(ident.into(), Make::Boxed(Box::new(factory)));
     }
 
+    /// Register a widget made with `Default`.
+    /// The widget is allocated in the [`WidgetArena`] of the tree
     pub fn register_default<W: 'static + Widget + Default>(&mut self, ident: &str) {
-        self.0.insert(ident.into(), Box::new(|_| Box::<W>::default()));
+        self.0
+            .insert(ident.into(), Make::Default(|arena| arena.alloc(W::default())));
+    }
+
+    /// The idents of all the registered widgets, in no particular order
+    pub fn idents(&self) -> impl Iterator<Item = &str> + '_ {
+        self.0.keys().map(|ident| &**ident)
     }
 }
@@ -0,0 +1,138 @@
+use std::ops::ControlFlow;
+
+use anathema_geometry::Pos;
+use anathema_state::StateId;
+use anathema_store::tree::visitor::NodeVisitor;
+
+use super::{AttributeStorage, Components, DirtyWidgets, WidgetId, WidgetTree};
+use crate::WidgetKind;
+
+/// The components the cursor entered or left,
+/// as widget and state ids.
+#[derive(Debug, Default, PartialEq)]
+pub struct HoverChanges {
+    pub entered: Vec<(WidgetId, StateId)>,
+    pub left: Vec<(WidgetId, StateId)>,
+}
+
+/// Tracks the elements and components under the mouse cursor.
+///
+/// Elements are hovered if their region contains the cursor.
+/// Components are hovered if any of their elements are hovered.
+#[derive(Debug, Default)]
+pub struct Hover {
+    elements: Vec<WidgetId>,
+    components: Vec<(WidgetId, StateId)>,
+}
+
+impl Hover {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns true if the element or component is under the cursor
+    pub fn is_hovered(&self, id: WidgetId) -> bool {
+        self.elements.contains(&id) || self.components.iter().any(|(widget_id, _)| *widget_id == id)
+    }
+
+    /// The innermost element under the cursor
+    pub fn element(&self) -> Option<WidgetId> {
+        self.elements.last().copied()
+    }
+
+    /// Widget and state id of the innermost component under the cursor
+    pub fn component(&self) -> Option<(WidgetId, StateId)> {
+        self.components.last().copied()
+    }
+
+    /// Scroll the innermost element under the cursor that can scroll,
+    /// as decided by [`Widget::on_scroll`](crate::Widget::on_scroll).
+    /// Returns false if no element scrolled.
+    pub fn scroll(&self, delta: Pos, tree: &mut WidgetTree<'_>, dirty_widgets: &mut DirtyWidgets) -> bool {
+        for &widget_id in self.elements.iter().rev() {
+            let Some(WidgetKind::Element(el)) = tree.get_mut_by_id(widget_id) else { continue };
+            if el.container.inner.any_on_scroll(delta) {
+                dirty_widgets.push(widget_id);
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Update the hover state with the position of the cursor.
+    ///
+    /// Elements the cursor entered or left are notified through [`Widget::on_hover`](crate::Widget::on_hover).
+    /// The components the cursor entered or left are returned,
+    /// as it's up to the runtime to call the components.
+    pub fn update<'bp>(
+        &mut self,
+        pos: Pos,
+        tree: &mut WidgetTree<'bp>,
+        components: &Components,
+        attribute_storage: &mut AttributeStorage<'bp>,
+        dirty_widgets: &mut DirtyWidgets,
+    ) -> HoverChanges {
+        let mut visitor = HoverVisitor {
+            pos,
+            prev: &self.elements,
+            hovered: vec![],
+            attribute_storage,
+            dirty_widgets,
+        };
+        tree.apply_visitor(&mut visitor);
+
+        let hovered = visitor.hovered;
+        self.elements = hovered.iter().map(|(id, _)| *id).collect();
+
+        let mut changes = HoverChanges::default();
+        let mut current = vec![];
+        for entry in components.inner.iter() {
+            let is_hovered = hovered.iter().any(|(_, path)| path.starts_with(&entry.path));
+            let was_hovered = self.components.iter().any(|(id, _)| *id == entry.widget_id);
+
+            match (was_hovered, is_hovered) {
+                (false, true) => changes.entered.push((entry.widget_id, entry.state_id)),
+                (true, false) => changes.left.push((entry.widget_id, entry.state_id)),
+                _ => (),
+            }
+
+            if is_hovered {
+                current.push((entry.widget_id, entry.state_id));
+            }
+        }
+        self.components = current;
+
+        changes
+    }
+}
+
+struct HoverVisitor<'a, 'bp> {
+    pos: Pos,
+    prev: &'a [WidgetId],
+    hovered: Vec<(WidgetId, Box<[u16]>)>,
+    attribute_storage: &'a mut AttributeStorage<'bp>,
+    dirty_widgets: &'a mut DirtyWidgets,
+}
+
+impl<'bp> NodeVisitor<WidgetKind<'bp>> for HoverVisitor<'_, 'bp> {
+    fn visit(&mut self, value: &mut WidgetKind<'bp>, path: &[u16], widget_id: WidgetId) -> ControlFlow<bool> {
+        let WidgetKind::Element(el) = value else { return ControlFlow::Continue(()) };
+
+        let is_hovered = el.region().contains(self.pos);
+        if is_hovered {
+            self.hovered.push((widget_id, path.into()));
+        }
+
+        if is_hovered != self.prev.contains(&widget_id) {
+            el.container.hovered = is_hovered;
+            let attributes = self.attribute_storage.get_mut(el.id());
+            el.container.inner.any_on_hover(is_hovered, attributes);
+
+            if attributes.take_changed() || el.container.inner.any_needs_reflow() {
+                self.dirty_widgets.push(widget_id);
+            }
+        }
+
+        ControlFlow::Continue(())
+    }
+}
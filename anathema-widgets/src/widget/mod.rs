@@ -1,11 +1,11 @@
 use std::any::Any;
 use std::cmp::Ordering;
 use std::fmt::{self, Debug};
-use std::ops::ControlFlow;
+use std::ops::{ControlFlow, Range};
 
 pub type WidgetId = anathema_store::slab::Key;
 
-use anathema_geometry::{Pos, Rect, Size};
+use anathema_geometry::{Pos, Rect, Region, Size};
 use anathema_state::StateId;
 use anathema_store::slab::SecondaryMap;
 use anathema_store::smallmap::SmallMap;
@@ -13,16 +13,25 @@ use anathema_store::sorted::SortedList;
 use anathema_store::tree::{NodeWalker, Tree, TreeForEach};
 use anathema_templates::WidgetComponentId;
 
+pub use self::arena::WidgetArena;
+pub(crate) use self::arena::WidgetBox;
 pub use self::attributes::{AttributeStorage, Attributes};
 pub use self::factory::Factory;
+pub use self::hover::{Hover, HoverChanges};
 pub use self::query::Elements;
-use crate::layout::{Constraints, LayoutCtx, LayoutFilter, PositionCtx};
-use crate::paint::{CellAttributes, PaintCtx, PaintFilter, SizePos};
+pub use self::selection::Selection;
+pub use self::selector::{Ancestor, Selector};
+use crate::layout::{Constraints, ContentWidth, LayoutCtx, LayoutFilter, PositionCtx};
+use crate::paint::{CellAttributes, CursorShape, PaintCtx, PaintFilter, SizePos};
 use crate::WidgetKind;
 
+mod arena;
 mod attributes;
 mod factory;
+mod hover;
 mod query;
+mod selection;
+mod selector;
 
 #[derive(Debug)]
 pub struct CompEntry {
@@ -34,7 +43,7 @@ pub struct CompEntry {
 
 impl PartialOrd for CompEntry {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.path.cmp(&other.path))
+        Some(self.cmp(other))
     }
 }
 
@@ -56,6 +65,7 @@ pub struct Components {
     pub tab_index: usize,
     inner: SortedList<CompEntry>,
     comp_ids: SmallMap<WidgetComponentId, usize>,
+    errors: Vec<(WidgetId, StateId, String)>,
 }
 
 impl Components {
@@ -64,6 +74,7 @@ impl Components {
             tab_index: 0,
             inner: SortedList::empty(),
             comp_ids: SmallMap::empty(),
+            errors: vec![],
         }
     }
 
@@ -98,6 +109,34 @@ impl Components {
         self.inner.get(*index)
     }
 
+    /// Widget and state ids of every instance of a component
+    pub fn instances(&self, id: WidgetComponentId) -> impl Iterator<Item = (WidgetId, StateId)> + '_ {
+        self.inner
+            .iter()
+            .filter(move |entry| entry.component_id == id)
+            .map(|entry| (entry.widget_id, entry.state_id))
+    }
+
+    /// Widget and state ids of the components above the component, starting from the root
+    pub fn ancestors(&self, id: WidgetComponentId) -> Vec<(WidgetId, StateId)> {
+        let Some(entry) = self.inner.iter().find(|e| e.component_id == id) else { return vec![] };
+        self.inner
+            .iter()
+            .filter(|e| e.path.len() < entry.path.len() && entry.path.starts_with(&e.path))
+            .map(|e| (e.widget_id, e.state_id))
+            .collect()
+    }
+
+    /// Returns true if the component at the given index is either the scope component
+    /// or a descendant of the scope component.
+    ///
+    /// If the scope component no longer exists every component is in scope.
+    pub fn in_focus_scope(&self, index: usize, scope: WidgetComponentId) -> bool {
+        let Some(scope) = self.inner.iter().find(|e| e.component_id == scope) else { return true };
+        let Some(entry) = self.inner.iter().nth(index) else { return false };
+        entry.path.starts_with(&scope.path)
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = &CompEntry> {
         self.inner.iter()
     }
@@ -106,10 +145,21 @@ impl Components {
         self.inner.len()
     }
 
-    pub fn dodgy_remove(&mut self, widget_id: WidgetId) {
-        let Some(index) = self.inner.iter().position(|entry| entry.widget_id == widget_id) else { return };
+    pub(crate) fn push_error(&mut self, widget_id: WidgetId, state_id: StateId, error: String) {
+        self.errors.push((widget_id, state_id, error));
+    }
+
+    /// Take all the errors caught by error boundaries since the last call.
+    /// The widget id and state id belong to the error boundary.
+    pub fn take_errors(&mut self) -> Vec<(WidgetId, StateId, String)> {
+        std::mem::take(&mut self.errors)
+    }
+
+    pub fn dodgy_remove(&mut self, widget_id: WidgetId) -> Option<WidgetComponentId> {
+        let index = self.inner.iter().position(|entry| entry.widget_id == widget_id)?;
         let entry = self.inner.remove(index);
         self.comp_ids.remove(&entry.component_id);
+        Some(entry.component_id)
     }
 }
 
@@ -154,9 +204,12 @@ impl DirtyWidgets {
         self.inner.clear();
     }
 
+    /// Mark the widgets, and the widgets above them, as needing layout.
+    /// Widgets that were removed since they were pushed are skipped,
+    /// e.g an outlet inside an error boundary that caught an error.
     pub fn apply(&self, tree: &mut Tree<WidgetKind<'_>>) {
         for id in &self.inner {
-            let path = tree.path(*id);
+            let Some(path) = tree.try_path(*id) else { continue };
             tree.apply_node_walker(&path, WidgetNeedsLayout);
         }
     }
@@ -205,6 +258,8 @@ impl ComponentParents {
     }
 }
 
+/// The tree of widgets.
+/// Use [`Tree::pre_order`], [`Tree::post_order`] or [`Tree::apply_visitor`] to traverse it.
 pub type WidgetTree<'a> = Tree<WidgetKind<'a>>;
 pub type LayoutChildren<'a, 'frame, 'bp> = TreeForEach<'a, 'frame, WidgetKind<'bp>, LayoutFilter<'frame, 'bp>>;
 pub type PositionChildren<'a, 'frame, 'bp> = TreeForEach<'a, 'frame, WidgetKind<'bp>, LayoutFilter<'frame, 'bp>>;
@@ -240,6 +295,13 @@ pub trait AnyWidget {
         ctx: &mut LayoutCtx<'_, 'bp>,
     ) -> Size;
 
+    fn any_measure<'bp>(
+        &mut self,
+        children: LayoutChildren<'_, '_, 'bp>,
+        id: WidgetId,
+        ctx: &mut LayoutCtx<'_, 'bp>,
+    ) -> ContentWidth;
+
     fn any_position<'bp>(
         &mut self,
         children: PositionChildren<'_, '_, 'bp>,
@@ -258,9 +320,21 @@ pub trait AnyWidget {
 
     fn any_floats(&self) -> bool;
 
+    fn any_baseline(&self) -> Option<usize>;
+
     fn any_inner_bounds(&self, pos: Pos, size: Size) -> Rect;
 
     fn any_needs_reflow(&self) -> bool;
+
+    fn any_on_hover(&mut self, hovered: bool, attributes: &mut Attributes<'_>);
+
+    fn any_on_scroll(&mut self, delta: Pos) -> bool;
+
+    fn any_visible_iterations(&self, count: usize, attributes: &Attributes<'_>) -> Option<Range<usize>>;
+
+    fn any_generated_iterations(&mut self, generated: Range<usize>, count: usize);
+
+    fn any_text(&self, size: Size, attributes: &Attributes<'_>) -> Vec<(u16, String)>;
 }
 
 impl<T: 'static + Widget> AnyWidget for T {
@@ -282,6 +356,15 @@ impl<T: 'static + Widget> AnyWidget for T {
         self.layout(children, constraints, id, ctx)
     }
 
+    fn any_measure<'bp>(
+        &mut self,
+        children: LayoutChildren<'_, '_, 'bp>,
+        id: WidgetId,
+        ctx: &mut LayoutCtx<'_, 'bp>,
+    ) -> ContentWidth {
+        self.measure(children, id, ctx)
+    }
+
     fn any_position<'bp>(
         &mut self,
         children: PositionChildren<'_, '_, 'bp>,
@@ -310,9 +393,33 @@ impl<T: 'static + Widget> AnyWidget for T {
         self.floats()
     }
 
+    fn any_baseline(&self) -> Option<usize> {
+        self.baseline()
+    }
+
     fn any_needs_reflow(&self) -> bool {
         self.needs_reflow()
     }
+
+    fn any_on_hover(&mut self, hovered: bool, attributes: &mut Attributes<'_>) {
+        self.on_hover(hovered, attributes)
+    }
+
+    fn any_on_scroll(&mut self, delta: Pos) -> bool {
+        self.on_scroll(delta)
+    }
+
+    fn any_visible_iterations(&self, count: usize, attributes: &Attributes<'_>) -> Option<Range<usize>> {
+        self.visible_iterations(count, attributes)
+    }
+
+    fn any_generated_iterations(&mut self, generated: Range<usize>, count: usize) {
+        self.generated_iterations(generated, count)
+    }
+
+    fn any_text(&self, size: Size, attributes: &Attributes<'_>) -> Vec<(u16, String)> {
+        self.text(size, attributes)
+    }
 }
 
 impl Debug for dyn AnyWidget {
@@ -330,6 +437,23 @@ pub trait Widget {
         ctx: &mut LayoutCtx<'_, 'bp>,
     ) -> Size;
 
+    /// Measure the min-content and max-content width of the widget before it's laid out,
+    /// so a parent can size it to its content.
+    /// The default is the widest of the children.
+    fn measure<'bp>(
+        &mut self,
+        mut children: LayoutChildren<'_, '_, 'bp>,
+        _id: WidgetId,
+        ctx: &mut LayoutCtx<'_, 'bp>,
+    ) -> ContentWidth {
+        let mut width = ContentWidth::ZERO;
+        children.for_each(|child, children| {
+            width = width.widest(child.measure(children, ctx));
+            ControlFlow::Continue(())
+        });
+        width
+    }
+
     fn paint<'bp>(
         &mut self,
         mut children: PaintChildren<'_, '_, 'bp>,
@@ -356,6 +480,13 @@ pub trait Widget {
         false
     }
 
+    /// The row of the text baseline, counted from the top of the widget, after it's laid out.
+    /// This is used to line up text of different heights,
+    /// e.g. `hstack [align: "baseline"]`.
+    fn baseline(&self) -> Option<usize> {
+        None
+    }
+
     fn inner_bounds(&self, pos: Pos, size: Size) -> Rect {
         Rect::from((pos, size))
     }
@@ -363,6 +494,45 @@ pub trait Widget {
     fn needs_reflow(&self) -> bool {
         false
     }
+
+    /// Called when the mouse cursor enters (`hovered` is true) or leaves the widget.
+    /// Changing the attributes will cause the widget to be laid out and painted again.
+    #[allow(unused_variables)]
+    fn on_hover(&mut self, hovered: bool, attributes: &mut Attributes<'_>) {}
+
+    /// Called when the mouse wheel is scrolled over the widget.
+    /// Return `true` if the widget scrolled,
+    /// otherwise the scroll is passed on to the widget's ancestors.
+    #[allow(unused_variables)]
+    fn on_scroll(&mut self, delta: Pos) -> bool {
+        false
+    }
+
+    /// Only generate the iterations of a loop, directly inside the widget, that the widget shows,
+    /// e.g `overflow [virtual: true]` only generates the iterations that are scrolled into view.
+    /// Return the range of the `count` iterations to generate, or `None` to generate all of them.
+    ///
+    /// This is called when the loop is evaluated, and again after every layout.
+    /// If the range changed, the iterations that are still visible are kept,
+    /// the rest are removed and the missing ones are generated.
+    #[allow(unused_variables)]
+    fn visible_iterations(&self, count: usize, attributes: &Attributes<'_>) -> Option<Range<usize>> {
+        None
+    }
+
+    /// The range of the `count` iterations the loop inside the widget generated,
+    /// after [`Widget::visible_iterations`] returned a range.
+    /// The range moves as iterations are inserted and removed in front of it.
+    #[allow(unused_variables)]
+    fn generated_iterations(&mut self, generated: Range<usize>, count: usize) {}
+
+    /// The text painted by the widget, as one line per row from the top of the widget,
+    /// and the column each line starts at.
+    /// This is the text copied from a mouse selection, see [`Selection`].
+    #[allow(unused_variables)]
+    fn text(&self, size: Size, attributes: &Attributes<'_>) -> Vec<(u16, String)> {
+        vec![]
+    }
 }
 
 impl Debug for dyn Widget {
@@ -374,7 +544,77 @@ impl Debug for dyn Widget {
 pub trait WidgetRenderer {
     fn draw_glyph(&mut self, c: char, local_pos: Pos);
 
+    /// Draw a glyph made out of more than one char (a grapheme cluster).
+    /// Renderers that can't draw clusters draw the first char instead.
+    fn draw_cluster(&mut self, glyph: &str, local_pos: Pos) {
+        if let Some(c) = glyph.chars().next() {
+            self.draw_glyph(c, local_pos);
+        }
+    }
+
     fn set_attributes(&mut self, attribs: &dyn CellAttributes, local_pos: Pos);
 
     fn size(&self) -> Size;
+
+    /// Show the terminal cursor at the given position, with the given shape, once the frame is rendered.
+    /// The cursor is hidden again unless it's set on the next frame as well.
+    ///
+    /// Renderers without a cursor ignore this.
+    #[allow(unused_variables)]
+    fn set_cursor(&mut self, pos: Pos, shape: CursorShape) {}
+
+    /// A region of the screen that changed since the last frame.
+    /// Renderers can use this to only compare the damaged regions with the previous frame.
+    ///
+    /// Only the first paint of a frame reports the damage.
+    #[allow(unused_variables)]
+    fn damage(&mut self, region: Region) {}
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn focus_scope() {
+        let mut components = Components::new();
+        components.push(Box::new([0]), (0, 0).into(), 0.into(), 0usize.into());
+        components.push(Box::new([0, 1]), (1, 0).into(), 1.into(), 1usize.into());
+        components.push(Box::new([1]), (2, 0).into(), 2.into(), 2usize.into());
+        components.push(Box::new([1, 0]), (3, 0).into(), 3.into(), 3usize.into());
+
+        let scope = 2usize.into();
+        assert!(!components.in_focus_scope(0, scope));
+        assert!(!components.in_focus_scope(1, scope));
+        assert!(components.in_focus_scope(2, scope));
+        assert!(components.in_focus_scope(3, scope));
+
+        // The scope component doesn't exist
+        assert!(components.in_focus_scope(0, 100usize.into()));
+    }
+
+    #[test]
+    fn component_ancestors() {
+        let mut components = Components::new();
+        components.push(Box::new([0]), (0, 0).into(), 0.into(), 0usize.into());
+        components.push(Box::new([0, 0]), (1, 0).into(), 1.into(), 1usize.into());
+        components.push(Box::new([0, 0, 1]), (2, 0).into(), 2.into(), 2usize.into());
+        components.push(Box::new([1]), (3, 0).into(), 3.into(), 3usize.into());
+
+        let ancestors = components.ancestors(2usize.into());
+        assert_eq!(ancestors, vec![((0, 0).into(), 0.into()), ((1, 0).into(), 1.into())]);
+        assert!(components.ancestors(3usize.into()).is_empty());
+    }
+
+    #[test]
+    fn component_instances() {
+        let mut components = Components::new();
+        components.push(Box::new([0]), (0, 0).into(), 0.into(), 0usize.into());
+        components.push(Box::new([1]), (1, 0).into(), 1.into(), 1usize.into());
+        components.push(Box::new([2]), (2, 0).into(), 2.into(), 0usize.into());
+
+        let instances = components.instances(0usize.into()).collect::<Vec<_>>();
+        assert_eq!(instances, vec![((0, 0).into(), 0.into()), ((2, 0).into(), 2.into())]);
+        assert_eq!(components.instances(3usize.into()).count(), 0);
+    }
 }
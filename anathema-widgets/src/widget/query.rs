@@ -5,6 +5,8 @@ use anathema_state::CommonVal;
 use anathema_store::tree::visitor::NodeVisitor;
 use anathema_store::tree::{apply_visitor, Node, TreeValues};
 
+use super::selector::{Ancestor, Selector};
+use crate::error::Result;
 use crate::nodes::element::Element;
 use crate::{AttributeStorage, Attributes, DirtyWidgets, WidgetId, WidgetKind};
 
@@ -40,6 +42,14 @@ impl<'tree, 'bp> Elements<'tree, 'bp> {
         }
     }
 
+    /// Find the element with the given widget id
+    pub fn by_id(&mut self, id: WidgetId) -> Query<'_, 'tree, 'bp, Kind<'_>> {
+        Query {
+            filter: Kind::ById(id),
+            elements: self,
+        }
+    }
+
     pub fn by_tag<'tag>(&mut self, tag: &'tag str) -> Query<'_, 'tree, 'bp, Kind<'tag>> {
         Query {
             filter: Kind::ByTag(tag),
@@ -57,6 +67,33 @@ impl<'tree, 'bp> Elements<'tree, 'bp> {
             elements: self,
         }
     }
+
+    /// Query elements using a [`Selector`].
+    ///
+    /// ```ignore
+    /// elements.select("overflow > text[highlight]").each(|el, attributes| {
+    ///     attributes.set("foreground", "red");
+    /// });
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the selector is invalid, see [`Elements::try_select`]
+    pub fn select<'a>(&mut self, selector: &'a str) -> Query<'_, 'tree, 'bp, Selector<'a>> {
+        match self.try_select(selector) {
+            Ok(query) => query,
+            Err(err) => panic!("{err}"),
+        }
+    }
+
+    /// Query elements using a [`Selector`].
+    /// Returns an error if the selector is invalid.
+    pub fn try_select<'a>(&mut self, selector: &'a str) -> Result<Query<'_, 'tree, 'bp, Selector<'a>>> {
+        Ok(Query {
+            filter: Selector::parse(selector)?,
+            elements: self,
+        })
+    }
 }
 
 // -----------------------------------------------------------------------------
@@ -97,6 +134,26 @@ where
         self.by_filter(Kind::ByAttribute(key, value.into()))
     }
 
+    /// Filter the elements further using a [`Selector`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the selector is invalid
+    pub fn select<'a>(self, selector: &'a str) -> Query<'el, 'tree, 'bp, FilterChain<F, Selector<'a>>> {
+        let selector = match Selector::parse(selector) {
+            Ok(selector) => selector,
+            Err(err) => panic!("{err}"),
+        };
+
+        Query {
+            filter: FilterChain {
+                a: self.filter,
+                b: selector,
+            },
+            elements: self.elements,
+        }
+    }
+
     fn query(self, f: impl FnMut(&mut Element<'_>, &mut Attributes<'_>), continuous: bool) {
         let mut run = QueryRun {
             filter: self.filter,
@@ -104,9 +161,12 @@ where
             continuous,
             attributes: self.elements.attributes,
             dirty_widgets: self.elements.dirty_widgets,
+            ancestors: vec![],
+            depth: vec![],
+            current: None,
         };
 
-        apply_visitor(self.elements.nodes, self.elements.widgets, &mut run);
+        let _ = apply_visitor(self.elements.nodes, self.elements.widgets, &mut run);
     }
 
     pub fn each<T>(self, f: T)
@@ -116,8 +176,16 @@ where
         self.query(f, true);
     }
 
-    pub fn first(self, f: impl FnMut(&mut Element<'_>, &mut Attributes<'_>)) {
-        self.query(f, false);
+    /// Call the closure with the first matching element
+    /// and return the value from the closure.
+    ///
+    /// ```ignore
+    /// let region = elements.by_tag("border").first(|el, _| el.region());
+    /// ```
+    pub fn first<R>(self, mut f: impl FnMut(&mut Element<'_>, &mut Attributes<'_>) -> R) -> Option<R> {
+        let mut value = None;
+        self.query(|el, attributes| value = Some(f(el, attributes)), false);
+        value
     }
 }
 
@@ -128,6 +196,7 @@ pub enum Kind<'a> {
     ByTag(&'a str),
     ByAttribute(&'a str, CommonVal<'a>),
     AtPosition(Pos),
+    ById(WidgetId),
 }
 
 impl<'bp, 'a> Filter<'bp> for Kind<'a> {
@@ -149,6 +218,7 @@ impl<'bp, 'a> Filter<'bp> for Kind<'a> {
                 let region = Region::from((el.container.pos, el.container.size));
                 region.contains(*pos)
             }
+            Kind::ById(id) => el.container.id == *id,
         }
     }
 }
@@ -159,6 +229,17 @@ impl<'bp, 'a> Filter<'bp> for Kind<'a> {
 pub trait Filter<'bp> {
     fn filter(&self, el: &Element<'bp>, attributes: &mut AttributeStorage<'_>) -> bool;
 
+    /// Same as [`Filter::filter`] but with access to the ancestors of the element,
+    /// closest ancestor last.
+    fn filter_nested(
+        &self,
+        el: &Element<'bp>,
+        _ancestors: &[Ancestor<'bp>],
+        attributes: &mut AttributeStorage<'_>,
+    ) -> bool {
+        self.filter(el, attributes)
+    }
+
     fn chain(self, other: impl Filter<'bp>) -> impl Filter<'bp>
     where
         Self: Sized,
@@ -208,6 +289,36 @@ impl<'bp, A: Filter<'bp>, B: Filter<'bp>> Filter<'bp> for FilterChain<A, B> {
             true => self.b.filter(el, attributes),
         }
     }
+
+    fn filter_nested(
+        &self,
+        el: &Element<'bp>,
+        ancestors: &[Ancestor<'bp>],
+        attributes: &mut AttributeStorage<'_>,
+    ) -> bool {
+        match self.a.filter_nested(el, ancestors, attributes) {
+            false => false,
+            true => self.b.filter_nested(el, ancestors, attributes),
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+//   - Selector -
+// -----------------------------------------------------------------------------
+impl<'bp> Filter<'bp> for Selector<'_> {
+    fn filter(&self, el: &Element<'bp>, attributes: &mut AttributeStorage<'_>) -> bool {
+        self.matches(el, &[], attributes)
+    }
+
+    fn filter_nested(
+        &self,
+        el: &Element<'bp>,
+        ancestors: &[Ancestor<'bp>],
+        attributes: &mut AttributeStorage<'_>,
+    ) -> bool {
+        self.matches(el, ancestors, attributes)
+    }
 }
 
 // -----------------------------------------------------------------------------
@@ -219,6 +330,11 @@ pub struct QueryRun<'bp, 'tag, T: Filter<'bp>, F> {
     continuous: bool,
     attributes: &'tag mut AttributeStorage<'bp>,
     dirty_widgets: &'tag mut DirtyWidgets,
+    ancestors: Vec<Ancestor<'bp>>,
+    // Whether or not a node pushed an ancestor,
+    // as only elements are ancestors
+    depth: Vec<bool>,
+    current: Option<Ancestor<'bp>>,
 }
 
 impl<'bp, 'tag, T: Filter<'bp>, F> NodeVisitor<WidgetKind<'bp>> for QueryRun<'bp, 'tag, T, F>
@@ -226,21 +342,44 @@ where
     F: FnMut(&mut Element<'bp>, &mut Attributes<'_>),
 {
     fn visit(&mut self, value: &mut WidgetKind<'bp>, _path: &[u16], widget_id: WidgetId) -> ControlFlow<bool> {
+        self.current = None;
+
         if let WidgetKind::Element(el) = value {
-            if self.filter.filter(el, self.attributes) {
+            self.current = Some(Ancestor {
+                ident: el.ident,
+                widget_id,
+            });
+
+            if self.filter.filter_nested(el, &self.ancestors, self.attributes) {
                 let attributes = self.attributes.get_mut(el.id());
                 (self.f)(el, attributes);
 
-                if el.container.inner.any_needs_reflow() {
+                if attributes.take_changed() || el.container.inner.any_needs_reflow() {
                     self.dirty_widgets.push(widget_id);
                 }
 
                 if !self.continuous {
-                    return ControlFlow::Break(false);
+                    return ControlFlow::Break(true);
                 }
             }
         }
 
         ControlFlow::Continue(())
     }
+
+    fn push(&mut self) {
+        match self.current.take() {
+            Some(ancestor) => {
+                self.ancestors.push(ancestor);
+                self.depth.push(true);
+            }
+            None => self.depth.push(false),
+        }
+    }
+
+    fn pop(&mut self) {
+        if let Some(true) = self.depth.pop() {
+            self.ancestors.pop();
+        }
+    }
 }
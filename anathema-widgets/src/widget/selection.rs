@@ -0,0 +1,216 @@
+use std::collections::BTreeMap;
+
+use anathema_geometry::Pos;
+use anathema_state::{Color, Hex};
+use anathema_store::tree::{Node, TreeValues};
+
+use super::{AttributeStorage, WidgetRenderer, WidgetTree};
+use crate::components::events::{MouseButton, MouseEvent, MouseState};
+use crate::debug::Overlay;
+use crate::layout::Display;
+use crate::paint::CellAttributes;
+use crate::{glyphs, WidgetKind};
+
+/// Text selected by dragging the mouse with the left button held down.
+///
+/// The selection covers every cell from where the button was pressed to the cursor,
+/// in reading order like the selection of a terminal, across any number of widgets.
+/// Pressing the left button again clears the selection.
+///
+/// The selected text is the [`Widget::text`](crate::Widget::text) of the widgets in the selection,
+/// with a newline between rows.
+#[derive(Debug, Default)]
+pub struct Selection {
+    // Where the left button was pressed, while it's held down
+    anchor: Option<Pos>,
+    // The first and the last selected cell
+    range: Option<(Pos, Pos)>,
+}
+
+impl Selection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns true if nothing is selected
+    pub fn is_empty(&self) -> bool {
+        self.range.is_none()
+    }
+
+    pub fn clear(&mut self) {
+        self.anchor = None;
+        self.range = None;
+    }
+
+    /// Feed a mouse event to the selection.
+    /// Returns true if the selection changed, and has to be painted again.
+    pub fn update(&mut self, mouse: MouseEvent) -> bool {
+        match mouse.state {
+            MouseState::Down(MouseButton::Left) => {
+                self.anchor = Some(mouse.pos());
+                self.range.take().is_some()
+            }
+            MouseState::Drag(MouseButton::Left) => {
+                let Some(anchor) = self.anchor else { return false };
+                let pos = mouse.pos();
+                let range = match (anchor.y, anchor.x) <= (pos.y, pos.x) {
+                    true => (anchor, pos),
+                    false => (pos, anchor),
+                };
+                self.range.replace(range) != Some(range)
+            }
+            MouseState::Up(MouseButton::Left) => {
+                self.anchor = None;
+                false
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns true if the cell is selected
+    pub fn contains(&self, pos: Pos) -> bool {
+        let Some((start, end)) = self.range else { return false };
+        (start.y, start.x) <= (pos.y, pos.x) && (pos.y, pos.x) <= (end.y, end.x)
+    }
+
+    /// The selected text of the widgets that are shown, with a newline between rows.
+    /// Gaps between widgets on the same row are filled with spaces.
+    ///
+    /// Returns `None` if no text is selected.
+    pub fn text(&self, tree: &mut WidgetTree<'_>, attribute_storage: &AttributeStorage<'_>) -> Option<String> {
+        self.range?;
+
+        let mut cells = BTreeMap::new();
+        let (nodes, values) = tree.split_mut();
+        self.collect_cells(nodes, values, attribute_storage, &mut cells);
+
+        let mut text = String::new();
+        // The row of the previous glyph, and the column after it
+        let mut end: Option<Pos> = None;
+        for ((y, x), glyph) in cells {
+            match end {
+                // Skip glyphs covered by a wide glyph
+                Some(end) if end.y == y && x < end.x => continue,
+                Some(end) if end.y == y => text.push_str(&" ".repeat((x - end.x) as usize)),
+                Some(end) => text.push_str(&"\n".repeat((y - end.y) as usize)),
+                None => {}
+            }
+
+            text.push_str(&glyph);
+            end = Some(Pos::new(x + glyphs::width(&glyph) as i32, y));
+        }
+
+        Some(text)
+    }
+
+    // The selected glyphs, by row and column.
+    // Widgets later in the tree are painted on top of earlier widgets.
+    fn collect_cells(
+        &self,
+        nodes: &[Node],
+        values: &TreeValues<WidgetKind<'_>>,
+        attribute_storage: &AttributeStorage<'_>,
+        cells: &mut BTreeMap<(i32, i32), String>,
+    ) {
+        for node in nodes {
+            let Some((_, widget)) = values.get(node.value()) else { continue };
+            match widget {
+                WidgetKind::Element(el) => {
+                    let attributes = attribute_storage.get(el.id());
+                    if attributes.get::<Display>("display").unwrap_or_default() != Display::Show {
+                        continue;
+                    }
+
+                    let origin = el.get_pos();
+                    let lines = el.container.inner.any_text(el.size(), attributes);
+                    for (y, (x, line)) in lines.into_iter().enumerate() {
+                        let mut pos = Pos::new(origin.x + x as i32, origin.y + y as i32);
+                        for glyph in glyphs::glyphs(&line) {
+                            if self.contains(pos) {
+                                cells.insert((pos.y, pos.x), glyph.to_string());
+                            }
+                            pos.x += glyphs::width(glyph) as i32;
+                        }
+                    }
+                }
+                WidgetKind::If(widget) if !widget.show => continue,
+                WidgetKind::Else(widget) if !widget.show => continue,
+                _ => {}
+            }
+
+            self.collect_cells(node.children(), values, attribute_storage, cells);
+        }
+    }
+}
+
+/// Highlights the selected cells by swapping the foreground and background colours
+impl Overlay for Selection {
+    fn paint(&self, surface: &mut dyn WidgetRenderer) {
+        let Some((start, end)) = self.range else { return };
+        let size = surface.size();
+        let (width, height) = (size.width as i32, size.height as i32);
+
+        for y in start.y.max(0)..=end.y.min(height - 1) {
+            let from = if y == start.y { start.x } else { 0 };
+            let to = if y == end.y { end.x } else { width - 1 };
+            for x in from.max(0)..=to.min(width - 1) {
+                surface.set_attributes(&Highlight, Pos::new(x, y));
+            }
+        }
+    }
+}
+
+struct Highlight;
+
+impl CellAttributes for Highlight {
+    fn with_str(&self, _: &str, _: &mut dyn FnMut(&str)) {}
+
+    fn get_i64(&self, _: &str) -> Option<i64> {
+        None
+    }
+
+    fn get_u8(&self, _: &str) -> Option<u8> {
+        None
+    }
+
+    fn get_hex(&self, _: &str) -> Option<Hex> {
+        None
+    }
+
+    fn get_color(&self, _: &str) -> Option<Color> {
+        None
+    }
+
+    fn get_bool(&self, key: &str) -> bool {
+        key == "inverse"
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn mouse(state: MouseState, x: u16, y: u16) -> MouseEvent {
+        MouseEvent { x, y, state, clicks: 0 }
+    }
+
+    #[test]
+    fn select_backwards() {
+        let left = MouseButton::Left;
+        let mut selection = Selection::new();
+        assert!(!selection.update(mouse(MouseState::Down(left), 4, 1)));
+        assert!(selection.update(mouse(MouseState::Drag(left), 2, 0)));
+        assert!(!selection.update(mouse(MouseState::Up(left), 2, 0)));
+
+        assert!(!selection.contains(Pos::new(1, 0)));
+        assert!(selection.contains(Pos::new(2, 0)));
+        assert!(selection.contains(Pos::new(9, 0)));
+        assert!(selection.contains(Pos::new(0, 1)));
+        assert!(selection.contains(Pos::new(4, 1)));
+        assert!(!selection.contains(Pos::new(5, 1)));
+
+        // Pressing the button again clears the selection
+        assert!(selection.update(mouse(MouseState::Down(left), 0, 0)));
+        assert!(selection.is_empty());
+    }
+}
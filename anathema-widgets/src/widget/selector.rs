@@ -0,0 +1,297 @@
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+use anathema_state::CommonVal;
+
+use crate::error::{Error, Result};
+use crate::nodes::element::Element;
+use crate::{AttributeStorage, WidgetId};
+
+/// An element above the element being matched.
+#[derive(Debug, Copy, Clone)]
+pub struct Ancestor<'bp> {
+    pub ident: &'bp str,
+    pub widget_id: WidgetId,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Combinator {
+    // `a > b`
+    Child,
+    // `a b`
+    Descendant,
+}
+
+#[derive(Debug, PartialEq)]
+struct Compound<'a> {
+    // `None` matches any element
+    ident: Option<&'a str>,
+    attributes: Vec<(&'a str, Option<CommonVal<'a>>)>,
+}
+
+impl<'a> Compound<'a> {
+    fn matches(&self, ident: &str, widget_id: WidgetId, attributes: &AttributeStorage<'_>) -> bool {
+        if self.ident.is_some_and(|i| i != ident) {
+            return false;
+        }
+
+        let attribs = attributes.get(widget_id);
+        self.attributes.iter().all(|(key, value)| {
+            let Some(attribute) = attribs.get_val(key) else { return false };
+            match value {
+                None => true,
+                Some(value) => attribute
+                    .load_common_val()
+                    .and_then(|either| either.to_common().map(|attrib_val| value.eq(&attrib_val)))
+                    .unwrap_or(false),
+            }
+        })
+    }
+}
+
+/// A CSS-like selector.
+///
+/// * `text` matches elements by ident
+/// * `*` matches any element
+/// * `[key]` matches elements that has the attribute
+/// * `[key=value]` matches elements where the attribute has the given value
+/// * `a b` matches `b` if it's a descendant of `a`
+/// * `a > b` matches `b` if it's a direct child of `a`
+///
+/// ```
+/// # use anathema_widgets::Selector;
+/// let selector = Selector::parse("overflow > text[highlight]").unwrap();
+/// ```
+#[derive(Debug, PartialEq)]
+pub struct Selector<'a> {
+    compounds: Vec<Compound<'a>>,
+    // The combinator at index `n` is between the compound at `n` and `n + 1`
+    combinators: Vec<Combinator>,
+}
+
+impl<'a> Selector<'a> {
+    /// Parse a selector
+    pub fn parse(src: &'a str) -> Result<Self> {
+        Parser {
+            src,
+            chars: src.char_indices().peekable(),
+        }
+        .parse()
+    }
+
+    pub(crate) fn matches(
+        &self,
+        el: &Element<'_>,
+        ancestors: &[Ancestor<'_>],
+        attributes: &AttributeStorage<'_>,
+    ) -> bool {
+        let Some(last) = self.compounds.last() else { return false };
+        if !last.matches(el.ident, el.id(), attributes) {
+            return false;
+        }
+
+        self.match_ancestors(self.compounds.len() - 1, ancestors, attributes)
+    }
+
+    // Match `compounds[..index]` against the ancestors, right to left
+    fn match_ancestors(&self, index: usize, ancestors: &[Ancestor<'_>], attributes: &AttributeStorage<'_>) -> bool {
+        if index == 0 {
+            return true;
+        }
+
+        let compound = &self.compounds[index - 1];
+        match self.combinators[index - 1] {
+            Combinator::Child => {
+                let Some((parent, ancestors)) = ancestors.split_last() else { return false };
+                compound.matches(parent.ident, parent.widget_id, attributes)
+                    && self.match_ancestors(index - 1, ancestors, attributes)
+            }
+            Combinator::Descendant => (0..ancestors.len()).rev().any(|i| {
+                let ancestor = ancestors[i];
+                compound.matches(ancestor.ident, ancestor.widget_id, attributes)
+                    && self.match_ancestors(index - 1, &ancestors[..i], attributes)
+            }),
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+//   - Parser -
+// -----------------------------------------------------------------------------
+struct Parser<'a> {
+    src: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl<'a> Parser<'a> {
+    fn parse(mut self) -> Result<Selector<'a>> {
+        let mut compounds = vec![];
+        let mut combinators = vec![];
+
+        self.skip_whitespace();
+
+        loop {
+            compounds.push(self.compound()?);
+
+            let whitespace = self.skip_whitespace();
+            match self.chars.peek() {
+                None => break,
+                Some((_, '>')) => {
+                    self.chars.next();
+                    self.skip_whitespace();
+                    combinators.push(Combinator::Child);
+                }
+                Some(_) if whitespace => combinators.push(Combinator::Descendant),
+                Some(_) => return Err(self.error()),
+            }
+        }
+
+        Ok(Selector { compounds, combinators })
+    }
+
+    fn compound(&mut self) -> Result<Compound<'a>> {
+        let ident = match self.chars.peek() {
+            Some((_, '*')) => {
+                self.chars.next();
+                None
+            }
+            _ => Some(self.ident()),
+        };
+
+        let mut attributes = vec![];
+        while let Some((_, '[')) = self.chars.peek() {
+            self.chars.next();
+            self.skip_whitespace();
+            let key = self.ident();
+            if key.is_empty() {
+                return Err(self.error());
+            }
+            self.skip_whitespace();
+
+            let value = match self.chars.next() {
+                Some((_, ']')) => None,
+                Some((_, '=')) => {
+                    self.skip_whitespace();
+                    let value = self.value()?;
+                    self.skip_whitespace();
+                    match self.chars.next() {
+                        Some((_, ']')) => Some(value),
+                        _ => return Err(self.error()),
+                    }
+                }
+                _ => return Err(self.error()),
+            };
+
+            attributes.push((key, value));
+        }
+
+        match ident {
+            Some("") if attributes.is_empty() => Err(self.error()),
+            Some("") => Ok(Compound {
+                ident: None,
+                attributes,
+            }),
+            ident => Ok(Compound { ident, attributes }),
+        }
+    }
+
+    fn ident(&mut self) -> &'a str {
+        self.take_while(|c| c.is_alphanumeric() || c == '_' || c == '-')
+    }
+
+    fn value(&mut self) -> Result<CommonVal<'a>> {
+        match self.chars.peek() {
+            Some(&(_, quote @ ('\'' | '"'))) => {
+                self.chars.next();
+                let value = self.take_while(|c| c != quote);
+                match self.chars.next() {
+                    Some(_) => Ok(CommonVal::Str(value)),
+                    None => Err(self.error()),
+                }
+            }
+            _ => {
+                let value = self.take_while(|c| c != ']' && !c.is_whitespace());
+                if value.is_empty() {
+                    return Err(self.error());
+                }
+
+                let value = match value {
+                    "true" => CommonVal::Bool(true),
+                    "false" => CommonVal::Bool(false),
+                    _ => match (value.parse::<i64>(), value.parse::<f64>()) {
+                        (Ok(int), _) => CommonVal::Int(int),
+                        (_, Ok(float)) => CommonVal::Float(float),
+                        _ => CommonVal::Str(value),
+                    },
+                };
+
+                Ok(value)
+            }
+        }
+    }
+
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> &'a str {
+        let start = self.offset();
+        while self.chars.next_if(|&(_, c)| f(c)).is_some() {}
+        &self.src[start..self.offset()]
+    }
+
+    fn skip_whitespace(&mut self) -> bool {
+        !self.take_while(char::is_whitespace).is_empty()
+    }
+
+    fn offset(&mut self) -> usize {
+        self.chars.peek().map(|(i, _)| *i).unwrap_or(self.src.len())
+    }
+
+    fn error(&self) -> Error {
+        Error::InvalidSelector(self.src.to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn compound<'a>(ident: Option<&'a str>, attributes: Vec<(&'a str, Option<CommonVal<'a>>)>) -> Compound<'a> {
+        Compound { ident, attributes }
+    }
+
+    #[test]
+    fn parse_ident() {
+        let selector = Selector::parse("text").unwrap();
+        assert_eq!(selector.compounds, vec![compound(Some("text"), vec![])]);
+        assert!(selector.combinators.is_empty());
+    }
+
+    #[test]
+    fn parse_attributes() {
+        let selector = Selector::parse("*[highlight][id=2][name='a b']").unwrap();
+        let expected = compound(
+            None,
+            vec![
+                ("highlight", None),
+                ("id", Some(CommonVal::Int(2))),
+                ("name", Some(CommonVal::Str("a b"))),
+            ],
+        );
+        assert_eq!(selector.compounds, vec![expected]);
+    }
+
+    #[test]
+    fn parse_combinators() {
+        let selector = Selector::parse("overflow > vstack  text[highlight]").unwrap();
+        assert_eq!(selector.compounds.len(), 3);
+        assert_eq!(selector.combinators, vec![Combinator::Child, Combinator::Descendant]);
+    }
+
+    #[test]
+    fn invalid_selectors() {
+        assert!(Selector::parse("").is_err());
+        assert!(Selector::parse("text >").is_err());
+        assert!(Selector::parse("text[").is_err());
+        assert!(Selector::parse("text[a=]").is_err());
+        assert!(Selector::parse("text[a='b]").is_err());
+        assert!(Selector::parse("text!").is_err());
+    }
+}
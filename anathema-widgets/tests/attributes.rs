@@ -1,3 +1,4 @@
+use anathema_geometry::Pos;
 use run::TestCase;
 mod run;
 
@@ -34,3 +35,64 @@ test
         })
         .expect_frame(frame);
 }
+
+#[test]
+fn select_attributes() {
+    let template = "
+test
+    test [a: 'b']
+        test [c: 1]
+    test [c: 1]
+        ";
+
+    let frame = "
+test
+    test[a: Str(\"b\")]
+        test[c: Int(1), d: Bool(true)]
+    test[c: Int(1)]
+        ";
+
+    TestCase::setup(template)
+        .build(())
+        .with_query(0, |_state, mut elements| {
+            elements.select("test[a=b] > test[c=1]").each(|_el, attribs| {
+                attribs.set("d", true);
+            });
+
+            // The root element is not part of the query
+            let mut count = 0;
+            elements.select("test test[c]").each(|_, _| count += 1);
+            assert_eq!(count, 1);
+
+            let mut count = 0;
+            elements.select("*[c]").each(|_, _| count += 1);
+            assert_eq!(count, 2);
+        })
+        .expect_frame(frame);
+}
+
+#[test]
+fn first_element_region() {
+    let template = "
+test
+    test [a: 1]
+    test [a: 2]
+        ";
+
+    TestCase::setup(template)
+        .build(())
+        .with_query(0, |_state, mut elements| {
+            let mut calls = 0;
+            let region = elements.by_tag("test").first(|el, _| {
+                calls += 1;
+                el.region()
+            });
+
+            let region = region.unwrap();
+            assert_eq!(calls, 1);
+            assert_eq!(region.from, Pos::ZERO);
+
+            let value = elements.by_attribute("a", 3).first(|_, _| ());
+            assert!(value.is_none());
+        });
+}
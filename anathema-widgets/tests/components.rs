@@ -0,0 +1,161 @@
+use anathema_state::Map;
+use anathema_widgets::components::Component;
+use run::TestCase;
+mod run;
+
+struct Boundary;
+
+impl Component for Boundary {
+    type Message = ();
+    type State = ();
+
+    fn error_boundary(&self) -> bool {
+        true
+    }
+}
+
+#[test]
+fn error_boundary_fallback() {
+    let src = "
+test
+    @comp
+        $fallback
+            test 'fallback'
+    ";
+
+    let (case, component_id) = TestCase::setup_with_component(src, "comp", "test\n    does_not_exist");
+    let mut runner = case.build_with((), |registry| registry.add_component(component_id, Boundary, ()));
+
+    runner.expect_frame(
+        r#"
+test
+    <component>
+        test Str("fallback")
+        "#,
+    );
+
+    assert_eq!(runner.errors(), vec!["element `does_not_exist` does not exist"]);
+}
+
+#[test]
+fn fallback_slot_without_boundary() {
+    let src = "
+test
+    @comp
+        $fallback
+            test 'fallback'
+    ";
+
+    // The `$fallback` slot is only taken by error boundaries
+    let (case, component_id) = TestCase::setup_with_component(src, "comp", "test\n    $fallback");
+    let mut runner = case.build_with((), |registry| registry.add_component(component_id, (), ()));
+
+    runner.expect_frame(
+        r#"
+test
+    <component>
+        test
+            test Str("fallback")
+        "#,
+    );
+}
+
+#[test]
+fn recursive_component() {
+    let template = "
+test
+    for child in children
+        @comp {children: child}
+    ";
+
+    let (case, component_id) = TestCase::setup_with_component("@comp {children: [[[]]]}", "comp", template);
+    let mut runner = case.build_with((), |registry| registry.add_prototype(component_id, || (), || ()));
+
+    runner.expect_frame(
+        r#"
+<component>
+    test
+        <for>
+            <iter binding = child, index = 0>
+                <component>
+                    test
+                        <for>
+                            <iter binding = child, index = 0>
+                                <component>
+                                    test
+                                        <for>
+        "#,
+    );
+}
+
+#[test]
+fn recursion_limit() {
+    let template = "
+test
+    for child in children
+        @comp {children: child}
+    ";
+
+    // The list is nested deeper than the default recursion limit
+    let src = format!("@comp {{children: {}{}}}", "[".repeat(20), "]".repeat(20));
+    let (case, component_id) = TestCase::setup_with_component(&src, "comp", template);
+    let mut runner = case.build_with((), |registry| registry.add_prototype(component_id, || Boundary, || ()));
+
+    assert_eq!(runner.errors(), vec!["`@comp` is nested inside itself too many times"]);
+}
+
+#[test]
+fn prototype_state_from_attributes() {
+    let src = "
+test
+    @comp [title: 'a']
+    @comp [title: 'b']
+    ";
+
+    let (case, component_id) = TestCase::setup_with_component(src, "comp", "test title");
+    let mut runner = case.build_with((), |registry| {
+        registry.add_prototype_with_attributes(
+            component_id,
+            || (),
+            |attributes| {
+                let mut state = Map::<String>::empty();
+                let title = attributes.get_ref::<&str>("title").unwrap_or_default();
+                state.insert("title", title.to_string());
+                state
+            },
+        )
+    });
+
+    runner.expect_frame(
+        r#"
+test
+    <component>
+        test Str("a")
+    <component>
+        test Str("b")
+        "#,
+    );
+}
+
+#[test]
+fn hover_components() {
+    let src = "
+test
+    test 'a'
+    @comp
+    ";
+
+    let (case, component_id) = TestCase::setup_with_component(src, "comp", "test 'b'");
+    let mut runner = case.build_with((), |registry| registry.add_component(component_id, (), ()));
+
+    let entered = runner.hover((0, 1));
+    assert_eq!(entered.entered.len(), 1);
+    assert!(entered.left.is_empty());
+
+    // Still inside the component
+    assert_eq!(runner.hover((0, 1)), Default::default());
+
+    let left = runner.hover((0, 0));
+    assert!(left.entered.is_empty());
+    assert_eq!(left.left, entered.entered);
+}
@@ -4,12 +4,13 @@ use std::ops::ControlFlow;
 use anathema_geometry::{Pos, Size};
 use anathema_state::{drain_changes, drain_futures, Changes, FutureValues, State, StateId, States};
 use anathema_templates::blueprints::Blueprint;
-use anathema_templates::{Document, Globals};
+use anathema_templates::{Document, Globals, ToSourceKind, WidgetComponentId};
 use anathema_widgets::components::ComponentRegistry;
 use anathema_widgets::layout::{layout_widget, position_widget, Constraints, LayoutCtx, LayoutFilter, Viewport};
 use anathema_widgets::{
     eval_blueprint, try_resolve_future_values, update_tree, AttributeStorage, Components, DirtyWidgets, Elements,
-    EvalContext, Factory, FloatingWidgets, LayoutChildren, Scope, Stringify, Widget, WidgetTree,
+    EvalContext, Factory, FloatingWidgets, Hover, HoverChanges, LayoutChildren, Scope, Stringify, Widget, WidgetArena,
+    WidgetTree,
 };
 
 #[macro_export]
@@ -31,6 +32,7 @@ pub struct TestCaseRunner<'bp, S> {
     globals: &'bp Globals,
     blueprint: &'bp Blueprint,
     factory: Factory,
+    arena: &'bp WidgetArena,
     tree: WidgetTree<'bp>,
     attribute_storage: AttributeStorage<'bp>,
     dirty_widgets: DirtyWidgets,
@@ -41,6 +43,7 @@ pub struct TestCaseRunner<'bp, S> {
     changes: Changes,
     viewport: Viewport,
     components: Components,
+    hover: Hover,
 }
 
 impl<'bp, S> TestCaseRunner<'bp, S>
@@ -53,6 +56,7 @@ where
         let mut ctx = EvalContext::new(
             self.globals,
             &self.factory,
+            self.arena,
             &mut scope,
             &mut self.states,
             &mut self.component_registry,
@@ -112,6 +116,16 @@ where
         });
     }
 
+    /// Errors caught by error boundaries
+    #[allow(dead_code)]
+    pub fn errors(&mut self) -> Vec<String> {
+        self.components
+            .take_errors()
+            .into_iter()
+            .map(|(_, _, err)| err)
+            .collect()
+    }
+
     pub fn expect_frame(&mut self, frame: &str) -> &mut Self {
         let mut stringify = Stringify::new(&self.attribute_storage);
         self.tree.apply_visitor(&mut stringify);
@@ -123,6 +137,18 @@ where
         self
     }
 
+    /// Move the mouse cursor
+    #[allow(dead_code)]
+    pub fn hover(&mut self, pos: impl Into<Pos>) -> HoverChanges {
+        self.hover.update(
+            pos.into(),
+            &mut self.tree,
+            &self.components,
+            &mut self.attribute_storage,
+            &mut self.dirty_widgets,
+        )
+    }
+
     fn apply_futures(&mut self) {
         let mut scope = Scope::with_capacity(10);
         drain_futures(&mut self.future_values);
@@ -134,6 +160,7 @@ where
             try_resolve_future_values(
                 self.globals,
                 &self.factory,
+                self.arena,
                 &mut scope,
                 &mut self.states,
                 &mut self.component_registry,
@@ -159,6 +186,7 @@ where
                 update_tree(
                     self.globals,
                     &self.factory,
+                    self.arena,
                     &mut scope,
                     &mut self.states,
                     &mut self.component_registry,
@@ -170,8 +198,14 @@ where
                     &mut self.floating_widgets,
                     &mut self.components,
                 );
+
+                // Lay out the entire path to the changed widget
+                self.dirty_widgets.push(sub.into());
             });
-        })
+        });
+
+        self.dirty_widgets.apply(&mut self.tree);
+        self.dirty_widgets.clear();
     }
 
     /// Perform a state changing operation.
@@ -254,17 +288,49 @@ where
 pub struct TestCase {
     blueprint: Blueprint,
     globals: Globals,
+    arena: WidgetArena,
 }
 
 impl TestCase {
+    #[allow(dead_code)]
     pub fn setup(src: &str) -> Self {
         let (blueprint, globals) = Document::new(src).compile().unwrap();
-        Self { blueprint, globals }
+        Self {
+            blueprint,
+            globals,
+            arena: WidgetArena::new(),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn setup_with_component(src: &str, name: &str, template: &str) -> (Self, WidgetComponentId) {
+        let mut document = Document::new(src);
+        let component_id = document.add_component(name, template.to_template()).unwrap();
+        let (blueprint, globals) = document.compile().unwrap();
+        (
+            Self {
+                blueprint,
+                globals,
+                arena: WidgetArena::new(),
+            },
+            component_id.into(),
+        )
     }
 
+    #[allow(dead_code)]
     pub fn build<S: 'static + State>(&self, state: S) -> TestCaseRunner<'_, S> {
+        self.build_with(state, |_| ())
+    }
+
+    /// Build the test case, registering components with `f`
+    pub fn build_with<S, F>(&self, state: S, f: F) -> TestCaseRunner<'_, S>
+    where
+        S: 'static + State,
+        F: FnOnce(&mut ComponentRegistry),
+    {
         let tree = WidgetTree::empty();
-        let components = ComponentRegistry::new();
+        let mut components = ComponentRegistry::new();
+        f(&mut components);
         let mut states = States::new();
         states.insert(Box::new(state));
 
@@ -274,6 +340,7 @@ impl TestCase {
             _p: PhantomData,
             globals: &self.globals,
             blueprint: &self.blueprint,
+            arena: &self.arena,
             tree,
             states,
             component_registry: components,
@@ -285,6 +352,7 @@ impl TestCase {
             viewport: Viewport::new((1, 1)),
             components: Components::new(),
             dirty_widgets: DirtyWidgets::empty(),
+            hover: Hover::new(),
         };
 
         runner.exec();
@@ -3,11 +3,37 @@ use anathema_runtime::Runtime;
 use anathema_state::{CommonVal, State, Value};
 use anathema_templates::Document;
 use anathema_widgets::components::events::{KeyCode, KeyEvent, KeyState};
-use anathema_widgets::components::{Component, Context};
+use anathema_widgets::components::{Component, Context, TypedEvent};
 use anathema_widgets::Elements;
 
 struct App;
 
+// Published by the button
+struct Click;
+
+impl TypedEvent for Click {
+    type Value = String;
+
+    const NAME: &'static str = "click";
+}
+
+// Received by the app: `@button (click->increment)`
+struct Increment;
+
+impl TypedEvent for Increment {
+    type Value = String;
+
+    const NAME: &'static str = "increment";
+}
+
+struct Decrement;
+
+impl TypedEvent for Decrement {
+    type Value = String;
+
+    const NAME: &'static str = "decrement";
+}
+
 #[derive(State)]
 struct AppState {
     number: Value<i32>,
@@ -20,14 +46,14 @@ impl Component for App {
     fn receive(
         &mut self,
         ident: &str,
-        _value: CommonVal<'_>,
+        value: CommonVal<'_>,
         state: &mut Self::State,
         _elements: Elements<'_, '_>,
         _context: Context<'_, Self::State>,
     ) {
-        if ident == "increment" {
+        if Increment::receive(ident, value).is_some() {
             *state.number.to_mut() += 1;
-        } else if ident == "decrement" {
+        } else if Decrement::receive(ident, value).is_some() {
             *state.number.to_mut() -= 1;
         }
     }
@@ -66,7 +92,7 @@ impl Component for Button {
     ) {
         if matches!(key.state, KeyState::Press) {
             if let KeyCode::Enter = key.code {
-                context.publish("click", |state| &state.caption)
+                context.publish_event::<Click>(|state| &state.caption)
             }
         }
     }
@@ -0,0 +1,109 @@
+use anathema_backend::tui::TuiBackend;
+use anathema_runtime::Runtime;
+use anathema_state::{List, State, Value};
+use anathema_templates::Document;
+use anathema_widgets::components::events::{KeyCode, KeyEvent, KeyState};
+use anathema_widgets::components::{Component, Context};
+use anathema_widgets::Elements;
+
+// A reusable list, generic over the items it holds
+struct ListView<T> {
+    next: Box<dyn FnMut() -> T>,
+}
+
+#[derive(State)]
+struct ListViewState<T> {
+    title: Value<String>,
+    items: Value<List<T>>,
+}
+
+impl<T: State> ListViewState<T> {
+    fn new(title: &str) -> Self {
+        Self {
+            title: title.to_string().into(),
+            items: List::empty(),
+        }
+    }
+}
+
+impl<T: State> Component for ListView<T> {
+    type Message = T;
+    type State = ListViewState<T>;
+
+    fn on_key(
+        &mut self,
+        key: KeyEvent,
+        state: &mut Self::State,
+        _elements: Elements<'_, '_>,
+        _context: Context<'_, Self::State>,
+    ) {
+        if matches!(key.state, KeyState::Press) {
+            match key.code {
+                KeyCode::Enter => state.items.push_back((self.next)()),
+                KeyCode::Backspace => drop(state.items.pop_back()),
+                _ => (),
+            }
+        }
+    }
+
+    fn message(
+        &mut self,
+        message: Self::Message,
+        state: &mut Self::State,
+        _elements: Elements<'_, '_>,
+        _context: Context<'_, Self::State>,
+    ) {
+        state.items.push_back(message);
+    }
+}
+
+fn main() {
+    let doc = Document::new("@main");
+
+    let backend = TuiBackend::builder()
+        .enable_alt_screen()
+        .enable_raw_mode()
+        .hide_cursor()
+        .finish()
+        .unwrap();
+
+    let mut runtime = Runtime::builder(doc, backend);
+
+    runtime
+        .register_component("main", "examples/templates/generic-component/main.aml", (), ())
+        .unwrap();
+
+    // The same component, registered with two different item types
+    let mut names = ["Alice", "Bob", "Carol"].into_iter().cycle();
+    let names_id = runtime
+        .register_component(
+            "names",
+            "examples/templates/generic-component/list_view.aml",
+            ListView {
+                next: Box::new(move || names.next().unwrap().to_string()),
+            },
+            ListViewState::<String>::new("Names"),
+        )
+        .unwrap();
+
+    let mut number = 0;
+    runtime
+        .register_component(
+            "numbers",
+            "examples/templates/generic-component/list_view.aml",
+            ListView {
+                next: Box::new(move || {
+                    number += 1;
+                    number
+                }),
+            },
+            ListViewState::<usize>::new("Numbers"),
+        )
+        .unwrap();
+
+    let emitter = runtime.emitter();
+    emitter.emit(names_id, "Dave".to_string()).unwrap();
+
+    let mut runtime = runtime.finish().unwrap();
+    runtime.run();
+}
@@ -12,6 +12,11 @@ pub use {
 };
 
 pub mod prelude {
+    #[cfg(feature = "termion")]
+    pub use crate::backend::termion::TermionBackend;
+    #[cfg(feature = "termwiz")]
+    pub use crate::backend::termwiz::TermwizBackend;
+    #[cfg(feature = "crossterm")]
     pub use crate::backend::tui::TuiBackend;
     pub use crate::runtime::{GlobalContext, GlobalEvents, Runtime};
     pub use crate::templates::{Document, SourceKind, ToSourceKind, WidgetComponentId};
@@ -20,6 +25,8 @@ pub mod prelude {
 pub mod component {
     pub use crate::state::{Color, CommonVal, List, Map, State, Value};
     pub use crate::widgets::components::events::{Event, KeyCode, KeyEvent, MouseButton, MouseEvent, MouseState};
-    pub use crate::widgets::components::{Component, ComponentId, Context, Emitter};
+    pub use crate::widgets::components::{
+        Component, ComponentId, Context, Emitter, MountId, Priority, Shutdown, TimerId,
+    };
     pub use crate::widgets::Elements;
 }